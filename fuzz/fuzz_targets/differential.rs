@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasmer_bin_fuzz::differential::{DifferentialInput, Oracle};
+
+fuzz_target!(|input: DifferentialInput| {
+    let wasm_bytes = input.module.to_bytes();
+
+    if let Ok(path) = std::env::var("DUMP_TESTCASE") {
+        use std::fs::File;
+        use std::io::Write;
+        let mut file = File::create(path).unwrap();
+        file.write_all(&wasm_bytes).unwrap();
+        return;
+    }
+
+    Oracle::all().check(&wasm_bytes, &input.args);
+});
@@ -0,0 +1,434 @@
+//! The backends compared by the differential oracle: every compiler and
+//! engine enabled with the features of the crate, and `wasmi` as a
+//! reference interpreter with the `reference` feature.
+
+use crate::differential::{
+    Backend, Execution, Export, Instantiation, MemoryDigest, Outcome, Trap, Value,
+};
+use wasmer::{
+    imports, CompilerConfig, Extern, ExternRef, Instance, InstantiationError, LinkError, Module,
+    RuntimeError, Store, Type, Val,
+};
+#[cfg(feature = "cranelift")]
+use wasmer_compiler_cranelift::Cranelift;
+#[cfg(feature = "llvm")]
+use wasmer_compiler_llvm::LLVM;
+#[cfg(feature = "singlepass")]
+use wasmer_compiler_singlepass::Singlepass;
+#[cfg(feature = "jit")]
+use wasmer_engine_jit::JIT;
+#[cfg(feature = "native")]
+use wasmer_engine_native::Native;
+#[cfg(feature = "object-file")]
+use wasmer_engine_object_file::ObjectFile;
+use wasmer_vm::TrapCode;
+
+/// Every backend enabled with the features of the crate.
+pub fn all() -> Vec<Box<dyn Backend>> {
+    let mut backends: Vec<Box<dyn Backend>> = Vec::new();
+    for &engine in EngineKind::ALL {
+        for &compiler in CompilerKind::ALL {
+            backends.push(Box::new(WasmerBackend { compiler, engine }));
+        }
+    }
+    #[cfg(feature = "reference")]
+    backends.push(Box::new(reference::Wasmi));
+    backends
+}
+
+/// Every backend running modules with the engine `engine`.
+pub fn with_engine(engine: EngineKind) -> Vec<Box<dyn Backend>> {
+    CompilerKind::ALL
+        .iter()
+        .map(|&compiler| Box::new(WasmerBackend { compiler, engine }) as Box<dyn Backend>)
+        .collect()
+}
+
+/// The compilers enabled with the features of the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilerKind {
+    #[cfg(feature = "singlepass")]
+    Singlepass,
+    #[cfg(feature = "cranelift")]
+    Cranelift,
+    #[cfg(feature = "llvm")]
+    LLVM,
+}
+
+impl CompilerKind {
+    pub const ALL: &'static [Self] = &[
+        #[cfg(feature = "singlepass")]
+        Self::Singlepass,
+        #[cfg(feature = "cranelift")]
+        Self::Cranelift,
+        #[cfg(feature = "llvm")]
+        Self::LLVM,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "singlepass")]
+            Self::Singlepass => "singlepass",
+            #[cfg(feature = "cranelift")]
+            Self::Cranelift => "cranelift",
+            #[cfg(feature = "llvm")]
+            Self::LLVM => "llvm",
+        }
+    }
+
+    fn config(self) -> Box<dyn CompilerConfig> {
+        match self {
+            #[cfg(feature = "singlepass")]
+            Self::Singlepass => {
+                let mut compiler = Singlepass::default();
+                compiler.canonicalize_nans(true);
+                Box::new(compiler)
+            }
+            #[cfg(feature = "cranelift")]
+            Self::Cranelift => {
+                let mut compiler = Cranelift::default();
+                compiler.canonicalize_nans(true);
+                compiler.enable_verifier();
+                Box::new(compiler)
+            }
+            #[cfg(feature = "llvm")]
+            Self::LLVM => {
+                let mut compiler = LLVM::default();
+                compiler.canonicalize_nans(true);
+                compiler.enable_verifier();
+                Box::new(compiler)
+            }
+        }
+    }
+
+    /// The errors of the compiler when it rejects a valid module, because
+    /// it doesn't implement one of its features yet.
+    fn unsupported_errors(self) -> &'static [&'static str] {
+        match self {
+            #[cfg(feature = "singlepass")]
+            Self::Singlepass => &[
+                "Validation error: invalid result arity: func type returns multiple values",
+                "Validation error: blocks, loops, and ifs accept no parameters when multi-value is not enabled",
+                "multi-value returns not yet implemented",
+            ],
+            #[allow(unreachable_patterns)]
+            _ => &[],
+        }
+    }
+}
+
+/// The engines enabled with the features of the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineKind {
+    #[cfg(feature = "jit")]
+    JIT,
+    /// Modules are compiled to shared objects, which are loaded back
+    /// by a headless engine before being run.
+    #[cfg(feature = "native")]
+    Native,
+    /// Modules are compiled to object files, which can't be run without
+    /// being linked in an executable: only their compilation is checked.
+    #[cfg(feature = "object-file")]
+    ObjectFile,
+}
+
+impl EngineKind {
+    pub const ALL: &'static [Self] = &[
+        #[cfg(feature = "jit")]
+        Self::JIT,
+        #[cfg(feature = "native")]
+        Self::Native,
+        #[cfg(feature = "object-file")]
+        Self::ObjectFile,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "jit")]
+            Self::JIT => "jit",
+            #[cfg(feature = "native")]
+            Self::Native => "native",
+            #[cfg(feature = "object-file")]
+            Self::ObjectFile => "object-file",
+        }
+    }
+}
+
+/// A compiler and an engine of Wasmer.
+pub struct WasmerBackend {
+    pub compiler: CompilerKind,
+    pub engine: EngineKind,
+}
+
+impl WasmerBackend {
+    fn compile(&self, wasm: &[u8]) -> Result<Module, Instantiation> {
+        let store = match self.engine {
+            #[cfg(feature = "jit")]
+            EngineKind::JIT => Store::new(&JIT::new(self.compiler.config()).engine()),
+            #[cfg(feature = "native")]
+            EngineKind::Native => Store::new(&Native::new(self.compiler.config()).engine()),
+            #[cfg(feature = "object-file")]
+            EngineKind::ObjectFile => Store::new(&ObjectFile::new(self.compiler.config()).engine()),
+        };
+        match Module::new(&store, wasm) {
+            Ok(module) => Ok(module),
+            Err(e) => {
+                let error_message = format!("{}", e);
+                if self
+                    .compiler
+                    .unsupported_errors()
+                    .iter()
+                    .any(|unsupported| error_message.contains(unsupported))
+                {
+                    return Err(Instantiation::Unsupported(error_message));
+                }
+                panic!(
+                    "{} failed to compile a valid module: {}",
+                    self.name(),
+                    error_message
+                );
+            }
+        }
+    }
+}
+
+impl Backend for WasmerBackend {
+    fn name(&self) -> String {
+        format!("{}-{}", self.compiler.name(), self.engine.name())
+    }
+
+    fn instantiate(&self, wasm: &[u8]) -> Instantiation {
+        let module = match self.compile(wasm) {
+            Ok(module) => module,
+            Err(unsupported) => return unsupported,
+        };
+        let module = match self.engine {
+            #[cfg(feature = "jit")]
+            EngineKind::JIT => module,
+            #[cfg(feature = "native")]
+            EngineKind::Native => {
+                let serialized = module.serialize().unwrap();
+                let store = Store::new(&Native::headless().engine());
+                unsafe { Module::deserialize(&store, &serialized) }.unwrap()
+            }
+            #[cfg(feature = "object-file")]
+            EngineKind::ObjectFile => {
+                module.serialize().unwrap();
+                return Instantiation::Unsupported(
+                    "object files can't be run without being linked".to_string(),
+                );
+            }
+        };
+        match Instance::new(&module, &imports! {}) {
+            Ok(instance) => Instantiation::Instance(Box::new(WasmerExecution { instance })),
+            Err(InstantiationError::Start(e))
+            | Err(InstantiationError::Link(LinkError::Trap(e))) => Instantiation::Trap(trap(e)),
+            Err(e) => Instantiation::Failed(format!("{}", e)),
+        }
+    }
+}
+
+struct WasmerExecution {
+    instance: Instance,
+}
+
+impl Execution for WasmerExecution {
+    fn exports(&self) -> Option<Vec<Export>> {
+        let exports = self
+            .instance
+            .exports
+            .iter()
+            .map(|(name, export)| match export {
+                Extern::Function(f) => Export::Function {
+                    name: name.clone(),
+                    params: f.ty().params().to_vec(),
+                    results: f.ty().results().to_vec(),
+                },
+                Extern::Memory(_) => Export::Memory(name.clone()),
+                Extern::Global(_) => Export::Global(name.clone()),
+                Extern::Table(_) => Export::Table(name.clone()),
+                Extern::Tag(_) => unreachable!("the fuzzed modules don't use exception handling"),
+            })
+            .collect();
+        Some(exports)
+    }
+
+    fn call(&mut self, name: &str, args: &[Value]) -> Outcome {
+        let f = self.instance.exports.get_function(name).unwrap();
+        let args = f
+            .ty()
+            .params()
+            .iter()
+            .zip(args)
+            .map(|(ty, arg)| match *arg {
+                Value::I32(v) => Val::I32(v),
+                Value::I64(v) => Val::I64(v),
+                Value::F32(bits) => Val::F32(f32::from_bits(bits)),
+                Value::F64(bits) => Val::F64(f64::from_bits(bits)),
+                Value::V128(v) => Val::V128(v),
+                Value::Ref { .. } if *ty == Type::ExternRef => Val::ExternRef(ExternRef::null()),
+                Value::Ref { .. } => Val::FuncRef(None),
+            })
+            .collect::<Vec<_>>();
+        match f.call(&args) {
+            Ok(values) => Outcome::Values(values.iter().map(value).collect()),
+            Err(e) => Outcome::Trap(trap(e)),
+        }
+    }
+
+    fn memory(&self, name: &str) -> MemoryDigest {
+        let memory = self.instance.exports.get_memory(name).unwrap();
+        // Safety: nothing runs in the instance while its memory is read.
+        MemoryDigest::new(unsafe { memory.data_unchecked() })
+    }
+
+    fn global(&self, name: &str) -> Value {
+        value(&self.instance.exports.get_global(name).unwrap().get())
+    }
+}
+
+fn value(val: &Val) -> Value {
+    match val {
+        Val::I32(v) => Value::I32(*v),
+        Val::I64(v) => Value::I64(*v),
+        Val::F32(v) => Value::F32(v.to_bits()),
+        Val::F64(v) => Value::F64(v.to_bits()),
+        Val::V128(v) => Value::V128(*v),
+        Val::ExternRef(r) => Value::Ref {
+            is_null: r.is_null(),
+        },
+        Val::FuncRef(f) => Value::Ref {
+            is_null: f.is_none(),
+        },
+    }
+}
+
+fn trap(error: RuntimeError) -> Trap {
+    match error.to_trap() {
+        Some(TrapCode::StackOverflow) => Trap::StackOverflow,
+        Some(TrapCode::HeapAccessOutOfBounds)
+        | Some(TrapCode::TableAccessOutOfBounds)
+        | Some(TrapCode::OutOfBounds) => Trap::OutOfBounds,
+        Some(TrapCode::IndirectCallToNull) => Trap::IndirectCallToNull,
+        Some(TrapCode::BadSignature) => Trap::BadSignature,
+        Some(TrapCode::IntegerOverflow) | Some(TrapCode::BadConversionToInteger) => {
+            Trap::IntegerOverflow
+        }
+        Some(TrapCode::IntegerDivisionByZero) => Trap::IntegerDivisionByZero,
+        Some(TrapCode::UnreachableCodeReached) => Trap::Unreachable,
+        Some(TrapCode::HeapMisaligned) | Some(TrapCode::UnalignedAtomic) | None => Trap::Other,
+    }
+}
+
+#[cfg(feature = "reference")]
+mod reference {
+    use crate::differential::{
+        Backend, Execution, Export, Instantiation, MemoryDigest, Outcome, Trap, Value,
+    };
+    use wasmi::{
+        ExternVal, ImportsBuilder, ModuleInstance, ModuleRef, NopExternals, RuntimeValue, TrapKind,
+    };
+
+    /// The `wasmi` interpreter, which only supports the MVP: the modules
+    /// using later proposals are rejected, and aren't compared.
+    pub struct Wasmi;
+
+    impl Backend for Wasmi {
+        fn name(&self) -> String {
+            "wasmi".to_string()
+        }
+
+        fn canonical_nans(&self) -> bool {
+            false
+        }
+
+        fn instantiate(&self, wasm: &[u8]) -> Instantiation {
+            let module = match wasmi::Module::from_buffer(wasm) {
+                Ok(module) => module,
+                Err(e) => return Instantiation::Unsupported(e.to_string()),
+            };
+            // Out of bounds segments fail the instantiation in wasmi, where
+            // they trap in Wasmer, after the bulk memory proposal.
+            let instance = match ModuleInstance::new(&module, &ImportsBuilder::default()) {
+                Ok(instance) => instance,
+                Err(e) => return Instantiation::Unsupported(e.to_string()),
+            };
+            match instance.run_start(&mut NopExternals) {
+                Ok(instance) => Instantiation::Instance(Box::new(WasmiExecution { instance })),
+                Err(e) => Instantiation::Trap(trap(e.kind())),
+            }
+        }
+    }
+
+    struct WasmiExecution {
+        instance: ModuleRef,
+    }
+
+    impl Execution for WasmiExecution {
+        fn exports(&self) -> Option<Vec<Export>> {
+            None
+        }
+
+        fn call(&mut self, name: &str, args: &[Value]) -> Outcome {
+            let args = args
+                .iter()
+                .map(|arg| match *arg {
+                    Value::I32(v) => RuntimeValue::I32(v),
+                    Value::I64(v) => RuntimeValue::I64(v),
+                    Value::F32(bits) => {
+                        RuntimeValue::F32(wasmi::nan_preserving_float::F32::from_bits(bits))
+                    }
+                    Value::F64(bits) => {
+                        RuntimeValue::F64(wasmi::nan_preserving_float::F64::from_bits(bits))
+                    }
+                    Value::V128(_) | Value::Ref { .. } => {
+                        unreachable!("wasmi doesn't load modules using SIMD or references")
+                    }
+                })
+                .collect::<Vec<_>>();
+            match self.instance.invoke_export(name, &args, &mut NopExternals) {
+                Ok(result) => Outcome::Values(result.iter().map(value).collect()),
+                Err(wasmi::Error::Trap(e)) => Outcome::Trap(trap(e.kind())),
+                Err(e) => panic!("wasmi failed to call `{}`: {}", name, e),
+            }
+        }
+
+        fn memory(&self, name: &str) -> MemoryDigest {
+            match self.instance.export_by_name(name) {
+                Some(ExternVal::Memory(memory)) => memory.with_direct_access(MemoryDigest::new),
+                _ => panic!("wasmi has no exported memory `{}`", name),
+            }
+        }
+
+        fn global(&self, name: &str) -> Value {
+            match self.instance.export_by_name(name) {
+                Some(ExternVal::Global(global)) => value(&global.get()),
+                _ => panic!("wasmi has no exported global `{}`", name),
+            }
+        }
+    }
+
+    fn value(value: &RuntimeValue) -> Value {
+        match value {
+            RuntimeValue::I32(v) => Value::I32(*v),
+            RuntimeValue::I64(v) => Value::I64(*v),
+            RuntimeValue::F32(v) => Value::F32(v.to_bits()),
+            RuntimeValue::F64(v) => Value::F64(v.to_bits()),
+        }
+    }
+
+    fn trap(kind: &TrapKind) -> Trap {
+        match kind {
+            TrapKind::StackOverflow => Trap::StackOverflow,
+            TrapKind::MemoryAccessOutOfBounds | TrapKind::TableAccessOutOfBounds => {
+                Trap::OutOfBounds
+            }
+            TrapKind::ElemUninitialized => Trap::IndirectCallToNull,
+            TrapKind::UnexpectedSignature => Trap::BadSignature,
+            TrapKind::InvalidConversionToInt => Trap::IntegerOverflow,
+            TrapKind::DivisionByZero => Trap::IntegerDivisionByZero,
+            TrapKind::Unreachable => Trap::Unreachable,
+            TrapKind::Host(_) => Trap::Other,
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! A differential oracle: it runs the same module on several backends,
+//! and checks that they all observe the same results, traps, memories
+//! and globals after each call of an exported function.
+//!
+//! Silent miscompilations, where a backend runs a module without error
+//! but computes something else, aren't found by any other fuzz target.
+
+use crate::backends;
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::Hasher;
+use wasm_smith::{Config, ConfiguredModule};
+use wasmer::Type;
+
+#[derive(Arbitrary, Debug, Default, Copy, Clone)]
+pub struct DifferentialConfig;
+impl Config for DifferentialConfig {
+    fn max_imports(&self) -> usize {
+        0
+    }
+    fn max_memory_pages(&self) -> u32 {
+        // The memories are compared after each call, so they are kept
+        // small, which also keeps the reference interpreter fast.
+        1024
+    }
+    fn min_funcs(&self) -> usize {
+        1
+    }
+    fn min_exports(&self) -> usize {
+        1
+    }
+}
+
+/// A module generated by `wasm-smith`, with the arguments its exported
+/// functions are called with.
+pub struct DifferentialInput {
+    pub module: ConfiguredModule<DifferentialConfig>,
+    pub args: Vec<u64>,
+}
+
+impl<'a> Arbitrary<'a> for DifferentialInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut module = ConfiguredModule::<DifferentialConfig>::arbitrary(u)?;
+        module.ensure_termination(100000);
+        let args = Vec::arbitrary(u)?;
+        Ok(Self { module, args })
+    }
+}
+
+impl fmt::Debug for DifferentialInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "args: {:?}", self.args)?;
+        f.write_str(&wasmprinter::print_bytes(self.module.to_bytes()).unwrap())
+    }
+}
+
+/// A value observed by a backend.
+#[derive(Debug, Clone, Copy)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    /// The bits of a `f32`.
+    F32(u32),
+    /// The bits of a `f64`.
+    F64(u64),
+    V128(u128),
+    /// A `funcref` or an `externref`, which can only be compared by
+    /// nullness across backends.
+    Ref {
+        is_null: bool,
+    },
+}
+
+impl Value {
+    /// The value of type `ty` made from the raw bits `bits`. References
+    /// are always null.
+    pub fn from_bits(ty: Type, bits: u64) -> Self {
+        match ty {
+            Type::I32 => Value::I32(bits as i32),
+            Type::I64 => Value::I64(bits as i64),
+            Type::F32 => Value::F32(bits as u32),
+            Type::F64 => Value::F64(bits),
+            Type::V128 => Value::V128(u128::from(bits) << 64 | u128::from(bits)),
+            Type::ExternRef | Type::FuncRef => Value::Ref { is_null: true },
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (*self, *other) {
+            (Value::I32(a), Value::I32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            // The payload of a NaN produced by an arithmetic instruction
+            // is nondeterministic.
+            (Value::F32(a), Value::F32(b)) => {
+                a == b || (f32::from_bits(a).is_nan() && f32::from_bits(b).is_nan())
+            }
+            (Value::F64(a), Value::F64(b)) => {
+                a == b || (f64::from_bits(a).is_nan() && f64::from_bits(b).is_nan())
+            }
+            (Value::V128(a), Value::V128(b)) => a == b,
+            (Value::Ref { is_null: a }, Value::Ref { is_null: b }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// The kinds of traps, as far as backends agree on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    /// The call stack was exhausted. Backends have stacks of different
+    /// sizes, so nothing after this trap is compared.
+    StackOverflow,
+    /// A memory or table access was out of bounds.
+    OutOfBounds,
+    IndirectCallToNull,
+    BadSignature,
+    /// An integer operation overflowed, or a float which isn't a number
+    /// or is out of range was converted to an integer.
+    IntegerOverflow,
+    IntegerDivisionByZero,
+    Unreachable,
+    /// Any other error, like an unaligned atomic access.
+    Other,
+}
+
+/// What happened when calling a function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Values(Vec<Value>),
+    Trap(Trap),
+}
+
+/// The contents of a memory, without keeping all its bytes around.
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryDigest {
+    /// The size of the memory, in bytes
+    pub size: u64,
+    /// A hash of the bytes of the memory
+    pub hash: u64,
+}
+
+impl MemoryDigest {
+    pub fn new(data: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(data);
+        Self {
+            size: data.len() as u64,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+/// An export of a module.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Export {
+    Function {
+        name: String,
+        params: Vec<Type>,
+        results: Vec<Type>,
+    },
+    Memory(String),
+    Global(String),
+    /// Tables aren't compared.
+    Table(String),
+}
+
+/// A compiler and engine, or another way to run modules.
+pub trait Backend {
+    /// The name of the backend, like `cranelift-jit`.
+    fn name(&self) -> String;
+
+    /// Whether NaNs produced by this backend are canonical. The memories
+    /// of backends which don't canonicalize NaNs only have their sizes
+    /// compared, since NaNs can be stored in them.
+    fn canonical_nans(&self) -> bool {
+        true
+    }
+
+    /// Compiles and instantiates `wasm`.
+    fn instantiate(&self, wasm: &[u8]) -> Instantiation;
+}
+
+/// What happened when instantiating a module.
+pub enum Instantiation {
+    /// The backend doesn't support the module, for this reason, and
+    /// isn't compared.
+    Unsupported(String),
+    /// The start function trapped.
+    Trap(Trap),
+    /// The module couldn't be instantiated for a reason which doesn't
+    /// depend on the backend, like running out of memory. Nothing is
+    /// compared.
+    Failed(String),
+    Instance(Box<dyn Execution>),
+}
+
+/// A module instantiated by a [`Backend`].
+pub trait Execution {
+    /// The exports of the module, in order, if the backend can list
+    /// them.
+    fn exports(&self) -> Option<Vec<Export>>;
+
+    /// Calls the exported function `name`.
+    fn call(&mut self, name: &str, args: &[Value]) -> Outcome;
+
+    /// The contents of the exported memory `name`.
+    fn memory(&self, name: &str) -> MemoryDigest;
+
+    /// The value of the exported global `name`.
+    fn global(&self, name: &str) -> Value;
+}
+
+/// Runs modules on several backends and compares what they observe.
+#[derive(Default)]
+pub struct Oracle {
+    backends: Vec<Box<dyn Backend>>,
+}
+
+impl Oracle {
+    /// An oracle without backends.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An oracle with every compiler and engine enabled with the features
+    /// of the crate, and the reference interpreter with the `reference`
+    /// feature.
+    pub fn all() -> Self {
+        let mut oracle = Self::new();
+        for backend in backends::all() {
+            oracle = oracle.with_backend(backend);
+        }
+        oracle
+    }
+
+    /// Adds `backend` to the backends compared.
+    pub fn with_backend(mut self, backend: Box<dyn Backend>) -> Self {
+        self.backends.push(backend);
+        self
+    }
+
+    /// The names of the backends compared.
+    pub fn backend_names(&self) -> Vec<String> {
+        self.backends.iter().map(|backend| backend.name()).collect()
+    }
+
+    /// Runs `wasm` on every backend, calling each exported function with
+    /// arguments taken in turn from `args`, and panics if two backends
+    /// disagree.
+    pub fn check(&self, wasm: &[u8], args: &[u64]) {
+        let mut runs = Vec::new();
+        let mut start_traps = Vec::new();
+        for backend in self.backends.iter().map(|backend| backend.as_ref()) {
+            match backend.instantiate(wasm) {
+                Instantiation::Unsupported(_) => {}
+                Instantiation::Failed(_) => return,
+                Instantiation::Trap(Trap::StackOverflow) => return,
+                Instantiation::Trap(trap) => start_traps.push((backend, trap)),
+                Instantiation::Instance(instance) => runs.push((backend, instance)),
+            }
+        }
+        if let Some((first, trap)) = start_traps.first() {
+            if let Some((backend, _)) = runs.first() {
+                panic!(
+                    "{} trapped with {:?} when instantiating, but {} didn't trap",
+                    first.name(),
+                    trap,
+                    backend.name()
+                );
+            }
+            for (backend, other) in &start_traps[1..] {
+                assert_eq!(
+                    trap,
+                    other,
+                    "{} and {} trapped differently when instantiating",
+                    first.name(),
+                    backend.name()
+                );
+            }
+            return;
+        }
+
+        let mut listed = runs
+            .iter()
+            .filter_map(|(backend, instance)| Some((backend.name(), instance.exports()?)));
+        let (first, exports) = match listed.next() {
+            Some(listed) => listed,
+            None => return,
+        };
+        for (backend, other) in listed {
+            assert_eq!(
+                exports, other,
+                "{} and {} disagree on the exports",
+                first, backend
+            );
+        }
+
+        let mut args = args.iter().copied().chain(std::iter::repeat(0));
+        for export in &exports {
+            let (name, params) = match export {
+                Export::Function { name, params, .. } => (name, params),
+                _ => continue,
+            };
+            let call_args = params
+                .iter()
+                .map(|ty| Value::from_bits(*ty, args.next().unwrap()))
+                .collect::<Vec<_>>();
+            let outcomes = runs
+                .iter_mut()
+                .map(|(backend, instance)| (backend, instance.call(name, &call_args)))
+                .collect::<Vec<_>>();
+            if outcomes
+                .iter()
+                .any(|(_, outcome)| *outcome == Outcome::Trap(Trap::StackOverflow))
+            {
+                return;
+            }
+            let (first, outcome) = &outcomes[0];
+            for (backend, other) in &outcomes[1..] {
+                assert_eq!(
+                    outcome,
+                    other,
+                    "{} and {} disagree when calling `{}` with {:?}",
+                    first.name(),
+                    backend.name(),
+                    name,
+                    call_args
+                );
+            }
+            self.compare_state(&runs, &exports, name);
+        }
+    }
+
+    /// Compares the exported memories and globals after calling `called`.
+    fn compare_state(
+        &self,
+        runs: &[(&dyn Backend, Box<dyn Execution>)],
+        exports: &[Export],
+        called: &str,
+    ) {
+        let (first, instance) = &runs[0];
+        for export in exports {
+            match export {
+                Export::Memory(name) => {
+                    let memory = instance.memory(name);
+                    for (backend, other) in &runs[1..] {
+                        let other = other.memory(name);
+                        let same = if first.canonical_nans() && backend.canonical_nans() {
+                            memory.size == other.size && memory.hash == other.hash
+                        } else {
+                            memory.size == other.size
+                        };
+                        assert!(
+                            same,
+                            "{} and {} disagree on the memory `{}` after calling `{}`: {:?} != {:?}",
+                            first.name(),
+                            backend.name(),
+                            name,
+                            called,
+                            memory,
+                            other
+                        );
+                    }
+                }
+                Export::Global(name) => {
+                    let global = instance.global(name);
+                    for (backend, other) in &runs[1..] {
+                        assert_eq!(
+                            global,
+                            other.global(name),
+                            "{} and {} disagree on the global `{}` after calling `{}`",
+                            first.name(),
+                            backend.name(),
+                            name,
+                            called
+                        );
+                    }
+                }
+                Export::Function { .. } | Export::Table(_) => {}
+            }
+        }
+    }
+}
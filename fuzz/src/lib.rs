@@ -0,0 +1,4 @@
+//! Code shared by the fuzz targets.
+
+pub mod backends;
+pub mod differential;
@@ -84,6 +84,13 @@ pub trait WasmerEnv: Clone + Send + Sync {
     ///
     /// This function is called after `Instance` is created but before it is
     /// returned to the user via `Instance::new`.
+    ///
+    /// The exports of the given `Instance` don't keep it alive, since the
+    /// environment is itself owned by the instance. Once the instance has
+    /// been freed, its memories, tables and globals remain usable but
+    /// calling its functions fails with a [`RuntimeError`].
+    ///
+    /// [`RuntimeError`]: crate::RuntimeError
     fn init_with_instance(&mut self, _instance: &Instance) -> Result<(), HostEnvInitError> {
         Ok(())
     }
@@ -0,0 +1,202 @@
+use crate::exports::{ExportError, Exportable};
+use crate::externals::Extern;
+use crate::types::{TagType, Val, ValType};
+use crate::RuntimeError;
+use loupe::MemoryUsage;
+use std::error::Error;
+use std::fmt;
+use wasmer_engine::Export;
+use wasmer_vm::{VMException, VMExceptionTag};
+
+/// The tag of a WebAssembly exception, which identifies it and gives the
+/// types of its payload.
+///
+/// Tags are compared by identity: two tags with the same payload types
+/// are different tags. Modules export their tags, and import those of the
+/// host or of other instances, to throw and catch the same exceptions.
+///
+/// Spec: <https://github.com/WebAssembly/exception-handling/blob/master/proposals/exception-handling/Exceptions.md>
+#[derive(Clone, Debug, PartialEq, Eq, MemoryUsage)]
+pub struct ExceptionTag {
+    vm_tag: VMExceptionTag,
+}
+
+impl ExceptionTag {
+    /// Creates a new tag for exceptions carrying values of type `params`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `params` has reference types, which exception payloads
+    /// don't support yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{ExceptionTag, Type};
+    /// let tag = ExceptionTag::new(&[Type::I32]).unwrap();
+    ///
+    /// assert_eq!(tag.params(), &[Type::I32]);
+    /// assert_ne!(tag, ExceptionTag::new(&[Type::I32]).unwrap());
+    /// assert!(ExceptionTag::new(&[Type::ExternRef]).is_err());
+    /// ```
+    pub fn new(params: &[ValType]) -> Result<Self, RuntimeError> {
+        if params.iter().any(|ty| ty.is_ref()) {
+            return Err(RuntimeError::new(format!(
+                "exception payloads can't have reference types yet, unlike {:?}",
+                params
+            )));
+        }
+        Ok(Self {
+            vm_tag: VMExceptionTag::new(params),
+        })
+    }
+
+    /// The types of the payload of the exceptions with this tag.
+    pub fn params(&self) -> &[ValType] {
+        self.vm_tag.params()
+    }
+
+    /// The type of this tag, as in the imports and exports of modules.
+    pub fn ty(&self) -> TagType {
+        TagType::new(self.params())
+    }
+
+    pub(crate) fn from_vm_export(vm_tag: VMExceptionTag) -> Self {
+        Self { vm_tag }
+    }
+}
+
+impl<'a> Exportable<'a> for ExceptionTag {
+    fn to_export(&self) -> Export {
+        Export::Tag(self.vm_tag.clone())
+    }
+
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Tag(tag) => Ok(tag),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
+
+/// A WebAssembly exception, thrown by a `throw` instruction or by a host
+/// function.
+///
+/// A host function throws an exception to its WebAssembly caller by
+/// returning it as its error, either directly or as a [`RuntimeError`].
+/// The exception unwinds the stack like a trap, up to the innermost `try`
+/// which catches it. An exception nobody caught is returned to the host as
+/// a `RuntimeError`, see [`Exception::from_error`].
+#[derive(Clone, Debug)]
+pub struct Exception {
+    vm_exception: VMException,
+}
+
+impl Exception {
+    /// Creates an exception with the tag `tag`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `payload` doesn't have a value of each type of the
+    /// parameters of `tag`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Exception, ExceptionTag, Type, Value};
+    /// let tag = ExceptionTag::new(&[Type::I32, Type::F64]).unwrap();
+    /// let exception = Exception::new(&tag, &[Value::I32(1), Value::F64(2.0)]).unwrap();
+    ///
+    /// assert_eq!(exception.tag(), tag);
+    /// assert_eq!(exception.payload(), vec![Value::I32(1), Value::F64(2.0)]);
+    /// assert!(Exception::new(&tag, &[Value::I32(1)]).is_err());
+    /// ```
+    pub fn new(tag: &ExceptionTag, payload: &[Val]) -> Result<Self, RuntimeError> {
+        let types = payload.iter().map(Val::ty).collect::<Vec<_>>();
+        if types != tag.params() {
+            return Err(RuntimeError::new(format!(
+                "the payload of the exception has the types {:?} instead of {:?}",
+                types,
+                tag.params()
+            )));
+        }
+        let payload = payload
+            .iter()
+            .map(|value| match *value {
+                Val::I32(value) => value as u32 as u128,
+                Val::I64(value) => value as u64 as u128,
+                Val::F32(value) => value.to_bits() as u128,
+                Val::F64(value) => value.to_bits() as u128,
+                Val::V128(value) => value,
+                _ => unreachable!("tags don't have reference types"),
+            })
+            .collect();
+        Ok(Self {
+            vm_exception: VMException::new(tag.vm_tag.clone(), payload),
+        })
+    }
+
+    /// Gets the exception a WebAssembly function threw and nobody caught,
+    /// or gives the error back if it's not an exception.
+    pub fn from_error(error: RuntimeError) -> Result<Self, RuntimeError> {
+        error
+            .downcast::<VMException>()
+            .map(|vm_exception| Self { vm_exception })
+    }
+
+    /// The tag of the exception.
+    pub fn tag(&self) -> ExceptionTag {
+        ExceptionTag {
+            vm_tag: self.vm_exception.tag().clone(),
+        }
+    }
+
+    /// The payload of the exception.
+    pub fn payload(&self) -> Vec<Val> {
+        // Only the low bits of a slot hold a value narrower than 128 bits.
+        self.vm_exception
+            .tag()
+            .params()
+            .iter()
+            .zip(self.vm_exception.payload())
+            .map(|(ty, &raw)| match ty {
+                ValType::I32 => Val::I32(raw as u32 as i32),
+                ValType::I64 => Val::I64(raw as u64 as i64),
+                ValType::F32 => Val::F32(f32::from_bits(raw as u32)),
+                ValType::F64 => Val::F64(f64::from_bits(raw as u64)),
+                ValType::V128 => Val::V128(raw),
+                _ => unreachable!("tags don't have reference types"),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "uncaught exception")
+    }
+}
+
+impl Error for Exception {}
+
+impl From<Exception> for RuntimeError {
+    fn from(exception: Exception) -> Self {
+        Self::from_trap(wasmer_vm::Trap::User(Box::new(exception.vm_exception)))
+    }
+}
+
+/// Turns the error a host function returned into the trap it raises: an
+/// exception is raised as its `VMException`, for WebAssembly to catch it.
+pub(crate) fn into_trap(error: Box<dyn Error + Send + Sync>) -> Box<dyn Error + Send + Sync> {
+    let error = match error.downcast::<Exception>() {
+        Ok(exception) => return Box::new(exception.vm_exception),
+        Err(error) => error,
+    };
+    match error.downcast::<RuntimeError>() {
+        Ok(error) => match error.downcast::<VMException>() {
+            Ok(exception) => Box::new(exception),
+            Err(error) => Box::new(error),
+        },
+        Err(error) => error,
+    }
+}
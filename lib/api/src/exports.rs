@@ -1,3 +1,4 @@
+use crate::exception::ExceptionTag;
 use crate::externals::{Extern, Function, Global, Memory, Table};
 use crate::import_object::LikeNamespace;
 use crate::native::NativeFunc;
@@ -134,6 +135,11 @@ impl Exports {
         self.get(name)
     }
 
+    /// Get an export as an `ExceptionTag`.
+    pub fn get_tag(&self, name: &str) -> Result<&ExceptionTag, ExportError> {
+        self.get(name)
+    }
+
     /// Get an export as a `Func`.
     pub fn get_function(&self, name: &str) -> Result<&Function, ExportError> {
         self.get(name)
@@ -256,6 +262,14 @@ where
             _ => None,
         })
     }
+
+    /// Get only the exception tags.
+    pub fn tags(self) -> impl Iterator<Item = (&'a String, &'a ExceptionTag)> + Sized {
+        self.iter.filter_map(|(name, export)| match export {
+            Extern::Tag(tag) => Some((name, tag)),
+            _ => None,
+        })
+    }
 }
 
 impl FromIterator<(String, Extern)> for Exports {
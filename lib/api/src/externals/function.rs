@@ -1,6 +1,8 @@
+use crate::exception::into_trap;
 use crate::exports::{ExportError, Exportable};
 use crate::externals::Extern;
 use crate::store::Store;
+use crate::trace::TracedCall;
 use crate::types::{Val, ValFuncRef};
 use crate::FunctionType;
 use crate::NativeFunc;
@@ -16,8 +18,9 @@ use std::ffi::c_void;
 use std::fmt;
 use std::sync::Arc;
 use wasmer_engine::{Export, ExportFunction, ExportFunctionMetadata};
+use wasmer_types::{Trace, Tracer};
 use wasmer_vm::{
-    raise_user_trap, resume_panic, wasmer_call_trampoline, ImportInitializerFuncPtr,
+    raise_user_trap, resume_panic, wasmer_call_trampoline, ImportInitializerFuncPtr, InstanceRef,
     VMCallerCheckedAnyfunc, VMDynamicFunctionContext, VMFuncRef, VMFunction, VMFunctionBody,
     VMFunctionEnvironment, VMFunctionKind, VMTrampoline,
 };
@@ -39,10 +42,20 @@ use wasmer_vm::{
 ///   with native functions. Attempting to create a native `Function` with one will
 ///   result in a panic.
 ///   [Closures as host functions tracking issue](https://github.com/wasmerio/wasmer/issues/1840)
-#[derive(Clone, PartialEq, MemoryUsage)]
+#[derive(Clone, MemoryUsage)]
 pub struct Function {
     pub(crate) store: Store,
     pub(crate) exported: ExportFunction,
+    /// The call reported to the call tracer of the store, if this is a
+    /// traced export.
+    #[loupe(skip)]
+    pub(crate) traced_call: Option<Arc<TracedCall>>,
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        self.store == other.store && self.exported == other.exported
+    }
 }
 
 impl wasmer_types::WasmValueType for Function {
@@ -232,19 +245,23 @@ impl Function {
         let address = std::ptr::null() as *const VMFunctionBody;
         let vmctx = VMFunctionEnvironment { host_env };
 
+        let exported = ExportFunction {
+            metadata: Some(Arc::new(metadata)),
+            vm_function: VMFunction {
+                address,
+                kind: VMFunctionKind::Dynamic,
+                vmctx,
+                signature: ty,
+                call_trampoline: None,
+                instance_ref: None,
+            },
+        };
+        store.track_function(&exported);
+
         Self {
             store: store.clone(),
-            exported: ExportFunction {
-                metadata: Some(Arc::new(metadata)),
-                vm_function: VMFunction {
-                    address,
-                    kind: VMFunctionKind::Dynamic,
-                    vmctx,
-                    signature: ty,
-                    call_trampoline: None,
-                    instance_ref: None,
-                },
-            },
+            exported,
+            traced_call: None,
         }
     }
 
@@ -297,6 +314,7 @@ impl Function {
                     instance_ref: None,
                 },
             },
+            traced_call: None,
         }
     }
 
@@ -342,19 +360,23 @@ impl Function {
         let vmctx = VMFunctionEnvironment { host_env };
         let signature = function.ty();
 
+        let exported = ExportFunction {
+            metadata: Some(Arc::new(metadata)),
+            vm_function: VMFunction {
+                address,
+                kind: VMFunctionKind::Static,
+                vmctx,
+                signature,
+                call_trampoline: None,
+                instance_ref: None,
+            },
+        };
+        store.track_function(&exported);
+
         Self {
             store: store.clone(),
-            exported: ExportFunction {
-                metadata: Some(Arc::new(metadata)),
-                vm_function: VMFunction {
-                    address,
-                    kind: VMFunctionKind::Static,
-                    vmctx,
-                    signature,
-                    call_trampoline: None,
-                    instance_ref: None,
-                },
-            },
+            exported,
+            traced_call: None,
         }
     }
 
@@ -389,19 +411,23 @@ impl Function {
         let vmctx = VMFunctionEnvironment { host_env };
         let signature = function.ty();
 
+        let exported = ExportFunction {
+            metadata: Some(Arc::new(metadata)),
+            vm_function: VMFunction {
+                address,
+                kind: VMFunctionKind::Static,
+                vmctx,
+                signature,
+                call_trampoline: None,
+                instance_ref: None,
+            },
+        };
+        store.track_function(&exported);
+
         Self {
             store: store.clone(),
-            exported: ExportFunction {
-                metadata: Some(Arc::new(metadata)),
-                vm_function: VMFunction {
-                    address,
-                    kind: VMFunctionKind::Static,
-                    vmctx,
-                    signature,
-                    call_trampoline: None,
-                    instance_ref: None,
-                },
-            },
+            exported,
+            traced_call: None,
         }
     }
 
@@ -571,9 +597,21 @@ impl Function {
     /// assert_eq!(sum.call(&[Value::I32(1), Value::I32(2)]).unwrap().to_vec(), vec![Value::I32(3)]);
     /// ```
     pub fn call(&self, params: &[Val]) -> Result<Box<[Val]>, RuntimeError> {
+        let _instance = hold_instance(&self.exported)?;
         if let Some(trampoline) = self.exported.vm_function.call_trampoline {
             let mut results = vec![Val::null(); self.result_arity()];
-            self.call_wasm(trampoline, params, &mut results)?;
+            let tracer = match &self.traced_call {
+                Some(call) => self.store.call_tracer().map(|tracer| (call, tracer)),
+                None => None,
+            };
+            if let Some((call, tracer)) = &tracer {
+                tracer.enter(call, params);
+            }
+            let result = self.call_wasm(trampoline, params, &mut results);
+            if let Some((call, tracer)) = &tracer {
+                tracer.exit(call, params, result.as_ref().map(|()| &results[..]));
+            }
+            result?;
             return Ok(results.into_boxed_slice());
         }
 
@@ -584,6 +622,7 @@ impl Function {
         Self {
             store: store.clone(),
             exported: wasmer_export,
+            traced_call: None,
         }
     }
 
@@ -705,7 +744,11 @@ impl Function {
             }
         }
 
-        Ok(NativeFunc::new(self.store.clone(), self.exported.clone()))
+        Ok(NativeFunc::new(
+            self.store.clone(),
+            self.exported.clone(),
+            self.traced_call.clone(),
+        ))
     }
 
     #[track_caller]
@@ -714,6 +757,12 @@ impl Function {
     }
 }
 
+unsafe impl Trace for Function {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        tracer.object(&self.exported.vm_function);
+    }
+}
+
 impl<'a> Exportable<'a> for Function {
     fn to_export(&self) -> Export {
         self.exported.clone().into()
@@ -736,6 +785,24 @@ impl fmt::Debug for Function {
     }
 }
 
+/// Keeps the instance of the function `exported` alive during a call.
+///
+/// The functions given to host environments don't keep their instance
+/// alive, and fail to be called once it has been freed.
+pub(crate) fn hold_instance(
+    exported: &ExportFunction,
+) -> Result<Option<InstanceRef>, RuntimeError> {
+    match &exported.vm_function.instance_ref {
+        Some(instance_ref) => match instance_ref.upgrade() {
+            Some(instance_ref) => Ok(Some(instance_ref)),
+            None => Err(RuntimeError::new(
+                "the instance of this function has been freed",
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
 /// This trait is one that all dynamic functions must fulfill.
 pub(crate) trait VMDynamicFunction: Send + Sync {
     fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError>;
@@ -836,7 +903,7 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
 
         match result {
             Ok(Ok(())) => {}
-            Ok(Err(trap)) => raise_user_trap(Box::new(trap)),
+            Ok(Err(trap)) => raise_user_trap(into_trap(Box::new(trap))),
             Err(panic) => resume_panic(panic),
         }
     }
@@ -845,6 +912,7 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
 /// This private inner module contains the low-level implementation
 /// for `Function` and its siblings.
 mod inner {
+    use crate::exception::into_trap;
     use std::array::TryFromSliceError;
     use std::convert::{Infallible, TryInto};
     use std::error::Error;
@@ -1339,7 +1407,7 @@ mod inner {
 
                         match result {
                             Ok(Ok(result)) => return result.into_c_struct(),
-                            Ok(Err(trap)) => unsafe { raise_user_trap(Box::new(trap)) },
+                            Ok(Err(trap)) => unsafe { raise_user_trap(into_trap(Box::new(trap))) },
                             Err(panic) => unsafe { resume_panic(panic) },
                         }
                     }
@@ -1383,7 +1451,7 @@ mod inner {
 
                         match result {
                             Ok(Ok(result)) => return result.into_c_struct(),
-                            Ok(Err(trap)) => unsafe { raise_user_trap(Box::new(trap)) },
+                            Ok(Err(trap)) => unsafe { raise_user_trap(into_trap(Box::new(trap))) },
                             Err(panic) => unsafe { resume_panic(panic) },
                         }
                     }
@@ -1429,7 +1497,7 @@ mod inner {
 
                         match result {
                             Ok(Ok(result)) => return result.into_c_struct(),
-                            Ok(Err(trap)) => unsafe { raise_user_trap(Box::new(trap)) },
+                            Ok(Err(trap)) => unsafe { raise_user_trap(into_trap(Box::new(trap))) },
                             Err(panic) => unsafe { resume_panic(panic) },
                         }
                     }
@@ -9,6 +9,7 @@ use loupe::MemoryUsage;
 use std::fmt;
 use std::sync::Arc;
 use wasmer_engine::Export;
+use wasmer_types::{Trace, Tracer};
 use wasmer_vm::{Global as RuntimeGlobal, VMGlobal};
 
 /// A WebAssembly `global` instance.
@@ -220,6 +221,12 @@ impl fmt::Debug for Global {
     }
 }
 
+unsafe impl Trace for Global {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        tracer.object(&self.vm_global);
+    }
+}
+
 impl<'a> Exportable<'a> for Global {
     fn to_export(&self) -> Export {
         self.vm_global.clone().into()
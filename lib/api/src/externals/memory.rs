@@ -1,13 +1,14 @@
 use crate::exports::{ExportError, Exportable};
 use crate::externals::Extern;
 use crate::store::Store;
-use crate::{MemoryType, MemoryView};
+use crate::{MemoryAccessError, MemoryType, MemoryView};
 use loupe::MemoryUsage;
-use std::convert::TryInto;
+use std::mem;
+use std::ptr;
 use std::slice;
 use std::sync::Arc;
 use wasmer_engine::Export;
-use wasmer_types::{Pages, ValueType};
+use wasmer_types::{Pages, Trace, Tracer, ValueType};
 use wasmer_vm::{MemoryError, VMMemory};
 
 /// A WebAssembly `memory` instance.
@@ -48,8 +49,35 @@ impl Memory {
         let tunables = store.tunables();
         let style = tunables.memory_style(&ty);
         let memory = tunables.create_host_memory(&ty, &style)?;
+        Ok(Self::from_host_memory(store, memory))
+    }
+
+    /// Creates a new host `Memory` indexed with 64-bit addresses, as in the
+    /// [memory64 proposal], from the provided [`MemoryType`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new64(&store, MemoryType::new(1, None, false)).unwrap();
+    ///
+    /// assert!(m.is_64());
+    /// ```
+    ///
+    /// [memory64 proposal]: https://github.com/WebAssembly/memory64
+    pub fn new64(store: &Store, ty: MemoryType) -> Result<Self, MemoryError> {
+        let tunables = store.tunables();
+        let style = tunables.memory64_style(&ty);
+        let memory = tunables.create_host_memory64(&ty, &style)?;
+        Ok(Self::from_host_memory(store, memory))
+    }
 
-        Ok(Self {
+    fn from_host_memory(store: &Store, memory: Arc<dyn wasmer_vm::Memory>) -> Self {
+        store.track_memory(&memory);
+
+        Self {
             store: store.clone(),
             vm_memory: VMMemory {
                 from: memory,
@@ -57,7 +85,7 @@ impl Memory {
                 // associated instance with this memory
                 instance_ref: None,
             },
-        })
+        }
     }
 
     /// Returns the [`MemoryType`] of the `Memory`.
@@ -77,6 +105,22 @@ impl Memory {
         self.vm_memory.from.ty()
     }
 
+    /// Returns whether the `Memory` is indexed with 64-bit addresses.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    ///
+    /// assert!(!m.is_64());
+    /// ```
+    pub fn is_64(&self) -> bool {
+        self.vm_memory.is_64()
+    }
+
     /// Returns the [`Store`] where the `Memory` belongs.
     ///
     /// # Example
@@ -117,7 +161,7 @@ impl Memory {
     pub unsafe fn data_unchecked_mut(&self) -> &mut [u8] {
         let definition = self.vm_memory.from.vmmemory();
         let def = definition.as_ref();
-        slice::from_raw_parts_mut(def.base, def.current_length.try_into().unwrap())
+        slice::from_raw_parts_mut(def.base, def.current_length)
     }
 
     /// Returns the pointer to the raw bytes of the `Memory`.
@@ -131,7 +175,7 @@ impl Memory {
     pub fn data_size(&self) -> u64 {
         let definition = self.vm_memory.from.vmmemory();
         let def = unsafe { definition.as_ref() };
-        def.current_length.into()
+        def.current_length as u64
     }
 
     /// Returns the size (in [`Pages`]) of the `Memory`.
@@ -226,6 +270,84 @@ impl Memory {
         unsafe { MemoryView::new(base as _, length as u32) }
     }
 
+    /// Returns the part of the memory at `offset` of `len` bytes, as a
+    /// pointer, if it fits in the memory.
+    pub(crate) fn checked_range(
+        &self,
+        offset: u64,
+        len: u64,
+    ) -> Result<*mut u8, MemoryAccessError> {
+        let end = offset.checked_add(len).ok_or(MemoryAccessError::Overflow)?;
+        if end > self.data_size() {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+        // `offset` fits in `usize`, since it's lower than the size of the
+        // memory mapped in this process.
+        Ok(unsafe { self.data_ptr().add(offset as usize) })
+    }
+
+    /// Copies the bytes of the memory at `offset` into `buf`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write(0x100, b"hello").unwrap();
+    ///
+    /// let mut buf = [0; 5];
+    /// m.read(0x100, &mut buf).unwrap();
+    /// assert_eq!(&buf, b"hello");
+    /// ```
+    pub fn read(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
+        let src = self.checked_range(offset, buf.len() as u64)?;
+        unsafe { ptr::copy_nonoverlapping(src, buf.as_mut_ptr(), buf.len()) };
+        Ok(())
+    }
+
+    /// Copies `data` into the memory at `offset`.
+    ///
+    /// Nothing is written when `data` doesn't fit in the memory.
+    pub fn write(&self, offset: u64, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let dst = self.checked_range(offset, data.len() as u64)?;
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), dst, data.len()) };
+        Ok(())
+    }
+
+    /// Reads a value of type `T` at `offset`, which doesn't need to be
+    /// aligned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryAccessError, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write_value(0x101, 42u32).unwrap();
+    ///
+    /// assert_eq!(m.read_value::<u32>(0x101), Ok(42));
+    /// assert_eq!(m.read_value::<u32>(0xfffe), Err(MemoryAccessError::HeapOutOfBounds));
+    /// ```
+    pub fn read_value<T: ValueType>(&self, offset: u64) -> Result<T, MemoryAccessError> {
+        let src = self.checked_range(offset, mem::size_of::<T>() as u64)?;
+        // `ValueType` guarantees that any bit pattern is a valid `T`.
+        Ok(unsafe { ptr::read_unaligned(src as *const T) })
+    }
+
+    /// Writes `value` at `offset`, which doesn't need to be aligned.
+    pub fn write_value<T: ValueType>(
+        &self,
+        offset: u64,
+        value: T,
+    ) -> Result<(), MemoryAccessError> {
+        let dst = self.checked_range(offset, mem::size_of::<T>() as u64)?;
+        unsafe { ptr::write_unaligned(dst as *mut T, value) };
+        Ok(())
+    }
+
     pub(crate) fn from_vm_export(store: &Store, vm_memory: VMMemory) -> Self {
         Self {
             store: store.clone(),
@@ -250,6 +372,12 @@ impl Memory {
     }
 }
 
+unsafe impl Trace for Memory {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        tracer.object(&self.vm_memory);
+    }
+}
+
 impl<'a> Exportable<'a> for Memory {
     fn to_export(&self) -> Export {
         self.vm_memory.clone().into()
@@ -13,12 +13,14 @@ pub use self::global::Global;
 pub use self::memory::Memory;
 pub use self::table::Table;
 
+use crate::exception::ExceptionTag;
 use crate::exports::{ExportError, Exportable};
 use crate::store::{Store, StoreObject};
 use crate::ExternType;
 use loupe::MemoryUsage;
 use std::fmt;
 use wasmer_engine::Export;
+use wasmer_types::{Trace, Tracer};
 
 /// An `Extern` is the runtime representation of an entity that
 /// can be imported or exported.
@@ -34,6 +36,8 @@ pub enum Extern {
     Table(Table),
     /// A external [`Memory`].
     Memory(Memory),
+    /// A external [`ExceptionTag`].
+    Tag(ExceptionTag),
 }
 
 impl Extern {
@@ -44,6 +48,7 @@ impl Extern {
             Self::Memory(ft) => ExternType::Memory(ft.ty()),
             Self::Table(tt) => ExternType::Table(*tt.ty()),
             Self::Global(gt) => ExternType::Global(*gt.ty()),
+            Self::Tag(tag) => ExternType::Tag(tag.ty()),
         }
     }
 
@@ -54,6 +59,20 @@ impl Extern {
             Export::Memory(m) => Self::Memory(Memory::from_vm_export(store, m)),
             Export::Global(g) => Self::Global(Global::from_vm_export(store, g)),
             Export::Table(t) => Self::Table(Table::from_vm_export(store, t)),
+            Export::Tag(t) => Self::Tag(ExceptionTag::from_vm_export(t)),
+        }
+    }
+}
+
+unsafe impl Trace for Extern {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        match self {
+            Self::Function(f) => f.trace(tracer),
+            Self::Global(g) => g.trace(tracer),
+            Self::Memory(m) => m.trace(tracer),
+            Self::Table(t) => t.trace(tracer),
+            // Exception payloads can't hold references.
+            Self::Tag(_) => {}
         }
     }
 }
@@ -65,6 +84,7 @@ impl<'a> Exportable<'a> for Extern {
             Self::Global(g) => g.to_export(),
             Self::Memory(m) => m.to_export(),
             Self::Table(t) => t.to_export(),
+            Self::Tag(t) => t.to_export(),
         }
     }
 
@@ -81,6 +101,8 @@ impl StoreObject for Extern {
             Self::Global(g) => g.store(),
             Self::Memory(m) => m.store(),
             Self::Table(t) => t.store(),
+            // Tags don't belong to a store.
+            Self::Tag(_) => return true,
         };
         Store::same(my_store, store)
     }
@@ -96,6 +118,7 @@ impl fmt::Debug for Extern {
                 Self::Global(_) => "Global(...)",
                 Self::Memory(_) => "Memory(...)",
                 Self::Table(_) => "Table(...)",
+                Self::Tag(_) => "Tag(...)",
             }
         )
     }
@@ -124,3 +147,9 @@ impl From<Table> for Extern {
         Self::Table(r)
     }
 }
+
+impl From<ExceptionTag> for Extern {
+    fn from(r: ExceptionTag) -> Self {
+        Self::Tag(r)
+    }
+}
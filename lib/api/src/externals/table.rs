@@ -7,6 +7,7 @@ use crate::TableType;
 use loupe::MemoryUsage;
 use std::sync::Arc;
 use wasmer_engine::Export;
+use wasmer_types::{Trace, Tracer};
 use wasmer_vm::{Table as RuntimeTable, TableElement, VMTable};
 
 /// A WebAssembly `table` instance.
@@ -46,6 +47,7 @@ impl Table {
         let table = tunables
             .create_host_table(&ty, &style)
             .map_err(RuntimeError::new)?;
+        store.track_table(&table);
 
         let num_elements = table.size();
         for i in 0..num_elements {
@@ -148,6 +150,12 @@ impl Table {
     }
 }
 
+unsafe impl Trace for Table {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        tracer.object(&self.vm_table);
+    }
+}
+
 impl<'a> Exportable<'a> for Table {
     fn to_export(&self) -> Export {
         self.vm_table.clone().into()
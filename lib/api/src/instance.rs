@@ -2,6 +2,7 @@ use crate::exports::Exports;
 use crate::externals::Extern;
 use crate::module::Module;
 use crate::store::Store;
+use crate::trace::{TracedCall, TracingResolver};
 use crate::{HostEnvInitError, LinkError, RuntimeError};
 use loupe::MemoryUsage;
 use std::fmt;
@@ -112,23 +113,32 @@ impl Instance {
     /// Those are, as defined by the spec:
     ///  * Link errors that happen when plugging the imports into the instance
     ///  * Runtime errors that happen when running the module `start` function.
+    ///
+    /// If a call tracer is set on the store (see [`Store::set_call_tracer`]),
+    /// the calls to the imported and exported functions of the instance
+    /// are traced.
     pub fn new(module: &Module, resolver: &dyn Resolver) -> Result<Self, InstantiationError> {
         let store = module.store();
-        let handle = module.instantiate(resolver)?;
-        let exports = module
-            .exports()
-            .map(|export| {
-                let name = export.name().to_string();
-                let export = handle.lookup(&name).expect("export");
-                let extern_ = Extern::from_vm_export(store, export.into());
-                (name, extern_)
-            })
-            .collect::<Exports>();
-
+        let traced = store.call_tracer().is_some();
+        let handle = if traced {
+            module.instantiate(&TracingResolver::new(module, resolver))?
+        } else {
+            module.instantiate(resolver)?
+        };
+        store.track_instance(&handle);
+
+        // The host environments are owned by the instance, so the exports
+        // they get must not keep it alive, which would create a cycle.
+        let env_handle = handle.downgrade();
+        let env_instance = Self {
+            exports: Self::exports(module, &env_handle, traced),
+            handle: Arc::new(Mutex::new(env_handle)),
+            module: module.clone(),
+        };
         let instance = Self {
+            exports: Self::exports(module, &handle, traced),
             handle: Arc::new(Mutex::new(handle)),
             module: module.clone(),
-            exports,
         };
 
         // # Safety
@@ -136,7 +146,7 @@ impl Instance {
         // returning an `Instance` to the user. We set up the host environments
         // via `WasmerEnv::init_with_instance`.
         //
-        // This usage is correct because we pass a valid pointer to `instance` and the
+        // This usage is correct because we pass a valid pointer to `env_instance` and the
         // correct error type returned by `WasmerEnv::init_with_instance` as a generic
         // parameter.
         unsafe {
@@ -144,12 +154,47 @@ impl Instance {
                 .handle
                 .lock()
                 .unwrap()
-                .initialize_host_envs::<HostEnvInitError>(&instance as *const _ as *const _)?;
+                .initialize_host_envs::<HostEnvInitError>(&env_instance as *const _ as *const _)?;
         }
 
         Ok(instance)
     }
 
+    fn exports(module: &Module, handle: &InstanceHandle, traced: bool) -> Exports {
+        module
+            .exports()
+            .map(|export| {
+                let name = export.name().to_string();
+                let export = handle.lookup(&name).expect("export");
+                let mut extern_ = Extern::from_vm_export(module.store(), export.into());
+                if traced {
+                    if let Extern::Function(function) = &mut extern_ {
+                        function.traced_call = Some(Arc::new(TracedCall::export(module, &name)));
+                    }
+                }
+                (name, extern_)
+            })
+            .collect()
+    }
+
+    /// Drops this handle to the instance, and returns whether it was the
+    /// last one, freeing the instance along with its memories, tables,
+    /// globals and host environments.
+    ///
+    /// This doesn't free anything still in use: the instance is kept alive
+    /// by its clones and by its exports, such as the [`Function`]s returned
+    /// by [`Exports::get_function`], wherever they are held. They must be
+    /// dropped first for the instance to be freed, which [`Store::usage`]
+    /// helps to track down.
+    ///
+    /// [`Function`]: crate::Function
+    #[must_use]
+    pub fn release(self) -> bool {
+        let handle = self.handle.lock().unwrap().downgrade();
+        drop(self);
+        !handle.is_alive()
+    }
+
     /// Gets the [`Module`] associated with this instance.
     pub fn module(&self) -> &Module {
         &self.module
@@ -254,14 +254,18 @@
 //! [wasmer-wasi]: https://docs.rs/wasmer-wasi/*/wasmer_wasi/
 
 mod env;
+mod exception;
 mod exports;
 mod externals;
 mod import_object;
 mod instance;
+mod linker;
+mod mem_access;
 mod module;
 mod native;
 mod ptr;
 mod store;
+mod trace;
 mod tunables;
 mod types;
 mod utils;
@@ -284,20 +288,24 @@ pub mod internals {
 }
 
 pub use crate::env::{HostEnvInitError, LazyInit, WasmerEnv};
+pub use crate::exception::{Exception, ExceptionTag};
 pub use crate::exports::{ExportError, Exportable, Exports, ExportsIterator};
 pub use crate::externals::{
     Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, Table, WasmTypeList,
 };
 pub use crate::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
 pub use crate::instance::{Instance, InstantiationError};
+pub use crate::linker::{Linker, LinkerError};
+pub use crate::mem_access::{MemoryAccessError, WasmRef, WasmSlice};
 pub use crate::module::Module;
 pub use crate::native::NativeFunc;
-pub use crate::ptr::{Array, Item, WasmPtr};
-pub use crate::store::{Store, StoreObject};
-pub use crate::tunables::BaseTunables;
+pub use crate::ptr::{Array, Item, WasmPtr, WasmPtr64};
+pub use crate::store::{Store, StoreObject, StoreUsage};
+pub use crate::trace::{CallKind, CallTracer, TracedCall};
+pub use crate::tunables::{BaseTunables, StackTunables};
 pub use crate::types::{
     ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, Mutability,
-    TableType, Val, ValType,
+    TableType, TagType, Val, ValType,
 };
 pub use crate::types::{Val as Value, ValType as Type};
 pub use crate::utils::is_wasm;
@@ -308,21 +316,22 @@ pub use wasmer_compiler::{
     ModuleMiddleware,
 };
 pub use wasmer_compiler::{
-    CompileError, CpuFeature, Features, ParseCpuFeatureError, Target, WasmError, WasmResult,
+    unique_fingerprint, CompileError, CpuFeature, Features, ParseCpuFeatureError, Target,
+    WasmError, WasmResult,
 };
 pub use wasmer_engine::{
-    ChainableNamedResolver, DeserializeError, Engine, Export, FrameInfo, LinkError, NamedResolver,
-    NamedResolverChain, Resolver, RuntimeError, SerializeError, Tunables,
+    ChainableNamedResolver, DeserializeError, Engine, Export, FrameInfo, ImportError, LinkError,
+    NamedResolver, NamedResolverChain, Resolver, RuntimeError, SerializeError, Tunables,
 };
 #[cfg(feature = "experimental-reference-types-extern-ref")]
 pub use wasmer_types::ExternRef;
 pub use wasmer_types::{
-    Atomically, Bytes, ExportIndex, GlobalInit, LocalFunctionIndex, MemoryView, Pages, ValueType,
-    WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
+    Atomically, Bytes, ExportIndex, ExternRefHeap, GlobalInit, LocalFunctionIndex, MemoryView,
+    Pages, Trace, Tracer, ValueType, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
 };
 
 // TODO: should those be moved into wasmer::vm as well?
-pub use wasmer_vm::{raise_user_trap, MemoryError};
+pub use wasmer_vm::{raise_user_trap, MemoryError, StackConfig};
 pub mod vm {
     //! The vm module re-exports wasmer-vm types.
 
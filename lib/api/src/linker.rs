@@ -0,0 +1,329 @@
+//! The linker module contains the [`Linker`], a helper that instantiates
+//! modules whose imports are satisfied by previously defined host modules
+//! and named instances.
+use crate::exports::{Exportable, Exports};
+use crate::externals::Extern;
+use crate::import_object::ImportObject;
+use crate::instance::{Instance, InstantiationError};
+use crate::module::Module;
+use crate::store::{Store, StoreObject};
+use indexmap::{IndexMap, IndexSet};
+use std::collections::VecDeque;
+use std::fmt;
+use thiserror::Error;
+use wasmer_engine::{Export, LinkError, NamedResolver};
+
+/// An error while defining items in, or instantiating modules with,
+/// a [`Linker`].
+#[derive(Error, Debug)]
+pub enum LinkerError {
+    /// The item is already defined and shadowing is not allowed.
+    #[error("`{0}`.`{1}` is already defined in the linker")]
+    AlreadyDefined(String, String),
+
+    /// The item doesn't belong to the same [`Store`] as the linker.
+    #[error("`{0}`.`{1}` belongs to a different store than the linker")]
+    WrongStore(String, String),
+
+    /// Two modules with the same name were given to
+    /// [`Linker::instantiate_graph`].
+    #[error("module `{0}` appears more than once in the module graph")]
+    DuplicateModule(String),
+
+    /// The modules given to [`Linker::instantiate_graph`] import from
+    /// each other in a cycle, so there is no valid instantiation order.
+    #[error("cyclic imports between modules: {}", .0.join(", "))]
+    Cycle(Vec<String>),
+
+    /// The imports of a module couldn't be resolved.
+    #[error(transparent)]
+    Link(LinkError),
+
+    /// The module couldn't be instantiated.
+    #[error(transparent)]
+    Instantiation(InstantiationError),
+}
+
+impl From<LinkError> for LinkerError {
+    fn from(other: LinkError) -> Self {
+        Self::Link(other)
+    }
+}
+
+impl From<InstantiationError> for LinkerError {
+    fn from(other: InstantiationError) -> Self {
+        match other {
+            InstantiationError::Link(e) => Self::Link(e),
+            other => Self::Instantiation(other),
+        }
+    }
+}
+
+/// A `Linker` resolves the imports of modules against a set of
+/// named definitions.
+///
+/// Definitions are grouped by module name, just like the imports of a
+/// WebAssembly module. They can be host items registered one by one
+/// with [`Linker::define`], whole host modules registered with
+/// [`Linker::define_exports`] or [`Linker::define_import_object`], or
+/// the exports of an instance registered with [`Linker::define_instance`].
+///
+/// [`Linker::module`] instantiates a module and registers its exports
+/// under a name, so other modules can import from it. A whole set of
+/// modules that import from each other can be instantiated at once, in
+/// dependency order, with [`Linker::instantiate_graph`].
+///
+/// The `Linker` implements [`NamedResolver`], so it can also be passed
+/// directly to [`Instance::new`].
+///
+/// ```
+/// # use wasmer::{Store, Module, Linker, Value};
+/// # fn main() -> anyhow::Result<()> {
+/// let store = Store::default();
+/// let math = Module::new(&store, r#"
+/// (module
+///   (func (export "double") (param i32) (result i32)
+///     local.get 0
+///     i32.const 2
+///     i32.mul))
+/// "#)?;
+/// let app = Module::new(&store, r#"
+/// (module
+///   (import "math" "double" (func $double (param i32) (result i32)))
+///   (func (export "quadruple") (param i32) (result i32)
+///     local.get 0
+///     call $double
+///     call $double))
+/// "#)?;
+///
+/// let mut linker = Linker::new(&store);
+/// linker.module("math", &math)?;
+/// let instance = linker.instantiate(&app)?;
+///
+/// let quadruple = instance.exports.get_function("quadruple")?;
+/// assert_eq!(quadruple.call(&[Value::I32(3)])?[0], Value::I32(12));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Linker {
+    store: Store,
+    namespaces: IndexMap<String, IndexMap<String, Extern>>,
+    allow_shadowing: bool,
+}
+
+impl Linker {
+    /// Creates a new, empty `Linker` for the given [`Store`].
+    pub fn new(store: &Store) -> Self {
+        Self {
+            store: store.clone(),
+            namespaces: IndexMap::new(),
+            allow_shadowing: false,
+        }
+    }
+
+    /// Returns the [`Store`] where the `Linker` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Configures whether defining an item twice replaces the previous
+    /// definition (`true`) or fails with [`LinkerError::AlreadyDefined`]
+    /// (`false`, the default).
+    pub fn allow_shadowing(&mut self, allow: bool) -> &mut Self {
+        self.allow_shadowing = allow;
+        self
+    }
+
+    /// Defines `item` as `module`.`name`.
+    pub fn define(
+        &mut self,
+        module: &str,
+        name: &str,
+        item: impl Into<Extern>,
+    ) -> Result<&mut Self, LinkerError> {
+        let item = item.into();
+        if !item.comes_from_same_store(&self.store) {
+            return Err(LinkerError::WrongStore(module.to_string(), name.to_string()));
+        }
+        if !self.allow_shadowing && self.get(module, name).is_some() {
+            return Err(LinkerError::AlreadyDefined(
+                module.to_string(),
+                name.to_string(),
+            ));
+        }
+        self.namespaces
+            .entry(module.to_string())
+            .or_default()
+            .insert(name.to_string(), item);
+        Ok(self)
+    }
+
+    /// Defines every item of `exports` under the module name `module`.
+    ///
+    /// This is the way to register a host module.
+    pub fn define_exports(
+        &mut self,
+        module: &str,
+        exports: &Exports,
+    ) -> Result<&mut Self, LinkerError> {
+        for (name, item) in exports.iter() {
+            self.define(module, name, item.clone())?;
+        }
+        Ok(self)
+    }
+
+    /// Defines every export of `instance` under the module name `module`.
+    pub fn define_instance(
+        &mut self,
+        module: &str,
+        instance: &Instance,
+    ) -> Result<&mut Self, LinkerError> {
+        self.define_exports(module, &instance.exports)
+    }
+
+    /// Defines every item of an [`ImportObject`], keeping the module names
+    /// it was registered with.
+    pub fn define_import_object(
+        &mut self,
+        import_object: &ImportObject,
+    ) -> Result<&mut Self, LinkerError> {
+        for ((module, name), export) in import_object.clone() {
+            let item = Extern::from_vm_export(&self.store, export);
+            self.define(&module, &name, item)?;
+        }
+        Ok(self)
+    }
+
+    /// Gets the item defined as `module`.`name`, if any.
+    pub fn get(&self, module: &str, name: &str) -> Option<&Extern> {
+        self.namespaces.get(module)?.get(name)
+    }
+
+    /// Returns whether any item has been defined under the module name
+    /// `module`.
+    pub fn contains_module(&self, module: &str) -> bool {
+        self.namespaces.contains_key(module)
+    }
+
+    /// Iterates over all the definitions, as `(module, name, item)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &Extern)> {
+        self.namespaces.iter().flat_map(|(module, items)| {
+            items
+                .iter()
+                .map(move |(name, item)| (module.as_str(), name.as_str(), item))
+        })
+    }
+
+    /// Instantiates `module`, resolving its imports against the
+    /// definitions of the linker.
+    pub fn instantiate(&self, module: &Module) -> Result<Instance, LinkerError> {
+        Ok(Instance::new(module, self)?)
+    }
+
+    /// Instantiates `module` and defines all of its exports under the
+    /// module name `name`, so later modules can import from it.
+    pub fn module(&mut self, name: &str, module: &Module) -> Result<Instance, LinkerError> {
+        let instance = self.instantiate(module)?;
+        self.define_instance(name, &instance)?;
+        Ok(instance)
+    }
+
+    /// Instantiates a set of named modules that may import from each
+    /// other and from the definitions already in the linker.
+    ///
+    /// The modules are instantiated in dependency order, and the exports
+    /// of each one are defined under its name as with [`Linker::module`].
+    /// The definitions are only added to the linker once all the modules
+    /// are instantiated: if one fails, the linker is left unchanged,
+    /// though the start functions of the modules instantiated before it
+    /// have run.
+    pub fn instantiate_graph<I, S>(
+        &mut self,
+        modules: I,
+    ) -> Result<IndexMap<String, Instance>, LinkerError>
+    where
+        I: IntoIterator<Item = (S, Module)>,
+        S: Into<String>,
+    {
+        let mut graph: IndexMap<String, Module> = IndexMap::new();
+        for (name, module) in modules {
+            let name = name.into();
+            if graph.contains_key(&name) {
+                return Err(LinkerError::DuplicateModule(name));
+            }
+            graph.insert(name, module);
+        }
+
+        let order = Self::instantiation_order(&graph)?;
+
+        let mut staged = self.clone();
+        let mut instances = IndexMap::with_capacity(order.len());
+        for name in order {
+            let instance = staged.module(&name, &graph[&name])?;
+            instances.insert(name, instance);
+        }
+        *self = staged;
+        Ok(instances)
+    }
+
+    /// Sorts the modules of `graph` so that every module comes after the
+    /// modules of the graph it imports from.
+    fn instantiation_order(graph: &IndexMap<String, Module>) -> Result<Vec<String>, LinkerError> {
+        let mut dependencies: IndexMap<&str, IndexSet<&str>> = graph
+            .iter()
+            .map(|(name, module)| {
+                let deps = module
+                    .imports()
+                    .filter_map(|import| graph.get_key_value(import.module()))
+                    .map(|(dep, _)| dep.as_str())
+                    .collect();
+                (name.as_str(), deps)
+            })
+            .collect();
+
+        let mut order = Vec::with_capacity(graph.len());
+        let mut ready: VecDeque<&str> = dependencies
+            .iter()
+            .filter(|(_, deps)| deps.is_empty())
+            .map(|(name, _)| *name)
+            .collect();
+        while let Some(name) = ready.pop_front() {
+            dependencies.shift_remove(name);
+            for (dependent, deps) in dependencies.iter_mut() {
+                if deps.shift_remove(name) && deps.is_empty() {
+                    ready.push_back(*dependent);
+                }
+            }
+            order.push(name.to_string());
+        }
+
+        if !dependencies.is_empty() {
+            return Err(LinkerError::Cycle(
+                dependencies.keys().map(|name| name.to_string()).collect(),
+            ));
+        }
+        Ok(order)
+    }
+}
+
+impl NamedResolver for Linker {
+    fn resolve_by_name(&self, module: &str, field: &str) -> Option<Export> {
+        self.get(module, field).map(|item| item.to_export())
+    }
+}
+
+impl fmt::Debug for Linker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Linker")
+            .field(
+                "definitions",
+                &self
+                    .iter()
+                    .map(|(module, name, _)| format!("{}.{}", module, name))
+                    .collect::<Vec<_>>(),
+            )
+            .field("allow_shadowing", &self.allow_shadowing)
+            .finish()
+    }
+}
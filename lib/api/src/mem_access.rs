@@ -0,0 +1,371 @@
+//! Bounds-checked access to the contents of a Wasm linear memory.
+//!
+//! Unlike [`MemoryView`](crate::MemoryView), which holds a raw pointer to
+//! the memory, every access here looks up the current base and size of
+//! the memory, so it stays valid when the memory is grown. Values are
+//! copied in and out of the memory, without alignment requirements, and
+//! accesses which don't fit in the memory return a [`MemoryAccessError`].
+//!
+//! Offsets are 64-bit, so they can address both 32-bit and 64-bit memories.
+
+use crate::externals::Memory;
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+use std::ptr;
+use std::string::FromUtf8Error;
+use thiserror::Error;
+use wasmer_types::ValueType;
+
+/// An error when accessing the contents of a memory.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MemoryAccessError {
+    /// The access doesn't fit in the memory.
+    #[error("out of bounds memory access")]
+    HeapOutOfBounds,
+    /// The address or the length of the access overflows.
+    #[error("address calculation overflow")]
+    Overflow,
+    /// A string read from the memory isn't valid UTF-8.
+    #[error("string is not valid UTF-8")]
+    NonUtf8String,
+}
+
+impl From<FromUtf8Error> for MemoryAccessError {
+    fn from(_: FromUtf8Error) -> Self {
+        Self::NonUtf8String
+    }
+}
+
+/// A reference to a value of type `T` in a memory.
+///
+/// The value is read and written with [`WasmRef::read`] and
+/// [`WasmRef::write`], which check that it fits in the memory at the time
+/// of the access.
+#[derive(Clone, Copy)]
+pub struct WasmRef<'a, T: ValueType> {
+    memory: &'a Memory,
+    offset: u64,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: ValueType> WasmRef<'a, T> {
+    /// Creates a reference to the value at `offset` in `memory`.
+    #[inline]
+    pub fn new(memory: &'a Memory, offset: u64) -> Self {
+        Self {
+            memory,
+            offset,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The offset of the value in the memory.
+    #[inline]
+    pub fn offset(self) -> u64 {
+        self.offset
+    }
+
+    /// The memory the value is in.
+    #[inline]
+    pub fn memory(self) -> &'a Memory {
+        self.memory
+    }
+
+    /// Reads the value.
+    #[inline]
+    pub fn read(self) -> Result<T, MemoryAccessError> {
+        self.memory.read_value(self.offset)
+    }
+
+    /// Writes the value.
+    #[inline]
+    pub fn write(self, value: T) -> Result<(), MemoryAccessError> {
+        self.memory.write_value(self.offset, value)
+    }
+}
+
+impl<'a, T: ValueType> fmt::Debug for WasmRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "WasmRef(offset: {:#x}, type: {})",
+            self.offset,
+            std::any::type_name::<T>()
+        )
+    }
+}
+
+/// A slice of `len` values of type `T` in a memory.
+///
+/// Creating a slice only checks that its end can be computed: whether it
+/// fits in the memory is checked on each access, since the memory can be
+/// grown in between.
+#[derive(Clone, Copy)]
+pub struct WasmSlice<'a, T: ValueType> {
+    memory: &'a Memory,
+    offset: u64,
+    len: u64,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: ValueType> WasmSlice<'a, T> {
+    /// Creates a slice of `len` values starting at `offset` in `memory`.
+    ///
+    /// Returns [`MemoryAccessError::Overflow`] if the end of the slice
+    /// overflows.
+    #[inline]
+    pub fn new(memory: &'a Memory, offset: u64, len: u64) -> Result<Self, MemoryAccessError> {
+        len.checked_mul(mem::size_of::<T>() as u64)
+            .and_then(|size| offset.checked_add(size))
+            .ok_or(MemoryAccessError::Overflow)?;
+        Ok(Self {
+            memory,
+            offset,
+            len,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// The offset of the slice in the memory.
+    #[inline]
+    pub fn offset(self) -> u64 {
+        self.offset
+    }
+
+    /// The number of values in the slice.
+    #[inline]
+    pub fn len(self) -> u64 {
+        self.len
+    }
+
+    /// Whether the slice is empty.
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.len == 0
+    }
+
+    /// The size of the slice, in bytes.
+    #[inline]
+    fn size(self) -> u64 {
+        self.len * mem::size_of::<T>() as u64
+    }
+
+    /// A reference to the value at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` isn't lower than the length of the slice.
+    #[inline]
+    pub fn index(self, index: u64) -> WasmRef<'a, T> {
+        assert!(
+            index < self.len,
+            "WasmSlice index out of bounds: the len is {} but the index is {}",
+            self.len,
+            index
+        );
+        WasmRef::new(
+            self.memory,
+            self.offset + index * mem::size_of::<T>() as u64,
+        )
+    }
+
+    /// The slice of the values in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` isn't in the slice.
+    #[inline]
+    pub fn subslice(self, range: Range<u64>) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "WasmSlice range {:?} out of bounds for length {}",
+            range,
+            self.len
+        );
+        Self {
+            memory: self.memory,
+            offset: self.offset + range.start * mem::size_of::<T>() as u64,
+            len: range.end - range.start,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Reads the value at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` isn't lower than the length of the slice.
+    #[inline]
+    pub fn read(self, index: u64) -> Result<T, MemoryAccessError> {
+        self.index(index).read()
+    }
+
+    /// Writes `value` at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` isn't lower than the length of the slice.
+    #[inline]
+    pub fn write(self, index: u64, value: T) -> Result<(), MemoryAccessError> {
+        self.index(index).write(value)
+    }
+
+    /// Copies the values of the slice into `buf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` doesn't have the length of the slice.
+    pub fn read_slice(self, buf: &mut [T]) -> Result<(), MemoryAccessError> {
+        assert_eq!(
+            buf.len() as u64,
+            self.len,
+            "the buffer must have the length of the WasmSlice"
+        );
+        let src = self.memory.checked_range(self.offset, self.size())?;
+        unsafe { ptr::copy_nonoverlapping(src, buf.as_mut_ptr() as *mut u8, self.size() as usize) };
+        Ok(())
+    }
+
+    /// Copies the values of `data` into the slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` doesn't have the length of the slice.
+    pub fn write_slice(self, data: &[T]) -> Result<(), MemoryAccessError> {
+        assert_eq!(
+            data.len() as u64,
+            self.len,
+            "the data must have the length of the WasmSlice"
+        );
+        let dst = self.memory.checked_range(self.offset, self.size())?;
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr() as *const u8, dst, self.size() as usize) };
+        Ok(())
+    }
+
+    /// Reads the values of the slice into a `Vec`.
+    pub fn read_to_vec(self) -> Result<Vec<T>, MemoryAccessError> {
+        let src = self.memory.checked_range(self.offset, self.size())?;
+        let len = usize::try_from(self.len).map_err(|_| MemoryAccessError::Overflow)?;
+        let mut vec = Vec::with_capacity(len);
+        unsafe {
+            ptr::copy_nonoverlapping(src, vec.as_mut_ptr() as *mut u8, self.size() as usize);
+            // `ValueType` guarantees that any bit pattern is a valid `T`.
+            vec.set_len(len);
+        }
+        Ok(vec)
+    }
+
+    /// Iterates over references to the values of the slice.
+    pub fn iter(self) -> impl Iterator<Item = WasmRef<'a, T>> + 'a
+    where
+        T: 'a,
+    {
+        (0..self.len).map(move |index| self.index(index))
+    }
+}
+
+impl<'a> WasmSlice<'a, u8> {
+    /// Reads the bytes of the slice as a UTF-8 string.
+    pub fn read_to_string(self) -> Result<String, MemoryAccessError> {
+        Ok(String::from_utf8(self.read_to_vec()?)?)
+    }
+}
+
+impl<'a, T: ValueType> fmt::Debug for WasmSlice<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "WasmSlice(offset: {:#x}, len: {}, type: {})",
+            self.offset,
+            self.len,
+            std::any::type_name::<T>()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MemoryType, Pages, Store};
+
+    /// Ensure that accesses on the edges of the memory work, and that out
+    /// of bounds and overflowing accesses are reported.
+    #[test]
+    fn memory_access_bounds_checks_hold() {
+        let store = Store::default();
+        let memory = Memory::new(&store, MemoryType::new(1, Some(2), false)).unwrap();
+        let size = memory.data_size();
+
+        assert_eq!(memory.write(size - 4, &[1, 2, 3, 4]), Ok(()));
+        let mut buf = [0; 4];
+        assert_eq!(memory.read(size - 4, &mut buf), Ok(()));
+        assert_eq!(buf, [1, 2, 3, 4]);
+        assert_eq!(memory.read(size, &mut []), Ok(()));
+        assert_eq!(
+            memory.read(size - 3, &mut buf),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+        assert_eq!(
+            memory.write(u64::MAX, &[0]),
+            Err(MemoryAccessError::Overflow)
+        );
+
+        // Unaligned accesses are fine.
+        assert_eq!(memory.read_value::<u32>(size - 4), Ok(0x0403_0201));
+        assert_eq!(
+            memory.write_value(size - 16, 0x0102_0304_0506_0708u64),
+            Ok(())
+        );
+        assert_eq!(
+            memory.read_value::<u64>(size - 16),
+            Ok(0x0102_0304_0506_0708)
+        );
+        assert_eq!(
+            memory.read_value::<u64>(size - 7),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+
+        // Accesses are checked against the current size of the memory.
+        let slice = WasmSlice::<u32>::new(&memory, size - 4, 2).unwrap();
+        assert_eq!(slice.read(0), Ok(0x0403_0201));
+        assert_eq!(slice.read(1), Err(MemoryAccessError::HeapOutOfBounds));
+        assert_eq!(slice.read_to_vec(), Err(MemoryAccessError::HeapOutOfBounds));
+        memory.grow(Pages(1)).unwrap();
+        assert_eq!(slice.read_to_vec(), Ok(vec![0x0403_0201, 0]));
+
+        assert_eq!(
+            WasmSlice::<u32>::new(&memory, 8, u64::MAX / 2).unwrap_err(),
+            MemoryAccessError::Overflow
+        );
+    }
+
+    #[test]
+    fn wasm_slice_reads_and_writes() {
+        let store = Store::default();
+        let memory = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+
+        let slice = WasmSlice::<u16>::new(&memory, 3, 4).unwrap();
+        slice.write_slice(&[1, 2, 3, 4]).unwrap();
+        assert_eq!(slice.subslice(1..3).read_to_vec(), Ok(vec![2, 3]));
+        slice.write(3, 40).unwrap();
+        let mut buf = [0; 4];
+        slice.read_slice(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 40]);
+        assert_eq!(
+            slice.iter().map(|r| r.offset()).collect::<Vec<_>>(),
+            vec![3, 5, 7, 9]
+        );
+
+        let bytes = WasmSlice::<u8>::new(&memory, 0x100, 5).unwrap();
+        bytes.write_slice(b"hello").unwrap();
+        assert_eq!(bytes.read_to_string(), Ok("hello".to_string()));
+        bytes.write(0, 0xff).unwrap();
+        assert_eq!(
+            bytes.read_to_string(),
+            Err(MemoryAccessError::NonUtf8String)
+        );
+    }
+}
@@ -9,9 +9,11 @@
 //! ```
 use std::marker::PhantomData;
 
-use crate::externals::function::{DynamicFunction, VMDynamicFunction};
+use crate::externals::function::{hold_instance, DynamicFunction, VMDynamicFunction};
+use crate::trace::TracedCall;
 use crate::{FromToNativeWasmType, Function, RuntimeError, Store, WasmTypeList};
 use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
 use wasmer_engine::ExportFunction;
 use wasmer_types::NativeWasmType;
 use wasmer_vm::{VMDynamicFunctionContext, VMFunctionBody, VMFunctionEnvironment, VMFunctionKind};
@@ -22,6 +24,7 @@ use wasmer_vm::{VMDynamicFunctionContext, VMFunctionBody, VMFunctionEnvironment,
 pub struct NativeFunc<Args = (), Rets = ()> {
     store: Store,
     exported: ExportFunction,
+    traced_call: Option<Arc<TracedCall>>,
     _phantom: PhantomData<(Args, Rets)>,
 }
 
@@ -32,10 +35,15 @@ where
     Args: WasmTypeList,
     Rets: WasmTypeList,
 {
-    pub(crate) fn new(store: Store, exported: ExportFunction) -> Self {
+    pub(crate) fn new(
+        store: Store,
+        exported: ExportFunction,
+        traced_call: Option<Arc<TracedCall>>,
+    ) -> Self {
         Self {
             store,
             exported,
+            traced_call,
             _phantom: PhantomData,
         }
     }
@@ -55,6 +63,12 @@ where
     pub(crate) fn arg_kind(&self) -> VMFunctionKind {
         self.exported.vm_function.kind
     }
+
+    /// Whether the calls to this function are reported to the call tracer
+    /// of the store.
+    pub(crate) fn is_traced(&self) -> bool {
+        self.traced_call.is_some() && self.store.call_tracer().is_some()
+    }
 }
 
 /*
@@ -95,6 +109,7 @@ where
         Self {
             store: other.store,
             exported: other.exported,
+            traced_call: other.traced_call,
         }
     }
 }
@@ -109,7 +124,27 @@ macro_rules! impl_native_traits {
         {
             /// Call the typed func and return results.
             pub fn call(&self, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
-                if !self.is_host() {
+                let _instance = hold_instance(&self.exported)?;
+                if self.is_traced() {
+                    // Traced calls go through `Function::call`, which
+                    // reports the values to the tracer.
+                    let params_list = [ $( $x.to_native().to_value() ),* ];
+                    let function = Function {
+                        store: self.store.clone(),
+                        exported: self.exported.clone(),
+                        traced_call: self.traced_call.clone(),
+                    };
+                    let results = function.call(&params_list)?;
+                    let mut rets_list_array = Rets::empty_array();
+                    let mut_rets = rets_list_array.as_mut() as *mut [i128] as *mut i128;
+                    for (i, ret) in results.iter().enumerate() {
+                        unsafe {
+                            ret.write_value_to(mut_rets.add(i));
+                        }
+                    }
+                    Ok(Rets::from_array(rets_list_array))
+                }
+                else if !self.is_host() {
                     // We assume the trampoline is always going to be present for
                     // Wasm functions
                     let trampoline = self.exported.vm_function.call_trampoline.expect("Call trampoline not found in wasm function");
@@ -6,7 +6,7 @@
 //! Therefore, you should use this abstraction whenever possible to avoid memory
 //! related bugs when implementing an ABI.
 
-use crate::{externals::Memory, FromToNativeWasmType};
+use crate::{externals::Memory, FromToNativeWasmType, MemoryAccessError, WasmRef, WasmSlice};
 use std::{cell::Cell, fmt, marker::PhantomData, mem};
 use wasmer_types::ValueType;
 
@@ -96,9 +96,33 @@ fn align_pointer(ptr: usize, align: usize) -> usize {
 /// that implement [`ValueType`], meaning that they're valid for all possible
 /// bit patterns.
 impl<T: Copy + ValueType> WasmPtr<T, Item> {
+    /// A [`WasmRef`] to the value the `WasmPtr` points to, which can be
+    /// read and written with explicit bounds-checking errors.
+    ///
+    /// This should be preferred over [`WasmPtr::deref`].
+    #[inline]
+    pub fn access(self, memory: &Memory) -> WasmRef<'_, T> {
+        WasmRef::new(memory, self.offset.into())
+    }
+
+    /// Reads the value the `WasmPtr` points to.
+    #[inline]
+    pub fn read(self, memory: &Memory) -> Result<T, MemoryAccessError> {
+        self.access(memory).read()
+    }
+
+    /// Writes the value the `WasmPtr` points to.
+    #[inline]
+    pub fn write(self, memory: &Memory, value: T) -> Result<(), MemoryAccessError> {
+        self.access(memory).write(value)
+    }
+
     /// Dereference the `WasmPtr` getting access to a `&Cell<T>` allowing for
     /// reading and mutating of the inner value.
     ///
+    /// The reference is aligned down to the alignment of `T`, and is
+    /// dangling if the memory is grown: prefer [`WasmPtr::access`].
+    ///
     /// This method is unsound if used with unsynchronized shared memory.
     /// If you're unsure what that means, it likely does not apply to you.
     /// This invariant will be enforced in the future.
@@ -144,9 +168,21 @@ impl<T: Copy + ValueType> WasmPtr<T, Item> {
 /// types that implement [`ValueType`], meaning that they're valid for all
 /// possible bit patterns.
 impl<T: Copy + ValueType> WasmPtr<T, Array> {
+    /// A [`WasmSlice`] of the `len` values the `WasmPtr` points to, which
+    /// can be read and written with explicit bounds-checking errors.
+    ///
+    /// This should be preferred over [`WasmPtr::deref`].
+    #[inline]
+    pub fn slice(self, memory: &Memory, len: u32) -> Result<WasmSlice<'_, T>, MemoryAccessError> {
+        WasmSlice::new(memory, self.offset.into(), len.into())
+    }
+
     /// Dereference the `WasmPtr` getting access to a `&[Cell<T>]` allowing for
     /// reading and mutating of the inner values.
     ///
+    /// The slice is dangling if the memory is grown: prefer
+    /// [`WasmPtr::slice`].
+    ///
     /// This method is unsound if used with unsynchronized shared memory.
     /// If you're unsure what that means, it likely does not apply to you.
     /// This invariant will be enforced in the future.
@@ -212,6 +248,18 @@ impl<T: Copy + ValueType> WasmPtr<T, Array> {
         Some(cell_ptrs)
     }
 
+    /// Reads a UTF-8 `String` of `str_len` bytes from the `WasmPtr`.
+    ///
+    /// Unlike [`WasmPtr::get_utf8_string`], it tells why the string
+    /// couldn't be read.
+    pub fn read_utf8_string(
+        self,
+        memory: &Memory,
+        str_len: u32,
+    ) -> Result<String, MemoryAccessError> {
+        WasmSlice::<u8>::new(memory, self.offset.into(), str_len.into())?.read_to_string()
+    }
+
     /// Get a UTF-8 string from the `WasmPtr` with the given length.
     ///
     /// Note that . The
@@ -333,6 +381,124 @@ impl<T: Copy, Ty> fmt::Debug for WasmPtr<T, Ty> {
     }
 }
 
+/// A pointer to something in a 64-bit Wasm linear memory.
+///
+/// Unlike [`WasmPtr`], it only gives bounds-checked access to the memory,
+/// through [`WasmRef`] and [`WasmSlice`].
+///
+/// ```
+/// # use wasmer::{Memory, MemoryAccessError, WasmPtr64};
+/// pub fn host_import(memory: Memory, ptr: WasmPtr64<u32>) -> Result<(), MemoryAccessError> {
+///     let inner_val = ptr.read(&memory)?;
+///     println!("Got {} from Wasm memory address 0x{:X}", inner_val, ptr.offset());
+///     // update the value being pointed to
+///     ptr.write(&memory, inner_val + 1)
+/// }
+/// ```
+#[repr(transparent)]
+pub struct WasmPtr64<T: Copy, Ty = Item> {
+    offset: u64,
+    _phantom: PhantomData<(T, Ty)>,
+}
+
+/// Methods relevant to all types of `WasmPtr64`.
+impl<T: Copy, Ty> WasmPtr64<T, Ty> {
+    /// Create a new `WasmPtr64` at the given offset.
+    #[inline]
+    pub fn new(offset: u64) -> Self {
+        Self {
+            offset,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get the offset into Wasm linear memory for this `WasmPtr64`.
+    #[inline]
+    pub fn offset(self) -> u64 {
+        self.offset
+    }
+}
+
+/// Methods for `WasmPtr64`s to data that implements [`ValueType`].
+impl<T: Copy + ValueType> WasmPtr64<T, Item> {
+    /// A [`WasmRef`] to the value the `WasmPtr64` points to.
+    #[inline]
+    pub fn access(self, memory: &Memory) -> WasmRef<'_, T> {
+        WasmRef::new(memory, self.offset)
+    }
+
+    /// Reads the value the `WasmPtr64` points to.
+    #[inline]
+    pub fn read(self, memory: &Memory) -> Result<T, MemoryAccessError> {
+        self.access(memory).read()
+    }
+
+    /// Writes the value the `WasmPtr64` points to.
+    #[inline]
+    pub fn write(self, memory: &Memory, value: T) -> Result<(), MemoryAccessError> {
+        self.access(memory).write(value)
+    }
+}
+
+/// Methods for `WasmPtr64`s to arrays of data that implements [`ValueType`].
+impl<T: Copy + ValueType> WasmPtr64<T, Array> {
+    /// A [`WasmSlice`] of the `len` values the `WasmPtr64` points to.
+    #[inline]
+    pub fn slice(self, memory: &Memory, len: u64) -> Result<WasmSlice<'_, T>, MemoryAccessError> {
+        WasmSlice::new(memory, self.offset, len)
+    }
+
+    /// Reads a UTF-8 `String` of `str_len` bytes from the `WasmPtr64`.
+    pub fn read_utf8_string(
+        self,
+        memory: &Memory,
+        str_len: u64,
+    ) -> Result<String, MemoryAccessError> {
+        WasmSlice::<u8>::new(memory, self.offset, str_len)?.read_to_string()
+    }
+}
+
+unsafe impl<T: Copy, Ty> FromToNativeWasmType for WasmPtr64<T, Ty> {
+    type Native = i64;
+
+    fn to_native(self) -> Self::Native {
+        self.offset as i64
+    }
+    fn from_native(n: Self::Native) -> Self {
+        Self {
+            offset: n as u64,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+unsafe impl<T: Copy, Ty> ValueType for WasmPtr64<T, Ty> {}
+
+impl<T: Copy, Ty> Clone for WasmPtr64<T, Ty> {
+    fn clone(&self) -> Self {
+        Self {
+            offset: self.offset,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Copy, Ty> Copy for WasmPtr64<T, Ty> {}
+
+impl<T: Copy, Ty> PartialEq for WasmPtr64<T, Ty> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+    }
+}
+
+impl<T: Copy, Ty> Eq for WasmPtr64<T, Ty> {}
+
+impl<T: Copy, Ty> fmt::Debug for WasmPtr64<T, Ty> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WasmPtr64({:#x})", self.offset)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -423,4 +589,52 @@ mod test {
             assert!(unsafe { oob_end_array_ptr.deref_mut(&memory, 1, 0).is_none() });
         }
     }
+
+    /// Ensure that the checked accessors report out of bounds accesses
+    /// instead of aligning the pointer.
+    #[test]
+    fn wasm_ptr_checked_accessors() {
+        let store = Store::default();
+        let memory = Memory::new(&store, MemoryType::new(1, Some(1), false)).unwrap();
+        let memory_size = memory.size().bytes().0 as u32;
+
+        let unaligned: WasmPtr<u32> = WasmPtr::new(1);
+        unaligned.write(&memory, 0xdead_beef).unwrap();
+        assert_eq!(unaligned.read(&memory), Ok(0xdead_beef));
+        assert_eq!(memory.read_value::<u8>(1), Ok(0xef));
+
+        let end: WasmPtr<u32> = WasmPtr::new(memory_size - 3);
+        assert_eq!(end.read(&memory), Err(MemoryAccessError::HeapOutOfBounds));
+
+        let array: WasmPtr<u8, Array> = WasmPtr::new(memory_size - 2);
+        array.slice(&memory, 2).unwrap().write_slice(b"ok").unwrap();
+        assert_eq!(array.read_utf8_string(&memory, 2), Ok("ok".to_string()));
+        assert_eq!(
+            array.read_utf8_string(&memory, 3),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn wasm_ptr64_accessors() {
+        let store = Store::default();
+        let memory = Memory::new64(&store, MemoryType::new(1, Some(1), false)).unwrap();
+        let memory_size = memory.data_size();
+
+        let ptr: WasmPtr64<u64> = WasmPtr64::new(memory_size - 8);
+        ptr.write(&memory, u64::MAX).unwrap();
+        assert_eq!(ptr.read(&memory), Ok(u64::MAX));
+        assert_eq!(
+            WasmPtr64::<u8>::new(1 << 32).read(&memory),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+
+        let array: WasmPtr64<u8, Array> = WasmPtr64::new(0);
+        array.slice(&memory, 2).unwrap().write_slice(b"ok").unwrap();
+        assert_eq!(array.read_utf8_string(&memory, 2), Ok("ok".to_string()));
+        assert_eq!(
+            array.slice(&memory, u64::MAX).unwrap().read_to_vec(),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        );
+    }
 }
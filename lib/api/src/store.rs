@@ -1,12 +1,18 @@
+use crate::trace::CallTracer;
 use crate::tunables::BaseTunables;
 use loupe::MemoryUsage;
 use std::any::Any;
+use std::collections::BTreeSet;
 use std::fmt;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 #[cfg(all(feature = "compiler", feature = "engine"))]
 use wasmer_compiler::CompilerConfig;
-use wasmer_engine::{is_wasm_pc, Engine, Tunables};
-use wasmer_vm::{init_traps, TrapHandler, TrapHandlerFn};
+use wasmer_engine::{is_wasm_pc, Engine, ExportFunction, ExportFunctionMetadata, Tunables};
+use wasmer_types::ExternRefHeap;
+use wasmer_vm::{
+    collect_extern_refs, init_traps, InstanceHandle, Memory, StackConfig, Table, TrapHandler,
+    TrapHandlerFn,
+};
 
 /// The store represents all global state that can be manipulated by
 /// WebAssembly programs. It consists of the runtime representation
@@ -17,6 +23,10 @@ use wasmer_vm::{init_traps, TrapHandler, TrapHandlerFn};
 /// the Wasm bytes into a valid module artifact), in addition to the
 /// [`Tunables`] (that are used to create the memories, tables and globals).
 ///
+/// The `Store` also tracks the instances, memories, tables and host
+/// functions created in it, without keeping them alive: see
+/// [`Store::usage`].
+///
 /// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#store>
 #[derive(Clone, MemoryUsage)]
 pub struct Store {
@@ -24,6 +34,12 @@ pub struct Store {
     tunables: Arc<dyn Tunables + Send + Sync>,
     #[loupe(skip)]
     trap_handler: Arc<RwLock<Option<Box<TrapHandlerFn>>>>,
+    #[loupe(skip)]
+    extern_ref_heap: ExternRefHeap,
+    #[loupe(skip)]
+    objects: Arc<Mutex<StoreObjects>>,
+    #[loupe(skip)]
+    call_tracer: Arc<RwLock<Option<Arc<dyn CallTracer>>>>,
 }
 
 impl Store {
@@ -41,6 +57,22 @@ impl Store {
         *m = handler;
     }
 
+    /// Set the call tracer in this store.
+    ///
+    /// The tracer is told about the calls of the instances of this store to
+    /// their imported functions, and about the calls of the host to their
+    /// exported functions. Only the instances created while a tracer is set
+    /// are traced, and they report their calls to the tracer set at the time
+    /// of the call, if any.
+    pub fn set_call_tracer(&self, tracer: Option<Arc<dyn CallTracer>>) {
+        let mut m = self.call_tracer.write().unwrap();
+        *m = tracer;
+    }
+
+    pub(crate) fn call_tracer(&self) -> Option<Arc<dyn CallTracer>> {
+        self.call_tracer.read().unwrap().clone()
+    }
+
     /// Creates a new `Store` with a specific [`Engine`] and [`Tunables`].
     pub fn new_with_tunables<E>(engine: &E, tunables: impl Tunables + Send + Sync + 'static) -> Self
     where
@@ -54,6 +86,9 @@ impl Store {
             engine: engine.cloned(),
             tunables: Arc::new(tunables),
             trap_handler: Arc::new(RwLock::new(None)),
+            extern_ref_heap: ExternRefHeap::new(),
+            objects: Arc::new(Mutex::new(StoreObjects::default())),
+            call_tracer: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -67,6 +102,137 @@ impl Store {
         &self.engine
     }
 
+    /// Returns the heap of the `ExternRef`s of this store.
+    ///
+    /// `ExternRef`s allocated in it with `ExternRef::new_in` or
+    /// `ExternRef::new_traced_in` are counted by [`Store::live_extern_refs`],
+    /// and the traced ones can be collected by [`Store::gc`].
+    pub fn extern_ref_heap(&self) -> &ExternRefHeap {
+        &self.extern_ref_heap
+    }
+
+    /// Returns the number of live `ExternRef`s allocated in this store.
+    pub fn live_extern_refs(&self) -> usize {
+        self.extern_ref_heap.live_count()
+    }
+
+    /// Collects the `ExternRef`s of this store which are only kept alive by
+    /// cycles of references, such as an `ExternRef` stored in a table that
+    /// its data holds, and returns how many were collected.
+    ///
+    /// Only the references reported by the [`Trace`] implementations of
+    /// the data are followed. The data of the collected `ExternRef`s is
+    /// dropped, which frees them along with the tables and instances
+    /// of their cycles.
+    ///
+    /// # Safety
+    ///
+    /// This must not be called while another thread uses the objects of
+    /// this store, nor while WebAssembly code compiled by LLVM or
+    /// Singlepass is running, since it doesn't count the `ExternRef`s it
+    /// holds, which could then be collected while in use.
+    ///
+    /// [`Trace`]: crate::Trace
+    pub unsafe fn gc(&self) -> usize {
+        collect_extern_refs(&self.extern_ref_heap)
+    }
+
+    /// Returns the number of live instances, memories, tables and host
+    /// functions created in this store, and the number of bytes they hold.
+    ///
+    /// The memories and tables defined by instances are counted in the
+    /// bytes of their instances. Objects shared by several others are only
+    /// counted once.
+    pub fn usage(&self) -> StoreUsage {
+        let mut objects = self.objects.lock().unwrap();
+        objects.forget_freed();
+        let mut tracker: BTreeSet<*const ()> = BTreeSet::new();
+        let mut bytes = 0;
+        for instance in &objects.instances {
+            bytes += instance.size_of_val(&mut tracker);
+        }
+        for memory in &objects.memories {
+            bytes += memory.size_of_val(&mut tracker);
+        }
+        for table in &objects.tables {
+            bytes += table.size_of_val(&mut tracker);
+        }
+        for function in &objects.functions {
+            bytes += function.size_of_val(&mut tracker);
+        }
+        StoreUsage {
+            instances: objects.instances.len(),
+            memories: objects.memories.len(),
+            tables: objects.tables.len(),
+            functions: objects.functions.len(),
+            bytes,
+        }
+    }
+
+    /// Drops the host environments of the instances of this store, along
+    /// with the cycles of `ExternRef`s collected like [`Store::gc`] does,
+    /// and returns the usage of what is left.
+    ///
+    /// The objects of a store are freed as soon as the last handle to them
+    /// is dropped, which [`Instance::release`] checks for an instance. The
+    /// host environments of the instances can hold handles to them, which
+    /// keeps them alive until they are dropped here. Nothing else is freed:
+    /// whatever is left is kept alive by handles held outside of the store,
+    /// and once they are dropped, `drop_host_envs` returns an empty usage.
+    ///
+    /// To bound what the objects of a store hold while they are alive, use
+    /// [`Tunables`] limiting the memories and tables they create.
+    ///
+    /// # Safety
+    ///
+    /// The same restrictions as for [`Store::gc`] apply. Moreover, the
+    /// imported functions of the instances of this store must not be
+    /// called anymore, since their host environments are dropped.
+    ///
+    /// [`Instance::release`]: crate::Instance::release
+    pub unsafe fn drop_host_envs(&self) -> StoreUsage {
+        self.gc();
+        let instances = {
+            let mut objects = self.objects.lock().unwrap();
+            objects.forget_freed();
+            objects
+                .instances
+                .iter()
+                .map(InstanceHandle::downgrade)
+                .collect::<Vec<_>>()
+        };
+        // Dropping the host environments may free other objects, which
+        // the store must not be locked for.
+        for instance in &instances {
+            instance.drop_host_envs();
+        }
+        self.usage()
+    }
+
+    pub(crate) fn track_instance(&self, handle: &InstanceHandle) {
+        let mut objects = self.objects.lock().unwrap();
+        track(&mut objects.instances, handle.downgrade(), |i| i.is_alive());
+    }
+
+    pub(crate) fn track_memory(&self, memory: &Arc<dyn Memory>) {
+        let mut objects = self.objects.lock().unwrap();
+        track(&mut objects.memories, Arc::downgrade(memory), is_alive);
+    }
+
+    pub(crate) fn track_table(&self, table: &Arc<dyn Table>) {
+        let mut objects = self.objects.lock().unwrap();
+        track(&mut objects.tables, Arc::downgrade(table), is_alive);
+    }
+
+    /// Tracks a host function, if it has an environment: the others don't
+    /// hold anything.
+    pub(crate) fn track_function(&self, function: &ExportFunction) {
+        if let Some(metadata) = &function.metadata {
+            let mut objects = self.objects.lock().unwrap();
+            track(&mut objects.functions, Arc::downgrade(metadata), is_alive);
+        }
+    }
+
     /// Checks whether two stores are identical. A store is considered
     /// equal to another store if both have the same engine. The
     /// tunables are excluded from the logic.
@@ -94,6 +260,10 @@ unsafe impl TrapHandler for Store {
             false
         }
     }
+
+    fn stack_config(&self) -> StackConfig {
+        self.tunables.stack_config()
+    }
 }
 
 // This is required to be able to set the trap_handler in the
@@ -151,6 +321,67 @@ impl fmt::Debug for Store {
     }
 }
 
+/// The usage of the objects created in a [`Store`], as returned by
+/// [`Store::usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreUsage {
+    /// The number of live instances.
+    pub instances: usize,
+    /// The number of live memories created by [`Memory::new`].
+    ///
+    /// [`Memory::new`]: crate::Memory::new
+    pub memories: usize,
+    /// The number of live tables created by [`Table::new`].
+    ///
+    /// [`Table::new`]: crate::Table::new
+    pub tables: usize,
+    /// The number of live host functions with an environment.
+    pub functions: usize,
+    /// The number of bytes held by these objects, as measured by their
+    /// [`MemoryUsage`] implementations.
+    pub bytes: usize,
+}
+
+impl StoreUsage {
+    /// Whether no object of the store is alive.
+    pub fn is_empty(&self) -> bool {
+        self.instances == 0 && self.memories == 0 && self.tables == 0 && self.functions == 0
+    }
+}
+
+/// The objects created in a `Store`, which it tracks without keeping them
+/// alive.
+#[derive(Default)]
+struct StoreObjects {
+    instances: Vec<InstanceHandle>,
+    memories: Vec<Weak<dyn Memory>>,
+    tables: Vec<Weak<dyn Table>>,
+    functions: Vec<Weak<ExportFunctionMetadata>>,
+}
+
+impl StoreObjects {
+    fn forget_freed(&mut self) {
+        self.instances.retain(|i| i.is_alive());
+        self.memories.retain(is_alive);
+        self.tables.retain(is_alive);
+        self.functions.retain(is_alive);
+    }
+}
+
+fn is_alive<T: ?Sized>(object: &Weak<T>) -> bool {
+    object.strong_count() > 0
+}
+
+/// Adds `object` to `objects`, forgetting the freed ones first when it is
+/// full, which keeps the cost of forgetting them proportional to the
+/// number of objects added.
+fn track<T>(objects: &mut Vec<T>, object: T, is_alive: impl Fn(&T) -> bool) {
+    if objects.len() == objects.capacity() {
+        objects.retain(is_alive);
+    }
+    objects.push(object);
+}
+
 /// A trait represinting any object that lives in the `Store`.
 pub trait StoreObject {
     /// Return true if the object `Store` is the same as the provided `Store`.
@@ -0,0 +1,239 @@
+//! Tracing of the calls crossing the boundary between the host and
+//! WebAssembly.
+//!
+//! When a [`CallTracer`] is set on a [`Store`], the imported functions of
+//! the instances created in it are replaced by host functions which report
+//! the calls to the tracer around calling the imported functions, and the
+//! exported functions of the instances report the calls made by the host.
+
+use crate::externals::function::DynamicFunction;
+use crate::{Function, HostEnvInitError, Instance, Module, RuntimeError, Store, Val, WasmerEnv};
+use std::ffi::c_void;
+use std::mem;
+use std::sync::Arc;
+use wasmer_engine::{Export, ExportFunction, ExportFunctionMetadata, Resolver};
+use wasmer_vm::{
+    ImportInitializerFuncPtr, VMDynamicFunctionContext, VMFunction, VMFunctionEnvironment,
+    VMFunctionKind, VMTrampoline,
+};
+
+/// The direction of a [`TracedCall`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallKind {
+    /// A call of WebAssembly to an imported function.
+    Import,
+    /// A call of the host to a function exported by an instance.
+    Export,
+}
+
+/// A call reported to a [`CallTracer`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TracedCall {
+    /// Whether the called function is imported or exported.
+    pub kind: CallKind,
+    /// The module name of the import, or the name of the module of the
+    /// exporting instance, which is empty if it has none.
+    pub module: String,
+    /// The field name of the import, or the name of the export.
+    pub field: String,
+}
+
+impl TracedCall {
+    pub(crate) fn export(module: &Module, name: &str) -> Self {
+        Self {
+            kind: CallKind::Export,
+            module: module.name().unwrap_or_default().to_string(),
+            field: name.to_string(),
+        }
+    }
+}
+
+/// An observer of the calls crossing the boundary between the host and
+/// WebAssembly, set with [`Store::set_call_tracer`].
+///
+/// It is called on the thread making the call, and may be called again
+/// before the call returns: by an imported function calling an export,
+/// for example.
+pub trait CallTracer: Send + Sync {
+    /// Called before `call` is made with `params`.
+    fn enter(&self, _call: &TracedCall, _params: &[Val]) {}
+
+    /// Called after `call`, made with `params`, returned `results` or
+    /// failed with an error, such as a trap.
+    fn exit(&self, _call: &TracedCall, _params: &[Val], _results: Result<&[Val], &RuntimeError>) {}
+}
+
+/// Resolves the imports of a module with another resolver, replacing the
+/// functions by traced ones.
+pub(crate) struct TracingResolver<'a> {
+    module: &'a Module,
+    resolver: &'a dyn Resolver,
+}
+
+impl<'a> TracingResolver<'a> {
+    pub(crate) fn new(module: &'a Module, resolver: &'a dyn Resolver) -> Self {
+        Self { module, resolver }
+    }
+}
+
+impl<'a> Resolver for TracingResolver<'a> {
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<Export> {
+        match self.resolver.resolve(index, module, field)? {
+            Export::Function(function) => {
+                let call = TracedCall {
+                    kind: CallKind::Import,
+                    module: module.to_string(),
+                    field: field.to_string(),
+                };
+                Some(TracedImport::wrap(self.module, call, function).into())
+            }
+            export => Some(export),
+        }
+    }
+}
+
+/// A host env of an imported function.
+struct HostEnv {
+    metadata: Arc<ExportFunctionMetadata>,
+    env: *mut c_void,
+}
+
+impl HostEnv {
+    /// Clones `env`, the host env of the function of `metadata`.
+    unsafe fn new(metadata: &Arc<ExportFunctionMetadata>, env: *mut c_void) -> Self {
+        Self {
+            metadata: metadata.clone(),
+            env: metadata.clone_host_env(env),
+        }
+    }
+}
+
+impl Clone for HostEnv {
+    fn clone(&self) -> Self {
+        unsafe { Self::new(&self.metadata, self.env) }
+    }
+}
+
+impl Drop for HostEnv {
+    fn drop(&mut self) {
+        unsafe { self.metadata.drop_host_env(self.env) }
+    }
+}
+
+// Host envs come from `WasmerEnv`s, which are `Send` and `Sync`.
+unsafe impl Send for HostEnv {}
+unsafe impl Sync for HostEnv {}
+
+/// The env of the host function replacing a traced import, which calls
+/// the imported function.
+#[derive(Clone)]
+struct TracedImport {
+    call: Arc<TracedCall>,
+    function: ExportFunction,
+    /// The copy of the host env of the imported function, if it has one,
+    /// which replaces it for the instance.
+    host_env: Option<HostEnv>,
+    /// The trampoline to call the imported function, unless it is
+    /// dynamic.
+    trampoline: Option<VMTrampoline>,
+    store: Store,
+}
+
+impl TracedImport {
+    /// Returns a host function making `call` to `function`, an import of
+    /// `module`.
+    fn wrap(module: &Module, call: TracedCall, function: ExportFunction) -> ExportFunction {
+        let store = module.store();
+        let vm_function = &function.vm_function;
+        let host_env = function
+            .metadata
+            .as_ref()
+            .map(|metadata| unsafe { HostEnv::new(metadata, vm_function.vmctx.host_env) });
+        let trampoline = match vm_function.kind {
+            VMFunctionKind::Dynamic => None,
+            VMFunctionKind::Static => Some(vm_function.call_trampoline.unwrap_or_else(|| {
+                // The type of an import is one of the signatures of its module.
+                let (index, _) = module
+                    .info()
+                    .signatures
+                    .iter()
+                    .find(|(_, signature)| **signature == vm_function.signature)
+                    .expect("the signature of an import is in its module");
+                module.artifact().finished_function_call_trampolines()[index]
+            })),
+        };
+        let ty = vm_function.signature.clone();
+        let traced = Self {
+            call: Arc::new(call),
+            function,
+            host_env,
+            trampoline,
+            store: store.clone(),
+        };
+        Function::new_with_env(store, ty, traced, Self::call).exported
+    }
+
+    fn call(&self, params: &[Val]) -> Result<Vec<Val>, RuntimeError> {
+        let tracer = self.store.call_tracer();
+        if let Some(tracer) = &tracer {
+            tracer.enter(&self.call, params);
+        }
+        let results = self.call_imported(params);
+        if let Some(tracer) = &tracer {
+            tracer.exit(&self.call, params, results.as_deref());
+        }
+        results
+    }
+
+    fn call_imported(&self, params: &[Val]) -> Result<Vec<Val>, RuntimeError> {
+        let vm_function = &self.function.vm_function;
+        let vmctx = match &self.host_env {
+            Some(host_env) => VMFunctionEnvironment {
+                host_env: host_env.env,
+            },
+            None => vm_function.vmctx,
+        };
+        match self.trampoline {
+            // Dynamic functions are only created by `Function::new_with_env`.
+            None => unsafe {
+                type VMContextWithEnv = VMDynamicFunctionContext<DynamicFunction<c_void>>;
+                let ctx = vmctx.host_env as *const VMContextWithEnv;
+                (*ctx).ctx.call(params)
+            },
+            Some(trampoline) => {
+                let function = Function::from_vm_export(
+                    &self.store,
+                    ExportFunction {
+                        metadata: None,
+                        vm_function: VMFunction {
+                            vmctx,
+                            call_trampoline: Some(trampoline),
+                            ..vm_function.clone()
+                        },
+                    },
+                );
+                Ok(function.call(params)?.into_vec())
+            }
+        }
+    }
+}
+
+impl WasmerEnv for TracedImport {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let host_env = match &self.host_env {
+            Some(host_env) => host_env,
+            None => return Ok(()),
+        };
+        if let Some(init) = host_env.metadata.import_init_function_ptr() {
+            // The host env is initialized like the instance would have
+            // initialized it, with the same error type.
+            let init = unsafe {
+                mem::transmute::<ImportInitializerFuncPtr, ImportInitializerFuncPtr<HostEnvInitError>>(
+                    init,
+                )
+            };
+            init(host_env.env, instance as *const Instance as *const c_void)?;
+        }
+        Ok(())
+    }
+}
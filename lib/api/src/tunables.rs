@@ -8,8 +8,8 @@ use wasmer_compiler::Target;
 use wasmer_engine::Tunables;
 use wasmer_vm::MemoryError;
 use wasmer_vm::{
-    LinearMemory, LinearTable, Memory, MemoryStyle, Table, TableStyle, VMMemoryDefinition,
-    VMTableDefinition,
+    LinearMemory, LinearTable, Memory, MemoryStyle, StackConfig, Table, TableStyle,
+    VMMemoryDefinition, VMTableDefinition,
 };
 
 /// Tunable parameters for WebAssembly compilation.
@@ -90,6 +90,14 @@ impl Tunables for BaseTunables {
         }
     }
 
+    /// Get a `MemoryStyle` for the provided `MemoryType` of a 64-bit memory,
+    /// which is always dynamic.
+    fn memory64_style(&self, _memory: &MemoryType) -> MemoryStyle {
+        MemoryStyle::Dynamic {
+            offset_guard_size: self.dynamic_memory_offset_guard_size,
+        }
+    }
+
     /// Get a [`TableStyle`] for the provided [`TableType`].
     fn table_style(&self, _table: &TableType) -> TableStyle {
         TableStyle::CallerChecksSignature
@@ -122,6 +130,33 @@ impl Tunables for BaseTunables {
         )?))
     }
 
+    /// Create a 64-bit memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
+    fn create_host_memory64(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(LinearMemory::new64(&ty, &style)?))
+    }
+
+    /// Create a 64-bit memory owned by the VM given a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid, owned `VMMemoryDefinition`,
+    ///   for example in `VMContext`.
+    unsafe fn create_vm_memory64(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(LinearMemory::from_definition64(
+            &ty,
+            &style,
+            vm_definition_location,
+        )?))
+    }
+
     /// Create a table owned by the host given a [`TableType`] and a [`TableStyle`].
     fn create_host_table(
         &self,
@@ -150,6 +185,132 @@ impl Tunables for BaseTunables {
     }
 }
 
+/// Tunables setting how much stack the guest can use, and which stack it
+/// runs on, and delegating everything else to other tunables.
+///
+/// # Usage
+///
+/// ```
+/// use wasmer::{BaseTunables, StackConfig, StackTunables, Target};
+///
+/// let tunables = StackTunables::new(
+///     BaseTunables::for_target(&Target::default()),
+///     StackConfig::with_max_size(512 * 1024),
+/// );
+/// ```
+#[derive(Clone, MemoryUsage)]
+pub struct StackTunables<T: Tunables> {
+    base: T,
+    stack: StackConfig,
+}
+
+impl<T: Tunables> StackTunables<T> {
+    /// Creates tunables delegating to `base`, but for the stack of the
+    /// guest, set by `stack`.
+    pub fn new(base: T, stack: StackConfig) -> Self {
+        Self { base, stack }
+    }
+}
+
+impl<T: Tunables> Tunables for StackTunables<T> {
+    /// Construct a `MemoryStyle` for the provided `MemoryType`
+    ///
+    /// Delegated to base.
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(memory)
+    }
+
+    /// Construct a `MemoryStyle` for the provided `MemoryType` of a 64-bit memory
+    ///
+    /// Delegated to base.
+    fn memory64_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory64_style(memory)
+    }
+
+    /// Construct a `TableStyle` for the provided `TableType`
+    ///
+    /// Delegated to base.
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    /// Get the [`StackConfig`] of the guest.
+    fn stack_config(&self) -> StackConfig {
+        self.stack
+    }
+
+    /// Create a memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// Delegated to base.
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.base.create_host_memory(ty, style)
+    }
+
+    /// Create a memory owned by the VM given a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// Delegated to base.
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.base
+            .create_vm_memory(ty, style, vm_definition_location)
+    }
+
+    /// Create a 64-bit memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// Delegated to base.
+    fn create_host_memory64(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.base.create_host_memory64(ty, style)
+    }
+
+    /// Create a 64-bit memory owned by the VM given a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// Delegated to base.
+    unsafe fn create_vm_memory64(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.base
+            .create_vm_memory64(ty, style, vm_definition_location)
+    }
+
+    /// Create a table owned by the host given a [`TableType`] and a [`TableStyle`].
+    ///
+    /// Delegated to base.
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    /// Create a table owned by the VM given a [`TableType`] and a [`TableStyle`].
+    ///
+    /// Delegated to base.
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,5 +352,13 @@ mod tests {
             }
             s => panic!("Unexpected memory style: {:?}", s),
         }
+
+        // 64-bit memories are dynamic, whatever their maximum
+        let requested = MemoryType::new(3, Some(16), true);
+        let style = tunables.memory64_style(&requested);
+        match style {
+            MemoryStyle::Dynamic { offset_guard_size } => assert_eq!(offset_guard_size, 256),
+            s => panic!("Unexpected memory style: {:?}", s),
+        }
     }
 }
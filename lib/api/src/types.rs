@@ -4,7 +4,7 @@ use crate::RuntimeError;
 use wasmer_types::Value;
 pub use wasmer_types::{
     ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, Mutability,
-    TableType, Type as ValType,
+    TableType, TagType, Type as ValType,
 };
 use wasmer_vm::VMFuncRef;
 
@@ -0,0 +1,166 @@
+#![cfg(all(feature = "cranelift", feature = "jit"))]
+
+use anyhow::Result;
+use wasmer::*;
+
+const EXCEPTIONS: &str = r#"
+(module
+  (import "env" "throw_native" (func $throw_native (param i32)))
+  (import "env" "throw_dynamic" (func $throw_dynamic (param i32)))
+  (event $e (param i32 f64))
+  (func (export "throw") (param i32)
+    (throw $e (local.get 0) (f64.const 1.5)))
+  (func (export "catch_native") (param i32) (result i32)
+    (try (result i32)
+      (do (call $throw_native (local.get 0)) (i32.const 0))
+      (catch_all (i32.const 1))))
+  (func (export "rethrow_dynamic") (param i32)
+    (try
+      (do (call $throw_dynamic (local.get 0)))
+      (catch_all (rethrow 0)))))
+"#;
+
+fn exceptions_store() -> Store {
+    let mut features = Features::default();
+    features.exceptions(true);
+    let engine = JIT::new(Cranelift::default()).features(features).engine();
+    Store::new(&engine)
+}
+
+#[derive(Clone, WasmerEnv)]
+struct TagEnv {
+    tag: ExceptionTag,
+}
+
+fn throw_if_not_zero(env: &TagEnv, value: i32) -> Result<(), Exception> {
+    if value == 0 {
+        return Ok(());
+    }
+    Err(Exception::new(&env.tag, &[Value::I32(value)]).unwrap())
+}
+
+fn instantiate(store: &Store, tag: &ExceptionTag) -> Result<Instance> {
+    let module = Module::new(store, EXCEPTIONS)?;
+    let env = TagEnv { tag: tag.clone() };
+    let throw_native = Function::new_native_with_env(store, env.clone(), throw_if_not_zero);
+    let throw_dynamic = Function::new_with_env(
+        store,
+        FunctionType::new(vec![Type::I32], vec![]),
+        env,
+        |env, args| Err(Exception::new(&env.tag, args)?.into()),
+    );
+    Ok(Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "throw_native" => throw_native,
+                "throw_dynamic" => throw_dynamic,
+            },
+        },
+    )?)
+}
+
+#[test]
+fn uncaught_exceptions_reach_the_host() -> Result<()> {
+    let store = exceptions_store();
+    let instance = instantiate(&store, &ExceptionTag::new(&[Type::I32])?)?;
+    let throw = instance.exports.get_native_function::<i32, ()>("throw")?;
+
+    let error = throw.call(42).unwrap_err();
+    let exception = Exception::from_error(error).unwrap();
+    assert_eq!(exception.tag().params(), &[Type::I32, Type::F64]);
+    assert_eq!(exception.payload(), vec![Value::I32(42), Value::F64(1.5)]);
+    Ok(())
+}
+
+#[test]
+fn host_functions_throw_exceptions() -> Result<()> {
+    let store = exceptions_store();
+    let tag = ExceptionTag::new(&[Type::I32])?;
+    let instance = instantiate(&store, &tag)?;
+
+    let catch_native = instance
+        .exports
+        .get_native_function::<i32, i32>("catch_native")?;
+    assert_eq!(catch_native.call(0)?, 0);
+    assert_eq!(catch_native.call(7)?, 1);
+
+    let rethrow_dynamic = instance
+        .exports
+        .get_native_function::<i32, ()>("rethrow_dynamic")?;
+    let exception = Exception::from_error(rethrow_dynamic.call(3).unwrap_err()).unwrap();
+    assert_eq!(exception.tag(), tag);
+    assert_eq!(exception.payload(), vec![Value::I32(3)]);
+    Ok(())
+}
+
+#[test]
+fn tags_are_imported_and_exported() -> Result<()> {
+    let store = exceptions_store();
+    let thrower = Module::new(
+        &store,
+        r#"
+(module
+  (event $e (export "e") (param i32))
+  (func (export "throw") (param i32)
+    (throw $e (local.get 0))))
+"#,
+    )?;
+    let thrower = Instance::new(&thrower, &imports! {})?;
+    let tag = thrower.exports.get_tag("e")?;
+    assert_eq!(tag.ty(), TagType::new(vec![Type::I32]));
+
+    let catcher = Module::new(
+        &store,
+        r#"
+(module
+  (import "thrower" "e" (event $e (param i32)))
+  (import "thrower" "throw" (func $throw (param i32)))
+  (import "host" "e" (event $host (param i32)))
+  (func (export "catch") (param i32) (result i32)
+    (try (result i32)
+      (do (call $throw (local.get 0)) (i32.const 0))
+      (catch $host (drop) (i32.const -1))
+      (catch $e)))
+  (func (export "throw_host") (param i32)
+    (throw $host (local.get 0))))
+"#,
+    )?;
+    let host_tag = ExceptionTag::new(&[Type::I32])?;
+    let catcher = Instance::new(
+        &catcher,
+        &imports! {
+            "thrower" => {
+                "e" => tag.clone(),
+                "throw" => thrower.exports.get_function("throw")?.clone(),
+            },
+            "host" => {
+                "e" => host_tag.clone(),
+            },
+        },
+    )?;
+
+    let catch = catcher.exports.get_native_function::<i32, i32>("catch")?;
+    assert_eq!(catch.call(9)?, 9);
+
+    let throw_host = catcher
+        .exports
+        .get_native_function::<i32, ()>("throw_host")?;
+    let exception = Exception::from_error(throw_host.call(5).unwrap_err()).unwrap();
+    assert_eq!(exception.tag(), host_tag);
+    assert_ne!(exception.tag(), *tag);
+    Ok(())
+}
+
+#[test]
+fn tags_take_only_numeric_params() {
+    assert!(ExceptionTag::new(&[Type::ExternRef]).is_err());
+    assert!(ExceptionTag::new(&[Type::FuncRef]).is_err());
+}
+
+#[test]
+fn exceptions_require_the_feature() -> Result<()> {
+    let store = Store::default();
+    assert!(Module::new(&store, EXCEPTIONS).is_err());
+    Ok(())
+}
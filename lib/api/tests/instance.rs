@@ -1,4 +1,6 @@
 use anyhow::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use wasmer::*;
 
 #[test]
@@ -37,3 +39,195 @@ fn exports_work_after_multiple_instances_have_been_freed() -> Result<()> {
 
     Ok(())
 }
+
+#[derive(WasmerEnv, Clone)]
+struct MemoryEnv {
+    #[wasmer(export)]
+    memory: LazyInit<Memory>,
+}
+
+fn memory_size(env: &MemoryEnv) -> u32 {
+    env.memory_ref().unwrap().size().0
+}
+
+fn instantiate_with_memory_env(store: &Store) -> Result<Instance> {
+    let module = Module::new(
+        store,
+        r#"
+    (module
+      (import "env" "memory_size" (func $memory_size (result i32)))
+      (memory (export "memory") 2)
+      (func (export "size") (result i32)
+        call $memory_size))
+"#,
+    )?;
+    let env = MemoryEnv {
+        memory: LazyInit::new(),
+    };
+    let import_object = imports! {
+        "env" => {
+            "memory_size" => Function::new_native_with_env(store, env, memory_size),
+        },
+    };
+    Ok(Instance::new(&module, &import_object)?)
+}
+
+#[test]
+fn instances_with_host_envs_are_released() -> Result<()> {
+    let store = Store::default();
+    let instance = instantiate_with_memory_env(&store)?;
+    let size = instance.exports.get_native_function::<(), u32>("size")?;
+    assert_eq!(size.call()?, 2);
+    drop(size);
+    assert_eq!(store.usage().instances, 1);
+
+    assert!(instance.release());
+    assert_eq!(store.usage().instances, 0);
+    Ok(())
+}
+
+#[test]
+fn instances_are_kept_alive_by_their_exports() -> Result<()> {
+    let store = Store::default();
+    let instance = instantiate_with_memory_env(&store)?;
+    let memory = instance.exports.get_memory("memory")?.clone();
+
+    assert!(!instance.release());
+    assert_eq!(memory.size(), Pages(2));
+    let usage = unsafe { store.drop_host_envs() };
+    assert_eq!(usage.instances, 1);
+    assert!(usage.bytes >= 2 * WASM_PAGE_SIZE);
+
+    drop(memory);
+    assert!(unsafe { store.drop_host_envs() }.is_empty());
+    Ok(())
+}
+
+/// Counts its drops, and holds the instance importing it.
+#[derive(Clone)]
+struct CyclicEnv {
+    instance: Arc<Mutex<Option<Instance>>>,
+    drops: Arc<AtomicUsize>,
+}
+
+impl Drop for CyclicEnv {
+    fn drop(&mut self) {
+        self.drops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl WasmerEnv for CyclicEnv {}
+
+#[test]
+fn drop_host_envs_frees_the_instances_they_keep_alive() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (import "env" "f" (func)))"#)?;
+    let instance = Arc::new(Mutex::new(None));
+    let drops = Arc::new(AtomicUsize::new(0));
+    let env = CyclicEnv {
+        instance: instance.clone(),
+        drops: drops.clone(),
+    };
+    let import_object = imports! {
+        "env" => {
+            "f" => Function::new_native_with_env(&store, env, |_: &CyclicEnv| {}),
+        },
+    };
+    *instance.lock().unwrap() = Some(Instance::new(&module, &import_object)?);
+    drop(import_object);
+    drop(instance);
+    let drops_before = drops.load(Ordering::SeqCst);
+    assert_eq!(store.usage().instances, 1);
+
+    let usage = unsafe { store.drop_host_envs() };
+    assert!(usage.is_empty());
+    assert_eq!(drops.load(Ordering::SeqCst), drops_before + 1);
+    Ok(())
+}
+
+/// Shares the exports it is initialized with.
+#[derive(Clone, Default)]
+struct SharedExportsEnv {
+    exports: Arc<Mutex<Option<(Memory, Function)>>>,
+}
+
+impl WasmerEnv for SharedExportsEnv {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let memory = instance.exports.get_memory("memory")?.clone();
+        let size = instance.exports.get_function("size")?.clone();
+        *self.exports.lock().unwrap() = Some((memory, size));
+        Ok(())
+    }
+}
+
+#[test]
+fn exports_of_host_envs_are_checked_once_their_instance_is_freed() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "env" "memory_size" (func $memory_size (result i32)))
+      (memory (export "memory") 2)
+      (func (export "size") (result i32)
+        call $memory_size))
+"#,
+    )?;
+    let env = SharedExportsEnv::default();
+    let exports = env.exports.clone();
+    let import_object = imports! {
+        "env" => {
+            "memory_size" => Function::new_native_with_env(&store, env, |_: &SharedExportsEnv| 2),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    drop(import_object);
+    assert!(instance.release());
+
+    let (memory, size) = exports.lock().unwrap().take().unwrap();
+    assert_eq!(memory.size(), Pages(2));
+    assert_eq!(memory.grow(1)?, Pages(2));
+    memory.view::<u8>()[3 * WASM_PAGE_SIZE - 1].set(42);
+    assert_eq!(memory.view::<u8>()[3 * WASM_PAGE_SIZE - 1].get(), 42);
+
+    let error = size.call(&[]).unwrap_err();
+    assert_eq!(
+        error.message(),
+        "the instance of this function has been freed"
+    );
+    assert!(size.native::<(), u32>()?.call().is_err());
+
+    let import_object = imports! {
+        "env" => {
+            "memory_size" => size,
+        },
+    };
+    assert!(matches!(
+        Instance::new(&module, &import_object),
+        Err(InstantiationError::Link(LinkError::Trap(_)))
+    ));
+    Ok(())
+}
+
+#[test]
+fn store_usage_counts_host_objects() -> Result<()> {
+    let store = Store::default();
+    assert_eq!(store.usage(), StoreUsage::default());
+
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let table = Table::new(
+        &store,
+        TableType::new(Type::FuncRef, 1, None),
+        Value::FuncRef(None),
+    )?;
+    let function = Function::new_native_with_env(&store, 7u32, |env: &u32| *env);
+    let usage = store.usage();
+    assert_eq!((usage.memories, usage.tables, usage.functions), (1, 1, 1));
+    assert!(usage.bytes >= WASM_PAGE_SIZE);
+
+    drop(memory);
+    drop(table);
+    drop(function);
+    assert!(store.usage().is_empty());
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use wasmer::*;
+
+const MATH_WAT: &str = r#"
+(module
+  (func (export "double") (param i32) (result i32)
+    local.get 0
+    i32.const 2
+    i32.mul))
+"#;
+
+const APP_WAT: &str = r#"
+(module
+  (import "math" "double" (func $double (param i32) (result i32)))
+  (func (export "quadruple") (param i32) (result i32)
+    local.get 0
+    call $double
+    call $double))
+"#;
+
+#[test]
+fn linker_resolves_registered_instances() -> Result<()> {
+    let store = Store::default();
+    let math = Module::new(&store, MATH_WAT)?;
+    let app = Module::new(&store, APP_WAT)?;
+
+    let mut linker = Linker::new(&store);
+    linker.module("math", &math)?;
+    let instance = linker.instantiate(&app)?;
+
+    let quadruple: NativeFunc<i32, i32> = instance.exports.get_native_function("quadruple")?;
+    assert_eq!(quadruple.call(5)?, 20);
+
+    Ok(())
+}
+
+#[test]
+fn linker_resolves_host_modules() -> Result<()> {
+    let store = Store::default();
+    let app = Module::new(&store, APP_WAT)?;
+
+    let mut linker = Linker::new(&store);
+    linker.define_import_object(&imports! {
+        "math" => {
+            "double" => Function::new_native(&store, |x: i32| x * 2),
+        },
+    })?;
+    let instance = linker.instantiate(&app)?;
+
+    let quadruple: NativeFunc<i32, i32> = instance.exports.get_native_function("quadruple")?;
+    assert_eq!(quadruple.call(1)?, 4);
+
+    Ok(())
+}
+
+#[test]
+fn linker_rejects_duplicates_unless_shadowing() -> Result<()> {
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    linker.define("env", "g", Global::new(&store, Value::I32(1)))?;
+
+    assert!(matches!(
+        linker.define("env", "g", Global::new(&store, Value::I32(2))),
+        Err(LinkerError::AlreadyDefined(..))
+    ));
+
+    linker.allow_shadowing(true);
+    linker.define("env", "g", Global::new(&store, Value::I32(3)))?;
+    match linker.get("env", "g") {
+        Some(Extern::Global(g)) => assert_eq!(g.get(), Value::I32(3)),
+        _ => panic!("`env`.`g` should be a global"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn linker_reports_unknown_and_incompatible_imports() -> Result<()> {
+    let store = Store::default();
+    let app = Module::new(&store, APP_WAT)?;
+
+    let mut linker = Linker::new(&store);
+    match linker.instantiate(&app) {
+        Err(LinkerError::Link(LinkError::Import(module, name, ImportError::UnknownImport(_)))) => {
+            assert_eq!((module.as_str(), name.as_str()), ("math", "double"));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    linker.define(
+        "math",
+        "double",
+        Function::new_native(&store, |x: i64| x * 2),
+    )?;
+    match linker.instantiate(&app) {
+        Err(LinkerError::Link(LinkError::Import(
+            module,
+            name,
+            ImportError::IncompatibleType(expected, found),
+        ))) => {
+            assert_eq!((module.as_str(), name.as_str()), ("math", "double"));
+            assert_eq!(
+                expected,
+                ExternType::Function(FunctionType::new(vec![Type::I32], vec![Type::I32]))
+            );
+            assert_eq!(
+                found,
+                ExternType::Function(FunctionType::new(vec![Type::I64], vec![Type::I64]))
+            );
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn linker_is_unchanged_when_a_module_graph_fails() -> Result<()> {
+    let store = Store::default();
+    let trapping = Module::new(
+        &store,
+        r#"
+(module
+  (import "math" "double" (func (param i32) (result i32)))
+  (func $start unreachable)
+  (start $start))
+"#,
+    )?;
+
+    let mut linker = Linker::new(&store);
+    match linker.instantiate_graph(vec![
+        ("math", Module::new(&store, MATH_WAT)?),
+        ("trapping", trapping),
+    ]) {
+        Err(LinkerError::Instantiation(InstantiationError::Start(_))) => {}
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    assert!(!linker.contains_module("math"));
+    assert!(!linker.contains_module("trapping"));
+
+    Ok(())
+}
+
+#[test]
+fn linker_instantiates_module_graphs_in_dependency_order() -> Result<()> {
+    let store = Store::default();
+    let main = Module::new(
+        &store,
+        r#"
+(module
+  (import "app" "quadruple" (func $quadruple (param i32) (result i32)))
+  (func (export "run") (result i32)
+    i32.const 10
+    call $quadruple))
+"#,
+    )?;
+
+    let mut linker = Linker::new(&store);
+    let instances = linker.instantiate_graph(vec![
+        ("main", main),
+        ("app", Module::new(&store, APP_WAT)?),
+        ("math", Module::new(&store, MATH_WAT)?),
+    ])?;
+
+    assert_eq!(
+        instances.keys().map(String::as_str).collect::<Vec<_>>(),
+        vec!["math", "app", "main"]
+    );
+    let run: NativeFunc<(), i32> = instances["main"].exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 40);
+    assert!(linker.contains_module("main"));
+
+    Ok(())
+}
+
+#[test]
+fn linker_detects_cycles_in_module_graphs() -> Result<()> {
+    let store = Store::default();
+    let a = Module::new(
+        &store,
+        r#"(module (import "b" "f" (func)) (func (export "f")))"#,
+    )?;
+    let b = Module::new(
+        &store,
+        r#"(module (import "a" "f" (func)) (func (export "f")))"#,
+    )?;
+
+    let mut linker = Linker::new(&store);
+    match linker.instantiate_graph(vec![("a", a), ("b", b)]) {
+        Err(LinkerError::Cycle(modules)) => assert_eq!(modules, vec!["a", "b"]),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    assert!(!linker.contains_module("a"));
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+#![cfg(all(any(feature = "cranelift", feature = "llvm"), feature = "jit"))]
+
+use anyhow::Result;
+use wasmer::*;
+
+const MEMORY64: &str = r#"
+(module
+  (memory (export "memory") i64 1)
+  (func (export "store") (param i64 i32)
+    local.get 0
+    local.get 1
+    i32.store offset=4)
+  (func (export "load") (param i64) (result i32)
+    local.get 0
+    i32.load offset=4)
+  (func (export "size") (result i64)
+    memory.size)
+  (func (export "grow") (param i64) (result i64)
+    local.get 0
+    memory.grow))
+"#;
+
+fn memory64_store<T: Into<Box<dyn CompilerConfig>>>(compiler: T) -> Store {
+    let mut features = Features::default();
+    features.memory64(true);
+    let engine = JIT::new(compiler).features(features).engine();
+    Store::new(&engine)
+}
+
+#[cfg(feature = "cranelift")]
+#[test]
+fn memory64_load_store_and_grow_cranelift() -> Result<()> {
+    load_store_and_grow(&memory64_store(Cranelift::default()))
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn memory64_load_store_and_grow_llvm() -> Result<()> {
+    load_store_and_grow(&memory64_store(LLVM::default()))
+}
+
+fn load_store_and_grow(store: &Store) -> Result<()> {
+    let module = Module::new(store, MEMORY64)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let memory = instance.exports.get_memory("memory")?;
+    assert!(memory.is_64());
+
+    let store_i32 = instance
+        .exports
+        .get_native_function::<(i64, i32), ()>("store")?;
+    let load_i32 = instance.exports.get_native_function::<i64, i32>("load")?;
+    let size = instance.exports.get_native_function::<(), i64>("size")?;
+    let grow = instance.exports.get_native_function::<i64, i64>("grow")?;
+
+    store_i32.call(8, 42)?;
+    assert_eq!(load_i32.call(8)?, 42);
+    assert_eq!(memory.read_value::<i32>(12), Ok(42));
+    assert_eq!(WasmPtr64::<i32>::new(12).read(memory), Ok(42));
+
+    assert_eq!(size.call()?, 1);
+    assert_eq!(grow.call(1)?, 1);
+    assert_eq!(size.call()?, 2);
+    assert_eq!(grow.call(1 << 40)?, -1);
+
+    // The last byte of the access is out of bounds.
+    let error = load_i32.call(2 * 0x1_0000 - 7).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    // Addresses aren't wrapped to 32 bits.
+    let error = load_i32.call(0x1_0000_0000).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    // Adding the offset overflows.
+    let error = load_i32.call(-2).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    Ok(())
+}
+
+#[test]
+fn memory64_requires_the_feature() -> Result<()> {
+    let store = Store::default();
+    assert!(Module::new(&store, MEMORY64).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "cranelift")]
+#[test]
+fn memory64_imports_check_the_index_type() -> Result<()> {
+    let store = memory64_store(Cranelift::default());
+    let module = Module::new(&store, r#"(module (import "env" "memory" (memory i64 1)))"#)?;
+
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let imports = imports! { "env" => { "memory" => memory } };
+    assert!(Instance::new(&module, &imports).is_err());
+
+    let memory = Memory::new64(&store, MemoryType::new(1, None, false))?;
+    let imports = imports! { "env" => { "memory" => memory } };
+    Instance::new(&module, &imports)?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+#![cfg(all(feature = "cranelift", feature = "jit"))]
+
+use anyhow::Result;
+use wasmer::*;
+
+const MULTI_MEMORY: &str = r#"
+(module
+  (import "env" "memory" (memory $imported 1))
+  (memory $first (export "first") 1)
+  (memory $second (export "second") 2)
+  (func (export "store") (param i32 i32)
+    (i32.store (memory $second) (local.get 0) (local.get 1)))
+  (func (export "copy") (param i32 i32 i32)
+    (memory.copy $first $second (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "load_imported") (param i32) (result i32)
+    (i32.load (memory $imported) (local.get 0)))
+  (func (export "grow_second") (param i32) (result i32)
+    (memory.grow $second (local.get 0))))
+"#;
+
+fn multi_memory_store() -> Store {
+    let mut features = Features::default();
+    features.multi_memory(true);
+    let engine = JIT::new(Cranelift::default()).features(features).engine();
+    Store::new(&engine)
+}
+
+#[test]
+fn multi_memory_accesses_the_named_memory() -> Result<()> {
+    let store = multi_memory_store();
+    let module = Module::new(&store, MULTI_MEMORY)?;
+    let imported = Memory::new(&store, MemoryType::new(1, None, false))?;
+    imported.write_value(16, 7u32)?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "memory" => imported.clone(),
+            },
+        },
+    )?;
+
+    let first = instance.exports.get_memory("first")?;
+    let second = instance.exports.get_memory("second")?;
+    let store_i32 = instance
+        .exports
+        .get_native_function::<(i32, i32), ()>("store")?;
+    let copy = instance
+        .exports
+        .get_native_function::<(i32, i32, i32), ()>("copy")?;
+    let load_imported = instance
+        .exports
+        .get_native_function::<i32, i32>("load_imported")?;
+    let grow_second = instance
+        .exports
+        .get_native_function::<i32, i32>("grow_second")?;
+
+    store_i32.call(0x1_0000, 42)?;
+    assert_eq!(second.read_value::<u32>(0x1_0000), Ok(42));
+    assert_eq!(first.read_value::<u32>(0), Ok(0));
+
+    copy.call(8, 0x1_0000, 4)?;
+    assert_eq!(first.read_value::<u32>(8), Ok(42));
+    let error = copy.call(0xffff, 0x1_0000, 4).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+
+    assert_eq!(load_imported.call(16)?, 7);
+
+    assert_eq!(grow_second.call(1)?, 2);
+    assert_eq!(second.size(), Pages(3));
+    assert_eq!(first.size(), Pages(1));
+    assert_eq!(imported.size(), Pages(1));
+    Ok(())
+}
+
+#[test]
+fn multi_memory_requires_the_feature() -> Result<()> {
+    let store = Store::default();
+    assert!(Module::new(&store, MULTI_MEMORY).is_err());
+    Ok(())
+}
@@ -495,3 +495,102 @@ fn extern_ref_ref_counting_table_instructions() -> Result<()> {
 
     Ok(())
 }
+
+/// Host data holding a table, which records when it is dropped.
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+struct TableHolder {
+    table: Table,
+    dropped: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+unsafe impl Trace for TableHolder {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        self.table.trace(tracer);
+    }
+}
+
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+impl Drop for TableHolder {
+    fn drop(&mut self) {
+        self.dropped.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Stores an `ExternRef` holding `table` in `table`.
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+fn store_holder_in_table(store: &Store, table: &Table) -> Result<Arc<AtomicBool>> {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let holder = TableHolder {
+        table: table.clone(),
+        dropped: dropped.clone(),
+    };
+    let er = ExternRef::new_traced_in(store.extern_ref_heap(), holder);
+    table.set(0, Val::ExternRef(er))?;
+    Ok(dropped)
+}
+
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+#[test]
+fn extern_ref_cycles_are_collected() -> Result<()> {
+    let store = Store::default();
+    let table = Table::new(
+        &store,
+        TableType::new(Type::ExternRef, 1, None),
+        Val::ExternRef(ExternRef::null()),
+    )?;
+    let dropped = store_holder_in_table(&store, &table)?;
+
+    assert_eq!(store.live_extern_refs(), 1);
+    assert_eq!(unsafe { store.gc() }, 0);
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    drop(table);
+    assert_eq!(store.live_extern_refs(), 1);
+    assert_eq!(unsafe { store.gc() }, 1);
+    assert!(dropped.load(Ordering::SeqCst));
+    assert_eq!(store.live_extern_refs(), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+#[test]
+fn extern_ref_cycles_through_instances_are_collected() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (table $table (export "table") 1 externref)
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let dropped = store_holder_in_table(&store, instance.exports.get_table("table")?)?;
+
+    assert_eq!(unsafe { store.gc() }, 0);
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    drop(instance);
+    assert_eq!(unsafe { store.gc() }, 1);
+    assert!(dropped.load(Ordering::SeqCst));
+    assert_eq!(store.live_extern_refs(), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+#[test]
+fn extern_ref_live_count() -> Result<()> {
+    let store = Store::default();
+    let er = ExternRef::new_in(store.extern_ref_heap(), 3usize);
+    let er2 = er.clone();
+    let untracked = ExternRef::new(5usize);
+    assert_eq!(store.live_extern_refs(), 1);
+
+    drop(er);
+    assert_eq!(store.live_extern_refs(), 1);
+    assert_eq!(unsafe { store.gc() }, 0);
+    drop(er2);
+    assert_eq!(store.live_extern_refs(), 0);
+    drop(untracked);
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+#![cfg(unix)]
+
+use anyhow::Result;
+use std::thread;
+use wasmer::*;
+
+/// A function recursing forever, counting its depth in a global.
+const RECURSE: &str = r#"
+(module
+  (global $depth (export "depth") (mut i32) (i32.const 0))
+  (func $recurse (export "recurse")
+    global.get $depth
+    i32.const 1
+    i32.add
+    global.set $depth
+    call $recurse))
+"#;
+
+/// Every frame saves at least a return address and a frame pointer.
+const MIN_FRAME_SIZE: usize = 16;
+
+fn store_with_stack(stack: StackConfig) -> Store {
+    let store = Store::default();
+    let tunables = StackTunables::new(BaseTunables::for_target(&Target::default()), stack);
+    Store::new_with_tunables(&**store.engine(), tunables)
+}
+
+/// Calls `recurse` until it overflows the stack, and returns the depth
+/// it reached.
+fn overflow(store: &Store) -> Result<i32> {
+    let module = Module::new(store, RECURSE)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let recurse = instance.exports.get_function("recurse")?;
+    let error = recurse.call(&[]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::StackOverflow));
+    match instance.exports.get_global("depth")?.get() {
+        Val::I32(depth) => Ok(depth),
+        value => panic!("unexpected depth {:?}", value),
+    }
+}
+
+#[test]
+fn max_stack_size_limits_recursion() -> Result<()> {
+    let max_size = 64 * 1024;
+    let depth = overflow(&store_with_stack(StackConfig::with_max_size(max_size)))?;
+    assert!(depth > 0);
+    assert!((depth as usize) < max_size / MIN_FRAME_SIZE);
+
+    // The guard page is removed after the call, and set again by the
+    // next one.
+    let depth_again = overflow(&store_with_stack(StackConfig::with_max_size(max_size)))?;
+    assert!((depth_again as usize) < max_size / MIN_FRAME_SIZE);
+    Ok(())
+}
+
+// Dedicated stacks are only available there.
+#[cfg(any(target_os = "macos", all(target_os = "linux", target_env = "gnu")))]
+#[test]
+fn dedicated_stack_limits_recursion() -> Result<()> {
+    let max_size = 64 * 1024;
+    let depth = overflow(&store_with_stack(StackConfig::dedicated(max_size)))?;
+    assert!(depth > 0);
+    assert!((depth as usize) < max_size / MIN_FRAME_SIZE);
+    Ok(())
+}
+
+// Dedicated stacks are only available there.
+#[cfg(any(target_os = "macos", all(target_os = "linux", target_env = "gnu")))]
+#[test]
+fn max_stack_size_larger_than_the_thread_stack() -> Result<()> {
+    // The thread doesn't have enough stack, so the guest runs on a
+    // dedicated stack of the requested size.
+    let max_size = 4 * 1024 * 1024;
+    let depth = thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || overflow(&store_with_stack(StackConfig::with_max_size(max_size))))?
+        .join()
+        .unwrap()?;
+    assert!((depth as usize) > 256 * 1024 / MIN_FRAME_SIZE / 4);
+    Ok(())
+}
+
+#[test]
+fn dedicated_stack_returns_values() -> Result<()> {
+    let store = store_with_stack(StackConfig::dedicated(256 * 1024));
+    let module = Module::new(
+        &store,
+        r#"(module (func (export "add") (param i32 i32) (result i32)
+             local.get 0
+             local.get 1
+             i32.add))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(1, 2)?, 3);
+    Ok(())
+}
@@ -0,0 +1,143 @@
+#![cfg(all(feature = "cranelift", feature = "jit"))]
+
+use anyhow::Result;
+use wasmer::*;
+
+const TAIL_CALLS: &str = r#"
+(module
+  (import "env" "double" (func $double (param i64) (result i64)))
+  (type $countdown (func (param i64) (result i64)))
+  (table funcref (elem $even $odd))
+  (func $even (export "even") (param i64) (result i64)
+    (if (result i64) (i64.eqz (local.get 0))
+      (then (i64.const 1))
+      (else
+        (return_call_indirect (type $countdown)
+          (i64.sub (local.get 0) (i64.const 1)) (i32.const 1)))))
+  (func $odd (param i64) (result i64)
+    (if (result i64) (i64.eqz (local.get 0))
+      (then (i64.const 0))
+      (else
+        (return_call $even (i64.sub (local.get 0) (i64.const 1))))))
+  (func (export "double_plus_one") (param i64) (result i64)
+    (i64.add (call $double_tail (local.get 0)) (i64.const 1)))
+  (func $double_tail (param i64) (result i64)
+    (return_call $double (local.get 0)))
+  (func (export "bad_signature") (result i32)
+    (return_call_indirect (result i32) (i32.const 0))))
+"#;
+
+const DOUBLE: &str = r#"
+(module
+  (func (export "double") (param i64) (result i64)
+    (i64.mul (local.get 0) (i64.const 2))))
+"#;
+
+const IMPORTS_EVEN: &str = r#"
+(module
+  (import "env" "even" (func (param i64) (result i64))))
+"#;
+
+fn tail_calls_store() -> Store {
+    let mut features = Features::default();
+    features.tail_call(true);
+    let engine = JIT::new(Cranelift::default()).features(features).engine();
+    Store::new(&engine)
+}
+
+fn instantiate(store: &Store) -> Result<Instance> {
+    let module = Module::new(store, TAIL_CALLS)?;
+    let double = Function::new_native(store, |value: i64| value * 2);
+    Ok(Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "double" => double,
+            },
+        },
+    )?)
+}
+
+#[test]
+fn tail_calls_run_in_constant_stack() -> Result<()> {
+    let store = tail_calls_store();
+    let instance = instantiate(&store)?;
+    let even = instance.exports.get_native_function::<i64, i64>("even")?;
+
+    assert_eq!(even.call(10)?, 1);
+    assert_eq!(even.call(7)?, 0);
+    assert_eq!(even.call(10_000_000)?, 1);
+    Ok(())
+}
+
+#[test]
+fn host_functions_are_tail_called() -> Result<()> {
+    let store = tail_calls_store();
+    let instance = instantiate(&store)?;
+    let double_plus_one = instance
+        .exports
+        .get_function("double_plus_one")?
+        .native::<i64, i64>()?;
+
+    assert_eq!(double_plus_one.call(20)?, 41);
+    Ok(())
+}
+
+#[test]
+fn indirect_tail_calls_check_the_signature() -> Result<()> {
+    let store = tail_calls_store();
+    let instance = instantiate(&store)?;
+    let bad_signature = instance.exports.get_function("bad_signature")?;
+
+    let error = bad_signature.call(&[]).unwrap_err();
+    assert_eq!(error.message(), "indirect call type mismatch");
+    Ok(())
+}
+
+#[test]
+fn tail_calls_require_the_feature() -> Result<()> {
+    let store = Store::default();
+    assert!(Module::new(&store, TAIL_CALLS).is_err());
+    Ok(())
+}
+
+#[test]
+fn modules_with_tail_calls_only_import_functions_with_tail_calls() -> Result<()> {
+    let store = tail_calls_store();
+    let other_store = Store::default();
+    let double = Instance::new(&Module::new(&other_store, DOUBLE)?, &imports! {})?;
+    let module = Module::new(&store, TAIL_CALLS)?;
+
+    let error = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "double" => double.exports.get_function("double")?.clone(),
+            },
+        },
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("compiled without tail calls"),
+        "wrong error: {}",
+        error
+    );
+
+    let instance = instantiate(&store)?;
+    let module = Module::new(&other_store, IMPORTS_EVEN)?;
+    let error = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "even" => instance.exports.get_function("even")?.clone(),
+            },
+        },
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("compiled with tail calls"),
+        "wrong error: {}",
+        error
+    );
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use wasmer::*;
+
+const TRACED: &str = r#"
+(module $traced
+  (import "env" "double" (func $double (param i32) (result i32)))
+  (import "env" "load" (func $load (param i32) (result i32)))
+  (import "env" "fail" (func $fail))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "\2a")
+  (func (export "double_load") (param i32) (result i32)
+    (call $double (call $load (local.get 0))))
+  (func (export "fail") (call $fail))
+  (func (export "trap") (unreachable)))
+"#;
+
+/// Records the calls it is told about.
+#[derive(Default)]
+struct Recorder {
+    calls: Mutex<Vec<String>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.calls.lock().unwrap())
+    }
+}
+
+impl CallTracer for Recorder {
+    fn enter(&self, call: &TracedCall, params: &[Val]) {
+        self.calls.lock().unwrap().push(format!(
+            "enter {:?} {}.{} {:?}",
+            call.kind, call.module, call.field, params
+        ));
+    }
+
+    fn exit(&self, call: &TracedCall, params: &[Val], results: Result<&[Val], &RuntimeError>) {
+        let results = match results {
+            Ok(results) => format!("{:?}", results),
+            Err(error) => format!("error: {}", error.message()),
+        };
+        self.calls.lock().unwrap().push(format!(
+            "exit {:?} {}.{} {:?} {}",
+            call.kind, call.module, call.field, params, results
+        ));
+    }
+}
+
+#[derive(WasmerEnv, Clone, Default)]
+struct MemoryEnv {
+    #[wasmer(export)]
+    memory: LazyInit<Memory>,
+}
+
+fn instantiate(store: &Store) -> Result<Instance> {
+    let module = Module::new(store, TRACED)?;
+    let env = imports! {
+        "env" => {
+            "double" => Function::new_native(store, |value: i32| value * 2),
+            "load" => Function::new_native_with_env(
+                store,
+                MemoryEnv::default(),
+                |env: &MemoryEnv, ptr: i32| {
+                    env.memory_ref().unwrap().view::<u8>()[ptr as usize].get() as i32
+                },
+            ),
+            "fail" => Function::new(store, FunctionType::new(vec![], vec![]), |_| {
+                Err(RuntimeError::new("failed"))
+            }),
+        },
+    };
+    Ok(Instance::new(&module, &env)?)
+}
+
+fn traced_store() -> (Store, Arc<Recorder>) {
+    let store = Store::default();
+    let recorder = Arc::new(Recorder::default());
+    store.set_call_tracer(Some(recorder.clone()));
+    (store, recorder)
+}
+
+#[test]
+fn imports_and_exports_are_traced() -> Result<()> {
+    let (store, recorder) = traced_store();
+    let instance = instantiate(&store)?;
+    let double_load = instance.exports.get_function("double_load")?;
+
+    assert_eq!(
+        double_load.call(&[Val::I32(16)])?.to_vec(),
+        vec![Val::I32(84)]
+    );
+    assert_eq!(
+        recorder.take(),
+        vec![
+            "enter Export traced.double_load [I32(16)]",
+            "enter Import env.load [I32(16)]",
+            "exit Import env.load [I32(16)] [I32(42)]",
+            "enter Import env.double [I32(42)]",
+            "exit Import env.double [I32(42)] [I32(84)]",
+            "exit Export traced.double_load [I32(16)] [I32(84)]",
+        ]
+    );
+
+    let double_load = double_load.native::<i32, i32>()?;
+    assert_eq!(double_load.call(16)?, 84);
+    assert_eq!(recorder.take().len(), 6);
+    Ok(())
+}
+
+#[test]
+fn errors_and_traps_are_traced() -> Result<()> {
+    let (store, recorder) = traced_store();
+    let instance = instantiate(&store)?;
+
+    let fail = instance.exports.get_function("fail")?;
+    assert_eq!(fail.call(&[]).unwrap_err().message(), "failed");
+    assert_eq!(
+        recorder.take(),
+        vec![
+            "enter Export traced.fail []",
+            "enter Import env.fail []",
+            "exit Import env.fail [] error: failed",
+            "exit Export traced.fail [] error: failed",
+        ]
+    );
+
+    let trap = instance.exports.get_native_function::<(), ()>("trap")?;
+    assert!(trap.call().is_err());
+    let calls = recorder.take();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0], "enter Export traced.trap []");
+    assert!(calls[1].starts_with("exit Export traced.trap [] error: "));
+    assert!(calls[1].contains("unreachable"), "wrong call: {}", calls[1]);
+    Ok(())
+}
+
+#[test]
+fn only_instances_created_with_a_tracer_are_traced() -> Result<()> {
+    let store = Store::default();
+    let untraced = instantiate(&store)?;
+    let recorder = Arc::new(Recorder::default());
+    store.set_call_tracer(Some(recorder.clone()));
+    let traced = instantiate(&store)?;
+
+    let double_load = untraced.exports.get_function("double_load")?;
+    assert_eq!(
+        double_load.call(&[Val::I32(16)])?.to_vec(),
+        vec![Val::I32(84)]
+    );
+    assert!(recorder.take().is_empty());
+
+    store.set_call_tracer(None);
+    let double_load = traced.exports.get_function("double_load")?;
+    assert_eq!(
+        double_load.call(&[Val::I32(16)])?.to_vec(),
+        vec![Val::I32(84)]
+    );
+    assert!(recorder.take().is_empty());
+    Ok(())
+}
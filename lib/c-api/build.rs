@@ -459,6 +459,7 @@ fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
         .exclude_item("wasi_config_inherit_stdin")
         .exclude_item("wasi_config_inherit_stdout")
         .exclude_item("wasi_config_mapdir")
+        .exclude_item("wasi_config_mapdir_image")
         .exclude_item("wasi_config_new")
         .exclude_item("wasi_config_preopen_dir")
         .exclude_item("wasi_config_t")
@@ -568,6 +568,7 @@ impl From<&ExternType> for wasmer_import_export_kind {
             ExternType::Global(_) => wasmer_import_export_kind::WASM_GLOBAL,
             ExternType::Table(_) => wasmer_import_export_kind::WASM_TABLE,
             ExternType::Function(_) => wasmer_import_export_kind::WASM_FUNCTION,
+            ExternType::Tag(_) => unreachable!("the C API doesn't enable exception handling"),
         }
     }
 }
@@ -191,6 +191,7 @@ pub unsafe extern "C" fn wasmer_import_object_get_import(
                 let writer = import_export_value_out.func as *mut Global;
                 *writer = global.clone();
             }
+            Extern::Tag(_) => unreachable!("the C API doesn't enable exception handling"),
         }
 
         import_out.value = *import_export_value;
@@ -325,6 +326,7 @@ pub unsafe extern "C" fn wasmer_import_object_iter_next(
                     memory: Box::into_raw(tbl) as *mut _ as *const _,
                 };
             }
+            Extern::Tag(_) => unreachable!("the C API doesn't enable exception handling"),
         }
 
         wasmer_result_t::WASMER_OK
@@ -15,12 +15,15 @@ use crate::error::{update_last_error, CApiError};
 use std::cmp::min;
 use std::convert::TryFrom;
 use std::ffi::CStr;
+use std::fs;
 use std::os::raw::c_char;
+use std::path::PathBuf;
 use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use wasmer::{Extern, NamedResolver};
 use wasmer_wasi::{
-    generate_import_object_from_env, get_wasi_version, WasiEnv, WasiFile, WasiState,
-    WasiStateBuilder, WasiVersion,
+    generate_import_object_from_env, get_wasi_version, unpack_dir_image, WasiEnv, WasiFile,
+    WasiState, WasiStateBuilder, WasiVersion,
 };
 
 #[derive(Debug)]
@@ -30,6 +33,21 @@ pub struct wasi_config_t {
     inherit_stderr: bool,
     inherit_stdin: bool,
     state_builder: WasiStateBuilder,
+    /// The directories where directory images were unpacked, removed
+    /// along with the config or the environment built from it.
+    image_dirs: Vec<PathBuf>,
+}
+
+impl Drop for wasi_config_t {
+    fn drop(&mut self) {
+        remove_image_dirs(&self.image_dirs);
+    }
+}
+
+fn remove_image_dirs(image_dirs: &[PathBuf]) {
+    for dir in image_dirs {
+        let _ = fs::remove_dir_all(dir);
+    }
 }
 
 #[no_mangle]
@@ -46,6 +64,7 @@ pub unsafe extern "C" fn wasi_config_new(
         inherit_stderr: true,
         inherit_stdin: true,
         state_builder: WasiState::new(prog_name),
+        image_dirs: vec![],
     }))
 }
 
@@ -133,6 +152,55 @@ pub unsafe extern "C" fn wasi_config_mapdir(
     true
 }
 
+/// Unpacks a directory image, as packed by `wasmer create-exe
+/// --embed-dir`, into a new temporary directory, and maps it read-only
+/// at `alias`.
+///
+/// The temporary directory is removed when the `wasi_env_t` built from
+/// this config is deleted, so the environment must outlive the
+/// instances using it.
+#[no_mangle]
+pub unsafe extern "C" fn wasi_config_mapdir_image(
+    config: &mut wasi_config_t,
+    alias: *const c_char,
+    image: *const u8,
+    image_len: usize,
+) -> bool {
+    static NEXT_IMAGE_DIR: AtomicUsize = AtomicUsize::new(0);
+
+    let alias_cstr = CStr::from_ptr(alias);
+    let alias_str = match alias_cstr.to_str() {
+        Ok(alias_str) => alias_str,
+        Err(e) => {
+            update_last_error(e);
+            return false;
+        }
+    };
+    let image = slice::from_raw_parts(image, image_len);
+
+    let dir = std::env::temp_dir().join(format!(
+        "wasmer-image-{}-{}",
+        std::process::id(),
+        NEXT_IMAGE_DIR.fetch_add(1, Ordering::SeqCst)
+    ));
+    if let Err(e) = fs::create_dir(&dir) {
+        update_last_error(e);
+        return false;
+    }
+    config.image_dirs.push(dir.clone());
+
+    if let Err(e) = unpack_dir_image(image, &dir) {
+        update_last_error(e);
+        return false;
+    }
+    if let Err(e) = config.state_builder.map_dir_read_only(alias_str, &dir) {
+        update_last_error(e);
+        return false;
+    }
+
+    true
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_capture_stdout(config: &mut wasi_config_t) {
     config.inherit_stdout = false;
@@ -167,6 +235,14 @@ pub extern "C" fn wasi_config_inherit_stdin(config: &mut wasi_config_t) {
 pub struct wasi_env_t {
     /// cbindgen:ignore
     pub(super) inner: WasiEnv,
+    /// cbindgen:ignore
+    image_dirs: Vec<PathBuf>,
+}
+
+impl Drop for wasi_env_t {
+    fn drop(&mut self) {
+        remove_image_dirs(&self.image_dirs);
+    }
 }
 
 /// Create a new WASI environment.
@@ -192,6 +268,7 @@ pub extern "C" fn wasi_env_new(mut config: Box<wasi_config_t>) -> Option<Box<was
 
     Some(Box::new(wasi_env_t {
         inner: WasiEnv::new(wasi_state),
+        image_dirs: std::mem::take(&mut config.image_dirs),
     }))
 }
 
@@ -714,6 +714,13 @@ void wasi_config_inherit_stdout(struct wasi_config_t *config);
 bool wasi_config_mapdir(struct wasi_config_t *config, const char *alias, const char *dir);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+bool wasi_config_mapdir_image(struct wasi_config_t *config,
+                              const char *alias,
+                              const uint8_t *image,
+                              uintptr_t image_len);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 struct wasi_config_t *wasi_config_new(const char *program_name);
 #endif
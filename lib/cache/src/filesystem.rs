@@ -1,15 +1,39 @@
 use crate::cache::Cache;
 use crate::hash::Hash;
-use std::fs::{create_dir_all, File};
+use filetime::FileTime;
+use std::fs::{self, create_dir_all, File};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
 use wasmer::{DeserializeError, Module, SerializeError, Store};
 
+/// The extension of the files being written, before they are renamed
+/// to their final name.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// How old a temporary file must be to be considered abandoned, by a
+/// process that crashed while storing a module.
+const ABANDONED_TEMP_FILE_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Makes the temporary files of the threads of a process unique.
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 /// Representation of a directory that contains compiled wasm artifacts.
 ///
 /// The `FileSystemCache` type implements the [`Cache`] trait, which allows it to be used
 /// generically when some sort of cache is required.
 ///
+/// The same directory can be shared by many processes: modules are written
+/// to a temporary file which is then renamed, so a module is never loaded
+/// while it's being stored.  Artifacts that fail to load because they are
+/// corrupted or incompatible are removed, to be stored again.
+///
+/// The cache grows without bounds unless a maximum size is set with
+/// [`FileSystemCache::set_max_size`], or it's [pruned](FileSystemCache::prune)
+/// regularly.  Both evict the least recently used artifacts first.
+///
 /// # Usage
 ///
 /// ```
@@ -33,6 +57,34 @@ use wasmer::{DeserializeError, Module, SerializeError, Store};
 pub struct FileSystemCache {
     path: PathBuf,
     ext: Option<String>,
+    max_size: Option<u64>,
+}
+
+/// An artifact stored in a [`FileSystemCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// The path of the artifact.
+    pub path: PathBuf,
+    /// The size of the artifact, in bytes.
+    pub size: u64,
+    /// When the artifact was last stored or loaded.
+    pub last_used: SystemTime,
+}
+
+/// The number and total size of artifacts in a [`FileSystemCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of artifacts.
+    pub entries: usize,
+    /// The total size of the artifacts, in bytes.
+    pub size: u64,
+}
+
+impl CacheStats {
+    fn add(&mut self, entry: &CacheEntry) {
+        self.entries += 1;
+        self.size += entry.size;
+    }
 }
 
 impl FileSystemCache {
@@ -43,7 +95,11 @@ impl FileSystemCache {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self { path, ext: None })
+                    Ok(Self {
+                        path,
+                        ext: None,
+                        max_size: None,
+                    })
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -64,7 +120,11 @@ impl FileSystemCache {
         } else {
             // Create the directory and any parent directories if they don't yet exist.
             create_dir_all(&path)?;
-            Ok(Self { path, ext: None })
+            Ok(Self {
+                path,
+                ext: None,
+                max_size: None,
+            })
         }
     }
 
@@ -75,34 +135,279 @@ impl FileSystemCache {
     pub fn set_cache_extension(&mut self, ext: Option<impl ToString>) {
         self.ext = ext.map(|ext| ext.to_string());
     }
-}
 
-impl Cache for FileSystemCache {
-    type DeserializeError = DeserializeError;
-    type SerializeError = SerializeError;
+    /// Set the maximum size of the cache, in bytes.
+    ///
+    /// When set, storing a module evicts the least recently used
+    /// artifacts until the cache fits again.
+    pub fn set_max_size(&mut self, max_size: Option<u64>) {
+        self.max_size = max_size;
+    }
 
-    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+    /// The artifacts of the cache, including the ones in subdirectories,
+    /// from the least to the most recently used.
+    pub fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let mut entries = vec![];
+        let mut temp_files = vec![];
+        collect_entries(&self.path, &mut entries, &mut temp_files)?;
+        entries.sort_by_key(|entry| entry.last_used);
+        Ok(entries)
+    }
+
+    /// The number and total size of the artifacts of the cache.
+    pub fn stats(&self) -> io::Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        for entry in self.entries()? {
+            stats.add(&entry);
+        }
+        Ok(stats)
+    }
+
+    /// Removes the artifacts that were not used for `max_age`, then the
+    /// least recently used ones until the cache is at most `max_size`
+    /// bytes.
+    ///
+    /// Temporary files abandoned by processes that crashed while storing
+    /// a module are removed too.  Returns what was removed.
+    pub fn prune(
+        &self,
+        max_size: Option<u64>,
+        max_age: Option<Duration>,
+    ) -> io::Result<CacheStats> {
+        let mut entries = vec![];
+        let mut temp_files = vec![];
+        collect_entries(&self.path, &mut entries, &mut temp_files)?;
+        entries.sort_by_key(|entry| entry.last_used);
+
+        let now = SystemTime::now();
+        let age = |entry: &CacheEntry| now.duration_since(entry.last_used).unwrap_or_default();
+        for temp_file in temp_files {
+            if age(&temp_file) > ABANDONED_TEMP_FILE_AGE {
+                remove_entry(&temp_file)?;
+            }
+        }
+
+        let mut size: u64 = entries.iter().map(|entry| entry.size).sum();
+        let mut removed = CacheStats::default();
+        for entry in entries {
+            let too_old = max_age.map_or(false, |max_age| age(&entry) > max_age);
+            let too_big = max_size.map_or(false, |max_size| size > max_size);
+            if !too_old && !too_big {
+                // the next entries are more recent
+                break;
+            }
+            remove_entry(&entry)?;
+            size -= entry.size;
+            removed.add(&entry);
+        }
+        Ok(removed)
+    }
+
+    fn path_for(&self, key: Hash) -> PathBuf {
         let filename = if let Some(ref ext) = self.ext {
             format!("{}.{}", key.to_string(), ext)
         } else {
             key.to_string()
         };
-        let path = self.path.join(filename);
-        Module::deserialize_from_file(&store, path)
+        self.path.join(filename)
     }
+}
 
-    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
-        let filename = if let Some(ref ext) = self.ext {
-            format!("{}.{}", key.to_string(), ext)
-        } else {
-            key.to_string()
+/// Collects the artifacts and the temporary files below `dir`.
+fn collect_entries(
+    dir: &Path,
+    entries: &mut Vec<CacheEntry>,
+    temp_files: &mut Vec<CacheEntry>,
+) -> io::Result<()> {
+    for dir_entry in fs::read_dir(dir)? {
+        let dir_entry = dir_entry?;
+        let metadata = match dir_entry.metadata() {
+            Ok(metadata) => metadata,
+            // removed by another process in the meantime
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let path = dir_entry.path();
+        if metadata.is_dir() {
+            collect_entries(&path, entries, temp_files)?;
+            continue;
+        }
+        let entry = CacheEntry {
+            size: metadata.len(),
+            last_used: metadata.modified()?,
+            path,
         };
-        let path = self.path.join(filename);
-        let mut file = File::create(path)?;
+        if is_temp_file(&entry.path) {
+            temp_files.push(entry);
+        } else {
+            entries.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn is_temp_file(path: &Path) -> bool {
+    let is_hidden = path
+        .file_name()
+        .map_or(false, |name| name.to_string_lossy().starts_with('.'));
+    is_hidden && path.extension().map_or(false, |ext| ext == TEMP_EXTENSION)
+}
+
+/// Removes the file of `entry`, unless another process already did.
+fn remove_entry(entry: &CacheEntry) -> io::Result<()> {
+    match fs::remove_file(&entry.path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+impl Cache for FileSystemCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        let path = self.path_for(key);
+        match Module::deserialize_from_file(&store, &path) {
+            Ok(module) => {
+                // The modification time tells when an artifact was last
+                // used, to evict the least recently used ones first.
+                let _ = filetime::set_file_mtime(&path, FileTime::now());
+                Ok(module)
+            }
+            Err(e @ DeserializeError::Generic(_))
+            | Err(e @ DeserializeError::Incompatible(_))
+            | Err(e @ DeserializeError::CorruptedBinary(_)) => {
+                // The artifact is unusable: remove it, so that it's stored
+                // again.  This can fail on platforms where files in use
+                // can't be removed, and then it will be overwritten.
+                let _ = fs::remove_file(&path);
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
 
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        let path = self.path_for(key);
         let buffer = module.serialize()?;
-        file.write_all(&buffer)?;
 
+        // Write the artifact to a temporary file, then move it in place:
+        // concurrent loads see either the previous artifact or the new
+        // one, never a partially written one.
+        let temp_path = self.path.join(format!(
+            ".{}.{}-{}.{}",
+            key.to_string(),
+            process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed),
+            TEMP_EXTENSION
+        ));
+        let written = File::create(&temp_path).and_then(|mut file| {
+            file.write_all(&buffer)?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|()| fs::rename(&temp_path, &path)) {
+            let _ = fs::remove_file(&temp_path);
+            // Renaming fails on some platforms when the artifact already
+            // exists and is in use: another process stored it already.
+            if !path.exists() {
+                return Err(e.into());
+            }
+        }
+
+        if let Some(max_size) = self.max_size {
+            // The module is stored already: failing to evict other
+            // artifacts is not an error for this store.
+            let _ = self.prune(Some(max_size), None);
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use wasmer_compiler_singlepass::Singlepass;
+    use wasmer_engine_jit::JIT;
+
+    /// Creates a fake artifact of `size` bytes, last used `age` ago.
+    fn create_entry(dir: &Path, name: &str, size: usize, age: Duration) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, vec![0; size]).unwrap();
+        let last_used = FileTime::from_system_time(SystemTime::now() - age);
+        filetime::set_file_mtime(&path, last_used).unwrap();
+        path
+    }
+
+    #[test]
+    fn prune_evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        let cache = FileSystemCache::new(dir.path()).unwrap();
+        let subdir = dir.path().join("singlepass");
+        fs::create_dir(&subdir).unwrap();
+        let old = create_entry(dir.path(), "old", 100, Duration::from_secs(300));
+        let recent = create_entry(&subdir, "recent", 100, Duration::from_secs(10));
+        let middle = create_entry(dir.path(), "middle", 100, Duration::from_secs(100));
+        let abandoned = create_entry(
+            dir.path(),
+            ".abandoned.tmp",
+            100,
+            2 * ABANDONED_TEMP_FILE_AGE,
+        );
+        let in_progress = create_entry(dir.path(), ".in-progress.tmp", 100, Duration::from_secs(1));
+
+        let entries = cache.entries().unwrap();
+        let paths: Vec<&Path> = entries.iter().map(|entry| entry.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![old.as_path(), middle.as_path(), recent.as_path()]
+        );
+        assert_eq!(
+            cache.stats().unwrap(),
+            CacheStats {
+                entries: 3,
+                size: 300
+            }
+        );
+
+        let removed = cache.prune(Some(250), None).unwrap();
+        assert_eq!(
+            removed,
+            CacheStats {
+                entries: 1,
+                size: 100
+            }
+        );
+        assert!(!old.exists() && middle.exists() && recent.exists());
+        assert!(!abandoned.exists() && in_progress.exists());
+
+        let removed = cache.prune(None, Some(Duration::from_secs(60))).unwrap();
+        assert_eq!(
+            removed,
+            CacheStats {
+                entries: 1,
+                size: 100
+            }
+        );
+        assert!(!middle.exists() && recent.exists());
+    }
+
+    #[test]
+    fn store_and_load() {
+        let dir = TempDir::new().unwrap();
+        let mut cache = FileSystemCache::new(dir.path()).unwrap();
+        let store = Store::new(&JIT::new(Singlepass::new()).engine());
+        let module = Module::new(&store, b"\0asm\x01\0\0\0").unwrap();
+        let key = Hash::generate(b"module");
+
+        cache.store(key, &module).unwrap();
+        assert_eq!(cache.stats().unwrap().entries, 1);
+        unsafe { cache.load(&store, key) }.unwrap();
+
+        // A corrupted artifact is removed on load.
+        let entry = cache.entries().unwrap().pop().unwrap();
+        fs::write(&entry.path, b"corrupted").unwrap();
+        assert!(unsafe { cache.load(&store, key) }.is_err());
+        assert!(!entry.path.exists());
+    }
+}
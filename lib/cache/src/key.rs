@@ -0,0 +1,136 @@
+use crate::hash::Hash;
+use std::string::ToString;
+use wasmer::{Engine, Store};
+
+/// The key of a compiled module in a [`Cache`].
+///
+/// A compiled artifact depends on more than the Wasm bytes it comes
+/// from: the engine, the compiler and its configuration (middlewares
+/// included), the target, the enabled features and the version of
+/// Wasmer all change it.  A `CacheKey` combines the hash of the module
+/// with a fingerprint of all of them, so changing any of those misses the
+/// cache instead of loading a stale artifact.
+///
+/// The key is turned into a [`Hash`] with [`CacheKey::hash`] to load and
+/// store modules.
+///
+/// # Usage
+///
+/// ```
+/// use wasmer::{Module, Store};
+/// use wasmer_cache::{Cache, CacheKey, FileSystemCache};
+///
+/// fn load_module(store: &Store, bytes: &[u8]) -> Option<Module> {
+///     let fs_cache = FileSystemCache::new("some/directory/goes/here").ok()?;
+///     let key = CacheKey::new(store, bytes);
+///     unsafe { fs_cache.load(store, key.hash()) }.ok()
+/// }
+/// ```
+///
+/// [`Cache`]: crate::Cache
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    module: Hash,
+    engine: Hash,
+}
+
+impl CacheKey {
+    /// Creates the key of the module made of `wasm_bytes`, compiled with
+    /// the engine of `store`.
+    pub fn new(store: &Store, wasm_bytes: &[u8]) -> Self {
+        Self::with_module_hash(store, Hash::generate(wasm_bytes))
+    }
+
+    /// Creates the key of the module whose bytes hash to `module`,
+    /// compiled with the engine of `store`.
+    pub fn with_module_hash(store: &Store, module: Hash) -> Self {
+        Self::from_fingerprint(module, &store.engine().fingerprint())
+    }
+
+    /// Creates the key of the module whose bytes hash to `module`,
+    /// compiled with an engine whose [`Engine::fingerprint`] is
+    /// `engine_fingerprint`.
+    pub fn from_fingerprint(module: Hash, engine_fingerprint: &str) -> Self {
+        let engine = format!("wasmer-{} {}", wasmer::VERSION, engine_fingerprint);
+        Self {
+            module,
+            engine: Hash::generate(engine.as_bytes()),
+        }
+    }
+
+    /// The hash of the Wasm bytes of the module.
+    pub fn module_hash(&self) -> Hash {
+        self.module
+    }
+
+    /// The hash of the fingerprint of the engine and of the Wasmer version.
+    pub fn engine_hash(&self) -> Hash {
+        self.engine
+    }
+
+    /// The hash combining the module and the engine, used to load and
+    /// store the module in a [`Cache`](crate::Cache).
+    pub fn hash(&self) -> Hash {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&self.module.to_array());
+        bytes[32..].copy_from_slice(&self.engine.to_array());
+        Hash::generate(&bytes)
+    }
+}
+
+impl From<CacheKey> for Hash {
+    fn from(key: CacheKey) -> Self {
+        key.hash()
+    }
+}
+
+impl ToString for CacheKey {
+    /// Create the hexadecimal representation of the combined hash.
+    fn to_string(&self) -> String {
+        self.hash().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_compiler_singlepass::Singlepass;
+    use wasmer_engine_jit::JIT;
+
+    #[test]
+    fn key_depends_on_module_and_engine() {
+        let module = Hash::generate(b"module");
+        let key = CacheKey::from_fingerprint(module, "engine");
+
+        assert_eq!(key, CacheKey::from_fingerprint(module, "engine"));
+        assert_eq!(key.module_hash(), module);
+        assert_ne!(key.hash(), module);
+        assert_ne!(
+            key.hash(),
+            CacheKey::from_fingerprint(module, "other engine").hash()
+        );
+        assert_ne!(
+            key.hash(),
+            CacheKey::from_fingerprint(Hash::generate(b"other module"), "engine").hash()
+        );
+    }
+
+    #[test]
+    fn key_depends_on_compiler_config() {
+        let wasm = b"\0asm\x01\0\0\0";
+        let store = Store::new(&JIT::new(Singlepass::new()).engine());
+        let same_store = Store::new(&JIT::new(Singlepass::new()).engine());
+        let mut config = Singlepass::new();
+        config.canonicalize_nans(false);
+        let other_store = Store::new(&JIT::new(config).engine());
+
+        assert_eq!(
+            CacheKey::new(&store, wasm),
+            CacheKey::new(&same_store, wasm)
+        );
+        assert_ne!(
+            CacheKey::new(&store, wasm),
+            CacheKey::new(&other_store, wasm)
+        );
+    }
+}
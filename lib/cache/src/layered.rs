@@ -0,0 +1,188 @@
+use crate::cache::Cache;
+use crate::hash::Hash;
+use std::sync::Mutex;
+use wasmer::{DeserializeError, Module, SerializeError, Store};
+
+/// A cache made of two tiers: a fast `front` cache, and a slower but
+/// larger or shared `back` cache.
+///
+/// Loading falls through the tiers: a module missing from the front
+/// cache is loaded from the back one, and then stored in the front one
+/// to be faster to load next time.  Storing writes through both tiers.
+///
+/// Layered caches nest, to make caches of more than two tiers.
+///
+/// # Usage
+///
+/// ```
+/// use wasmer_cache::{FileSystemCache, HttpStorage, LayeredCache, MemoryCache, RemoteCache};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let cache = LayeredCache::new(
+///     MemoryCache::new(32),
+///     LayeredCache::new(
+///         FileSystemCache::new("some/directory/goes/here")?,
+///         RemoteCache::new(
+///             HttpStorage::new("http://cache.internal:8080/wasmer")?,
+///             b"secret shared by the hosts",
+///         ),
+///     ),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LayeredCache<F, B> {
+    /// Locked to be filled while loading, but not while loading from
+    /// the back cache, which may be slow.
+    front: Mutex<F>,
+    back: B,
+}
+
+impl<F, B> LayeredCache<F, B>
+where
+    F: Cache<DeserializeError = DeserializeError, SerializeError = SerializeError>,
+    B: Cache<DeserializeError = DeserializeError, SerializeError = SerializeError>,
+{
+    /// Creates a cache with `front` in front of `back`.
+    pub fn new(front: F, back: B) -> Self {
+        Self {
+            front: Mutex::new(front),
+            back,
+        }
+    }
+
+    /// Returns the front and back caches.
+    pub fn into_inner(self) -> (F, B) {
+        (self.front.into_inner().unwrap(), self.back)
+    }
+}
+
+impl<F, B> Cache for LayeredCache<F, B>
+where
+    F: Cache<DeserializeError = DeserializeError, SerializeError = SerializeError>,
+    B: Cache<DeserializeError = DeserializeError, SerializeError = SerializeError>,
+{
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        if let Ok(module) = self.front.lock().unwrap().load(store, key) {
+            return Ok(module);
+        }
+        let module = self.back.load(store, key)?;
+        // The module is loaded already: failing to store it in the front
+        // cache only makes the next load slower.
+        let _ = self.front.lock().unwrap().store(key, &module);
+        Ok(module)
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        // Both tiers are written even if the first one fails.
+        let front = self.front.get_mut().unwrap().store(key, module);
+        let back = self.back.store(key, module);
+        front.and(back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryCache, RemoteCache, RemoteStorage};
+    use std::collections::HashMap;
+    use std::io;
+    use std::sync::Arc;
+    use wasmer_compiler_singlepass::Singlepass;
+    use wasmer_engine_jit::JIT;
+
+    /// A remote storage kept in memory, counting its requests.
+    #[derive(Default, Clone)]
+    struct LocalStorage {
+        objects: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+        gets: Arc<Mutex<usize>>,
+    }
+
+    impl RemoteStorage for LocalStorage {
+        fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+            *self.gets.lock().unwrap() += 1;
+            Ok(self.objects.lock().unwrap().get(key).cloned())
+        }
+
+        fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), bytes.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn loads_fall_through_and_stores_write_through() {
+        let store = Store::new(&JIT::new(Singlepass::new()).engine());
+        let module = Module::new(&store, b"\0asm\x01\0\0\0").unwrap();
+        let key = Hash::generate(b"module");
+        let storage = LocalStorage::default();
+
+        // A host compiles the module and stores it in every tier.
+        let mut cache = LayeredCache::new(
+            MemoryCache::new(1),
+            RemoteCache::new(storage.clone(), b"secret"),
+        );
+        cache.store(key, &module).unwrap();
+        assert_eq!(storage.objects.lock().unwrap().len(), 1);
+        unsafe { cache.load(&store, key) }.unwrap();
+        assert_eq!(*storage.gets.lock().unwrap(), 0);
+
+        // Another host loads it from the remote tier, once.
+        let cache = LayeredCache::new(
+            MemoryCache::new(1),
+            RemoteCache::new(storage.clone(), b"secret"),
+        );
+        unsafe { cache.load(&store, key) }.unwrap();
+        unsafe { cache.load(&store, key) }.unwrap();
+        assert_eq!(*storage.gets.lock().unwrap(), 1);
+
+        // Missing modules are missing from every tier.
+        let missing = Hash::generate(b"missing");
+        assert!(matches!(
+            unsafe { cache.load(&store, missing) },
+            Err(DeserializeError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn remote_modules_are_only_loaded_if_signed_with_the_secret() {
+        let store = Store::new(&JIT::new(Singlepass::new()).engine());
+        let module = Module::new(&store, b"\0asm\x01\0\0\0").unwrap();
+        let key = Hash::generate(b"module");
+        let storage = LocalStorage::default();
+        let mut cache = RemoteCache::new(storage.clone(), b"secret");
+        cache.store(key, &module).unwrap();
+        unsafe { cache.load(&store, key) }.unwrap();
+
+        let other_secret = RemoteCache::new(storage.clone(), b"other secret");
+        assert!(matches!(
+            unsafe { other_secret.load(&store, key) },
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+
+        // Tampered with.
+        for bytes in storage.objects.lock().unwrap().values_mut() {
+            *bytes.last_mut().unwrap() ^= 1;
+        }
+        assert!(matches!(
+            unsafe { cache.load(&store, key) },
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+
+        // Stored unsigned.
+        storage
+            .put(&key.to_string(), &module.serialize().unwrap()[..16])
+            .unwrap();
+        assert!(matches!(
+            unsafe { cache.load(&store, key) },
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+    }
+}
@@ -22,10 +22,18 @@
 mod cache;
 mod filesystem;
 mod hash;
+mod key;
+mod layered;
+mod memory;
+mod remote;
 
 pub use crate::cache::Cache;
-pub use crate::filesystem::FileSystemCache;
+pub use crate::filesystem::{CacheEntry, CacheStats, FileSystemCache};
 pub use crate::hash::Hash;
+pub use crate::key::CacheKey;
+pub use crate::layered::LayeredCache;
+pub use crate::memory::MemoryCache;
+pub use crate::remote::{HttpStorage, RemoteCache, RemoteStorage};
 
 // We re-export those for convinience of users
 pub use wasmer::{DeserializeError, SerializeError};
@@ -0,0 +1,118 @@
+use crate::cache::Cache;
+use crate::hash::Hash;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::Mutex;
+use wasmer::{DeserializeError, Module, SerializeError, Store};
+
+/// An in-process cache of the most recently used modules.
+///
+/// Loading a module from memory is immediate, so this cache is meant to
+/// be the first tier of a [`LayeredCache`](crate::LayeredCache), in
+/// front of slower caches.  A module is only loaded in a store sharing
+/// the engine of the store it was compiled in.
+///
+/// # Usage
+///
+/// ```
+/// use wasmer_cache::{FileSystemCache, LayeredCache, MemoryCache};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let cache = LayeredCache::new(
+///     MemoryCache::new(32),
+///     FileSystemCache::new("some/directory/goes/here")?,
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    /// From the most to the least recently used.
+    modules: Mutex<VecDeque<(Hash, Module)>>,
+}
+
+impl MemoryCache {
+    /// Creates a cache keeping up to `capacity` modules, evicting the
+    /// least recently used ones.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            modules: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// The number of modules in the cache.
+    pub fn len(&self) -> usize {
+        self.modules.lock().unwrap().len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Cache for MemoryCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        let mut modules = self.modules.lock().unwrap();
+        let position = modules
+            .iter()
+            .position(|(hash, module)| *hash == key && Store::same(module.store(), store));
+        match position.and_then(|position| modules.remove(position)) {
+            Some((hash, module)) => {
+                modules.push_front((hash, module.clone()));
+                Ok(module)
+            }
+            None => Err(DeserializeError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no module cached in memory for {}", key.to_string()),
+            ))),
+        }
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        let modules = self.modules.get_mut().unwrap();
+        modules
+            .retain(|(hash, cached)| *hash != key || !Store::same(cached.store(), module.store()));
+        modules.push_front((key, module.clone()));
+        modules.truncate(self.capacity);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_compiler_singlepass::Singlepass;
+    use wasmer_engine_jit::JIT;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let store = Store::new(&JIT::new(Singlepass::new()).engine());
+        let module = Module::new(&store, b"\0asm\x01\0\0\0").unwrap();
+        let (a, b, c) = (
+            Hash::generate(b"a"),
+            Hash::generate(b"b"),
+            Hash::generate(b"c"),
+        );
+        let mut cache = MemoryCache::new(2);
+        cache.store(a, &module).unwrap();
+        cache.store(b, &module).unwrap();
+        // `a` becomes the most recently used
+        unsafe { cache.load(&store, a) }.unwrap();
+        cache.store(c, &module).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(unsafe { cache.load(&store, a) }.is_ok());
+        assert!(unsafe { cache.load(&store, b) }.is_err());
+        assert!(unsafe { cache.load(&store, c) }.is_ok());
+
+        // Modules are not shared across engines.
+        let other_store = Store::new(&JIT::new(Singlepass::new()).engine());
+        assert!(unsafe { cache.load(&other_store, a) }.is_err());
+    }
+}
@@ -0,0 +1,482 @@
+//! A cache tier storing artifacts out of process, in a storage shared by
+//! many hosts.
+
+use crate::cache::Cache;
+use crate::hash::Hash;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use wasmer::{DeserializeError, Module, SerializeError, Store};
+
+/// A storage of artifacts keyed by name, like an object store or an
+/// HTTP server.
+///
+/// Implement this trait to use a new kind of storage as a [`RemoteCache`].
+pub trait RemoteStorage: Send + Sync {
+    /// Gets the bytes stored under `key`, or `None` if there are none.
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores `bytes` under `key`, replacing what was there.
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()>;
+}
+
+/// The context of the keys signing the artifacts, derived from secrets.
+const SIGNING_KEY_CONTEXT: &str = "wasmer-cache 2021-03-01 remote artifact signing key";
+
+/// The length of the signatures prepended to the stored artifacts.
+const SIGNATURE_LEN: usize = blake3::OUT_LEN;
+
+/// The default size of the largest artifact an [`HttpStorage`] accepts.
+const DEFAULT_MAX_ARTIFACT_SIZE: usize = 1 << 30;
+
+/// A cache storing serialized modules in a [`RemoteStorage`].
+///
+/// It's meant to be the last tier of a [`LayeredCache`], shared by a
+/// fleet of hosts so that a module compiled by one of them is compiled
+/// once for all.  The keys should then be [`CacheKey`]s, so that hosts
+/// with different engines or targets don't share artifacts.
+///
+/// Deserializing a module runs the code it contains, so the artifacts
+/// are signed with a secret shared by the hosts, and are only loaded if
+/// their signature matches: anyone able to write to the storage, or to
+/// tamper with the connection to it, can't make a host run their code
+/// without knowing the secret.
+///
+/// # Usage
+///
+/// ```
+/// use wasmer_cache::{FileSystemCache, HttpStorage, LayeredCache, RemoteCache};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let local = FileSystemCache::new("some/directory/goes/here")?;
+/// let storage = HttpStorage::new("http://cache.internal:8080/wasmer")?;
+/// let remote = RemoteCache::new(storage, b"secret shared by the hosts");
+/// let cache = LayeredCache::new(local, remote);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`LayeredCache`]: crate::LayeredCache
+/// [`CacheKey`]: crate::CacheKey
+pub struct RemoteCache<S: RemoteStorage> {
+    storage: S,
+    /// The key signing the artifacts.
+    signing_key: [u8; blake3::KEY_LEN],
+}
+
+impl<S: RemoteStorage> RemoteCache<S> {
+    /// Creates a cache storing modules in `storage`, signed with a key
+    /// derived from `secret`.
+    pub fn new(storage: S, secret: &[u8]) -> Self {
+        let mut signing_key = [0; blake3::KEY_LEN];
+        blake3::derive_key(SIGNING_KEY_CONTEXT, secret, &mut signing_key);
+        Self {
+            storage,
+            signing_key,
+        }
+    }
+
+    /// The storage of the cache.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Signs the artifact stored under `key`, which ties it to the key.
+    fn sign(&self, key: &str, artifact: &[u8]) -> blake3::Hash {
+        let mut hasher = blake3::Hasher::new_keyed(&self.signing_key);
+        hasher.update(key.as_bytes());
+        hasher.update(artifact);
+        hasher.finalize()
+    }
+}
+
+impl<S: RemoteStorage + fmt::Debug> fmt::Debug for RemoteCache<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The signing key is secret.
+        f.debug_struct("RemoteCache")
+            .field("storage", &self.storage)
+            .finish()
+    }
+}
+
+impl<S: RemoteStorage> Cache for RemoteCache<S> {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        let key = key.to_string();
+        let bytes = match self.storage.get(&key)? {
+            Some(bytes) => bytes,
+            None => {
+                return Err(DeserializeError::Io(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no module stored under {}", key),
+                )))
+            }
+        };
+        if bytes.len() < SIGNATURE_LEN {
+            return Err(DeserializeError::CorruptedBinary(format!(
+                "the module stored under {} isn't signed",
+                key
+            )));
+        }
+        let (signature, artifact) = bytes.split_at(SIGNATURE_LEN);
+        let mut expected = [0; SIGNATURE_LEN];
+        expected.copy_from_slice(signature);
+        // `blake3::Hash` compares in constant time.
+        if self.sign(&key, artifact) != blake3::Hash::from(expected) {
+            return Err(DeserializeError::CorruptedBinary(format!(
+                "the signature of the module stored under {} doesn't match",
+                key
+            )));
+        }
+        Module::deserialize(store, artifact)
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        let key = key.to_string();
+        let artifact = module.serialize()?;
+        let bytes = [&self.sign(&key, &artifact).as_bytes()[..], &artifact[..]].concat();
+        self.storage.put(&key, &bytes)?;
+        Ok(())
+    }
+}
+
+/// A [`RemoteStorage`] on an HTTP server, such as an object store or a
+/// plain web server accepting `PUT` requests.
+///
+/// The artifact of a key is at `<url>/<key>`: it's fetched with `GET`
+/// and stored with `PUT`.  Only plain `http://` URLs are supported.
+#[derive(Debug, Clone)]
+pub struct HttpStorage {
+    /// The `host:port` of the server.
+    address: String,
+    /// The path of the artifacts on the server, without trailing `/`.
+    path: String,
+    timeout: Duration,
+    max_artifact_size: usize,
+}
+
+impl HttpStorage {
+    /// Creates a storage on the server at `url`.
+    pub fn new(url: &str) -> io::Result<Self> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("only `http://` URLs are supported, got `{}`", url),
+            )
+        })?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, ""),
+        };
+        if authority.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the URL `{}` has no host", url),
+            ));
+        }
+        let address = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+        Ok(Self {
+            address,
+            path: path.trim_end_matches('/').to_string(),
+            timeout: Duration::from_secs(30),
+            max_artifact_size: DEFAULT_MAX_ARTIFACT_SIZE,
+        })
+    }
+
+    /// Sets how long to wait for the server to accept a connection, and
+    /// then between the reads and writes of a request, before giving up on
+    /// it.  Defaults to 30 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Sets the size of the largest artifact to fetch from the server: the
+    /// responses with a larger body are rejected without reading it all.
+    /// Defaults to 1 GiB.
+    pub fn set_max_artifact_size(&mut self, max_artifact_size: usize) {
+        self.max_artifact_size = max_artifact_size;
+    }
+
+    /// Connects to the first address of the server accepting the
+    /// connection in time.
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut error = None;
+        for address in self.address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => error = Some(e),
+            }
+        }
+        Err(error.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("`{}` has no address", self.address),
+            )
+        }))
+    }
+
+    /// Sends a request for `key`, and returns the status code and the body
+    /// of the response.
+    fn request(&self, method: &str, key: &str, body: &[u8]) -> io::Result<(u16, Vec<u8>)> {
+        let mut stream = self.connect()?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        write!(
+            stream,
+            "{} {}/{} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            self.path,
+            key,
+            self.address,
+            body.len()
+        )?;
+        stream.write_all(body)?;
+        stream.flush()?;
+        read_response(BufReader::new(stream), self.max_artifact_size)
+    }
+}
+
+impl RemoteStorage for HttpStorage {
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match self.request("GET", key, &[])? {
+            (200, body) => Ok(Some(body)),
+            (404, _) => Ok(None),
+            (status, _) => Err(unexpected_status("GET", status)),
+        }
+    }
+
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        match self.request("PUT", key, bytes)? {
+            (200..=299, _) => Ok(()),
+            (status, _) => Err(unexpected_status("PUT", status)),
+        }
+    }
+}
+
+fn unexpected_status(method: &str, status: u16) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!(
+            "the cache server answered {} with status {}",
+            method, status
+        ),
+    )
+}
+
+fn invalid_response(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid HTTP response: {}", what),
+    )
+}
+
+/// Reads an HTTP/1.1 response, with a body of known length or chunked,
+/// of at most `max_body_size` bytes.
+fn read_response<R: BufRead>(mut reader: R, max_body_size: usize) -> io::Result<(u16, Vec<u8>)> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status: u16 = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid_response("bad status line"))?;
+
+    let mut content_length = None;
+    let mut chunked = false;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_response("truncated headers"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(colon) = header.find(':') {
+            let (name, value) = (&header[..colon], header[colon + 1..].trim());
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.parse().map_err(|_| invalid_response("bad length"))?);
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.eq_ignore_ascii_case("chunked");
+            }
+        }
+    }
+
+    let mut body = vec![];
+    if chunked {
+        loop {
+            line.clear();
+            reader.read_line(&mut line)?;
+            let size = line.trim().split(';').next().unwrap_or_default();
+            let size =
+                usize::from_str_radix(size, 16).map_err(|_| invalid_response("bad chunk size"))?;
+            if size == 0 {
+                break;
+            }
+            read_body(&mut reader, &mut body, size, max_body_size)?;
+            // the line break ending the chunk
+            line.clear();
+            reader.read_line(&mut line)?;
+        }
+    } else if let Some(content_length) = content_length {
+        read_body(&mut reader, &mut body, content_length, max_body_size)?;
+    } else {
+        // one more byte than allowed tells a body of the maximum size from
+        // a larger one
+        reader
+            .take(max_body_size as u64 + 1)
+            .read_to_end(&mut body)?;
+        if body.len() > max_body_size {
+            return Err(body_too_large());
+        }
+    }
+    Ok((status, body))
+}
+
+/// Appends the next `len` bytes of `reader` to `body`, as they arrive
+/// rather than allocating them upfront, unless `body` would then be larger
+/// than `max_body_size`.
+fn read_body<R: Read>(
+    reader: &mut R,
+    body: &mut Vec<u8>,
+    len: usize,
+    max_body_size: usize,
+) -> io::Result<()> {
+    match body.len().checked_add(len) {
+        Some(body_size) if body_size <= max_body_size => {}
+        _ => return Err(body_too_large()),
+    }
+    if reader.take(len as u64).read_to_end(body)? < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "invalid HTTP response: truncated body",
+        ));
+    }
+    Ok(())
+}
+
+fn body_too_large() -> io::Error {
+    invalid_response("the body is larger than the maximum artifact size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// A stand-in for a remote cache server, storing the bodies of `PUT`
+    /// requests and serving them back on `GET`.
+    fn spawn_server() -> (String, Arc<Mutex<HashMap<String, Vec<u8>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/cache/", listener.local_addr().unwrap());
+        let objects = Arc::new(Mutex::new(HashMap::new()));
+        let server_objects = objects.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap().to_string();
+                let path = parts.next().unwrap().to_string();
+                let mut content_length = 0;
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim_end().is_empty() {
+                        break;
+                    }
+                    if let Some(length) = line.strip_prefix("Content-Length: ") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let mut objects = server_objects.lock().unwrap();
+                let response = match method.as_str() {
+                    "PUT" => {
+                        objects.insert(path, body);
+                        b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n".to_vec()
+                    }
+                    _ => match objects.get(&path) {
+                        // The chunked encoding is used by some servers.
+                        Some(object) => [
+                            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec(),
+                            format!("{:x}\r\n", object.len()).into_bytes(),
+                            object.clone(),
+                            b"\r\n0\r\n\r\n".to_vec(),
+                        ]
+                        .concat(),
+                        None => {
+                            b"HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\n\r\nnot found".to_vec()
+                        }
+                    },
+                };
+                stream.write_all(&response).unwrap();
+            }
+        });
+        (url, objects)
+    }
+
+    #[test]
+    fn http_storage_gets_what_it_puts() {
+        let (url, objects) = spawn_server();
+        let storage = HttpStorage::new(&url).unwrap();
+
+        assert_eq!(storage.get("key").unwrap(), None);
+        storage.put("key", b"artifact").unwrap();
+        assert!(objects.lock().unwrap().contains_key("/cache/key"));
+        assert_eq!(storage.get("key").unwrap(), Some(b"artifact".to_vec()));
+    }
+
+    #[test]
+    fn responses_larger_than_the_maximum_are_rejected() {
+        let response = |headers: &str, body: &str| {
+            let response = format!("HTTP/1.1 200 OK\r\n{}\r\n{}", headers, body);
+            read_response(response.as_bytes(), 8)
+        };
+
+        assert_eq!(
+            response("Content-Length: 8\r\n", "artifact").unwrap(),
+            (200, b"artifact".to_vec())
+        );
+        assert!(response("Content-Length: 9\r\n", "artifacts").is_err());
+        assert!(response("Content-Length: 18446744073709551615\r\n", "").is_err());
+        assert!(response("Content-Length: 8\r\n", "short").is_err());
+
+        let chunked = "Transfer-Encoding: chunked\r\n";
+        assert_eq!(
+            response(chunked, "4\r\narti\r\n4\r\nfact\r\n0\r\n\r\n").unwrap(),
+            (200, b"artifact".to_vec())
+        );
+        assert!(response(chunked, "4\r\narti\r\n5\r\nfacts\r\n0\r\n\r\n").is_err());
+        assert!(response(chunked, "4\r\narti\r\nffffffffffffffff\r\n").is_err());
+
+        assert_eq!(
+            response("", "artifact").unwrap(),
+            (200, b"artifact".to_vec())
+        );
+        assert!(response("", "artifacts").is_err());
+    }
+
+    #[test]
+    fn http_storage_urls() {
+        assert!(HttpStorage::new("https://example.com").is_err());
+        assert!(HttpStorage::new("http:///path").is_err());
+        let storage = HttpStorage::new("http://example.com").unwrap();
+        assert_eq!(storage.address, "example.com:80");
+        assert_eq!(storage.path, "");
+    }
+}
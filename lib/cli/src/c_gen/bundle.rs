@@ -0,0 +1,277 @@
+//! Generate the C sources bundling several modules, and the WASI
+//! configuration to run them with, into an executable.
+
+use super::{generate_c, CStatement, CType};
+
+/// What is bundled in an executable, besides the compiled modules.
+#[derive(Debug, Default)]
+pub struct Bundle {
+    /// The names of the modules, with the prefix of their symbols.
+    pub modules: Vec<(String, String)>,
+    /// The module run when the executable isn't invoked by the name of
+    /// one of its modules.
+    pub entrypoint: String,
+    /// The arguments passed to the module before the arguments of the
+    /// executable.
+    pub args: Vec<String>,
+    /// The environment variables of the module.
+    pub env_vars: Vec<(String, String)>,
+    /// The host directories preopened when the executable runs.
+    pub pre_opened_directories: Vec<String>,
+    /// The host directories mapped when the executable runs, by alias.
+    pub mapped_dirs: Vec<(String, String)>,
+    /// The directory images mapped read-only when the executable runs,
+    /// by alias.
+    pub embedded_dirs: Vec<(String, Vec<u8>)>,
+}
+
+/// Generate the source of a module, wrapping the header generated for
+/// it by [`generate_header_file`] so that its names don't collide with
+/// the ones of the other modules.
+///
+/// It defines `wasmer_module_<prefix>_new`, with the signature of
+/// `wasmer_object_file_engine_new`.
+///
+/// [`generate_header_file`]: super::object_file_header::generate_header_file
+pub fn generate_module_source(prefix: &str, header_file_name: &str) -> String {
+    let mut source = String::from("#include \"wasmer_wasm.h\"\n\n");
+    for name in &[
+        "module_bytes_len",
+        "function_pointers",
+        "function_trampolines",
+        "dynamic_function_trampoline_pointers",
+        "generate_serialized_data",
+    ] {
+        source.push_str(&format!(
+            "#define {} wasmer_module_{}_{}\n",
+            name, prefix, name
+        ));
+    }
+    source.push_str(&format!(
+        "#define wasmer_object_file_engine_new wasmer_module_{}_new\n\n#include {}\n",
+        prefix,
+        c_string(header_file_name)
+    ));
+    source
+}
+
+/// Generate the header of the executable's `main`, describing the
+/// bundled modules and their configuration.
+pub fn generate_bundle_header(bundle: &Bundle) -> String {
+    let mut c_statements = vec![];
+
+    c_statements.push(CStatement::LiteralConstant {
+        value: "#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n".to_string(),
+    });
+    for (_, prefix) in &bundle.modules {
+        c_statements.push(CStatement::Declaration {
+            name: format!("wasmer_module_{}_new", prefix),
+            is_extern: false,
+            is_const: false,
+            ctype: CType::Function {
+                arguments: vec![
+                    CType::PointerTo {
+                        is_const: false,
+                        inner: Box::new(CType::TypeDef("wasm_store_t".to_string())),
+                    },
+                    CType::PointerTo {
+                        is_const: true,
+                        inner: Box::new(CType::I8),
+                    },
+                ],
+                return_value: Some(Box::new(CType::PointerTo {
+                    is_const: false,
+                    inner: Box::new(CType::TypeDef("wasm_module_t".to_string())),
+                })),
+            },
+            definition: None,
+        });
+    }
+    c_statements.push(CStatement::LiteralConstant {
+        value: "\n#ifdef __cplusplus\n}\n#endif\n\n".to_string(),
+    });
+
+    let mut modules = bundle
+        .modules
+        .iter()
+        .map(|(name, prefix)| CStatement::LiteralConstant {
+            value: format!("{{{}, wasmer_module_{}_new}}", c_string(name), prefix),
+        })
+        .collect::<Vec<_>>();
+    modules.push(CStatement::LiteralConstant {
+        value: "{NULL, NULL}".to_string(),
+    });
+    c_statements.push(CStatement::Declaration {
+        name: "wasmer_bundle_modules".to_string(),
+        is_extern: false,
+        is_const: true,
+        ctype: CType::Array {
+            inner: Box::new(CType::TypeDef("wasmer_bundle_module_t".to_string())),
+        },
+        definition: Some(Box::new(CStatement::LiteralArray { items: modules })),
+    });
+    c_statements.push(CStatement::Declaration {
+        name: "wasmer_bundle_entrypoint".to_string(),
+        is_extern: false,
+        is_const: true,
+        ctype: CType::PointerTo {
+            is_const: false,
+            inner: Box::new(CType::I8),
+        },
+        definition: Some(Box::new(CStatement::LiteralConstant {
+            value: c_string(&bundle.entrypoint),
+        })),
+    });
+
+    // Pairs are flattened, and all the lists end with `NULL`.
+    c_statements.push(string_list("wasmer_bundle_args", bundle.args.iter()));
+    c_statements.push(string_list(
+        "wasmer_bundle_env_vars",
+        bundle
+            .env_vars
+            .iter()
+            .flat_map(|(key, value)| vec![key, value]),
+    ));
+    c_statements.push(string_list(
+        "wasmer_bundle_pre_opened_directories",
+        bundle.pre_opened_directories.iter(),
+    ));
+    c_statements.push(string_list(
+        "wasmer_bundle_mapped_dirs",
+        bundle
+            .mapped_dirs
+            .iter()
+            .flat_map(|(alias, dir)| vec![alias, dir]),
+    ));
+
+    let mut images = vec![];
+    for (index, (alias, image)) in bundle.embedded_dirs.iter().enumerate() {
+        let name = format!("wasmer_bundle_image_{}", index);
+        c_statements.push(CStatement::Declaration {
+            name: name.clone(),
+            is_extern: false,
+            is_const: true,
+            ctype: CType::Array {
+                inner: Box::new(CType::U8),
+            },
+            definition: Some(Box::new(CStatement::LiteralConstant {
+                value: byte_array(image),
+            })),
+        });
+        images.push(CStatement::LiteralConstant {
+            value: format!("{{{}, {}, sizeof({})}}", c_string(alias), name, name),
+        });
+    }
+    images.push(CStatement::LiteralConstant {
+        value: "{NULL, NULL, 0}".to_string(),
+    });
+    c_statements.push(CStatement::Declaration {
+        name: "wasmer_bundle_embedded_dirs".to_string(),
+        is_extern: false,
+        is_const: true,
+        ctype: CType::Array {
+            inner: Box::new(CType::TypeDef("wasmer_bundle_image_t".to_string())),
+        },
+        definition: Some(Box::new(CStatement::LiteralArray { items: images })),
+    });
+
+    generate_c(&c_statements)
+}
+
+/// A `NULL`-terminated array of strings.
+fn string_list<'a>(name: &str, strings: impl Iterator<Item = &'a String>) -> CStatement {
+    let mut items = strings
+        .map(|string| CStatement::LiteralConstant {
+            value: c_string(string),
+        })
+        .collect::<Vec<_>>();
+    items.push(CStatement::LiteralConstant {
+        value: "NULL".to_string(),
+    });
+    CStatement::Declaration {
+        name: name.to_string(),
+        is_extern: false,
+        is_const: true,
+        ctype: CType::Array {
+            inner: Box::new(CType::PointerTo {
+                is_const: false,
+                inner: Box::new(CType::I8),
+            }),
+        },
+        definition: Some(Box::new(CStatement::LiteralArray { items })),
+    }
+}
+
+/// A C string literal, escaping everything but printable ASCII.
+fn c_string(string: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in string.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            // `?` is escaped too, not to form trigraphs.
+            b' '..=b'~' if byte != b'?' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// The initializer of an array of bytes, on lines of 16 bytes.
+fn byte_array(bytes: &[u8]) -> String {
+    let mut array = String::from("{\n");
+    for line in bytes.chunks(16) {
+        array.push('\t');
+        for byte in line {
+            array.push_str(&format!("{:#04x},", byte));
+        }
+        array.push('\n');
+    }
+    array.push('}');
+    array
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(c_string("plain text"), "\"plain text\"");
+        assert_eq!(
+            c_string("a \"quote\"\\ and?\n"),
+            "\"a \\\"quote\\\"\\\\ and\\077\\012\""
+        );
+        assert_eq!(c_string("é"), "\"\\303\\251\"");
+    }
+
+    #[test]
+    fn generates_bundle_header() {
+        let bundle = Bundle {
+            modules: vec![
+                ("cat".to_string(), "cat".to_string()),
+                ("my-ls".to_string(), "my_ls".to_string()),
+            ],
+            entrypoint: "cat".to_string(),
+            env_vars: vec![("KEY".to_string(), "VALUE".to_string())],
+            embedded_dirs: vec![("/etc".to_string(), vec![0, 255])],
+            ..Default::default()
+        };
+        let header = generate_bundle_header(&bundle);
+
+        assert!(
+            header.contains("wasm_module_t* wasmer_module_my_ls_new(wasm_store_t*, const char*);")
+        );
+        assert!(header.contains("\t{\"my-ls\", wasmer_module_my_ls_new},\n\t{NULL, NULL},\n"));
+        assert!(header.contains("const char* wasmer_bundle_entrypoint = \"cat\";"));
+        assert!(header.contains(
+            "const char* wasmer_bundle_env_vars[] = {\n\t\"KEY\",\n\t\"VALUE\",\n\tNULL,\n}"
+        ));
+        assert!(header.contains("const char* wasmer_bundle_args[] = {\n\tNULL,\n}"));
+        assert!(header.contains("const unsigned char wasmer_bundle_image_0[] = {\n\t0x00,0xff,\n}"));
+        assert!(header.contains("{\"/etc\", wasmer_bundle_image_0, sizeof(wasmer_bundle_image_0)}"));
+    }
+}
@@ -1,6 +1,7 @@
 //! A convenient little abstraction for building up C expressions and generating
 //! simple C code.
 
+pub mod bundle;
 pub mod object_file_header;
 
 /// An identifier in C.
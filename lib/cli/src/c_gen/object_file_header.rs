@@ -5,6 +5,9 @@ use wasmer_compiler::{Symbol, SymbolRegistry};
 use wasmer_vm::ModuleInfo;
 
 /// Helper functions to simplify the usage of the object file engine.
+///
+/// `WASMER_METADATA_SYMBOL` stands for the name of the metadata symbol of
+/// the module.
 const HELPER_FUNCTIONS: &str = r#"
 wasm_byte_vec_t generate_serialized_data() {
         // We need to pass all the bytes as one big buffer so we have to do all this logic to memcpy
@@ -12,7 +15,7 @@ wasm_byte_vec_t generate_serialized_data() {
         //
         // We should provide a `deseralize_vectored` function to avoid requiring this extra work.
 
-        char* byte_ptr = (char*)&WASMER_METADATA[0];
+        char* byte_ptr = (char*)&WASMER_METADATA_SYMBOL[0];
 
         size_t num_function_pointers
                 = sizeof(function_pointers) / sizeof(void*);
@@ -74,6 +77,7 @@ pub fn generate_header_file(
     symbol_registry: &dyn SymbolRegistry,
     metadata_length: usize,
 ) -> String {
+    let metadata_symbol = symbol_registry.symbol_to_name(Symbol::Metadata);
     let mut c_statements = vec![];
     c_statements.push(CStatement::LiteralConstant {
         value: "#include <stdlib.h>\n#include <string.h>\n\n".to_string(),
@@ -91,7 +95,7 @@ pub fn generate_header_file(
         })),
     });
     c_statements.push(CStatement::Declaration {
-        name: "WASMER_METADATA".to_string(),
+        name: metadata_symbol.clone(),
         is_extern: true,
         is_const: true,
         ctype: CType::Array {
@@ -285,7 +289,7 @@ pub fn generate_header_file(
     }
 
     c_statements.push(CStatement::LiteralConstant {
-        value: HELPER_FUNCTIONS.to_string(),
+        value: HELPER_FUNCTIONS.replace("WASMER_METADATA_SYMBOL", &metadata_symbol),
     });
 
     c_statements.push(CStatement::LiteralConstant {
@@ -1,7 +1,19 @@
 use crate::common::get_cache_dir;
+#[cfg(feature = "cache")]
+use crate::common::get_cache_max_size;
+#[cfg(feature = "cache")]
+use crate::utils::{parse_age, parse_size};
 use anyhow::{Context, Result};
+#[cfg(feature = "cache")]
+use bytesize::ByteSize;
 use clap::Clap;
+#[cfg(feature = "cache")]
+use std::collections::BTreeMap;
 use std::fs;
+#[cfg(feature = "cache")]
+use std::time::Duration;
+#[cfg(feature = "cache")]
+use wasmer_cache::{CacheStats, FileSystemCache};
 
 #[derive(Debug, Clap)]
 /// The options for the `wasmer cache` subcommand
@@ -13,6 +25,30 @@ pub enum Cache {
     /// Display the location of the cache
     #[clap(name = "dir")]
     Dir,
+
+    /// Display the number and size of the cached artifacts
+    #[cfg(feature = "cache")]
+    #[clap(name = "stats")]
+    Stats,
+
+    /// Remove the least recently used artifacts from the cache
+    #[cfg(feature = "cache")]
+    #[clap(name = "prune")]
+    Prune(Prune),
+}
+
+#[cfg(feature = "cache")]
+#[derive(Debug, Clap)]
+/// The options for the `wasmer cache prune` subcommand
+pub struct Prune {
+    /// Remove artifacts until the cache is at most this size (eg. `2GiB`).
+    /// Defaults to the `WASMER_CACHE_MAX_SIZE` environment variable
+    #[clap(long = "max-size", name = "SIZE", parse(try_from_str = parse_size))]
+    max_size: Option<u64>,
+
+    /// Remove the artifacts unused for this long (eg. `30d`, `12h`)
+    #[clap(long = "max-age", name = "AGE", parse(try_from_str = parse_age))]
+    max_age: Option<Duration>,
 }
 
 impl Cache {
@@ -25,6 +61,14 @@ impl Cache {
             Cache::Dir => {
                 self.dir()?;
             }
+            #[cfg(feature = "cache")]
+            Cache::Stats => {
+                self.stats().context("failed to read wasmer cache.")?;
+            }
+            #[cfg(feature = "cache")]
+            Cache::Prune(prune) => {
+                prune.execute().context("failed to prune wasmer cache.")?;
+            }
         }
         Ok(())
     }
@@ -41,4 +85,88 @@ impl Cache {
         println!("{}", get_cache_dir().to_string_lossy());
         Ok(())
     }
+    #[cfg(feature = "cache")]
+    fn stats(&self) -> Result<()> {
+        let cache_dir = get_cache_dir();
+        let cache = FileSystemCache::new(&cache_dir)?;
+        let entries = cache.entries()?;
+        println!("Directory: {}", cache_dir.to_string_lossy());
+        // The artifacts are stored in a directory per compiler.
+        let mut subdirs: BTreeMap<String, CacheStats> = BTreeMap::new();
+        let mut total = CacheStats::default();
+        for entry in &entries {
+            let subdir = entry
+                .path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(&cache_dir).ok())
+                .map(|subdir| subdir.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let stats = subdirs.entry(subdir).or_default();
+            stats.entries += 1;
+            stats.size += entry.size;
+            total.entries += 1;
+            total.size += entry.size;
+        }
+        for (subdir, stats) in subdirs {
+            let subdir = if subdir.is_empty() { "." } else { &subdir };
+            println!("  {}: {}", subdir, format_stats(stats));
+        }
+        println!("Total: {}", format_stats(total));
+        if let Some(max_size) = get_cache_max_size()? {
+            println!("Maximum size: {}", ByteSize(max_size));
+        }
+        if let (Some(oldest), Some(newest)) = (entries.first(), entries.last()) {
+            let age = |entry: &wasmer_cache::CacheEntry| {
+                entry.last_used.elapsed().unwrap_or_default().as_secs()
+            };
+            println!(
+                "Least recently used: {} ago, most recently used: {} ago",
+                format_age(age(oldest)),
+                format_age(age(newest))
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cache")]
+impl Prune {
+    fn execute(&self) -> Result<()> {
+        let max_size = match self.max_size {
+            Some(max_size) => Some(max_size),
+            None => get_cache_max_size()?,
+        };
+        if max_size.is_none() && self.max_age.is_none() {
+            bail!("`--max-size` or `--max-age` is required, unless `WASMER_CACHE_MAX_SIZE` is set");
+        }
+        let cache = FileSystemCache::new(get_cache_dir())?;
+        let removed = cache.prune(max_size, self.max_age)?;
+        let left = cache.stats()?;
+        eprintln!(
+            "Removed {}, {} left.",
+            format_stats(removed),
+            format_stats(left)
+        );
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cache")]
+fn format_stats(stats: CacheStats) -> String {
+    format!(
+        "{} artifact{} ({})",
+        stats.entries,
+        if stats.entries == 1 { "" } else { "s" },
+        ByteSize(stats.size)
+    )
+}
+
+#[cfg(feature = "cache")]
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{}s", s),
+        s if s < 60 * 60 => format!("{}m", s / 60),
+        s if s < 24 * 60 * 60 => format!("{}h", s / (60 * 60)),
+        s => format!("{}d", s / (24 * 60 * 60)),
+    }
 }
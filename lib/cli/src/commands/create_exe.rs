@@ -1,13 +1,21 @@
 //! Create a standalone native executable for a given Wasm file.
 
+use crate::c_gen::bundle::Bundle;
 use crate::store::{CompilerOptions, EngineType};
+use crate::utils::parse_envvar;
+#[cfg(feature = "wasi")]
+use crate::utils::{parse_mapdir, DirMapping};
 use anyhow::{Context, Result};
 use clap::Clap;
+use object::Object;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use wasmer::*;
+use wasmer_compiler::BinaryFormat;
+#[cfg(feature = "wasi")]
+use wasmer_wasi::pack_dir_image;
 
 const WASMER_MAIN_C_SOURCE: &[u8] = include_bytes!("wasmer_create_exe_main.c");
 
@@ -36,6 +44,60 @@ pub struct CreateExe {
     /// This is useful for fixing linker errors that may occur on some systems.
     #[clap(short = 'l', multiple = true)]
     libraries: Vec<String>,
+
+    /// Directory of prebuilt libwasmer for other targets, laid out as
+    /// `<DIR>/<TARGET>/lib/libwasmer.a` like the Wasmer release archives,
+    /// with the headers in `<DIR>/<TARGET>/include`
+    #[clap(long = "libwasmer-dir", parse(from_os_str))]
+    libwasmer_dir: Option<PathBuf>,
+
+    /// The C compiler compiling and linking the executable,
+    /// `cc` by default or `clang` when cross-compiling
+    #[clap(long = "cc", parse(from_os_str))]
+    c_compiler: Option<PathBuf>,
+
+    /// The sysroot of the target, passed to the C compiler
+    #[clap(long = "sysroot", parse(from_os_str))]
+    sysroot: Option<PathBuf>,
+
+    /// Bundle another module in the executable, named `NAME`.
+    /// The input file is bundled as well, named after its file stem
+    #[clap(long = "module", name = "NAME=MODULE_FILE", multiple = true, number_of_values = 1, parse(try_from_str = parse_named_module))]
+    modules: Vec<(String, PathBuf)>,
+
+    /// The module to run when the executable isn't invoked by the name of
+    /// one of its modules, the input file by default
+    #[clap(long = "entrypoint", name = "MODULE_NAME")]
+    entrypoint: Option<String>,
+
+    /// Pass an argument to the module, before the arguments of the executable
+    #[clap(
+        long = "arg",
+        name = "ARG",
+        multiple = true,
+        number_of_values = 1,
+        allow_hyphen_values = true
+    )]
+    args: Vec<String>,
+
+    /// Set an environment variable for the module
+    #[clap(long = "env", name = "KEY=VALUE", multiple = true, number_of_values = 1, parse(try_from_str = parse_envvar))]
+    env_vars: Vec<(String, String)>,
+
+    /// Preopen a directory of the host running the executable
+    #[clap(long = "dir", name = "DIR", multiple = true, number_of_values = 1)]
+    pre_opened_directories: Vec<String>,
+
+    /// Map a directory of the host running the executable to a different
+    /// location for the module
+    #[clap(long = "mapdir", name = "GUEST_DIR:HOST_DIR", multiple = true, number_of_values = 1, parse(try_from_str = parse_runtime_mapdir))]
+    mapped_dirs: Vec<(String, String)>,
+
+    /// Embed a directory in the executable, mapped read-only for the module
+    /// (needs Wasmer to be built with the `wasi` feature)
+    #[cfg(feature = "wasi")]
+    #[clap(long = "embed-dir", name = "GUEST_DIR:DIR", multiple = true, number_of_values = 1, parse(try_from_str = parse_mapdir))]
+    embedded_dirs: Vec<DirMapping>,
 }
 
 impl CreateExe {
@@ -57,45 +119,75 @@ impl CreateExe {
             })
             .unwrap_or_default();
         let engine_type = EngineType::ObjectFile;
-        let (store, compiler_type) = self
-            .compiler
-            .get_store_for_target_and_engine(target.clone(), engine_type)?;
+        let toolchain = self.toolchain(target.triple())?;
 
-        println!("Engine: {}", engine_type.to_string());
-        println!("Compiler: {}", compiler_type.to_string());
-        println!("Target: {}", target.triple());
+        let mut modules = vec![(module_name(&self.path)?, self.path.clone())];
+        modules.extend(self.modules.iter().cloned());
+        let bundle = self.bundle(&modules)?;
 
         let working_dir = tempfile::tempdir()?;
         let starting_cd = env::current_dir()?;
         let output_path = starting_cd.join(&self.output);
         env::set_current_dir(&working_dir)?;
 
-        #[cfg(not(windows))]
-        let wasm_object_path = PathBuf::from("wasm.o");
-        #[cfg(windows)]
-        let wasm_object_path = PathBuf::from("wasm.obj");
-
-        let wasm_module_path = starting_cd.join(&self.path);
-
-        let module =
-            Module::from_file(&store, &wasm_module_path).context("failed to compile Wasm")?;
-        let _ = module.serialize_to_file(&wasm_object_path)?;
-
-        let artifact: &wasmer_engine_object_file::ObjectFileArtifact =
-            module.artifact().as_ref().downcast_ref().context(
-                "Engine type is ObjectFile but could not downcast artifact into ObjectFileArtifact",
+        let mut object_paths = vec![];
+        for (index, ((name, path), (_, prefix))) in modules.iter().zip(&bundle.modules).enumerate()
+        {
+            let (store, compiler_type) = self
+                .compiler
+                .get_object_file_store_for_target(target.clone(), prefix.clone())?;
+            if index == 0 {
+                println!("Engine: {}", engine_type.to_string());
+                println!("Compiler: {}", compiler_type.to_string());
+                println!("Target: {}", target.triple());
+            }
+
+            let wasm_module_path = starting_cd.join(path);
+            let module = Module::from_file(&store, &wasm_module_path)
+                .with_context(|| format!("failed to compile Wasm module `{}`", name))?;
+            let wasm_object_path = PathBuf::from(format!(
+                "wasm_{}.{}",
+                prefix,
+                object_extension(target.triple())
+            ));
+            let _ = module.serialize_to_file(&wasm_object_path)?;
+            check_object_target(&fs::read(&wasm_object_path)?, target.triple())
+                .with_context(|| format!("the module `{}` wasn't compiled for the target", name))?;
+
+            let artifact: &wasmer_engine_object_file::ObjectFileArtifact =
+                module.artifact().as_ref().downcast_ref().context(
+                    "Engine type is ObjectFile but could not downcast artifact into ObjectFileArtifact",
+                )?;
+            let symbol_registry = artifact.symbol_registry();
+            let metadata_length = artifact.metadata_length();
+            let module_info = module.info();
+            let header_file_src = crate::c_gen::object_file_header::generate_header_file(
+                module_info,
+                symbol_registry,
+                metadata_length,
+            );
+            let header_file_name = format!("wasm_{}.h", prefix);
+            fs::write(&header_file_name, header_file_src)?;
+
+            let module_src_path = PathBuf::from(format!("wasmer_module_{}.c", prefix));
+            let module_obj_path = PathBuf::from(format!(
+                "wasmer_module_{}.{}",
+                prefix,
+                object_extension(target.triple())
+            ));
+            fs::write(
+                &module_src_path,
+                crate::c_gen::bundle::generate_module_source(prefix, &header_file_name),
             )?;
-        let symbol_registry = artifact.symbol_registry();
-        let metadata_length = artifact.metadata_length();
-        let module_info = module.info();
-        let header_file_src = crate::c_gen::object_file_header::generate_header_file(
-            module_info,
-            symbol_registry,
-            metadata_length,
-        );
+            run_c_compile(&toolchain, &module_src_path, &module_obj_path)
+                .with_context(|| format!("Failed to compile the C code of module `{}`", name))?;
 
-        generate_header(header_file_src.as_bytes())?;
-        self.compile_c(wasm_object_path, output_path)?;
+            object_paths.push(wasm_object_path);
+            object_paths.push(module_obj_path);
+        }
+
+        generate_header(crate::c_gen::bundle::generate_bundle_header(&bundle).as_bytes())?;
+        self.compile_c(&toolchain, object_paths, output_path)?;
 
         eprintln!(
             "✔ Native executable compiled successfully to `{}`.",
@@ -105,15 +197,134 @@ impl CreateExe {
         Ok(())
     }
 
-    fn compile_c(&self, wasm_object_path: PathBuf, output_path: PathBuf) -> anyhow::Result<()> {
+    /// Describes what's bundled in the executable besides the modules,
+    /// packing the embedded directories.
+    fn bundle(&self, modules: &[(String, PathBuf)]) -> Result<Bundle> {
+        let mut bundled_modules: Vec<(String, String)> = vec![];
+        for (name, _) in modules {
+            // The prefix is a C identifier.
+            let prefix = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>();
+            if let Some((other, _)) = bundled_modules
+                .iter()
+                .find(|(other, other_prefix)| other == name || *other_prefix == prefix)
+            {
+                bail!(
+                    "the modules `{}` and `{}` can't be bundled together, rename one of them",
+                    other,
+                    name
+                );
+            }
+            bundled_modules.push((name.clone(), prefix));
+        }
+
+        let entrypoint = self
+            .entrypoint
+            .clone()
+            .unwrap_or_else(|| modules[0].0.clone());
+        if !modules.iter().any(|(name, _)| *name == entrypoint) {
+            bail!("the entrypoint `{}` is not a bundled module", entrypoint);
+        }
+
+        #[cfg(feature = "wasi")]
+        let embedded_dirs = self
+            .embedded_dirs
+            .iter()
+            .map(|mapping| {
+                let alias = mapping.alias.clone().unwrap_or_default();
+                let image = pack_dir_image(&mapping.host_dir).with_context(|| {
+                    format!(
+                        "failed to embed the directory `{}`",
+                        mapping.host_dir.display()
+                    )
+                })?;
+                Ok((alias, image))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        #[cfg(not(feature = "wasi"))]
+        let embedded_dirs = Vec::new();
+
+        Ok(Bundle {
+            modules: bundled_modules,
+            entrypoint,
+            args: self.args.clone(),
+            env_vars: self.env_vars.clone(),
+            pre_opened_directories: self.pre_opened_directories.clone(),
+            mapped_dirs: self.mapped_dirs.clone(),
+            embedded_dirs,
+        })
+    }
+
+    /// The C toolchain building the executable for `target`.
+    fn toolchain(&self, target: &Triple) -> Result<CToolchain> {
+        let is_cross_compiling = *target != Triple::host();
+        let (libwasmer_path, include_dir) = match &self.libwasmer_dir {
+            Some(dir) => {
+                let target_dir = dir.join(target.to_string());
+                let libwasmer_path = [target_dir.join("lib"), target_dir.clone()]
+                    .iter()
+                    .map(|dir| dir.join(libwasmer_file_name(target)))
+                    .find(|path| path.exists())
+                    .with_context(|| {
+                        format!("No libwasmer for `{}` in `{}`", target, dir.display())
+                    })?;
+                let include_dir = target_dir.join("include");
+                let include_dir = if include_dir.is_dir() {
+                    include_dir
+                } else {
+                    get_wasmer_include_directory()?
+                };
+                (libwasmer_path, include_dir)
+            }
+            None => (get_libwasmer_path(target)?, get_wasmer_include_directory()?),
+        };
+
+        #[cfg(not(windows))]
+        let (c_compiler, linker) = if is_cross_compiling {
+            ("clang", "clang")
+        } else {
+            ("cc", "cc")
+        };
+        // We must use a C++ compiler on Windows because wasm.h uses `static_assert`
+        // which isn't available in `clang` on Windows.
+        #[cfg(windows)]
+        let (c_compiler, linker) = ("clang++", "clang");
+
+        let toolchain = CToolchain {
+            target: target.clone(),
+            is_cross_compiling,
+            c_compiler: self
+                .c_compiler
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(c_compiler)),
+            linker: self
+                .c_compiler
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(linker)),
+            sysroot: self.sysroot.clone(),
+            include_dir,
+            libwasmer_path,
+        };
+        toolchain.check_libwasmer()?;
+        Ok(toolchain)
+    }
+
+    fn compile_c(
+        &self,
+        toolchain: &CToolchain,
+        mut object_paths: Vec<PathBuf>,
+        output_path: PathBuf,
+    ) -> anyhow::Result<()> {
         use std::io::Write;
 
         // write C src to disk
         let c_src_path = Path::new("wasmer_main.c");
-        #[cfg(not(windows))]
-        let c_src_obj = PathBuf::from("wasmer_main.o");
-        #[cfg(windows)]
-        let c_src_obj = PathBuf::from("wasmer_main.obj");
+        let c_src_obj = PathBuf::from(format!(
+            "wasmer_main.{}",
+            object_extension(&toolchain.target)
+        ));
 
         {
             let mut c_src_file = fs::OpenOptions::new()
@@ -123,14 +334,15 @@ impl CreateExe {
                 .context("Failed to open C source code file")?;
             c_src_file.write_all(WASMER_MAIN_C_SOURCE)?;
         }
-        run_c_compile(&c_src_path, &c_src_obj, self.target_triple.clone())
+        run_c_compile(toolchain, &c_src_path, &c_src_obj)
             .context("Failed to compile C source code")?;
+        object_paths.insert(0, c_src_obj);
         LinkCode {
-            object_paths: vec![c_src_obj, wasm_object_path],
-            output_path,
+            toolchain,
+            optimization_flag: String::from("-O2"),
+            object_paths,
             additional_libraries: self.libraries.clone(),
-            target: self.target_triple.clone(),
-            ..Default::default()
+            output_path,
         }
         .run()
         .context("Failed to link objects together")?;
@@ -139,8 +351,51 @@ impl CreateExe {
     }
 }
 
+/// The name of the module of a file: its file stem.
+fn module_name(path: &Path) -> Result<String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(str::to_string)
+        .with_context(|| format!("can't name a module after `{}`", path.display()))
+}
+
+/// Parses a named module, like `NAME=FILE`.
+fn parse_named_module(entry: &str) -> Result<(String, PathBuf)> {
+    match entry.find('=') {
+        Some(position) if position > 0 && position < entry.len() - 1 => Ok((
+            entry[..position].to_string(),
+            PathBuf::from(&entry[position + 1..]),
+        )),
+        _ => bail!(
+            "Modules must be of the form `<name>=<file>`; found `{}`",
+            entry
+        ),
+    }
+}
+
+/// Parses a mapdir of the host running the executable, like
+/// `GUEST_DIR:HOST_DIR`: unlike `--mapdir` for `wasmer run`, the host
+/// directory doesn't have to exist when creating the executable.
+fn parse_runtime_mapdir(entry: &str) -> Result<(String, String)> {
+    // We try first splitting by `::`, and then by the first `:`
+    let position = entry
+        .find("::")
+        .map(|position| (position, 2))
+        .or_else(|| entry.find(':').map(|position| (position, 1)));
+    match position {
+        Some((position, len)) if position > 0 && position + len < entry.len() => Ok((
+            entry[..position].to_string(),
+            entry[position + len..].to_string(),
+        )),
+        _ => bail!(
+            "Directory mappings must consist of two paths separate by a `::` or `:`. Found {}",
+            entry
+        ),
+    }
+}
+
 fn generate_header(header_file_src: &[u8]) -> anyhow::Result<()> {
-    let header_file_path = Path::new("my_wasm.h");
+    let header_file_path = Path::new("wasmer_bundle.h");
     let mut header = std::fs::OpenOptions::new()
         .create(true)
         .truncate(true)
@@ -171,48 +426,152 @@ fn get_wasmer_include_directory() -> anyhow::Result<PathBuf> {
     Ok(path)
 }
 
+/// The file name of the static libwasmer of `target`.
+fn libwasmer_file_name(target: &Triple) -> &'static str {
+    // TODO: prefer headless Wasmer if/when it's a separate library.
+    match target.operating_system {
+        OperatingSystem::Windows => "wasmer.lib",
+        _ => "libwasmer.a",
+    }
+}
+
 /// path to the static libwasmer
-fn get_libwasmer_path() -> anyhow::Result<PathBuf> {
+fn get_libwasmer_path(target: &Triple) -> anyhow::Result<PathBuf> {
     let mut path = get_wasmer_dir()?;
     path.push("lib");
+    path.push(libwasmer_file_name(target));
+    Ok(path)
+}
 
-    // TODO: prefer headless Wasmer if/when it's a separate library.
-    #[cfg(not(windows))]
-    path.push("libwasmer.a");
-    #[cfg(windows)]
-    path.push("wasmer.lib");
+/// The extension of the object files of `target`.
+fn object_extension(target: &Triple) -> &'static str {
+    match target.operating_system {
+        OperatingSystem::Windows => "obj",
+        _ => "o",
+    }
+}
 
-    Ok(path)
+/// The C toolchain building the executable for its target.
+#[derive(Debug, Clone)]
+struct CToolchain {
+    /// The target of the executable.
+    target: Triple,
+    /// Whether the target differs from the host.
+    is_cross_compiling: bool,
+    /// Path to the C compiler.
+    c_compiler: PathBuf,
+    /// Path to the linker, a C compiler driver.
+    linker: PathBuf,
+    /// The sysroot of the target, if not the default one of the compiler.
+    sysroot: Option<PathBuf>,
+    /// Path to the dir containing the Wasmer headers.
+    include_dir: PathBuf,
+    /// Path to the static libwasmer library.
+    libwasmer_path: PathBuf,
+}
+
+impl CToolchain {
+    /// A command running `program`, a C compiler driver, for the target.
+    fn command(&self, program: &Path) -> Command {
+        let mut command = Command::new(program);
+        // Only clang takes the target as an argument, other compilers
+        // (like `aarch64-linux-gnu-gcc`) are built for a single target.
+        let is_clang = program
+            .file_name()
+            .map_or(false, |name| name.to_string_lossy().contains("clang"));
+        if is_clang && self.is_cross_compiling {
+            command.arg("-target").arg(self.target.to_string());
+        }
+        if let Some(sysroot) = &self.sysroot {
+            command.arg(format!("--sysroot={}", sysroot.display()));
+        }
+        command
+    }
+
+    /// Checks that the static libwasmer is built for the target, to fail
+    /// with a clearer error than the linker would.
+    fn check_libwasmer(&self) -> anyhow::Result<()> {
+        let bytes = fs::read(&self.libwasmer_path).with_context(|| {
+            format!(
+                "Failed to read libwasmer at `{}`",
+                self.libwasmer_path.display()
+            )
+        })?;
+        let archive = object::read::archive::ArchiveFile::parse(&*bytes).with_context(|| {
+            format!(
+                "`{}` is not a static library",
+                self.libwasmer_path.display()
+            )
+        })?;
+        // The first object of the archive tells what it's built for.
+        for member in archive.members() {
+            // Copied, as objects in archives aren't aligned enough to be parsed.
+            let data = match member.and_then(|member| member.data(&*bytes)) {
+                Ok(data) => data.to_vec(),
+                Err(_) => continue,
+            };
+            if object::File::parse(&*data).is_ok() {
+                return check_object_target(&data, &self.target).with_context(|| {
+                    format!(
+                        "`{}` can't be linked in an executable for `{}`, \
+                         pass a directory with a libwasmer for this target with `--libwasmer-dir`",
+                        self.libwasmer_path.display(),
+                        self.target
+                    )
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the object file `bytes` is built for `target`, when it's
+/// a target whose objects the `object` crate knows.
+fn check_object_target(bytes: &[u8], target: &Triple) -> anyhow::Result<()> {
+    let file = object::File::parse(bytes).context("Failed to parse object file")?;
+    let architecture = match target.architecture {
+        Architecture::X86_64 => Some(object::Architecture::X86_64),
+        Architecture::X86_32(_) => Some(object::Architecture::I386),
+        Architecture::Aarch64(_) => Some(object::Architecture::Aarch64),
+        Architecture::Arm(_) => Some(object::Architecture::Arm),
+        _ => None,
+    };
+    let format = match target.binary_format {
+        BinaryFormat::Elf => Some(object::BinaryFormat::Elf),
+        BinaryFormat::Macho => Some(object::BinaryFormat::MachO),
+        BinaryFormat::Coff => Some(object::BinaryFormat::Coff),
+        _ => None,
+    };
+    if architecture.map_or(false, |architecture| architecture != file.architecture())
+        || format.map_or(false, |format| format != file.format())
+    {
+        bail!(
+            "the object file is built for {:?} ({:?}), not for `{}`",
+            file.architecture(),
+            file.format(),
+            target
+        );
+    }
+    Ok(())
 }
 
 /// Compile the C code.
 fn run_c_compile(
+    toolchain: &CToolchain,
     path_to_c_src: &Path,
     output_name: &Path,
-    target: Option<Triple>,
 ) -> anyhow::Result<()> {
-    #[cfg(not(windows))]
-    let c_compiler = "cc";
-    // We must use a C++ compiler on Windows because wasm.h uses `static_assert`
-    // which isn't available in `clang` on Windows.
-    #[cfg(windows)]
-    let c_compiler = "clang++";
-
-    let mut command = Command::new(c_compiler);
-    let command = command
+    let output = toolchain
+        .command(&toolchain.c_compiler)
         .arg("-O2")
         .arg("-c")
         .arg(path_to_c_src)
         .arg("-I")
-        .arg(get_wasmer_include_directory()?);
-
-    let command = if let Some(target) = target {
-        command.arg("-target").arg(format!("{}", target))
-    } else {
-        command
-    };
-
-    let output = command.arg("-o").arg(output_name).output()?;
+        .arg(&toolchain.include_dir)
+        .arg("-o")
+        .arg(output_name)
+        .output()
+        .with_context(|| format!("Failed to run `{}`", toolchain.c_compiler.display()))?;
 
     if !output.status.success() {
         bail!(
@@ -228,9 +587,9 @@ fn run_c_compile(
 
 /// Data used to run a linking command for generated artifacts.
 #[derive(Debug)]
-struct LinkCode {
-    /// Path to the linker used to run the linking command.
-    linker_path: PathBuf,
+struct LinkCode<'a> {
+    /// The toolchain linking the executable.
+    toolchain: &'a CToolchain,
     /// String used as an optimization flag.
     optimization_flag: String,
     /// Paths of objects to link.
@@ -239,33 +598,11 @@ struct LinkCode {
     additional_libraries: Vec<String>,
     /// Path to the output target.
     output_path: PathBuf,
-    /// Path to the dir containing the static libwasmer library.
-    libwasmer_path: PathBuf,
-    /// The target to link the executable for.
-    target: Option<Triple>,
-}
-
-impl Default for LinkCode {
-    fn default() -> Self {
-        #[cfg(not(windows))]
-        let linker = "cc";
-        #[cfg(windows)]
-        let linker = "clang";
-        Self {
-            linker_path: PathBuf::from(linker),
-            optimization_flag: String::from("-O2"),
-            object_paths: vec![],
-            additional_libraries: vec![],
-            output_path: PathBuf::from("a.out"),
-            libwasmer_path: get_libwasmer_path().unwrap(),
-            target: None,
-        }
-    }
 }
 
-impl LinkCode {
+impl<'a> LinkCode<'a> {
     fn run(&self) -> anyhow::Result<()> {
-        let mut command = Command::new(&self.linker_path);
+        let mut command = self.toolchain.command(&self.toolchain.linker);
         let command = command
             .arg(&self.optimization_flag)
             .args(
@@ -275,32 +612,32 @@ impl LinkCode {
             )
             .arg(
                 &self
+                    .toolchain
                     .libwasmer_path
                     .canonicalize()
                     .context("Failed to find libwasmer")?,
             );
-        let command = if let Some(target) = &self.target {
-            command.arg("-target").arg(format!("{}", target))
-        } else {
-            command
-        };
         // Add libraries required per platform.
-        // We need userenv, sockets (Ws2_32), advapi32 for some system calls and bcrypt for random numbers.
-        #[cfg(windows)]
-        let command = command
-            .arg("-luserenv")
-            .arg("-lWs2_32")
-            .arg("-ladvapi32")
-            .arg("-lbcrypt");
-        // On unix we need dlopen-related symbols, libmath for a few things, and pthreads.
-        #[cfg(not(windows))]
-        let command = command.arg("-ldl").arg("-lm").arg("-pthread");
+        let command = match self.toolchain.target.operating_system {
+            // We need userenv, sockets (Ws2_32), advapi32 for some system calls and bcrypt for random numbers.
+            OperatingSystem::Windows => command
+                .arg("-luserenv")
+                .arg("-lWs2_32")
+                .arg("-ladvapi32")
+                .arg("-lbcrypt"),
+            // On unix we need dlopen-related symbols, libmath for a few things, and pthreads.
+            _ => command.arg("-ldl").arg("-lm").arg("-pthread"),
+        };
         let link_aganist_extra_libs = self
             .additional_libraries
             .iter()
             .map(|lib| format!("-l{}", lib));
         let command = command.args(link_aganist_extra_libs);
-        let output = command.arg("-o").arg(&self.output_path).output()?;
+        let output = command
+            .arg("-o")
+            .arg(&self.output_path)
+            .output()
+            .with_context(|| format!("Failed to run `{}`", self.toolchain.linker.display()))?;
 
         if !output.status.success() {
             bail!(
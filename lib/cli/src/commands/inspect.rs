@@ -2,73 +2,445 @@ use crate::store::StoreOptions;
 use anyhow::{Context, Result};
 use bytesize::ByteSize;
 use clap::Clap;
+use serde::Serialize;
 use std::path::PathBuf;
 use wasmer::*;
+use wasmer_types::entity::EntityRef;
+use wasmparser::{DataKind, Parser, Payload, Validator, WasmFeatures};
 
 #[derive(Debug, Clap)]
-/// The options for the `wasmer validate` subcommand
+/// The options for the `wasmer inspect` subcommand
 pub struct Inspect {
-    /// File to validate as WebAssembly
+    /// File to inspect
     #[clap(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
+    /// Print the report as JSON instead of human-readable text
+    #[clap(long = "json")]
+    json: bool,
+
     #[clap(flatten)]
     store: StoreOptions,
 }
 
+/// Everything `wasmer inspect` knows about a module.
+#[derive(Debug, Serialize)]
+struct Report {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: usize,
+    imports: Vec<ImportReport>,
+    exports: Vec<ExportReport>,
+    custom_sections: Vec<CustomSectionReport>,
+    functions: FunctionsReport,
+    data_segments: Vec<DataSegmentReport>,
+    memories: Vec<MemoryReport>,
+    tables: Vec<TableReport>,
+    start_function: Option<u32>,
+    wasi_versions: Vec<&'static str>,
+    required_features: Features,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportReport {
+    module: String,
+    name: String,
+    kind: &'static str,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportReport {
+    name: String,
+    kind: &'static str,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CustomSectionReport {
+    name: String,
+    size: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionsReport {
+    count: usize,
+    imported: usize,
+    code_size: usize,
+    local: Vec<FunctionReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionReport {
+    index: u32,
+    name: Option<String>,
+    code_size: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct DataSegmentReport {
+    index: u32,
+    kind: &'static str,
+    memory_index: Option<u32>,
+    size: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct MemoryReport {
+    index: u32,
+    imported: bool,
+    minimum: u32,
+    maximum: Option<u32>,
+    shared: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TableReport {
+    index: u32,
+    imported: bool,
+    element_type: String,
+    minimum: u32,
+    maximum: Option<u32>,
+}
+
+fn extern_kind(ty: &ExternType) -> &'static str {
+    match ty {
+        ExternType::Function(_) => "function",
+        ExternType::Global(_) => "global",
+        ExternType::Table(_) => "table",
+        ExternType::Memory(_) => "memory",
+        ExternType::Tag(_) => "tag",
+    }
+}
+
+fn extern_ty(ty: &ExternType) -> String {
+    match ty {
+        ExternType::Function(ty) => ty.to_string(),
+        ExternType::Global(ty) => ty.to_string(),
+        ExternType::Table(ty) => ty.to_string(),
+        ExternType::Memory(ty) => ty.to_string(),
+        ExternType::Tag(ty) => ty.to_string(),
+    }
+}
+
 impl Inspect {
-    /// Runs logic for the `validate` subcommand
+    /// Runs logic for the `inspect` subcommand
     pub fn execute(&self) -> Result<()> {
         self.inner_execute()
             .context(format!("failed to inspect `{}`", self.path.display()))
     }
+
     fn inner_execute(&self) -> Result<()> {
         let (store, _engine_type, _compiler_type) = self.store.get_store()?;
         let module_contents = std::fs::read(&self.path)?;
         let module = Module::new(&store, &module_contents)?;
-        println!(
-            "Type: {}",
-            if !is_wasm(&module_contents) {
-                "wat"
-            } else {
+        let report = Self::report(&module, &module_contents)?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            Self::print_report(&report);
+        }
+        Ok(())
+    }
+
+    fn report(module: &Module, module_contents: &[u8]) -> Result<Report> {
+        #[cfg(feature = "wat")]
+        let wasm_bytes = wat2wasm(module_contents)?;
+        #[cfg(not(feature = "wat"))]
+        let wasm_bytes = std::borrow::Cow::Borrowed(module_contents);
+
+        let info = module.info();
+        let mut custom_sections = Vec::new();
+        let mut local_functions = Vec::new();
+        let mut data_segments = Vec::new();
+        for payload in Parser::new(0).parse_all(&wasm_bytes) {
+            match payload? {
+                Payload::CustomSection { name, data, .. } => {
+                    custom_sections.push(CustomSectionReport {
+                        name: name.to_string(),
+                        size: data.len(),
+                    });
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let range = body.range();
+                    let index = info.num_imported_functions + local_functions.len();
+                    local_functions.push(FunctionReport {
+                        index: index as u32,
+                        name: info.function_names.get(&FunctionIndex::new(index)).cloned(),
+                        code_size: range.end - range.start,
+                    });
+                }
+                Payload::DataSection(reader) => {
+                    for (index, data) in reader.into_iter().enumerate() {
+                        let data = data?;
+                        let (kind, memory_index) = match data.kind {
+                            DataKind::Passive => ("passive", None),
+                            DataKind::Active { memory_index, .. } => ("active", Some(memory_index)),
+                        };
+                        data_segments.push(DataSegmentReport {
+                            index: index as u32,
+                            kind,
+                            memory_index,
+                            size: data.data.len(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let wasi_versions = Self::wasi_versions(module);
+        Ok(Report {
+            kind: if is_wasm(module_contents) {
                 "wasm"
+            } else {
+                "wat"
+            },
+            size: module_contents.len(),
+            imports: module
+                .imports()
+                .map(|import| ImportReport {
+                    module: import.module().to_string(),
+                    name: import.name().to_string(),
+                    kind: extern_kind(import.ty()),
+                    ty: extern_ty(import.ty()),
+                })
+                .collect(),
+            exports: module
+                .exports()
+                .map(|export| ExportReport {
+                    name: export.name().to_string(),
+                    kind: extern_kind(export.ty()),
+                    ty: extern_ty(export.ty()),
+                })
+                .collect(),
+            custom_sections,
+            functions: FunctionsReport {
+                count: info.functions.len(),
+                imported: info.num_imported_functions,
+                code_size: local_functions.iter().map(|f| f.code_size).sum(),
+                local: local_functions,
+            },
+            data_segments,
+            memories: info
+                .memories
+                .iter()
+                .map(|(index, memory)| MemoryReport {
+                    index: index.index() as u32,
+                    imported: info.is_imported_memory(index),
+                    minimum: memory.minimum.0,
+                    maximum: memory.maximum.map(|pages| pages.0),
+                    shared: memory.shared,
+                })
+                .collect(),
+            tables: info
+                .tables
+                .iter()
+                .map(|(index, table)| TableReport {
+                    index: index.index() as u32,
+                    imported: info.is_imported_table(index),
+                    element_type: table.ty.to_string(),
+                    minimum: table.minimum,
+                    maximum: table.maximum,
+                })
+                .collect(),
+            start_function: info.start_function.map(|index| index.index() as u32),
+            wasi_versions,
+            required_features: Self::required_features(&wasm_bytes),
+        })
+    }
+
+    #[cfg(feature = "wasi")]
+    fn wasi_versions(module: &Module) -> Vec<&'static str> {
+        wasmer_wasi::get_wasi_versions(module, false)
+            .map(|versions| {
+                versions
+                    .iter()
+                    .map(|version| version.get_namespace_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(feature = "wasi"))]
+    fn wasi_versions(_module: &Module) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Computes the smallest set of features the module needs, by
+    /// checking which proposals it fails to validate without.
+    fn required_features(wasm_bytes: &[u8]) -> Features {
+        let all = WasmFeatures {
+            reference_types: true,
+            multi_value: true,
+            bulk_memory: true,
+            module_linking: true,
+            simd: true,
+            threads: true,
+            tail_call: true,
+            deterministic_only: false,
+            multi_memory: true,
+            exceptions: true,
+            memory64: true,
+        };
+        let requires = |disable: fn(&mut WasmFeatures)| {
+            let mut features = all;
+            disable(&mut features);
+            Validator::new()
+                .wasm_features(features)
+                .validate_all(wasm_bytes)
+                .is_err()
+        };
+        Features {
+            threads: requires(|f| f.threads = false),
+            reference_types: requires(|f| f.reference_types = false),
+            simd: requires(|f| f.simd = false),
+            bulk_memory: requires(|f| f.bulk_memory = false),
+            multi_value: requires(|f| f.multi_value = false),
+            tail_call: requires(|f| f.tail_call = false),
+            module_linking: requires(|f| f.module_linking = false),
+            multi_memory: requires(|f| f.multi_memory = false),
+            memory64: requires(|f| f.memory64 = false),
+            exceptions: requires(|f| f.exceptions = false),
+        }
+    }
+
+    fn print_report(report: &Report) {
+        println!("Type: {}", report.kind);
+        println!("Size: {}", ByteSize(report.size as _));
+        println!("Imports:");
+        for kind in &["function", "memory", "table", "global"] {
+            println!("  {}:", Self::section_title(kind));
+            for import in report.imports.iter().filter(|i| i.kind == *kind) {
+                println!(
+                    "    \"{}\".\"{}\": {}",
+                    import.module, import.name, import.ty
+                );
             }
+        }
+        println!("Exports:");
+        for kind in &["function", "memory", "table", "global"] {
+            println!("  {}:", Self::section_title(kind));
+            for export in report.exports.iter().filter(|e| e.kind == *kind) {
+                println!("    \"{}\": {}", export.name, export.ty);
+            }
+        }
+        println!("Custom sections:");
+        for section in &report.custom_sections {
+            println!("  \"{}\": {}", section.name, ByteSize(section.size as _));
+        }
+        println!(
+            "Functions: {} ({} imported), code size {}",
+            report.functions.count,
+            report.functions.imported,
+            ByteSize(report.functions.code_size as _)
         );
-        println!("Size: {}", ByteSize(module_contents.len() as _));
-        println!("Imports:");
-        println!("  Functions:");
-        for f in module.imports().functions() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        for function in &report.functions.local {
+            match &function.name {
+                Some(name) => println!(
+                    "  {} \"{}\": {}",
+                    function.index,
+                    name,
+                    ByteSize(function.code_size as _)
+                ),
+                None => println!(
+                    "  {}: {}",
+                    function.index,
+                    ByteSize(function.code_size as _)
+                ),
+            }
         }
-        println!("  Memories:");
-        for f in module.imports().memories() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Data segments:");
+        for segment in &report.data_segments {
+            match segment.memory_index {
+                Some(memory_index) => println!(
+                    "  {} ({}, memory {}): {}",
+                    segment.index,
+                    segment.kind,
+                    memory_index,
+                    ByteSize(segment.size as _)
+                ),
+                None => println!(
+                    "  {} ({}): {}",
+                    segment.index,
+                    segment.kind,
+                    ByteSize(segment.size as _)
+                ),
+            }
         }
-        println!("  Tables:");
-        for f in module.imports().tables() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Memories:");
+        for memory in &report.memories {
+            println!(
+                "  {}{}: min {} pages, max {}{}",
+                memory.index,
+                if memory.imported { " (imported)" } else { "" },
+                memory.minimum,
+                memory
+                    .maximum
+                    .map_or_else(|| "unbounded".to_string(), |max| format!("{} pages", max)),
+                if memory.shared { ", shared" } else { "" }
+            );
         }
-        println!("  Globals:");
-        for f in module.imports().globals() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Tables:");
+        for table in &report.tables {
+            println!(
+                "  {}{}: {}, min {}, max {}",
+                table.index,
+                if table.imported { " (imported)" } else { "" },
+                table.element_type,
+                table.minimum,
+                table
+                    .maximum
+                    .map_or_else(|| "unbounded".to_string(), |max| max.to_string()),
+            );
         }
-        println!("Exports:");
-        println!("  Functions:");
-        for f in module.exports().functions() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        match report.start_function {
+            Some(index) => println!("Start function: {}", index),
+            None => println!("Start function: none"),
         }
-        println!("  Memories:");
-        for f in module.exports().memories() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        if report.wasi_versions.is_empty() {
+            println!("WASI version: none");
+        } else {
+            println!("WASI version: {}", report.wasi_versions.join(", "));
         }
-        println!("  Tables:");
-        for f in module.exports().tables() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        println!(
+            "Required features: {}",
+            Self::feature_names(&report.required_features)
+        );
+    }
+
+    fn section_title(kind: &str) -> &'static str {
+        match kind {
+            "function" => "Functions",
+            "memory" => "Memories",
+            "table" => "Tables",
+            _ => "Globals",
         }
-        println!("  Globals:");
-        for f in module.exports().globals() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+    }
+
+    fn feature_names(features: &Features) -> String {
+        let names = [
+            ("threads", features.threads),
+            ("reference-types", features.reference_types),
+            ("simd", features.simd),
+            ("bulk-memory", features.bulk_memory),
+            ("multi-value", features.multi_value),
+            ("tail-call", features.tail_call),
+            ("module-linking", features.module_linking),
+            ("multi-memory", features.multi_memory),
+            ("memory64", features.memory64),
+            ("exceptions", features.exceptions),
+        ]
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(", ")
         }
-        Ok(())
     }
 }
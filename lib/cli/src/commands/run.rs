@@ -1,4 +1,6 @@
 use crate::common::get_cache_dir;
+#[cfg(feature = "cache")]
+use crate::common::{get_cache_max_size, get_cache_remote};
 #[cfg(feature = "debug")]
 use crate::logging;
 use crate::store::{CompilerType, EngineType, StoreOptions};
@@ -7,15 +9,20 @@ use crate::warning;
 use anyhow::{anyhow, Context, Result};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 use wasmer::*;
 #[cfg(feature = "cache")]
-use wasmer_cache::{Cache, FileSystemCache, Hash};
+use wasmer_cache::{Cache, CacheKey, FileSystemCache, Hash, LayeredCache};
 
 use clap::Clap;
 
+mod limits;
+mod trace;
 #[cfg(feature = "wasi")]
 mod wasi;
 
+use limits::Limits;
+use trace::ImportTracer;
 #[cfg(feature = "wasi")]
 use wasi::Wasi;
 
@@ -49,6 +56,13 @@ pub struct Run {
     #[clap(flatten)]
     store: StoreOptions,
 
+    #[clap(flatten)]
+    limits: Limits,
+
+    /// Print the calls of the guest to its imports, such as the WASI syscalls
+    #[clap(long = "trace-imports")]
+    trace_imports: bool,
+
     // TODO: refactor WASI structure to allow shared options with Emscripten
     #[cfg(feature = "wasi")]
     #[clap(flatten)]
@@ -76,7 +90,14 @@ impl Run {
         if self.debug {
             logging::set_up_logging().unwrap();
         }
-        self.inner_execute().with_context(|| {
+        let run = self.clone();
+        let result = self.limits.with_timeout(move || run.inner_execute());
+        if let Err(error) = &result {
+            if let Some(exceeded) = self.limits.exceeded(error) {
+                exceeded.exit();
+            }
+        }
+        result.with_context(|| {
             format!(
                 "failed to run `{}`{}",
                 self.path.display(),
@@ -91,11 +112,21 @@ impl Run {
 
     fn inner_execute(&self) -> Result<()> {
         let module = self.get_module()?;
+        #[cfg_attr(not(feature = "wasi"), allow(unused_variables))]
+        let tracer = if self.trace_imports {
+            let tracer = Arc::new(ImportTracer::default());
+            module.store().set_call_tracer(Some(tracer.clone()));
+            Some(tracer)
+        } else {
+            None
+        };
         // Do we want to invoke a function?
         if let Some(ref invoke) = self.invoke {
             let imports = imports! {};
             let instance = Instance::new(&module, &imports)?;
-            let result = self.invoke_function(&instance, &invoke, &self.args)?;
+            let result = self
+                .invoke_function(&instance, &invoke, &self.args)
+                .map_err(|e| self.limits.check(&instance, e))?;
             println!(
                 "{}",
                 result
@@ -117,6 +148,11 @@ impl Run {
                 let mut emscripten_globals = EmscriptenGlobals::new(module.store(), &module)
                     .map_err(|e| anyhow!("{}", e))?;
                 let mut em_env = EmEnv::new(&emscripten_globals.data, Default::default());
+                #[cfg(all(feature = "wasi", unix))]
+                {
+                    let fs = self.wasi.emscripten_fs()?;
+                    em_env.set_syscall_policy(wasmer_emscripten::EmSandbox::new(fs));
+                }
                 let import_object =
                     generate_emscripten_env(module.store(), &mut emscripten_globals, &mut em_env);
                 let mut instance = match Instance::new(&module, &import_object) {
@@ -144,7 +180,8 @@ impl Run {
                     },
                     self.args.iter().map(|arg| arg.as_str()).collect(),
                     None, //run.em_entrypoint.clone(),
-                )?;
+                )
+                .map_err(|e| self.limits.check(&instance, e.into()))?;
                 return Ok(());
             }
         }
@@ -186,7 +223,13 @@ impl Run {
                         .unwrap_or_default();
                     return self
                         .wasi
-                        .execute(module, program_name, self.args.clone())
+                        .execute(
+                            module,
+                            program_name,
+                            self.args.clone(),
+                            &self.limits,
+                            tracer.as_deref(),
+                        )
                         .with_context(|| "WASI execution failed");
                 }
                 // not WASI
@@ -198,7 +241,9 @@ impl Run {
         let imports = imports! {};
         let instance = Instance::new(&module, &imports)?;
         let start: Function = self.try_find_function(&instance, "_start", &[])?;
-        start.call(&[])?;
+        start
+            .call(&[])
+            .map_err(|e| self.limits.check(&instance, e.into()))?;
 
         Ok(())
     }
@@ -209,7 +254,7 @@ impl Run {
         {
             if wasmer_engine_native::NativeArtifact::is_deserializable(&contents) {
                 let engine = wasmer_engine_native::Native::headless().engine();
-                let store = Store::new(&engine);
+                let store = self.limits.limit_store(Store::new(&engine));
                 let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
                 return Ok(module);
             }
@@ -218,12 +263,23 @@ impl Run {
         {
             if wasmer_engine_jit::JITArtifact::is_deserializable(&contents) {
                 let engine = wasmer_engine_jit::JIT::headless().engine();
-                let store = Store::new(&engine);
+                let store = self.limits.limit_store(Store::new(&engine));
                 let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
                 return Ok(module);
             }
         }
-        let (store, engine_type, compiler_type) = self.store.get_store()?;
+        #[cfg(feature = "compiler")]
+        let (store, engine_type, compiler_type) = self
+            .store
+            .get_store_with_middlewares(self.limits.middlewares())?;
+        #[cfg(not(feature = "compiler"))]
+        let (store, engine_type, compiler_type) = {
+            if self.limits.is_metered() {
+                bail!("`--fuel` requires a compiler to meter the module");
+            }
+            self.store.get_store()?
+        };
+        let store = self.limits.limit_store(store);
         #[cfg(feature = "cache")]
         let module_result: Result<Module> = if !self.disable_cache && contents.len() > 0x1000 {
             self.get_module_from_cache(&store, &contents, &engine_type, &compiler_type)
@@ -258,14 +314,25 @@ impl Run {
         // and the file length is greater than 4KB.
         // For files smaller than 4KB caching is not worth,
         // as it takes space and the speedup is minimal.
-        let mut cache = self.get_cache(engine_type, compiler_type)?;
-        // Try to get the hash from the provided `--cache-key`, otherwise
+        let local_cache = self.get_cache(engine_type, compiler_type)?;
+        // Share the artifacts with other hosts through the remote cache,
+        // if there is one.
+        let mut cache: Box<
+            dyn Cache<DeserializeError = DeserializeError, SerializeError = SerializeError>,
+        > = match get_cache_remote()? {
+            Some(remote_cache) => Box::new(LayeredCache::new(local_cache, remote_cache)),
+            None => Box::new(local_cache),
+        };
+        // Try to get the module hash from the provided `--cache-key`, otherwise
         // generate one from the provided file `.wasm` contents.
-        let hash = self
+        // The key also covers the engine, compiler config, target and
+        // features, so artifacts built differently are never mixed up.
+        let module_hash = self
             .cache_key
             .as_ref()
             .and_then(|key| Hash::from_str(&key).ok())
             .unwrap_or_else(|| Hash::generate(&contents));
+        let hash = CacheKey::with_module_hash(&store, module_hash).hash();
         match unsafe { cache.load(&store, hash) } {
             Ok(module) => Ok(module),
             Err(e) => {
@@ -278,8 +345,16 @@ impl Run {
                     }
                 }
                 let module = Module::new(&store, &contents)?;
-                // Store the compiled Module in cache
-                cache.store(hash, &module)?;
+                // Store the compiled Module in cache. The module is
+                // compiled already, so failing to cache it is not fatal.
+                if let Err(err) = cache.store(hash, &module) {
+                    warning!("failed to store the module in the cache: {}", err);
+                }
+                // Keep the whole cache, of every compiler, under its
+                // maximum size if there is one
+                if let Some(max_size) = get_cache_max_size()? {
+                    FileSystemCache::new(get_cache_dir())?.prune(Some(max_size), None)?;
+                }
                 Ok(module)
             }
         }
@@ -0,0 +1,403 @@
+use crate::utils::{parse_duration, parse_memory_pages, parse_size};
+use anyhow::{Error, Result};
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use std::fmt;
+use std::mem;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use wasmer::vm::{self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition};
+use wasmer::{
+    BaseTunables, Instance, MemoryType, Pages, StackConfig, StackTunables, Store, TableType,
+    Target, Tunables,
+};
+
+use clap::Clap;
+
+/// The stack size of the thread running the guest when `--timeout` is set.
+///
+/// It matches the usual size of the main thread stack, so deep guest
+/// recursion behaves the same with and without a timeout.
+const GUEST_THREAD_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clap, Clone, Default)]
+/// Resource limits for the guest
+pub struct Limits {
+    /// Maximum linear memory of the guest, in Wasm pages or with a unit (eg. `64MiB`)
+    #[clap(long = "max-memory", name = "SIZE", parse(try_from_str = parse_memory_pages))]
+    max_memory: Option<Pages>,
+
+    /// Maximum wall-clock time the guest can run, in seconds or with a unit (eg. `500ms`)
+    #[clap(long = "timeout", name = "DURATION", parse(try_from_str = parse_duration))]
+    timeout: Option<Duration>,
+
+    /// Maximum number of Wasm operators the guest can execute
+    #[clap(long = "fuel", name = "OPERATORS")]
+    fuel: Option<u64>,
+
+    /// Maximum stack the guest can use, in bytes or with a unit (eg. `512KiB`)
+    #[clap(long = "max-stack", name = "STACK_SIZE", parse(try_from_str = parse_size))]
+    max_stack: Option<u64>,
+
+    /// Run the guest on a stack allocated for it, of `--max-stack` bytes
+    #[clap(long = "dedicated-stack")]
+    dedicated_stack: bool,
+
+    /// Set when the guest tried to use more memory than `--max-memory`.
+    #[clap(skip)]
+    memory_exceeded: Arc<AtomicBool>,
+}
+
+/// A resource limit that the guest exceeded.
+///
+/// Each limit makes `wasmer run` exit with its own exit code.
+#[derive(Debug, Clone, Copy)]
+pub enum LimitExceeded {
+    /// The guest tried to use more memory than `--max-memory`.
+    Memory(Pages),
+    /// The guest executed more operators than `--fuel`.
+    Fuel(u64),
+    /// The guest ran for longer than `--timeout`.
+    Timeout(Duration),
+}
+
+impl LimitExceeded {
+    /// The exit code of `wasmer run` when this limit is exceeded.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Memory(_) => 122,
+            Self::Fuel(_) => 123,
+            // Same as the coreutils `timeout` command.
+            Self::Timeout(_) => 124,
+        }
+    }
+
+    /// Reports this limit and exits `wasmer run` with its exit code.
+    pub fn exit(&self) -> ! {
+        use colored::*;
+        eprintln!("{}", format!("{}: {}", "error".red(), self).bold());
+        std::process::exit(self.exit_code())
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Memory(pages) => write!(
+                f,
+                "the guest exceeded the memory limit of {} pages ({} bytes)",
+                pages.0,
+                pages.bytes().0
+            ),
+            Self::Fuel(fuel) => write!(f, "the guest ran out of fuel ({} operators)", fuel),
+            Self::Timeout(timeout) => {
+                write!(f, "the guest timed out after {:?}", timeout)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl Limits {
+    /// Returns whether the guest is metered with `--fuel`.
+    pub fn is_metered(&self) -> bool {
+        self.fuel.is_some()
+    }
+
+    /// The middlewares needed to enforce the limits at compile time.
+    #[cfg(feature = "compiler")]
+    pub fn middlewares(&self) -> Vec<Arc<dyn wasmer_compiler::ModuleMiddleware>> {
+        match self.fuel {
+            Some(fuel) => {
+                let metering: Arc<dyn wasmer_compiler::ModuleMiddleware> = Arc::new(
+                    wasmer_middlewares::Metering::new(fuel, |_: &wasmparser::Operator| 1)
+                        .with_fingerprint("one point per operator"),
+                );
+                vec![metering]
+            }
+            None => vec![],
+        }
+    }
+
+    /// Returns a store with the same engine as `store`, and tunables
+    /// enforcing `--max-memory` and `--max-stack` if they were set.
+    pub fn limit_store(&self, store: Store) -> Store {
+        if self.max_memory.is_none() && self.max_stack.is_none() && !self.dedicated_stack {
+            return store;
+        }
+        let base = StackTunables::new(
+            BaseTunables::for_target(&Target::default()),
+            StackConfig {
+                max_size: self.max_stack.map(|size| size as usize),
+                dedicated: self.dedicated_stack,
+            },
+        );
+        match self.max_memory {
+            Some(limit) => {
+                let tunables = LimitingTunables {
+                    base,
+                    limit,
+                    exceeded: self.memory_exceeded.clone(),
+                };
+                Store::new_with_tunables(&**store.engine(), tunables)
+            }
+            None => Store::new_with_tunables(&**store.engine(), base),
+        }
+    }
+
+    /// Runs `f`, interrupting it if it runs for longer than `--timeout`.
+    ///
+    /// The guest runs in its own thread so the timeout can be detected
+    /// while it's still running. A running guest can't be interrupted, so
+    /// when the timeout fires the whole process exits, stopping the guest.
+    pub fn with_timeout<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return f(),
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("wasmer-guest".to_string())
+            .stack_size(GUEST_THREAD_STACK_SIZE)
+            .spawn(move || {
+                // The receiver may be gone if we timed out already.
+                let _ = sender.send(f());
+            })?;
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => LimitExceeded::Timeout(timeout).exit(),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                bail!("the guest thread panicked")
+            }
+        }
+    }
+
+    /// Turns a failure of the guest running in `instance` into a
+    /// [`LimitExceeded`] error if it was caused by one of the limits.
+    pub fn check(&self, instance: &Instance, error: Error) -> Error {
+        if let Some(limit) = self.max_memory {
+            if self.memory_exceeded.load(Ordering::SeqCst) {
+                return LimitExceeded::Memory(limit).into();
+            }
+        }
+        #[cfg(feature = "compiler")]
+        {
+            use wasmer_middlewares::metering::{get_remaining_points, MeteringPoints};
+
+            if let Some(fuel) = self.fuel {
+                if get_remaining_points(instance) == MeteringPoints::Exhausted {
+                    return LimitExceeded::Fuel(fuel).into();
+                }
+            }
+        }
+        #[cfg(not(feature = "compiler"))]
+        let _ = instance;
+        error
+    }
+
+    /// Finds the limit that caused `error`, if any.
+    ///
+    /// This also covers the memory limit being hit while instantiating,
+    /// before there is any instance to [`check`](Self::check).
+    pub fn exceeded(&self, error: &Error) -> Option<LimitExceeded> {
+        if let Some(limit) = error.chain().find_map(|e| e.downcast_ref::<LimitExceeded>()) {
+            return Some(*limit);
+        }
+        match self.max_memory {
+            Some(limit) if self.memory_exceeded.load(Ordering::SeqCst) => {
+                Some(LimitExceeded::Memory(limit))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tunables that cap the size of every linear memory to `limit`.
+///
+/// All the other logic is delegated to the base tunables.
+struct LimitingTunables<T: Tunables> {
+    base: T,
+    limit: Pages,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl<T: Tunables> LimitingTunables<T> {
+    /// Clamps the maximum of the requested memory type to the limit.
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = requested.clone();
+        adjusted.maximum = Some(match requested.maximum {
+            Some(maximum) if maximum < self.limit => maximum,
+            _ => self.limit,
+        });
+        adjusted
+    }
+
+    /// Ensures the initial size of the memory fits in the limit.
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.limit {
+            self.exceeded.store(true, Ordering::SeqCst);
+            return Err(MemoryError::MinimumMemoryTooLarge {
+                min_requested: ty.minimum,
+                max_allowed: self.limit,
+            });
+        }
+        Ok(())
+    }
+
+    fn limit_memory(&self, memory: Arc<dyn vm::Memory>) -> Arc<dyn vm::Memory> {
+        Arc::new(LimitedMemory {
+            inner: memory,
+            limit: self.limit,
+            exceeded: self.exceeded.clone(),
+        })
+    }
+}
+
+impl<T: Tunables> MemoryUsage for LimitingTunables<T> {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.base.size_of_val(tracker) - mem::size_of_val(&self.base)
+    }
+}
+
+impl<T: Tunables> Tunables for LimitingTunables<T> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
+
+    fn memory64_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory64_style(&self.adjust_memory(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn stack_config(&self) -> StackConfig {
+        self.base.stack_config()
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        let memory = self.base.create_host_memory(&adjusted, style)?;
+        Ok(self.limit_memory(memory))
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        let memory = self
+            .base
+            .create_vm_memory(&adjusted, style, vm_definition_location)?;
+        Ok(self.limit_memory(memory))
+    }
+
+    fn create_host_memory64(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        let memory = self.base.create_host_memory64(&adjusted, style)?;
+        Ok(self.limit_memory(memory))
+    }
+
+    unsafe fn create_vm_memory64(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        let memory = self
+            .base
+            .create_vm_memory64(&adjusted, style, vm_definition_location)?;
+        Ok(self.limit_memory(memory))
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+/// A memory that records when the guest tries to grow it past the limit.
+#[derive(Debug, MemoryUsage)]
+struct LimitedMemory {
+    inner: Arc<dyn vm::Memory>,
+    limit: Pages,
+    #[loupe(skip)]
+    exceeded: Arc<AtomicBool>,
+}
+
+impl vm::Memory for LimitedMemory {
+    fn ty(&self) -> MemoryType {
+        self.inner.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.inner.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.inner.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let current = self.inner.size();
+        let within_limit = current
+            .0
+            .checked_add(delta.0)
+            .map_or(false, |new_size| Pages(new_size) <= self.limit);
+        if !within_limit {
+            self.exceeded.store(true, Ordering::SeqCst);
+            return Err(MemoryError::CouldNotGrow {
+                current,
+                attempted_delta: delta,
+            });
+        }
+        self.inner.grow(delta)
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.inner.vmmemory()
+    }
+
+    fn is_64(&self) -> bool {
+        self.inner.is_64()
+    }
+
+    fn detach_definition(&self) {
+        self.inner.detach_definition()
+    }
+}
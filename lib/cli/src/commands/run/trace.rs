@@ -0,0 +1,112 @@
+use std::sync::Mutex;
+use wasmer::{Array, CallKind, CallTracer, Memory, RuntimeError, TracedCall, Val, WasmPtr};
+#[cfg(feature = "wasi")]
+use wasmer_wasi::types::errno_name;
+
+/// The module names of the WASI imports.
+const WASI_NAMESPACES: &[&str] = &["wasi_unstable", "wasi_snapshot_preview1"];
+
+/// Prints the calls of the guest to its imports on stderr, like `strace`.
+///
+/// A call is printed once it returns, with its results:
+///
+/// ```text
+/// path_open(3, 1, "data/input.txt", 14, 0, 264240830, 268435455, 0, 1048560) = 44 (ENOENT)
+/// fd_write(1, 1048528, 1, 1048524) = 0
+/// proc_exit(0) = ? (WASI exited with code: 0)
+/// ```
+#[derive(Debug, Default)]
+pub struct ImportTracer {
+    /// The memory the paths given to WASI calls are read from, once the
+    /// instance is created.
+    memory: Mutex<Option<Memory>>,
+}
+
+impl ImportTracer {
+    /// Sets the memory to read the paths given to WASI calls from.
+    pub fn set_memory(&self, memory: Memory) {
+        *self.memory.lock().unwrap() = Some(memory);
+    }
+
+    /// Formats an argument, decoding it if it is a path.
+    fn format_arg(&self, call: &TracedCall, params: &[Val], index: usize) -> String {
+        let path = path_arg_indices(call)
+            .contains(&index)
+            .then(|| self.read_path(&params[index], params.get(index + 1)))
+            .flatten();
+        match path {
+            Some(path) => format!("{:?}", path),
+            None => params[index].to_string(),
+        }
+    }
+
+    fn read_path(&self, ptr: &Val, len: Option<&Val>) -> Option<String> {
+        let memory = self.memory.lock().unwrap();
+        match (memory.as_ref(), ptr, len) {
+            (Some(memory), Val::I32(ptr), Some(Val::I32(len))) => {
+                WasmPtr::<u8, Array>::new(*ptr as u32)
+                    .read_utf8_string(memory, *len as u32)
+                    .ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl CallTracer for ImportTracer {
+    fn exit(&self, call: &TracedCall, params: &[Val], results: Result<&[Val], &RuntimeError>) {
+        if call.kind != CallKind::Import {
+            return;
+        }
+        let is_wasi = is_wasi(call);
+        let name = if is_wasi {
+            call.field.clone()
+        } else {
+            format!("{}.{}", call.module, call.field)
+        };
+        let args = (0..params.len())
+            .map(|index| self.format_arg(call, params, index))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let results = match results {
+            Ok([]) => String::new(),
+            #[cfg(feature = "wasi")]
+            Ok([Val::I32(errno)]) if is_wasi && *errno != 0 => {
+                format!(" = {} ({})", errno, errno_name(*errno as u16))
+            }
+            Ok(results) => format!(
+                " = {}",
+                results
+                    .iter()
+                    .map(|val| val.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Err(error) => format!(" = ? ({})", error),
+        };
+        eprintln!("{}({}){}", name, args, results);
+    }
+}
+
+/// The indices of the arguments of a WASI call which are paths, each
+/// followed by its length.
+fn path_arg_indices(call: &TracedCall) -> &'static [usize] {
+    if !is_wasi(call) {
+        return &[];
+    }
+    match call.field.as_str() {
+        "path_create_directory"
+        | "path_readlink"
+        | "path_remove_directory"
+        | "path_unlink_file" => &[1],
+        "path_filestat_get" | "path_filestat_set_times" | "path_open" => &[2],
+        "path_link" => &[2, 5],
+        "path_rename" => &[1, 4],
+        "path_symlink" => &[0, 3],
+        _ => &[],
+    }
+}
+
+fn is_wasi(call: &TracedCall) -> bool {
+    WASI_NAMESPACES.contains(&call.module.as_str())
+}
@@ -1,27 +1,52 @@
-use crate::utils::{parse_envvar, parse_mapdir};
+use super::limits::Limits;
+use super::trace::ImportTracer;
+use crate::utils::{parse_dir, parse_envvar, parse_mapdir, DirMapping};
 use anyhow::{Context, Result};
 use std::collections::BTreeSet;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use wasmer::{Instance, Module};
-use wasmer_wasi::{get_wasi_versions, WasiError, WasiState, WasiVersion};
+use wasmer_wasi::{
+    get_wasi_versions, PathAccess, WasiError, WasiState, WasiStateBuilder, WasiVersion,
+};
 
 use clap::Clap;
 
 #[derive(Debug, Clap, Clone)]
 /// WASI Options
 pub struct Wasi {
-    /// WASI pre-opened directory
-    #[clap(long = "dir", name = "DIR", multiple = true, group = "wasi")]
-    pre_opened_directories: Vec<PathBuf>,
+    /// WASI pre-opened directory, read-only with a `:ro` suffix
+    #[clap(long = "dir", name = "DIR", multiple = true, group = "wasi", parse(try_from_str = parse_dir))]
+    pre_opened_directories: Vec<DirMapping>,
 
-    /// Map a host directory to a different location for the Wasm module
+    /// Map a host directory to a different location for the Wasm module,
+    /// read-only with a `:ro` suffix
     #[clap(long = "mapdir", name = "GUEST_DIR:HOST_DIR", multiple = true, parse(try_from_str = parse_mapdir))]
-    mapped_dirs: Vec<(String, PathBuf)>,
+    mapped_dirs: Vec<DirMapping>,
+
+    /// Hide a path inside the pre-opened directories from the Wasm module,
+    /// as the module sees it (eg. `/data/secrets`)
+    #[clap(long = "deny", name = "GUEST_PATH", multiple = true)]
+    denied_paths: Vec<PathBuf>,
+
+    /// Load directories and access rules from a WASI policy file
+    #[clap(long = "wasi-policy", name = "POLICY_FILE", parse(from_os_str))]
+    policy_file: Option<PathBuf>,
+
+    /// Give the files opened by the module only the rights it asks for,
+    /// rather than every right of their directory
+    #[clap(long = "wasi-enforce-requested-rights")]
+    enforce_requested_rights: bool,
 
     /// Pass custom environment variables
     #[clap(long = "env", name = "KEY=VALUE", multiple = true, parse(try_from_str = parse_envvar))]
     env_vars: Vec<(String, String)>,
 
+    /// The exported memory the WASI calls access, for modules with
+    /// several memories
+    #[clap(long = "wasi-memory", name = "MEMORY_EXPORT")]
+    memory_export: Option<String>,
+
     /// Enable experimental IO devices
     #[cfg(feature = "experimental-io-devices")]
     #[clap(long = "enable-experimental-io-devices")]
@@ -53,15 +78,18 @@ impl Wasi {
     }
 
     /// Helper function for executing Wasi from the `Run` command.
-    pub fn execute(&self, module: Module, program_name: String, args: Vec<String>) -> Result<()> {
+    pub fn execute(
+        &self,
+        module: Module,
+        program_name: String,
+        args: Vec<String>,
+        limits: &Limits,
+        tracer: Option<&ImportTracer>,
+    ) -> Result<()> {
         let args = args.iter().cloned().map(|arg| arg.into_bytes());
 
-        let mut wasi_state_builder = WasiState::new(program_name);
-        wasi_state_builder
-            .args(args)
-            .envs(self.env_vars.clone())
-            .preopen_dirs(self.pre_opened_directories.clone())?
-            .map_dirs(self.mapped_dirs.clone())?;
+        let mut wasi_state_builder = self.state_builder(program_name)?;
+        wasi_state_builder.args(args).envs(self.env_vars.clone());
 
         #[cfg(feature = "experimental-io-devices")]
         {
@@ -72,8 +100,17 @@ impl Wasi {
         }
 
         let mut wasi_env = wasi_state_builder.finalize()?;
+        if let Some(memory_export) = &self.memory_export {
+            wasi_env.set_memory_export(memory_export.as_str());
+        }
         let resolver = wasi_env.import_object_for_all_wasi_versions(&module)?;
         let instance = Instance::new(&module, &resolver)?;
+        if let Some(tracer) = tracer {
+            let memory_export = self.memory_export.as_deref().unwrap_or("memory");
+            if let Ok(memory) = instance.exports.get_memory(memory_export) {
+                tracer.set_memory(memory.clone());
+            }
+        }
 
         let start = instance.exports.get_function("_start")?;
         let result = start.call(&[]);
@@ -83,15 +120,135 @@ impl Wasi {
             Err(err) => {
                 let err: anyhow::Error = match err.downcast::<WasiError>() {
                     Ok(WasiError::Exit(exit_code)) => {
+                        // A guest giving up because it hit a resource limit
+                        // (eg. `malloc` failing under `--max-memory`) reports
+                        // the limit rather than its own exit code.
+                        if exit_code != 0 {
+                            let err = limits.check(
+                                &instance,
+                                anyhow!("the guest exited with code {}", exit_code),
+                            );
+                            if limits.exceeded(&err).is_some() {
+                                return Err(err);
+                            }
+                        }
                         // We should exit with the provided exit code
                         std::process::exit(exit_code as _);
                     }
-                    Ok(err) => err.into(),
-                    Err(err) => err.into(),
+                    Ok(err) => limits.check(&instance, err.into()),
+                    Err(err) => limits.check(&instance, err.into()),
                 };
                 Err(err)
             }
         }
         .with_context(|| "failed to run WASI `_start` function")
     }
+
+    /// Gets the filesystem sandboxing an Emscripten module, with the
+    /// directories and WASI policy given to it, and no files without them.
+    #[cfg(unix)]
+    pub fn emscripten_fs(&self) -> Result<wasmer_wasi::WasiFs> {
+        // the program name is only one of the arguments of WASI modules
+        Ok(self.state_builder(String::new())?.build()?.fs)
+    }
+
+    /// Preopens the directories of the module, with their access rules.
+    fn state_builder(&self, program_name: String) -> Result<WasiStateBuilder> {
+        let mut policy = match &self.policy_file {
+            Some(policy_file) => WasiPolicy::from_file(policy_file)?,
+            None => WasiPolicy::default(),
+        };
+        policy
+            .dirs
+            .extend(self.pre_opened_directories.iter().cloned());
+        policy.dirs.extend(self.mapped_dirs.iter().cloned());
+        policy.rules.extend(
+            self.denied_paths
+                .iter()
+                .map(|path| (path.clone(), PathAccess::Deny)),
+        );
+
+        let mut wasi_state_builder = WasiState::new(program_name);
+        wasi_state_builder.enforce_requested_rights(self.enforce_requested_rights);
+        for dir in &policy.dirs {
+            wasi_state_builder.preopen(|p| {
+                let p = p
+                    .directory(&dir.host_dir)
+                    .read(true)
+                    .write(!dir.read_only)
+                    .create(!dir.read_only);
+                match &dir.alias {
+                    Some(alias) => p.alias(alias),
+                    None => p,
+                }
+            })?;
+        }
+        for (guest_path, access) in &policy.rules {
+            wasi_state_builder.path_access(guest_path, *access);
+        }
+        Ok(wasi_state_builder)
+    }
+}
+
+/// The directories and access rules of a WASI sandbox.
+///
+/// A policy file has one directive per line, and `#` starts a comment line:
+///
+/// ```text
+/// # the same syntax as `--dir` and `--mapdir`
+/// dir fixtures:ro
+/// mapdir /data:/srv/data
+/// # access rules for paths inside the directories, as the module sees them
+/// deny /data/secrets
+/// ro /data/config
+/// rw /fixtures/tmp
+/// ```
+///
+/// Relative host directories are relative to the current directory.
+#[derive(Debug, Default)]
+struct WasiPolicy {
+    dirs: Vec<DirMapping>,
+    rules: Vec<(PathBuf, PathAccess)>,
+}
+
+impl WasiPolicy {
+    fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read the WASI policy file `{}`", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("invalid WASI policy file `{}`", path.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        let mut policy = Self::default();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            policy
+                .parse_directive(line)
+                .with_context(|| format!("line {}: `{}`", index + 1, line))?;
+        }
+        Ok(policy)
+    }
+
+    fn parse_directive(&mut self, line: &str) -> Result<()> {
+        let (directive, value) = match line.find(char::is_whitespace) {
+            Some(position) => (&line[..position], line[position..].trim()),
+            None => bail!("expected a directive followed by a path"),
+        };
+        match directive {
+            "dir" => self.dirs.push(parse_dir(value)?),
+            "mapdir" => self.dirs.push(parse_mapdir(value)?),
+            "deny" => self.rules.push((value.into(), PathAccess::Deny)),
+            "ro" => self.rules.push((value.into(), PathAccess::ReadOnly)),
+            "rw" => self.rules.push((value.into(), PathAccess::ReadWrite)),
+            _ => bail!(
+                "unknown directive `{}`, expected one of `dir`, `mapdir`, `deny`, `ro` or `rw`",
+                directive
+            ),
+        }
+        Ok(())
+    }
 }
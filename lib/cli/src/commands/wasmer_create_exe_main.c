@@ -1,6 +1,5 @@
 #include "wasmer_wasm.h"
 #include "wasm.h"
-#include "my_wasm.h"
 
 #include <stdio.h>
 #include <stdlib.h>
@@ -8,6 +7,26 @@
 
 #define own
 
+typedef wasm_module_t *(*wasmer_module_new_t)(wasm_store_t *store,
+                                               const char *wasm_name);
+
+// A module bundled in the executable.
+typedef struct {
+  const char *name;
+  wasmer_module_new_t new_module;
+} wasmer_bundle_module_t;
+
+// A directory image embedded in the executable, mapped at `alias`.
+typedef struct {
+  const char *alias;
+  const unsigned char *image;
+  size_t image_len;
+} wasmer_bundle_image_t;
+
+// Generated by `wasmer create-exe`: the bundled modules and the WASI
+// configuration baked into the executable.
+#include "wasmer_bundle.h"
+
 // TODO: make this define templated so that the Rust code can toggle it on/off
 #define WASI
 
@@ -20,7 +39,74 @@ static void print_wasmer_error() {
   free(error_str);
 }
 
+// The module the executable is invoked as, like busybox does, or the
+// entrypoint.
+static const wasmer_bundle_module_t *select_module(const char *program) {
+  const char *name = program;
+  for (const char *c = program; *c; ++c) {
+    if (*c == '/' || *c == '\\') {
+      name = c + 1;
+    }
+  }
+  size_t name_len = strlen(name);
+  // On Windows, the executable may be invoked as `name.exe`.
+  if (name_len > 4 && strcmp(name + name_len - 4, ".exe") == 0) {
+    name_len -= 4;
+  }
+
+  const wasmer_bundle_module_t *entrypoint = NULL;
+  for (const wasmer_bundle_module_t *module = wasmer_bundle_modules;
+       module->name; ++module) {
+    if (strlen(module->name) == name_len &&
+        strncmp(module->name, name, name_len) == 0) {
+      return module;
+    }
+    if (strcmp(module->name, wasmer_bundle_entrypoint) == 0) {
+      entrypoint = module;
+    }
+  }
+  return entrypoint;
+}
+
 #ifdef WASI
+// Deleting the WASI env removes the embedded directories unpacked for it,
+// so it's deleted when the program exits, however it exits.
+static wasi_env_t *wasi_env = NULL;
+
+static void delete_wasi_env(void) { wasi_env_delete(wasi_env); }
+
+// Applies the WASI configuration baked into the executable.
+static void apply_bundle_config(wasi_config_t *wasi_config) {
+  for (const char **arg = wasmer_bundle_args; *arg; ++arg) {
+    wasi_config_arg(wasi_config, *arg);
+  }
+  for (const char **env = wasmer_bundle_env_vars; *env; env += 2) {
+    wasi_config_env(wasi_config, env[0], env[1]);
+  }
+  for (const char **dir = wasmer_bundle_pre_opened_directories; *dir; ++dir) {
+    if (!wasi_config_preopen_dir(wasi_config, *dir)) {
+      fprintf(stderr, "Failed to preopen `%s`\n", *dir);
+      exit(-1);
+    }
+  }
+  for (const char **mapdir = wasmer_bundle_mapped_dirs; *mapdir;
+       mapdir += 2) {
+    if (!wasi_config_mapdir(wasi_config, mapdir[0], mapdir[1])) {
+      fprintf(stderr, "Failed to map `%s` to `%s`\n", mapdir[1], mapdir[0]);
+      exit(-1);
+    }
+  }
+  for (const wasmer_bundle_image_t *image = wasmer_bundle_embedded_dirs;
+       image->alias; ++image) {
+    if (!wasi_config_mapdir_image(wasi_config, image->alias, image->image,
+                                  image->image_len)) {
+      fprintf(stderr, "Failed to unpack the directory embedded at `%s`\n",
+              image->alias);
+      exit(-1);
+    }
+  }
+}
+
 static void pass_mapdir_arg(wasi_config_t *wasi_config, char *mapdir) {
   int colon_location = strchr(mapdir, ':') - mapdir;
   if (colon_location == 0) {
@@ -93,7 +179,8 @@ int main(int argc, char *argv[]) {
   wasm_engine_t *engine = wasm_engine_new_with_config(config);
   wasm_store_t *store = wasm_store_new(engine);
 
-  wasm_module_t *module = wasmer_object_file_engine_new(store, argv[0]);
+  const wasmer_bundle_module_t *bundle_module = select_module(argv[0]);
+  wasm_module_t *module = bundle_module->new_module(store, argv[0]);
   if (!module) {
     fprintf(stderr, "Failed to create module\n");
     print_wasmer_error();
@@ -105,14 +192,16 @@ int main(int argc, char *argv[]) {
 
 #ifdef WASI
   wasi_config_t *wasi_config = wasi_config_new(argv[0]);
+  apply_bundle_config(wasi_config);
   handle_arguments(wasi_config, argc, argv);
 
-  wasi_env_t *wasi_env = wasi_env_new(wasi_config);
+  wasi_env = wasi_env_new(wasi_config);
   if (!wasi_env) {
     fprintf(stderr, "Error building WASI env!\n");
     print_wasmer_error();
     return 1;
   }
+  atexit(delete_wasi_env);
 #endif
 
   wasm_importtype_vec_t import_types;
@@ -124,7 +213,6 @@ int main(int argc, char *argv[]) {
 
 #ifdef WASI
   bool get_imports_result = wasi_get_imports(store, module, wasi_env, &imports);
-  wasi_env_delete(wasi_env);
 
   if (!get_imports_result) {
     fprintf(stderr, "Error getting WASI imports!\n");
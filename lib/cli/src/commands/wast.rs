@@ -2,8 +2,9 @@
 use crate::store::StoreOptions;
 use anyhow::{Context, Result};
 use clap::Clap;
+use std::fs;
 use std::path::PathBuf;
-use wasmer_wast::Wast as WastSpectest;
+use wasmer_wast::{ReportFormat, Wast as WastSpectest};
 
 #[derive(Debug, Clap)]
 /// The options for the `wasmer wast` subcommand
@@ -18,6 +19,14 @@ pub struct Wast {
     #[clap(short, long)]
     /// A flag to indicate wast stop at the first error or continue.
     fail_fast: bool,
+
+    /// Write the result of every directive to this file
+    #[clap(long = "report", name = "REPORT_FILE", parse(from_os_str))]
+    report: Option<PathBuf>,
+
+    /// The format of the report: `json` or `junit`
+    #[clap(long = "report-format", default_value = "json")]
+    report_format: ReportFormat,
 }
 
 impl Wast {
@@ -30,7 +39,20 @@ impl Wast {
         let (store, _engine_name, _compiler_name) = self.store.get_store()?;
         let mut wast = WastSpectest::new_with_spectest(store);
         wast.fail_fast = self.fail_fast;
-        wast.run_file(&self.path).with_context(|| "tests failed")?;
+        let result = wast.run_file(&self.path);
+        let report = wast.report();
+        if let Some(report_path) = &self.report {
+            fs::write(report_path, report.render(self.report_format)).with_context(|| {
+                format!("failed to write the report to `{}`", report_path.display())
+            })?;
+        }
+        eprintln!(
+            "{} directives passed, {} failed, {} skipped.",
+            report.passed(),
+            report.failed(),
+            report.skipped()
+        );
+        result.with_context(|| "tests failed")?;
         eprintln!("Wast tests succeeded for `{}`.", self.path.display());
         Ok(())
     }
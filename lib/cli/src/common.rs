@@ -29,6 +29,22 @@ pub struct WasmFeatures {
     #[clap(long = "enable-bulk-memory")]
     pub bulk_memory: bool,
 
+    /// Enable support for the memory64 proposal.
+    #[clap(long = "enable-memory64")]
+    pub memory64: bool,
+
+    /// Enable support for the multi memory proposal.
+    #[clap(long = "enable-multi-memory")]
+    pub multi_memory: bool,
+
+    /// Enable support for the exception handling proposal (Cranelift only).
+    #[clap(long = "enable-exceptions")]
+    pub exceptions: bool,
+
+    /// Enable support for the tail-call proposal (Cranelift and LLVM only).
+    #[clap(long = "enable-tail-call")]
+    pub tail_call: bool,
+
     /// Enable support for all pre-standard proposals.
     #[clap(long = "enable-all")]
     pub all: bool,
@@ -51,3 +67,43 @@ pub fn get_cache_dir() -> PathBuf {
         }
     }
 }
+
+/// Get the maximum size of the cache, in bytes, from the
+/// `WASMER_CACHE_MAX_SIZE` environment variable (eg. `2GiB`)
+#[cfg(feature = "cache")]
+pub fn get_cache_max_size() -> anyhow::Result<Option<u64>> {
+    use anyhow::Context;
+
+    match env::var("WASMER_CACHE_MAX_SIZE") {
+        Ok(max_size) => Ok(Some(
+            crate::utils::parse_size(&max_size).context("invalid `WASMER_CACHE_MAX_SIZE`")?,
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Get the remote cache shared with other hosts, from the
+/// `WASMER_CACHE_REMOTE` environment variable (eg. `http://cache.internal/wasmer`)
+/// and the `WASMER_CACHE_REMOTE_SECRET` one, a secret shared by the hosts to
+/// sign the artifacts they load from the cache.
+#[cfg(feature = "cache")]
+pub fn get_cache_remote(
+) -> anyhow::Result<Option<wasmer_cache::RemoteCache<wasmer_cache::HttpStorage>>> {
+    use anyhow::{bail, Context};
+
+    let url = match env::var("WASMER_CACHE_REMOTE") {
+        Ok(url) => url,
+        Err(_) => return Ok(None),
+    };
+    let storage = wasmer_cache::HttpStorage::new(&url).context("invalid `WASMER_CACHE_REMOTE`")?;
+    let secret = match env::var("WASMER_CACHE_REMOTE_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ => bail!(
+            "`WASMER_CACHE_REMOTE` requires a `WASMER_CACHE_REMOTE_SECRET` to sign the artifacts"
+        ),
+    };
+    Ok(Some(wasmer_cache::RemoteCache::new(
+        storage,
+        secret.as_bytes(),
+    )))
+}
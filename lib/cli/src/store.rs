@@ -11,7 +11,7 @@ use std::string::ToString;
 use std::sync::Arc;
 use wasmer::*;
 #[cfg(feature = "compiler")]
-use wasmer_compiler::CompilerConfig;
+use wasmer_compiler::{CompilerConfig, ModuleMiddleware};
 
 #[derive(Debug, Clone, Clap)]
 /// The compiler and engine options
@@ -113,6 +113,18 @@ impl CompilerOptions {
         if self.features.reference_types || self.features.all {
             features.reference_types(true);
         }
+        if self.features.memory64 || self.features.all {
+            features.memory64(true);
+        }
+        if self.features.multi_memory || self.features.all {
+            features.multi_memory(true);
+        }
+        if self.features.exceptions || self.features.all {
+            features.exceptions(true);
+        }
+        if self.features.tail_call || self.features.all {
+            features.tail_call(true);
+        }
         Ok(features)
     }
 
@@ -128,6 +140,26 @@ impl CompilerOptions {
         Ok((store, compiler_type))
     }
 
+    /// Gets the Store of an ObjectFile engine for a given target, prefixing
+    /// the symbols of the modules it compiles with `prefix`, so that the
+    /// objects of several modules can be linked together.
+    #[cfg(feature = "object-file")]
+    pub fn get_object_file_store_for_target(
+        &self,
+        target: Target,
+        prefix: String,
+    ) -> Result<(Store, CompilerType)> {
+        let (compiler_config, compiler_type) = self.get_compiler_config()?;
+        let features = self.get_features(compiler_config.default_features_for_target(&target))?;
+        let mut engine = wasmer_engine_object_file::ObjectFile::new(compiler_config)
+            .target(target)
+            .features(features)
+            .engine();
+        engine.set_deterministic_prefixer(move |_| prefix.clone());
+        let store = Store::new(&engine);
+        Ok((store, compiler_type))
+    }
+
     fn get_engine_by_type(
         &self,
         target: Target,
@@ -391,7 +423,30 @@ impl StoreOptions {
         &self,
         target: Target,
     ) -> Result<(Store, EngineType, CompilerType)> {
-        let (compiler_config, compiler_type) = self.compiler.get_compiler_config()?;
+        self.get_store_for_target_with_middlewares(target, vec![])
+    }
+
+    /// Gets the store for the host target, with the given middlewares
+    /// pushed onto the compiler config
+    pub fn get_store_with_middlewares(
+        &self,
+        middlewares: Vec<Arc<dyn ModuleMiddleware>>,
+    ) -> Result<(Store, EngineType, CompilerType)> {
+        let target = Target::default();
+        self.get_store_for_target_with_middlewares(target, middlewares)
+    }
+
+    /// Gets the store for a given target, with the given middlewares
+    /// pushed onto the compiler config
+    pub fn get_store_for_target_with_middlewares(
+        &self,
+        target: Target,
+        middlewares: Vec<Arc<dyn ModuleMiddleware>>,
+    ) -> Result<(Store, EngineType, CompilerType)> {
+        let (mut compiler_config, compiler_type) = self.compiler.get_compiler_config()?;
+        for middleware in middlewares {
+            compiler_config.push_middleware(middleware);
+        }
         let (engine, engine_type) = self.get_engine_with_compiler(target, compiler_config)?;
         let store = Store::new(&*engine);
         Ok((store, engine_type, compiler_type))
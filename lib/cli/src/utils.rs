@@ -2,6 +2,8 @@
 use anyhow::{bail, Result};
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
+use wasmer::{Pages, WASM_PAGE_SIZE};
 
 /// Whether or not Wasmer should print with color
 pub fn wasmer_should_print_color() -> bool {
@@ -23,8 +25,71 @@ fn retrieve_alias_pathbuf(alias: &str, real_dir: &str) -> Result<(String, PathBu
     Ok((alias.to_string(), pb))
 }
 
-/// Parses a mapdir from a string
-pub fn parse_mapdir(entry: &str) -> Result<(String, PathBuf)> {
+/// A host directory given to the guest with `--dir` or `--mapdir`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirMapping {
+    /// The name the guest sees the directory as, if it's not the host path.
+    pub alias: Option<String>,
+    /// The directory on the host.
+    pub host_dir: PathBuf,
+    /// Whether the guest can only read the directory.
+    pub read_only: bool,
+}
+
+/// Splits the `:ro` or `:rw` access suffix of a directory mapping.
+fn split_access(entry: &str) -> Option<(&str, bool)> {
+    for (suffix, read_only) in &[
+        ("::ro", true),
+        ("::rw", false),
+        (":ro", true),
+        (":rw", false),
+    ] {
+        if let Some(rest) = entry.strip_suffix(*suffix) {
+            return Some((rest, *read_only));
+        }
+    }
+    None
+}
+
+/// Parses a preopened directory from a string, like `HOST_DIR`
+/// or `HOST_DIR:ro`.
+pub fn parse_dir(entry: &str) -> Result<DirMapping> {
+    // A directory that is really named like `foo:ro` can still be given
+    // with an explicit suffix, as `foo:ro:rw`.
+    let (host_dir, read_only) = match split_access(entry) {
+        Some((host_dir, read_only)) if !host_dir.is_empty() => (host_dir, read_only),
+        _ => (entry, false),
+    };
+    Ok(DirMapping {
+        alias: None,
+        host_dir: PathBuf::from(host_dir),
+        read_only,
+    })
+}
+
+/// Parses a mapdir from a string, like `GUEST_DIR:HOST_DIR`
+/// or `GUEST_DIR:HOST_DIR:ro`.
+pub fn parse_mapdir(entry: &str) -> Result<DirMapping> {
+    // The suffix is optional, so if the entry doesn't make sense without
+    // it, it was part of the host directory.
+    if let Some((rest, read_only)) = split_access(entry) {
+        if let Ok((alias, host_dir)) = parse_alias_and_dir(rest) {
+            return Ok(DirMapping {
+                alias: Some(alias),
+                host_dir,
+                read_only,
+            });
+        }
+    }
+    let (alias, host_dir) = parse_alias_and_dir(entry)?;
+    Ok(DirMapping {
+        alias: Some(alias),
+        host_dir,
+        read_only: false,
+    })
+}
+
+fn parse_alias_and_dir(entry: &str) -> Result<(String, PathBuf)> {
     // We try first splitting by `::`
     if let [alias, real_dir] = entry.split("::").collect::<Vec<&str>>()[..] {
         retrieve_alias_pathbuf(alias, real_dir)
@@ -64,9 +129,125 @@ pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
     }
 }
 
+/// Parses a memory size, either as a number of Wasm pages (`16`) or as
+/// a number of bytes with a unit (`64KiB`, `10MiB`, `1GiB`), rounded up
+/// to whole pages.
+pub fn parse_memory_pages(entry: &str) -> Result<Pages> {
+    let entry = entry.trim();
+    let split = entry
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(entry.len());
+    let (number, unit) = entry.split_at(split);
+    let number: u64 = match number.parse() {
+        Ok(number) => number,
+        Err(_) => bail!(
+            "Memory size must be a number of pages or of bytes with a unit (eg. `64MiB`); found `{}`",
+            entry
+        ),
+    };
+    let multiplier = match unit.trim() {
+        "" => return Ok(Pages(number.min(u32::MAX as u64) as u32)),
+        unit => match size_unit_multiplier(unit) {
+            Some(multiplier) => multiplier,
+            None => bail!("Unknown memory size unit `{}` in `{}`", unit, entry),
+        },
+    };
+    let bytes = number.saturating_mul(multiplier);
+    let page_size = WASM_PAGE_SIZE as u64;
+    let pages = bytes / page_size + if bytes % page_size == 0 { 0 } else { 1 };
+    Ok(Pages(pages.min(u32::MAX as u64) as u32))
+}
+
+/// Parses a size in bytes, with an optional unit (`4096`, `64KiB`,
+/// `512MiB`, `1GiB`).
+pub fn parse_size(entry: &str) -> Result<u64> {
+    let entry = entry.trim();
+    let split = entry
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(entry.len());
+    let (number, unit) = entry.split_at(split);
+    let number: u64 = match number.parse() {
+        Ok(number) => number,
+        Err(_) => bail!(
+            "Size must be a number of bytes, optionally with a unit (eg. `512MiB`); found `{}`",
+            entry
+        ),
+    };
+    match size_unit_multiplier(unit.trim()) {
+        Some(multiplier) => Ok(number.saturating_mul(multiplier)),
+        None => bail!("Unknown size unit `{}` in `{}`", unit.trim(), entry),
+    }
+}
+
+/// The number of bytes of a size unit.
+fn size_unit_multiplier(unit: &str) -> Option<u64> {
+    Some(match unit {
+        "" | "B" => 1,
+        "K" | "KB" | "KiB" => 1 << 10,
+        "M" | "MB" | "MiB" => 1 << 20,
+        "G" | "GB" | "GiB" => 1 << 30,
+        "T" | "TB" | "TiB" => 1 << 40,
+        _ => return None,
+    })
+}
+
+/// Parses an age, like a [duration](parse_duration) but also in hours
+/// or days (`12h`, `30d`).
+pub fn parse_age(entry: &str) -> Result<Duration> {
+    let entry = entry.trim();
+    let (number, unit_seconds) = if let Some(hours) = entry.strip_suffix('h') {
+        (hours, 60 * 60)
+    } else if let Some(days) = entry.strip_suffix('d') {
+        (days, 24 * 60 * 60)
+    } else {
+        return parse_duration(entry);
+    };
+    match number.trim().parse::<u64>() {
+        Ok(number) => Ok(Duration::from_secs(number.saturating_mul(unit_seconds))),
+        Err(_) => bail!(
+            "Age must be a number with a unit (eg. `30d`); found `{}`",
+            entry
+        ),
+    }
+}
+
+/// Parses a duration, either as a number of seconds (`10`, `1.5`) or
+/// with a unit (`500ms`, `30s`, `2m`).
+pub fn parse_duration(entry: &str) -> Result<Duration> {
+    let entry = entry.trim();
+    let split = entry
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(entry.len());
+    let (number, unit) = entry.split_at(split);
+    let number: f64 = match number.parse() {
+        Ok(number) if number >= 0.0 => number,
+        _ => bail!(
+            "Duration must be a number of seconds or have a unit (eg. `500ms`); found `{}`",
+            entry
+        ),
+    };
+    let seconds = match unit.trim() {
+        "" | "s" => number,
+        "ms" => number / 1000.0,
+        "m" => number * 60.0,
+        unit => bail!("Unknown duration unit `{}` in `{}`", unit, entry),
+    };
+    // `Duration` counts whole seconds in a `u64`
+    if seconds >= u64::MAX as f64 {
+        bail!("Duration `{}` is too long", entry);
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_envvar;
+    use super::{
+        parse_age, parse_dir, parse_duration, parse_envvar, parse_mapdir, parse_memory_pages,
+        parse_size,
+    };
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use wasmer::Pages;
 
     #[test]
     fn test_parse_envvar() {
@@ -89,4 +270,109 @@ mod tests {
             ("A".into(), "B=C=D".into())
         );
     }
+
+    #[test]
+    fn test_parse_dir() {
+        let dir = parse_dir("fixtures").unwrap();
+        assert_eq!(dir.host_dir, PathBuf::from("fixtures"));
+        assert!(!dir.read_only);
+        assert_eq!(dir.alias, None);
+
+        let dir = parse_dir("fixtures:ro").unwrap();
+        assert_eq!(dir.host_dir, PathBuf::from("fixtures"));
+        assert!(dir.read_only);
+
+        let dir = parse_dir("fixtures:rw").unwrap();
+        assert!(!dir.read_only);
+
+        // A directory named like a suffix is kept as is.
+        assert_eq!(parse_dir(":ro").unwrap().host_dir, PathBuf::from(":ro"));
+        assert_eq!(
+            parse_dir("x:ro:rw").unwrap().host_dir,
+            PathBuf::from("x:ro")
+        );
+    }
+
+    #[test]
+    fn test_parse_mapdir() {
+        let dir = parse_mapdir("code:src").unwrap();
+        assert_eq!(dir.alias.as_deref(), Some("code"));
+        assert_eq!(dir.host_dir, PathBuf::from("src"));
+        assert!(!dir.read_only);
+
+        let dir = parse_mapdir("code:src:ro").unwrap();
+        assert_eq!(dir.alias.as_deref(), Some("code"));
+        assert_eq!(dir.host_dir, PathBuf::from("src"));
+        assert!(dir.read_only);
+
+        let dir = parse_mapdir("code::src::ro").unwrap();
+        assert_eq!(dir.host_dir, PathBuf::from("src"));
+        assert!(dir.read_only);
+
+        let dir = parse_mapdir("code::src:rw").unwrap();
+        assert_eq!(dir.host_dir, PathBuf::from("src"));
+        assert!(!dir.read_only);
+
+        assert!(parse_mapdir("code").is_err());
+        assert!(parse_mapdir("code:does-not-exist:ro").is_err());
+    }
+
+    #[test]
+    fn test_parse_memory_pages() {
+        assert_eq!(parse_memory_pages("16").unwrap(), Pages(16));
+        assert_eq!(parse_memory_pages("64KiB").unwrap(), Pages(1));
+        assert_eq!(parse_memory_pages("65537B").unwrap(), Pages(2));
+        assert_eq!(parse_memory_pages("10MiB").unwrap(), Pages(160));
+        assert_eq!(parse_memory_pages("1G").unwrap(), Pages(16384));
+        assert_eq!(
+            parse_memory_pages("10XB").unwrap_err().to_string(),
+            "Unknown memory size unit `XB` in `10XB`"
+        );
+        assert!(parse_memory_pages("MiB").is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("64KiB").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("2G").unwrap(), 2 << 30);
+        assert_eq!(
+            parse_size("1XB").unwrap_err().to_string(),
+            "Unknown size unit `XB` in `1XB`"
+        );
+        assert!(parse_size("GiB").is_err());
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d").unwrap(), Duration::from_secs(30 * 86400));
+        assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 3600));
+        assert_eq!(parse_age("90s").unwrap(), Duration::from_secs(90));
+        assert!(parse_age("d").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(
+            parse_duration("3h").unwrap_err().to_string(),
+            "Unknown duration unit `h` in `3h`"
+        );
+        assert!(parse_duration("-1").is_err());
+        assert_eq!(
+            parse_duration("99999999999999999999999")
+                .unwrap_err()
+                .to_string(),
+            "Duration `99999999999999999999999` is too long"
+        );
+        assert_eq!(
+            parse_duration("307445734561825861m")
+                .unwrap_err()
+                .to_string(),
+            "Duration `307445734561825861m` is too long"
+        );
+    }
 }
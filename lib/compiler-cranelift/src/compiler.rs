@@ -25,9 +25,9 @@ use wasmer_compiler::CompileError;
 use wasmer_compiler::{CallingConvention, ModuleTranslationState, Target};
 use wasmer_compiler::{
     Compilation, CompileModuleInfo, CompiledFunction, CompiledFunctionFrameInfo,
-    CompiledFunctionUnwindInfo, Compiler, Dwarf, FunctionBinaryReader, FunctionBody,
-    FunctionBodyData, MiddlewareBinaryReader, ModuleMiddleware, ModuleMiddlewareChain,
-    SectionIndex,
+    CompiledFunctionUnwindInfo, Compiler, CompilerConfig, Dwarf, FunctionBinaryReader,
+    FunctionBody, FunctionBodyData, MiddlewareBinaryReader, ModuleMiddleware,
+    ModuleMiddlewareChain, SectionIndex,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
@@ -57,6 +57,10 @@ impl Compiler for CraneliftCompiler {
         &self.config.middlewares
     }
 
+    fn fingerprint(&self) -> String {
+        self.config.fingerprint()
+    }
+
     /// Compile the module using Cranelift, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -115,6 +119,8 @@ impl Compiler for CraneliftCompiler {
                     &signatures,
                     &memory_styles,
                     &table_styles,
+                    compile_info.features.exceptions,
+                    compile_info.features.tail_call,
                 );
                 context.func.name = get_function_name(func_index);
                 context.func.signature = signatures[module.functions[func_index]].clone();
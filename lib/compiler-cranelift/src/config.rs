@@ -4,7 +4,8 @@ use cranelift_codegen::settings::{self, Configurable};
 use loupe::MemoryUsage;
 use std::sync::Arc;
 use wasmer_compiler::{
-    Architecture, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target,
+    Architecture, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, ModuleMiddlewareChain,
+    Target,
 };
 
 // Runtime Environment
@@ -194,6 +195,16 @@ impl CompilerConfig for Cranelift {
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>) {
         self.middlewares.push(middleware);
     }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "cranelift opt_level={:?} nan_canonicalization={} pic={} middlewares={}",
+            self.opt_level,
+            self.enable_nan_canonicalization,
+            self.enable_pic,
+            self.middlewares.fingerprint()
+        )
+    }
 }
 
 impl Default for Cranelift {
@@ -19,7 +19,7 @@ use wasmer_types::entity::EntityRef;
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::{
     FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex,
-    TableIndex, Type as WasmerType,
+    TableIndex, TagIndex, Type as WasmerType,
 };
 use wasmer_vm::VMBuiltinFunctionIndex;
 use wasmer_vm::VMOffsets;
@@ -30,11 +30,6 @@ pub fn get_function_name(func_index: FunctionIndex) -> ir::ExternalName {
     ir::ExternalName::user(0, func_index.as_u32())
 }
 
-/// The type of the `current_length` field.
-pub fn type_of_vmmemory_definition_current_length(vmoffsets: &VMOffsets) -> ir::Type {
-    ir::Type::int(u16::from(vmoffsets.size_of_vmmemory_definition_current_length()) * 8).unwrap()
-}
-
 /// The type of the `current_elements` field.
 pub fn type_of_vmtable_definition_current_elements(vmoffsets: &VMOffsets) -> ir::Type {
     ir::Type::int(u16::from(vmoffsets.size_of_vmtable_definition_current_elements()) * 8).unwrap()
@@ -61,6 +56,10 @@ pub struct FuncEnvironment<'module_environment> {
     /// for locally-defined 32-bit memories.
     memory32_size_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's `memory.size`
+    /// for 64-bit memories.
+    memory64_size_sig: Option<ir::SigRef>,
+
     /// The external function signature for implementing wasm's `table.size`
     /// for locally-defined tables.
     table_size_sig: Option<ir::SigRef>,
@@ -69,6 +68,10 @@ pub struct FuncEnvironment<'module_environment> {
     /// for locally-defined memories.
     memory_grow_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's `memory.grow`
+    /// for 64-bit memories.
+    memory64_grow_sig: Option<ir::SigRef>,
+
     /// The external function signature for implementing wasm's `table.grow`
     /// for locally-defined tables.
     table_grow_sig: Option<ir::SigRef>,
@@ -87,13 +90,33 @@ pub struct FuncEnvironment<'module_environment> {
     /// (it's the same for both local and imported memories).
     memory_copy_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's `memory.copy`
+    /// for 64-bit memories.
+    memory64_copy_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `memory.copy`
+    /// between two different 32-bit memories.
+    memory32_copy_between_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `memory.copy`
+    /// between two different memories, one of them 64-bit.
+    memory64_copy_between_sig: Option<ir::SigRef>,
+
     /// The external function signature for implementing wasm's `memory.fill`
     /// (it's the same for both local and imported memories).
     memory_fill_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's `memory.fill`
+    /// for 64-bit memories.
+    memory64_fill_sig: Option<ir::SigRef>,
+
     /// The external function signature for implementing wasm's `memory.init`.
     memory_init_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's `memory.init`
+    /// for 64-bit memories.
+    memory64_init_sig: Option<ir::SigRef>,
+
     /// The external function signature for implementing wasm's `data.drop`.
     data_drop_sig: Option<ir::SigRef>,
 
@@ -114,6 +137,53 @@ pub struct FuncEnvironment<'module_environment> {
 
     /// The external function signature for implementing reference decrement for `extern.ref`.
     externref_dec_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `throw`.
+    throw_sig: Option<ir::SigRef>,
+
+    /// The external function signature for raising the pending exception
+    /// to the caller.
+    exception_raise_sig: Option<ir::SigRef>,
+
+    /// The external function signature for calling a function in the body
+    /// of a `try`.
+    try_call_sig: Option<ir::SigRef>,
+
+    /// The external function signature for calling a function indirectly in
+    /// the body of a `try`.
+    try_call_indirect_sig: Option<ir::SigRef>,
+
+    /// The external function signature for matching the tag of the pending
+    /// exception.
+    exception_matches_sig: Option<ir::SigRef>,
+
+    /// The external function signature for catching an exception.
+    exception_catch_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `rethrow`
+    /// (it's the same for releasing a caught exception).
+    exception_rethrow_sig: Option<ir::SigRef>,
+
+    /// Whether the calls in the body of a `try` catch exceptions.
+    exceptions: bool,
+
+    /// The external function signature for implementing wasm's `return_call`.
+    return_call_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's
+    /// `return_call_indirect`.
+    return_call_indirect_sig: Option<ir::SigRef>,
+
+    /// The external function signature for checking whether a tail call is
+    /// pending.
+    tail_call_pending_sig: Option<ir::SigRef>,
+
+    /// The external function signature for making the pending tail calls.
+    tail_call_sig: Option<ir::SigRef>,
+
+    /// Whether calls make the pending tail calls.
+    tail_calls: bool,
+
     /// Offsets to struct fields accessed by JIT code.
     offsets: VMOffsets,
 
@@ -131,6 +201,8 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         signatures: &'module_environment PrimaryMap<SignatureIndex, ir::Signature>,
         memory_styles: &'module_environment PrimaryMap<MemoryIndex, MemoryStyle>,
         table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
+        exceptions: bool,
+        tail_calls: bool,
     ) -> Self {
         Self {
             target_config,
@@ -139,15 +211,22 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             type_stack: vec![],
             vmctx: None,
             memory32_size_sig: None,
+            memory64_size_sig: None,
             table_size_sig: None,
             memory_grow_sig: None,
+            memory64_grow_sig: None,
             table_grow_sig: None,
             table_copy_sig: None,
             table_init_sig: None,
             elem_drop_sig: None,
             memory_copy_sig: None,
+            memory64_copy_sig: None,
+            memory32_copy_between_sig: None,
+            memory64_copy_between_sig: None,
             memory_fill_sig: None,
+            memory64_fill_sig: None,
             memory_init_sig: None,
+            memory64_init_sig: None,
             table_get_sig: None,
             table_set_sig: None,
             data_drop_sig: None,
@@ -155,6 +234,19 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             table_fill_sig: None,
             externref_inc_sig: None,
             externref_dec_sig: None,
+            throw_sig: None,
+            exception_raise_sig: None,
+            try_call_sig: None,
+            try_call_indirect_sig: None,
+            exception_matches_sig: None,
+            exception_catch_sig: None,
+            exception_rethrow_sig: None,
+            exceptions,
+            return_call_sig: None,
+            return_call_indirect_sig: None,
+            tail_call_pending_sig: None,
+            tail_call_sig: None,
+            tail_calls,
             offsets: VMOffsets::new(target_config.pointer_bytes(), module),
             memory_styles,
             table_styles,
@@ -165,6 +257,20 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         self.target_config.pointer_type()
     }
 
+    /// Whether the memory at `index` is a 64-bit memory.
+    fn is_memory64(&self, index: MemoryIndex) -> bool {
+        self.module.memory64[index]
+    }
+
+    /// The type of the addresses in the memory at `index`.
+    fn memory_index_type(&self, index: MemoryIndex) -> ir::Type {
+        if self.is_memory64(index) {
+            I64
+        } else {
+            I32
+        }
+    }
+
     fn vmctx(&mut self, func: &mut Function) -> ir::GlobalValue {
         self.vmctx.unwrap_or_else(|| {
             let vmctx = func.create_global_value(ir::GlobalValueData::VMContext);
@@ -407,6 +513,24 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         sig
     }
 
+    fn get_memory64_grow_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory64_grow_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Delta, in pages.
+                    AbiParam::new(I64),
+                    // Memory index.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![AbiParam::new(I64)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory64_grow_sig = Some(sig);
+        sig
+    }
+
     /// Return the memory.grow function signature to call for the given index, along with the
     /// translated index value to pass to it and its index in `VMBuiltinFunctionsArray`.
     fn get_memory_grow_func(
@@ -414,6 +538,22 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         func: &mut Function,
         index: MemoryIndex,
     ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        if self.is_memory64(index) {
+            let sig = self.get_memory64_grow_sig(func);
+            return if self.module.is_imported_memory(index) {
+                (
+                    sig,
+                    index.index(),
+                    VMBuiltinFunctionIndex::get_imported_memory64_grow_index(),
+                )
+            } else {
+                (
+                    sig,
+                    self.module.local_memory_index(index).unwrap().index(),
+                    VMBuiltinFunctionIndex::get_memory64_grow_index(),
+                )
+            };
+        }
         if self.module.is_imported_memory(index) {
             (
                 self.get_memory_grow_sig(func),
@@ -481,6 +621,21 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         sig
     }
 
+    fn get_memory64_size_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory64_size_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    AbiParam::new(I32),
+                ],
+                returns: vec![AbiParam::new(I64)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory64_size_sig = Some(sig);
+        sig
+    }
+
     /// Return the memory.size function signature to call for the given index, along with the
     /// translated index value to pass to it and its index in `VMBuiltinFunctionsArray`.
     fn get_memory_size_func(
@@ -488,6 +643,22 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         func: &mut Function,
         index: MemoryIndex,
     ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        if self.is_memory64(index) {
+            let sig = self.get_memory64_size_sig(func);
+            return if self.module.is_imported_memory(index) {
+                (
+                    sig,
+                    index.index(),
+                    VMBuiltinFunctionIndex::get_imported_memory64_size_index(),
+                )
+            } else {
+                (
+                    sig,
+                    self.module.local_memory_index(index).unwrap().index(),
+                    VMBuiltinFunctionIndex::get_memory64_size_index(),
+                )
+            };
+        }
         if self.module.is_imported_memory(index) {
             (
                 self.get_memory32_size_sig(func),
@@ -623,11 +794,49 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         sig
     }
 
+    fn get_memory64_copy_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory64_copy_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Destination address.
+                    AbiParam::new(I64),
+                    // Source address.
+                    AbiParam::new(I64),
+                    // Length.
+                    AbiParam::new(I64),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory64_copy_sig = Some(sig);
+        sig
+    }
+
     fn get_memory_copy_func(
         &mut self,
         func: &mut Function,
         memory_index: MemoryIndex,
     ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        if self.is_memory64(memory_index) {
+            let sig = self.get_memory64_copy_sig(func);
+            return if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+                (
+                    sig,
+                    local_memory_index.index(),
+                    VMBuiltinFunctionIndex::get_memory64_copy_index(),
+                )
+            } else {
+                (
+                    sig,
+                    memory_index.index(),
+                    VMBuiltinFunctionIndex::get_imported_memory64_copy_index(),
+                )
+            };
+        }
         let sig = self.get_memory_copy_sig(func);
         if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
             (
@@ -644,6 +853,67 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         }
     }
 
+    fn get_memory_copy_between_sig(
+        &mut self,
+        func: &mut Function,
+        index_type: ir::Type,
+    ) -> ir::SigRef {
+        let cached = if index_type == I64 {
+            self.memory64_copy_between_sig
+        } else {
+            self.memory32_copy_between_sig
+        };
+        let sig = cached.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Destination memory index.
+                    AbiParam::new(I32),
+                    // Source memory index.
+                    AbiParam::new(I32),
+                    // Destination address.
+                    AbiParam::new(index_type),
+                    // Source address.
+                    AbiParam::new(index_type),
+                    // Length.
+                    AbiParam::new(index_type),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        if index_type == I64 {
+            self.memory64_copy_between_sig = Some(sig);
+        } else {
+            self.memory32_copy_between_sig = Some(sig);
+        }
+        sig
+    }
+
+    /// Return the function signature to call for a `memory.copy` between two
+    /// different memories, along with the type its addresses are passed as
+    /// and its index in `VMBuiltinFunctionsArray`.
+    fn get_memory_copy_between_func(
+        &mut self,
+        func: &mut Function,
+        dst_index: MemoryIndex,
+        src_index: MemoryIndex,
+    ) -> (ir::SigRef, ir::Type, VMBuiltinFunctionIndex) {
+        if self.is_memory64(dst_index) || self.is_memory64(src_index) {
+            (
+                self.get_memory_copy_between_sig(func, I64),
+                I64,
+                VMBuiltinFunctionIndex::get_memory64_copy_between_index(),
+            )
+        } else {
+            (
+                self.get_memory_copy_between_sig(func, I32),
+                I32,
+                VMBuiltinFunctionIndex::get_memory32_copy_between_index(),
+            )
+        }
+    }
+
     fn get_memory_fill_sig(&mut self, func: &mut Function) -> ir::SigRef {
         let sig = self.memory_fill_sig.unwrap_or_else(|| {
             func.import_signature(Signature {
@@ -666,11 +936,49 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         sig
     }
 
+    fn get_memory64_fill_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory64_fill_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Destination address.
+                    AbiParam::new(I64),
+                    // Value.
+                    AbiParam::new(I32),
+                    // Length.
+                    AbiParam::new(I64),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory64_fill_sig = Some(sig);
+        sig
+    }
+
     fn get_memory_fill_func(
         &mut self,
         func: &mut Function,
         memory_index: MemoryIndex,
     ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        if self.is_memory64(memory_index) {
+            let sig = self.get_memory64_fill_sig(func);
+            return if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+                (
+                    sig,
+                    local_memory_index.index(),
+                    VMBuiltinFunctionIndex::get_memory64_fill_index(),
+                )
+            } else {
+                (
+                    sig,
+                    memory_index.index(),
+                    VMBuiltinFunctionIndex::get_imported_memory64_fill_index(),
+                )
+            };
+        }
         let sig = self.get_memory_fill_sig(func);
         if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
             (
@@ -711,12 +1019,42 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         sig
     }
 
+    fn get_memory64_init_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory64_init_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Data index.
+                    AbiParam::new(I32),
+                    // Destination address.
+                    AbiParam::new(I64),
+                    // Source index within the data segment.
+                    AbiParam::new(I32),
+                    // Length.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory64_init_sig = Some(sig);
+        sig
+    }
+
     fn get_memory_init_func(
         &mut self,
         func: &mut Function,
+        memory_index: MemoryIndex,
     ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
-        let sig = self.get_memory_init_sig(func);
-        (sig, VMBuiltinFunctionIndex::get_memory_init_index())
+        if self.is_memory64(memory_index) {
+            let sig = self.get_memory64_init_sig(func);
+            (sig, VMBuiltinFunctionIndex::get_memory64_init_index())
+        } else {
+            let sig = self.get_memory_init_sig(func);
+            (sig, VMBuiltinFunctionIndex::get_memory_init_index())
+        }
     }
 
     fn get_data_drop_sig(&mut self, func: &mut Function) -> ir::SigRef {
@@ -740,6 +1078,251 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         (sig, VMBuiltinFunctionIndex::get_data_drop_index())
     }
 
+    fn get_throw_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.throw_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Tag index.
+                    AbiParam::new(I32),
+                    // Payload.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.throw_sig = Some(sig);
+        sig
+    }
+
+    fn get_exception_raise_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.exception_raise_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![AbiParam::special(
+                    self.pointer_type(),
+                    ArgumentPurpose::VMContext,
+                )],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.exception_raise_sig = Some(sig);
+        sig
+    }
+
+    fn get_try_call_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.try_call_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Function index.
+                    AbiParam::new(I32),
+                    // Arguments and results.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.try_call_sig = Some(sig);
+        sig
+    }
+
+    fn get_try_call_indirect_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.try_call_indirect_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Table index.
+                    AbiParam::new(I32),
+                    // Signature index.
+                    AbiParam::new(I32),
+                    // Element index.
+                    AbiParam::new(I32),
+                    // Arguments and results.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.try_call_indirect_sig = Some(sig);
+        sig
+    }
+
+    fn get_exception_matches_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.exception_matches_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Tag index.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.exception_matches_sig = Some(sig);
+        sig
+    }
+
+    fn get_exception_catch_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.exception_catch_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Payload.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.exception_catch_sig = Some(sig);
+        sig
+    }
+
+    fn get_exception_rethrow_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.exception_rethrow_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Caught exception.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.exception_rethrow_sig = Some(sig);
+        sig
+    }
+
+    fn get_return_call_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.return_call_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Function index.
+                    AbiParam::new(I32),
+                    // Arguments.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.return_call_sig = Some(sig);
+        sig
+    }
+
+    fn get_return_call_indirect_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.return_call_indirect_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Table index.
+                    AbiParam::new(I32),
+                    // Signature index.
+                    AbiParam::new(I32),
+                    // Element index.
+                    AbiParam::new(I32),
+                    // Arguments.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.return_call_indirect_sig = Some(sig);
+        sig
+    }
+
+    fn get_tail_call_pending_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.tail_call_pending_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![AbiParam::special(
+                    self.pointer_type(),
+                    ArgumentPurpose::VMContext,
+                )],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.tail_call_pending_sig = Some(sig);
+        sig
+    }
+
+    fn get_tail_call_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.tail_call_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Results.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.tail_call_sig = Some(sig);
+        sig
+    }
+
+    /// Creates a stack slot for `count` values, one 16-byte slot per value
+    /// as in the arguments of a trampoline, and returns its address.
+    fn make_value_slots(&self, pos: &mut FuncCursor<'_>, count: usize) -> ir::Value {
+        let slot = pos.func.create_stack_slot(ir::StackSlotData::new(
+            ir::StackSlotKind::ExplicitSlot,
+            count as u32 * 16,
+        ));
+        pos.ins().stack_addr(self.pointer_type(), slot, 0)
+    }
+
+    /// Stores `values` to the slots at `address`.
+    fn store_value_slots(pos: &mut FuncCursor<'_>, values: &[ir::Value], address: ir::Value) {
+        // The slots are not 16-byte aligned.
+        let mut mem_flags = ir::MemFlags::new();
+        mem_flags.set_notrap();
+        for (index, value) in values.iter().enumerate() {
+            pos.ins()
+                .store(mem_flags, *value, address, (index * 16) as i32);
+        }
+    }
+
+    /// Loads values of the types `types` from the slots at `address`.
+    fn load_value_slots(
+        pos: &mut FuncCursor<'_>,
+        types: &[ir::Type],
+        address: ir::Value,
+    ) -> Vec<ir::Value> {
+        let mut mem_flags = ir::MemFlags::new();
+        mem_flags.set_notrap();
+        types
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| pos.ins().load(*ty, mem_flags, address, (index * 16) as i32))
+            .collect()
+    }
+
+    /// Creates a stack slot for the payload of an exception of the tag
+    /// `tag_index`, and returns its address.
+    fn make_exception_payload(
+        &mut self,
+        pos: &mut FuncCursor<'_>,
+        tag_index: TagIndex,
+    ) -> (Vec<ir::Type>, ir::Value) {
+        let signature = self.module.tags[tag_index];
+        let types = self.module.signatures[signature]
+            .params()
+            .iter()
+            .map(|ty| type_to_irtype(*ty, self.target_config()).unwrap())
+            .collect::<Vec<_>>();
+        let address = self.make_value_slots(pos, types.len());
+        (types, address)
+    }
+
     /// Translates load of builtin function and returns a pair of values `vmctx`
     /// and address of the loaded function.
     fn translate_load_builtin_function_address(
@@ -1015,6 +1598,7 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
 
     fn make_heap(&mut self, func: &mut ir::Function, index: MemoryIndex) -> WasmResult<ir::Heap> {
         let pointer_type = self.pointer_type();
+        let index_type = self.memory_index_type(index);
 
         let (ptr, base_offset, current_length_offset) = {
             let vmctx = self.vmctx(func);
@@ -1049,7 +1633,10 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
                 let heap_bound = func.create_global_value(ir::GlobalValueData::Load {
                     base: ptr,
                     offset: Offset32::new(current_length_offset),
-                    global_type: type_of_vmmemory_definition_current_length(&self.offsets),
+                    // The bound must have the type of the addresses: 32-bit
+                    // memories are never 4GiB or larger, so loading the low
+                    // half of `current_length` is enough for them.
+                    global_type: index_type,
                     readonly: false,
                 });
                 (
@@ -1083,7 +1670,7 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
             min_size: 0.into(),
             offset_guard_size,
             style: heap_style,
-            index_type: I32,
+            index_type,
         }))
     }
 
@@ -1309,12 +1896,42 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         mut pos: FuncCursor,
         src_index: MemoryIndex,
         _src_heap: ir::Heap,
-        _dst_index: MemoryIndex,
+        dst_index: MemoryIndex,
         _dst_heap: ir::Heap,
         dst: ir::Value,
         src: ir::Value,
         len: ir::Value,
     ) -> WasmResult<()> {
+        if dst_index != src_index {
+            let (func_sig, index_type, func_idx) =
+                self.get_memory_copy_between_func(&mut pos.func, dst_index, src_index);
+            let dst_index_arg = pos.ins().iconst(I32, dst_index.index() as i64);
+            let src_index_arg = pos.ins().iconst(I32, src_index.index() as i64);
+            // Addresses of 32-bit memories are widened if the other memory
+            // is a 64-bit one.
+            let mut args = [dst, src, len];
+            for arg in args.iter_mut() {
+                if pos.func.dfg.value_type(*arg) != index_type {
+                    *arg = pos.ins().uextend(index_type, *arg);
+                }
+            }
+            let (vmctx, func_addr) =
+                self.translate_load_builtin_function_address(&mut pos, func_idx);
+            pos.ins().call_indirect(
+                func_sig,
+                func_addr,
+                &[
+                    vmctx,
+                    dst_index_arg,
+                    src_index_arg,
+                    args[0],
+                    args[1],
+                    args[2],
+                ],
+            );
+            return Ok(());
+        }
+
         let (func_sig, src_index, func_idx) = self.get_memory_copy_func(&mut pos.func, src_index);
 
         let src_index_arg = pos.ins().iconst(I32, src_index as i64);
@@ -1362,7 +1979,7 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         src: ir::Value,
         len: ir::Value,
     ) -> WasmResult<()> {
-        let (func_sig, func_idx) = self.get_memory_init_func(&mut pos.func);
+        let (func_sig, func_idx) = self.get_memory_init_func(&mut pos.func, memory_index);
 
         let memory_index_arg = pos.ins().iconst(I32, memory_index.index() as i64);
         let seg_index_arg = pos.ins().iconst(I32, seg_index as i64);
@@ -1504,6 +2121,235 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         ))
     }
 
+    fn exceptions_enabled(&self) -> bool {
+        self.exceptions
+    }
+
+    fn translate_throw(
+        &mut self,
+        mut pos: FuncCursor,
+        tag_index: TagIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let (_types, payload) = self.make_exception_payload(&mut pos, tag_index);
+        Self::store_value_slots(&mut pos, args, payload);
+
+        let func_sig = self.get_throw_sig(&mut pos.func);
+        let tag_index_arg = pos.ins().iconst(I32, tag_index.as_u32() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_throw_index(),
+        );
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, tag_index_arg, payload]);
+        Ok(())
+    }
+
+    fn translate_rethrow(&mut self, mut pos: FuncCursor, caught: ir::Value) -> WasmResult<()> {
+        let func_sig = self.get_exception_rethrow_sig(&mut pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_exception_rethrow_index(),
+        );
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, caught]);
+        Ok(())
+    }
+
+    fn translate_exception_raise(&mut self, mut pos: FuncCursor) -> WasmResult<()> {
+        let func_sig = self.get_exception_raise_sig(&mut pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_exception_raise_index(),
+        );
+        pos.ins().call_indirect(func_sig, func_addr, &[vmctx]);
+        Ok(())
+    }
+
+    fn translate_try_call(
+        &mut self,
+        mut pos: FuncCursor,
+        callee_index: FunctionIndex,
+        args: &[ir::Value],
+        results: &[ir::Type],
+    ) -> WasmResult<(ir::Value, Vec<ir::Value>)> {
+        let slots = self.make_value_slots(&mut pos, args.len().max(results.len()));
+        Self::store_value_slots(&mut pos, args, slots);
+
+        let func_sig = self.get_try_call_sig(&mut pos.func);
+        let callee_index_arg = pos.ins().iconst(I32, callee_index.as_u32() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_try_call_index(),
+        );
+        let call_inst =
+            pos.ins()
+                .call_indirect(func_sig, func_addr, &[vmctx, callee_index_arg, slots]);
+        let threw = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+        Ok((threw, Self::load_value_slots(&mut pos, results, slots)))
+    }
+
+    fn translate_try_call_indirect(
+        &mut self,
+        mut pos: FuncCursor,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        callee: ir::Value,
+        args: &[ir::Value],
+        results: &[ir::Type],
+    ) -> WasmResult<(ir::Value, Vec<ir::Value>)> {
+        let slots = self.make_value_slots(&mut pos, args.len().max(results.len()));
+        Self::store_value_slots(&mut pos, args, slots);
+
+        let func_sig = self.get_try_call_indirect_sig(&mut pos.func);
+        let table_index_arg = pos.ins().iconst(I32, table_index.as_u32() as i64);
+        let sig_index_arg = pos.ins().iconst(I32, sig_index.as_u32() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_try_call_indirect_index(),
+        );
+        let call_inst = pos.ins().call_indirect(
+            func_sig,
+            func_addr,
+            &[vmctx, table_index_arg, sig_index_arg, callee, slots],
+        );
+        let threw = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+        Ok((threw, Self::load_value_slots(&mut pos, results, slots)))
+    }
+
+    fn translate_exception_matches(
+        &mut self,
+        mut pos: FuncCursor,
+        tag_index: TagIndex,
+    ) -> WasmResult<ir::Value> {
+        let func_sig = self.get_exception_matches_sig(&mut pos.func);
+        let tag_index_arg = pos.ins().iconst(I32, tag_index.as_u32() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_exception_matches_index(),
+        );
+        let call_inst = pos
+            .ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, tag_index_arg]);
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
+    }
+
+    fn translate_exception_catch(
+        &mut self,
+        mut pos: FuncCursor,
+        tag_index: Option<TagIndex>,
+    ) -> WasmResult<(ir::Value, Vec<ir::Value>)> {
+        // A `catch_all` clause doesn't see the payload.
+        let (types, payload) = match tag_index {
+            Some(tag_index) => self.make_exception_payload(&mut pos, tag_index),
+            None => (vec![], pos.ins().iconst(self.pointer_type(), 0)),
+        };
+
+        let func_sig = self.get_exception_catch_sig(&mut pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_exception_catch_index(),
+        );
+        let call_inst = pos
+            .ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, payload]);
+        let caught = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+        let values = Self::load_value_slots(&mut pos, &types, payload);
+        Ok((caught, values))
+    }
+
+    fn translate_exception_release(
+        &mut self,
+        mut pos: FuncCursor,
+        caught: ir::Value,
+    ) -> WasmResult<()> {
+        let func_sig = self.get_exception_rethrow_sig(&mut pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_exception_release_index(),
+        );
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, caught]);
+        Ok(())
+    }
+
+    fn tail_calls_enabled(&self) -> bool {
+        self.tail_calls
+    }
+
+    fn translate_return_call(
+        &mut self,
+        mut pos: FuncCursor,
+        callee_index: FunctionIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let slots = self.make_value_slots(&mut pos, args.len());
+        Self::store_value_slots(&mut pos, args, slots);
+
+        let func_sig = self.get_return_call_sig(&mut pos.func);
+        let callee_index_arg = pos.ins().iconst(I32, callee_index.as_u32() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_return_call_index(),
+        );
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, callee_index_arg, slots]);
+        Ok(())
+    }
+
+    fn translate_return_call_indirect(
+        &mut self,
+        mut pos: FuncCursor,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        callee: ir::Value,
+        args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let slots = self.make_value_slots(&mut pos, args.len());
+        Self::store_value_slots(&mut pos, args, slots);
+
+        let func_sig = self.get_return_call_indirect_sig(&mut pos.func);
+        let table_index_arg = pos.ins().iconst(I32, table_index.as_u32() as i64);
+        let sig_index_arg = pos.ins().iconst(I32, sig_index.as_u32() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_return_call_indirect_index(),
+        );
+        pos.ins().call_indirect(
+            func_sig,
+            func_addr,
+            &[vmctx, table_index_arg, sig_index_arg, callee, slots],
+        );
+        Ok(())
+    }
+
+    fn translate_tail_call_pending(&mut self, mut pos: FuncCursor) -> WasmResult<ir::Value> {
+        let func_sig = self.get_tail_call_pending_sig(&mut pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_tail_call_pending_index(),
+        );
+        let call_inst = pos.ins().call_indirect(func_sig, func_addr, &[vmctx]);
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
+    }
+
+    fn translate_tail_call(
+        &mut self,
+        mut pos: FuncCursor,
+        results: &[ir::Type],
+    ) -> WasmResult<Vec<ir::Value>> {
+        let slots = self.make_value_slots(&mut pos, results.len());
+
+        let func_sig = self.get_tail_call_sig(&mut pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_tail_call_index(),
+        );
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, slots]);
+        Ok(Self::load_value_slots(&mut pos, results, slots))
+    }
+
     fn get_global_type(&self, global_index: GlobalIndex) -> Option<WasmerType> {
         Some(self.module.globals.get(global_index)?.ty)
     }
@@ -1538,6 +2384,12 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         self.module.signatures.get(sig_index)
     }
 
+    fn get_tag_type(&self, tag_index: TagIndex) -> Option<&FunctionType> {
+        self.module
+            .signatures
+            .get(*self.module.tags.get(tag_index)?)
+    }
+
     fn translate_drop_locals(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()> {
         // TODO: this allocation can be removed without too much effort but it will require
         //       maneuvering around the borrow checker
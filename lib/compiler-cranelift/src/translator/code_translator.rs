@@ -96,7 +96,8 @@ use wasmer_compiler::wasmparser::{MemoryImmediate, Operator, Type as WPType};
 use wasmer_compiler::WasmResult;
 use wasmer_compiler::{wasm_unsupported, ModuleTranslationState};
 use wasmer_types::{
-    FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TableIndex, Type as WasmerType,
+    FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TableIndex, TagIndex,
+    Type as WasmerType,
 };
 
 // Clippy warns about "align: _" but its important to document that the align field is ignored
@@ -430,6 +431,14 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             let next_block = frame.following_code();
 
             if !builder.is_unreachable() || !builder.is_pristine() {
+                // The exception caught by a `catch` clause dies at its end.
+                if let ControlStackFrame::Try {
+                    caught: Some(caught),
+                    ..
+                } = frame
+                {
+                    environ.translate_exception_release(builder.cursor(), caught)?;
+                }
                 let return_count = frame.num_return_values();
                 let return_args = state.peekn(return_count);
                 canonicalise_then_jump(builder, frame.following_code(), return_args);
@@ -441,6 +450,14 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 // below.
             }
 
+            if let ControlStackFrame::Try {
+                handler: Some(handler),
+                ..
+            } = frame
+            {
+                translate_uncaught_exception(handler, builder, state, environ)?;
+            }
+
             builder.switch_to_block(next_block);
             builder.seal_block(next_block);
 
@@ -604,14 +621,51 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             state.popn(return_count);
             state.reachable = false;
         }
-        /********************************** Exception handing **********************************/
-        Operator::Try { .. }
-        | Operator::Catch { .. }
-        | Operator::Throw { .. }
-        | Operator::Unwind
-        | Operator::Rethrow { .. }
-        | Operator::Delegate { .. }
-        | Operator::CatchAll => {
+        /********************************** Exception handing **********************************
+         * A thrown exception stays pending until a `catch` clause catches it. Every `throw` is
+         * followed by a branch to the handler of the innermost `try`, which checks the pending
+         * exception against each of its `catch` clauses in turn, or, if there is no such `try`,
+         * by a call which raises the exception and unwinds the stack as a trap does. The calls
+         * in the body of a `try` are made through the runtime, which catches the exceptions
+         * raised by the callee and makes them pending again, and are then followed by the same
+         * branch to the handler. Other calls don't check for exceptions at all.
+         ***********************************************************************************/
+        Operator::Try { ty } => {
+            let (params, results) = module_translation_state.blocktype_params_results(*ty)?;
+            let next = block_with_params(builder, results, environ)?;
+            let handler = builder.create_block();
+            state.push_try(handler, next, params.len(), results.len());
+        }
+        Operator::Catch { index } => {
+            translate_catch(Some(TagIndex::from_u32(*index)), builder, state, environ)?;
+        }
+        Operator::CatchAll => translate_catch(None, builder, state, environ)?,
+        Operator::Throw { index } => {
+            let tag_index = TagIndex::from_u32(*index);
+            let num_args = environ.get_tag_type(tag_index).unwrap().params().len();
+            let (args, _) = state.peekn(num_args);
+            environ.translate_throw(builder.cursor(), tag_index, args)?;
+            state.popn(num_args);
+            translate_exception_propagation(builder, state, environ)?;
+            state.reachable = false;
+        }
+        Operator::Rethrow { relative_depth } => {
+            let i = state.control_stack.len() - 1 - (*relative_depth as usize);
+            let caught = match state.control_stack[i] {
+                ControlStackFrame::Try {
+                    caught: Some(caught),
+                    ..
+                } => caught,
+                _ => unreachable!(),
+            };
+            environ.translate_rethrow(builder.cursor(), caught)?;
+            translate_exception_propagation(builder, state, environ)?;
+            state.reachable = false;
+        }
+        Operator::Delegate { relative_depth } => {
+            translate_delegate(*relative_depth, builder, state, environ)?;
+        }
+        Operator::Unwind => {
             return Err(wasm_unsupported!(
                 "proposed exception handling operator {:?}",
                 op
@@ -624,6 +678,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
          ************************************************************************************/
         Operator::Call { function_index } => {
             let (fref, num_args) = state.get_direct_func(builder.func, *function_index, environ)?;
+            let try_body = environ.exceptions_enabled() && in_try_body(state);
 
             let (args, _args_metadata) = state.peekn_mut(num_args);
 
@@ -635,16 +690,6 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             });
             bitcast_arguments(args, &types, builder);
             let func_index = FunctionIndex::from_u32(*function_index);
-
-            let call = environ.translate_call(builder.cursor(), func_index, fref, args)?;
-            let inst_results = builder.inst_results(call);
-            debug_assert_eq!(
-                inst_results.len(),
-                builder.func.dfg.signatures[builder.func.dfg.ext_funcs[fref].signature]
-                    .returns
-                    .len(),
-                "translate_call results should match the call signature"
-            );
             let func_type = environ.get_function_type(func_index).unwrap();
             let mut results_metadata = Vec::with_capacity(func_type.results().len());
             for result in func_type.results() {
@@ -657,8 +702,38 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                     Default::default()
                 });
             }
-            state.popn(num_args);
-            state.pushn(inst_results, &results_metadata);
+
+            if try_body {
+                let callee_signature =
+                    &builder.func.dfg.signatures[builder.func.dfg.ext_funcs[fref].signature];
+                let result_types = wasm_param_types(&callee_signature.returns, |i| {
+                    environ.is_wasm_return(&callee_signature, i)
+                });
+                let (threw, results) = environ.translate_try_call(
+                    builder.cursor(),
+                    func_index,
+                    args,
+                    &result_types,
+                )?;
+                translate_try_call_check(threw, builder, state, environ)?;
+                state.popn(num_args);
+                state.pushn(&results, &results_metadata);
+            } else {
+                let call = environ.translate_call(builder.cursor(), func_index, fref, args)?;
+                let inst_results = builder.inst_results(call);
+                debug_assert_eq!(
+                    inst_results.len(),
+                    builder.func.dfg.signatures[builder.func.dfg.ext_funcs[fref].signature]
+                        .returns
+                        .len(),
+                    "translate_call results should match the call signature"
+                );
+                state.popn(num_args);
+                state.pushn(inst_results, &results_metadata);
+                if environ.tail_calls_enabled() {
+                    translate_tail_call_check(results_metadata.len(), builder, state, environ)?;
+                }
+            }
         }
         Operator::CallIndirect { index, table_index } => {
             // `index` is the index of the function's signature and `table_index` is the index of
@@ -666,6 +741,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             let (sigref, num_args) = state.get_indirect_sig(builder.func, *index, environ)?;
             let table = state.get_or_create_table(builder.func, *table_index, environ)?;
             let (callee, _) = state.pop1();
+            let try_body = environ.exceptions_enabled() && in_try_body(state);
 
             // Bitcast any vector arguments to their default type, I8X16, before calling.
             let callee_signature = &builder.func.dfg.signatures[sigref];
@@ -677,22 +753,6 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
 
             let (args, _args_metadata) = state.peekn(num_args);
             let sig_idx = SignatureIndex::from_u32(*index);
-
-            let call = environ.translate_call_indirect(
-                builder.cursor(),
-                TableIndex::from_u32(*table_index),
-                table,
-                sig_idx,
-                sigref,
-                callee,
-                args,
-            )?;
-            let inst_results = builder.inst_results(call);
-            debug_assert_eq!(
-                inst_results.len(),
-                builder.func.dfg.signatures[sigref].returns.len(),
-                "translate_call_indirect results should match the call signature"
-            );
             let func_type = environ.get_function_sig(sig_idx).unwrap();
             let mut results_metadata = Vec::with_capacity(func_type.results().len());
             for result in func_type.results() {
@@ -705,8 +765,45 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                     Default::default()
                 });
             }
-            state.popn(num_args);
-            state.pushn(inst_results, &results_metadata);
+
+            if try_body {
+                let callee_signature = &builder.func.dfg.signatures[sigref];
+                let result_types = wasm_param_types(&callee_signature.returns, |i| {
+                    environ.is_wasm_return(&callee_signature, i)
+                });
+                let (threw, results) = environ.translate_try_call_indirect(
+                    builder.cursor(),
+                    TableIndex::from_u32(*table_index),
+                    sig_idx,
+                    callee,
+                    args,
+                    &result_types,
+                )?;
+                translate_try_call_check(threw, builder, state, environ)?;
+                state.popn(num_args);
+                state.pushn(&results, &results_metadata);
+            } else {
+                let call = environ.translate_call_indirect(
+                    builder.cursor(),
+                    TableIndex::from_u32(*table_index),
+                    table,
+                    sig_idx,
+                    sigref,
+                    callee,
+                    args,
+                )?;
+                let inst_results = builder.inst_results(call);
+                debug_assert_eq!(
+                    inst_results.len(),
+                    builder.func.dfg.signatures[sigref].returns.len(),
+                    "translate_call_indirect results should match the call signature"
+                );
+                state.popn(num_args);
+                state.pushn(inst_results, &results_metadata);
+                if environ.tail_calls_enabled() {
+                    translate_tail_call_check(results_metadata.len(), builder, state, environ)?;
+                }
+            }
         }
         /******************************* Memory management ***********************************
          * Memory management is handled by environment. It is usually translated into calls to
@@ -1970,8 +2067,40 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
         | Operator::I8x16Popcnt => {
             return Err(wasm_unsupported!("proposed simd operator {:?}", op));
         }
-        Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. } => {
-            return Err(wasm_unsupported!("proposed tail-call operator {:?}", op));
+        /******************************* Tail calls ******************************************
+         * A tail call is made by the caller of the function which makes it: the function makes
+         * the call pending and returns, and every call checks for a pending tail call when it
+         * returns and makes it, which gives the results of the call. The stack hence doesn't grow
+         * with a chain of tail calls.
+         ************************************************************************************/
+        Operator::ReturnCall { function_index } => {
+            let func_index = FunctionIndex::from_u32(*function_index);
+            let num_args = environ
+                .get_function_type(func_index)
+                .unwrap()
+                .params()
+                .len();
+            let (args, _) = state.peekn(num_args);
+            environ.translate_return_call(builder.cursor(), func_index, args)?;
+            state.popn(num_args);
+            return_zero_values(builder);
+            state.reachable = false;
+        }
+        Operator::ReturnCallIndirect { index, table_index } => {
+            let sig_idx = SignatureIndex::from_u32(*index);
+            let num_args = environ.get_function_sig(sig_idx).unwrap().params().len();
+            let (callee, _) = state.pop1();
+            let (args, _) = state.peekn(num_args);
+            environ.translate_return_call_indirect(
+                builder.cursor(),
+                TableIndex::from_u32(*table_index),
+                sig_idx,
+                callee,
+                args,
+            )?;
+            state.popn(num_args);
+            return_zero_values(builder);
+            state.reachable = false;
         }
     };
     Ok(())
@@ -2004,9 +2133,30 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
                 ty,
             );
         }
-        Operator::Loop { ty: _ } | Operator::Block { ty: _ } => {
+        Operator::Loop { ty: _ } | Operator::Block { ty: _ } | Operator::Try { ty: _ } => {
             state.push_block(ir::Block::reserved_value(), 0, 0);
         }
+        // The clauses of a `try` whose body ends unreachable may still catch
+        // exceptions, unlike those of an unreachable `try`, for which we
+        // pushed a placeholder block.
+        Operator::Catch { index } => {
+            if let Some(ControlStackFrame::Try { .. }) = state.control_stack.last() {
+                translate_catch(Some(TagIndex::from_u32(index)), builder, state, environ)?;
+            }
+        }
+        Operator::CatchAll => {
+            if let Some(ControlStackFrame::Try { .. }) = state.control_stack.last() {
+                translate_catch(None, builder, state, environ)?;
+            }
+        }
+        Operator::Delegate { relative_depth } => {
+            if let Some(ControlStackFrame::Try { .. }) = state.control_stack.last() {
+                translate_delegate(relative_depth, builder, state, environ)?;
+            } else {
+                let frame = state.control_stack.pop().unwrap();
+                frame.truncate_value_stack_to_original_size(&mut state.stack);
+            }
+        }
         Operator::Else => {
             let i = state.control_stack.len() - 1;
             match state.control_stack[i] {
@@ -2056,9 +2206,15 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
             }
         }
         Operator::End => {
+            let frame = state.control_stack.pop().unwrap();
+            if let ControlStackFrame::Try {
+                handler: Some(handler),
+                ..
+            } = frame
+            {
+                translate_uncaught_exception(handler, builder, state, environ)?;
+            }
             let stack = &mut state.stack;
-            let control_stack = &mut state.control_stack;
-            let frame = control_stack.pop().unwrap();
 
             // Pop unused parameters from stack.
             frame.truncate_value_stack_to_original_size(stack);
@@ -2295,7 +2451,20 @@ fn fold_atomic_mem_addr(
     builder: &mut FunctionBuilder,
 ) -> Value {
     let access_ty_bytes = access_ty.bytes();
-    let final_lma = if memarg.offset > 0 {
+    let final_lma = if memarg.offset > 0 && builder.func.dfg.value_type(linear_mem_addr) == I64 {
+        // Addresses in 64-bit memories can't be widened: check that adding
+        // the offset doesn't wrap around instead.
+        let a = builder
+            .ins()
+            .iadd_imm(linear_mem_addr, i64::from(memarg.offset));
+        let cflags = builder.ins().ifcmp(a, linear_mem_addr);
+        builder.ins().trapif(
+            IntCC::UnsignedLessThan,
+            cflags,
+            ir::TrapCode::HeapOutOfBounds,
+        );
+        a
+    } else if memarg.offset > 0 {
         assert!(builder.func.dfg.value_type(linear_mem_addr) == I32);
         let linear_mem_addr = builder.ins().uextend(I64, linear_mem_addr);
         let a = builder
@@ -2624,6 +2793,281 @@ fn translate_br_if_args(
     (br_destination, inputs)
 }
 
+/// Ends the body or the previous clause of the innermost `try`, and starts its `catch` clause
+/// for the tag `tag_index`, or its `catch_all` clause if `None`.
+fn translate_catch<FE: FuncEnvironment + ?Sized>(
+    tag_index: Option<TagIndex>,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let i = state.control_stack.len() - 1;
+    let (destination, handler, num_return_values, caught) = match state.control_stack[i] {
+        ControlStackFrame::Try {
+            destination,
+            handler: Some(handler),
+            num_return_values,
+            caught,
+            ..
+        } => (destination, handler, num_return_values, caught),
+        _ => unreachable!(),
+    };
+    if state.reachable {
+        if let Some(caught) = caught {
+            environ.translate_exception_release(builder.cursor(), caught)?;
+        }
+        canonicalise_then_jump(builder, destination, state.peekn(num_return_values));
+        state.control_stack[i].set_branched_to_exit();
+    }
+    state.control_stack[i].truncate_value_stack_to_original_size(&mut state.stack);
+
+    // All the branches to the handler are in the body of the `try`.
+    builder.switch_to_block(handler);
+    builder.seal_block(handler);
+    let next_handler = match tag_index {
+        Some(tag_index) => {
+            let matches = environ.translate_exception_matches(builder.cursor(), tag_index)?;
+            let next_handler = builder.create_block();
+            builder.ins().brz(matches, next_handler, &[]);
+            let clause = builder.create_block();
+            builder.ins().jump(clause, &[]);
+            builder.seal_block(clause); // The only predecessor is the handler.
+            builder.switch_to_block(clause);
+            Some(next_handler)
+        }
+        None => None,
+    };
+    let (caught, payload) = environ.translate_exception_catch(builder.cursor(), tag_index)?;
+    state.stack.extend_from_slice(&payload);
+    if let ControlStackFrame::Try {
+        ref mut handler,
+        ref mut in_catch,
+        caught: ref mut frame_caught,
+        ..
+    } = state.control_stack[i]
+    {
+        *handler = next_handler;
+        *in_catch = true;
+        *frame_caught = Some(caught);
+    }
+    state.reachable = true;
+    Ok(())
+}
+
+/// Ends the innermost `try` with a `delegate`: the exceptions thrown in its body are handled
+/// by the `try` at `relative_depth`, or by the caller if it's the function body.
+fn translate_delegate<FE: FuncEnvironment + ?Sized>(
+    relative_depth: u32,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let frame = state.control_stack.pop().unwrap();
+    let (destination, handler) = match frame {
+        ControlStackFrame::Try {
+            destination,
+            handler: Some(handler),
+            ..
+        } => (destination, handler),
+        _ => unreachable!(),
+    };
+    if state.reachable {
+        canonicalise_then_jump(builder, destination, state.peekn(frame.num_return_values()));
+    }
+    let destination_is_reachable = state.reachable || frame.exit_is_branched_to();
+
+    builder.switch_to_block(handler);
+    builder.seal_block(handler);
+    let i = state.control_stack.len() - 1 - (relative_depth as usize);
+    let target = match state.control_stack[i] {
+        ControlStackFrame::Try { .. } => Some(i),
+        _ => None,
+    };
+    translate_exception_propagation_to(target, builder, state, environ)?;
+
+    frame.truncate_value_stack_to_original_size(&mut state.stack);
+    state.reachable = destination_is_reachable;
+    if destination_is_reachable {
+        builder.switch_to_block(destination);
+        builder.seal_block(destination);
+        state
+            .stack
+            .extend_from_slice(builder.block_params(destination));
+    }
+    Ok(())
+}
+
+/// Propagates the exceptions that no clause of a `try` caught, once its frame was popped.
+fn translate_uncaught_exception<FE: FuncEnvironment + ?Sized>(
+    handler: ir::Block,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    builder.switch_to_block(handler);
+    builder.seal_block(handler);
+    translate_exception_propagation(builder, state, environ)
+}
+
+/// Whether we are in the body of a `try`, where calls catch the exceptions they raise.
+fn in_try_body(state: &FuncTranslationState) -> bool {
+    state.control_stack.iter().any(|frame| {
+        matches!(
+            frame,
+            ControlStackFrame::Try {
+                in_catch: false,
+                ..
+            }
+        )
+    })
+}
+
+/// Propagates the exception caught by a call in the body of a `try`, if `threw` tells it did
+/// catch one.
+fn translate_try_call_check<FE: FuncEnvironment + ?Sized>(
+    threw: ir::Value,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let propagation = builder.create_block();
+    builder.ins().brnz(threw, propagation, &[]);
+    let next_block = builder.create_block();
+    builder.ins().jump(next_block, &[]);
+    builder.seal_block(propagation); // The only predecessor is the current block.
+    builder.seal_block(next_block); // The only predecessor is the current block.
+
+    builder.switch_to_block(propagation);
+    translate_exception_propagation(builder, state, environ)?;
+    builder.switch_to_block(next_block);
+    Ok(())
+}
+
+/// Branches to the handler of the innermost `try` whose body we are in, if any.
+fn translate_exception_propagation<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let target = state.control_stack.iter().rposition(|frame| {
+        matches!(
+            frame,
+            ControlStackFrame::Try {
+                in_catch: false,
+                ..
+            }
+        )
+    });
+    translate_exception_propagation_to(target, builder, state, environ)
+}
+
+/// Branches to the handler of the `try` at index `target` of the control stack, or raises the
+/// exception to the caller if `None`, releasing the exceptions caught by the clauses we leave
+/// on the way.
+fn translate_exception_propagation_to<FE: FuncEnvironment + ?Sized>(
+    target: Option<usize>,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let start = target.map_or(0, |target| target + 1);
+    for frame in state.control_stack[start..].iter().rev() {
+        if let ControlStackFrame::Try {
+            caught: Some(caught),
+            ..
+        } = *frame
+        {
+            environ.translate_exception_release(builder.cursor(), caught)?;
+        }
+    }
+    match target.map(|target| &state.control_stack[target]) {
+        Some(ControlStackFrame::Try {
+            handler: Some(handler),
+            ..
+        }) => {
+            builder.ins().jump(*handler, &[]);
+        }
+        Some(_) => unreachable!(),
+        None => {
+            environ.translate_exception_raise(builder.cursor())?;
+            builder.ins().trap(ir::TrapCode::UnreachableCodeReached);
+        }
+    }
+    Ok(())
+}
+
+/// Returns zero values, which the caller ignores.
+fn return_zero_values(builder: &mut FunctionBuilder) {
+    let return_types = builder
+        .func
+        .signature
+        .returns
+        .iter()
+        .map(|param| param.value_type)
+        .collect::<Vec<_>>();
+    let return_args = return_types
+        .into_iter()
+        .map(|ty| zero_value(builder, ty))
+        .collect::<Vec<_>>();
+    builder.ins().return_(&return_args);
+}
+
+/// Makes the tail calls the callee left pending, if any, whose results then replace the
+/// `num_results` results of the call on top of the stack.
+fn translate_tail_call_check<FE: FuncEnvironment + ?Sized>(
+    num_results: usize,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let results = state.peekn(num_results).0.to_vec();
+    let types = results
+        .iter()
+        .map(|result| builder.func.dfg.value_type(*result))
+        .collect::<Vec<_>>();
+    let pending = environ.translate_tail_call_pending(builder.cursor())?;
+    let tail_call = builder.create_block();
+    let next_block = builder.create_block();
+    for ty in &types {
+        builder.append_block_param(next_block, *ty);
+    }
+    builder.ins().brnz(pending, tail_call, &[]);
+    builder.ins().jump(next_block, &results);
+    builder.seal_block(tail_call); // The only predecessor is the current block.
+
+    builder.switch_to_block(tail_call);
+    let tail_call_results = environ.translate_tail_call(builder.cursor(), &types)?;
+    builder.ins().jump(next_block, &tail_call_results);
+    builder.seal_block(next_block);
+
+    builder.switch_to_block(next_block);
+    state.popn(num_results);
+    state
+        .stack
+        .extend_from_slice(builder.block_params(next_block));
+    Ok(())
+}
+
+/// The zero value of the type `ty`, or the null reference.
+fn zero_value(builder: &mut FunctionBuilder, ty: ir::Type) -> Value {
+    if ty.is_ref() {
+        builder.ins().null(ty)
+    } else if ty.is_vector() {
+        let constant_handle = builder
+            .func
+            .dfg
+            .constants
+            .insert(vec![0; ty.bytes() as usize].into());
+        builder.ins().vconst(ty, constant_handle)
+    } else if ty == F32 {
+        builder.ins().f32const(ir::immediates::Ieee32::with_bits(0))
+    } else if ty == F64 {
+        builder.ins().f64const(ir::immediates::Ieee64::with_bits(0))
+    } else {
+        builder.ins().iconst(ty, 0)
+    }
+}
+
 /// Determine the returned value type of a WebAssembly operator
 fn type_of(operator: &Operator) -> Type {
     match operator {
@@ -16,7 +16,7 @@ use wasmer_compiler::wasmparser::{Operator, Type};
 use wasmer_compiler::WasmResult;
 use wasmer_types::{
     FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex,
-    TableIndex, Type as WasmerType,
+    TableIndex, TagIndex, Type as WasmerType,
 };
 
 /// The value of a WebAssembly global variable.
@@ -454,6 +454,118 @@ pub trait FuncEnvironment: TargetEnvironment {
         count: ir::Value,
     ) -> WasmResult<ir::Value>;
 
+    /// Whether the exception handling proposal is enabled, aka whether the
+    /// calls in the body of a `try` must catch the exceptions they raise.
+    fn exceptions_enabled(&self) -> bool {
+        false
+    }
+
+    /// Translate a `throw` WebAssembly instruction, which makes the thrown
+    /// exception pending.
+    fn translate_throw(
+        &mut self,
+        pos: FuncCursor,
+        tag_index: TagIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()>;
+
+    /// Translate a `rethrow` WebAssembly instruction, which makes the caught
+    /// exception `caught` pending again.
+    fn translate_rethrow(&mut self, pos: FuncCursor, caught: ir::Value) -> WasmResult<()>;
+
+    /// Raises the pending exception, which no `try` of the function caught,
+    /// to the innermost call which catches exceptions. Doesn't return.
+    fn translate_exception_raise(&mut self, pos: FuncCursor) -> WasmResult<()>;
+
+    /// Translate a `call` WebAssembly instruction in the body of a `try`,
+    /// which catches the exception raised by the callee.
+    ///
+    /// Returns an `i32` telling whether an exception is then pending, and
+    /// the results of the call, of the types `results`, which are only
+    /// valid if not.
+    fn translate_try_call(
+        &mut self,
+        pos: FuncCursor,
+        callee_index: FunctionIndex,
+        args: &[ir::Value],
+        results: &[ir::Type],
+    ) -> WasmResult<(ir::Value, Vec<ir::Value>)>;
+
+    /// Translate a `call_indirect` WebAssembly instruction in the body of a
+    /// `try`, as [`FuncEnvironment::translate_try_call`] does a `call`.
+    #[allow(clippy::too_many_arguments)]
+    fn translate_try_call_indirect(
+        &mut self,
+        pos: FuncCursor,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        callee: ir::Value,
+        args: &[ir::Value],
+        results: &[ir::Type],
+    ) -> WasmResult<(ir::Value, Vec<ir::Value>)>;
+
+    /// Returns an `i32` telling whether the pending exception has the tag
+    /// `tag_index`.
+    fn translate_exception_matches(
+        &mut self,
+        pos: FuncCursor,
+        tag_index: TagIndex,
+    ) -> WasmResult<ir::Value>;
+
+    /// Catches the pending exception, for a `catch` clause of `tag_index`
+    /// or a `catch_all` clause if `None`.
+    ///
+    /// Returns the caught exception, to rethrow or release it, and the
+    /// values of its payload.
+    fn translate_exception_catch(
+        &mut self,
+        pos: FuncCursor,
+        tag_index: Option<TagIndex>,
+    ) -> WasmResult<(ir::Value, Vec<ir::Value>)>;
+
+    /// Releases the caught exception `caught` at the end of its `catch`
+    /// clause.
+    fn translate_exception_release(&mut self, pos: FuncCursor, caught: ir::Value)
+        -> WasmResult<()>;
+
+    /// Whether the tail-call proposal is enabled, aka whether calls must
+    /// make the pending tail calls when they return.
+    fn tail_calls_enabled(&self) -> bool {
+        false
+    }
+
+    /// Translate a `return_call` WebAssembly instruction, which makes the
+    /// call to `callee_index` pending.
+    fn translate_return_call(
+        &mut self,
+        pos: FuncCursor,
+        callee_index: FunctionIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()>;
+
+    /// Translate a `return_call_indirect` WebAssembly instruction, which
+    /// checks the signature of the function at `callee` in the table
+    /// `table_index` and makes the call pending.
+    fn translate_return_call_indirect(
+        &mut self,
+        pos: FuncCursor,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        callee: ir::Value,
+        args: &[ir::Value],
+    ) -> WasmResult<()>;
+
+    /// Returns an `i32` telling whether a tail call is pending.
+    fn translate_tail_call_pending(&mut self, pos: FuncCursor) -> WasmResult<ir::Value>;
+
+    /// Makes the pending tail calls, and returns the results of the last
+    /// one, of the types `results`.
+    fn translate_tail_call(
+        &mut self,
+        pos: FuncCursor,
+        results: &[ir::Type],
+    ) -> WasmResult<Vec<ir::Value>>;
+
     /// Emit code at the beginning of every wasm loop.
     ///
     /// This can be used to insert explicit interrupt or safepoint checking at
@@ -506,6 +618,9 @@ pub trait FuncEnvironment: TargetEnvironment {
     /// Get the type of a function with the given signature index.
     fn get_function_sig(&self, sig_index: SignatureIndex) -> Option<&FunctionType>;
 
+    /// Get the type of the payload of the exceptions with the given tag.
+    fn get_tag_type(&self, tag_index: TagIndex) -> Option<&FunctionType>;
+
     /// Drops all locals that need to be dropped. Useful for returning from functions.
     fn translate_drop_locals(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()>;
 }
@@ -12,9 +12,9 @@ use rayon::iter::ParallelBridge;
 use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use std::sync::Arc;
 use wasmer_compiler::{
-    Compilation, CompileError, CompileModuleInfo, Compiler, CustomSection, CustomSectionProtection,
-    Dwarf, FunctionBodyData, ModuleMiddleware, ModuleTranslationState, RelocationTarget,
-    SectionBody, SectionIndex, Symbol, SymbolRegistry, Target,
+    Compilation, CompileError, CompileModuleInfo, Compiler, CompilerConfig, CustomSection,
+    CustomSectionProtection, Dwarf, FunctionBodyData, ModuleMiddleware, ModuleTranslationState,
+    RelocationTarget, SectionBody, SectionIndex, Symbol, SymbolRegistry, Target,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
@@ -49,10 +49,14 @@ impl SymbolRegistry for ShortNames {
             Symbol::Section(index) => format!("s{}", index.index()),
             Symbol::FunctionCallTrampoline(index) => format!("t{}", index.index()),
             Symbol::DynamicFunctionTrampoline(index) => format!("d{}", index.index()),
+            Symbol::Metadata => "m".to_string(),
         }
     }
 
     fn name_to_symbol(&self, name: &str) -> Option<Symbol> {
+        if name == "m" {
+            return Some(Symbol::Metadata);
+        }
         if name.len() < 2 {
             return None;
         }
@@ -101,6 +105,7 @@ impl LLVMCompiler {
                     self.config(),
                     &compile_info.memory_styles,
                     &compile_info.table_styles,
+                    compile_info.features.tail_call,
                     symbol_registry,
                 )?;
                 Ok(module.write_bitcode_to_memory().as_slice().to_vec())
@@ -166,8 +171,11 @@ impl LLVMCompiler {
                 .collect::<Vec<_>>()
                 .as_slice(),
         );
-        let metadata_gv =
-            merged_module.add_global(metadata_init.get_type(), None, "WASMER_METADATA");
+        let metadata_gv = merged_module.add_global(
+            metadata_init.get_type(),
+            None,
+            &symbol_registry.symbol_to_name(Symbol::Metadata),
+        );
         metadata_gv.set_initializer(&metadata_init);
         metadata_gv.set_linkage(Linkage::DLLExport);
         metadata_gv.set_dll_storage_class(DLLStorageClass::Export);
@@ -193,6 +201,10 @@ impl Compiler for LLVMCompiler {
         &self.config.middlewares
     }
 
+    fn fingerprint(&self) -> String {
+        self.config.fingerprint()
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -254,6 +266,7 @@ impl Compiler for LLVMCompiler {
                         self.config(),
                         memory_styles,
                         &table_styles,
+                        compile_info.features.tail_call,
                         &ShortNames {},
                     )
                 },
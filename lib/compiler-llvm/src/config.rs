@@ -9,7 +9,9 @@ use loupe::MemoryUsage;
 use std::fmt::Debug;
 use std::sync::Arc;
 use target_lexicon::Architecture;
-use wasmer_compiler::{Compiler, CompilerConfig, ModuleMiddleware, Target, Triple};
+use wasmer_compiler::{
+    Compiler, CompilerConfig, ModuleMiddleware, ModuleMiddlewareChain, Target, Triple,
+};
 use wasmer_types::{FunctionType, LocalFunctionIndex};
 
 /// The InkWell ModuleInfo type
@@ -218,6 +220,16 @@ impl CompilerConfig for LLVM {
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>) {
         self.middlewares.push(middleware);
     }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "llvm opt_level={:?} nan_canonicalization={} pic={} middlewares={}",
+            self.opt_level,
+            self.enable_nan_canonicalization,
+            self.is_pic,
+            self.middlewares.fingerprint()
+        )
+    }
 }
 
 impl Default for LLVM {
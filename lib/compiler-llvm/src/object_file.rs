@@ -58,6 +58,11 @@ where
         "wasmer_vm_imported_memory32_size".to_string(),
         LibCall::ImportedMemory32Size,
     );
+    libcalls.insert("wasmer_vm_memory64_size".to_string(), LibCall::Memory64Size);
+    libcalls.insert(
+        "wasmer_vm_imported_memory64_size".to_string(),
+        LibCall::ImportedMemory64Size,
+    );
     libcalls.insert("wasmer_vm_table_copy".to_string(), LibCall::TableCopy);
     libcalls.insert("wasmer_vm_table_init".to_string(), LibCall::TableInit);
     libcalls.insert("wasmer_vm_table_fill".to_string(), LibCall::TableFill);
@@ -94,7 +99,36 @@ where
         LibCall::ImportedMemory32Fill,
     );
     libcalls.insert("wasmer_vm_memory32_init".to_string(), LibCall::Memory32Init);
+    libcalls.insert("wasmer_vm_memory64_copy".to_string(), LibCall::Memory64Copy);
+    libcalls.insert(
+        "wasmer_vm_imported_memory64_copy".to_string(),
+        LibCall::ImportedMemory64Copy,
+    );
+    libcalls.insert("wasmer_vm_memory64_fill".to_string(), LibCall::Memory64Fill);
+    libcalls.insert(
+        "wasmer_vm_imported_memory64_fill".to_string(),
+        LibCall::ImportedMemory64Fill,
+    );
+    libcalls.insert("wasmer_vm_memory64_init".to_string(), LibCall::Memory64Init);
+    libcalls.insert(
+        "wasmer_vm_memory32_copy_between".to_string(),
+        LibCall::Memory32CopyBetween,
+    );
+    libcalls.insert(
+        "wasmer_vm_memory64_copy_between".to_string(),
+        LibCall::Memory64CopyBetween,
+    );
     libcalls.insert("wasmer_vm_data_drop".to_string(), LibCall::DataDrop);
+    libcalls.insert("wasmer_vm_return_call".to_string(), LibCall::ReturnCall);
+    libcalls.insert(
+        "wasmer_vm_return_call_indirect".to_string(),
+        LibCall::ReturnCallIndirect,
+    );
+    libcalls.insert(
+        "wasmer_vm_tail_call_pending".to_string(),
+        LibCall::TailCallPending,
+    );
+    libcalls.insert("wasmer_vm_tail_call".to_string(), LibCall::TailCall);
     libcalls.insert("wasmer_vm_raise_trap".to_string(), LibCall::RaiseTrap);
     libcalls.insert("wasmer_vm_probestack".to_string(), LibCall::Probestack);
 
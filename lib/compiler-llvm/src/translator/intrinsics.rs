@@ -202,6 +202,17 @@ pub struct Intrinsics<'ctx> {
     pub imported_memory_copy: FunctionValue<'ctx>,
     pub memory_fill: FunctionValue<'ctx>,
     pub imported_memory_fill: FunctionValue<'ctx>,
+    pub memory64_init: FunctionValue<'ctx>,
+    pub memory64_copy: FunctionValue<'ctx>,
+    pub imported_memory64_copy: FunctionValue<'ctx>,
+    pub memory64_fill: FunctionValue<'ctx>,
+    pub imported_memory64_fill: FunctionValue<'ctx>,
+    pub memory32_copy_between: FunctionValue<'ctx>,
+    pub memory64_copy_between: FunctionValue<'ctx>,
+    pub return_call: FunctionValue<'ctx>,
+    pub return_call_indirect: FunctionValue<'ctx>,
+    pub tail_call_pending: FunctionValue<'ctx>,
+    pub tail_call: FunctionValue<'ctx>,
 
     pub throw_trap: FunctionValue<'ctx>,
 
@@ -218,6 +229,10 @@ pub struct Intrinsics<'ctx> {
     pub imported_memory32_grow_ptr_ty: PointerType<'ctx>,
     pub memory32_size_ptr_ty: PointerType<'ctx>,
     pub imported_memory32_size_ptr_ty: PointerType<'ctx>,
+    pub memory64_grow_ptr_ty: PointerType<'ctx>,
+    pub imported_memory64_grow_ptr_ty: PointerType<'ctx>,
+    pub memory64_size_ptr_ty: PointerType<'ctx>,
+    pub imported_memory64_size_ptr_ty: PointerType<'ctx>,
 
     // Pointer to the VM.
     pub ctx_ptr_ty: PointerType<'ctx>,
@@ -728,6 +743,149 @@ impl<'ctx> Intrinsics<'ctx> {
                 ),
                 None,
             ),
+            memory64_init: module.add_function(
+                "wasmer_vm_memory64_init",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i32_ty_basic,
+                        i64_ty_basic,
+                        i32_ty_basic,
+                        i32_ty_basic,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            memory64_copy: module.add_function(
+                "wasmer_vm_memory64_copy",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i64_ty_basic,
+                        i64_ty_basic,
+                        i64_ty_basic,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            imported_memory64_copy: module.add_function(
+                "wasmer_vm_imported_memory64_copy",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i64_ty_basic,
+                        i64_ty_basic,
+                        i64_ty_basic,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            memory64_fill: module.add_function(
+                "wasmer_vm_memory64_fill",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i64_ty_basic,
+                        i32_ty_basic,
+                        i64_ty_basic,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            imported_memory64_fill: module.add_function(
+                "wasmer_vm_imported_memory64_fill",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i64_ty_basic,
+                        i32_ty_basic,
+                        i64_ty_basic,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            memory32_copy_between: module.add_function(
+                "wasmer_vm_memory32_copy_between",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i32_ty_basic,
+                        i32_ty_basic,
+                        i32_ty_basic,
+                        i32_ty_basic,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            memory64_copy_between: module.add_function(
+                "wasmer_vm_memory64_copy_between",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i32_ty_basic,
+                        i64_ty_basic,
+                        i64_ty_basic,
+                        i64_ty_basic,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            return_call: module.add_function(
+                "wasmer_vm_return_call",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i128_ptr_ty.as_basic_type_enum(),
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            return_call_indirect: module.add_function(
+                "wasmer_vm_return_call_indirect",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i32_ty_basic,
+                        i32_ty_basic,
+                        i32_ty_basic,
+                        i128_ptr_ty.as_basic_type_enum(),
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            tail_call_pending: module.add_function(
+                "wasmer_vm_tail_call_pending",
+                i32_ty.fn_type(&[ctx_ptr_ty.as_basic_type_enum()], false),
+                None,
+            ),
+            tail_call: module.add_function(
+                "wasmer_vm_tail_call",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty.as_basic_type_enum(),
+                        i128_ptr_ty.as_basic_type_enum(),
+                    ],
+                    false,
+                ),
+                None,
+            ),
             data_drop: module.add_function(
                 "wasmer_vm_data_drop",
                 void_ty.fn_type(&[ctx_ptr_ty.as_basic_type_enum(), i32_ty_basic], false),
@@ -757,7 +915,7 @@ impl<'ctx> Intrinsics<'ctx> {
 
             // TODO: this i64 is actually a rust usize
             vmmemory_definition_ptr_ty: context
-                .struct_type(&[i8_ptr_ty_basic, i32_ty_basic], false)
+                .struct_type(&[i8_ptr_ty_basic, i64_ty_basic], false)
                 .ptr_type(AddressSpace::Generic),
             vmmemory_definition_base_element: 0,
             vmmemory_definition_current_length_element: 1,
@@ -780,6 +938,24 @@ impl<'ctx> Intrinsics<'ctx> {
             imported_memory32_size_ptr_ty: i32_ty
                 .fn_type(&[ctx_ptr_ty.as_basic_type_enum(), i32_ty_basic], false)
                 .ptr_type(AddressSpace::Generic),
+            memory64_grow_ptr_ty: i64_ty
+                .fn_type(
+                    &[ctx_ptr_ty.as_basic_type_enum(), i64_ty_basic, i32_ty_basic],
+                    false,
+                )
+                .ptr_type(AddressSpace::Generic),
+            imported_memory64_grow_ptr_ty: i64_ty
+                .fn_type(
+                    &[ctx_ptr_ty.as_basic_type_enum(), i64_ty_basic, i32_ty_basic],
+                    false,
+                )
+                .ptr_type(AddressSpace::Generic),
+            memory64_size_ptr_ty: i64_ty
+                .fn_type(&[ctx_ptr_ty.as_basic_type_enum(), i32_ty_basic], false)
+                .ptr_type(AddressSpace::Generic),
+            imported_memory64_size_ptr_ty: i64_ty
+                .fn_type(&[ctx_ptr_ty.as_basic_type_enum(), i32_ty_basic], false)
+                .ptr_type(AddressSpace::Generic),
 
             ctx_ptr_ty,
         };
@@ -1329,7 +1505,20 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
             &self.ctx_ptr_value,
         );
         *cached_memory_grow.entry(memory_index).or_insert_with(|| {
-            let (grow_fn, grow_fn_ty) = if wasm_module.local_memory_index(memory_index).is_some() {
+            let is_local = wasm_module.local_memory_index(memory_index).is_some();
+            let (grow_fn, grow_fn_ty) = if wasm_module.memory64[memory_index] {
+                if is_local {
+                    (
+                        VMBuiltinFunctionIndex::get_memory64_grow_index(),
+                        intrinsics.memory64_grow_ptr_ty,
+                    )
+                } else {
+                    (
+                        VMBuiltinFunctionIndex::get_imported_memory64_grow_index(),
+                        intrinsics.imported_memory64_grow_ptr_ty,
+                    )
+                }
+            } else if is_local {
                 (
                     VMBuiltinFunctionIndex::get_memory32_grow_index(),
                     intrinsics.memory32_grow_ptr_ty,
@@ -1370,7 +1559,20 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
             &self.ctx_ptr_value,
         );
         *cached_memory_size.entry(memory_index).or_insert_with(|| {
-            let (size_fn, size_fn_ty) = if wasm_module.local_memory_index(memory_index).is_some() {
+            let is_local = wasm_module.local_memory_index(memory_index).is_some();
+            let (size_fn, size_fn_ty) = if wasm_module.memory64[memory_index] {
+                if is_local {
+                    (
+                        VMBuiltinFunctionIndex::get_memory64_size_index(),
+                        intrinsics.memory64_size_ptr_ty,
+                    )
+                } else {
+                    (
+                        VMBuiltinFunctionIndex::get_imported_memory64_size_index(),
+                        intrinsics.imported_memory64_size_ptr_ty,
+                    )
+                }
+            } else if is_local {
                 (
                     VMBuiltinFunctionIndex::get_memory32_size_index(),
                     intrinsics.memory32_size_ptr_ty,
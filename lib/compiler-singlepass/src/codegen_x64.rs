@@ -17,8 +17,7 @@ use wasmer_types::{
     FunctionType,
 };
 use wasmer_types::{
-    FunctionIndex, GlobalIndex, LocalFunctionIndex, LocalMemoryIndex, MemoryIndex, SignatureIndex,
-    TableIndex, Type,
+    FunctionIndex, GlobalIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex, TableIndex, Type,
 };
 use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle, TrapCode, VMBuiltinFunctionIndex, VMOffsets};
 
@@ -1238,34 +1237,34 @@ impl<'a> FuncGen<'a> {
         value_size: usize,
         cb: F,
     ) -> Result<(), CodegenError> {
-        let need_check = match self.memory_styles[MemoryIndex::new(0)] {
+        let memory_index = MemoryIndex::from_u32(memarg.memory);
+        let need_check = match self.memory_styles[memory_index] {
             MemoryStyle::Static { .. } => false,
             MemoryStyle::Dynamic { .. } => true,
         };
         let tmp_addr = self.machine.acquire_temp_gpr().unwrap();
 
         // Reusing `tmp_addr` for temporary indirection here, since it's not used before the last reference to `{base,bound}_loc`.
-        let (base_loc, bound_loc) = if self.module.num_imported_memories != 0 {
-            // Imported memories require one level of indirection.
-            let offset = self
-                .vmoffsets
-                .vmctx_vmmemory_import_definition(MemoryIndex::new(0));
-            self.emit_relaxed_binop(
-                Assembler::emit_mov,
-                Size::S64,
-                Location::Memory(Machine::get_vmctx_reg(), offset as i32),
-                Location::GPR(tmp_addr),
-            );
-            (Location::Memory(tmp_addr, 0), Location::Memory(tmp_addr, 8))
-        } else {
-            let offset = self
-                .vmoffsets
-                .vmctx_vmmemory_definition(LocalMemoryIndex::new(0));
-            (
-                Location::Memory(Machine::get_vmctx_reg(), offset as i32),
-                Location::Memory(Machine::get_vmctx_reg(), (offset + 8) as i32),
-            )
-        };
+        let (base_loc, bound_loc) =
+            if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+                let offset = self.vmoffsets.vmctx_vmmemory_definition(local_memory_index);
+                (
+                    Location::Memory(Machine::get_vmctx_reg(), offset as i32),
+                    Location::Memory(Machine::get_vmctx_reg(), (offset + 8) as i32),
+                )
+            } else {
+                // Imported memories require one level of indirection.
+                let offset = self
+                    .vmoffsets
+                    .vmctx_vmmemory_import_definition(memory_index);
+                self.emit_relaxed_binop(
+                    Assembler::emit_mov,
+                    Size::S64,
+                    Location::Memory(Machine::get_vmctx_reg(), offset as i32),
+                    Location::GPR(tmp_addr),
+                );
+                (Location::Memory(tmp_addr, 0), Location::Memory(tmp_addr, 8))
+            };
 
         let tmp_base = self.machine.acquire_temp_gpr().unwrap();
         let tmp_bound = self.machine.acquire_temp_gpr().unwrap();
@@ -1277,7 +1276,7 @@ impl<'a> FuncGen<'a> {
         // Load bound into temporary register, if needed.
         if need_check {
             self.assembler
-                .emit_mov(Size::S32, bound_loc, Location::GPR(tmp_bound));
+                .emit_mov(Size::S64, bound_loc, Location::GPR(tmp_bound));
 
             // Wasm -> Effective.
             // Assuming we never underflow - should always be true on Linux/macOS and Windows >=8,
@@ -5649,17 +5648,22 @@ impl<'a> FuncGen<'a> {
             Operator::Nop => {}
             Operator::MemorySize { mem, mem_byte: _ } => {
                 let memory_index = MemoryIndex::new(mem as usize);
+                let (memory_size_index, memory_index) =
+                    match self.module.local_memory_index(memory_index) {
+                        Some(local_memory_index) => (
+                            VMBuiltinFunctionIndex::get_memory32_size_index(),
+                            local_memory_index.index(),
+                        ),
+                        None => (
+                            VMBuiltinFunctionIndex::get_imported_memory32_size_index(),
+                            memory_index.index(),
+                        ),
+                    };
                 self.assembler.emit_mov(
                     Size::S64,
                     Location::Memory(
                         Machine::get_vmctx_reg(),
-                        self.vmoffsets.vmctx_builtin_function(
-                            if self.module.local_memory_index(memory_index).is_some() {
-                                VMBuiltinFunctionIndex::get_memory32_size_index()
-                            } else {
-                                VMBuiltinFunctionIndex::get_imported_memory32_size_index()
-                            },
-                        ) as i32,
+                        self.vmoffsets.vmctx_builtin_function(memory_size_index) as i32,
                     ),
                     Location::GPR(GPR::RAX),
                 );
@@ -5668,7 +5672,7 @@ impl<'a> FuncGen<'a> {
                         this.assembler.emit_call_register(GPR::RAX);
                     },
                     // [vmctx, memory_index]
-                    iter::once(Location::Imm32(memory_index.index() as u32)),
+                    iter::once(Location::Imm32(memory_index as u32)),
                 )?;
                 let ret = self.machine.acquire_locations(
                     &mut self.assembler,
@@ -5737,9 +5741,46 @@ impl<'a> FuncGen<'a> {
                     iter::once(Location::Imm32(segment)),
                 )?;
             }
-            Operator::MemoryCopy { src, dst } => {
-                // ignore until we support multiple memories
-                let _dst = dst;
+            Operator::MemoryCopy { src, dst } if src != dst => {
+                let len = self.value_stack.pop().unwrap();
+                let src_pos = self.value_stack.pop().unwrap();
+                let dst_pos = self.value_stack.pop().unwrap();
+                self.machine
+                    .release_locations_only_regs(&[len, src_pos, dst_pos]);
+
+                self.assembler.emit_mov(
+                    Size::S64,
+                    Location::Memory(
+                        Machine::get_vmctx_reg(),
+                        self.vmoffsets.vmctx_builtin_function(
+                            VMBuiltinFunctionIndex::get_memory32_copy_between_index(),
+                        ) as i32,
+                    ),
+                    Location::GPR(GPR::RAX),
+                );
+
+                // TODO: should this be 3?
+                self.machine.release_locations_only_osr_state(1);
+
+                self.emit_call_sysv(
+                    |this| {
+                        this.assembler.emit_call_register(GPR::RAX);
+                    },
+                    // [vmctx, dst_memory_index, src_memory_index, dst, src, len]
+                    [
+                        Location::Imm32(dst),
+                        Location::Imm32(src),
+                        dst_pos,
+                        src_pos,
+                        len,
+                    ]
+                    .iter()
+                    .cloned(),
+                )?;
+                self.machine
+                    .release_locations_only_stack(&mut self.assembler, &[dst_pos, src_pos, len]);
+            }
+            Operator::MemoryCopy { src, dst: _ } => {
                 let len = self.value_stack.pop().unwrap();
                 let src_pos = self.value_stack.pop().unwrap();
                 let dst_pos = self.value_stack.pop().unwrap();
@@ -5748,16 +5789,15 @@ impl<'a> FuncGen<'a> {
 
                 let memory_index = MemoryIndex::new(src as usize);
                 let (memory_copy_index, memory_index) =
-                    if self.module.local_memory_index(memory_index).is_some() {
-                        (
+                    match self.module.local_memory_index(memory_index) {
+                        Some(local_memory_index) => (
                             VMBuiltinFunctionIndex::get_memory_copy_index(),
-                            memory_index,
-                        )
-                    } else {
-                        (
+                            local_memory_index.index(),
+                        ),
+                        None => (
                             VMBuiltinFunctionIndex::get_imported_memory_copy_index(),
-                            memory_index,
-                        )
+                            memory_index.index(),
+                        ),
                     };
 
                 self.assembler.emit_mov(
@@ -5777,14 +5817,9 @@ impl<'a> FuncGen<'a> {
                         this.assembler.emit_call_register(GPR::RAX);
                     },
                     // [vmctx, memory_index, dst, src, len]
-                    [
-                        Location::Imm32(memory_index.index() as u32),
-                        dst_pos,
-                        src_pos,
-                        len,
-                    ]
-                    .iter()
-                    .cloned(),
+                    [Location::Imm32(memory_index as u32), dst_pos, src_pos, len]
+                        .iter()
+                        .cloned(),
                 )?;
                 self.machine
                     .release_locations_only_stack(&mut self.assembler, &[dst_pos, src_pos, len]);
@@ -5797,16 +5832,15 @@ impl<'a> FuncGen<'a> {
 
                 let memory_index = MemoryIndex::new(mem as usize);
                 let (memory_fill_index, memory_index) =
-                    if self.module.local_memory_index(memory_index).is_some() {
-                        (
+                    match self.module.local_memory_index(memory_index) {
+                        Some(local_memory_index) => (
                             VMBuiltinFunctionIndex::get_memory_fill_index(),
-                            memory_index,
-                        )
-                    } else {
-                        (
+                            local_memory_index.index(),
+                        ),
+                        None => (
                             VMBuiltinFunctionIndex::get_imported_memory_fill_index(),
-                            memory_index,
-                        )
+                            memory_index.index(),
+                        ),
                     };
 
                 self.assembler.emit_mov(
@@ -5826,7 +5860,7 @@ impl<'a> FuncGen<'a> {
                         this.assembler.emit_call_register(GPR::RAX);
                     },
                     // [vmctx, memory_index, dst, src, len]
-                    [Location::Imm32(memory_index.index() as u32), dst, val, len]
+                    [Location::Imm32(memory_index as u32), dst, val, len]
                         .iter()
                         .cloned(),
                 )?;
@@ -5835,6 +5869,17 @@ impl<'a> FuncGen<'a> {
             }
             Operator::MemoryGrow { mem, mem_byte: _ } => {
                 let memory_index = MemoryIndex::new(mem as usize);
+                let (memory_grow_index, memory_index) =
+                    match self.module.local_memory_index(memory_index) {
+                        Some(local_memory_index) => (
+                            VMBuiltinFunctionIndex::get_memory32_grow_index(),
+                            local_memory_index.index(),
+                        ),
+                        None => (
+                            VMBuiltinFunctionIndex::get_imported_memory32_grow_index(),
+                            memory_index.index(),
+                        ),
+                    };
                 let param_pages = self.value_stack.pop().unwrap();
 
                 self.machine.release_locations_only_regs(&[param_pages]);
@@ -5843,13 +5888,7 @@ impl<'a> FuncGen<'a> {
                     Size::S64,
                     Location::Memory(
                         Machine::get_vmctx_reg(),
-                        self.vmoffsets.vmctx_builtin_function(
-                            if self.module.local_memory_index(memory_index).is_some() {
-                                VMBuiltinFunctionIndex::get_memory32_grow_index()
-                            } else {
-                                VMBuiltinFunctionIndex::get_imported_memory32_grow_index()
-                            },
-                        ) as i32,
+                        self.vmoffsets.vmctx_builtin_function(memory_grow_index) as i32,
                     ),
                     Location::GPR(GPR::RAX),
                 );
@@ -5861,8 +5900,7 @@ impl<'a> FuncGen<'a> {
                         this.assembler.emit_call_register(GPR::RAX);
                     },
                     // [vmctx, val, memory_index]
-                    iter::once(param_pages)
-                        .chain(iter::once(Location::Imm32(memory_index.index() as u32))),
+                    iter::once(param_pages).chain(iter::once(Location::Imm32(memory_index as u32))),
                 )?;
 
                 self.machine
@@ -47,6 +47,10 @@ impl Compiler for SinglepassCompiler {
         &self.config.middlewares
     }
 
+    fn fingerprint(&self) -> String {
+        self.config.fingerprint()
+    }
+
     /// Compile the module using Singlepass, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -67,6 +71,14 @@ impl Compiler for SinglepassCompiler {
         if compile_info.features.multi_value {
             return Err(CompileError::UnsupportedFeature("multivalue".to_string()));
         }
+        if compile_info
+            .module
+            .memory64
+            .values()
+            .any(|&memory64| memory64)
+        {
+            return Err(CompileError::UnsupportedFeature("memory64".to_string()));
+        }
         let memory_styles = &compile_info.memory_styles;
         let table_styles = &compile_info.table_styles;
         let vmoffsets = VMOffsets::new(8, &compile_info.module);
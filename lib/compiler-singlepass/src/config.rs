@@ -4,7 +4,9 @@
 use crate::compiler::SinglepassCompiler;
 use loupe::MemoryUsage;
 use std::sync::Arc;
-use wasmer_compiler::{Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target};
+use wasmer_compiler::{
+    Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, ModuleMiddlewareChain, Target,
+};
 use wasmer_types::Features;
 
 #[derive(Debug, Clone, MemoryUsage)]
@@ -28,11 +30,13 @@ impl Singlepass {
 
     /// Enable stack check.
     ///
-    /// When enabled, an explicit stack depth check will be performed on entry
-    /// to each function to prevent stack overflow.
-    ///
-    /// Note that this doesn't guarantee deterministic execution across
-    /// different platforms.
+    /// This has no effect: the stack of the guest is limited the same way
+    /// for every compiler, with the stack configuration of the tunables
+    /// (`Tunables::stack_config`, set with `StackTunables` in `wasmer`).
+    #[deprecated(
+        since = "1.0.3",
+        note = "Please use the stack configuration of the tunables instead."
+    )]
     pub fn enable_stack_check(&mut self, enable: bool) -> &mut Self {
         self.enable_stack_check = enable;
         self
@@ -70,6 +74,15 @@ impl CompilerConfig for Singlepass {
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>) {
         self.middlewares.push(middleware);
     }
+
+    fn fingerprint(&self) -> String {
+        format!(
+            "singlepass nan_canonicalization={} stack_check={} middlewares={}",
+            self.enable_nan_canonicalization,
+            self.enable_stack_check,
+            self.middlewares.fingerprint()
+        )
+    }
 }
 
 impl Default for Singlepass {
@@ -6,7 +6,7 @@ use crate::function::Compilation;
 use crate::lib::std::boxed::Box;
 use crate::lib::std::sync::Arc;
 use crate::module::CompileModuleInfo;
-use crate::target::Target;
+use crate::target::{unique_fingerprint, Target};
 use crate::translator::ModuleMiddleware;
 use crate::FunctionBodyData;
 use crate::ModuleTranslationState;
@@ -47,6 +47,18 @@ pub trait CompilerConfig {
 
     /// Pushes a middleware onto the back of the middleware chain.
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>);
+
+    /// A textual description of every option that changes the code
+    /// generated with this config, middlewares included.
+    ///
+    /// Configs generating different code must have different
+    /// fingerprints: they are used to tell cached artifacts apart.
+    ///
+    /// Defaults to [`unique_fingerprint`], so the artifacts of configs
+    /// that don't describe themselves are never shared by caches.
+    fn fingerprint(&self) -> String {
+        unique_fingerprint()
+    }
 }
 
 impl<T> From<T> for Box<dyn CompilerConfig + 'static>
@@ -120,6 +132,13 @@ pub trait Compiler: Send + MemoryUsage {
 
     /// Get the middlewares for this compiler
     fn get_middlewares(&self) -> &[Arc<dyn ModuleMiddleware>];
+
+    /// The fingerprint of the config of this compiler.
+    ///
+    /// See [`CompilerConfig::fingerprint`], including its default.
+    fn fingerprint(&self) -> String {
+        unique_fingerprint()
+    }
 }
 
 /// The kinds of wasmer_types objects that might be found in a native object file.
@@ -136,6 +155,9 @@ pub enum Symbol {
 
     /// The dynamic function trampoline for a given function.
     DynamicFunctionTrampoline(FunctionIndex),
+
+    /// The serialized metadata of the module.
+    Metadata,
 }
 
 /// This trait facilitates symbol name lookups in a native object file.
@@ -2,6 +2,7 @@
 use crate::error::ParseCpuFeatureError;
 use crate::lib::std::str::FromStr;
 use crate::lib::std::string::{String, ToString};
+use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use enumset::{EnumSet, EnumSetType};
 use loupe::MemoryUsage;
 pub use target_lexicon::{
@@ -187,6 +188,43 @@ impl Target {
     pub fn cpu_features(&self) -> &EnumSet<CpuFeature> {
         &self.cpu_features
     }
+
+    /// A textual description of the target, such as
+    /// `x86_64-unknown-linux-gnu+sse2+avx`.
+    ///
+    /// Two targets have the same fingerprint if and only if they are equal.
+    pub fn fingerprint(&self) -> String {
+        let mut fingerprint = self.triple.to_string();
+        for feature in self.cpu_features.iter() {
+            fingerprint.push('+');
+            fingerprint.push_str(&feature.to_string());
+        }
+        fingerprint
+    }
+}
+
+/// A fingerprint different from all the others, even across processes,
+/// for the engines, compiler configs and middlewares that can't describe
+/// themselves.
+///
+/// Artifacts keyed on it always miss caches, which is slower but safe.
+pub fn unique_fingerprint() -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let count = NEXT.fetch_add(1, SeqCst);
+    #[cfg(feature = "std")]
+    {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or(0);
+        format!("unique-{}-{}-{}", std::process::id(), nanos, count)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let mut fingerprint = String::from("unique-");
+        fingerprint.push_str(&count.to_string());
+        fingerprint
+    }
 }
 
 /// The default for the Target will use the HOST as the triple
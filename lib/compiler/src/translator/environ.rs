@@ -16,7 +16,7 @@ use wasmer_types::{
     CustomSectionIndex, DataIndex, DataInitializer, DataInitializerLocation, ElemIndex,
     ExportIndex, FunctionIndex, GlobalIndex, GlobalInit, GlobalType, ImportIndex,
     LocalFunctionIndex, MemoryIndex, MemoryType, SignatureIndex, TableIndex, TableInitializer,
-    TableType,
+    TableType, TagIndex,
 };
 use wasmer_vm::ModuleInfo;
 
@@ -191,6 +191,7 @@ impl<'data> ModuleEnvironment<'data> {
     pub(crate) fn declare_memory_import(
         &mut self,
         memory: MemoryType,
+        memory64: bool,
         module: &str,
         field: &str,
     ) -> WasmResult<()> {
@@ -207,6 +208,7 @@ impl<'data> ModuleEnvironment<'data> {
             field,
         )?;
         self.result.module.memories.push(memory);
+        self.result.module.memory64.push(memory64);
         self.result.module.num_imported_memories += 1;
         self.imports += 1;
         Ok(())
@@ -236,6 +238,31 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    pub(crate) fn declare_tag_import(
+        &mut self,
+        signature: SignatureIndex,
+        module: &str,
+        field: &str,
+    ) -> WasmResult<()> {
+        debug_assert_eq!(
+            self.result.module.tags.len(),
+            self.result.module.num_imported_tags,
+            "Imported tags must be declared first"
+        );
+        self.check_tag_signature(signature)?;
+        self.declare_import(
+            ImportIndex::Tag(TagIndex::from_u32(
+                self.result.module.num_imported_tags as _,
+            )),
+            module,
+            field,
+        )?;
+        self.result.module.tags.push(signature);
+        self.result.module.num_imported_tags += 1;
+        self.imports += 1;
+        Ok(())
+    }
+
     pub(crate) fn finish_imports(&mut self) -> WasmResult<()> {
         Ok(())
     }
@@ -277,13 +304,41 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
-    pub(crate) fn declare_memory(&mut self, memory: MemoryType) -> WasmResult<()> {
+    pub(crate) fn declare_memory(&mut self, memory: MemoryType, memory64: bool) -> WasmResult<()> {
         if memory.shared {
             return Err(WasmError::Unsupported(
                 "shared memories are not supported yet".to_owned(),
             ));
         }
         self.result.module.memories.push(memory);
+        self.result.module.memory64.push(memory64);
+        Ok(())
+    }
+
+    pub(crate) fn reserve_tags(&mut self, num: u32) -> WasmResult<()> {
+        self.result
+            .module
+            .tags
+            .reserve_exact(usize::try_from(num).unwrap());
+        Ok(())
+    }
+
+    pub(crate) fn declare_tag(&mut self, signature: SignatureIndex) -> WasmResult<()> {
+        self.check_tag_signature(signature)?;
+        self.result.module.tags.push(signature);
+        Ok(())
+    }
+
+    fn check_tag_signature(&self, signature: SignatureIndex) -> WasmResult<()> {
+        // The payload of exceptions is copied in and out of the runtime
+        // as raw values, which can't hold references.
+        let ty = &self.result.module.signatures[signature];
+        if ty.params().iter().any(|param| param.is_ref()) {
+            return Err(WasmError::Unsupported(
+                "exception tags with reference types in their payload are not supported yet"
+                    .to_owned(),
+            ));
+        }
         Ok(())
     }
 
@@ -345,6 +400,10 @@ impl<'data> ModuleEnvironment<'data> {
         self.declare_export(ExportIndex::Global(global_index), name)
     }
 
+    pub(crate) fn declare_tag_export(&mut self, tag_index: TagIndex, name: &str) -> WasmResult<()> {
+        self.declare_export(ExportIndex::Tag(tag_index), name)
+    }
+
     pub(crate) fn declare_start_function(&mut self, func_index: FunctionIndex) -> WasmResult<()> {
         debug_assert!(self.result.module.start_function.is_none());
         self.result.module.start_function = Some(func_index);
@@ -11,6 +11,7 @@ use wasmer_vm::ModuleInfo;
 use wasmparser::{BinaryReader, Operator, Range, Type};
 
 use crate::error::{MiddlewareError, WasmResult};
+use crate::target::unique_fingerprint;
 use crate::translator::environ::FunctionBinaryReader;
 
 /// A shared builder for function middlewares.
@@ -27,6 +28,16 @@ pub trait ModuleMiddleware: Debug + Send + Sync + MemoryUsage {
 
     /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
     fn transform_module_info(&self, _: &mut ModuleInfo) {}
+
+    /// A textual description of how this middleware transforms modules,
+    /// used to tell apart the artifacts compiled with different middlewares,
+    /// so it must include the parameters of the middleware.
+    ///
+    /// Defaults to [`unique_fingerprint`], so the artifacts compiled with
+    /// middlewares that don't describe themselves are never shared by caches.
+    fn fingerprint(&self) -> String {
+        unique_fingerprint()
+    }
 }
 
 /// A function middleware specialized for a single function.
@@ -72,6 +83,9 @@ pub trait ModuleMiddlewareChain {
 
     /// Applies the chain on a `ModuleInfo` struct.
     fn apply_on_module_info(&self, module_info: &mut ModuleInfo);
+
+    /// The fingerprints of the middlewares of the chain, in order.
+    fn fingerprint(&self) -> String;
 }
 
 impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
@@ -91,6 +105,12 @@ impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
             item.transform_module_info(module_info);
         }
     }
+
+    /// The fingerprints of the middlewares of the chain, in order.
+    fn fingerprint(&self) -> String {
+        let fingerprints: Vec<String> = self.iter().map(|x| x.fingerprint()).collect();
+        format!("[{}]", fingerprints.join(", "))
+    }
 }
 
 impl<'a> MiddlewareReaderState<'a> {
@@ -5,9 +5,9 @@
 //! to deal with each part of it.
 use super::environ::ModuleEnvironment;
 use super::sections::{
-    parse_data_section, parse_element_section, parse_export_section, parse_function_section,
-    parse_global_section, parse_import_section, parse_memory_section, parse_name_section,
-    parse_start_section, parse_table_section, parse_type_section,
+    parse_data_section, parse_element_section, parse_event_section, parse_export_section,
+    parse_function_section, parse_global_section, parse_import_section, parse_memory_section,
+    parse_name_section, parse_start_section, parse_table_section, parse_type_section,
 };
 use super::state::ModuleTranslationState;
 use crate::WasmResult;
@@ -49,6 +49,10 @@ pub fn translate_module<'data>(
                 parse_global_section(globals, environ)?;
             }
 
+            Payload::EventSection(events) => {
+                parse_event_section(events, environ)?;
+            }
+
             Payload::ExportSection(exports) => {
                 parse_export_section(exports, environ)?;
             }
@@ -83,7 +87,6 @@ pub fn translate_module<'data>(
 
             Payload::InstanceSection(_)
             | Payload::AliasSection(_)
-            | Payload::EventSection(_)
             | Payload::ModuleSectionStart { .. }
             | Payload::ModuleSectionEntry { .. } => {
                 unimplemented!("module linking not implemented yet")
@@ -22,16 +22,43 @@ use wasmer_types::entity::packed_option::ReservedValue;
 use wasmer_types::entity::EntityRef;
 use wasmer_types::{
     DataIndex, ElemIndex, FunctionIndex, FunctionType, GlobalIndex, GlobalInit, GlobalType,
-    MemoryIndex, MemoryType, Pages, SignatureIndex, TableIndex, TableType, Type, V128,
+    MemoryIndex, MemoryType, Pages, SignatureIndex, TableIndex, TableType, TagIndex, Type, V128,
+    WASM64_MAX_PAGES,
 };
 use wasmparser::{
     self, Data, DataKind, DataSectionReader, Element, ElementItem, ElementItems, ElementKind,
-    ElementSectionReader, Export, ExportSectionReader, ExternalKind, FuncType as WPFunctionType,
-    FunctionSectionReader, GlobalSectionReader, GlobalType as WPGlobalType, ImportSectionEntryType,
-    ImportSectionReader, MemorySectionReader, MemoryType as WPMemoryType, NameSectionReader,
-    Naming, NamingReader, Operator, TableSectionReader, TypeDef, TypeSectionReader,
+    ElementSectionReader, EventSectionReader, Export, ExportSectionReader, ExternalKind,
+    FuncType as WPFunctionType, FunctionSectionReader, GlobalSectionReader,
+    GlobalType as WPGlobalType, ImportSectionEntryType, ImportSectionReader, MemorySectionReader,
+    MemoryType as WPMemoryType, NameSectionReader, Naming, NamingReader, Operator,
+    TableSectionReader, TypeDef, TypeSectionReader,
 };
 
+/// Helper function translating a wasmparser memory type to a `MemoryType`,
+/// and whether the memory is indexed with 64-bit addresses.
+fn memory_type(ty: WPMemoryType) -> WasmResult<(MemoryType, bool)> {
+    match ty {
+        WPMemoryType::M32 { limits, shared } => Ok((
+            MemoryType::new(Pages(limits.initial), limits.maximum.map(Pages), shared),
+            false,
+        )),
+        WPMemoryType::M64 { limits, shared } => {
+            let minimum = u32::try_from(limits.initial)
+                .ok()
+                .filter(|&pages| pages <= WASM64_MAX_PAGES)
+                .ok_or(WasmError::ImplLimitExceeded)?;
+            // A memory can't grow past `WASM64_MAX_PAGES` anyway.
+            let maximum = limits
+                .maximum
+                .map(|pages| u32::try_from(pages).unwrap_or(WASM64_MAX_PAGES));
+            Ok((
+                MemoryType::new(Pages(minimum), maximum.map(Pages), shared),
+                true,
+            ))
+        }
+    }
+}
+
 /// Helper function translating wasmparser types to Wasm Type.
 pub fn wptype_to_type(ty: wasmparser::Type) -> WasmResult<Type> {
     match ty {
@@ -105,27 +132,24 @@ pub fn parse_import_section<'data>(
                     field_name.unwrap_or_default(),
                 )?;
             }
-            ImportSectionEntryType::Module(_)
-            | ImportSectionEntryType::Instance(_)
-            | ImportSectionEntryType::Event(_) => {
+            ImportSectionEntryType::Module(_) | ImportSectionEntryType::Instance(_) => {
                 unimplemented!("module linking not implemented yet")
             }
-            ImportSectionEntryType::Memory(WPMemoryType::M32 {
-                limits: ref memlimits,
-                shared,
-            }) => {
-                environ.declare_memory_import(
-                    MemoryType {
-                        minimum: Pages(memlimits.initial),
-                        maximum: memlimits.maximum.map(Pages),
-                        shared,
-                    },
+            ImportSectionEntryType::Event(event) => {
+                environ.declare_tag_import(
+                    SignatureIndex::from_u32(event.type_index),
                     module_name,
                     field_name.unwrap_or_default(),
                 )?;
             }
-            ImportSectionEntryType::Memory(WPMemoryType::M64 { .. }) => {
-                unimplemented!("64bit memory not implemented yet")
+            ImportSectionEntryType::Memory(ty) => {
+                let (memory, memory64) = memory_type(ty)?;
+                environ.declare_memory_import(
+                    memory,
+                    memory64,
+                    module_name,
+                    field_name.unwrap_or_default(),
+                )?;
             }
             ImportSectionEntryType::Global(ref ty) => {
                 environ.declare_global_import(
@@ -204,16 +228,24 @@ pub fn parse_memory_section(
 
     for entry in memories {
         let memory = entry?;
-        match memory {
-            WPMemoryType::M32 { limits, shared } => {
-                environ.declare_memory(MemoryType {
-                    minimum: Pages(limits.initial),
-                    maximum: limits.maximum.map(Pages),
-                    shared,
-                })?;
-            }
-            WPMemoryType::M64 { .. } => unimplemented!("64bit memory not implemented yet"),
-        }
+        let (memory, memory64) = memory_type(memory)?;
+        environ.declare_memory(memory, memory64)?;
+    }
+
+    Ok(())
+}
+
+/// Parses the Event section of the wasm module, which declares the
+/// exception tags.
+pub fn parse_event_section(
+    events: EventSectionReader,
+    environ: &mut ModuleEnvironment,
+) -> WasmResult<()> {
+    environ.reserve_tags(events.get_count())?;
+
+    for entry in events {
+        let event = entry?;
+        environ.declare_tag(SignatureIndex::from_u32(event.type_index))?;
     }
 
     Ok(())
@@ -294,12 +326,10 @@ pub fn parse_export_section<'data>(
             ExternalKind::Global => {
                 environ.declare_global_export(GlobalIndex::new(index), field)?
             }
-            ExternalKind::Type
-            | ExternalKind::Module
-            | ExternalKind::Instance
-            | ExternalKind::Event => {
+            ExternalKind::Type | ExternalKind::Module | ExternalKind::Instance => {
                 unimplemented!("module linking not implemented yet")
             }
+            ExternalKind::Event => environ.declare_tag_export(TagIndex::new(index), field)?,
         }
     }
 
@@ -394,6 +424,10 @@ pub fn parse_data_section<'data>(
                 let mut init_expr_reader = init_expr.get_binary_reader();
                 let (base, offset) = match init_expr_reader.read_operator()? {
                     Operator::I32Const { value } => (None, value as u32 as usize),
+                    Operator::I64Const { value } => (
+                        None,
+                        usize::try_from(value as u64).map_err(|_| WasmError::ImplLimitExceeded)?,
+                    ),
                     Operator::GlobalGet { global_index } => {
                         (Some(GlobalIndex::from_u32(global_index)), 0)
                     }
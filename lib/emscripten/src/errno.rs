@@ -8,6 +8,14 @@ pub fn ___seterrno(_ctx: &EmEnv, _value: i32) {
     // value
 }
 
+/// The errno of the syscalls the guest can't make, numbered like in
+/// emscripten's libc rather than like on the host.
+pub const ENOSYS: i32 = 38;
+
+/// The errno of the `ioctl`s the guest makes with requests that have no
+/// equivalent on the host.
+pub const ENOTTY: i32 = 25;
+
 // pub enum ErrnoCodes {
 //     EPERM = 1,
 //     ENOENT = 2,
@@ -1,3 +1,4 @@
+use crate::syscalls::host_call_allowed;
 use crate::varargs::VarArgs;
 use crate::EmEnv;
 use libc::execvp as libc_execvp;
@@ -5,6 +6,10 @@ use std::cell::Cell;
 use std::ffi::CString;
 
 pub fn execvp(ctx: &EmEnv, command_name_offset: u32, argv_offset: u32) -> i32 {
+    if !host_call_allowed(ctx, 11) {
+        return -1;
+    }
+
     // a single reference to re-use
     let emscripten_memory = ctx.memory(0);
 
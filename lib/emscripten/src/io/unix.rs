@@ -3,6 +3,7 @@ use super::super::utils::copy_cstr_into_wasm;
 use libc::{chroot as _chroot, getpwuid as _getpwuid, printf as _printf};
 use std::mem;
 
+use crate::syscalls::host_call_allowed;
 use crate::EmEnv;
 
 /// putchar
@@ -22,6 +23,9 @@ pub fn printf(ctx: &EmEnv, memory_offset: i32, extra: i32) -> i32 {
 /// chroot
 pub fn chroot(ctx: &EmEnv, name_ptr: i32) -> i32 {
     debug!("emscripten::chroot");
+    if !host_call_allowed(ctx, 61) {
+        return -1;
+    }
     let name = emscripten_memory_pointer!(ctx.memory(0), name_ptr) as *const i8;
     unsafe { _chroot(name as *const _) }
 }
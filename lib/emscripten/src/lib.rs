@@ -53,6 +53,8 @@ mod memory;
 mod process;
 mod pthread;
 mod ptr;
+#[cfg(unix)]
+mod sandbox;
 mod signal;
 mod storage;
 mod syscalls;
@@ -62,21 +64,36 @@ mod unistd;
 mod utils;
 mod varargs;
 
+#[cfg(unix)]
+pub use self::sandbox::EmSandbox;
 pub use self::storage::{align_memory, static_alloc};
+pub use self::syscalls::{AllowAllSyscalls, SyscallAction, SyscallPolicy};
 pub use self::utils::{
     allocate_cstr_on_stack, allocate_on_stack, get_emscripten_memory_size, get_emscripten_metadata,
     get_emscripten_table_size, is_emscripten_module,
 };
+pub use self::varargs::VarArgs;
 
 #[derive(Clone)]
 /// The environment provided to the Emscripten imports.
 pub struct EmEnv {
-    memory: Arc<RwLock<Option<Memory>>>,
+    memories: Arc<RwLock<Vec<Memory>>>,
+    /// The exported memories to use instead of the imported one, by index.
+    memory_exports: Vec<String>,
     data: Arc<Mutex<EmscriptenData>>,
+    syscall_policy: Arc<RwLock<Arc<dyn SyscallPolicy>>>,
 }
 
 impl WasmerEnv for EmEnv {
     fn init_with_instance(&mut self, instance: &Instance) -> Result<(), wasmer::HostEnvInitError> {
+        if !self.memory_exports.is_empty() {
+            let memories = self
+                .memory_exports
+                .iter()
+                .map(|name| instance.exports.get_memory(name).map(Clone::clone))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.set_memories(memories);
+        }
         let mut ed = self.data.lock().unwrap();
         ed.init_with_instance(instance)?;
         Ok(())
@@ -86,19 +103,58 @@ impl WasmerEnv for EmEnv {
 impl EmEnv {
     pub fn new(data: &EmscriptenGlobalsData, mapped_dirs: HashMap<String, PathBuf>) -> Self {
         Self {
-            memory: Arc::new(RwLock::new(None)),
+            memories: Arc::new(RwLock::new(Vec::new())),
+            memory_exports: Vec::new(),
             data: Arc::new(Mutex::new(EmscriptenData::new(data.clone(), mapped_dirs))),
+            syscall_policy: Arc::new(RwLock::new(Arc::new(AllowAllSyscalls))),
         }
     }
 
+    /// Set the memory the Emscripten imports access.
     pub fn set_memory(&mut self, memory: Memory) {
-        let mut w = self.memory.write().unwrap();
-        *w = Some(memory);
+        self.set_memories(vec![memory]);
+    }
+
+    /// Set all the memories the Emscripten imports can access, by index.
+    pub fn set_memories(&mut self, memories: Vec<Memory>) {
+        let mut w = self.memories.write().unwrap();
+        *w = memories;
+    }
+
+    /// Use the memories exported by the instance under `names` instead of
+    /// the memory created by the `EmscriptenGlobals`.
+    ///
+    /// It must be set before generating the imports of the module, and the
+    /// memories are looked up when the instance is created.
+    pub fn set_memory_exports(&mut self, names: Vec<String>) {
+        self.memory_exports = names;
     }
 
-    /// Get a reference to the memory
-    pub fn memory(&self, _mem_idx: u32) -> Memory {
-        (&*self.memory.read().unwrap()).as_ref().cloned().unwrap()
+    /// Whether the memories are looked up in the exports of the instance.
+    pub fn uses_memory_exports(&self) -> bool {
+        !self.memory_exports.is_empty()
+    }
+
+    /// Get a reference to the memory at `mem_idx`
+    pub fn memory(&self, mem_idx: u32) -> Memory {
+        self.memories
+            .read()
+            .unwrap()
+            .get(mem_idx as usize)
+            .cloned()
+            .unwrap_or_else(|| panic!("Memory {} should be set on `EmEnv` first", mem_idx))
+    }
+
+    /// Set the policy checking the syscalls made by the guest, which
+    /// allows all of them by default.
+    pub fn set_syscall_policy(&mut self, policy: impl SyscallPolicy + 'static) {
+        let mut w = self.syscall_policy.write().unwrap();
+        *w = Arc::new(policy);
+    }
+
+    /// Get the policy checking the syscalls made by the guest
+    pub fn syscall_policy(&self) -> Arc<dyn SyscallPolicy> {
+        self.syscall_policy.read().unwrap().clone()
     }
 }
 
@@ -367,7 +423,9 @@ pub fn run_emscripten_instance(
     args: Vec<&str>,
     entrypoint: Option<String>,
 ) -> Result<(), RuntimeError> {
-    env.set_memory(globals.memory.clone());
+    if !env.uses_memory_exports() {
+        env.set_memory(globals.memory.clone());
+    }
     set_up_emscripten(instance)?;
 
     // println!("running emscripten instance");
@@ -618,116 +676,116 @@ pub fn generate_emscripten_env(
 
         // Syscalls
         "___syscall1" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall1),
-        "___syscall3" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall3),
-        "___syscall4" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall4),
-        "___syscall5" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall5),
-        "___syscall6" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall6),
-        "___syscall9" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall9),
-        "___syscall10" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall10),
-        "___syscall12" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall12),
-        "___syscall14" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall14),
-        "___syscall15" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall15),
-        "___syscall20" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall20),
-        "___syscall21" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall21),
-        "___syscall25" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall25),
-        "___syscall29" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall29),
-        "___syscall32" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall32),
-        "___syscall33" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall33),
-        "___syscall34" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall34),
-        "___syscall36" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall36),
-        "___syscall39" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall39),
-        "___syscall38" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall38),
-        "___syscall40" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall40),
-        "___syscall41" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall41),
-        "___syscall42" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall42),
-        "___syscall51" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall51),
-        "___syscall52" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall52),
-        "___syscall53" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall53),
-        "___syscall54" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall54),
-        "___syscall57" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall57),
-        "___syscall60" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall60),
-        "___syscall63" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall63),
-        "___syscall64" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall64),
-        "___syscall66" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall66),
-        "___syscall75" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall75),
-        "___syscall77" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall77),
-        "___syscall83" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall83),
-        "___syscall85" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall85),
-        "___syscall91" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall91),
-        "___syscall94" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall94),
-        "___syscall96" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall96),
-        "___syscall97" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall97),
-        "___syscall102" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall102),
-        "___syscall110" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall110),
-        "___syscall114" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall114),
-        "___syscall118" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall118),
-        "___syscall121" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall121),
-        "___syscall122" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall122),
-        "___syscall125" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall125),
-        "___syscall132" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall132),
-        "___syscall133" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall133),
-        "___syscall140" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall140),
-        "___syscall142" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall142),
-        "___syscall144" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall144),
-        "___syscall145" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall145),
-        "___syscall146" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall146),
-        "___syscall147" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall147),
-        "___syscall148" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall148),
-        "___syscall150" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall150),
-        "___syscall151" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall151),
-        "___syscall152" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall152),
-        "___syscall153" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall153),
-        "___syscall163" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall163),
-        "___syscall168" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall168),
-        "___syscall180" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall180),
-        "___syscall181" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall181),
-        "___syscall183" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall183),
-        "___syscall191" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall191),
-        "___syscall192" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall192),
-        "___syscall193" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall193),
-        "___syscall194" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall194),
-        "___syscall195" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall195),
-        "___syscall196" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall196),
-        "___syscall197" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall197),
-        "___syscall198" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall198),
-        "___syscall199" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall199),
-        "___syscall200" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall200),
-        "___syscall201" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall201),
-        "___syscall202" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall202),
-        "___syscall205" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall205),
-        "___syscall207" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall207),
-        "___syscall209" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall209),
-        "___syscall211" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall211),
-        "___syscall212" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall212),
-        "___syscall218" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall218),
-        "___syscall219" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall219),
-        "___syscall220" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall220),
-        "___syscall221" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall221),
-        "___syscall268" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall268),
-        "___syscall269" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall269),
-        "___syscall272" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall272),
-        "___syscall295" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall295),
-        "___syscall296" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall296),
-        "___syscall297" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall297),
-        "___syscall298" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall298),
-        "___syscall300" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall300),
-        "___syscall301" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall301),
-        "___syscall302" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall302),
-        "___syscall303" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall303),
-        "___syscall304" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall304),
-        "___syscall305" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall305),
-        "___syscall306" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall306),
-        "___syscall307" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall307),
-        "___syscall308" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall308),
-        "___syscall320" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall320),
-        "___syscall324" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall324),
-        "___syscall330" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall330),
-        "___syscall331" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall331),
-        "___syscall333" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall333),
-        "___syscall334" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall334),
-        "___syscall337" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall337),
-        "___syscall340" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall340),
-        "___syscall345" => Function::new_native_with_env(store, env.clone(), crate::syscalls::___syscall345),
+        "___syscall3" => syscall!(store, env, crate::syscalls::___syscall3),
+        "___syscall4" => syscall!(store, env, crate::syscalls::___syscall4),
+        "___syscall5" => syscall!(store, env, crate::syscalls::___syscall5),
+        "___syscall6" => syscall!(store, env, crate::syscalls::___syscall6),
+        "___syscall9" => syscall!(store, env, crate::syscalls::___syscall9),
+        "___syscall10" => syscall!(store, env, crate::syscalls::___syscall10),
+        "___syscall12" => syscall!(store, env, crate::syscalls::___syscall12),
+        "___syscall14" => syscall!(store, env, crate::syscalls::___syscall14),
+        "___syscall15" => syscall!(store, env, crate::syscalls::___syscall15),
+        "___syscall20" => syscall!(store, env, crate::syscalls::___syscall20),
+        "___syscall21" => syscall!(store, env, crate::syscalls::___syscall21),
+        "___syscall25" => syscall!(store, env, crate::syscalls::___syscall25),
+        "___syscall29" => syscall!(store, env, crate::syscalls::___syscall29),
+        "___syscall32" => syscall!(store, env, crate::syscalls::___syscall32),
+        "___syscall33" => syscall!(store, env, crate::syscalls::___syscall33),
+        "___syscall34" => syscall!(store, env, crate::syscalls::___syscall34),
+        "___syscall36" => syscall!(store, env, crate::syscalls::___syscall36),
+        "___syscall39" => syscall!(store, env, crate::syscalls::___syscall39),
+        "___syscall38" => syscall!(store, env, crate::syscalls::___syscall38),
+        "___syscall40" => syscall!(store, env, crate::syscalls::___syscall40),
+        "___syscall41" => syscall!(store, env, crate::syscalls::___syscall41),
+        "___syscall42" => syscall!(store, env, crate::syscalls::___syscall42),
+        "___syscall51" => syscall!(store, env, crate::syscalls::___syscall51),
+        "___syscall52" => syscall!(store, env, crate::syscalls::___syscall52),
+        "___syscall53" => syscall!(store, env, crate::syscalls::___syscall53),
+        "___syscall54" => syscall!(store, env, crate::syscalls::___syscall54),
+        "___syscall57" => syscall!(store, env, crate::syscalls::___syscall57),
+        "___syscall60" => syscall!(store, env, crate::syscalls::___syscall60),
+        "___syscall63" => syscall!(store, env, crate::syscalls::___syscall63),
+        "___syscall64" => syscall!(store, env, crate::syscalls::___syscall64),
+        "___syscall66" => syscall!(store, env, crate::syscalls::___syscall66),
+        "___syscall75" => syscall!(store, env, crate::syscalls::___syscall75),
+        "___syscall77" => syscall!(store, env, crate::syscalls::___syscall77),
+        "___syscall83" => syscall!(store, env, crate::syscalls::___syscall83),
+        "___syscall85" => syscall!(store, env, crate::syscalls::___syscall85),
+        "___syscall91" => syscall!(store, env, crate::syscalls::___syscall91),
+        "___syscall94" => syscall!(store, env, crate::syscalls::___syscall94),
+        "___syscall96" => syscall!(store, env, crate::syscalls::___syscall96),
+        "___syscall97" => syscall!(store, env, crate::syscalls::___syscall97),
+        "___syscall102" => syscall!(store, env, crate::syscalls::___syscall102),
+        "___syscall110" => syscall!(store, env, crate::syscalls::___syscall110),
+        "___syscall114" => syscall!(store, env, crate::syscalls::___syscall114),
+        "___syscall118" => syscall!(store, env, crate::syscalls::___syscall118),
+        "___syscall121" => syscall!(store, env, crate::syscalls::___syscall121),
+        "___syscall122" => syscall!(store, env, crate::syscalls::___syscall122),
+        "___syscall125" => syscall!(store, env, crate::syscalls::___syscall125),
+        "___syscall132" => syscall!(store, env, crate::syscalls::___syscall132),
+        "___syscall133" => syscall!(store, env, crate::syscalls::___syscall133),
+        "___syscall140" => syscall!(store, env, crate::syscalls::___syscall140),
+        "___syscall142" => syscall!(store, env, crate::syscalls::___syscall142),
+        "___syscall144" => syscall!(store, env, crate::syscalls::___syscall144),
+        "___syscall145" => syscall!(store, env, crate::syscalls::___syscall145),
+        "___syscall146" => syscall!(store, env, crate::syscalls::___syscall146),
+        "___syscall147" => syscall!(store, env, crate::syscalls::___syscall147),
+        "___syscall148" => syscall!(store, env, crate::syscalls::___syscall148),
+        "___syscall150" => syscall!(store, env, crate::syscalls::___syscall150),
+        "___syscall151" => syscall!(store, env, crate::syscalls::___syscall151),
+        "___syscall152" => syscall!(store, env, crate::syscalls::___syscall152),
+        "___syscall153" => syscall!(store, env, crate::syscalls::___syscall153),
+        "___syscall163" => syscall!(store, env, crate::syscalls::___syscall163),
+        "___syscall168" => syscall!(store, env, crate::syscalls::___syscall168),
+        "___syscall180" => syscall!(store, env, crate::syscalls::___syscall180),
+        "___syscall181" => syscall!(store, env, crate::syscalls::___syscall181),
+        "___syscall183" => syscall!(store, env, crate::syscalls::___syscall183),
+        "___syscall191" => syscall!(store, env, crate::syscalls::___syscall191),
+        "___syscall192" => syscall!(store, env, crate::syscalls::___syscall192),
+        "___syscall193" => syscall!(store, env, crate::syscalls::___syscall193),
+        "___syscall194" => syscall!(store, env, crate::syscalls::___syscall194),
+        "___syscall195" => syscall!(store, env, crate::syscalls::___syscall195),
+        "___syscall196" => syscall!(store, env, crate::syscalls::___syscall196),
+        "___syscall197" => syscall!(store, env, crate::syscalls::___syscall197),
+        "___syscall198" => syscall!(store, env, crate::syscalls::___syscall198),
+        "___syscall199" => syscall!(store, env, crate::syscalls::___syscall199),
+        "___syscall200" => syscall!(store, env, crate::syscalls::___syscall200),
+        "___syscall201" => syscall!(store, env, crate::syscalls::___syscall201),
+        "___syscall202" => syscall!(store, env, crate::syscalls::___syscall202),
+        "___syscall205" => syscall!(store, env, crate::syscalls::___syscall205),
+        "___syscall207" => syscall!(store, env, crate::syscalls::___syscall207),
+        "___syscall209" => syscall!(store, env, crate::syscalls::___syscall209),
+        "___syscall211" => syscall!(store, env, crate::syscalls::___syscall211),
+        "___syscall212" => syscall!(store, env, crate::syscalls::___syscall212),
+        "___syscall218" => syscall!(store, env, crate::syscalls::___syscall218),
+        "___syscall219" => syscall!(store, env, crate::syscalls::___syscall219),
+        "___syscall220" => syscall!(store, env, crate::syscalls::___syscall220),
+        "___syscall221" => syscall!(store, env, crate::syscalls::___syscall221),
+        "___syscall268" => syscall!(store, env, crate::syscalls::___syscall268),
+        "___syscall269" => syscall!(store, env, crate::syscalls::___syscall269),
+        "___syscall272" => syscall!(store, env, crate::syscalls::___syscall272),
+        "___syscall295" => syscall!(store, env, crate::syscalls::___syscall295),
+        "___syscall296" => syscall!(store, env, crate::syscalls::___syscall296),
+        "___syscall297" => syscall!(store, env, crate::syscalls::___syscall297),
+        "___syscall298" => syscall!(store, env, crate::syscalls::___syscall298),
+        "___syscall300" => syscall!(store, env, crate::syscalls::___syscall300),
+        "___syscall301" => syscall!(store, env, crate::syscalls::___syscall301),
+        "___syscall302" => syscall!(store, env, crate::syscalls::___syscall302),
+        "___syscall303" => syscall!(store, env, crate::syscalls::___syscall303),
+        "___syscall304" => syscall!(store, env, crate::syscalls::___syscall304),
+        "___syscall305" => syscall!(store, env, crate::syscalls::___syscall305),
+        "___syscall306" => syscall!(store, env, crate::syscalls::___syscall306),
+        "___syscall307" => syscall!(store, env, crate::syscalls::___syscall307),
+        "___syscall308" => syscall!(store, env, crate::syscalls::___syscall308),
+        "___syscall320" => syscall!(store, env, crate::syscalls::___syscall320),
+        "___syscall324" => syscall!(store, env, crate::syscalls::___syscall324),
+        "___syscall330" => syscall!(store, env, crate::syscalls::___syscall330),
+        "___syscall331" => syscall!(store, env, crate::syscalls::___syscall331),
+        "___syscall333" => syscall!(store, env, crate::syscalls::___syscall333),
+        "___syscall334" => syscall!(store, env, crate::syscalls::___syscall334),
+        "___syscall337" => syscall!(store, env, crate::syscalls::___syscall337),
+        "___syscall340" => syscall!(store, env, crate::syscalls::___syscall340),
+        "___syscall345" => syscall!(store, env, crate::syscalls::___syscall345),
 
         // Process
         "abort" => Function::new_native_with_env(store, env.clone(), crate::process::em_abort),
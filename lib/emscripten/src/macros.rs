@@ -4,3 +4,17 @@ macro_rules! emscripten_memory_pointer {
         (&$memory.view::<u8>()[($pointer as usize)..]).as_ptr() as *mut Cell<u8> as *mut u8
     }};
 }
+
+/// A host function making a syscall, if the `SyscallPolicy` of the
+/// environment allows it.
+macro_rules! syscall {
+    ($store:expr, $env:expr, $syscall:path) => {
+        Function::new_native_with_env(
+            $store,
+            $env.clone(),
+            |env: &crate::EmEnv, which: i32, varargs: i32| -> i32 {
+                crate::syscalls::checked_syscall(env, which, varargs, $syscall)
+            },
+        )
+    };
+}
@@ -0,0 +1,937 @@
+//! A sandbox confining the files of an Emscripten guest to the
+//! directories preopened in a [`WasiFs`], with the same access rules
+//! as a WASI guest.
+//!
+//! [`EmSandbox`] is a [`SyscallPolicy`]: it makes the file syscalls
+//! itself, on the files of the [`WasiFs`], allows the syscalls which
+//! don't reach the host filesystem, and denies all the others.
+
+use crate::errno::ENOTTY;
+use crate::syscalls::{SyscallAction, SyscallPolicy};
+use crate::utils::copy_stat_into_wasm;
+use crate::varargs::VarArgs;
+use crate::EmEnv;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{CString, OsStr};
+use std::fs;
+use std::io::{self, SeekFrom};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, DirEntryExt, MetadataExt, OpenOptionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use wasmer_wasi::types::*;
+use wasmer_wasi::{HostFile, WasiFile, WasiFs, WasiFsError, VIRTUAL_ROOT_FD};
+
+// The errnos of the guest, as defined by Emscripten's libc.
+const EPERM: i32 = 1;
+const ENOENT: i32 = 2;
+const EIO: i32 = 5;
+const EBADF: i32 = 9;
+const EACCES: i32 = 13;
+const EFAULT: i32 = 14;
+const EEXIST: i32 = 17;
+const EXDEV: i32 = 18;
+const ENOTDIR: i32 = 20;
+const EISDIR: i32 = 21;
+const EINVAL: i32 = 22;
+const EMFILE: i32 = 24;
+const EFBIG: i32 = 27;
+const ENOSPC: i32 = 28;
+const ESPIPE: i32 = 29;
+const EROFS: i32 = 30;
+const ERANGE: i32 = 34;
+const ENAMETOOLONG: i32 = 36;
+const ENOTEMPTY: i32 = 39;
+const ELOOP: i32 = 40;
+
+// The flags of `open` and `fcntl`.
+const O_ACCMODE: i32 = 0o3;
+const O_WRONLY: i32 = 0o1;
+const O_RDWR: i32 = 0o2;
+const O_CREAT: i32 = 0o100;
+const O_EXCL: i32 = 0o200;
+const O_TRUNC: i32 = 0o1000;
+const O_APPEND: i32 = 0o2000;
+const O_DIRECTORY: i32 = 0o200000;
+const O_NOFOLLOW: i32 = 0o400000;
+const F_GETFD: i32 = 1;
+const F_SETFD: i32 = 2;
+const F_GETFL: i32 = 3;
+const F_SETFL: i32 = 4;
+const W_OK: i32 = 2;
+
+// The types of `dirent` entries.
+const DT_UNKNOWN: u8 = 0;
+const DT_DIR: u8 = 4;
+const DT_REG: u8 = 8;
+const DT_LNK: u8 = 10;
+
+/// The size of the `dirent` records written by `getdents64`.
+const DIRENT_LEN: usize = 256 + 12;
+
+/// A value returned to the guest, or the errno the syscall fails with.
+type SyscallResult = Result<i32, i32>;
+
+/// A sandbox for the files of an Emscripten guest, to set with
+/// [`EmEnv::set_syscall_policy`].
+///
+/// The guest sees the directories preopened in the [`WasiFs`] under
+/// their names, and a directory preopened as `.` as `/`.  Relative
+/// paths start from the current directory of the guest, which is `/`
+/// at first.  As for WASI guests, symlinks and `..` can't leave the
+/// preopened directories, and paths denied by the access rules don't
+/// exist.
+///
+/// Syscalls creating processes or reaching the host filesystem in
+/// other ways fail with `ENOSYS`, and so do `execvp` and `chroot`.
+pub struct EmSandbox {
+    state: Mutex<SandboxState>,
+}
+
+impl EmSandbox {
+    /// Creates a sandbox giving the guest the directories preopened in
+    /// `fs`, and its stdin, stdout and stderr.
+    pub fn new(fs: WasiFs) -> Self {
+        Self {
+            state: Mutex::new(SandboxState {
+                fs,
+                cwd: PathBuf::from("/"),
+                files: HashMap::new(),
+                next_fd: 3,
+            }),
+        }
+    }
+}
+
+impl SyscallPolicy for EmSandbox {
+    fn check(&self, env: &EmEnv, which: i32, mut varargs: VarArgs) -> SyscallAction {
+        let mut state = self.state.lock().unwrap();
+        let varargs = &mut varargs;
+        let result = match which {
+            3 => state.read(env, varargs),
+            4 => state.write(env, varargs),
+            5 => state.open(env, varargs),
+            6 => state.close(env, varargs),
+            10 => state.unlink(env, varargs),
+            12 => state.chdir(env, varargs),
+            33 => state.access(env, varargs),
+            38 => state.rename(env, varargs),
+            39 => state.mkdir(env, varargs),
+            40 => state.rmdir(env, varargs),
+            54 => state.ioctl(env, varargs),
+            85 => state.readlink(env, varargs),
+            118 | 148 => state.fsync(env, varargs),
+            140 => state.llseek(env, varargs),
+            145 => state.readv(env, varargs),
+            146 => state.writev(env, varargs),
+            183 => state.getcwd(env, varargs),
+            194 => state.ftruncate(env, varargs),
+            195 => state.stat(env, varargs, true),
+            196 => state.stat(env, varargs, false),
+            197 => state.fstat(env, varargs),
+            220 => state.getdents(env, varargs),
+            221 => state.fcntl(env, varargs),
+            // `getpid`, `umask`, `getppid`, `setsid`, `setrlimit`,
+            // `getrusage`, `munmap`, `uname`, `mprotect`, `mremap`,
+            // `ugetrlimit`, `mmap2`, the `get*id32`, `utimensat` and
+            // `prlimit64` only query the host, or are stubs.
+            20
+            | 60
+            | 64
+            | 66
+            | 75
+            | 77
+            | 91
+            | 122
+            | 125
+            | 163
+            | 191
+            | 192
+            | 199..=202
+            | 320
+            | 340 => return SyscallAction::Allow,
+            _ => return SyscallAction::Deny,
+        };
+        SyscallAction::Return(result.unwrap_or_else(|errno| -errno))
+    }
+}
+
+struct SandboxState {
+    fs: WasiFs,
+    /// The current directory of the guest, absolute and normalized.
+    cwd: PathBuf,
+    files: HashMap<i32, OpenFile>,
+    next_fd: i32,
+}
+
+enum OpenFile {
+    File {
+        file: Box<dyn WasiFile>,
+        rights: __wasi_rights_t,
+        flags: i32,
+    },
+    Dir {
+        host_path: PathBuf,
+        /// The entries not read yet by `getdents64`.
+        entries: VecDeque<DirEntry>,
+    },
+}
+
+struct DirEntry {
+    name: Vec<u8>,
+    ino: u64,
+    kind: u8,
+}
+
+impl SandboxState {
+    fn read(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let buf: u32 = varargs.get(env);
+        let count: u32 = varargs.get(env);
+        self.read_into(env, fd, buf, count)
+    }
+
+    fn write(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let buf: u32 = varargs.get(env);
+        let count: u32 = varargs.get(env);
+        self.write_from(env, fd, buf, count)
+    }
+
+    fn readv(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let iov: u32 = varargs.get(env);
+        let iovcnt: i32 = varargs.get(env);
+        let mut total = 0;
+        for (buf, len) in read_iovecs(env, iov, iovcnt)? {
+            let read = self.read_into(env, fd, buf, len)?;
+            total += read;
+            if (read as u32) < len {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    fn writev(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let iov: u32 = varargs.get(env);
+        let iovcnt: i32 = varargs.get(env);
+        let mut total = 0;
+        for (buf, len) in read_iovecs(env, iov, iovcnt)? {
+            total += self.write_from(env, fd, buf, len)?;
+        }
+        Ok(total)
+    }
+
+    fn read_into(&mut self, env: &EmEnv, fd: i32, buf: u32, count: u32) -> SyscallResult {
+        check_guest_range(env, buf, count)?;
+        let mut bytes = vec![0; count as usize];
+        let read = self
+            .file(fd, __WASI_RIGHT_FD_READ)?
+            .read(&mut bytes)
+            .map_err(io_errno)?;
+        write_guest(env, buf, &bytes[..read])?;
+        Ok(read as i32)
+    }
+
+    fn write_from(&mut self, env: &EmEnv, fd: i32, buf: u32, count: u32) -> SyscallResult {
+        let bytes = read_guest(env, buf, count)?;
+        let written = self
+            .file(fd, __WASI_RIGHT_FD_WRITE)?
+            .write(&bytes)
+            .map_err(io_errno)?;
+        Ok(written as i32)
+    }
+
+    fn open(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let flags: i32 = varargs.get(env);
+        let mode: u32 = varargs.get(env);
+        self.open_path(&path, flags, mode)
+    }
+
+    fn open_path(&mut self, path: &str, flags: i32, mode: u32) -> SyscallResult {
+        let follow_symlinks = flags & O_NOFOLLOW == 0;
+        let (host_path, rights) = self.resolve(path, follow_symlinks)?;
+        require(rights, __WASI_RIGHT_PATH_OPEN)?;
+
+        let metadata = if follow_symlinks {
+            fs::metadata(&host_path)
+        } else {
+            fs::symlink_metadata(&host_path)
+        };
+        if metadata.as_ref().map_or(false, fs::Metadata::is_dir) {
+            if flags & O_ACCMODE != 0 {
+                return Err(EISDIR);
+            }
+            let entries = self.dir_entries(&host_path)?;
+            return self.insert(OpenFile::Dir { host_path, entries });
+        }
+        if flags & O_DIRECTORY != 0 {
+            return Err(if metadata.is_ok() { ENOTDIR } else { ENOENT });
+        }
+
+        let read = flags & O_ACCMODE != O_WRONLY;
+        let write = flags & O_ACCMODE == O_WRONLY || flags & O_ACCMODE == O_RDWR;
+        let append = flags & O_APPEND != 0;
+        let create = flags & O_CREAT != 0 && metadata.is_err();
+        let truncate = flags & O_TRUNC != 0 && write;
+        if write {
+            require(rights, __WASI_RIGHT_FD_WRITE)?;
+        }
+        if create {
+            require(rights, __WASI_RIGHT_PATH_CREATE_FILE)?;
+        }
+        if truncate {
+            require(rights, __WASI_RIGHT_PATH_FILESTAT_SET_SIZE)?;
+        }
+        let file = fs::OpenOptions::new()
+            .read(read)
+            // files created read-only still have to be opened to write
+            .write(write || create)
+            .append(append)
+            .truncate(truncate)
+            .create(flags & O_CREAT != 0)
+            .create_new(flags & O_CREAT != 0 && flags & O_EXCL != 0)
+            .mode(mode)
+            // the last component was resolved without following it
+            .custom_flags(if follow_symlinks { 0 } else { libc::O_NOFOLLOW })
+            .open(&host_path)
+            .map_err(io_errno)?;
+
+        let mut rights = rights;
+        if !read {
+            rights &= !__WASI_RIGHT_FD_READ;
+        }
+        if !write {
+            rights &= !__WASI_RIGHT_FD_WRITE;
+        }
+        self.insert(OpenFile::File {
+            file: Box::new(HostFile::new(file, host_path, read, write, append)),
+            rights,
+            flags: flags & (O_ACCMODE | O_APPEND),
+        })
+    }
+
+    fn close(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        match fd {
+            // stdin, stdout and stderr stay open for the host
+            0..=2 => Ok(0),
+            _ => self.files.remove(&fd).map(|_| 0).ok_or(EBADF),
+        }
+    }
+
+    fn unlink(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let (host_path, rights) = self.resolve(&path, false)?;
+        require(rights, __WASI_RIGHT_PATH_UNLINK_FILE)?;
+        if fs::symlink_metadata(&host_path).map_or(false, |metadata| metadata.is_dir()) {
+            return Err(EISDIR);
+        }
+        fs::remove_file(&host_path).map_err(io_errno)?;
+        self.fs.forget_host_path(&host_path);
+        Ok(0)
+    }
+
+    fn mkdir(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let mode: u32 = varargs.get(env);
+        let (host_path, rights) = self.resolve(&path, false)?;
+        require(rights, __WASI_RIGHT_PATH_CREATE_DIRECTORY)?;
+        fs::DirBuilder::new()
+            .mode(mode)
+            .create(&host_path)
+            .map_err(io_errno)?;
+        Ok(0)
+    }
+
+    fn rmdir(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let (host_path, rights) = self.resolve(&path, false)?;
+        require(rights, __WASI_RIGHT_PATH_REMOVE_DIRECTORY)?;
+        fs::remove_dir(&host_path).map_err(io_errno)?;
+        self.fs.forget_host_path(&host_path);
+        Ok(0)
+    }
+
+    fn rename(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let old_path = read_guest_str(env, varargs.get(env))?;
+        let new_path = read_guest_str(env, varargs.get(env))?;
+        let (old_host_path, old_rights) = self.resolve(&old_path, false)?;
+        let (new_host_path, new_rights) = self.resolve(&new_path, false)?;
+        require(old_rights, __WASI_RIGHT_PATH_RENAME_SOURCE)?;
+        require(new_rights, __WASI_RIGHT_PATH_RENAME_TARGET)?;
+        fs::rename(&old_host_path, &new_host_path).map_err(io_errno)?;
+        self.fs.forget_host_path(&old_host_path);
+        self.fs.forget_host_path(&new_host_path);
+        Ok(0)
+    }
+
+    fn chdir(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let (host_path, _) = self.resolve(&path, true)?;
+        if !host_path.is_dir() {
+            return Err(ENOTDIR);
+        }
+        self.cwd = self.guest_path(&path);
+        Ok(0)
+    }
+
+    fn getcwd(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let buf: u32 = varargs.get(env);
+        let size: u32 = varargs.get(env);
+        let mut cwd = self.cwd.as_os_str().as_bytes().to_vec();
+        cwd.push(0);
+        if (size as usize) < cwd.len() {
+            return Err(ERANGE);
+        }
+        write_guest(env, buf, &cwd)?;
+        Ok(buf as i32)
+    }
+
+    fn access(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let amode: i32 = varargs.get(env);
+        let (host_path, rights) = self.resolve(&path, true)?;
+        if amode & W_OK != 0 {
+            require(rights, __WASI_RIGHT_FD_WRITE)?;
+        }
+        let host_path = host_c_path(&host_path)?;
+        if unsafe { libc::access(host_path.as_ptr(), amode) } != 0 {
+            return Err(io_errno(io::Error::last_os_error()));
+        }
+        Ok(0)
+    }
+
+    fn readlink(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let buf: u32 = varargs.get(env);
+        let size: u32 = varargs.get(env);
+        let (host_path, rights) = self.resolve(&path, false)?;
+        require(rights, __WASI_RIGHT_PATH_READLINK)?;
+        let target = fs::read_link(&host_path).map_err(io_errno)?;
+        let target = target.as_os_str().as_bytes();
+        let len = target.len().min(size as usize);
+        write_guest(env, buf, &target[..len])?;
+        Ok(len as i32)
+    }
+
+    fn stat(&mut self, env: &EmEnv, varargs: &mut VarArgs, follow_symlinks: bool) -> SyscallResult {
+        let path = read_guest_str(env, varargs.get(env))?;
+        let buf: u32 = varargs.get(env);
+        let (host_path, rights) = self.resolve(&path, follow_symlinks)?;
+        require(rights, __WASI_RIGHT_PATH_FILESTAT_GET)?;
+        let stat = host_stat(&host_path, follow_symlinks)?;
+        copy_stat(env, buf, &stat)
+    }
+
+    fn fstat(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let buf: u32 = varargs.get(env);
+        let stat = match self.files.get(&fd) {
+            Some(OpenFile::Dir { host_path, .. }) => host_stat(host_path, true)?,
+            _ => {
+                let file = self.file(fd, __WASI_RIGHT_FD_FILESTAT_GET)?;
+                match file.get_raw_fd() {
+                    Some(raw_fd) => {
+                        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+                        if unsafe { libc::fstat(raw_fd, &mut stat) } != 0 {
+                            return Err(io_errno(io::Error::last_os_error()));
+                        }
+                        stat
+                    }
+                    // files living in the host only, like pipes
+                    None => {
+                        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+                        stat.st_mode = libc::S_IFREG | 0o600;
+                        stat.st_size = file.size() as _;
+                        stat
+                    }
+                }
+            }
+        };
+        copy_stat(env, buf, &stat)
+    }
+
+    fn ftruncate(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let length: i64 = varargs.get(env);
+        if length < 0 {
+            return Err(EINVAL);
+        }
+        self.file(fd, __WASI_RIGHT_FD_FILESTAT_SET_SIZE)?
+            .set_len(length as u64)
+            .map_err(wasi_fs_errno)?;
+        Ok(0)
+    }
+
+    fn fsync(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        if let Some(OpenFile::Dir { .. }) = self.files.get(&fd) {
+            return Ok(0);
+        }
+        self.file(fd, 0)?.sync_to_disk().map_err(wasi_fs_errno)?;
+        Ok(0)
+    }
+
+    fn llseek(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let offset_high: u32 = varargs.get(env);
+        let offset_low: u32 = varargs.get(env);
+        let result: u32 = varargs.get(env);
+        let whence: i32 = varargs.get(env);
+        if (0..=2).contains(&fd) {
+            return Err(ESPIPE);
+        }
+        let offset = ((offset_high as u64) << 32 | offset_low as u64) as i64;
+        let position = match whence {
+            0 if offset >= 0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(EINVAL),
+        };
+        let position = self
+            .file(fd, __WASI_RIGHT_FD_SEEK)?
+            .seek(position)
+            .map_err(io_errno)?;
+        write_guest(env, result, &(position as i64).to_le_bytes())?;
+        Ok(0)
+    }
+
+    fn ioctl(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        // none of the files of the sandbox is a terminal
+        if (0..=2).contains(&fd) || self.files.contains_key(&fd) {
+            Err(ENOTTY)
+        } else {
+            Err(EBADF)
+        }
+    }
+
+    fn fcntl(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let cmd: i32 = varargs.get(env);
+        let flags = match (fd, self.files.get(&fd)) {
+            (0, _) => 0,
+            (1, _) | (2, _) => O_WRONLY,
+            (_, Some(OpenFile::File { flags, .. })) => *flags,
+            (_, Some(OpenFile::Dir { .. })) => O_DIRECTORY,
+            (_, None) => return Err(EBADF),
+        };
+        match cmd {
+            // there is no `exec` to close files on, and the flags which
+            // can be set don't matter to the sandbox
+            F_GETFD | F_SETFD | F_SETFL => Ok(0),
+            F_GETFL => Ok(flags),
+            _ => Err(EINVAL),
+        }
+    }
+
+    fn getdents(&mut self, env: &EmEnv, varargs: &mut VarArgs) -> SyscallResult {
+        let fd: i32 = varargs.get(env);
+        let dirp: u32 = varargs.get(env);
+        let count: u32 = varargs.get(env);
+        let entries = match self.files.get_mut(&fd) {
+            Some(OpenFile::Dir { entries, .. }) => entries,
+            Some(OpenFile::File { .. }) => return Err(ENOTDIR),
+            None => return Err(EBADF),
+        };
+        let mut records = vec![];
+        while records.len() + DIRENT_LEN <= count as usize {
+            let entry = match entries.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+            let mut record = [0; DIRENT_LEN];
+            record[0..4].copy_from_slice(&(entry.ino as u32).to_le_bytes());
+            record[4..8].copy_from_slice(&(records.len() as u32).to_le_bytes());
+            record[8..10].copy_from_slice(&(DIRENT_LEN as u16).to_le_bytes());
+            record[10] = entry.kind;
+            let name_len = entry.name.len().min(255);
+            record[11..11 + name_len].copy_from_slice(&entry.name[..name_len]);
+            records.extend_from_slice(&record);
+        }
+        if records.is_empty() && !entries.is_empty() {
+            return Err(EINVAL);
+        }
+        write_guest(env, dirp, &records)?;
+        Ok(records.len() as i32)
+    }
+
+    /// The absolute guest path of `path`, without `.` and `..`.
+    fn guest_path(&self, path: &str) -> PathBuf {
+        let mut guest_path = PathBuf::from("/");
+        for component in self.cwd.join(path).components() {
+            match component {
+                Component::Normal(name) => guest_path.push(name),
+                Component::ParentDir => {
+                    guest_path.pop();
+                }
+                _ => (),
+            }
+        }
+        guest_path
+    }
+
+    /// Resolves the guest path `path` to a host path, in the preopened
+    /// directory with the longest name it starts with.
+    fn resolve(
+        &mut self,
+        path: &str,
+        follow_symlinks: bool,
+    ) -> Result<(PathBuf, __wasi_rights_t), i32> {
+        if path.is_empty() {
+            return Err(ENOENT);
+        }
+        let guest_path = self.guest_path(path);
+        let guest_components = normal_components(&guest_path);
+
+        let mut preopen: Option<(u32, usize)> = None;
+        for &fd in &self.fs.preopen_fds {
+            if fd == VIRTUAL_ROOT_FD {
+                continue;
+            }
+            let inode = match self.fs.fd_map.get(&fd) {
+                Some(fd) => fd.inode,
+                None => continue,
+            };
+            let name = normal_components(Path::new(&self.fs.inodes[inode].name));
+            if guest_components.starts_with(&name)
+                && preopen.map_or(true, |(_, len)| name.len() > len)
+            {
+                preopen = Some((fd, name.len()));
+            }
+        }
+        let (fd, len) = preopen.ok_or(ENOENT)?;
+
+        let relative = guest_components[len..]
+            .iter()
+            .map(|component| component.to_str().ok_or(EINVAL))
+            .collect::<Result<Vec<_>, _>>()?
+            .join("/");
+        let relative = if relative.is_empty() { "." } else { &relative };
+        self.fs
+            .resolve_host_path(fd, relative, follow_symlinks)
+            .map_err(wasi_errno)
+    }
+
+    /// The entries of a directory, without the ones denied by the
+    /// access rules.
+    fn dir_entries(&self, host_path: &Path) -> Result<VecDeque<DirEntry>, i32> {
+        let ino = fs::metadata(host_path).map_err(io_errno)?.ino();
+        let mut entries = VecDeque::new();
+        for name in &[".", ".."] {
+            entries.push_back(DirEntry {
+                name: name.as_bytes().to_vec(),
+                ino,
+                kind: DT_DIR,
+            });
+        }
+        for entry in fs::read_dir(host_path).map_err(io_errno)? {
+            let entry = entry.map_err(io_errno)?;
+            if self.fs.path_rights(&entry.path()) == 0 {
+                continue;
+            }
+            let file_type = entry.file_type().map_err(io_errno)?;
+            let kind = if file_type.is_dir() {
+                DT_DIR
+            } else if file_type.is_file() {
+                DT_REG
+            } else if file_type.is_symlink() {
+                DT_LNK
+            } else {
+                DT_UNKNOWN
+            };
+            entries.push_back(DirEntry {
+                name: entry.file_name().as_bytes().to_vec(),
+                ino: entry.ino(),
+                kind,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// The file open at `fd`, if it was opened with the `needed` rights.
+    fn file(&mut self, fd: i32, needed: __wasi_rights_t) -> Result<&mut dyn WasiFile, i32> {
+        let file = match fd {
+            0 => self.fs.stdin_mut(),
+            1 => self.fs.stdout_mut(),
+            2 => self.fs.stderr_mut(),
+            _ => {
+                return match self.files.get_mut(&fd) {
+                    Some(OpenFile::File { file, rights, .. }) if *rights & needed == needed => {
+                        Ok(&mut **file)
+                    }
+                    Some(OpenFile::Dir { .. }) => Err(EISDIR),
+                    _ => Err(EBADF),
+                }
+            }
+        };
+        match file.map_err(wasi_fs_errno)? {
+            Some(file) => Ok(&mut **file),
+            None => Err(EBADF),
+        }
+    }
+
+    fn insert(&mut self, file: OpenFile) -> SyscallResult {
+        let fd = self.next_fd;
+        self.next_fd = fd.checked_add(1).ok_or(EMFILE)?;
+        self.files.insert(fd, file);
+        Ok(fd)
+    }
+}
+
+fn normal_components(path: &Path) -> Vec<&OsStr> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+fn require(rights: __wasi_rights_t, needed: __wasi_rights_t) -> Result<(), i32> {
+    if rights & needed == needed {
+        Ok(())
+    } else {
+        Err(EACCES)
+    }
+}
+
+fn host_c_path(host_path: &Path) -> Result<CString, i32> {
+    CString::new(host_path.as_os_str().as_bytes()).map_err(|_| EINVAL)
+}
+
+fn host_stat(host_path: &Path, follow_symlinks: bool) -> Result<libc::stat, i32> {
+    let host_path = host_c_path(host_path)?;
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        if follow_symlinks {
+            libc::stat(host_path.as_ptr(), &mut stat)
+        } else {
+            libc::lstat(host_path.as_ptr(), &mut stat)
+        }
+    };
+    if result != 0 {
+        return Err(io_errno(io::Error::last_os_error()));
+    }
+    Ok(stat)
+}
+
+fn copy_stat(env: &EmEnv, buf: u32, stat: &libc::stat) -> SyscallResult {
+    // the largest `stat` of the guest
+    check_guest_range(env, buf, 96)?;
+    unsafe { copy_stat_into_wasm(env, buf, stat) };
+    Ok(0)
+}
+
+fn read_iovecs(env: &EmEnv, iov: u32, iovcnt: i32) -> Result<Vec<(u32, u32)>, i32> {
+    if iovcnt < 0 {
+        return Err(EINVAL);
+    }
+    let bytes = read_guest(env, iov, (iovcnt as u32).checked_mul(8).ok_or(EINVAL)?)?;
+    Ok(bytes
+        .chunks(8)
+        .map(|iovec| {
+            let word = |at: usize| {
+                u32::from_le_bytes([iovec[at], iovec[at + 1], iovec[at + 2], iovec[at + 3]])
+            };
+            (word(0), word(4))
+        })
+        .collect())
+}
+
+fn check_guest_range(env: &EmEnv, ptr: u32, len: u32) -> Result<(), i32> {
+    let end = ptr as usize + len as usize;
+    if end > env.memory(0).view::<u8>().len() {
+        return Err(EFAULT);
+    }
+    Ok(())
+}
+
+fn read_guest(env: &EmEnv, ptr: u32, len: u32) -> Result<Vec<u8>, i32> {
+    let memory = env.memory(0);
+    let view = memory.view::<u8>();
+    let cells = view
+        .get(ptr as usize..ptr as usize + len as usize)
+        .ok_or(EFAULT)?;
+    Ok(cells.iter().map(|cell| cell.get()).collect())
+}
+
+fn read_guest_str(env: &EmEnv, ptr: u32) -> Result<String, i32> {
+    let memory = env.memory(0);
+    let view = memory.view::<u8>();
+    let bytes = view
+        .get(ptr as usize..)
+        .ok_or(EFAULT)?
+        .iter()
+        .map(|cell| cell.get())
+        .take_while(|&byte| byte != 0)
+        .collect::<Vec<_>>();
+    if bytes.len() > 4096 {
+        return Err(ENAMETOOLONG);
+    }
+    String::from_utf8(bytes).map_err(|_| EINVAL)
+}
+
+fn write_guest(env: &EmEnv, ptr: u32, bytes: &[u8]) -> Result<(), i32> {
+    let memory = env.memory(0);
+    let view = memory.view::<u8>();
+    let cells = view
+        .get(ptr as usize..ptr as usize + bytes.len())
+        .ok_or(EFAULT)?;
+    for (cell, &byte) in cells.iter().zip(bytes) {
+        cell.set(byte);
+    }
+    Ok(())
+}
+
+/// The errno of `error`, numbered like in emscripten's libc.
+pub(crate) fn io_errno(error: io::Error) -> i32 {
+    match error.raw_os_error() {
+        Some(libc::EPERM) => EPERM,
+        Some(libc::ENOENT) => ENOENT,
+        Some(libc::EBADF) => EBADF,
+        Some(libc::EACCES) => EACCES,
+        Some(libc::EEXIST) => EEXIST,
+        Some(libc::EXDEV) => EXDEV,
+        Some(libc::ENOTDIR) => ENOTDIR,
+        Some(libc::EISDIR) => EISDIR,
+        Some(libc::EINVAL) => EINVAL,
+        Some(libc::EFBIG) => EFBIG,
+        Some(libc::ENOSPC) => ENOSPC,
+        Some(libc::ESPIPE) => ESPIPE,
+        Some(libc::EROFS) => EROFS,
+        Some(libc::ENAMETOOLONG) => ENAMETOOLONG,
+        Some(libc::ENOTEMPTY) => ENOTEMPTY,
+        Some(libc::ELOOP) => ELOOP,
+        _ => EIO,
+    }
+}
+
+fn wasi_errno(errno: __wasi_errno_t) -> i32 {
+    match errno {
+        __WASI_EACCES => EACCES,
+        __WASI_EBADF => EBADF,
+        __WASI_ENOENT => ENOENT,
+        __WASI_ENOTDIR => ENOTDIR,
+        __WASI_EINVAL => EINVAL,
+        __WASI_ENAMETOOLONG => ENAMETOOLONG,
+        // symlinks are followed up to a depth, which WASI reports as
+        // `EMLINK`
+        __WASI_EMLINK | __WASI_ELOOP => ELOOP,
+        _ => EIO,
+    }
+}
+
+fn wasi_fs_errno(error: WasiFsError) -> i32 {
+    wasi_errno(error.into_wasi_err())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use wasmer_wasi::{PathAccess, WasiState};
+
+    /// A sandbox giving the directory `sandbox` of a scratch directory,
+    /// next to a directory `outside` it must not reach.
+    fn sandbox(name: &str) -> (PathBuf, SandboxState) {
+        let dir = std::env::temp_dir().join(format!(
+            "wasmer-emscripten-sandbox-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        for subdir in &["sandbox/denied", "sandbox/read-only", "outside"] {
+            fs::create_dir_all(dir.join(subdir)).unwrap();
+        }
+        for file in &[
+            "sandbox/file",
+            "sandbox/denied/file",
+            "sandbox/read-only/file",
+            "outside/secret",
+        ] {
+            fs::write(dir.join(file), b"data").unwrap();
+        }
+        symlink("../outside", dir.join("sandbox/escape")).unwrap();
+        symlink("file", dir.join("sandbox/link")).unwrap();
+
+        let fs = WasiState::new("")
+            .map_dir("sandbox", dir.join("sandbox"))
+            .unwrap()
+            .path_access("/sandbox/denied", PathAccess::Deny)
+            .path_access("/sandbox/read-only", PathAccess::ReadOnly)
+            .build()
+            .unwrap()
+            .fs;
+        let state = SandboxState {
+            fs,
+            cwd: PathBuf::from("/"),
+            files: HashMap::new(),
+            next_fd: 3,
+        };
+        (dir, state)
+    }
+
+    #[test]
+    fn files_of_the_preopened_directories_can_be_opened() {
+        let (dir, mut state) = sandbox("open");
+        assert_eq!(state.open_path("/sandbox/file", 0, 0), Ok(3));
+        assert_eq!(state.open_path("sandbox/link", O_RDWR, 0), Ok(4));
+        assert_eq!(
+            state.open_path("/sandbox/new", O_WRONLY | O_CREAT, 0o600),
+            Ok(5)
+        );
+        assert!(dir.join("sandbox/new").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn paths_cannot_leave_the_preopened_directories() {
+        let (dir, mut state) = sandbox("escape");
+        assert_eq!(
+            state.open_path("/sandbox/../outside/secret", 0, 0),
+            Err(ENOENT)
+        );
+        assert_eq!(state.open_path("../../outside/secret", 0, 0), Err(ENOENT));
+        let absolute = dir.join("outside/secret");
+        assert_eq!(
+            state.open_path(absolute.to_str().unwrap(), 0, 0),
+            Err(ENOENT)
+        );
+        assert!(state.open_path("/sandbox/escape/secret", 0, 0).is_err());
+        assert!(state.files.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn symlinks_are_not_followed_with_o_nofollow() {
+        let (dir, mut state) = sandbox("nofollow");
+        assert!(state
+            .open_path("/sandbox/link", O_WRONLY | O_TRUNC | O_NOFOLLOW, 0)
+            .is_err());
+        assert_eq!(fs::read(dir.join("sandbox/file")).unwrap(), b"data");
+        assert_eq!(state.open_path("/sandbox/file", O_NOFOLLOW, 0), Ok(3));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn access_rules_deny_opens() {
+        let (dir, mut state) = sandbox("rules");
+        assert_eq!(state.open_path("/sandbox/denied/file", 0, 0), Err(ENOENT));
+        assert_eq!(
+            state.open_path("/sandbox/read-only/file", O_WRONLY, 0),
+            Err(EACCES)
+        );
+        assert_eq!(
+            state.open_path("/sandbox/read-only/new", O_WRONLY | O_CREAT, 0o600),
+            Err(EACCES)
+        );
+        assert!(!dir.join("sandbox/read-only/new").exists());
+        assert_eq!(state.open_path("/sandbox/read-only/file", 0, 0), Ok(3));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
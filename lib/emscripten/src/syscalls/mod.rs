@@ -4,12 +4,16 @@ mod unix;
 #[cfg(windows)]
 mod windows;
 
+mod policy;
+
 #[cfg(unix)]
 pub use self::unix::*;
 
 #[cfg(windows)]
 pub use self::windows::*;
 
+pub use self::policy::*;
+
 use crate::{
     ptr::{Array, WasmPtr},
     utils::{copy_stat_into_wasm, get_cstr_path, get_current_directory},
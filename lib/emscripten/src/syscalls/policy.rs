@@ -0,0 +1,128 @@
+//! Embedders decide which syscalls the guest may make with a
+//! [`SyscallPolicy`], set with [`EmEnv::set_syscall_policy`].
+
+use crate::errno::ENOSYS;
+use crate::varargs::VarArgs;
+use crate::EmEnv;
+use wasmer::FromToNativeWasmType;
+
+/// What happens to a syscall made by the guest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallAction {
+    /// The syscall is made.
+    Allow,
+    /// The syscall isn't made, and fails with `ENOSYS`.
+    Deny,
+    /// The syscall isn't made, and the guest gets this value instead:
+    /// a result, or a negated errno.
+    Return(i32),
+}
+
+/// Checks the syscalls made by the guest, but `exit`, before they are
+/// made.  `execvp` and `chroot` aren't syscalls in Emscripten, but are
+/// checked as the syscalls `11` and `61`, without arguments.
+///
+/// A policy can virtualize a syscall by doing the work itself, reading
+/// its arguments with [`VarArgs::get`], and returning
+/// [`SyscallAction::Return`].
+pub trait SyscallPolicy: Send + Sync {
+    /// Checks the syscall `which`, with its arguments at `varargs`.
+    fn check(&self, env: &EmEnv, which: i32, varargs: VarArgs) -> SyscallAction;
+}
+
+impl<F> SyscallPolicy for F
+where
+    F: Fn(&EmEnv, i32, VarArgs) -> SyscallAction + Send + Sync,
+{
+    fn check(&self, env: &EmEnv, which: i32, varargs: VarArgs) -> SyscallAction {
+        self(env, which, varargs)
+    }
+}
+
+/// The default policy, allowing every syscall.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAllSyscalls;
+
+impl SyscallPolicy for AllowAllSyscalls {
+    fn check(&self, _env: &EmEnv, _which: i32, _varargs: VarArgs) -> SyscallAction {
+        SyscallAction::Allow
+    }
+}
+
+/// Makes the syscall `which` with `syscall`, if the policy of `env`
+/// allows it.
+pub(crate) fn checked_syscall<A, R>(
+    env: &EmEnv,
+    which: i32,
+    varargs: i32,
+    syscall: fn(&EmEnv, i32, A) -> R,
+) -> i32
+where
+    A: FromToNativeWasmType<Native = i32>,
+    R: Into<i32>,
+{
+    match env
+        .syscall_policy()
+        .check(env, which, VarArgs::from_native(varargs))
+    {
+        SyscallAction::Allow => syscall(env, which, A::from_native(varargs)).into(),
+        SyscallAction::Deny => -ENOSYS,
+        SyscallAction::Return(value) => value,
+    }
+}
+
+/// Whether the policy of `env` allows the host call `which`, checked
+/// like a syscall without arguments.
+pub(crate) fn host_call_allowed(env: &EmEnv, which: i32) -> bool {
+    env.syscall_policy()
+        .check(env, which, VarArgs { pointer: 0 })
+        == SyscallAction::Allow
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(policy: impl SyscallPolicy + 'static) -> EmEnv {
+        let mut env = EmEnv::new(&Default::default(), HashMap::new());
+        env.set_syscall_policy(policy);
+        env
+    }
+
+    fn syscall(_env: &EmEnv, which: i32, _varargs: i32) -> i32 {
+        which * 2
+    }
+
+    fn unreachable_syscall(_env: &EmEnv, which: i32, _varargs: i32) -> i32 {
+        panic!("the syscall {} was made", which)
+    }
+
+    #[test]
+    fn allowed_syscalls_are_made() {
+        let env = env(AllowAllSyscalls);
+        assert_eq!(checked_syscall(&env, 21, 0, syscall), 42);
+        assert!(host_call_allowed(&env, 11));
+    }
+
+    #[test]
+    fn denied_syscalls_fail_with_enosys() {
+        let env = env(|_: &EmEnv, _, _| SyscallAction::Deny);
+        assert_eq!(checked_syscall(&env, 21, 0, unreachable_syscall), -ENOSYS);
+        assert_eq!(-ENOSYS, -38);
+        assert!(!host_call_allowed(&env, 11));
+    }
+
+    #[test]
+    fn policies_can_return_values() {
+        let env = env(|_: &EmEnv, which, _| match which {
+            5 => SyscallAction::Return(7),
+            61 => SyscallAction::Return(0),
+            _ => SyscallAction::Allow,
+        });
+        assert_eq!(checked_syscall(&env, 5, 0, unreachable_syscall), 7);
+        assert_eq!(checked_syscall(&env, 6, 0, syscall), 12);
+        assert!(!host_call_allowed(&env, 61));
+        assert!(host_call_allowed(&env, 11));
+    }
+}
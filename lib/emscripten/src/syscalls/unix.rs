@@ -73,41 +73,60 @@ use libc::{
     // sockaddr_in,
     FIOCLEX,
     FIONBIO,
+    FIONCLEX,
+    FIONREAD,
     F_GETFD,
+    F_GETFL,
     F_SETFD,
+    F_SETFL,
+    O_NONBLOCK,
     SOL_SOCKET,
+    TIOCGPGRP,
     TIOCGWINSZ,
     TIOCSPGRP,
+    TIOCSWINSZ,
     // TCGETS,
     // TCSETSW,
 };
 
 // They are not exposed in in Rust libc in macOS
 const TCGETS: u64 = 0x5401;
+const TCSETS: u64 = 0x5402;
 const TCSETSW: u64 = 0x5403;
+const TCSETSF: u64 = 0x5404;
 
 // `libc` constants as provided by `emscripten`. Maybe move to own file?
 const WASM_FIONBIO: u32 = 0x5421;
 const WASM_FIOCLEX: u32 = 0x5451;
+const WASM_FIONCLEX: u32 = 0x5450;
+const WASM_FIONREAD: u32 = 0x541B;
+const WASM_TIOCGPGRP: u32 = 0x540F;
 const WASM_TIOCSPGRP: u32 = 0x5410;
 const WASM_TIOCGWINSZ: u32 = 0x5413;
+const WASM_TIOCSWINSZ: u32 = 0x5414;
 const WASM_TCGETS: u32 = 0x5401;
+const WASM_TCSETS: u32 = 0x5402;
 const WASM_TCSETSW: u32 = 0x5403;
+const WASM_TCSETSF: u32 = 0x5404;
 
 // Based on @syrusakbary sugerence at
 // https://github.com/wasmerio/wasmer/pull/532#discussion_r300837800
-fn translate_ioctl(wasm_ioctl: u32) -> c_ulong {
-    match wasm_ioctl {
+fn translate_ioctl(wasm_ioctl: u32) -> Option<c_ulong> {
+    Some(match wasm_ioctl {
         WASM_FIOCLEX => FIOCLEX as _,
-        WASM_TIOCGWINSZ => TIOCGWINSZ as _,
+        WASM_FIONCLEX => FIONCLEX as _,
+        WASM_FIONREAD => FIONREAD as _,
+        WASM_TIOCGPGRP => TIOCGPGRP as _,
         WASM_TIOCSPGRP => TIOCSPGRP as _,
+        WASM_TIOCGWINSZ => TIOCGWINSZ as _,
+        WASM_TIOCSWINSZ => TIOCSWINSZ as _,
         WASM_FIONBIO => FIONBIO as _,
         WASM_TCGETS => TCGETS as _,
+        WASM_TCSETS => TCSETS as _,
         WASM_TCSETSW => TCSETSW as _,
-        _otherwise => {
-            unimplemented!("The ioctl {} is not yet implemented", wasm_ioctl);
-        }
-    }
+        WASM_TCSETSF => TCSETSF as _,
+        _otherwise => return None,
+    })
 }
 
 #[allow(unused_imports)]
@@ -277,8 +296,11 @@ pub fn ___syscall194(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
     unsafe {
         ftruncate(_fd, _length)
     }
+    // `off_t` is always 64 bits on macOS.
     #[cfg(target_os = "macos")]
-    unimplemented!("emscripten::___syscall194 (ftruncate64) {}", _which)
+    unsafe {
+        libc::ftruncate(_fd, _length)
+    }
 }
 
 /// lchown
@@ -477,12 +499,10 @@ pub fn ___syscall54(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("=> fd: {}, op: {}", fd, request);
 
     // Got the equivalents here: https://code.woboq.org/linux/linux/include/uapi/asm-generic/ioctls.h.html
-    match request {
-        WASM_FIOCLEX | WASM_FIONBIO | WASM_TIOCGWINSZ | WASM_TIOCSPGRP | WASM_TCGETS
-        | WASM_TCSETSW => {
+    match translate_ioctl(request) {
+        Some(translated_request) => {
             let argp: u32 = varargs.get(ctx);
             let argp_ptr = emscripten_memory_pointer!(ctx.memory(0), argp) as *mut c_void;
-            let translated_request = translate_ioctl(request);
             let ret = unsafe { ioctl(fd, translated_request as _, argp_ptr) };
             debug!(
                 " => request: {}, translated: {}, return: {}",
@@ -496,12 +516,9 @@ pub fn ___syscall54(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
             }
             ret
         }
-        _ => {
-            debug!(
-                " => not implemented case {} (noop, hardcoded to 0)",
-                request
-            );
-            0
+        None => {
+            debug!(" => not implemented case {} (ENOTTY)", request);
+            -crate::errno::ENOTTY
         }
     }
 }
@@ -545,13 +562,14 @@ pub fn ___syscall102(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
             if ty_and_flags & SOCK_CLOEXC != 0 {
                 // set_cloexec
                 unsafe {
-                    ioctl(fd, translate_ioctl(WASM_FIOCLEX) as _);
+                    ioctl(fd, FIOCLEX as _);
                 };
             }
 
             if ty_and_flags & SOCK_NON_BLOCK != 0 {
-                // do something here
-                unimplemented!("non blocking sockets");
+                unsafe {
+                    fcntl(fd, F_SETFL, fcntl(fd, F_GETFL) | O_NONBLOCK);
+                };
             }
 
             // why is this here?
@@ -651,7 +669,7 @@ pub fn ___syscall102(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
             // why is this here?
             // set_cloexec
             unsafe {
-                ioctl(fd, translate_ioctl(WASM_FIOCLEX) as _);
+                ioctl(fd, FIOCLEX as _);
             };
 
             debug!(
@@ -1135,8 +1153,29 @@ pub fn ___syscall324(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
     unsafe {
         fallocate(_fd, _mode, _offset, _len)
     }
+    // Without `fallocate`, only the default mode is emulated, by growing
+    // the file if it's shorter than `offset + len`.
     #[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "android"))]
     {
-        unimplemented!("emscripten::___syscall324 (fallocate) {}", _which)
+        if _mode != 0 {
+            return -crate::errno::ENOSYS;
+        }
+        if _offset < 0 || _len <= 0 {
+            return -EINVAL;
+        }
+        let mut stat: stat = unsafe { mem::zeroed() };
+        if unsafe { libc::fstat(_fd, &mut stat) } == -1 {
+            return -crate::sandbox::io_errno(Error::last_os_error());
+        }
+        match _offset.checked_add(_len) {
+            Some(end) if end > stat.st_size as off_t => {
+                if unsafe { libc::ftruncate(_fd, end) } == -1 {
+                    return -crate::sandbox::io_errno(Error::last_os_error());
+                }
+                0
+            }
+            Some(_) => 0,
+            None => -libc::EFBIG,
+        }
     }
 }
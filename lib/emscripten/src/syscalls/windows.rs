@@ -1,3 +1,4 @@
+use crate::errno::ENOSYS;
 use crate::utils::{copy_cstr_into_wasm, get_cstr_path};
 use crate::varargs::VarArgs;
 use crate::EmEnv;
@@ -68,13 +69,13 @@ pub fn ___syscall5(ctx: &EmEnv, which: c_int, mut varargs: VarArgs) -> c_int {
 /// link
 pub fn ___syscall9(_ctx: &EmEnv, _which: c_int, mut _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall9 (link) {}", _which);
-    unimplemented!("emscripten::___syscall9 (link) {}", _which);
+    -ENOSYS
 }
 
 /// ftruncate64
 pub fn ___syscall194(_ctx: &EmEnv, _one: i32, _two: i32) -> i32 {
     debug!("emscripten::___syscall194 - stub");
-    unimplemented!("emscripten::___syscall194 - stub")
+    -ENOSYS
 }
 
 // chown
@@ -88,13 +89,13 @@ pub fn ___syscall212(_ctx: &EmEnv, which: c_int, mut _varargs: VarArgs) -> c_int
 /// access
 pub fn ___syscall33(_ctx: &EmEnv, _which: c_int, mut _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall33 (access) {}", _which);
-    unimplemented!("emscripten::___syscall33 (access) {}", _which);
+    -ENOSYS
 }
 
 /// nice
 pub fn ___syscall34(_ctx: &EmEnv, _which: c_int, mut _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall34 (nice) {}", _which);
-    unimplemented!("emscripten::___syscall34 (nice) {}", _which);
+    -ENOSYS
 }
 
 // mkdir
@@ -115,19 +116,19 @@ pub fn ___syscall39(ctx: &EmEnv, which: c_int, mut varargs: VarArgs) -> c_int {
 /// dup
 pub fn ___syscall41(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall41 (dup) {}", _which);
-    unimplemented!("emscripten::___syscall41 (dup) {}", _which);
+    -ENOSYS
 }
 
 /// getrusage
 pub fn ___syscall77(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall77 (getrusage) {}", _which);
-    unimplemented!("emscripten::___syscall77 (getrusage) {}", _which);
+    -ENOSYS
 }
 
 /// symlink
 pub fn ___syscall83(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall83 (symlink) {}", _which);
-    unimplemented!("emscripten::___syscall83 (symlink) {}", _which);
+    -ENOSYS
 }
 
 /// readlink
@@ -145,38 +146,38 @@ pub fn ___syscall132(_ctx: &EmEnv, _which: c_int, mut _varargs: VarArgs) -> c_in
 /// lchown
 pub fn ___syscall198(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall198 (lchown) {}", _which);
-    unimplemented!("emscripten::___syscall198 (lchown) {}", _which);
+    -ENOSYS
 }
 
 /// getgid32
 pub fn ___syscall200(_ctx: &EmEnv, _one: i32, _two: i32) -> i32 {
     debug!("emscripten::___syscall200 (getgid32)");
-    unimplemented!("emscripten::___syscall200 (getgid32)");
+    -ENOSYS
 }
 
 // geteuid32
 pub fn ___syscall201(_ctx: &EmEnv, _one: i32, _two: i32) -> i32 {
     debug!("emscripten::___syscall201 (geteuid32)");
-    unimplemented!("emscripten::___syscall201 (geteuid32)");
+    -ENOSYS
 }
 
 // getegid32
 pub fn ___syscall202(_ctx: &EmEnv, _one: i32, _two: i32) -> i32 {
     // gid_t
     debug!("emscripten::___syscall202 (getegid32)");
-    unimplemented!("emscripten::___syscall202 (getegid32)");
+    -ENOSYS
 }
 
 /// getgroups
 pub fn ___syscall205(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall205 (getgroups) {}", _which);
-    unimplemented!("emscripten::___syscall205 (getgroups) {}", _which);
+    -ENOSYS
 }
 
 /// madvise
 pub fn ___syscall219(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall212 (chown) {}", _which);
-    unimplemented!("emscripten::___syscall212 (chown) {}", _which);
+    -ENOSYS
 }
 
 /// dup3
@@ -196,7 +197,7 @@ pub fn ___syscall54(_ctx: &EmEnv, which: c_int, mut _varargs: VarArgs) -> c_int
 /// fchmod
 pub fn ___syscall94(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall118 (fchmod) {}", _which);
-    unimplemented!("emscripten::___syscall118 (fchmod) {}", _which);
+    -ENOSYS
 }
 
 // socketcall
@@ -211,7 +212,7 @@ pub fn ___syscall102(_ctx: &EmEnv, which: c_int, mut _varargs: VarArgs) -> c_int
 /// fsync
 pub fn ___syscall118(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall118 (fsync) {}", _which);
-    unimplemented!("emscripten::___syscall118 (fsync) {}", _which);
+    -ENOSYS
 }
 
 // pread
@@ -249,7 +250,7 @@ pub fn ___syscall142(_ctx: &EmEnv, which: c_int, mut _varargs: VarArgs) -> c_int
 /// fdatasync
 pub fn ___syscall148(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall148 (fdatasync) {}", _which);
-    unimplemented!("emscripten::___syscall148 (fdatasync) {}", _which);
+    -ENOSYS
 }
 
 // setpgid
@@ -302,11 +303,11 @@ pub fn ___syscall221(_ctx: &EmEnv, _which: c_int, mut _varargs: VarArgs) -> c_in
 /// fchown
 pub fn ___syscall207(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall207 (fchown) {}", _which);
-    unimplemented!("emscripten::___syscall207 (fchown) {}", _which)
+    -ENOSYS
 }
 
 /// fallocate
 pub fn ___syscall324(_ctx: &EmEnv, _which: c_int, _varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall324 (fallocate) {}", _which);
-    unimplemented!("emscripten::___syscall324 (fallocate) {}", _which)
+    -ENOSYS
 }
@@ -74,8 +74,14 @@ impl JITArtifact {
 
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = module
             .memories
-            .values()
-            .map(|memory_type| tunables.memory_style(memory_type))
+            .iter()
+            .map(|(index, memory_type)| {
+                if module.memory64[index] {
+                    tunables.memory64_style(memory_type)
+                } else {
+                    tunables.memory_style(memory_type)
+                }
+            })
             .collect();
         let table_styles: PrimaryMap<TableIndex, TableStyle> = module
             .tables
@@ -140,6 +140,24 @@ impl Engine for JITEngine {
         &self.engine_id
     }
 
+    fn fingerprint(&self) -> String {
+        let inner = self.inner();
+        #[cfg(feature = "compiler")]
+        let compiler = match &inner.compiler {
+            Some(compiler) => compiler.fingerprint(),
+            None => "headless".to_string(),
+        };
+        #[cfg(not(feature = "compiler"))]
+        let compiler = "headless";
+        format!(
+            "jit-{} target={} features={:?} compiler=({})",
+            env!("CARGO_PKG_VERSION"),
+            self.target.fingerprint(),
+            inner.features,
+            compiler
+        )
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
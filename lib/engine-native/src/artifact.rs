@@ -1,11 +1,17 @@
 //! Define `NativeArtifact` to allow compiling and instantiating to be
 //! done as separate steps.
 
+#[cfg(feature = "compiler")]
+use crate::engine::Linker;
 use crate::engine::{NativeEngine, NativeEngineInner};
 use crate::serialize::{ArchivedModuleMetadata, ModuleMetadata};
+#[cfg(feature = "compiler")]
+use crate::shared_object;
 use libloading::{Library, Symbol as LibrarySymbol};
 use loupe::MemoryUsage;
 use std::error::Error;
+#[cfg(feature = "compiler")]
+use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -124,8 +130,14 @@ impl NativeArtifact {
 
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = module
             .memories
-            .values()
-            .map(|memory_type| tunables.memory_style(memory_type))
+            .iter()
+            .map(|(index, memory_type)| {
+                if module.memory64[index] {
+                    tunables.memory64_style(memory_type)
+                } else {
+                    tunables.memory_style(memory_type)
+                }
+            })
             .collect();
         let table_styles: PrimaryMap<TableIndex, TableStyle> = module
             .tables
@@ -212,20 +224,8 @@ impl NativeArtifact {
             &metadata_binary,
         );
 
-        let filepath = match maybe_obj_bytes {
-            Some(obj_bytes) => {
-                let obj_bytes = obj_bytes?;
-                let file = tempfile::Builder::new()
-                    .prefix("wasmer_native")
-                    .suffix(".o")
-                    .tempfile()
-                    .map_err(to_compile_error)?;
-
-                // Re-open it.
-                let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
-                file.write(&obj_bytes).map_err(to_compile_error)?;
-                filepath
-            }
+        let obj_bytes = match maybe_obj_bytes {
+            Some(obj_bytes) => obj_bytes?,
             None => {
                 let compilation = compiler.compile_module(
                     &target,
@@ -243,18 +243,7 @@ impl NativeArtifact {
                 .map_err(to_compile_error)?;
                 emit_compilation(&mut obj, compilation, &symbol_registry, &target_triple)
                     .map_err(to_compile_error)?;
-                let file = tempfile::Builder::new()
-                    .prefix("wasmer_native")
-                    .suffix(".o")
-                    .tempfile()
-                    .map_err(to_compile_error)?;
-
-                // Re-open it.
-                let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
-                let obj_bytes = obj.write().map_err(to_compile_error)?;
-
-                file.write(&obj_bytes).map_err(to_compile_error)?;
-                filepath
+                obj.write().map_err(to_compile_error)?
             }
         };
 
@@ -272,6 +261,50 @@ impl NativeArtifact {
         };
 
         let is_cross_compiling = engine_inner.is_cross_compiling();
+        match engine_inner.linker() {
+            Linker::Builtin => {
+                trace!("Linking for target {} in process", target_triple);
+                let shared_object = shared_object::link(&obj_bytes)?;
+                fs::write(&shared_filepath, shared_object).map_err(to_compile_error)?;
+            }
+            linker => {
+                let file = tempfile::Builder::new()
+                    .prefix("wasmer_native")
+                    .suffix(".o")
+                    .tempfile()
+                    .map_err(to_compile_error)?;
+
+                // Re-open it.
+                let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
+                file.write(&obj_bytes).map_err(to_compile_error)?;
+                Self::run_linker(
+                    linker,
+                    is_cross_compiling,
+                    &target_triple,
+                    &filepath,
+                    &shared_filepath,
+                )?;
+            }
+        }
+
+        if is_cross_compiling {
+            Self::from_parts_crosscompiled(metadata, shared_filepath)
+        } else {
+            let lib = unsafe { Library::new(&shared_filepath).map_err(to_compile_error)? };
+            Self::from_parts(&mut engine_inner, metadata, shared_filepath, lib)
+        }
+    }
+
+    /// Link the object file at `filepath` into a shared object at
+    /// `shared_filepath` with a system linker.
+    #[cfg(feature = "compiler")]
+    fn run_linker(
+        linker: Linker,
+        is_cross_compiling: bool,
+        target_triple: &Triple,
+        filepath: &Path,
+        shared_filepath: &Path,
+    ) -> Result<(), CompileError> {
         let target_triple_str = {
             let into_str = target_triple.to_string();
             // We have to adapt the target triple string, because otherwise
@@ -310,8 +343,7 @@ impl NativeArtifact {
             Triple::host().to_string(),
         );
 
-        let linker = engine_inner.linker().executable();
-        let output = Command::new(linker)
+        let output = Command::new(linker.executable())
             .arg(&filepath)
             .arg("-o")
             .arg(&shared_filepath)
@@ -331,12 +363,7 @@ impl NativeArtifact {
             )));
         }
         trace!("gcc command result {:?}", output);
-        if is_cross_compiling {
-            Self::from_parts_crosscompiled(metadata, shared_filepath)
-        } else {
-            let lib = unsafe { Library::new(&shared_filepath).map_err(to_compile_error)? };
-            Self::from_parts(&mut engine_inner, metadata, shared_filepath, lib)
-        }
+        Ok(())
     }
 
     /// Get the default extension when serializing this artifact
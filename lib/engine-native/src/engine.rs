@@ -31,7 +31,7 @@ impl NativeEngine {
     #[cfg(feature = "compiler")]
     pub fn new(compiler: Box<dyn Compiler>, target: Target, features: Features) -> Self {
         let is_cross_compiling = *target.triple() != Triple::host();
-        let linker = Linker::find_linker(is_cross_compiling);
+        let linker = Linker::find_linker(target.triple(), is_cross_compiling);
 
         Self {
             inner: Arc::new(Mutex::new(NativeEngineInner {
@@ -99,6 +99,17 @@ impl NativeEngine {
         inner.prefixer = Some(Box::new(prefixer));
     }
 
+    /// Links the shared objects in process, rather than with a system
+    /// linker.
+    ///
+    /// This is the default when neither `gcc` nor `clang` are installed.
+    /// Only ELF targets on x86_64 and AArch64 are supported, compiling
+    /// modules for other targets will fail.
+    #[cfg(feature = "compiler")]
+    pub fn use_builtin_linker(&mut self) {
+        self.inner_mut().linker = Linker::Builtin;
+    }
+
     pub(crate) fn inner(&self) -> std::sync::MutexGuard<'_, NativeEngineInner> {
         self.inner.lock().unwrap()
     }
@@ -179,6 +190,26 @@ impl Engine for NativeEngine {
         &self.engine_id
     }
 
+    fn fingerprint(&self) -> String {
+        #[cfg(feature = "compiler")]
+        let (features, compiler) = {
+            let inner = self.inner();
+            match &inner.compiler {
+                Some(compiler) => (format!("{:?}", inner.features), compiler.fingerprint()),
+                None => ("headless".to_string(), "headless".to_string()),
+            }
+        };
+        #[cfg(not(feature = "compiler"))]
+        let (features, compiler) = ("headless", "headless");
+        format!(
+            "native-{} target={} features={} compiler=({})",
+            env!("CARGO_PKG_VERSION"),
+            self.target.fingerprint(),
+            features,
+            compiler
+        )
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
@@ -187,6 +218,8 @@ impl Engine for NativeEngine {
 #[derive(Clone, Copy, MemoryUsage)]
 pub(crate) enum Linker {
     None,
+    /// The in-process linker of the `shared_object` module.
+    Builtin,
     Clang11,
     Clang10,
     Clang,
@@ -195,7 +228,7 @@ pub(crate) enum Linker {
 
 impl Linker {
     #[cfg(feature = "compiler")]
-    fn find_linker(is_cross_compiling: bool) -> Self {
+    fn find_linker(triple: &Triple, is_cross_compiling: bool) -> Self {
         let (possibilities, requirements): (&[_], _) = if is_cross_compiling {
             (
                 &[Linker::Clang11, Linker::Clang10, Linker::Clang],
@@ -204,22 +237,26 @@ impl Linker {
         } else {
             (&[Linker::Gcc], "`gcc`")
         };
-        *possibilities
+        possibilities
             .iter()
-            .filter(|linker| which::which(linker.executable()).is_ok())
-            .next()
+            .find(|linker| which::which(linker.executable()).is_ok())
+            .copied()
             .unwrap_or_else(|| {
+                if crate::shared_object::is_supported(triple) {
+                    return Linker::Builtin;
+                }
                 panic!(
-                    "Need {} installed in order to use `NativeEngine` when {}cross-compiling",
+                    "Need {} installed in order to use `NativeEngine` when {}cross-compiling to {}",
                     requirements,
-                    if is_cross_compiling { "" } else { "not " }
+                    if is_cross_compiling { "" } else { "not " },
+                    triple
                 )
             })
     }
 
     pub(crate) fn executable(self) -> &'static str {
         match self {
-            Self::None => "",
+            Self::None | Self::Builtin => "",
             Self::Clang11 => "clang-11",
             Self::Clang10 => "clang-10",
             Self::Clang => "clang",
@@ -4,6 +4,10 @@
 //! it generates a shared object file (`.so` or `.dylib` depending on
 //! the target), saves it temporarily to disk and uses it natively
 //! via `dlopen` and `dlsym` (using the `libloading` library).
+//!
+//! The shared object is linked with `gcc` or `clang` when available,
+//! or else in process for the targets that support it (ELF on x86_64
+//! and AArch64).
 
 #![deny(missing_docs, trivial_numeric_casts, unused_extern_crates)]
 #![warn(unused_import_braces)]
@@ -26,6 +30,8 @@ mod artifact;
 mod builder;
 mod engine;
 mod serialize;
+#[cfg(feature = "compiler")]
+mod shared_object;
 
 pub use crate::artifact::NativeArtifact;
 pub use crate::builder::Native;
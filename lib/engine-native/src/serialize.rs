@@ -124,11 +124,15 @@ impl<'a> SymbolRegistry for ModuleMetadataSymbolRegistry<'a> {
                     index.index()
                 )
             }
+            Symbol::Metadata => "WASMER_METADATA".to_string(),
         }
     }
 
     fn name_to_symbol(&self, name: &str) -> Option<Symbol> {
-        if let Some(index) = name.strip_prefix(&format!("wasmer_function_{}_", self.prefix)) {
+        if name == "WASMER_METADATA" {
+            Some(Symbol::Metadata)
+        } else if let Some(index) = name.strip_prefix(&format!("wasmer_function_{}_", self.prefix))
+        {
             index
                 .parse::<u32>()
                 .ok()
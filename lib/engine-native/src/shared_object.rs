@@ -0,0 +1,1321 @@
+//! Write shared objects without a system linker.
+//!
+//! The compilers emit a relocatable object for each module, that has to be
+//! turned into a shared object to be loaded with `dlopen`.  Rather than
+//! running `gcc` or `clang`, this lays out the loadable sections of the
+//! object, applies its relocations, and writes the dynamic symbols and
+//! relocations needed to load it: this way the `NativeEngine` works on
+//! hosts without a C toolchain.
+//!
+//! This is not a general purpose linker: it supports what the compilers
+//! emit, for ELF on x86_64 and AArch64.  Undefined symbols, like the
+//! `wasmer_vm_*` libcalls, are left to the dynamic loader.
+
+use object::elf;
+use object::endian::{LittleEndian, I64, U16, U32, U64};
+use object::pod::bytes_of;
+use object::read::elf::ElfFile64;
+use object::{
+    Architecture as ObjectArchitecture, Endianness as ObjectEndianness, Object, ObjectSection,
+    ObjectSymbol, Relocation, RelocationKind, RelocationTarget, SectionFlags, SectionIndex,
+    SectionKind, SymbolIndex, SymbolKind, SymbolScope, SymbolSection,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use wasmer_compiler::{Architecture, BinaryFormat, CompileError, Endianness, Triple};
+
+const LE: LittleEndian = LittleEndian;
+
+/// Returns whether shared objects for `triple` can be written without a
+/// system linker.
+pub(crate) fn is_supported(triple: &Triple) -> bool {
+    triple.binary_format == BinaryFormat::Elf
+        && matches!(
+            triple.architecture,
+            Architecture::X86_64 | Architecture::Aarch64(_)
+        )
+        && matches!(triple.endianness(), Ok(Endianness::Little))
+}
+
+/// Links the relocatable object `object_bytes` into a shared object,
+/// exporting its global symbols.
+pub(crate) fn link(object_bytes: &[u8]) -> Result<Vec<u8>, CompileError> {
+    let file = ElfFile64::<ObjectEndianness>::parse(object_bytes)
+        .map_err(|e| link_error(format!("invalid object: {}", e)))?;
+    let arch = match (file.architecture(), file.is_little_endian()) {
+        (ObjectArchitecture::X86_64, true) => Arch::X86_64,
+        (ObjectArchitecture::Aarch64, true) => Arch::Aarch64,
+        (architecture, _) => {
+            return Err(link_error(format!(
+                "unsupported architecture {:?}",
+                architecture
+            )))
+        }
+    };
+    Linker::new(arch, &file)?.write()
+}
+
+fn link_error(message: String) -> CompileError {
+    CompileError::Codegen(format!("Shared object writer failed: {}", message))
+}
+
+#[derive(Clone, Copy)]
+enum Arch {
+    X86_64,
+    Aarch64,
+}
+
+impl Arch {
+    fn machine(self) -> u16 {
+        match self {
+            Self::X86_64 => elf::EM_X86_64,
+            Self::Aarch64 => elf::EM_AARCH64,
+        }
+    }
+
+    /// The maximum page size, to align segments.
+    fn page_size(self) -> u64 {
+        match self {
+            Self::X86_64 => 0x1000,
+            Self::Aarch64 => 0x10000,
+        }
+    }
+
+    fn absolute_relocation(self) -> u32 {
+        match self {
+            Self::X86_64 => elf::R_X86_64_64,
+            Self::Aarch64 => elf::R_AARCH64_ABS64,
+        }
+    }
+
+    fn relative_relocation(self) -> u32 {
+        match self {
+            Self::X86_64 => elf::R_X86_64_RELATIVE,
+            Self::Aarch64 => elf::R_AARCH64_RELATIVE,
+        }
+    }
+
+    /// The ELF relocation type of `relocation`, as `object` translates the
+    /// common ones to generic kinds.
+    fn relocation_type(self, relocation: &Relocation) -> Option<u32> {
+        Some(match (self, relocation.kind(), relocation.size()) {
+            (_, RelocationKind::Elf(r_type), _) => r_type,
+            (Self::X86_64, RelocationKind::Absolute, 64) => elf::R_X86_64_64,
+            (Self::X86_64, RelocationKind::Relative, 32) => elf::R_X86_64_PC32,
+            (Self::X86_64, RelocationKind::PltRelative, 32) => elf::R_X86_64_PLT32,
+            (Self::X86_64, RelocationKind::GotRelative, 32) => elf::R_X86_64_GOTPCREL,
+            (Self::Aarch64, RelocationKind::Absolute, 64) => elf::R_AARCH64_ABS64,
+            (Self::Aarch64, RelocationKind::Relative, 32) => elf::R_AARCH64_PREL32,
+            (Self::Aarch64, RelocationKind::Relative, 64) => elf::R_AARCH64_PREL64,
+            _ => return None,
+        })
+    }
+
+    /// Whether a relocation of this type loads the address of its target
+    /// from the GOT.
+    fn uses_got(self, r_type: u32) -> bool {
+        match self {
+            Self::X86_64 => matches!(
+                r_type,
+                elf::R_X86_64_GOTPCREL | elf::R_X86_64_GOTPCRELX | elf::R_X86_64_REX_GOTPCRELX
+            ),
+            Self::Aarch64 => matches!(
+                r_type,
+                elf::R_AARCH64_ADR_GOT_PAGE | elf::R_AARCH64_LD64_GOT_LO12_NC
+            ),
+        }
+    }
+
+    /// Whether a relocation of this type is a call, that goes through a
+    /// stub when its target is undefined.
+    fn is_call(self, r_type: u32) -> bool {
+        match self {
+            Self::X86_64 => r_type == elf::R_X86_64_PLT32,
+            Self::Aarch64 => matches!(r_type, elf::R_AARCH64_CALL26 | elf::R_AARCH64_JUMP26),
+        }
+    }
+
+    /// The size of the stubs jumping to undefined functions.
+    fn stub_size(self) -> u64 {
+        match self {
+            Self::X86_64 => 8,
+            Self::Aarch64 => 16,
+        }
+    }
+
+    /// Writes a stub at `stub_address`, jumping to the address stored in
+    /// the GOT at `got_address`.
+    fn write_stub(
+        self,
+        stub: &mut [u8],
+        stub_address: u64,
+        got_address: u64,
+    ) -> Result<(), CompileError> {
+        match self {
+            Self::X86_64 => {
+                // jmp *got(%rip)
+                stub[..2].copy_from_slice(&[0xff, 0x25]);
+                write_i32(stub, 2, got_address.wrapping_sub(stub_address + 6) as i64)?;
+                // int3 padding
+                stub[6..8].copy_from_slice(&[0xcc, 0xcc]);
+            }
+            Self::Aarch64 => {
+                // adrp x16, got; ldr x17, [x16, :lo12:got]; br x17; nop
+                let adrp = encode_adr(0x9000_0010, page_delta(got_address, stub_address)?);
+                let ldr = 0xf940_0211 | (((got_address as u32 & 0xfff) >> 3) << 10);
+                for (index, insn) in [adrp, ldr, 0xd61f_0220, 0xd503_201f].iter().enumerate() {
+                    stub[index * 4..index * 4 + 4].copy_from_slice(&insn.to_le_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the loadable sections of the object are gathered.
+#[derive(Clone, Copy, PartialEq)]
+enum Group {
+    Text,
+    ReadOnlyData,
+    Data,
+}
+
+struct InputSection<'data> {
+    index: SectionIndex,
+    name: String,
+    /// Empty for uninitialized data.
+    data: &'data [u8],
+    size: u64,
+    align: u64,
+    group: Group,
+    address: u64,
+}
+
+/// The target of a relocation, once resolved.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Target {
+    /// An offset in an input section.
+    Defined { section: usize, offset: u64 },
+    /// An absolute address.
+    Absolute(u64),
+    /// A dynamic symbol, resolved when loading.
+    Undefined(u32),
+}
+
+struct Fixup {
+    section: usize,
+    offset: u64,
+    r_type: u32,
+    target: Target,
+    addend: i64,
+}
+
+struct DynamicSymbol<'data> {
+    name: &'data str,
+    /// Where the symbol is defined, or `None` if it's undefined.
+    target: Option<Target>,
+    size: u64,
+    kind: SymbolKind,
+    weak: bool,
+}
+
+// The indices of the sections of the shared object that are referred to,
+// in `SECTION_NAMES`.
+const SECTION_DYNSYM: u16 = 2;
+const SECTION_DYNSTR: u16 = 3;
+const SECTION_TEXT: u16 = 5;
+const SECTION_RODATA: u16 = 6;
+const SECTION_DATA: u16 = 9;
+const SECTION_SHSTRTAB: u16 = 10;
+const SECTION_NAMES: [&str; 11] = [
+    "",
+    ".hash",
+    ".dynsym",
+    ".dynstr",
+    ".rela.dyn",
+    ".text",
+    ".rodata",
+    ".dynamic",
+    ".got",
+    ".data",
+    ".shstrtab",
+];
+
+const PROGRAM_HEADERS: u64 = 4;
+const FILE_HEADER_SIZE: u64 = 64;
+const PROGRAM_HEADER_SIZE: u64 = 56;
+const SECTION_HEADER_SIZE: u64 = 64;
+const SYMBOL_SIZE: u64 = 24;
+const RELA_SIZE: u64 = 24;
+const DYN_SIZE: u64 = 16;
+
+struct Linker<'data> {
+    arch: Arch,
+    sections: Vec<InputSection<'data>>,
+    fixups: Vec<Fixup>,
+    symbols: Vec<DynamicSymbol<'data>>,
+    got: Vec<Target>,
+    got_slots: HashMap<Target, u64>,
+    stubs: HashMap<u32, u64>,
+    /// The number of dynamic relocations.
+    relocation_count: u64,
+    /// Whether read-only sections have dynamic relocations.
+    text_relocations: bool,
+}
+
+impl<'data> Linker<'data> {
+    fn new(arch: Arch, file: &ElfFile64<'data>) -> Result<Self, CompileError> {
+        let mut linker = Self {
+            arch,
+            sections: vec![],
+            fixups: vec![],
+            symbols: vec![],
+            got: vec![],
+            got_slots: HashMap::new(),
+            stubs: HashMap::new(),
+            relocation_count: 0,
+            text_relocations: false,
+        };
+
+        for section in file.sections() {
+            let sh_flags = match section.flags() {
+                SectionFlags::Elf { sh_flags } => sh_flags,
+                _ => 0,
+            };
+            if sh_flags & u64::from(elf::SHF_ALLOC) == 0 {
+                // Debug info and linker metadata are not loaded.
+                continue;
+            }
+            let name = section.name().unwrap_or_default().to_string();
+            if sh_flags & u64::from(elf::SHF_TLS) != 0 {
+                return Err(link_error(format!(
+                    "thread-local section `{}` is not supported",
+                    name
+                )));
+            }
+            let group = if sh_flags & u64::from(elf::SHF_EXECINSTR) != 0 {
+                Group::Text
+            } else if sh_flags & u64::from(elf::SHF_WRITE) != 0 {
+                Group::Data
+            } else {
+                Group::ReadOnlyData
+            };
+            let data = if section.kind() == SectionKind::UninitializedData {
+                &[]
+            } else {
+                section
+                    .data()
+                    .map_err(|e| link_error(format!("invalid section `{}`: {}", name, e)))?
+            };
+            linker.sections.push(InputSection {
+                index: section.index(),
+                name,
+                data,
+                size: section.size(),
+                align: section.align().max(1),
+                group,
+                address: 0,
+            });
+        }
+
+        // The global symbols are exported.
+        for symbol in file.symbols() {
+            if symbol.is_undefined() || symbol.scope() != SymbolScope::Dynamic {
+                continue;
+            }
+            if let SymbolSection::Section(index) = symbol.section() {
+                let target = match linker.section_position(index) {
+                    Some(section) => Target::Defined {
+                        section,
+                        offset: symbol.address(),
+                    },
+                    None => continue,
+                };
+                linker.symbols.push(DynamicSymbol {
+                    name: symbol.name().unwrap_or_default(),
+                    target: Some(target),
+                    size: symbol.size(),
+                    kind: symbol.kind(),
+                    weak: symbol.is_weak(),
+                });
+            }
+        }
+
+        for position in 0..linker.sections.len() {
+            let section = file
+                .section_by_index(linker.sections[position].index)
+                .map_err(|e| link_error(e.to_string()))?;
+            for (offset, relocation) in section.relocations() {
+                linker.add_fixup(file, position, offset, &relocation)?;
+            }
+        }
+        Ok(linker)
+    }
+
+    /// The position in `self.sections` of the input section `index`.
+    fn section_position(&self, index: SectionIndex) -> Option<usize> {
+        self.sections
+            .iter()
+            .position(|section| section.index == index)
+    }
+
+    fn add_fixup(
+        &mut self,
+        file: &ElfFile64<'data>,
+        section: usize,
+        offset: u64,
+        relocation: &Relocation,
+    ) -> Result<(), CompileError> {
+        let r_type = self.arch.relocation_type(relocation).ok_or_else(|| {
+            link_error(format!(
+                "unsupported relocation {:?} in section `{}`",
+                relocation.kind(),
+                self.sections[section].name
+            ))
+        })?;
+        if relocation.has_implicit_addend() {
+            return Err(link_error(format!(
+                "implicit addends are not supported, in section `{}`",
+                self.sections[section].name
+            )));
+        }
+        let target = match relocation.target() {
+            RelocationTarget::Symbol(index) => self.resolve(file, index)?,
+            RelocationTarget::Section(index) => Target::Defined {
+                section: self.section_position(index).ok_or_else(|| {
+                    link_error(format!(
+                        "section `{}` refers to a section that isn't loaded",
+                        self.sections[section].name
+                    ))
+                })?,
+                offset: 0,
+            },
+            RelocationTarget::Absolute => Target::Absolute(0),
+        };
+
+        if self.arch.uses_got(r_type) && !self.got_slots.contains_key(&target) {
+            self.got_slots.insert(target, self.got.len() as u64);
+            self.got.push(target);
+            if !matches!(target, Target::Absolute(_)) {
+                self.relocation_count += 1;
+            }
+        }
+        if let Target::Undefined(symbol) = target {
+            if self.arch.is_call(r_type) && !self.stubs.contains_key(&symbol) {
+                self.stubs.insert(symbol, self.stubs.len() as u64);
+                if !self.got_slots.contains_key(&target) {
+                    self.got_slots.insert(target, self.got.len() as u64);
+                    self.got.push(target);
+                    self.relocation_count += 1;
+                }
+            }
+        }
+        if r_type == self.arch.absolute_relocation() && !matches!(target, Target::Absolute(_)) {
+            self.relocation_count += 1;
+            if self.sections[section].group != Group::Data {
+                self.text_relocations = true;
+            }
+        }
+
+        self.fixups.push(Fixup {
+            section,
+            offset,
+            r_type,
+            target,
+            addend: relocation.addend(),
+        });
+        Ok(())
+    }
+
+    /// Resolves the symbol `index`, adding it to the dynamic symbols if
+    /// it's undefined.
+    fn resolve(
+        &mut self,
+        file: &ElfFile64<'data>,
+        index: SymbolIndex,
+    ) -> Result<Target, CompileError> {
+        let symbol = file
+            .symbol_by_index(index)
+            .map_err(|e| link_error(e.to_string()))?;
+        let name = symbol.name().unwrap_or_default();
+        match symbol.section() {
+            SymbolSection::Section(section) => match self.section_position(section) {
+                Some(section) => Ok(Target::Defined {
+                    section,
+                    offset: symbol.address(),
+                }),
+                None => Err(link_error(format!(
+                    "symbol `{}` is in a section that isn't loaded",
+                    name
+                ))),
+            },
+            SymbolSection::Absolute => Ok(Target::Absolute(symbol.address())),
+            SymbolSection::Undefined => {
+                let position = self
+                    .symbols
+                    .iter()
+                    .position(|symbol| symbol.target.is_none() && symbol.name == name);
+                let position = match position {
+                    Some(position) => position,
+                    None => {
+                        self.symbols.push(DynamicSymbol {
+                            name,
+                            target: None,
+                            size: 0,
+                            kind: symbol.kind(),
+                            weak: symbol.is_weak(),
+                        });
+                        self.symbols.len() - 1
+                    }
+                };
+                // The first dynamic symbol is the null symbol.
+                Ok(Target::Undefined(position as u32 + 1))
+            }
+            _ => Err(link_error(format!(
+                "symbol `{}` has an unsupported definition",
+                name
+            ))),
+        }
+    }
+
+    fn write(mut self) -> Result<Vec<u8>, CompileError> {
+        let arch = self.arch;
+        let page_size = arch.page_size();
+
+        // The dynamic string table.
+        let mut dynstr = vec![0];
+        let symbol_names = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let offset = dynstr.len() as u32;
+                dynstr.extend_from_slice(symbol.name.as_bytes());
+                dynstr.push(0);
+                offset
+            })
+            .collect::<Vec<_>>();
+        let hash = sysv_hash_table(&self.symbols);
+        let dynamic_count = if self.text_relocations { 11 } else { 9 };
+
+        // The read-only and executable segment.
+        let mut offset = FILE_HEADER_SIZE + PROGRAM_HEADERS * PROGRAM_HEADER_SIZE;
+        let hash_offset = align(offset, 8);
+        let dynsym_offset = align(hash_offset + hash.len() as u64 * 4, 8);
+        let symbol_count = self.symbols.len() as u64 + 1;
+        let dynstr_offset = dynsym_offset + symbol_count * SYMBOL_SIZE;
+        let rela_offset = align(dynstr_offset + dynstr.len() as u64, 8);
+        offset = rela_offset + self.relocation_count * RELA_SIZE;
+        let text_offset = align(offset, 16);
+        offset = self.place(Group::Text, text_offset);
+        let stubs_offset = align(offset, 16);
+        offset = stubs_offset + self.stubs.len() as u64 * arch.stub_size();
+        let rodata_offset = align(offset, 16);
+        let rodata_end = self.place(Group::ReadOnlyData, rodata_offset);
+
+        // The writable segment.
+        let dynamic_offset = align(rodata_end, page_size);
+        let got_offset = dynamic_offset + dynamic_count * DYN_SIZE;
+        offset = got_offset + self.got.len() as u64 * 8;
+        let data_offset = align(offset, 16);
+        let data_end = self.place(Group::Data, data_offset);
+
+        let mut shstrtab = vec![];
+        let section_names = SECTION_NAMES
+            .iter()
+            .map(|name| {
+                let offset = shstrtab.len() as u32;
+                shstrtab.extend_from_slice(name.as_bytes());
+                shstrtab.push(0);
+                offset
+            })
+            .collect::<Vec<_>>();
+        let shstrtab_offset = data_end;
+        let section_headers_offset = align(shstrtab_offset + shstrtab.len() as u64, 8);
+        let file_size = section_headers_offset + SECTION_NAMES.len() as u64 * SECTION_HEADER_SIZE;
+
+        // As the shared object is loaded at address 0, file offsets and
+        // addresses are the same.
+        let mut out = vec![0; file_size as usize];
+        for section in &self.sections {
+            let start = section.address as usize;
+            out[start..start + section.data.len()].copy_from_slice(section.data);
+        }
+
+        let mut relocations = vec![];
+        let stub_address = |symbol: u32, stubs: &HashMap<u32, u64>| {
+            stubs
+                .get(&symbol)
+                .map(|stub| stubs_offset + stub * arch.stub_size())
+        };
+        let got_address =
+            |target: &Target, got_slots: &HashMap<Target, u64>| got_offset + got_slots[target] * 8;
+        for fixup in &self.fixups {
+            let place = self.sections[fixup.section].address + fixup.offset;
+            let p = place as usize;
+            let symbol_address = match fixup.target {
+                Target::Defined { section, offset } => {
+                    Some(self.sections[section].address + offset)
+                }
+                Target::Absolute(address) => Some(address),
+                Target::Undefined(symbol) if arch.is_call(fixup.r_type) => {
+                    stub_address(symbol, &self.stubs)
+                }
+                Target::Undefined(_) => None,
+            };
+            let value = symbol_address.map(|address| address.wrapping_add(fixup.addend as u64));
+
+            if fixup.r_type == arch.absolute_relocation() {
+                match fixup.target {
+                    Target::Undefined(symbol) => {
+                        relocations.push((place, symbol, arch.absolute_relocation(), fixup.addend))
+                    }
+                    Target::Defined { .. } => relocations.push((
+                        place,
+                        0,
+                        arch.relative_relocation(),
+                        value.unwrap() as i64,
+                    )),
+                    Target::Absolute(_) => {}
+                }
+                out[p..p + 8].copy_from_slice(&value.unwrap_or(0).to_le_bytes());
+                continue;
+            }
+
+            let got = if arch.uses_got(fixup.r_type) {
+                Some(got_address(&fixup.target, &self.got_slots))
+            } else {
+                None
+            };
+            let value = match (got, value) {
+                (Some(got), _) => got,
+                (None, Some(value)) => value,
+                (None, None) => {
+                    return Err(link_error(format!(
+                        "section `{}` refers directly to the undefined symbol `{}`",
+                        self.sections[fixup.section].name,
+                        self.symbols[symbol_index(fixup.target)].name
+                    )))
+                }
+            };
+            apply(arch, fixup.r_type, &mut out, place, value, fixup.addend).map_err(|e| {
+                link_error(format!(
+                    "{}, in section `{}` at offset {:#x}",
+                    e, self.sections[fixup.section].name, fixup.offset
+                ))
+            })?;
+        }
+
+        // The GOT and the stubs.
+        for (slot, target) in self.got.iter().enumerate() {
+            let place = got_offset + slot as u64 * 8;
+            let value = match *target {
+                Target::Defined { section, offset } => {
+                    let value = self.sections[section].address + offset;
+                    relocations.push((place, 0, arch.relative_relocation(), value as i64));
+                    value
+                }
+                Target::Absolute(address) => address,
+                Target::Undefined(symbol) => {
+                    relocations.push((place, symbol, arch.absolute_relocation(), 0));
+                    0
+                }
+            };
+            out[place as usize..place as usize + 8].copy_from_slice(&value.to_le_bytes());
+        }
+        for (symbol, stub) in &self.stubs {
+            let place = stubs_offset + stub * arch.stub_size();
+            let got = got_address(&Target::Undefined(*symbol), &self.got_slots);
+            let end = (place + arch.stub_size()) as usize;
+            arch.write_stub(&mut out[place as usize..end], place, got)?;
+        }
+        debug_assert_eq!(relocations.len() as u64, self.relocation_count);
+        // The relative relocations go first, as loaders expect.
+        relocations.sort_by_key(|&(place, symbol, _, _)| (symbol != 0, place));
+
+        // The dynamic linking tables.
+        let mut cursor = hash_offset as usize;
+        for word in &hash {
+            out[cursor..cursor + 4].copy_from_slice(&word.to_le_bytes());
+            cursor += 4;
+        }
+        cursor = dynsym_offset as usize + SYMBOL_SIZE as usize;
+        for (symbol, name) in self.symbols.iter().zip(symbol_names) {
+            let (st_shndx, st_value) = match symbol.target {
+                Some(Target::Defined { section, offset }) => {
+                    let section = &self.sections[section];
+                    let shndx = match section.group {
+                        Group::Text => SECTION_TEXT,
+                        Group::ReadOnlyData => SECTION_RODATA,
+                        Group::Data => SECTION_DATA,
+                    };
+                    (shndx, section.address + offset)
+                }
+                Some(Target::Absolute(address)) => (elf::SHN_ABS, address),
+                _ => (elf::SHN_UNDEF, 0),
+            };
+            let st_type = match symbol.kind {
+                SymbolKind::Text => elf::STT_FUNC,
+                SymbolKind::Data => elf::STT_OBJECT,
+                _ => elf::STT_NOTYPE,
+            };
+            let st_bind = if symbol.weak {
+                elf::STB_WEAK
+            } else {
+                elf::STB_GLOBAL
+            };
+            let sym = elf::Sym64::<LittleEndian> {
+                st_name: U32::new(LE, name),
+                st_info: (st_bind << 4) | st_type,
+                st_other: elf::STV_DEFAULT,
+                st_shndx: U16::new(LE, st_shndx),
+                st_value: U64::new(LE, st_value),
+                st_size: U64::new(LE, symbol.size),
+            };
+            write_pod(&mut out, &mut cursor, &sym);
+        }
+        let start = dynstr_offset as usize;
+        out[start..start + dynstr.len()].copy_from_slice(&dynstr);
+        cursor = rela_offset as usize;
+        for (place, symbol, r_type, addend) in &relocations {
+            let rela = elf::Rela64::<LittleEndian> {
+                r_offset: U64::new(LE, *place),
+                r_info: elf::Rela64::r_info(LE, *symbol, *r_type),
+                r_addend: I64::new(LE, *addend),
+            };
+            write_pod(&mut out, &mut cursor, &rela);
+        }
+
+        let mut dynamic = vec![
+            (elf::DT_HASH, hash_offset),
+            (elf::DT_STRTAB, dynstr_offset),
+            (elf::DT_SYMTAB, dynsym_offset),
+            (elf::DT_STRSZ, dynstr.len() as u64),
+            (elf::DT_SYMENT, SYMBOL_SIZE),
+            (elf::DT_RELA, rela_offset),
+            (elf::DT_RELASZ, relocations.len() as u64 * RELA_SIZE),
+            (elf::DT_RELAENT, RELA_SIZE),
+        ];
+        if self.text_relocations {
+            dynamic.push((elf::DT_TEXTREL, 0));
+            dynamic.push((elf::DT_FLAGS, u64::from(elf::DF_TEXTREL)));
+        }
+        dynamic.push((elf::DT_NULL, 0));
+        debug_assert_eq!(dynamic.len() as u64, dynamic_count);
+        cursor = dynamic_offset as usize;
+        for (tag, value) in dynamic {
+            let entry = elf::Dyn64::<LittleEndian> {
+                d_tag: U64::new(LE, u64::from(tag)),
+                d_val: U64::new(LE, value),
+            };
+            write_pod(&mut out, &mut cursor, &entry);
+        }
+
+        // The headers.
+        let header = elf::FileHeader64::<LittleEndian> {
+            e_ident: elf::Ident {
+                magic: elf::ELFMAG,
+                class: elf::ELFCLASS64,
+                data: elf::ELFDATA2LSB,
+                version: elf::EV_CURRENT,
+                os_abi: elf::ELFOSABI_NONE,
+                abi_version: 0,
+                padding: [0; 7],
+            },
+            e_type: U16::new(LE, elf::ET_DYN),
+            e_machine: U16::new(LE, arch.machine()),
+            e_version: U32::new(LE, u32::from(elf::EV_CURRENT)),
+            e_entry: U64::new(LE, 0),
+            e_phoff: U64::new(LE, FILE_HEADER_SIZE),
+            e_shoff: U64::new(LE, section_headers_offset),
+            e_flags: U32::new(LE, 0),
+            e_ehsize: U16::new(LE, FILE_HEADER_SIZE as u16),
+            e_phentsize: U16::new(LE, PROGRAM_HEADER_SIZE as u16),
+            e_phnum: U16::new(LE, PROGRAM_HEADERS as u16),
+            e_shentsize: U16::new(LE, SECTION_HEADER_SIZE as u16),
+            e_shnum: U16::new(LE, SECTION_NAMES.len() as u16),
+            e_shstrndx: U16::new(LE, SECTION_SHSTRTAB),
+        };
+        cursor = 0;
+        write_pod(&mut out, &mut cursor, &header);
+
+        let program_headers = [
+            (
+                elf::PT_LOAD,
+                elf::PF_R | elf::PF_X,
+                0,
+                rodata_end,
+                page_size,
+            ),
+            (
+                elf::PT_LOAD,
+                elf::PF_R | elf::PF_W,
+                dynamic_offset,
+                data_end - dynamic_offset,
+                page_size,
+            ),
+            (
+                elf::PT_DYNAMIC,
+                elf::PF_R | elf::PF_W,
+                dynamic_offset,
+                dynamic_count * DYN_SIZE,
+                8,
+            ),
+            // The stack doesn't need to be executable.
+            (elf::PT_GNU_STACK, elf::PF_R | elf::PF_W, 0, 0, 16),
+        ];
+        for (p_type, p_flags, offset, size, p_align) in program_headers.iter() {
+            let program_header = elf::ProgramHeader64::<LittleEndian> {
+                p_type: U32::new(LE, *p_type),
+                p_flags: U32::new(LE, *p_flags),
+                p_offset: U64::new(LE, *offset),
+                p_vaddr: U64::new(LE, *offset),
+                p_paddr: U64::new(LE, *offset),
+                p_filesz: U64::new(LE, *size),
+                p_memsz: U64::new(LE, *size),
+                p_align: U64::new(LE, *p_align),
+            };
+            write_pod(&mut out, &mut cursor, &program_header);
+        }
+
+        let start = shstrtab_offset as usize;
+        out[start..start + shstrtab.len()].copy_from_slice(&shstrtab);
+        let alloc = u64::from(elf::SHF_ALLOC);
+        let section_headers = [
+            (elf::SHT_NULL, 0, 0, 0, 0, 0, 0, 0),
+            (
+                elf::SHT_HASH,
+                alloc,
+                hash_offset,
+                hash.len() as u64 * 4,
+                SECTION_DYNSYM,
+                0,
+                8,
+                4,
+            ),
+            (
+                elf::SHT_DYNSYM,
+                alloc,
+                dynsym_offset,
+                symbol_count * SYMBOL_SIZE,
+                SECTION_DYNSTR,
+                // The index of the first non-local symbol.
+                1,
+                8,
+                SYMBOL_SIZE,
+            ),
+            (
+                elf::SHT_STRTAB,
+                alloc,
+                dynstr_offset,
+                dynstr.len() as u64,
+                0,
+                0,
+                1,
+                0,
+            ),
+            (
+                elf::SHT_RELA,
+                alloc,
+                rela_offset,
+                relocations.len() as u64 * RELA_SIZE,
+                SECTION_DYNSYM,
+                0,
+                8,
+                RELA_SIZE,
+            ),
+            (
+                elf::SHT_PROGBITS,
+                alloc | u64::from(elf::SHF_EXECINSTR),
+                text_offset,
+                rodata_offset - text_offset,
+                0,
+                0,
+                16,
+                0,
+            ),
+            (
+                elf::SHT_PROGBITS,
+                alloc,
+                rodata_offset,
+                rodata_end - rodata_offset,
+                0,
+                0,
+                16,
+                0,
+            ),
+            (
+                elf::SHT_DYNAMIC,
+                alloc | u64::from(elf::SHF_WRITE),
+                dynamic_offset,
+                dynamic_count * DYN_SIZE,
+                SECTION_DYNSTR,
+                0,
+                8,
+                DYN_SIZE,
+            ),
+            (
+                elf::SHT_PROGBITS,
+                alloc | u64::from(elf::SHF_WRITE),
+                got_offset,
+                self.got.len() as u64 * 8,
+                0,
+                0,
+                8,
+                8,
+            ),
+            (
+                elf::SHT_PROGBITS,
+                alloc | u64::from(elf::SHF_WRITE),
+                data_offset,
+                data_end - data_offset,
+                0,
+                0,
+                16,
+                0,
+            ),
+            (
+                elf::SHT_STRTAB,
+                0,
+                shstrtab_offset,
+                shstrtab.len() as u64,
+                0,
+                0,
+                1,
+                0,
+            ),
+        ];
+        cursor = section_headers_offset as usize;
+        for ((sh_type, sh_flags, offset, size, link, info, sh_align, entsize), name) in
+            section_headers.iter().zip(section_names)
+        {
+            let is_null = *sh_type == elf::SHT_NULL;
+            let section_header = elf::SectionHeader64::<LittleEndian> {
+                sh_name: U32::new(LE, name),
+                sh_type: U32::new(LE, *sh_type),
+                sh_flags: U64::new(LE, *sh_flags),
+                sh_addr: U64::new(LE, if *sh_flags & alloc != 0 { *offset } else { 0 }),
+                sh_offset: U64::new(LE, *offset),
+                sh_size: U64::new(LE, *size),
+                sh_link: U32::new(LE, u32::from(*link)),
+                sh_info: U32::new(LE, *info),
+                sh_addralign: U64::new(LE, if is_null { 0 } else { *sh_align }),
+                sh_entsize: U64::new(LE, *entsize),
+            };
+            write_pod(&mut out, &mut cursor, &section_header);
+        }
+
+        Ok(out)
+    }
+
+    /// Assigns addresses to the input sections of `group` from `start`,
+    /// and returns the end of the group.
+    fn place(&mut self, group: Group, start: u64) -> u64 {
+        let mut offset = start;
+        for section in self.sections.iter_mut().filter(|s| s.group == group) {
+            section.address = align(offset, section.align);
+            offset = section.address + section.size;
+        }
+        offset
+    }
+}
+
+/// The index in the symbols of the linker of an undefined `target`.
+fn symbol_index(target: Target) -> usize {
+    match target {
+        Target::Undefined(symbol) => symbol as usize - 1,
+        _ => unreachable!("only undefined symbols are dynamic symbols"),
+    }
+}
+
+/// Applies the relocation `r_type` at `place`, for a target at `value`
+/// (the GOT entry of the target for relocations using the GOT).
+fn apply(
+    arch: Arch,
+    r_type: u32,
+    out: &mut [u8],
+    place: u64,
+    value: u64,
+    addend: i64,
+) -> Result<(), String> {
+    let p = place as usize;
+    let relative = value.wrapping_sub(place) as i64;
+    match (arch, r_type) {
+        (Arch::X86_64, elf::R_X86_64_PC32) | (Arch::X86_64, elf::R_X86_64_PLT32) => {
+            write_i32(out, p, relative).map_err(|_| "relative address out of range".to_string())
+        }
+        (Arch::X86_64, elf::R_X86_64_GOTPCREL)
+        | (Arch::X86_64, elf::R_X86_64_GOTPCRELX)
+        | (Arch::X86_64, elf::R_X86_64_REX_GOTPCRELX) => {
+            // `value` is the address of the GOT entry, without the addend.
+            write_i32(out, p, relative.wrapping_add(addend))
+                .map_err(|_| "GOT entry out of range".to_string())
+        }
+        (Arch::Aarch64, elf::R_AARCH64_PREL32) => {
+            write_i32(out, p, relative).map_err(|_| "relative address out of range".to_string())
+        }
+        (Arch::Aarch64, elf::R_AARCH64_PREL64) => {
+            out[p..p + 8].copy_from_slice(&relative.to_le_bytes());
+            Ok(())
+        }
+        (Arch::Aarch64, elf::R_AARCH64_CALL26) | (Arch::Aarch64, elf::R_AARCH64_JUMP26) => {
+            if relative % 4 != 0 || !(-(1 << 27)..(1 << 27)).contains(&relative) {
+                return Err("branch target out of range".to_string());
+            }
+            update_insn(out, p, 0x03ff_ffff, (relative >> 2) as u32);
+            Ok(())
+        }
+        (Arch::Aarch64, elf::R_AARCH64_ADR_PREL_PG_HI21)
+        | (Arch::Aarch64, elf::R_AARCH64_ADR_PREL_PG_HI21_NC)
+        | (Arch::Aarch64, elf::R_AARCH64_ADR_GOT_PAGE) => {
+            let value = if r_type == elf::R_AARCH64_ADR_GOT_PAGE {
+                value.wrapping_add(addend as u64)
+            } else {
+                value
+            };
+            let delta = page_delta(value, place).map_err(|_| "page out of range".to_string())?;
+            let insn = u32::from_le_bytes([out[p], out[p + 1], out[p + 2], out[p + 3]]);
+            out[p..p + 4].copy_from_slice(&encode_adr(insn, delta).to_le_bytes());
+            Ok(())
+        }
+        (Arch::Aarch64, elf::R_AARCH64_ADD_ABS_LO12_NC) => {
+            update_insn(out, p, 0xfff << 10, (value as u32 & 0xfff) << 10);
+            Ok(())
+        }
+        (Arch::Aarch64, elf::R_AARCH64_LDST8_ABS_LO12_NC)
+        | (Arch::Aarch64, elf::R_AARCH64_LDST16_ABS_LO12_NC)
+        | (Arch::Aarch64, elf::R_AARCH64_LDST32_ABS_LO12_NC)
+        | (Arch::Aarch64, elf::R_AARCH64_LDST64_ABS_LO12_NC)
+        | (Arch::Aarch64, elf::R_AARCH64_LDST128_ABS_LO12_NC)
+        | (Arch::Aarch64, elf::R_AARCH64_LD64_GOT_LO12_NC) => {
+            let shift = match r_type {
+                elf::R_AARCH64_LDST8_ABS_LO12_NC => 0,
+                elf::R_AARCH64_LDST16_ABS_LO12_NC => 1,
+                elf::R_AARCH64_LDST32_ABS_LO12_NC => 2,
+                elf::R_AARCH64_LDST128_ABS_LO12_NC => 4,
+                _ => 3,
+            };
+            let value = if r_type == elf::R_AARCH64_LD64_GOT_LO12_NC {
+                value.wrapping_add(addend as u64)
+            } else {
+                value
+            };
+            update_insn(out, p, 0xfff << 10, ((value as u32 & 0xfff) >> shift) << 10);
+            Ok(())
+        }
+        _ => Err(format!("unsupported relocation type {}", r_type)),
+    }
+}
+
+fn write_i32(out: &mut [u8], offset: usize, value: i64) -> Result<(), CompileError> {
+    let value = i32::try_from(value)
+        .map_err(|_| link_error(format!("{:#x} doesn't fit in 32 bits", value)))?;
+    out[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+/// Sets the bits of `mask` in the instruction at `offset` to `bits`.
+fn update_insn(out: &mut [u8], offset: usize, mask: u32, bits: u32) {
+    let insn = u32::from_le_bytes([
+        out[offset],
+        out[offset + 1],
+        out[offset + 2],
+        out[offset + 3],
+    ]);
+    let insn = (insn & !mask) | (bits & mask);
+    out[offset..offset + 4].copy_from_slice(&insn.to_le_bytes());
+}
+
+/// The number of 4KiB pages from the page of `place` to the page of
+/// `address`, as encoded by `adrp`.
+fn page_delta(address: u64, place: u64) -> Result<i64, CompileError> {
+    let delta = ((address & !0xfff) as i64).wrapping_sub((place & !0xfff) as i64) >> 12;
+    if !(-(1 << 20)..(1 << 20)).contains(&delta) {
+        return Err(link_error(format!("page {:#x} out of range", address)));
+    }
+    Ok(delta)
+}
+
+/// Sets the immediate of an `adr` or `adrp` instruction.
+fn encode_adr(insn: u32, immediate: i64) -> u32 {
+    let immediate = immediate as u32;
+    let immlo = immediate & 0x3;
+    let immhi = (immediate >> 2) & 0x7ffff;
+    (insn & !((0x3 << 29) | (0x7ffff << 5))) | (immlo << 29) | (immhi << 5)
+}
+
+fn align(offset: u64, align: u64) -> u64 {
+    (offset + align - 1) / align * align
+}
+
+fn write_pod<T: object::pod::Pod>(out: &mut [u8], cursor: &mut usize, value: &T) {
+    let bytes = bytes_of(value);
+    out[*cursor..*cursor + bytes.len()].copy_from_slice(bytes);
+    *cursor += bytes.len();
+}
+
+/// Builds the `.hash` section of the dynamic symbols: the number of
+/// buckets and chains, the buckets and the chains.
+fn sysv_hash_table(symbols: &[DynamicSymbol]) -> Vec<u32> {
+    let symbol_count = symbols.len() as u32 + 1;
+    let bucket_count = symbol_count;
+    let mut buckets = vec![0; bucket_count as usize];
+    let mut chains = vec![0; symbol_count as usize];
+    for (index, symbol) in symbols.iter().enumerate() {
+        let index = index as u32 + 1;
+        let bucket = (sysv_hash(symbol.name.as_bytes()) % bucket_count) as usize;
+        chains[index as usize] = buckets[bucket];
+        buckets[bucket] = index;
+    }
+    let mut table = vec![bucket_count, symbol_count];
+    table.extend(buckets);
+    table.extend(chains);
+    table
+}
+
+fn sysv_hash(name: &[u8]) -> u32 {
+    let mut hash = 0u32;
+    for byte in name {
+        hash = (hash << 4).wrapping_add(u32::from(*byte));
+        let high = hash & 0xf000_0000;
+        if high != 0 {
+            hash ^= high >> 24;
+        }
+        hash &= !high;
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object::write::{self, StandardSection, Symbol as ObjSymbol, SymbolSection as ObjSection};
+    use object::{BinaryFormat as ObjectFormat, RelocationEncoding, SymbolFlags};
+
+    fn add_symbol(
+        obj: &mut write::Object,
+        name: &str,
+        kind: SymbolKind,
+        section: StandardSection,
+        data: &[u8],
+    ) -> write::SymbolId {
+        let symbol = obj.add_symbol(ObjSymbol {
+            name: name.as_bytes().to_vec(),
+            value: 0,
+            size: 0,
+            kind,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: ObjSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        let section = obj.section_id(section);
+        obj.add_symbol_data(symbol, section, data, 16);
+        symbol
+    }
+
+    fn add_relocation(
+        obj: &mut write::Object,
+        section: StandardSection,
+        function: write::SymbolId,
+        offset: u64,
+        kind: RelocationKind,
+        symbol: write::SymbolId,
+        addend: i64,
+    ) {
+        let (_, function_offset) = obj.symbol_section_and_offset(function).unwrap();
+        let section = obj.section_id(section);
+        obj.add_relocation(
+            section,
+            write::Relocation {
+                offset: function_offset + offset,
+                size: if kind == RelocationKind::Absolute {
+                    64
+                } else {
+                    32
+                },
+                kind,
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend,
+            },
+        )
+        .unwrap();
+    }
+
+    /// An object with data, functions referring to it, and functions
+    /// referring to `labs` from the C library.
+    fn x86_64_object() -> Vec<u8> {
+        let mut obj = write::Object::new(
+            ObjectFormat::Elf,
+            ObjectArchitecture::X86_64,
+            ObjectEndianness::Little,
+        );
+        let answer = add_symbol(
+            &mut obj,
+            "answer",
+            SymbolKind::Data,
+            StandardSection::Data,
+            &42u64.to_le_bytes(),
+        );
+        let answer_ptr = add_symbol(
+            &mut obj,
+            "answer_ptr",
+            SymbolKind::Data,
+            StandardSection::Data,
+            &[0; 8],
+        );
+        add_relocation(
+            &mut obj,
+            StandardSection::Data,
+            answer_ptr,
+            0,
+            RelocationKind::Absolute,
+            answer,
+            0,
+        );
+        let labs = obj.add_symbol(ObjSymbol {
+            name: b"labs".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Unknown,
+            scope: SymbolScope::Unknown,
+            weak: false,
+            section: ObjSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+
+        // mov rax, [rip + answer]; ret
+        let code = [0x48, 0x8b, 0x05, 0, 0, 0, 0, 0xc3];
+        let function = add_symbol(
+            &mut obj,
+            "get_answer",
+            SymbolKind::Text,
+            StandardSection::Text,
+            &code,
+        );
+        add_relocation(
+            &mut obj,
+            StandardSection::Text,
+            function,
+            3,
+            RelocationKind::Relative,
+            answer,
+            -4,
+        );
+        // mov rax, [rip + answer_ptr]; mov rax, [rax]; ret
+        let code = [0x48, 0x8b, 0x05, 0, 0, 0, 0, 0x48, 0x8b, 0x00, 0xc3];
+        let function = add_symbol(
+            &mut obj,
+            "get_answer_indirect",
+            SymbolKind::Text,
+            StandardSection::Text,
+            &code,
+        );
+        add_relocation(
+            &mut obj,
+            StandardSection::Text,
+            function,
+            3,
+            RelocationKind::Relative,
+            answer_ptr,
+            -4,
+        );
+        // jmp labs
+        let code = [0xe9, 0, 0, 0, 0];
+        let function = add_symbol(
+            &mut obj,
+            "call_labs",
+            SymbolKind::Text,
+            StandardSection::Text,
+            &code,
+        );
+        add_relocation(
+            &mut obj,
+            StandardSection::Text,
+            function,
+            1,
+            RelocationKind::PltRelative,
+            labs,
+            -4,
+        );
+        // mov rax, [rip + labs@GOTPCREL]; ret
+        let code = [0x48, 0x8b, 0x05, 0, 0, 0, 0, 0xc3];
+        let function = add_symbol(
+            &mut obj,
+            "get_labs",
+            SymbolKind::Text,
+            StandardSection::Text,
+            &code,
+        );
+        add_relocation(
+            &mut obj,
+            StandardSection::Text,
+            function,
+            3,
+            RelocationKind::GotRelative,
+            labs,
+            -4,
+        );
+
+        obj.write().unwrap()
+    }
+
+    #[test]
+    fn exports_global_symbols() {
+        let shared_object = link(&x86_64_object()).unwrap();
+        let file = ElfFile64::<ObjectEndianness>::parse(&*shared_object).unwrap();
+        let mut exports = file
+            .dynamic_symbols()
+            .filter(|symbol| !symbol.is_undefined())
+            .map(|symbol| symbol.name().unwrap())
+            .collect::<Vec<_>>();
+        exports.sort();
+        assert_eq!(
+            exports,
+            [
+                "answer",
+                "answer_ptr",
+                "call_labs",
+                "get_answer",
+                "get_answer_indirect",
+                "get_labs"
+            ]
+        );
+        let imports = file
+            .dynamic_symbols()
+            // The null symbol is undefined.
+            .skip(1)
+            .filter(|symbol| symbol.is_undefined())
+            .map(|symbol| symbol.name().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(imports, ["labs"]);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn loads_with_dlopen() {
+        use libloading::{Library, Symbol as LibrarySymbol};
+        use std::io::Write;
+
+        extern "C" {
+            fn labs(value: i64) -> i64;
+        }
+
+        let shared_object = link(&x86_64_object()).unwrap();
+        let mut file = tempfile::Builder::new().suffix(".so").tempfile().unwrap();
+        file.write_all(&shared_object).unwrap();
+        unsafe {
+            let lib = Library::new(file.path()).unwrap();
+            let get_answer: LibrarySymbol<extern "C" fn() -> u64> = lib.get(b"get_answer").unwrap();
+            assert_eq!(get_answer(), 42);
+            let get_answer_indirect: LibrarySymbol<extern "C" fn() -> u64> =
+                lib.get(b"get_answer_indirect").unwrap();
+            assert_eq!(get_answer_indirect(), 42);
+            let call_labs: LibrarySymbol<extern "C" fn(i64) -> i64> =
+                lib.get(b"call_labs").unwrap();
+            assert_eq!(call_labs(-7), 7);
+            let get_labs: LibrarySymbol<extern "C" fn() -> usize> = lib.get(b"get_labs").unwrap();
+            assert_eq!(get_labs(), labs as *const () as usize);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Compiles modules to shared objects linked in process, loads them and
+//! calls into them, on the hosts the builtin linker supports.
+#![cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+
+use wasmer::{imports, Cranelift, Function, Instance, Module, NativeFunc, Store};
+use wasmer_engine_native::Native;
+
+const MODULE: &str = r#"
+(module
+  (import "env" "double" (func $double (param i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "\2a")
+  (global $counter (mut i32) (i32.const 0))
+  (func $load (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+  (func (export "quadruple_stored") (param i32) (result i32)
+    (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+    (call $double (call $double (call $load (local.get 0)))))
+  (func (export "grow") (result i32)
+    (memory.grow (i32.const 1)))
+  (func (export "calls") (result i32)
+    (global.get $counter)))
+"#;
+
+fn store() -> Store {
+    let mut engine = Native::new(Cranelift::default()).engine();
+    engine.use_builtin_linker();
+    Store::new(&engine)
+}
+
+#[test]
+fn compiles_links_instantiates_and_calls() {
+    let store = store();
+    let module = Module::new(&store, MODULE).unwrap();
+    let import_object = imports! {
+        "env" => {
+            "double" => Function::new_native(&store, |x: i32| x * 2),
+        },
+    };
+    let instance = Instance::new(&module, &import_object).unwrap();
+
+    let quadruple_stored: NativeFunc<i32, i32> = instance
+        .exports
+        .get_native_function("quadruple_stored")
+        .unwrap();
+    assert_eq!(quadruple_stored.call(16).unwrap(), 168);
+    assert_eq!(quadruple_stored.call(0).unwrap(), 0);
+
+    let grow: NativeFunc<(), i32> = instance.exports.get_native_function("grow").unwrap();
+    assert_eq!(grow.call().unwrap(), 1);
+    assert_eq!(instance.exports.get_memory("memory").unwrap().size().0, 2);
+
+    let calls: NativeFunc<(), i32> = instance.exports.get_native_function("calls").unwrap();
+    assert_eq!(calls.call().unwrap(), 2);
+}
+
+#[test]
+fn serialized_modules_are_loaded_again() {
+    let store = store();
+    let module = Module::new(&store, MODULE).unwrap();
+    let bytes = module.serialize().unwrap();
+    let module = unsafe { Module::deserialize(&store, &bytes) }.unwrap();
+
+    let import_object = imports! {
+        "env" => {
+            "double" => Function::new_native(&store, |x: i32| x * 2),
+        },
+    };
+    let instance = Instance::new(&module, &import_object).unwrap();
+    let quadruple_stored: NativeFunc<i32, i32> = instance
+        .exports
+        .get_native_function("quadruple_stored")
+        .unwrap();
+    assert_eq!(quadruple_stored.call(16).unwrap(), 168);
+}
@@ -12,7 +12,7 @@ use wasmer_compiler::{CompileError, Features, OperatingSystem, SymbolRegistry, T
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{
     CompileModuleInfo, Compiler, FunctionBodyData, ModuleEnvironment, ModuleMiddlewareChain,
-    ModuleTranslationState,
+    ModuleTranslationState, Symbol,
 };
 use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
@@ -53,9 +53,6 @@ fn to_compile_error(err: impl Error) -> CompileError {
     CompileError::Codegen(format!("{}", err))
 }
 
-#[allow(dead_code)]
-const WASMER_METADATA_SYMBOL: &[u8] = b"WASMER_METADATA";
-
 impl ObjectFileArtifact {
     // Mach-O header in Mac
     #[allow(dead_code)]
@@ -123,8 +120,14 @@ impl ObjectFileArtifact {
 
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = module
             .memories
-            .values()
-            .map(|memory_type| tunables.memory_style(memory_type))
+            .iter()
+            .map(|(index, memory_type)| {
+                if module.memory64[index] {
+                    tunables.memory64_style(memory_type)
+                } else {
+                    tunables.memory_style(memory_type)
+                }
+            })
             .collect();
         let table_styles: PrimaryMap<TableIndex, TableStyle> = module
             .tables
@@ -243,8 +246,13 @@ impl ObjectFileArtifact {
             .collect::<PrimaryMap<LocalFunctionIndex, u64>>();
              */
             let mut obj = get_object_for_target(&target_triple).map_err(to_compile_error)?;
-            emit_data(&mut obj, WASMER_METADATA_SYMBOL, &metadata_binary, 1)
-                .map_err(to_compile_error)?;
+            emit_data(
+                &mut obj,
+                symbol_registry.symbol_to_name(Symbol::Metadata).as_bytes(),
+                &metadata_binary,
+                1,
+            )
+            .map_err(to_compile_error)?;
             emit_compilation(&mut obj, compilation, &symbol_registry, &target_triple)
                 .map_err(to_compile_error)?;
             obj.write().map_err(to_compile_error)?
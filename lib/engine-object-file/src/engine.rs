@@ -169,6 +169,26 @@ impl Engine for ObjectFileEngine {
         &self.engine_id
     }
 
+    fn fingerprint(&self) -> String {
+        #[cfg(feature = "compiler")]
+        let (features, compiler) = {
+            let inner = self.inner();
+            match &inner.compiler {
+                Some(compiler) => (format!("{:?}", inner.features), compiler.fingerprint()),
+                None => ("headless".to_string(), "headless".to_string()),
+            }
+        };
+        #[cfg(not(feature = "compiler"))]
+        let (features, compiler) = ("headless", "headless");
+        format!(
+            "object-file-{} target={} features={} compiler=({})",
+            env!("CARGO_PKG_VERSION"),
+            self.target.fingerprint(),
+            features,
+            compiler
+        )
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
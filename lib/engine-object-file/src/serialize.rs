@@ -56,11 +56,17 @@ impl SymbolRegistry for ModuleMetadataSymbolRegistry {
                     index.index()
                 )
             }
+            // The metadata of unprefixed modules keeps its historical name.
+            Symbol::Metadata if self.prefix.is_empty() => "WASMER_METADATA".to_string(),
+            Symbol::Metadata => format!("WASMER_METADATA_{}", self.prefix),
         }
     }
 
     fn name_to_symbol(&self, name: &str) -> Option<Symbol> {
-        if let Some(index) = name.strip_prefix(&format!("wasmer_function_{}_", self.prefix)) {
+        if name == self.symbol_to_name(Symbol::Metadata) {
+            Some(Symbol::Metadata)
+        } else if let Some(index) = name.strip_prefix(&format!("wasmer_function_{}_", self.prefix))
+        {
             index
                 .parse::<u32>()
                 .ok()
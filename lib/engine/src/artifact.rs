@@ -103,6 +103,7 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
             let mut imports = resolve_imports(
                 &module,
                 resolver,
+                self.features(),
                 &self.finished_dynamic_function_trampolines(),
                 self.memory_styles(),
                 self.table_styles(),
@@ -147,6 +148,7 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
             imports,
             self.signatures().clone(),
             self.func_data_registry(),
+            self.features(),
             host_state,
             import_function_envs,
         )
@@ -68,6 +68,21 @@ pub trait Engine: MemoryUsage {
     /// of trait representation.
     fn id(&self) -> &EngineId;
 
+    /// A textual description of everything that changes the artifacts
+    /// produced by this engine: the engine kind and version, the target,
+    /// the enabled features and the compiler configuration.
+    ///
+    /// Unlike [`Engine::id`], two engines configured the same way share
+    /// their fingerprint, so it can be used to key cached artifacts.
+    ///
+    /// Defaults to a [`unique_fingerprint`], so the artifacts of engines
+    /// that don't describe themselves are never shared by caches.
+    ///
+    /// [`unique_fingerprint`]: wasmer_compiler::unique_fingerprint
+    fn fingerprint(&self) -> String {
+        wasmer_compiler::unique_fingerprint()
+    }
+
     /// Clone the engine
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync>;
 }
@@ -1,6 +1,8 @@
 use loupe::MemoryUsage;
 use std::sync::Arc;
-use wasmer_vm::{ImportInitializerFuncPtr, VMExtern, VMFunction, VMGlobal, VMMemory, VMTable};
+use wasmer_vm::{
+    ImportInitializerFuncPtr, VMExceptionTag, VMExtern, VMFunction, VMGlobal, VMMemory, VMTable,
+};
 
 /// The value of an export passed from one instance to another.
 #[derive(Debug, Clone)]
@@ -16,6 +18,9 @@ pub enum Export {
 
     /// A global export value.
     Global(VMGlobal),
+
+    /// An exception tag export value.
+    Tag(VMExceptionTag),
 }
 
 impl From<Export> for VMExtern {
@@ -25,6 +30,7 @@ impl From<Export> for VMExtern {
             Export::Memory(vm_memory) => Self::Memory(vm_memory),
             Export::Table(vm_table) => Self::Table(vm_table),
             Export::Global(vm_global) => Self::Global(vm_global),
+            Export::Tag(vm_tag) => Self::Tag(vm_tag),
         }
     }
 }
@@ -39,6 +45,7 @@ impl From<VMExtern> for Export {
             VMExtern::Memory(vm_memory) => Self::Memory(vm_memory),
             VMExtern::Table(vm_table) => Self::Table(vm_table),
             VMExtern::Global(vm_global) => Self::Global(vm_global),
+            VMExtern::Tag(vm_tag) => Self::Tag(vm_tag),
         }
     }
 }
@@ -116,6 +123,28 @@ impl ExportFunctionMetadata {
             host_env_drop_fn,
         }
     }
+
+    /// Returns the function to initialize the host envs of this function
+    /// with an `Instance`, if any.
+    pub fn import_init_function_ptr(&self) -> Option<ImportInitializerFuncPtr> {
+        self.import_init_function_ptr
+    }
+
+    /// Clones `host_env` with `host_env_clone_fn`, returning a leaked `Box`.
+    ///
+    /// # Safety
+    /// - `host_env` must be the host env of this function, or a clone of it.
+    pub unsafe fn clone_host_env(&self, host_env: *mut std::ffi::c_void) -> *mut std::ffi::c_void {
+        (self.host_env_clone_fn)(host_env)
+    }
+
+    /// Frees a host env returned by [`ExportFunctionMetadata::clone_host_env`].
+    ///
+    /// # Safety
+    /// - `host_env` must not be used afterwards.
+    pub unsafe fn drop_host_env(&self, host_env: *mut std::ffi::c_void) {
+        (self.host_env_drop_fn)(host_env)
+    }
 }
 
 // We have to free `host_env` here because we always clone it before using it
@@ -1,13 +1,15 @@
 //! Define the `Resolver` trait, allowing custom resolution for external
 //! references.
 
-use crate::{Export, ExportFunctionMetadata, ImportError, LinkError};
+use crate::{Export, ExportFunctionMetadata, ImportError, LinkError, RuntimeError};
 use more_asserts::assert_ge;
 use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
-use wasmer_types::{ExternType, FunctionIndex, ImportIndex, MemoryIndex, TableIndex};
+use wasmer_types::{
+    ExternType, Features, FunctionIndex, ImportIndex, MemoryIndex, TableIndex, TagType,
+};
 
 use wasmer_vm::{
-    FunctionBodyPtr, ImportFunctionEnv, Imports, MemoryStyle, ModuleInfo, TableStyle,
+    FunctionBodyPtr, ImportFunctionEnv, Imports, InstanceRef, MemoryStyle, ModuleInfo, TableStyle,
     VMFunctionBody, VMFunctionEnvironment, VMFunctionImport, VMFunctionKind, VMGlobalImport,
     VMMemoryImport, VMTableImport,
 };
@@ -105,6 +107,7 @@ fn get_extern_from_import(module: &ModuleInfo, import_index: &ImportIndex) -> Ex
             let global = module.globals[*index];
             ExternType::Global(global)
         }
+        ImportIndex::Tag(index) => ExternType::Tag(module.tag_type(*index)),
     }
 }
 
@@ -118,6 +121,7 @@ fn get_extern_from_export(_module: &ModuleInfo, export: &Export) -> ExternType {
             let global = g.from.ty();
             ExternType::Global(*global)
         }
+        Export::Tag(ref t) => ExternType::Tag(TagType::new(t.params())),
     }
 }
 
@@ -125,9 +129,15 @@ fn get_extern_from_export(_module: &ModuleInfo, export: &Export) -> ExternType {
 /// a `Resolver`.
 ///
 /// If all imports are satisfied returns an `Imports` instance required for a module instantiation.
+///
+/// The functions and tables of instances can only be imported by modules
+/// compiled with the same tail-call feature, in `features`: the functions
+/// of modules compiled with tail calls leave them pending when they return,
+/// for their caller to make.
 pub fn resolve_imports(
     module: &ModuleInfo,
     resolver: &dyn Resolver,
+    features: &Features,
     finished_dynamic_function_trampolines: &BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
     _table_styles: &PrimaryMap<TableIndex, TableStyle>,
@@ -138,6 +148,7 @@ pub fn resolve_imports(
     let mut table_imports = PrimaryMap::with_capacity(module.num_imported_tables);
     let mut memory_imports = PrimaryMap::with_capacity(module.num_imported_memories);
     let mut global_imports = PrimaryMap::with_capacity(module.num_imported_globals);
+    let mut tag_imports = PrimaryMap::with_capacity(module.num_imported_tags);
 
     for ((module_name, field, import_idx), import_index) in module.imports.iter() {
         let resolved = resolver.resolve(*import_idx, module_name, field);
@@ -162,6 +173,17 @@ pub fn resolve_imports(
         }
         match resolved {
             Export::Function(ref f) => {
+                // The functions given to host environments don't keep
+                // their instance alive.
+                if let Some(instance_ref) = &f.vm_function.instance_ref {
+                    if !instance_ref.is_alive() {
+                        return Err(LinkError::Trap(RuntimeError::new(format!(
+                            "the instance of the imported function {:?}.{:?} has been freed",
+                            module_name, field
+                        ))));
+                    }
+                    check_tail_calls(instance_ref, features, module_name, field)?;
+                }
                 let address = match f.vm_function.kind {
                     VMFunctionKind::Dynamic => {
                         // If this is a dynamic imported function,
@@ -230,6 +252,11 @@ pub fn resolve_imports(
                         ));
                     }
 
+                    // The tables of freed instances are host tables.
+                    if let Some(instance_ref) = t.instance_ref.as_ref().filter(|i| i.is_alive()) {
+                        check_tail_calls(instance_ref, features, module_name, field)?;
+                    }
+
                     table_imports.push(VMTableImport {
                         definition: t.from.vmtable(),
                         from: t.from.clone(),
@@ -242,6 +269,15 @@ pub fn resolve_imports(
             Export::Memory(ref m) => {
                 match import_index {
                     ImportIndex::Memory(index) => {
+                        // The index type isn't part of the memory type.
+                        if m.is_64() != module.memory64[*index] {
+                            return Err(LinkError::Import(
+                                module_name.to_string(),
+                                field.to_string(),
+                                ImportError::IncompatibleType(import_extern, export_extern),
+                            ));
+                        }
+
                         // Sanity-check: Ensure that the imported memory has at least
                         // guard-page protections the importing module expects it to have.
                         let export_memory_style = m.style();
@@ -280,6 +316,10 @@ pub fn resolve_imports(
                     from: g.from.clone(),
                 });
             }
+
+            Export::Tag(ref t) => {
+                tag_imports.push(t.clone());
+            }
         }
     }
 
@@ -289,6 +329,7 @@ pub fn resolve_imports(
         table_imports,
         memory_imports,
         global_imports,
+        tag_imports,
     ))
 }
 
@@ -381,3 +422,26 @@ where
         }
     }
 }
+
+/// Checks that the instance of the import `module_name`.`field` was
+/// compiled with the tail-call feature of `features`.
+fn check_tail_calls(
+    instance_ref: &InstanceRef,
+    features: &Features,
+    module_name: &str,
+    field: &str,
+) -> Result<(), LinkError> {
+    if instance_ref.tail_calls() == features.tail_call {
+        return Ok(());
+    }
+    Err(LinkError::Trap(RuntimeError::new(format!(
+        "the import {:?}.{:?} comes from a module {} tail calls, unlike the importing module",
+        module_name,
+        field,
+        if features.tail_call {
+            "compiled without"
+        } else {
+            "compiled with"
+        }
+    ))))
+}
@@ -9,7 +9,7 @@ use wasmer_types::{
 };
 use wasmer_vm::MemoryError;
 use wasmer_vm::{Global, Memory, ModuleInfo, Table};
-use wasmer_vm::{MemoryStyle, TableStyle};
+use wasmer_vm::{MemoryStyle, StackConfig, TableStyle};
 use wasmer_vm::{VMMemoryDefinition, VMTableDefinition};
 
 /// An engine delegates the creation of memories, tables, and globals
@@ -18,9 +18,28 @@ pub trait Tunables: MemoryUsage {
     /// Construct a `MemoryStyle` for the provided `MemoryType`
     fn memory_style(&self, memory: &MemoryType) -> MemoryStyle;
 
+    /// Construct a `MemoryStyle` for the provided `MemoryType` of a memory
+    /// indexed with 64-bit addresses.
+    ///
+    /// 64-bit memories can't be reserved up front, so by default they are
+    /// dynamic, with the offset guard `memory_style` picks.
+    fn memory64_style(&self, memory: &MemoryType) -> MemoryStyle {
+        MemoryStyle::Dynamic {
+            offset_guard_size: self.memory_style(memory).offset_guard_size(),
+        }
+    }
+
     /// Construct a `TableStyle` for the provided `TableType`
     fn table_style(&self, table: &TableType) -> TableStyle;
 
+    /// How much stack the guest can use, and which stack it runs on.
+    ///
+    /// By default, the guest uses the stack of the host thread, without
+    /// other limit than its size.
+    fn stack_config(&self) -> StackConfig {
+        StackConfig::default()
+    }
+
     /// Create a memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
     fn create_host_memory(
         &self,
@@ -39,6 +58,38 @@ pub trait Tunables: MemoryUsage {
         vm_definition_location: NonNull<VMMemoryDefinition>,
     ) -> Result<Arc<dyn Memory>, MemoryError>;
 
+    /// Create a memory indexed with 64-bit addresses owned by the host given
+    /// a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// By default, 64-bit memories are not supported.
+    fn create_host_memory64(
+        &self,
+        _ty: &MemoryType,
+        _style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Err(MemoryError::Generic(
+            "these tunables don't support 64-bit memories".to_string(),
+        ))
+    }
+
+    /// Create a memory indexed with 64-bit addresses owned by the VM given
+    /// a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// By default, 64-bit memories are not supported.
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid location in VM memory.
+    unsafe fn create_vm_memory64(
+        &self,
+        _ty: &MemoryType,
+        _style: &MemoryStyle,
+        _vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Err(MemoryError::Generic(
+            "these tunables don't support 64-bit memories".to_string(),
+        ))
+    }
+
     /// Create a table owned by the host given a [`TableType`] and a [`TableStyle`].
     fn create_host_table(
         &self,
@@ -77,8 +128,13 @@ pub trait Tunables: MemoryUsage {
             let ty = &module.memories[mi];
             let style = &memory_styles[mi];
             let mdl = memory_definition_locations[index];
-            memories.push(
+            let memory = if module.memory64[mi] {
+                self.create_vm_memory64(ty, style, mdl)
+            } else {
                 self.create_vm_memory(ty, style, mdl)
+            };
+            memories.push(
+                memory
                     .map_err(|e| LinkError::Resource(format!("Failed to create memory: {}", e)))?,
             );
         }
@@ -8,8 +8,8 @@ use std::mem;
 use std::sync::{Arc, Mutex};
 use wasmer::wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
 use wasmer::{
-    ExportIndex, FunctionMiddleware, GlobalInit, GlobalType, Instance, LocalFunctionIndex,
-    MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+    unique_fingerprint, ExportIndex, FunctionMiddleware, GlobalInit, GlobalType, Instance,
+    LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
 };
 use wasmer_types::GlobalIndex;
 use wasmer_vm::ModuleInfo;
@@ -56,6 +56,9 @@ pub struct Metering<F: Fn(&Operator) -> u64 + Send + Sync> {
     /// Function that maps each operator to a cost in "points".
     cost_function: Arc<F>,
 
+    /// A description of the cost function, if it was given.
+    cost_function_fingerprint: Option<String>,
+
     /// The global indexes for metering points.
     global_indexes: Mutex<Option<MeteringGlobalIndexes>>,
 }
@@ -88,9 +91,20 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> Metering<F> {
         Self {
             initial_limit,
             cost_function: Arc::new(cost_function),
+            cost_function_fingerprint: None,
             global_indexes: Mutex::new(None),
         }
     }
+
+    /// Describes the cost function with `fingerprint`, so that the
+    /// modules compiled with this middleware can be cached.
+    ///
+    /// Cost functions must have the same fingerprint if and only if they
+    /// give the same costs.
+    pub fn with_fingerprint(mut self, fingerprint: impl Into<String>) -> Self {
+        self.cost_function_fingerprint = Some(fingerprint.into());
+        self
+    }
 }
 
 impl<F: Fn(&Operator) -> u64 + Send + Sync> fmt::Debug for Metering<F> {
@@ -98,6 +112,7 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> fmt::Debug for Metering<F> {
         f.debug_struct("Metering")
             .field("initial_limit", &self.initial_limit)
             .field("cost_function", &"<function>")
+            .field("cost_function_fingerprint", &self.cost_function_fingerprint)
             .field("global_indexes", &self.global_indexes)
             .finish()
     }
@@ -113,6 +128,19 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync + 'static> ModuleMiddleware for Meter
         })
     }
 
+    /// The initial limit is compiled in the module.  Cost functions can't
+    /// be told apart, so the fingerprint is unique unless one was given
+    /// with [`Metering::with_fingerprint`].
+    fn fingerprint(&self) -> String {
+        match &self.cost_function_fingerprint {
+            Some(cost_function) => format!(
+                "Metering(initial_limit={}, cost_function={})",
+                self.initial_limit, cost_function
+            ),
+            None => unique_fingerprint(),
+        }
+    }
+
     /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
     fn transform_module_info(&self, module_info: &mut ModuleInfo) {
         let mut global_indexes = self.global_indexes.lock().unwrap();
@@ -412,4 +440,25 @@ mod tests {
             MeteringPoints::Remaining(4)
         );
     }
+
+    #[test]
+    fn fingerprint_needs_a_described_cost_function() {
+        let metering = Metering::new(10, cost_function);
+        assert_ne!(metering.fingerprint(), metering.fingerprint());
+
+        let metering = Metering::new(10, cost_function).with_fingerprint("cost_function");
+        assert_eq!(metering.fingerprint(), metering.fingerprint());
+        assert_ne!(
+            metering.fingerprint(),
+            Metering::new(20, cost_function)
+                .with_fingerprint("cost_function")
+                .fingerprint()
+        );
+        assert_ne!(
+            metering.fingerprint(),
+            Metering::new(10, |_: &Operator| 1)
+                .with_fingerprint("one")
+                .fingerprint()
+        );
+    }
 }
@@ -1,10 +1,12 @@
 use std::any::Any;
+use std::collections::HashSet;
+use std::fmt;
 use std::ptr;
-use std::sync::atomic;
+use std::sync::{atomic, Arc, Mutex};
 
 /// This type does not do reference counting automatically, reference counting can be done with
 /// [`Self::ref_clone`] and [`Self::ref_drop`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct VMExternRef(*const VMExternRefInner);
 
@@ -47,6 +49,29 @@ impl VMExternRef {
         Self(Box::into_raw(Box::new(VMExternRefInner::new::<T>(value))))
     }
 
+    /// Make a new extern reference, allocated in `heap`.
+    pub fn new_in<T>(heap: &ExternRefHeap, value: T) -> Self
+    where
+        T: Any + Send + Sync + 'static + Sized,
+    {
+        heap.insert(Self::new(value))
+    }
+
+    /// Make a new extern reference, allocated in `heap`, whose references
+    /// are traced by the collector of `heap`.
+    pub fn new_traced_in<T>(heap: &ExternRefHeap, value: T) -> Self
+    where
+        T: Trace + Any + Send + Sync + 'static + Sized,
+    {
+        let mut inner = VMExternRefInner::new::<T>(value);
+        inner.trace = Some(|data, tracer| {
+            if let Some(data) = data.downcast_ref::<T>() {
+                data.trace(tracer);
+            }
+        });
+        heap.insert(Self(Box::into_raw(Box::new(inner))))
+    }
+
     /// Try to downcast to the given value
     pub fn downcast<T>(&self) -> Option<&T>
     where
@@ -130,30 +155,76 @@ impl VMExternRef {
                     ref_inner.decrement_and_drop()
                 };
                 if should_drop {
-                    let _ = Box::from_raw(self.0 as *mut VMExternRefInner);
+                    let ref_inner = Box::from_raw(self.0 as *mut VMExternRefInner);
+                    if let Some(heap) = &ref_inner.heap {
+                        heap.remove(*self);
+                    }
                 }
             }
         }
     }
 
-    #[allow(dead_code)]
     /// Get the number of strong references to this data.
-    fn strong_count(&self) -> usize {
+    pub fn strong_count(&self) -> usize {
         if self.0.is_null() {
             0
         } else {
             unsafe { (&*self.0).strong.load(atomic::Ordering::SeqCst) }
         }
     }
+
+    /// Reports the references held by the data to `tracer`, if the data
+    /// was allocated with [`Self::new_traced_in`].
+    pub fn trace(&self, tracer: &mut dyn Tracer) {
+        if self.0.is_null() {
+            return;
+        }
+        let ref_inner = unsafe { &*self.0 };
+        if let Some(trace) = ref_inner.trace {
+            trace(ref_inner.data.as_ref(), tracer);
+        }
+    }
+
+    /// Drops the data, leaving this reference without any. Used by the
+    /// collector to break cycles of references.
+    ///
+    /// # Safety
+    ///
+    /// No reference to the data, such as one returned by
+    /// [`Self::downcast`], may be alive.
+    pub unsafe fn drop_data(&self) {
+        if self.0.is_null() {
+            return;
+        }
+        let ref_inner = self.0 as *mut VMExternRefInner;
+        (*ref_inner).trace = None;
+        let data = ptr::replace(&mut (*ref_inner).data, Box::new(()));
+        drop(data);
+    }
 }
 
-#[derive(Debug)]
+/// A function reporting the references held by the data of an externref.
+type TraceFn = fn(&(dyn Any + Send + Sync + 'static), &mut dyn Tracer);
+
 #[repr(C)]
 pub(crate) struct VMExternRefInner {
     strong: atomic::AtomicUsize,
     /// Do something obviously correct to get started. This can "easily" be improved
     /// to be an inline allocation later as the logic is fully encapsulated.
     data: Box<dyn Any + Send + Sync + 'static>,
+    /// The heap this reference was allocated in, if any.
+    heap: Option<ExternRefHeap>,
+    /// Traces the references held by `data`, if its type implements [`Trace`].
+    trace: Option<TraceFn>,
+}
+
+impl fmt::Debug for VMExternRefInner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VMExternRefInner")
+            .field("strong", &self.strong)
+            .field("data", &self.data)
+            .finish()
+    }
 }
 
 impl VMExternRefInner {
@@ -164,7 +235,28 @@ impl VMExternRefInner {
         Self {
             strong: atomic::AtomicUsize::new(1),
             data: Box::new(value),
+            heap: None,
+            trace: None,
+        }
+    }
+
+    /// Increments the reference count, unless it is zero because the
+    /// reference is being dropped.
+    /// Returns whether the count was incremented.
+    fn increment_if_live(&self) -> bool {
+        let mut count = self.strong.load(atomic::Ordering::Relaxed);
+        while count != 0 {
+            match self.strong.compare_exchange_weak(
+                count,
+                count + 1,
+                atomic::Ordering::Acquire,
+                atomic::Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(current) => count = current,
+            }
         }
+        false
     }
 
     /// Increments the reference count.
@@ -263,6 +355,31 @@ impl ExternRef {
         }
     }
 
+    #[cfg(feature = "experimental-reference-types-extern-ref")]
+    /// Make a new extern reference, allocated in `heap`.
+    pub fn new_in<T>(heap: &ExternRefHeap, value: T) -> Self
+    where
+        T: Any + Send + Sync + 'static + Sized,
+    {
+        Self {
+            inner: VMExternRef::new_in(heap, value),
+        }
+    }
+
+    #[cfg(feature = "experimental-reference-types-extern-ref")]
+    /// Make a new extern reference, allocated in `heap`, whose references
+    /// are traced by the collector of `heap`.
+    ///
+    /// Cycles of references going through `value` can then be collected.
+    pub fn new_traced_in<T>(heap: &ExternRefHeap, value: T) -> Self
+    where
+        T: Trace + Any + Send + Sync + 'static + Sized,
+    {
+        Self {
+            inner: VMExternRef::new_traced_in(heap, value),
+        }
+    }
+
     #[cfg(feature = "experimental-reference-types-extern-ref")]
     /// Try to downcast to the given value
     pub fn downcast<T>(&self) -> Option<&T>
@@ -279,6 +396,20 @@ impl ExternRef {
     }
 }
 
+impl ExternRef {
+    /// Returns the underlying [`VMExternRef`], without incrementing the
+    /// reference count.
+    pub fn vm_extern_ref(&self) -> VMExternRef {
+        self.inner
+    }
+}
+
+unsafe impl Trace for ExternRef {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        tracer.extern_ref(self.inner);
+    }
+}
+
 impl From<VMExternRef> for ExternRef {
     fn from(other: VMExternRef) -> Self {
         Self { inner: other }
@@ -293,3 +424,101 @@ impl From<ExternRef> for VMExternRef {
         out
     }
 }
+
+/// The data of an [`ExternRef`] that holds references to WebAssembly
+/// objects, such as other `ExternRef`s or tables, and reports them to
+/// the collector of its heap.
+///
+/// # Safety
+///
+/// [`Trace::trace`] must report each reference owned by `self` at most
+/// once, and must not report references it doesn't own, including ones
+/// shared through an `Arc`: the collector would free data that is still
+/// in use. References that aren't reported are always kept alive.
+pub unsafe trait Trace {
+    /// Reports the references owned by `self` to `tracer`.
+    fn trace(&self, tracer: &mut dyn Tracer);
+}
+
+/// Receives the references reported by [`Trace::trace`].
+pub trait Tracer {
+    /// Receives a reference to an externref.
+    fn extern_ref(&mut self, extern_ref: VMExternRef);
+
+    /// Receives a reference to a runtime object, such as the `VMTable`
+    /// of a table.
+    fn object(&mut self, object: &dyn Any);
+}
+
+unsafe impl<T: Trace> Trace for Option<T> {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        if let Some(value) = self {
+            value.trace(tracer);
+        }
+    }
+}
+
+unsafe impl<T: Trace + ?Sized> Trace for Box<T> {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        self.as_ref().trace(tracer);
+    }
+}
+
+unsafe impl<T: Trace> Trace for Vec<T> {
+    fn trace(&self, tracer: &mut dyn Tracer) {
+        for value in self {
+            value.trace(tracer);
+        }
+    }
+}
+
+/// The externrefs allocated in a store.
+///
+/// The heap doesn't own its externrefs, which are still freed when their
+/// reference count drops to zero. It keeps track of them to report how
+/// many are live, and to collect the ones that are only kept alive by
+/// cycles of references.
+#[derive(Debug, Clone, Default)]
+pub struct ExternRefHeap {
+    live: Arc<Mutex<HashSet<VMExternRef>>>,
+}
+
+// The heap only stores the addresses of its externrefs, which are
+// reference counted atomically.
+unsafe impl Send for ExternRefHeap {}
+unsafe impl Sync for ExternRefHeap {}
+
+impl ExternRefHeap {
+    /// Creates an empty heap.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of live externrefs allocated in this heap.
+    pub fn live_count(&self) -> usize {
+        self.live.lock().unwrap().len()
+    }
+
+    /// Returns the live externrefs allocated in this heap.
+    pub fn extern_refs(&self) -> Vec<ExternRef> {
+        let live = self.live.lock().unwrap();
+        live.iter()
+            // The externrefs are only removed from the heap once their
+            // count dropped to zero, while we hold the lock.
+            .filter(|extern_ref| unsafe { (&*extern_ref.0).increment_if_live() })
+            .map(|extern_ref| ExternRef::from(*extern_ref))
+            .collect()
+    }
+
+    fn insert(&self, extern_ref: VMExternRef) -> VMExternRef {
+        unsafe {
+            (*(extern_ref.0 as *mut VMExternRefInner)).heap = Some(self.clone());
+        }
+        self.live.lock().unwrap().insert(extern_ref);
+        extern_ref
+    }
+
+    fn remove(&self, extern_ref: VMExternRef) {
+        self.live.lock().unwrap().remove(&extern_ref);
+    }
+}
@@ -115,6 +115,22 @@ entity_impl!(MemoryIndex);
 #[cfg(feature = "enable-rkyv")]
 entity_impl!(ArchivedMemoryIndex);
 
+/// Index type of an exception tag inside the WebAssembly module.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, MemoryUsage)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "enable-rkyv",
+    derive(RkyvSerialize, RkyvDeserialize, Archive)
+)]
+#[cfg_attr(
+    feature = "enable-rkyv",
+    archive(derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug))
+)]
+pub struct TagIndex(u32);
+entity_impl!(TagIndex);
+#[cfg(feature = "enable-rkyv")]
+entity_impl!(ArchivedTagIndex);
+
 /// Index type of a signature (imported or local) inside the WebAssembly module.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, MemoryUsage)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -199,6 +215,8 @@ pub enum ExportIndex {
     Memory(MemoryIndex),
     /// Global export.
     Global(GlobalIndex),
+    /// Exception tag export.
+    Tag(TagIndex),
 }
 
 /// An entity to import.
@@ -221,4 +239,6 @@ pub enum ImportIndex {
     Memory(MemoryIndex),
     /// Global import.
     Global(GlobalIndex),
+    /// Exception tag import.
+    Tag(TagIndex),
 }
@@ -5,15 +5,22 @@ use std::env;
 fn main() {
     println!("cargo:rerun-if-changed=src/trap/handlers.c");
 
-    cc::Build::new()
-        .warnings(true)
-        .define(
-            &format!(
-                "CFG_TARGET_OS_{}",
-                env::var("CARGO_CFG_TARGET_OS").unwrap().to_uppercase()
-            ),
-            None,
-        )
-        .file("src/trap/handlers.c")
-        .compile("handlers");
+    let mut build = cc::Build::new();
+    build.warnings(true).define(
+        &format!(
+            "CFG_TARGET_OS_{}",
+            env::var("CARGO_CFG_TARGET_OS").unwrap().to_uppercase()
+        ),
+        None,
+    );
+    match env::var("CARGO_CFG_TARGET_ENV") {
+        Ok(target_env) if !target_env.is_empty() => {
+            build.define(
+                &format!("CFG_TARGET_ENV_{}", target_env.to_uppercase()),
+                None,
+            );
+        }
+        _ => (),
+    }
+    build.file("src/trap/handlers.c").compile("handlers");
 }
@@ -0,0 +1,323 @@
+//! Collection of the cycles of externrefs of an [`ExternRefHeap`].
+//!
+//! Externrefs are reference counted, which frees them as soon as they
+//! aren't referenced anymore, unless they are part of a cycle: a table
+//! holding an externref whose data holds the table, for example.
+//!
+//! The collector finds these cycles with trial deletion. Starting from the
+//! externrefs of the heap, it builds the graph of the references it knows
+//! about: the ones reported by the data of the externrefs (see
+//! [`Trace`][wasmer_types::Trace]), the elements of tables and the values of
+//! globals, and the tables and globals defined by instances. The references
+//! of an object which aren't explained by the graph come from outside of
+//! it, making the object a root. The externrefs that can't be reached from
+//! a root are garbage: dropping their data breaks the cycles, which are
+//! then freed by reference counting.
+
+use crate::global::Global;
+use crate::instance::InstanceRef;
+use crate::table::{Table, TableElement};
+use crate::{VMFunction, VMGlobal, VMMemory, VMTable};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasmer_types::{ExternRef, ExternRefHeap, Tracer, Type, VMExternRef};
+
+/// An object of the graph of references, holding a reference to keep it
+/// alive while collecting.
+enum Object {
+    ExternRef(ExternRef),
+    Table(Arc<dyn Table>),
+    Global(Arc<Global>),
+    Instance(InstanceRef),
+}
+
+impl Object {
+    /// The number of references to the object, including ours.
+    fn strong_count(&self) -> usize {
+        match self {
+            Self::ExternRef(extern_ref) => extern_ref.vm_extern_ref().strong_count(),
+            Self::Table(table) => Arc::strong_count(table),
+            Self::Global(global) => Arc::strong_count(global),
+            Self::Instance(instance) => instance.strong_count(),
+        }
+    }
+}
+
+/// The graph of the references known to the collector.
+#[derive(Default)]
+struct Graph {
+    objects: Vec<Object>,
+    /// The index of each externref.
+    extern_refs: HashMap<VMExternRef, usize>,
+    /// The index of each other object, by address.
+    indices: HashMap<usize, usize>,
+    /// The objects referenced by each object, once per reference.
+    references: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn push(&mut self, object: Object) -> usize {
+        self.objects.push(object);
+        self.references.push(Vec::new());
+        self.objects.len() - 1
+    }
+
+    fn add_extern_ref(&mut self, extern_ref: ExternRef) {
+        let key = extern_ref.vm_extern_ref();
+        let index = self.push(Object::ExternRef(extern_ref));
+        self.extern_refs.insert(key, index);
+    }
+
+    /// Adds `object`, unless the object at `address` is already in the
+    /// graph, and returns its index.
+    fn add(&mut self, address: usize, object: impl FnOnce() -> Object) -> usize {
+        if let Some(&index) = self.indices.get(&address) {
+            return index;
+        }
+        let index = self.push(object());
+        self.indices.insert(address, index);
+        index
+    }
+
+    fn add_table(&mut self, table: &Arc<dyn Table>) -> usize {
+        let address = Arc::as_ptr(table) as *const u8 as usize;
+        self.add(address, || Object::Table(table.clone()))
+    }
+
+    fn add_global(&mut self, global: &Arc<Global>) -> usize {
+        self.add(Arc::as_ptr(global) as usize, || {
+            Object::Global(global.clone())
+        })
+    }
+
+    fn add_instance(&mut self, instance: &InstanceRef) -> usize {
+        let address = instance.as_ref() as *const _ as usize;
+        self.add(address, || Object::Instance(instance.clone()))
+    }
+
+    /// The index of `extern_ref`, if it is allocated in the heap.
+    fn extern_ref_index(&self, extern_ref: VMExternRef) -> Option<usize> {
+        self.extern_refs.get(&extern_ref).copied()
+    }
+
+    /// Adds the references of the object at `index` to the graph, along
+    /// with the objects they reference.
+    fn scan(&mut self, index: usize) {
+        let mut references = Vec::new();
+        match &self.objects[index] {
+            Object::ExternRef(extern_ref) => {
+                let extern_ref = extern_ref.vm_extern_ref();
+                let mut tracer = GraphTracer {
+                    graph: self,
+                    references: &mut references,
+                };
+                extern_ref.trace(&mut tracer);
+            }
+            Object::Table(table) => {
+                if table.ty().ty == Type::ExternRef {
+                    for element in 0..table.size() {
+                        if let Some(TableElement::ExternRef(extern_ref)) = table.get(element) {
+                            references.extend(self.extern_ref_index(extern_ref.vm_extern_ref()));
+                        }
+                    }
+                }
+            }
+            Object::Global(global) => {
+                if global.ty().ty == Type::ExternRef {
+                    let extern_ref = unsafe { global.vmglobal().as_ref().to_externref() };
+                    references.extend(self.extern_ref_index(extern_ref));
+                }
+            }
+            Object::Instance(instance) => {
+                let instance = instance.clone();
+                for table in instance.as_ref().local_tables() {
+                    references.push(self.add_table(table));
+                }
+                for global in instance.as_ref().local_globals() {
+                    references.push(self.add_global(global));
+                }
+            }
+        }
+        self.references[index] = references;
+    }
+
+    /// Returns which objects are reachable from the objects that are
+    /// referenced from outside of the graph.
+    fn reachable(&self) -> Vec<bool> {
+        let mut unexplained: Vec<isize> = self
+            .objects
+            .iter()
+            // Don't count our own reference.
+            .map(|object| object.strong_count() as isize - 1)
+            .collect();
+        for &referenced in self.references.iter().flatten() {
+            unexplained[referenced] -= 1;
+        }
+
+        let mut reachable = vec![false; self.objects.len()];
+        let mut stack: Vec<usize> = (0..self.objects.len())
+            .filter(|&index| unexplained[index] != 0)
+            .collect();
+        while let Some(index) = stack.pop() {
+            if !reachable[index] {
+                reachable[index] = true;
+                stack.extend(&self.references[index]);
+            }
+        }
+        reachable
+    }
+}
+
+/// Adds the references reported by the data of an externref to the graph.
+struct GraphTracer<'a> {
+    graph: &'a mut Graph,
+    references: &'a mut Vec<usize>,
+}
+
+impl<'a> Tracer for GraphTracer<'a> {
+    fn extern_ref(&mut self, extern_ref: VMExternRef) {
+        self.references
+            .extend(self.graph.extern_ref_index(extern_ref));
+    }
+
+    fn object(&mut self, object: &dyn Any) {
+        let instance_ref = if let Some(table) = object.downcast_ref::<VMTable>() {
+            self.references.push(self.graph.add_table(&table.from));
+            &table.instance_ref
+        } else if let Some(global) = object.downcast_ref::<VMGlobal>() {
+            self.references.push(self.graph.add_global(&global.from));
+            &global.instance_ref
+        } else if let Some(memory) = object.downcast_ref::<VMMemory>() {
+            &memory.instance_ref
+        } else if let Some(function) = object.downcast_ref::<VMFunction>() {
+            &function.instance_ref
+        } else {
+            // The references of unknown objects aren't explained, keeping
+            // what they reference alive.
+            return;
+        };
+        // Weak references don't keep the instance alive.
+        if let Some(instance_ref) = instance_ref.as_ref().filter(|r| !r.is_weak()) {
+            self.references.push(self.graph.add_instance(instance_ref));
+        }
+    }
+}
+
+/// Collects the externrefs of `heap` which are only kept alive by cycles
+/// of references, and returns how many were collected.
+///
+/// The data of these externrefs is dropped, which frees them along with
+/// the tables and instances of their cycles.
+///
+/// # Safety
+///
+/// The collector relies on the reference counts of the externrefs, which
+/// code compiled by LLVM or Singlepass doesn't maintain: it must not run
+/// while such code is running, nor while another thread uses the objects
+/// of the heap.
+pub unsafe fn collect_extern_refs(heap: &ExternRefHeap) -> usize {
+    let mut graph = Graph::default();
+    for extern_ref in heap.extern_refs() {
+        graph.add_extern_ref(extern_ref);
+    }
+    let mut index = 0;
+    while index < graph.objects.len() {
+        graph.scan(index);
+        index += 1;
+    }
+
+    let reachable = graph.reachable();
+    let mut collected = 0;
+    for (object, reachable) in graph.objects.iter().zip(reachable) {
+        if let (Object::ExternRef(extern_ref), false) = (object, reachable) {
+            // Only the graph references the externref, and the graph
+            // doesn't access its data.
+            extern_ref.vm_extern_ref().drop_data();
+            collected += 1;
+        }
+    }
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::{LinearTable, TableStyle};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use wasmer_types::{TableType, Trace};
+
+    /// Host data holding a table, which sets `dropped` when dropped.
+    struct Holder {
+        table: VMTable,
+        dropped: Arc<AtomicBool>,
+    }
+
+    unsafe impl Trace for Holder {
+        fn trace(&self, tracer: &mut dyn Tracer) {
+            tracer.object(&self.table);
+        }
+    }
+
+    impl Drop for Holder {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn holder_in_its_table(heap: &ExternRefHeap) -> (ExternRef, Arc<AtomicBool>) {
+        let table_type = TableType::new(Type::ExternRef, 1, None);
+        let table: Arc<dyn Table> =
+            Arc::new(LinearTable::new(&table_type, &TableStyle::CallerChecksSignature).unwrap());
+        let dropped = Arc::new(AtomicBool::new(false));
+        let holder = Holder {
+            table: VMTable {
+                from: table.clone(),
+                instance_ref: None,
+            },
+            dropped: dropped.clone(),
+        };
+        let extern_ref: ExternRef = VMExternRef::new_traced_in(heap, holder).into();
+        table
+            .set(0, TableElement::ExternRef(extern_ref.clone()))
+            .unwrap();
+        (extern_ref, dropped)
+    }
+
+    #[test]
+    fn cycles_are_collected() {
+        let heap = ExternRefHeap::new();
+        let (extern_ref, dropped) = holder_in_its_table(&heap);
+        drop(extern_ref);
+        assert_eq!(heap.live_count(), 1);
+
+        assert_eq!(unsafe { collect_extern_refs(&heap) }, 1);
+        assert!(dropped.load(Ordering::SeqCst));
+        assert_eq!(heap.live_count(), 0);
+    }
+
+    #[test]
+    fn referenced_cycles_are_kept() {
+        let heap = ExternRefHeap::new();
+        let (extern_ref, dropped) = holder_in_its_table(&heap);
+
+        assert_eq!(unsafe { collect_extern_refs(&heap) }, 0);
+        assert!(!dropped.load(Ordering::SeqCst));
+        assert_eq!(heap.live_count(), 1);
+        assert_eq!(extern_ref.vm_extern_ref().strong_count(), 2);
+
+        drop(extern_ref);
+        assert_eq!(unsafe { collect_extern_refs(&heap) }, 1);
+    }
+
+    #[test]
+    fn untraced_extern_refs_are_kept() {
+        let heap = ExternRefHeap::new();
+        let extern_ref: ExternRef = VMExternRef::new_in(&heap, 7u32).into();
+
+        assert_eq!(unsafe { collect_extern_refs(&heap) }, 0);
+        assert_eq!(heap.live_count(), 1);
+        drop(extern_ref);
+        assert_eq!(heap.live_count(), 0);
+    }
+}
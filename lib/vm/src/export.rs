@@ -5,6 +5,7 @@ use crate::global::Global;
 use crate::instance::InstanceRef;
 use crate::memory::{Memory, MemoryStyle};
 use crate::table::{Table, TableStyle};
+use crate::trap::VMExceptionTag;
 use crate::vmcontext::{VMFunctionBody, VMFunctionEnvironment, VMFunctionKind, VMTrampoline};
 use loupe::MemoryUsage;
 use std::sync::Arc;
@@ -24,6 +25,9 @@ pub enum VMExtern {
 
     /// A global export value.
     Global(VMGlobal),
+
+    /// An exception tag export value.
+    Tag(VMExceptionTag),
 }
 
 /// A function export value.
@@ -149,6 +153,11 @@ impl VMMemory {
         self.from.style()
     }
 
+    /// Whether this exported memory is indexed with 64-bit addresses.
+    pub fn is_64(&self) -> bool {
+        self.from.is_64()
+    }
+
     /// Returns whether or not the two `VMMemory`s refer to the same Memory.
     pub fn same(&self, other: &Self) -> bool {
         Arc::ptr_eq(&self.from, &other.from)
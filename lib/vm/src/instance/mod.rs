@@ -19,7 +19,9 @@ use crate::global::Global;
 use crate::imports::Imports;
 use crate::memory::{Memory, MemoryError};
 use crate::table::{Table, TableElement};
-use crate::trap::{catch_traps, Trap, TrapCode, TrapHandler};
+use crate::trap::{
+    catch_traps, make_pending_tail_calls, TailCall, Trap, TrapCode, TrapHandler, VMExceptionTag,
+};
 use crate::vmcontext::{
     VMBuiltinFunctionsArray, VMCallerCheckedAnyfunc, VMContext, VMFunctionBody,
     VMFunctionEnvironment, VMFunctionImport, VMFunctionKind, VMGlobalDefinition, VMGlobalImport,
@@ -34,7 +36,7 @@ use more_asserts::assert_lt;
 use std::any::Any;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::ffi;
 use std::fmt;
 use std::mem;
@@ -43,9 +45,9 @@ use std::slice;
 use std::sync::Arc;
 use wasmer_types::entity::{packed_option::ReservedValue, BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{
-    DataIndex, DataInitializer, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit,
-    LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, Pages,
-    SignatureIndex, TableIndex, TableInitializer,
+    DataIndex, DataInitializer, ElemIndex, ExportIndex, Features, FunctionIndex, GlobalIndex,
+    GlobalInit, LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex,
+    MemoryIndex, Pages, SignatureIndex, TableIndex, TableInitializer, TagIndex,
 };
 
 /// The function pointer to call with data and an [`Instance`] pointer to
@@ -77,6 +79,9 @@ pub(crate) struct Instance {
     /// WebAssembly global data.
     globals: BoxedSlice<LocalGlobalIndex, Arc<Global>>,
 
+    /// WebAssembly exception tags, created for each instance.
+    tags: BoxedSlice<TagIndex, VMExceptionTag>,
+
     /// Pointers to functions in executable memory.
     functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
 
@@ -105,6 +110,10 @@ pub(crate) struct Instance {
     /// functions from other Wasm modules.
     imported_function_envs: BoxedSlice<FunctionIndex, ImportFunctionEnv>,
 
+    /// Whether the module was compiled with tail calls, and so makes the
+    /// pending tail calls after its calls.
+    tail_calls: bool,
+
     /// Additional context used by compiled WebAssembly code. This
     /// field is last, and represents a dynamically-sized array that
     /// extends beyond the nominal end of the struct (similar to a
@@ -425,7 +434,9 @@ impl Instance {
             catch_traps(trap_handler, || {
                 mem::transmute::<*const VMFunctionBody, unsafe extern "C" fn(VMFunctionEnvironment)>(
                     callee_address,
-                )(callee_vmctx)
+                )(callee_vmctx);
+                // The start function has no results.
+                make_pending_tail_calls(ptr::null_mut());
             })
         }
     }
@@ -729,9 +740,9 @@ impl Instance {
     pub(crate) fn local_memory_copy(
         &self,
         memory_index: LocalMemoryIndex,
-        dst: u32,
-        src: u32,
-        len: u32,
+        dst: u64,
+        src: u64,
+        len: u64,
     ) -> Result<(), Trap> {
         // https://webassembly.github.io/reference-types/core/exec/instructions.html#exec-memory-copy
 
@@ -744,9 +755,9 @@ impl Instance {
     pub(crate) fn imported_memory_copy(
         &self,
         memory_index: MemoryIndex,
-        dst: u32,
-        src: u32,
-        len: u32,
+        dst: u64,
+        src: u64,
+        len: u64,
     ) -> Result<(), Trap> {
         let import = self.imported_memory(memory_index);
         let memory = unsafe { import.definition.as_ref() };
@@ -754,6 +765,45 @@ impl Instance {
         unsafe { memory.memory_copy(dst, src, len) }
     }
 
+    /// Perform a `memory.copy` from the memory at `src_index` to the one at
+    /// `dst_index`, which may each be locally defined or imported.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error when the source or destination ranges are out of
+    /// bounds.
+    pub(crate) fn memory_copy_between(
+        &self,
+        dst_index: MemoryIndex,
+        src_index: MemoryIndex,
+        dst: u64,
+        src: u64,
+        len: u64,
+    ) -> Result<(), Trap> {
+        let dst_memory = self.get_memory(dst_index);
+        let src_memory = self.get_memory(src_index);
+        if src
+            .checked_add(len)
+            .map_or(true, |n| n > src_memory.current_length as u64)
+            || dst
+                .checked_add(len)
+                .map_or(true, |m| m > dst_memory.current_length as u64)
+        {
+            return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
+        }
+
+        // The ranges are in bounds, so they fit in a `usize`. The following
+        // memory copy is not synchronized and is not atomic:
+        unsafe {
+            ptr::copy(
+                src_memory.base.add(src as usize),
+                dst_memory.base.add(dst as usize),
+                len as usize,
+            );
+        }
+        Ok(())
+    }
+
     /// Perform the `memory.fill` operation on a locally defined memory.
     ///
     /// # Errors
@@ -762,9 +812,9 @@ impl Instance {
     pub(crate) fn local_memory_fill(
         &self,
         memory_index: LocalMemoryIndex,
-        dst: u32,
+        dst: u64,
         val: u32,
-        len: u32,
+        len: u64,
     ) -> Result<(), Trap> {
         let memory = self.memory(memory_index);
         // The following memory fill is not synchronized and is not atomic:
@@ -779,9 +829,9 @@ impl Instance {
     pub(crate) fn imported_memory_fill(
         &self,
         memory_index: MemoryIndex,
-        dst: u32,
+        dst: u64,
         val: u32,
-        len: u32,
+        len: u64,
     ) -> Result<(), Trap> {
         let import = self.imported_memory(memory_index);
         let memory = unsafe { import.definition.as_ref() };
@@ -800,7 +850,7 @@ impl Instance {
         &self,
         memory_index: MemoryIndex,
         data_index: DataIndex,
-        dst: u32,
+        dst: u64,
         src: u32,
         len: u32,
     ) -> Result<(), Trap> {
@@ -814,8 +864,8 @@ impl Instance {
             .checked_add(len)
             .map_or(true, |n| n as usize > data.len())
             || dst
-                .checked_add(len)
-                .map_or(true, |m| m > memory.current_length)
+                .checked_add(u64::from(len))
+                .map_or(true, |m| m > memory.current_length as u64)
         {
             return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
         }
@@ -852,11 +902,97 @@ impl Instance {
         self.tables[index].as_ref()
     }
 
+    /// Get the locally-defined tables.
+    pub(crate) fn local_tables(&self) -> impl Iterator<Item = &Arc<dyn Table>> {
+        self.tables.values()
+    }
+
+    /// Makes the locally-defined memories and tables which are still
+    /// used elsewhere own their definitions, before the instance holding
+    /// them is freed.
+    pub(crate) fn detach_definitions(&self) {
+        for memory in self.memories.values() {
+            if Arc::strong_count(memory) > 1 {
+                memory.detach_definition();
+            }
+        }
+        for table in self.tables.values() {
+            if Arc::strong_count(table) > 1 {
+                table.detach_definition();
+            }
+        }
+    }
+
+    /// Get the locally-defined globals.
+    pub(crate) fn local_globals(&self) -> impl Iterator<Item = &Arc<Global>> {
+        self.globals.values()
+    }
+
     /// Get an imported, foreign table.
     pub(crate) fn get_foreign_table(&self, index: TableIndex) -> &dyn Table {
         let import = self.imported_table(index);
         &*import.from
     }
+
+    /// Get an exception tag.
+    pub(crate) fn tag(&self, index: TagIndex) -> &VMExceptionTag {
+        &self.tags[index]
+    }
+
+    /// Whether the module of the instance was compiled with tail calls.
+    pub(crate) fn tail_calls(&self) -> bool {
+        self.tail_calls
+    }
+
+    /// The call of the function `index` made by a `return_call`, or by a
+    /// `call` in the body of a `try`.
+    pub(crate) fn direct_call(&self, index: FunctionIndex) -> TailCall {
+        let sig_index = self.module.functions[index];
+        unsafe { self.tail_call(&*self.funcrefs[index].0, sig_index) }
+    }
+
+    /// The call of the element `index` of the table `table_index` made by a
+    /// `return_call_indirect`, or by a `call_indirect` in the body of a
+    /// `try`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error when the element is out of bounds or null, or
+    /// when the function doesn't have the signature `sig_index`.
+    pub(crate) fn indirect_call(
+        &self,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        index: u32,
+    ) -> Result<TailCall, Trap> {
+        let funcref = match self.get_table(table_index).get(index) {
+            Some(TableElement::FuncRef(funcref)) => funcref,
+            Some(TableElement::ExternRef(_)) => unreachable!("indirect calls use funcref tables"),
+            None => return Err(Trap::lib(TrapCode::TableAccessOutOfBounds)),
+        };
+        if funcref.is_null() {
+            return Err(Trap::lib(TrapCode::IndirectCallToNull));
+        }
+        unsafe {
+            let callee = &**funcref;
+            if callee.type_index != *self.signature_ids_ptr().add(sig_index.index()) {
+                return Err(Trap::lib(TrapCode::BadSignature));
+            }
+            Ok(self.tail_call(callee, sig_index))
+        }
+    }
+
+    unsafe fn tail_call(
+        &self,
+        callee: &VMCallerCheckedAnyfunc,
+        sig_index: SignatureIndex,
+    ) -> TailCall {
+        TailCall::new(
+            self.function_call_trampolines[sig_index],
+            callee,
+            &self.module.signatures[sig_index],
+        )
+    }
 }
 
 /// A handle holding an `InstanceRef`, which holds an `Instance`
@@ -904,6 +1040,7 @@ impl InstanceHandle {
         imports: Imports,
         vmshared_signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
         func_data_registry: &FuncDataRegistry,
+        features: &Features,
         host_state: Box<dyn Any>,
         imported_function_envs: BoxedSlice<FunctionIndex, ImportFunctionEnv>,
     ) -> Result<Self, Trap> {
@@ -913,6 +1050,21 @@ impl InstanceHandle {
             .collect::<PrimaryMap<LocalGlobalIndex, _>>()
             .into_boxed_slice();
         let passive_data = RefCell::new(module.passive_data.clone());
+        // The imported tags come first, and every instance creates its own
+        // local tags.
+        let tags = imports
+            .tags
+            .values()
+            .cloned()
+            .chain(
+                module
+                    .tags
+                    .values()
+                    .skip(module.num_imported_tags)
+                    .map(|signature| VMExceptionTag::new(module.signatures[*signature].params())),
+            )
+            .collect::<PrimaryMap<TagIndex, _>>()
+            .into_boxed_slice();
 
         let handle = {
             let offsets = allocator.offsets().clone();
@@ -925,6 +1077,7 @@ impl InstanceHandle {
                 memories: finished_memories,
                 tables: finished_tables,
                 globals: finished_globals,
+                tags,
                 functions: finished_functions,
                 function_call_trampolines: finished_function_call_trampolines,
                 passive_elements: Default::default(),
@@ -932,6 +1085,7 @@ impl InstanceHandle {
                 host_state,
                 funcrefs,
                 imported_function_envs,
+                tail_calls: features.tail_call,
                 vmctx: VMContext {},
             };
 
@@ -1008,6 +1162,47 @@ impl InstanceHandle {
         &self.instance
     }
 
+    /// Creates a handle to the same `Instance` which doesn't keep it
+    /// alive, and whose exports don't either.
+    ///
+    /// Its methods panic once the `Instance` has been freed, which
+    /// [`InstanceHandle::is_alive`] tells.
+    pub fn downgrade(&self) -> Self {
+        Self {
+            instance: self.instance.downgrade(),
+        }
+    }
+
+    /// Drops the host environments of the imported functions of the
+    /// `Instance`, if it hasn't been freed yet, which frees it if they
+    /// were the only ones keeping it alive.
+    ///
+    /// # Safety
+    /// - The imported functions of the `Instance` which have a host
+    ///   environment must not be called anymore.
+    pub unsafe fn drop_host_envs(&self) {
+        let mut instance_ref = match self.instance.upgrade() {
+            Some(instance_ref) => instance_ref,
+            None => return,
+        };
+        let host_envs = instance_ref
+            .as_mut_unchecked()
+            .imported_function_envs
+            .values_mut()
+            .map(|host_env| mem::replace(host_env, ImportFunctionEnv::NoEnv))
+            .collect::<Vec<_>>();
+        // The host environments may hold the last other references to
+        // the `Instance`, which `instance_ref` outlives.
+        drop(host_envs);
+    }
+
+    /// Whether the `Instance` hasn't been freed yet, which is always
+    /// the case unless this handle comes from
+    /// [`InstanceHandle::downgrade`].
+    pub fn is_alive(&self) -> bool {
+        self.instance.is_alive()
+    }
+
     /// Finishes the instantiation process started by `Instance::new`.
     ///
     /// # Safety
@@ -1145,6 +1340,7 @@ impl InstanceHandle {
                 }
                 .into()
             }
+            ExportIndex::Tag(index) => VMExtern::Tag(instance_ref.tags[*index].clone()),
         }
     }
 
@@ -1268,11 +1464,18 @@ fn get_memory_init_start(init: &DataInitializer<'_>, instance: &Instance) -> usi
     let mut start = init.location.offset;
 
     if let Some(base) = init.location.base {
+        let memory64 = instance.module.memory64[init.location.memory_index];
         let val = unsafe {
-            if let Some(def_index) = instance.module.local_global_index(base) {
-                instance.global(def_index).to_u32()
+            let global = if let Some(def_index) = instance.module.local_global_index(base) {
+                instance.global(def_index)
             } else {
-                instance.imported_global(base).definition.as_ref().to_u32()
+                instance.imported_global(base).definition.as_ref().clone()
+            };
+            // The offset of a 64-bit memory is an `i64` global.
+            if memory64 {
+                global.to_u64()
+            } else {
+                u64::from(global.to_u32())
             }
         };
         start += usize::try_from(val).unwrap();
@@ -1296,7 +1499,7 @@ unsafe fn get_memory_slice<'instance>(
         let import = instance.imported_memory(init.location.memory_index);
         *import.definition.as_ref()
     };
-    slice::from_raw_parts_mut(memory.base, memory.current_length.try_into().unwrap())
+    slice::from_raw_parts_mut(memory.base, memory.current_length)
 }
 
 /// Compute the offset for a table element initializer.
@@ -1384,7 +1587,7 @@ fn initialize_memories(
         let start = get_memory_init_start(init, instance);
         if start
             .checked_add(init.data.len())
-            .map_or(true, |end| end > memory.current_length.try_into().unwrap())
+            .map_or(true, |end| end > memory.current_length)
         {
             return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
         }
@@ -3,7 +3,7 @@ use loupe::{MemoryUsage, MemoryUsageTracker};
 use std::alloc::Layout;
 use std::mem;
 use std::ptr::{self, NonNull};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 /// Dynamic instance allocation.
 ///
@@ -43,6 +43,9 @@ impl InstanceInner {
     unsafe fn deallocate_instance(&mut self) {
         let instance_ptr = self.instance.as_ptr();
 
+        // The memories and tables still used elsewhere must stop using
+        // the definitions stored in the instance.
+        self.as_ref().detach_definitions();
         ptr::drop_in_place(instance_ptr);
         std::alloc::dealloc(instance_ptr as *mut u8, self.instance_layout);
     }
@@ -109,8 +112,21 @@ impl MemoryUsage for InstanceInner {
 /// share an [`Instance`] between an [`InstanceHandle`] and the module
 /// exports, so that one can drop a [`InstanceHandle`] but still being
 /// able to use the exports properly.
-#[derive(Debug, PartialEq, Clone, MemoryUsage)]
-pub struct InstanceRef(Arc<InstanceInner>);
+///
+/// An `InstanceRef` can also be weak (see [`InstanceRef::downgrade`]):
+/// it doesn't keep the `Instance` alive then, which lets the host
+/// environments of an `Instance` hold its exports without creating a
+/// cycle of references. Its users check that the `Instance` is still
+/// alive with [`InstanceRef::upgrade`], which keeps it alive while the
+/// strong `InstanceRef` it returns is held.
+#[derive(Debug, Clone)]
+pub struct InstanceRef(InstanceRefInner);
+
+#[derive(Debug, Clone)]
+enum InstanceRefInner {
+    Strong(Arc<InstanceInner>),
+    Weak(Weak<InstanceInner>),
+}
 
 impl InstanceRef {
     /// Create a new `InstanceRef`. It allocates nothing. It fills
@@ -124,22 +140,89 @@ impl InstanceRef {
     /// [`InstanceAllocator`] for an example of how to correctly use
     /// this API.
     pub(super) unsafe fn new(instance: NonNull<Instance>, instance_layout: Layout) -> Self {
-        Self(Arc::new(InstanceInner {
+        Self(InstanceRefInner::Strong(Arc::new(InstanceInner {
             instance_layout,
             instance,
-        }))
+        })))
+    }
+
+    /// Creates a weak `InstanceRef` to the same `Instance`, which
+    /// doesn't keep it alive.
+    pub fn downgrade(&self) -> Self {
+        match &self.0 {
+            InstanceRefInner::Strong(inner) => Self(InstanceRefInner::Weak(Arc::downgrade(inner))),
+            InstanceRefInner::Weak(inner) => Self(InstanceRefInner::Weak(inner.clone())),
+        }
+    }
+
+    /// Creates a strong `InstanceRef` to the same `Instance`, unless it
+    /// has been freed.
+    pub fn upgrade(&self) -> Option<Self> {
+        match &self.0 {
+            InstanceRefInner::Strong(inner) => Some(Self(InstanceRefInner::Strong(inner.clone()))),
+            InstanceRefInner::Weak(inner) => Some(Self(InstanceRefInner::Strong(inner.upgrade()?))),
+        }
+    }
+
+    /// Whether this `InstanceRef` is weak.
+    pub fn is_weak(&self) -> bool {
+        matches!(self.0, InstanceRefInner::Weak(_))
+    }
+
+    /// Whether the `Instance` hasn't been freed yet, which is always
+    /// the case for a strong `InstanceRef`.
+    pub fn is_alive(&self) -> bool {
+        self.strong_count() > 0
+    }
+
+    /// Whether the module of the `Instance` was compiled with tail
+    /// calls.
+    ///
+    /// Its functions can then leave tail calls pending when they return,
+    /// which only the functions of such modules make.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Instance` has been freed.
+    pub fn tail_calls(&self) -> bool {
+        self.as_ref().tail_calls()
+    }
+
+    fn inner_ptr(&self) -> *const InstanceInner {
+        match &self.0 {
+            InstanceRefInner::Strong(inner) => Arc::as_ptr(inner),
+            InstanceRefInner::Weak(inner) => inner.as_ptr(),
+        }
     }
 
     /// Get a reference to the `Instance`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `InstanceRef` is weak and its `Instance` has been
+    /// freed.
     #[inline]
     pub(crate) fn as_ref(&self) -> &Instance {
-        (&*self.0).as_ref()
+        assert!(self.is_alive(), "the instance has been freed");
+        unsafe { (&*self.inner_ptr()).as_ref() }
     }
 
-    /// Only succeeds if ref count is 1.
+    /// Get the number of strong `InstanceRef`s to the `Instance`.
+    #[inline]
+    pub(crate) fn strong_count(&self) -> usize {
+        match &self.0 {
+            InstanceRefInner::Strong(inner) => Arc::strong_count(inner),
+            InstanceRefInner::Weak(inner) => inner.strong_count(),
+        }
+    }
+
+    /// Only succeeds if the `InstanceRef` is strong and its ref count is 1.
     #[inline]
     pub(super) fn as_mut(&mut self) -> Option<&mut Instance> {
-        Some(Arc::get_mut(&mut self.0)?.as_mut())
+        match &mut self.0 {
+            InstanceRefInner::Strong(inner) => Some(Arc::get_mut(inner)?.as_mut()),
+            InstanceRefInner::Weak(_) => None,
+        }
     }
 
     /// Like [`InstanceRef::as_mut`] but always succeeds.
@@ -151,7 +234,24 @@ impl InstanceRef {
     /// should be dereferenced for the lifetime of the returned `&mut Instance`.
     #[inline]
     pub(super) unsafe fn as_mut_unchecked(&mut self) -> &mut Instance {
-        let ptr: *mut InstanceInner = Arc::as_ptr(&self.0) as *mut _;
+        let ptr = self.inner_ptr() as *mut InstanceInner;
         (&mut *ptr).as_mut()
     }
 }
+
+impl PartialEq for InstanceRef {
+    /// Two `InstanceRef`s are equal if and only if they refer to the
+    /// same `Instance`.
+    fn eq(&self, other: &Self) -> bool {
+        self.inner_ptr() == other.inner_ptr()
+    }
+}
+
+impl MemoryUsage for InstanceRef {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        match &self.0 {
+            InstanceRefInner::Strong(inner) => inner.size_of_val(tracker),
+            InstanceRefInner::Weak(inner) => inner.size_of_val(tracker),
+        }
+    }
+}
@@ -21,6 +21,7 @@
     )
 )]
 
+mod collector;
 mod export;
 mod func_data_registry;
 mod global;
@@ -38,6 +39,7 @@ mod vmoffsets;
 
 pub mod libcalls;
 
+pub use crate::collector::collect_extern_refs;
 pub use crate::export::*;
 pub use crate::func_data_registry::{FuncDataRegistry, VMFuncRef};
 pub use crate::global::*;
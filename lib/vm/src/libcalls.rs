@@ -40,17 +40,23 @@
 use crate::func_data_registry::VMFuncRef;
 use crate::probestack::PROBESTACK;
 use crate::table::{RawTableElement, TableElement};
-use crate::trap::{raise_lib_trap, Trap, TrapCode};
+use crate::trap::{
+    catch_exceptions, make_pending_tail_calls, raise_lib_trap, raise_user_trap, with_exceptions,
+    with_tail_calls, TailCall, Trap, TrapCode, VMException,
+};
 use crate::vmcontext::VMContext;
 use crate::VMExternRef;
 use loupe::MemoryUsage;
 #[cfg(feature = "enable-rkyv")]
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 use std::fmt;
+use std::mem;
+use std::ptr;
 use wasmer_types::{
     DataIndex, ElemIndex, FunctionIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
-    TableIndex, Type,
+    SignatureIndex, TableIndex, TagIndex, Type,
 };
 
 /// Implementation of f32.ceil
@@ -212,6 +218,75 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_size(
     instance.imported_memory_size(memory_index).0
 }
 
+/// Implementation of memory.grow for locally-defined 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory64_grow(
+    vmctx: *mut VMContext,
+    delta: u64,
+    memory_index: u32,
+) -> u64 {
+    let instance = (&*vmctx).instance();
+    let memory_index = LocalMemoryIndex::from_u32(memory_index);
+
+    u32::try_from(delta)
+        .ok()
+        .and_then(|delta| instance.memory_grow(memory_index, delta).ok())
+        .map_or(u64::max_value(), |pages| pages.0.into())
+}
+
+/// Implementation of memory.grow for imported 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_imported_memory64_grow(
+    vmctx: *mut VMContext,
+    delta: u64,
+    memory_index: u32,
+) -> u64 {
+    let instance = (&*vmctx).instance();
+    let memory_index = MemoryIndex::from_u32(memory_index);
+
+    u32::try_from(delta)
+        .ok()
+        .and_then(|delta| instance.imported_memory_grow(memory_index, delta).ok())
+        .map_or(u64::max_value(), |pages| pages.0.into())
+}
+
+/// Implementation of memory.size for locally-defined 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory64_size(vmctx: *mut VMContext, memory_index: u32) -> u64 {
+    let instance = (&*vmctx).instance();
+    let memory_index = LocalMemoryIndex::from_u32(memory_index);
+
+    instance.memory_size(memory_index).0.into()
+}
+
+/// Implementation of memory.size for imported 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_imported_memory64_size(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+) -> u64 {
+    let instance = (&*vmctx).instance();
+    let memory_index = MemoryIndex::from_u32(memory_index);
+
+    instance.imported_memory_size(memory_index).0.into()
+}
+
 /// Implementation of `table.copy`.
 ///
 /// # Safety
@@ -547,7 +622,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_copy(
     let result = {
         let memory_index = LocalMemoryIndex::from_u32(memory_index);
         let instance = (&*vmctx).instance();
-        instance.local_memory_copy(memory_index, dst, src, len)
+        instance.local_memory_copy(memory_index, dst.into(), src.into(), len.into())
     };
     if let Err(trap) = result {
         raise_lib_trap(trap);
@@ -570,7 +645,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_copy(
     let result = {
         let memory_index = MemoryIndex::from_u32(memory_index);
         let instance = (&*vmctx).instance();
-        instance.imported_memory_copy(memory_index, dst, src, len)
+        instance.imported_memory_copy(memory_index, dst.into(), src.into(), len.into())
     };
     if let Err(trap) = result {
         raise_lib_trap(trap);
@@ -593,7 +668,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_fill(
     let result = {
         let memory_index = LocalMemoryIndex::from_u32(memory_index);
         let instance = (&*vmctx).instance();
-        instance.local_memory_fill(memory_index, dst, val, len)
+        instance.local_memory_fill(memory_index, dst.into(), val, len.into())
     };
     if let Err(trap) = result {
         raise_lib_trap(trap);
@@ -616,7 +691,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_fill(
     let result = {
         let memory_index = MemoryIndex::from_u32(memory_index);
         let instance = (&*vmctx).instance();
-        instance.imported_memory_fill(memory_index, dst, val, len)
+        instance.imported_memory_fill(memory_index, dst.into(), val, len.into())
     };
     if let Err(trap) = result {
         raise_lib_trap(trap);
@@ -636,6 +711,123 @@ pub unsafe extern "C" fn wasmer_vm_memory32_init(
     dst: u32,
     src: u32,
     len: u32,
+) {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let data_index = DataIndex::from_u32(data_index);
+        let instance = (&*vmctx).instance();
+        instance.memory_init(memory_index, data_index, dst.into(), src, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Implementation of `memory.copy` for locally defined 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory64_copy(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u64,
+    src: u64,
+    len: u64,
+) {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_copy(memory_index, dst, src, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Implementation of `memory.copy` for imported 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_imported_memory64_copy(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u64,
+    src: u64,
+    len: u64,
+) {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_copy(memory_index, dst, src, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Implementation of `memory.fill` for locally defined 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory64_fill(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u64,
+    val: u32,
+    len: u64,
+) {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_fill(memory_index, dst, val, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Implementation of `memory.fill` for imported 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_imported_memory64_fill(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u64,
+    val: u32,
+    len: u64,
+) {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_fill(memory_index, dst, val, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Implementation of `memory.init` for 64-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory64_init(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    data_index: u32,
+    dst: u64,
+    src: u32,
+    len: u32,
 ) {
     let result = {
         let memory_index = MemoryIndex::from_u32(memory_index);
@@ -648,6 +840,63 @@ pub unsafe extern "C" fn wasmer_vm_memory32_init(
     }
 }
 
+/// Implementation of `memory.copy` between two different 32-bit memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory32_copy_between(
+    vmctx: *mut VMContext,
+    dst_memory_index: u32,
+    src_memory_index: u32,
+    dst: u32,
+    src: u32,
+    len: u32,
+) {
+    let result = {
+        let dst_memory_index = MemoryIndex::from_u32(dst_memory_index);
+        let src_memory_index = MemoryIndex::from_u32(src_memory_index);
+        let instance = (&*vmctx).instance();
+        instance.memory_copy_between(
+            dst_memory_index,
+            src_memory_index,
+            dst.into(),
+            src.into(),
+            len.into(),
+        )
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Implementation of `memory.copy` between two different memories, at
+/// least one of which is a 64-bit memory.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory64_copy_between(
+    vmctx: *mut VMContext,
+    dst_memory_index: u32,
+    src_memory_index: u32,
+    dst: u64,
+    src: u64,
+    len: u64,
+) {
+    let result = {
+        let dst_memory_index = MemoryIndex::from_u32(dst_memory_index);
+        let src_memory_index = MemoryIndex::from_u32(src_memory_index);
+        let instance = (&*vmctx).instance();
+        instance.memory_copy_between(dst_memory_index, src_memory_index, dst, src, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
 /// Implementation of `data.drop`.
 ///
 /// # Safety
@@ -660,6 +909,228 @@ pub unsafe extern "C" fn wasmer_vm_data_drop(vmctx: *mut VMContext, data_index:
     instance.data_drop(data_index)
 }
 
+/// Implementation of `throw`.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `payload` must point to one
+/// 16-byte slot per parameter of the tag.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_throw(
+    vmctx: *mut VMContext,
+    tag_index: u32,
+    payload: *const u128,
+) {
+    let tag_index = TagIndex::from_u32(tag_index);
+    let instance = (&*vmctx).instance();
+    let tag = instance.tag(tag_index).clone();
+    // The slots are only guaranteed to be 8-byte aligned.
+    let payload = (0..tag.params().len())
+        .map(|index| ptr::read_unaligned(payload.add(index)))
+        .collect();
+    with_exceptions(|exceptions| exceptions.throw(VMException::new(tag, payload)));
+}
+
+/// Raises the pending exception, which no `try` of the function caught, to
+/// the innermost call catching exceptions.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, aka `wasmer_call` or
+/// `wasmer_call_trampoline` must have been previously called.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_exception_raise(_vmctx: *mut VMContext) -> ! {
+    let exception = with_exceptions(|exceptions| exceptions.take_pending());
+    raise_user_trap(Box::new(exception.expect("no exception is pending")))
+}
+
+/// Checks whether the pending exception has the tag `tag_index`, for a
+/// `catch` clause.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_exception_matches(vmctx: *mut VMContext, tag_index: u32) -> u32 {
+    let tag_index = TagIndex::from_u32(tag_index);
+    let instance = (&*vmctx).instance();
+    let tag = instance.tag(tag_index);
+    with_exceptions(|exceptions| exceptions.pending_has_tag(tag) as u32)
+}
+
+/// Catches the pending exception, copies its payload to `payload` unless
+/// it's null, and returns the index of the caught exception.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, and `payload` must be
+/// null or point to one 16-byte slot per value of the payload.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_exception_catch(
+    _vmctx: *mut VMContext,
+    payload: *mut u128,
+) -> u32 {
+    with_exceptions(|exceptions| {
+        let index = exceptions.catch();
+        if !payload.is_null() {
+            let values = exceptions.caught(index).payload();
+            ptr::copy_nonoverlapping(
+                values.as_ptr() as *const u8,
+                payload as *mut u8,
+                mem::size_of_val(values),
+            );
+        }
+        index
+    })
+}
+
+/// Implementation of `rethrow`.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, aka `wasmer_call` or
+/// `wasmer_call_trampoline` must have been previously called.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_exception_rethrow(_vmctx: *mut VMContext, index: u32) {
+    with_exceptions(|exceptions| exceptions.rethrow(index))
+}
+
+/// Releases a caught exception at the end of its `catch` clause.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, aka `wasmer_call` or
+/// `wasmer_call_trampoline` must have been previously called.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_exception_release(_vmctx: *mut VMContext, index: u32) {
+    with_exceptions(|exceptions| exceptions.release(index))
+}
+
+/// Implementation of `return_call`.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `args` must point to one 16-byte
+/// slot per parameter of the function.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_return_call(
+    vmctx: *mut VMContext,
+    function_index: u32,
+    args: *const u128,
+) {
+    let function_index = FunctionIndex::from_u32(function_index);
+    let instance = (&*vmctx).instance();
+    let call = instance.direct_call(function_index);
+    with_tail_calls(|tail_calls| tail_calls.call(call, args));
+}
+
+/// Implementation of `return_call_indirect`.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `args` must point to one 16-byte
+/// slot per parameter of the signature.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_return_call_indirect(
+    vmctx: *mut VMContext,
+    table_index: u32,
+    sig_index: u32,
+    elem_index: u32,
+    args: *const u128,
+) {
+    let result = {
+        let table_index = TableIndex::from_u32(table_index);
+        let sig_index = SignatureIndex::from_u32(sig_index);
+        let instance = (&*vmctx).instance();
+        instance.indirect_call(table_index, sig_index, elem_index)
+    };
+    match result {
+        Ok(call) => with_tail_calls(|tail_calls| tail_calls.call(call, args)),
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of a `call` in the body of a `try`: calls the function
+/// `function_index` with the values at `values`, and returns whether it
+/// raised an exception, which is then pending.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `values` must point to one 16-byte
+/// slot per parameter and per result of the function.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_try_call(
+    vmctx: *mut VMContext,
+    function_index: u32,
+    values: *mut u128,
+) -> u32 {
+    let function_index = FunctionIndex::from_u32(function_index);
+    let instance = (&*vmctx).instance();
+    let call = instance.direct_call(function_index);
+    try_call(&call, values) as u32
+}
+
+/// Implementation of a `call_indirect` in the body of a `try`: calls the
+/// element `elem_index` of the table `table_index` with the values at
+/// `values`, and returns whether it raised an exception, which is then
+/// pending.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `values` must point to one 16-byte
+/// slot per parameter and per result of the signature.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_try_call_indirect(
+    vmctx: *mut VMContext,
+    table_index: u32,
+    sig_index: u32,
+    elem_index: u32,
+    values: *mut u128,
+) -> u32 {
+    let result = {
+        let table_index = TableIndex::from_u32(table_index);
+        let sig_index = SignatureIndex::from_u32(sig_index);
+        let instance = (&*vmctx).instance();
+        instance.indirect_call(table_index, sig_index, elem_index)
+    };
+    match result {
+        Ok(call) => try_call(&call, values) as u32,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Makes `call`, and the tail calls it leaves pending, catching the
+/// exception they raise.
+unsafe fn try_call(call: &TailCall, values: *mut u128) -> bool {
+    catch_exceptions(|| {
+        call.call_with(values as *mut u8);
+        make_pending_tail_calls(values as *mut u8);
+    })
+}
+
+/// Checks whether a tail call is pending after a call.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, aka `wasmer_call` or
+/// `wasmer_call_trampoline` must have been previously called.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_tail_call_pending(_vmctx: *mut VMContext) -> u32 {
+    with_tail_calls(|tail_calls| tail_calls.is_pending() as u32)
+}
+
+/// Makes the pending tail calls, and writes the results of the last one
+/// to `results`.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, and `results` must
+/// point to one 16-byte slot per result of the function which was called.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_tail_call(_vmctx: *mut VMContext, results: *mut u128) {
+    make_pending_tail_calls(results as *mut u8)
+}
+
 /// Implementation for raising a trap
 ///
 /// # Safety
@@ -720,6 +1191,12 @@ pub enum LibCall {
     /// memory.size for imported functions
     ImportedMemory32Size,
 
+    /// memory.size for local 64-bit memories
+    Memory64Size,
+
+    /// memory.size for imported 64-bit memories
+    ImportedMemory64Size,
+
     /// table.copy
     TableCopy,
 
@@ -774,9 +1251,66 @@ pub enum LibCall {
     /// memory.init
     Memory32Init,
 
+    /// memory.copy for local 64-bit memories
+    Memory64Copy,
+
+    /// memory.copy for imported 64-bit memories
+    ImportedMemory64Copy,
+
+    /// memory.fill for local 64-bit memories
+    Memory64Fill,
+
+    /// memory.fill for imported 64-bit memories
+    ImportedMemory64Fill,
+
+    /// memory.init for 64-bit memories
+    Memory64Init,
+
+    /// memory.copy between two different 32-bit memories
+    Memory32CopyBetween,
+
+    /// memory.copy between two different memories, one of them 64-bit
+    Memory64CopyBetween,
+
     /// data.drop
     DataDrop,
 
+    /// throw
+    Throw,
+
+    /// raise the pending exception to the caller
+    ExceptionRaise,
+
+    /// check the tag of the pending exception
+    ExceptionMatches,
+
+    /// catch the pending exception
+    ExceptionCatch,
+
+    /// rethrow
+    ExceptionRethrow,
+
+    /// release a caught exception
+    ExceptionRelease,
+
+    /// return_call
+    ReturnCall,
+
+    /// return_call_indirect
+    ReturnCallIndirect,
+
+    /// a call in the body of a try
+    TryCall,
+
+    /// a call_indirect in the body of a try
+    TryCallIndirect,
+
+    /// check for a pending tail call after a call
+    TailCallPending,
+
+    /// make the pending tail calls
+    TailCall,
+
     /// A custom trap
     RaiseTrap,
 
@@ -799,6 +1333,8 @@ impl LibCall {
             Self::TruncF64 => wasmer_vm_f64_trunc as usize,
             Self::Memory32Size => wasmer_vm_memory32_size as usize,
             Self::ImportedMemory32Size => wasmer_vm_imported_memory32_size as usize,
+            Self::Memory64Size => wasmer_vm_memory64_size as usize,
+            Self::ImportedMemory64Size => wasmer_vm_imported_memory64_size as usize,
             Self::TableCopy => wasmer_vm_table_copy as usize,
             Self::TableInit => wasmer_vm_table_init as usize,
             Self::TableFill => wasmer_vm_table_fill as usize,
@@ -817,7 +1353,26 @@ impl LibCall {
             Self::Memory32Fill => wasmer_vm_memory32_fill as usize,
             Self::ImportedMemory32Fill => wasmer_vm_memory32_fill as usize,
             Self::Memory32Init => wasmer_vm_memory32_init as usize,
+            Self::Memory64Copy => wasmer_vm_memory64_copy as usize,
+            Self::ImportedMemory64Copy => wasmer_vm_imported_memory64_copy as usize,
+            Self::Memory64Fill => wasmer_vm_memory64_fill as usize,
+            Self::ImportedMemory64Fill => wasmer_vm_imported_memory64_fill as usize,
+            Self::Memory64Init => wasmer_vm_memory64_init as usize,
+            Self::Memory32CopyBetween => wasmer_vm_memory32_copy_between as usize,
+            Self::Memory64CopyBetween => wasmer_vm_memory64_copy_between as usize,
             Self::DataDrop => wasmer_vm_data_drop as usize,
+            Self::Throw => wasmer_vm_throw as usize,
+            Self::ExceptionRaise => wasmer_vm_exception_raise as usize,
+            Self::ExceptionMatches => wasmer_vm_exception_matches as usize,
+            Self::ExceptionCatch => wasmer_vm_exception_catch as usize,
+            Self::ExceptionRethrow => wasmer_vm_exception_rethrow as usize,
+            Self::ExceptionRelease => wasmer_vm_exception_release as usize,
+            Self::ReturnCall => wasmer_vm_return_call as usize,
+            Self::ReturnCallIndirect => wasmer_vm_return_call_indirect as usize,
+            Self::TryCall => wasmer_vm_try_call as usize,
+            Self::TryCallIndirect => wasmer_vm_try_call_indirect as usize,
+            Self::TailCallPending => wasmer_vm_tail_call_pending as usize,
+            Self::TailCall => wasmer_vm_tail_call as usize,
             Self::Probestack => wasmer_vm_probestack as usize,
             Self::RaiseTrap => wasmer_vm_raise_trap as usize,
         }
@@ -836,6 +1391,8 @@ impl LibCall {
             Self::TruncF64 => "wasmer_vm_f64_trunc",
             Self::Memory32Size => "wasmer_vm_memory32_size",
             Self::ImportedMemory32Size => "wasmer_vm_imported_memory32_size",
+            Self::Memory64Size => "wasmer_vm_memory64_size",
+            Self::ImportedMemory64Size => "wasmer_vm_imported_memory64_size",
             Self::TableCopy => "wasmer_vm_table_copy",
             Self::TableInit => "wasmer_vm_table_init",
             Self::TableFill => "wasmer_vm_table_fill",
@@ -854,7 +1411,26 @@ impl LibCall {
             Self::Memory32Fill => "wasmer_vm_memory32_fill",
             Self::ImportedMemory32Fill => "wasmer_vm_imported_memory32_fill",
             Self::Memory32Init => "wasmer_vm_memory32_init",
+            Self::Memory64Copy => "wasmer_vm_memory64_copy",
+            Self::ImportedMemory64Copy => "wasmer_vm_imported_memory64_copy",
+            Self::Memory64Fill => "wasmer_vm_memory64_fill",
+            Self::ImportedMemory64Fill => "wasmer_vm_imported_memory64_fill",
+            Self::Memory64Init => "wasmer_vm_memory64_init",
+            Self::Memory32CopyBetween => "wasmer_vm_memory32_copy_between",
+            Self::Memory64CopyBetween => "wasmer_vm_memory64_copy_between",
             Self::DataDrop => "wasmer_vm_data_drop",
+            Self::Throw => "wasmer_vm_throw",
+            Self::ExceptionRaise => "wasmer_vm_exception_raise",
+            Self::ExceptionMatches => "wasmer_vm_exception_matches",
+            Self::ExceptionCatch => "wasmer_vm_exception_catch",
+            Self::ExceptionRethrow => "wasmer_vm_exception_rethrow",
+            Self::ExceptionRelease => "wasmer_vm_exception_release",
+            Self::ReturnCall => "wasmer_vm_return_call",
+            Self::ReturnCallIndirect => "wasmer_vm_return_call_indirect",
+            Self::TryCall => "wasmer_vm_try_call",
+            Self::TryCallIndirect => "wasmer_vm_try_call_indirect",
+            Self::TailCallPending => "wasmer_vm_tail_call_pending",
+            Self::TailCall => "wasmer_vm_tail_call",
             Self::RaiseTrap => "wasmer_vm_raise_trap",
             // We have to do this because macOS requires a leading `_` and it's not
             // a normal function, it's a static variable, so we have to do it manually.
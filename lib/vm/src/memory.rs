@@ -19,7 +19,7 @@ use std::fmt;
 use std::ptr::NonNull;
 use std::sync::Mutex;
 use thiserror::Error;
-use wasmer_types::{Bytes, MemoryType, Pages};
+use wasmer_types::{Bytes, MemoryType, Pages, WASM64_MAX_PAGES};
 
 /// Error type describing things that can go wrong when operating on Wasm Memories.
 #[derive(Error, Debug, Clone, PartialEq, Hash)]
@@ -120,6 +120,22 @@ pub trait Memory: fmt::Debug + Send + Sync + MemoryUsage {
     ///
     /// The pointer returned in [`VMMemoryDefinition`] must be valid for the lifetime of this memory.
     fn vmmemory(&self) -> NonNull<VMMemoryDefinition>;
+
+    /// Whether the memory is indexed with 64-bit addresses, as in the
+    /// [memory64 proposal].
+    ///
+    /// [memory64 proposal]: https://github.com/WebAssembly/memory64
+    fn is_64(&self) -> bool {
+        false
+    }
+
+    /// Called when the instance defining this memory is freed while the
+    /// memory is still in use: a memory whose [`VMMemoryDefinition`] is
+    /// owned by the instance must take ownership of it.
+    ///
+    /// Memories wrapping another memory must forward this call, or the
+    /// wrapped memory keeps pointing into the freed instance.
+    fn detach_definition(&self);
 }
 
 /// A linear memory instance.
@@ -134,6 +150,9 @@ pub struct LinearMemory {
     /// The WebAssembly linear memory description.
     memory: MemoryType,
 
+    /// Whether the memory is indexed with 64-bit addresses.
+    memory64: bool,
+
     /// Our chosen implementation style.
     style: MemoryStyle,
 
@@ -141,9 +160,6 @@ pub struct LinearMemory {
     // constant offsets.
     offset_guard_size: usize,
 
-    /// The owned memory definition used by the generated code
-    vm_memory_definition: VMMemoryDefinitionOwnership,
-
     // Records whether we're using a bounds-checking strategy which requires
     // handlers to catch trapping accesses.
     pub(crate) needs_signal_handlers: bool,
@@ -180,6 +196,8 @@ struct WasmMmap {
     alloc: Mmap,
     // The current logical size in wasm pages of this linear memory.
     size: Pages,
+    /// The memory definition used by the generated code.
+    vm_memory_definition: VMMemoryDefinitionOwnership,
 }
 
 impl LinearMemory {
@@ -188,7 +206,13 @@ impl LinearMemory {
     /// This creates a `LinearMemory` with owned metadata: this can be used to create a memory
     /// that will be imported into Wasm modules.
     pub fn new(memory: &MemoryType, style: &MemoryStyle) -> Result<Self, MemoryError> {
-        unsafe { Self::new_internal(memory, style, None) }
+        unsafe { Self::new_internal(memory, style, false, None) }
+    }
+
+    /// Create a new linear memory instance indexed with 64-bit addresses,
+    /// as [`LinearMemory::new`] does a 32-bit one.
+    pub fn new64(memory: &MemoryType, style: &MemoryStyle) -> Result<Self, MemoryError> {
+        unsafe { Self::new_internal(memory, style, true, None) }
     }
 
     /// Create a new linear memory instance with specified minimum and maximum number of wasm pages.
@@ -203,27 +227,53 @@ impl LinearMemory {
         style: &MemoryStyle,
         vm_memory_location: NonNull<VMMemoryDefinition>,
     ) -> Result<Self, MemoryError> {
-        Self::new_internal(memory, style, Some(vm_memory_location))
+        Self::new_internal(memory, style, false, Some(vm_memory_location))
+    }
+
+    /// Create a new linear memory instance indexed with 64-bit addresses,
+    /// as [`LinearMemory::from_definition`] does a 32-bit one.
+    ///
+    /// # Safety
+    /// - `vm_memory_location` must point to a valid location in VM memory.
+    pub unsafe fn from_definition64(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        vm_memory_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Self, MemoryError> {
+        Self::new_internal(memory, style, true, Some(vm_memory_location))
+    }
+
+    /// The largest number of pages a memory of this index type can have,
+    /// whatever its maximum.
+    fn absolute_max(memory64: bool) -> Pages {
+        if memory64 {
+            Pages(WASM64_MAX_PAGES)
+        } else {
+            Pages::max_value()
+        }
     }
 
     /// Build a `LinearMemory` with either self-owned or VM owned metadata.
     unsafe fn new_internal(
         memory: &MemoryType,
         style: &MemoryStyle,
+        memory64: bool,
         vm_memory_location: Option<NonNull<VMMemoryDefinition>>,
     ) -> Result<Self, MemoryError> {
-        if memory.minimum > Pages::max_value() {
+        let absolute_max = Self::absolute_max(memory64);
+        if memory.minimum > absolute_max {
             return Err(MemoryError::MinimumMemoryTooLarge {
                 min_requested: memory.minimum,
-                max_allowed: Pages::max_value(),
+                max_allowed: absolute_max,
             });
         }
-        // `maximum` cannot be set to more than `65536` pages.
+        // `maximum` cannot be set to more than `65536` pages, or
+        // `WASM64_MAX_PAGES` for 64-bit memories.
         if let Some(max) = memory.maximum {
-            if max > Pages::max_value() {
+            if max > absolute_max {
                 return Err(MemoryError::MaximumMemoryTooLarge {
                     max_requested: max,
-                    max_allowed: Pages::max_value(),
+                    max_allowed: absolute_max,
                 });
             }
             if max < memory.minimum {
@@ -236,6 +286,16 @@ impl LinearMemory {
             }
         }
 
+        // Generated code relies on the static bound being larger than any
+        // 32-bit address, so 64-bit memories are always dynamic.
+        if memory64 {
+            if let MemoryStyle::Static { .. } = style {
+                return Err(MemoryError::InvalidMemory {
+                    reason: "64-bit memories must use the dynamic memory style".to_string(),
+                });
+            }
+        }
+
         let offset_guard_bytes = style.offset_guard_size() as usize;
 
         // If we have an offset guard, or if we're doing the static memory
@@ -259,51 +319,53 @@ impl LinearMemory {
         let mapped_pages = memory.minimum;
         let mapped_bytes = mapped_pages.bytes();
 
-        let mut mmap = WasmMmap {
-            alloc: Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
-                .map_err(MemoryError::Region)?,
+        let mut alloc = Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
+            .map_err(MemoryError::Region)?;
+        let base_ptr = alloc.as_mut_ptr();
+        let mem_length = memory.minimum.bytes().0;
+        let vm_memory_definition = if let Some(mem_loc) = vm_memory_location {
+            {
+                let mut ptr = mem_loc;
+                let md = ptr.as_mut();
+                md.base = base_ptr;
+                md.current_length = mem_length;
+            }
+            VMMemoryDefinitionOwnership::VMOwned(mem_loc)
+        } else {
+            VMMemoryDefinitionOwnership::HostOwned(Box::new(UnsafeCell::new(VMMemoryDefinition {
+                base: base_ptr,
+                current_length: mem_length,
+            })))
+        };
+        let mmap = WasmMmap {
+            alloc,
             size: memory.minimum,
+            vm_memory_definition,
         };
 
-        let base_ptr = mmap.alloc.as_mut_ptr();
-        let mem_length = memory.minimum.bytes().0.try_into().unwrap();
         Ok(Self {
             mmap: Mutex::new(mmap),
             maximum: memory.maximum,
             offset_guard_size: offset_guard_bytes,
             needs_signal_handlers,
-            vm_memory_definition: if let Some(mem_loc) = vm_memory_location {
-                {
-                    let mut ptr = mem_loc;
-                    let md = ptr.as_mut();
-                    md.base = base_ptr;
-                    md.current_length = mem_length;
-                }
-                VMMemoryDefinitionOwnership::VMOwned(mem_loc)
-            } else {
-                VMMemoryDefinitionOwnership::HostOwned(Box::new(UnsafeCell::new(
-                    VMMemoryDefinition {
-                        base: base_ptr,
-                        current_length: mem_length,
-                    },
-                )))
-            },
             memory: *memory,
+            memory64,
             style: style.clone(),
         })
     }
+}
 
+impl WasmMmap {
     /// Get the `VMMemoryDefinition`.
     ///
-    /// # Safety
-    /// - You must ensure that you have mutually exclusive access before calling
-    ///   this function. You can get this by locking the `mmap` mutex.
-    unsafe fn get_vm_memory_definition(&self) -> NonNull<VMMemoryDefinition> {
+    /// The definition is only accessed through the `mmap` mutex, which
+    /// guards the mapping along with it.
+    fn definition_ptr(&self) -> NonNull<VMMemoryDefinition> {
         match &self.vm_memory_definition {
             VMMemoryDefinitionOwnership::VMOwned(ptr) => *ptr,
-            VMMemoryDefinitionOwnership::HostOwned(boxed_ptr) => {
+            VMMemoryDefinitionOwnership::HostOwned(boxed_ptr) => unsafe {
                 NonNull::new_unchecked(boxed_ptr.get())
-            }
+            },
         }
     }
 }
@@ -325,9 +387,9 @@ impl Memory for LinearMemory {
 
     /// Returns the number of allocated wasm pages.
     fn size(&self) -> Pages {
-        // TODO: investigate this function for race conditions
+        let mmap_guard = self.mmap.lock().unwrap();
         unsafe {
-            let md_ptr = self.get_vm_memory_definition();
+            let md_ptr = mmap_guard.definition_ptr();
             let md = md_ptr.as_ref();
             Bytes::from(md.current_length).try_into().unwrap()
         }
@@ -347,7 +409,10 @@ impl Memory for LinearMemory {
 
         let new_pages = mmap
             .size
-            .checked_add(delta)
+            .0
+            .checked_add(delta.0)
+            .map(Pages)
+            .filter(|&pages| pages <= Self::absolute_max(self.memory64))
             .ok_or(MemoryError::CouldNotGrow {
                 current: mmap.size,
                 attempted_delta: delta,
@@ -365,8 +430,9 @@ impl Memory for LinearMemory {
 
         // Wasm linear memories are never allowed to grow beyond what is
         // indexable. If the memory has no maximum, enforce the greatest
-        // limit here.
-        if new_pages >= Pages::max_value() {
+        // limit here: generated code reads the size of 32-bit memories
+        // as a 32-bit number.
+        if !self.memory64 && new_pages >= Pages::max_value() {
             // Linear memory size would exceed the index range.
             return Err(MemoryError::CouldNotGrow {
                 current: mmap.size,
@@ -408,9 +474,9 @@ impl Memory for LinearMemory {
 
         // update memory definition
         unsafe {
-            let mut md_ptr = self.get_vm_memory_definition();
+            let mut md_ptr = mmap.definition_ptr();
             let md = md_ptr.as_mut();
-            md.current_length = new_pages.bytes().0.try_into().unwrap();
+            md.current_length = new_pages.bytes().0;
             md.base = mmap.alloc.as_mut_ptr() as _;
         }
 
@@ -419,7 +485,23 @@ impl Memory for LinearMemory {
 
     /// Return a `VMMemoryDefinition` for exposing the memory to compiled wasm code.
     fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
-        let _mmap_guard = self.mmap.lock().unwrap();
-        unsafe { self.get_vm_memory_definition() }
+        let mmap_guard = self.mmap.lock().unwrap();
+        mmap_guard.definition_ptr()
+    }
+
+    /// Whether the memory is indexed with 64-bit addresses.
+    fn is_64(&self) -> bool {
+        self.memory64
+    }
+
+    /// Copies the definition owned by the instance into one owned by this
+    /// memory, which the host keeps using once the instance is freed.
+    fn detach_definition(&self) {
+        let mut mmap_guard = self.mmap.lock().unwrap();
+        if let VMMemoryDefinitionOwnership::VMOwned(ptr) = mmap_guard.vm_memory_definition {
+            let definition = unsafe { *ptr.as_ref() };
+            mmap_guard.vm_memory_definition =
+                VMMemoryDefinitionOwnership::HostOwned(Box::new(UnsafeCell::new(definition)));
+        }
     }
 }
@@ -26,7 +26,7 @@ use wasmer_types::{
     CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, ExportType, ExternType, FunctionIndex,
     FunctionType, GlobalIndex, GlobalInit, GlobalType, ImportIndex, ImportType, LocalFunctionIndex,
     LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, MemoryType, SignatureIndex,
-    TableIndex, TableInitializer, TableType,
+    TableIndex, TableInitializer, TableType, TagIndex, TagType,
 };
 
 #[derive(Debug, Clone, MemoryUsage)]
@@ -109,9 +109,18 @@ pub struct ModuleInfo {
     /// WebAssembly linear memories (imported and local).
     pub memories: PrimaryMap<MemoryIndex, MemoryType>,
 
+    /// Whether each linear memory is indexed with 64-bit addresses, as in
+    /// the [memory64 proposal].
+    ///
+    /// [memory64 proposal]: https://github.com/WebAssembly/memory64
+    pub memory64: PrimaryMap<MemoryIndex, bool>,
+
     /// WebAssembly global variables (imported and local).
     pub globals: PrimaryMap<GlobalIndex, GlobalType>,
 
+    /// WebAssembly exception tags, with the signature of their payload.
+    pub tags: PrimaryMap<TagIndex, SignatureIndex>,
+
     /// Custom sections in the module.
     pub custom_sections: IndexMap<String, CustomSectionIndex>,
 
@@ -129,6 +138,9 @@ pub struct ModuleInfo {
 
     /// Number of imported globals in the module.
     pub num_imported_globals: usize,
+
+    /// Number of imported exception tags in the module.
+    pub num_imported_tags: usize,
 }
 
 /// Mirror version of ModuleInfo that can derive rkyv traits
@@ -148,13 +160,16 @@ pub struct ArchivableModuleInfo {
     functions: PrimaryMap<FunctionIndex, SignatureIndex>,
     tables: PrimaryMap<TableIndex, TableType>,
     memories: PrimaryMap<MemoryIndex, MemoryType>,
+    memory64: PrimaryMap<MemoryIndex, bool>,
     globals: PrimaryMap<GlobalIndex, GlobalType>,
+    tags: PrimaryMap<TagIndex, SignatureIndex>,
     custom_sections: ArchivableIndexMap<String, CustomSectionIndex>,
     custom_sections_data: PrimaryMap<CustomSectionIndex, Arc<[u8]>>,
     num_imported_functions: usize,
     num_imported_tables: usize,
     num_imported_memories: usize,
     num_imported_globals: usize,
+    num_imported_tags: usize,
 }
 
 #[cfg(feature = "enable-rkyv")]
@@ -174,13 +189,16 @@ impl From<ModuleInfo> for ArchivableModuleInfo {
             functions: it.functions,
             tables: it.tables,
             memories: it.memories,
+            memory64: it.memory64,
             globals: it.globals,
+            tags: it.tags,
             custom_sections: ArchivableIndexMap::from(it.custom_sections),
             custom_sections_data: it.custom_sections_data,
             num_imported_functions: it.num_imported_functions,
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            num_imported_tags: it.num_imported_tags,
         }
     }
 }
@@ -203,13 +221,16 @@ impl From<ArchivableModuleInfo> for ModuleInfo {
             functions: it.functions,
             tables: it.tables,
             memories: it.memories,
+            memory64: it.memory64,
             globals: it.globals,
+            tags: it.tags,
             custom_sections: it.custom_sections.into(),
             custom_sections_data: it.custom_sections_data,
             num_imported_functions: it.num_imported_functions,
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            num_imported_tags: it.num_imported_tags,
         }
     }
 }
@@ -265,13 +286,16 @@ impl PartialEq for ModuleInfo {
             && self.functions == other.functions
             && self.tables == other.tables
             && self.memories == other.memories
+            && self.memory64 == other.memory64
             && self.globals == other.globals
+            && self.tags == other.tags
             && self.custom_sections == other.custom_sections
             && self.custom_sections_data == other.custom_sections_data
             && self.num_imported_functions == other.num_imported_functions
             && self.num_imported_tables == other.num_imported_tables
             && self.num_imported_memories == other.num_imported_memories
             && self.num_imported_globals == other.num_imported_globals
+            && self.num_imported_tags == other.num_imported_tags
     }
 }
 
@@ -295,11 +319,14 @@ impl ModuleInfo {
             functions: PrimaryMap::new(),
             tables: PrimaryMap::new(),
             memories: PrimaryMap::new(),
+            memory64: PrimaryMap::new(),
             globals: PrimaryMap::new(),
+            tags: PrimaryMap::new(),
             num_imported_functions: 0,
             num_imported_tables: 0,
             num_imported_memories: 0,
             num_imported_globals: 0,
+            num_imported_tags: 0,
             custom_sections: IndexMap::new(),
             custom_sections_data: PrimaryMap::new(),
         }
@@ -346,6 +373,7 @@ impl ModuleInfo {
                     let global_type = self.globals.get(*i).unwrap();
                     ExternType::Global(*global_type)
                 }
+                ExportIndex::Tag(i) => ExternType::Tag(self.tag_type(*i)),
             };
             ExportType::new(name, extern_type)
         });
@@ -379,6 +407,7 @@ impl ModuleInfo {
                         let global_type = self.globals.get(*i).unwrap();
                         ExternType::Global(*global_type)
                     }
+                    ImportIndex::Tag(i) => ExternType::Tag(self.tag_type(*i)),
                 };
                 ImportType::new(module, field, extern_type)
             });
@@ -388,6 +417,11 @@ impl ModuleInfo {
         }
     }
 
+    /// Get the type of the exception tag `index`.
+    pub fn tag_type(&self, index: TagIndex) -> TagType {
+        TagType::new(self.signatures[self.tags[index]].params())
+    }
+
     /// Get the custom sections of the module given a `name`.
     pub fn custom_sections<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Arc<[u8]>> + 'a {
         self.custom_sections
@@ -475,6 +509,11 @@ impl ModuleInfo {
         index.index() < self.num_imported_globals
     }
 
+    /// Test whether the given tag index is for an imported tag.
+    pub fn is_imported_tag(&self, index: TagIndex) -> bool {
+        index.index() < self.num_imported_tags
+    }
+
     /// Get the Module name
     pub fn name(&self) -> String {
         match self.name {
@@ -544,6 +583,13 @@ impl<I: Iterator<Item = ExportType> + Sized> ExportsIterator<I> {
             _ => None,
         })
     }
+    /// Get only the exception tags
+    pub fn tags(self) -> impl Iterator<Item = ExportType<TagType>> + Sized {
+        self.iter.filter_map(|extern_| match extern_.ty() {
+            ExternType::Tag(ty) => Some(ExportType::new(extern_.name(), ty.clone())),
+            _ => None,
+        })
+    }
 }
 
 impl<I: Iterator<Item = ExportType> + Sized> Iterator for ExportsIterator<I> {
@@ -600,6 +646,17 @@ impl<I: Iterator<Item = ImportType> + Sized> ImportsIterator<I> {
             _ => None,
         })
     }
+    /// Get only the exception tags
+    pub fn tags(self) -> impl Iterator<Item = ImportType<TagType>> + Sized {
+        self.iter.filter_map(|extern_| match extern_.ty() {
+            ExternType::Tag(ty) => Some(ImportType::new(
+                extern_.module(),
+                extern_.name(),
+                ty.clone(),
+            )),
+            _ => None,
+        })
+    }
 }
 
 impl<I: Iterator<Item = ImportType> + Sized> Iterator for ImportsIterator<I> {
@@ -64,6 +64,14 @@ pub trait Table: fmt::Debug + Send + Sync + MemoryUsage {
     /// Return a `VMTableDefinition` for exposing the table to compiled wasm code.
     fn vmtable(&self) -> NonNull<VMTableDefinition>;
 
+    /// Called when the instance defining this table is freed while the
+    /// table is still in use: a table whose [`VMTableDefinition`] is owned
+    /// by the instance must take ownership of it.
+    ///
+    /// Tables wrapping another table must forward this call, or the
+    /// wrapped table keeps pointing into the freed instance.
+    fn detach_definition(&self);
+
     /// Copy `len` elements from `src_table[src_index..]` into `dst_table[dst_index..]`.
     ///
     /// # Errors
@@ -184,7 +192,9 @@ pub struct LinearTable {
     table: TableType,
     /// Our chosen implementation style.
     style: TableStyle,
-    vm_table_definition: VMTableDefinitionOwnership,
+    /// The table definition used by the generated code, locked after
+    /// `vec` when both are.
+    vm_table_definition: Mutex<VMTableDefinitionOwnership>,
 }
 
 /// A type to help manage who is responsible for the backing table of the
@@ -262,7 +272,7 @@ impl LinearTable {
                 maximum: table.maximum,
                 table: *table,
                 style: style.clone(),
-                vm_table_definition: if let Some(table_loc) = vm_table_location {
+                vm_table_definition: Mutex::new(if let Some(table_loc) = vm_table_location {
                     {
                         let mut ptr = table_loc;
                         let td = ptr.as_mut();
@@ -277,21 +287,35 @@ impl LinearTable {
                             current_elements: table_minimum as _,
                         },
                     )))
-                },
+                }),
             }),
         }
     }
 
     /// Get the `VMTableDefinition`.
     ///
-    /// # Safety
-    /// - You must ensure that you have mutually exclusive access before calling
-    ///   this function. You can get this by locking the `vec` mutex.
-    unsafe fn get_vm_table_definition(&self) -> NonNull<VMTableDefinition> {
-        match &self.vm_table_definition {
+    /// Writing to it requires mutually exclusive access, which you can get
+    /// by locking the `vec` mutex.
+    fn get_vm_table_definition(&self) -> NonNull<VMTableDefinition> {
+        match &*self.vm_table_definition.lock().unwrap() {
             VMTableDefinitionOwnership::VMOwned(ptr) => *ptr,
-            VMTableDefinitionOwnership::HostOwned(boxed_ptr) => {
+            VMTableDefinitionOwnership::HostOwned(boxed_ptr) => unsafe {
                 NonNull::new_unchecked(boxed_ptr.get())
+            },
+        }
+    }
+}
+
+impl Drop for LinearTable {
+    fn drop(&mut self) {
+        // Release the references held by the elements.
+        if self.table.ty == ValType::ExternRef {
+            let vec = match self.vec.get_mut() {
+                Ok(vec) => vec,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            for element in vec.iter_mut() {
+                unsafe { element.extern_ref.ref_drop() };
             }
         }
     }
@@ -310,12 +334,15 @@ impl Table for LinearTable {
 
     /// Returns the number of allocated elements.
     fn size(&self) -> u32 {
-        // TODO: investigate this function for race conditions
-        unsafe {
-            let td_ptr = self.get_vm_table_definition();
-            let td = td_ptr.as_ref();
-            td.current_elements
-        }
+        let ownership = self.vm_table_definition.lock().unwrap();
+        let td_ptr = match &*ownership {
+            VMTableDefinitionOwnership::VMOwned(ptr) => *ptr,
+            VMTableDefinitionOwnership::HostOwned(boxed_ptr) => unsafe {
+                NonNull::new_unchecked(boxed_ptr.get())
+            },
+        };
+        // The definition isn't freed while its ownership is locked.
+        unsafe { td_ptr.as_ref().current_elements }
     }
 
     /// Grow table by the specified amount of elements.
@@ -418,6 +445,49 @@ impl Table for LinearTable {
     /// Return a `VMTableDefinition` for exposing the table to compiled wasm code.
     fn vmtable(&self) -> NonNull<VMTableDefinition> {
         let _vec_guard = self.vec.lock().unwrap();
-        unsafe { self.get_vm_table_definition() }
+        self.get_vm_table_definition()
+    }
+
+    /// Copies the definition owned by the instance into one owned by this
+    /// table, which the host keeps using once the instance is freed.
+    fn detach_definition(&self) {
+        let _vec_guard = self.vec.lock().unwrap();
+        let mut ownership = self.vm_table_definition.lock().unwrap();
+        if let VMTableDefinitionOwnership::VMOwned(ptr) = *ownership {
+            let definition = unsafe { *ptr.as_ref() };
+            *ownership =
+                VMTableDefinitionOwnership::HostOwned(Box::new(UnsafeCell::new(definition)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detached_definition_outlives_the_vm_one() {
+        let ty = TableType::new(ValType::FuncRef, 1, None);
+        let mut definition = Box::new(VMTableDefinition {
+            base: std::ptr::null_mut(),
+            current_elements: 0,
+        });
+        let table = unsafe {
+            LinearTable::from_definition(
+                &ty,
+                &TableStyle::CallerChecksSignature,
+                NonNull::from(&mut *definition),
+            )
+        }
+        .unwrap();
+        assert_eq!(table.grow(2, TableElement::default()), Some(1));
+        assert_eq!(definition.current_elements, 3);
+
+        table.detach_definition();
+        definition.current_elements = 0;
+        drop(definition);
+        assert_eq!(table.size(), 3);
+        assert_eq!(table.grow(1, TableElement::default()), Some(3));
+        assert_eq!(unsafe { table.vmtable().as_ref() }.current_elements, 4);
     }
 }
@@ -0,0 +1,222 @@
+//! Exceptions of the WebAssembly exception handling proposal.
+//!
+//! A `throw` leaves the exception pending on the current call into
+//! WebAssembly, and branches to the handler of the innermost `try` of the
+//! function, which catches it. If no `try` does, the exception is raised
+//! like a trap, as a [`Trap::User`] holding the `VMException`, which is
+//! also how host functions throw exceptions.
+//!
+//! The trap unwinds the stack to the innermost call catching traps: the
+//! calls made in the body of a `try` catch the exceptions, through
+//! `catch_exceptions`, and make them pending again for the handler of the
+//! `try`, while the calls from the host return them with the other traps.
+//! The calls outside of a `try` thus don't check for exceptions at all.
+//!
+//! [`Trap::User`]: crate::Trap::User
+
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use wasmer_types::Type;
+
+/// The tag of an exception, which identifies it and gives the types of its
+/// payload.
+///
+/// Tags are compared by identity: every instance creates its own tags, and
+/// two tags with the same payload types are different tags.
+#[derive(Clone)]
+pub struct VMExceptionTag(Arc<[Type]>);
+
+impl VMExceptionTag {
+    /// Creates a new tag for exceptions carrying values of type `params`.
+    pub fn new(params: &[Type]) -> Self {
+        Self(params.into())
+    }
+
+    /// The types of the payload of the exceptions with this tag.
+    pub fn params(&self) -> &[Type] {
+        &self.0
+    }
+}
+
+impl PartialEq for VMExceptionTag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for VMExceptionTag {}
+
+impl fmt::Debug for VMExceptionTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VMExceptionTag")
+            .field(&Arc::as_ptr(&self.0))
+            .field(&self.params())
+            .finish()
+    }
+}
+
+impl MemoryUsage for VMExceptionTag {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self)
+            + if tracker.track(Arc::as_ptr(&self.0) as *const ()) {
+                mem::size_of_val(&*self.0)
+            } else {
+                0
+            }
+    }
+}
+
+/// A thrown exception: its tag and the raw values of its payload, one
+/// 16-byte slot per value, as in the arguments of a trampoline.
+#[derive(Clone, Debug)]
+pub struct VMException {
+    tag: VMExceptionTag,
+    payload: Box<[u128]>,
+}
+
+impl VMException {
+    /// Creates an exception with the tag `tag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `payload` doesn't have one value per parameter of `tag`.
+    pub fn new(tag: VMExceptionTag, payload: Vec<u128>) -> Self {
+        assert_eq!(
+            payload.len(),
+            tag.params().len(),
+            "the payload of an exception must have a value per parameter of its tag"
+        );
+        Self {
+            tag,
+            payload: payload.into_boxed_slice(),
+        }
+    }
+
+    /// The tag of the exception.
+    pub fn tag(&self) -> &VMExceptionTag {
+        &self.tag
+    }
+
+    /// The raw values of the payload of the exception.
+    pub fn payload(&self) -> &[u128] {
+        &self.payload
+    }
+}
+
+impl fmt::Display for VMException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "uncaught exception")
+    }
+}
+
+impl Error for VMException {}
+
+/// The exceptions of a call into WebAssembly.
+///
+/// Caught exceptions are kept until the end of the `catch` clause which
+/// caught them, so that it can rethrow them. They are referred to by their
+/// index, which is only valid in the call which caught them.
+#[derive(Default)]
+pub(crate) struct ExceptionState {
+    pending: Option<VMException>,
+    caught: Vec<Option<VMException>>,
+}
+
+impl ExceptionState {
+    /// Makes `exception` the pending exception.
+    pub(crate) fn throw(&mut self, exception: VMException) {
+        self.pending = Some(exception);
+    }
+
+    /// Takes the pending exception, if any.
+    pub(crate) fn take_pending(&mut self) -> Option<VMException> {
+        self.pending.take()
+    }
+
+    /// Whether the pending exception has the tag `tag`.
+    pub(crate) fn pending_has_tag(&self, tag: &VMExceptionTag) -> bool {
+        self.pending
+            .as_ref()
+            .map_or(false, |exception| exception.tag() == tag)
+    }
+
+    /// Catches the pending exception, and returns its index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no exception is pending.
+    pub(crate) fn catch(&mut self) -> u32 {
+        let exception = self.pending.take().expect("no exception is pending");
+        self.caught.push(Some(exception));
+        (self.caught.len() - 1) as u32
+    }
+
+    /// The caught exception at `index`.
+    pub(crate) fn caught(&self, index: u32) -> &VMException {
+        self.caught[index as usize]
+            .as_ref()
+            .expect("the exception was released")
+    }
+
+    /// Makes the caught exception at `index` pending again.
+    pub(crate) fn rethrow(&mut self, index: u32) {
+        self.pending = Some(self.caught(index).clone());
+    }
+
+    /// Releases the caught exception at `index`, at the end of the `catch`
+    /// clause which caught it.
+    pub(crate) fn release(&mut self, index: u32) {
+        if let Some(caught) = self.caught.get_mut(index as usize) {
+            *caught = None;
+        }
+        while let Some(None) = self.caught.last() {
+            self.caught.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_compared_by_identity() {
+        let tag = VMExceptionTag::new(&[Type::I32]);
+        assert_eq!(tag, tag.clone());
+        assert_ne!(tag, VMExceptionTag::new(&[Type::I32]));
+    }
+
+    #[test]
+    fn caught_exceptions_are_released() {
+        let tag = VMExceptionTag::new(&[Type::I64]);
+        let mut state = ExceptionState::default();
+        assert!(state.take_pending().is_none());
+
+        state.throw(VMException::new(tag.clone(), vec![42]));
+        assert!(state.pending_has_tag(&tag));
+        assert!(!state.pending_has_tag(&VMExceptionTag::new(&[Type::I64])));
+        let outer = state.catch();
+        assert!(!state.pending_has_tag(&tag));
+
+        state.throw(VMException::new(tag.clone(), vec![7]));
+        let inner = state.catch();
+        assert_eq!(state.caught(inner).payload(), &[7]);
+        state.rethrow(outer);
+        assert_eq!(state.take_pending().unwrap().payload(), &[42]);
+
+        // Releasing the outer exception first keeps the inner one.
+        state.release(outer);
+        assert_eq!(state.caught(inner).payload(), &[7]);
+        state.release(inner);
+        assert!(state.caught.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "a value per parameter")]
+    fn payload_matches_the_tag() {
+        VMException::new(VMExceptionTag::new(&[Type::I32, Type::F64]), vec![1]);
+    }
+}
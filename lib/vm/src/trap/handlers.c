@@ -1,7 +1,23 @@
 // This file contains partial code from other sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
+// The ucontext routines are only declared with `_XOPEN_SOURCE` on macOS.
+#if defined(CFG_TARGET_OS_MACOS)
+#define _XOPEN_SOURCE 600
+#endif
+
+// The ucontext routines used to switch stacks are missing from musl.
+#if defined(CFG_TARGET_OS_MACOS) || \
+    (defined(CFG_TARGET_OS_LINUX) && defined(CFG_TARGET_ENV_GNU))
+#define WASMER_SWITCH_STACK
+#endif
+
 #include <setjmp.h>
+#include <stddef.h>
+#include <stdint.h>
+#if defined(WASMER_SWITCH_STACK)
+#include <ucontext.h>
+#endif
 
 // Note that `sigsetjmp` and `siglongjmp` are used here where possible to
 // explicitly pass a 0 argument to `sigsetjmp` that we don't need to preserve
@@ -35,3 +51,43 @@ void wasmer_unwind(void *JmpBuf) {
   platform_jmp_buf *buf = (platform_jmp_buf*) JmpBuf;
   platform_longjmp(*buf, 1);
 }
+
+#if defined(WASMER_SWITCH_STACK)
+// `makecontext` only passes `int` arguments, so the pointer to the call is
+// split in two halves.
+struct wasmer_stack_call {
+  void (*body)(void*);
+  void *payload;
+};
+
+static void wasmer_stack_entry(unsigned int high, unsigned int low) {
+  struct wasmer_stack_call *call =
+      (struct wasmer_stack_call*) (uintptr_t) (((uint64_t) high << 32) | low);
+  call->body(call->payload);
+}
+
+// Runs `body(payload)` on the stack `[stack, stack + stack_size)`, and
+// returns 0 once it's done, or -1 if the stack couldn't be switched.
+int wasmer_switch_stack(
+    void *stack,
+    size_t stack_size,
+    void (*body)(void*),
+    void *payload) {
+  struct wasmer_stack_call call = { body, payload };
+  uint64_t address = (uint64_t) (uintptr_t) &call;
+  ucontext_t caller;
+  ucontext_t callee;
+  if (getcontext(&callee) != 0) {
+    return -1;
+  }
+  callee.uc_stack.ss_sp = stack;
+  callee.uc_stack.ss_size = stack_size;
+  callee.uc_link = &caller;
+  makecontext(&callee, (void (*)(void)) wasmer_stack_entry, 2,
+              (unsigned int) (address >> 32), (unsigned int) address);
+  if (swapcontext(&caller, &callee) != 0) {
+    return -1;
+  }
+  return 0;
+}
+#endif
@@ -3,10 +3,18 @@
 
 //! This is the module that facilitates the usage of Traps
 //! in Wasmer Runtime
+mod exception;
+mod stack;
+mod tail_call;
 mod trapcode;
 mod traphandlers;
 
+pub use exception::{VMException, VMExceptionTag};
+pub use stack::{StackConfig, DEFAULT_DEDICATED_STACK_SIZE};
+
+pub(crate) use tail_call::{make_pending_tail_calls, TailCall};
 pub use trapcode::TrapCode;
+pub(crate) use traphandlers::{catch_exceptions, with_exceptions, with_tail_calls};
 pub use traphandlers::{
     catch_traps, catch_traps_with_result, raise_lib_trap, raise_user_trap, wasmer_call_trampoline,
     TlsRestore, Trap, TrapHandler, TrapHandlerFn,
@@ -0,0 +1,298 @@
+//! Limits on the stack used by WebAssembly code.
+//!
+//! The limit is enforced with guard pages rather than with checks in the
+//! generated code, so that it's the same for every compiler: when the
+//! guest uses more stack than allowed, it touches a guard page, and the
+//! trap handler reports a [`TrapCode::StackOverflow`].
+//!
+//! [`TrapCode::StackOverflow`]: crate::TrapCode::StackOverflow
+
+use loupe::MemoryUsage;
+
+/// The size of a dedicated stack when [`StackConfig::max_size`] isn't set.
+///
+/// It matches the usual size of the main thread stack.
+pub const DEFAULT_DEDICATED_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// How much stack the guest can use, and which stack it runs on.
+///
+/// The stack used by host functions called by the guest counts in the
+/// limit too. The limit is only enforced on Unix, and applies to the
+/// outermost call into WebAssembly of each thread. Dedicated stacks are
+/// only available on Linux with glibc and on macOS: elsewhere, the guest
+/// runs on the stack of the host thread instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, MemoryUsage)]
+pub struct StackConfig {
+    /// The maximum size, in bytes, of the stack used by the guest. When
+    /// unset, the guest uses whatever the host thread has left.
+    ///
+    /// When the host thread has less stack left than that, the guest
+    /// runs on a dedicated stack instead, so that the limit doesn't
+    /// depend on the thread the guest is called from.
+    pub max_size: Option<usize>,
+
+    /// Whether the guest always runs on a stack allocated for it, of
+    /// `max_size` bytes, rather than on the stack of the host thread.
+    ///
+    /// The stack is allocated once per thread, and reused by the next
+    /// calls.
+    pub dedicated: bool,
+}
+
+impl StackConfig {
+    /// A configuration where the guest can use up to `max_size` bytes
+    /// of the stack of the host thread.
+    pub fn with_max_size(max_size: usize) -> Self {
+        Self {
+            max_size: Some(max_size),
+            dedicated: false,
+        }
+    }
+
+    /// A configuration where the guest runs on a stack of `max_size`
+    /// bytes allocated for it.
+    pub fn dedicated(max_size: usize) -> Self {
+        Self {
+            max_size: Some(max_size),
+            dedicated: true,
+        }
+    }
+}
+
+#[cfg(unix)]
+pub(crate) use self::unix::{guest_stack_contains, with_stack_limit};
+
+#[cfg(not(unix))]
+pub(crate) unsafe fn with_stack_limit<R>(_config: StackConfig, f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{StackConfig, DEFAULT_DEDICATED_STACK_SIZE};
+    use crate::trap::traphandlers::thread_stack;
+    use std::cell::Cell;
+
+    thread_local! {
+        /// The range of addresses of the dedicated stack the guest is
+        /// running on, guard page included, or `(0, 0)`.
+        static GUEST_STACK: Cell<(usize, usize)> = Cell::new((0, 0));
+    }
+
+    /// Whether `addr` is in the dedicated stack the guest is running on,
+    /// or in its guard page.
+    ///
+    /// Called from the signal handler, so it doesn't allocate.
+    pub(crate) fn guest_stack_contains(addr: usize) -> bool {
+        GUEST_STACK
+            .try_with(|range| {
+                let (start, end) = range.get();
+                start <= addr && addr < end
+            })
+            .unwrap_or(false)
+    }
+
+    /// Runs `f`, which calls into WebAssembly, with the stack limited by
+    /// `config`.
+    pub(crate) unsafe fn with_stack_limit<R>(config: StackConfig, f: impl FnOnce() -> R) -> R {
+        if config.dedicated {
+            let size = config.max_size.unwrap_or(DEFAULT_DEDICATED_STACK_SIZE);
+            return on_dedicated_stack(size, f);
+        }
+        let max_size = match config.max_size {
+            Some(max_size) => max_size,
+            None => return f(),
+        };
+        match HostStackGuard::new(max_size) {
+            Some(_guard) => f(),
+            None => on_dedicated_stack(max_size, f),
+        }
+    }
+
+    /// A guard page protecting the stack of the host thread, at the
+    /// limit of the stack the guest can use.
+    struct HostStackGuard {
+        page: *mut libc::c_void,
+    }
+
+    impl HostStackGuard {
+        /// Protects the page `max_size` bytes below the current stack
+        /// pointer, if the host thread has that much stack left.
+        unsafe fn new(max_size: usize) -> Option<Self> {
+            let page_size = region::page::size();
+            let marker = 0u8;
+            let stack_pointer = &marker as *const u8 as usize;
+            let limit = stack_pointer.checked_sub(max_size)?;
+            let page = (limit & !(page_size - 1)).checked_sub(page_size)?;
+            let (stack_start, _) = thread_stack();
+            // The page above the guard of the thread must remain usable.
+            if page < stack_start + page_size {
+                return None;
+            }
+            // The page isn't mapped yet if the stack grows on demand, like
+            // the one of the main thread on Linux.
+            if libc::mprotect(page as *mut libc::c_void, page_size, libc::PROT_NONE) != 0 {
+                return None;
+            }
+            Some(Self {
+                page: page as *mut libc::c_void,
+            })
+        }
+    }
+
+    impl Drop for HostStackGuard {
+        fn drop(&mut self) {
+            let r = unsafe {
+                libc::mprotect(
+                    self.page,
+                    region::page::size(),
+                    libc::PROT_READ | libc::PROT_WRITE,
+                )
+            };
+            assert_eq!(r, 0, "unprotecting the stack of the host thread failed");
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", all(target_os = "linux", target_env = "gnu"))))]
+    use self::fallback::on_dedicated_stack;
+    #[cfg(any(target_os = "macos", all(target_os = "linux", target_env = "gnu")))]
+    use self::ucontext::on_dedicated_stack;
+
+    /// Switching stacks requires `makecontext`, which isn't available
+    /// everywhere, such as with musl: the guest runs on the stack of the
+    /// host thread then, without other limit than its size.
+    #[cfg(not(any(target_os = "macos", all(target_os = "linux", target_env = "gnu"))))]
+    mod fallback {
+        pub(super) unsafe fn on_dedicated_stack<R>(_size: usize, f: impl FnOnce() -> R) -> R {
+            f()
+        }
+    }
+
+    #[cfg(any(target_os = "macos", all(target_os = "linux", target_env = "gnu")))]
+    mod ucontext {
+        use super::GUEST_STACK;
+        use std::cell::RefCell;
+        use std::panic::{self, AssertUnwindSafe};
+        use std::ptr;
+
+        extern "C" {
+            fn wasmer_switch_stack(
+                stack: *mut u8,
+                stack_size: usize,
+                body: extern "C" fn(*mut u8),
+                payload: *mut u8,
+            ) -> i32;
+        }
+
+        thread_local! {
+            /// The dedicated stack of the thread, allocated on first use.
+            static DEDICATED_STACK: RefCell<Option<DedicatedStack>> = RefCell::new(None);
+        }
+
+        /// A stack allocated for the guest, with a guard page below it.
+        struct DedicatedStack {
+            mmap_ptr: *mut libc::c_void,
+            mmap_size: usize,
+            size: usize,
+        }
+
+        impl DedicatedStack {
+            fn new(size: usize) -> Self {
+                let page_size = region::page::size();
+                let size = (size + page_size - 1) & !(page_size - 1);
+                let mmap_size = size + page_size;
+                unsafe {
+                    let ptr = libc::mmap(
+                        ptr::null_mut(),
+                        mmap_size,
+                        libc::PROT_NONE,
+                        libc::MAP_PRIVATE | libc::MAP_ANON,
+                        -1,
+                        0,
+                    );
+                    assert!(
+                        ptr != libc::MAP_FAILED,
+                        "allocating a stack of {} bytes for the guest failed",
+                        size
+                    );
+                    let r = libc::mprotect(
+                        (ptr as usize + page_size) as *mut libc::c_void,
+                        size,
+                        libc::PROT_READ | libc::PROT_WRITE,
+                    );
+                    assert_eq!(r, 0, "mprotect to configure the guest stack failed");
+                    Self {
+                        mmap_ptr: ptr,
+                        mmap_size,
+                        size,
+                    }
+                }
+            }
+
+            /// The lowest address of the usable part of the stack.
+            fn base(&self) -> *mut u8 {
+                (self.mmap_ptr as usize + self.mmap_size - self.size) as *mut u8
+            }
+        }
+
+        impl Drop for DedicatedStack {
+            fn drop(&mut self) {
+                unsafe {
+                    let r = libc::munmap(self.mmap_ptr, self.mmap_size);
+                    debug_assert_eq!(r, 0, "munmap failed during thread shutdown");
+                }
+            }
+        }
+
+        /// Runs `f` on the dedicated stack of the thread, of `size` bytes.
+        pub(super) unsafe fn on_dedicated_stack<R>(size: usize, f: impl FnOnce() -> R) -> R {
+            let (base, size, range) = DEDICATED_STACK.with(|slot| {
+                let mut slot = slot.borrow_mut();
+                let page_size = region::page::size();
+                let rounded = (size + page_size - 1) & !(page_size - 1);
+                if slot.as_ref().map(|stack| stack.size) != Some(rounded) {
+                    *slot = Some(DedicatedStack::new(size));
+                }
+                let stack = slot.as_ref().unwrap();
+                let start = stack.mmap_ptr as usize;
+                (stack.base(), stack.size, (start, start + stack.mmap_size))
+            });
+
+            // Panics can't unwind across the stack switch: they are caught on
+            // the guest stack, and resumed on the host one.
+            let mut f = Some(f);
+            let mut result = None;
+            let mut call = || {
+                let f = f.take().unwrap();
+                result = Some(panic::catch_unwind(AssertUnwindSafe(f)));
+            };
+            let previous = GUEST_STACK.with(|guest_stack| guest_stack.replace(range));
+            let r = wasmer_switch_stack(
+                base,
+                size,
+                closure_trampoline(&call),
+                &mut call as *mut _ as *mut u8,
+            );
+            GUEST_STACK.with(|guest_stack| guest_stack.set(previous));
+            assert_eq!(r, 0, "switching to the guest stack failed");
+            match result.unwrap() {
+                Ok(result) => result,
+                Err(panic) => panic::resume_unwind(panic),
+            }
+        }
+
+        fn closure_trampoline<F>(_: &F) -> extern "C" fn(*mut u8)
+        where
+            F: FnMut(),
+        {
+            call_closure::<F>
+        }
+
+        extern "C" fn call_closure<F>(payload: *mut u8)
+        where
+            F: FnMut(),
+        {
+            unsafe { (*(payload as *mut F))() }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Tail calls of the tail-call proposal.
+//!
+//! A `return_call` doesn't call its callee: it leaves the call pending on
+//! the current call into WebAssembly and returns to its own caller, which
+//! makes the pending call before using its results. The compiled code does
+//! so after every call when the feature is enabled, and
+//! `wasmer_call_trampoline` after every call from the host. Since the
+//! function which made the tail call has returned by then, a chain of tail
+//! calls runs in constant stack space.
+//!
+//! Only the modules compiled with tail calls make the pending calls, so
+//! the imports of instances are only resolved between such modules.
+
+use super::traphandlers::with_tail_calls;
+use crate::vmcontext::{
+    VMCallerCheckedAnyfunc, VMFunctionBody, VMFunctionEnvironment, VMTrampoline,
+};
+use std::cell::UnsafeCell;
+use std::mem;
+use std::ptr;
+use wasmer_types::FunctionType;
+
+/// The most parameters or results a function can have, as limited by the
+/// validation of modules.
+const MAX_VALUES: usize = 1000;
+
+/// The slots of the values of a tail call, as in the arguments of a
+/// trampoline: its arguments, and then its results.
+type Values = UnsafeCell<[u128; MAX_VALUES]>;
+
+thread_local! {
+    /// The two buffers of the values of the tail calls of a thread.
+    ///
+    /// A pending call is put in the buffer its caller isn't using: the
+    /// caller writes its results to its own buffer when it returns, after
+    /// the call was left pending, which would overwrite its arguments.
+    static VALUES: Box<[Values; 2]> = Box::new([
+        UnsafeCell::new([0; MAX_VALUES]),
+        UnsafeCell::new([0; MAX_VALUES]),
+    ]);
+}
+
+/// The slots of the values in the buffer `buffer` of this thread.
+fn values(buffer: usize) -> *mut u128 {
+    VALUES.with(|values| values[buffer].get() as *mut u128)
+}
+
+/// A call made by a `return_call` or a `return_call_indirect`, or by a
+/// call in the body of a `try`, which must catch the exceptions it raises.
+pub(crate) struct TailCall {
+    trampoline: VMTrampoline,
+    callee: *const VMFunctionBody,
+    vmctx: VMFunctionEnvironment,
+    num_params: usize,
+    num_results: usize,
+    /// The buffer holding the values of the call, once it is pending.
+    buffer: usize,
+}
+
+impl TailCall {
+    /// Creates a call to `callee`, of type `signature`.
+    ///
+    /// # Safety
+    ///
+    /// `trampoline` must be the call trampoline of `signature`, which must
+    /// be the type of `callee`.
+    pub(crate) unsafe fn new(
+        trampoline: VMTrampoline,
+        callee: &VMCallerCheckedAnyfunc,
+        signature: &FunctionType,
+    ) -> Self {
+        let num_params = signature.params().len();
+        let num_results = signature.results().len();
+        assert!(
+            num_params.max(num_results) <= MAX_VALUES,
+            "too many values for a tail call"
+        );
+        Self {
+            trampoline,
+            callee: callee.func_ptr,
+            vmctx: callee.vmctx,
+            num_params,
+            num_results,
+            buffer: 0,
+        }
+    }
+
+    unsafe fn call(&self) {
+        self.call_with(values(self.buffer) as *mut u8)
+    }
+
+    /// Makes the call with the values at `values` rather than in its
+    /// buffer, for a call in the body of a `try`.
+    ///
+    /// # Safety
+    ///
+    /// `values` must point to one 16-byte slot per parameter and per result
+    /// of the call.
+    pub(crate) unsafe fn call_with(&self, values: *mut u8) {
+        mem::transmute::<_, extern "C" fn(VMFunctionEnvironment, *const VMFunctionBody, *mut u8)>(
+            self.trampoline,
+        )(self.vmctx, self.callee, values);
+    }
+}
+
+/// The pending tail call of a call into WebAssembly.
+#[derive(Default)]
+pub(crate) struct TailCallState {
+    pending: Option<TailCall>,
+    /// The buffer of the tail call being made, if any, whose results are
+    /// written to it once it returns.
+    in_flight: Option<usize>,
+}
+
+impl TailCallState {
+    /// Makes `call` the pending tail call, with the arguments read from
+    /// `args`.
+    ///
+    /// # Safety
+    ///
+    /// `args` must point to one 16-byte slot per parameter of the call.
+    pub(crate) unsafe fn call(&mut self, mut call: TailCall, args: *const u128) {
+        debug_assert!(self.pending.is_none(), "a tail call is already pending");
+        call.buffer = match self.in_flight {
+            Some(buffer) => 1 - buffer,
+            None => 0,
+        };
+        // The slots are only guaranteed to be 8-byte aligned.
+        ptr::copy_nonoverlapping(
+            args as *const u8,
+            values(call.buffer) as *mut u8,
+            call.num_params * mem::size_of::<u128>(),
+        );
+        self.pending = Some(call);
+    }
+
+    /// Takes the pending tail call, if any.
+    pub(crate) fn take_pending(&mut self) -> Option<TailCall> {
+        self.pending.take()
+    }
+
+    /// Whether a tail call is pending.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+/// Makes the pending tail call, and the ones it leaves pending in turn,
+/// and writes the results of the last one to `results`.
+///
+/// Does nothing if no tail call is pending.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, and `results` must
+/// point to one 16-byte slot per result of the function which made the
+/// first tail call.
+pub(crate) unsafe fn make_pending_tail_calls(results: *mut u8) {
+    let mut last = None;
+    while let Some(call) = with_tail_calls(TailCallState::take_pending) {
+        let caller = with_tail_calls(|tail_calls| tail_calls.in_flight.replace(call.buffer));
+        call.call();
+        with_tail_calls(|tail_calls| tail_calls.in_flight = caller);
+        last = Some(call);
+    }
+    if let Some(call) = last {
+        if call.num_results > 0 {
+            ptr::copy_nonoverlapping(
+                values(call.buffer) as *const u8,
+                results,
+                call.num_results * mem::size_of::<u128>(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vmcontext::{VMContext, VMSharedSignatureIndex};
+    use wasmer_types::Type;
+
+    unsafe extern "C" fn trampoline(_: *mut VMContext, _: *const VMFunctionBody, _: *mut u128) {}
+
+    #[test]
+    fn tail_calls_copy_their_arguments() {
+        let callee = VMCallerCheckedAnyfunc {
+            func_ptr: ptr::null(),
+            type_index: VMSharedSignatureIndex::new(0),
+            vmctx: VMFunctionEnvironment {
+                vmctx: ptr::null_mut(),
+            },
+        };
+        let signature = FunctionType::new(vec![Type::I32, Type::F64], vec![Type::I64; 3]);
+        let args = [7u128, 2.5f64.to_bits() as u128];
+        let call = unsafe { TailCall::new(trampoline, &callee, &signature) };
+        assert_eq!(call.num_params, 2);
+        assert_eq!(call.num_results, 3);
+
+        let mut state = TailCallState::default();
+        assert!(!state.is_pending());
+        unsafe { state.call(call, args.as_ptr()) };
+        assert!(state.is_pending());
+        let call = state.take_pending().unwrap();
+        assert!(!state.is_pending());
+        let values = unsafe { &*(values(call.buffer) as *const [u128; MAX_VALUES]) };
+        assert_eq!(values[..2], args);
+    }
+
+    #[test]
+    fn pending_calls_do_not_use_the_buffer_of_their_caller() {
+        let callee = VMCallerCheckedAnyfunc {
+            func_ptr: ptr::null(),
+            type_index: VMSharedSignatureIndex::new(0),
+            vmctx: VMFunctionEnvironment {
+                vmctx: ptr::null_mut(),
+            },
+        };
+        let signature = FunctionType::new(vec![Type::I32], vec![Type::I32]);
+        let mut state = TailCallState::default();
+        for in_flight in 0..2 {
+            state.in_flight = Some(in_flight);
+            let call = unsafe { TailCall::new(trampoline, &callee, &signature) };
+            unsafe { state.call(call, [1u128].as_ptr()) };
+            assert_eq!(state.take_pending().unwrap().buffer, 1 - in_flight);
+        }
+    }
+}
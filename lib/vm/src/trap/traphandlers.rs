@@ -4,14 +4,18 @@
 //! WebAssembly trap handling, which is built on top of the lower-level
 //! signalhandling mechanisms.
 
+use super::exception::{ExceptionState, VMException};
+use super::stack::{self, StackConfig};
+use super::tail_call::{make_pending_tail_calls, TailCallState};
 use super::trapcode::TrapCode;
 use crate::vmcontext::{VMFunctionBody, VMFunctionEnvironment, VMTrampoline};
 use backtrace::Backtrace;
 use std::any::Any;
-use std::cell::{Cell, UnsafeCell};
+use std::cell::{Cell, RefCell, UnsafeCell};
 use std::error::Error;
 use std::io;
 use std::mem::{self, MaybeUninit};
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 use std::sync::Once;
 pub use tls::TlsRestore;
@@ -88,7 +92,7 @@ cfg_if::cfg_if! {
         }
 
         #[cfg(target_os = "macos")]
-        unsafe fn thread_stack() -> (usize, usize) {
+        pub(crate) unsafe fn thread_stack() -> (usize, usize) {
             let this_thread = libc::pthread_self();
             let stackaddr = libc::pthread_get_stackaddr_np(this_thread);
             let stacksize = libc::pthread_get_stacksize_np(this_thread);
@@ -96,7 +100,7 @@ cfg_if::cfg_if! {
         }
 
         #[cfg(not(target_os = "macos"))]
-        unsafe fn thread_stack() -> (usize, usize) {
+        pub(crate) unsafe fn thread_stack() -> (usize, usize) {
             let this_thread = libc::pthread_self();
             let mut thread_attrs: libc::pthread_attr_t = mem::zeroed();
             #[cfg(not(target_os = "freebsd"))]
@@ -129,7 +133,10 @@ cfg_if::cfg_if! {
                     // The stack and its guard page covers the
                     // range [stackaddr - guard pages .. stackaddr + stacksize).
                     // We assume the guard page is 1 page, and pages are 4KiB (or 16KiB in Apple Silicon)
-                    if stackaddr - region::page::size() <= addr && addr < stackaddr + stacksize {
+                    if stack::guest_stack_contains(addr)
+                        || (stackaddr - region::page::size() <= addr
+                            && addr < stackaddr + stacksize)
+                    {
                         Some(TrapCode::StackOverflow)
                     } else {
                         Some(TrapCode::HeapAccessOutOfBounds)
@@ -509,7 +516,8 @@ impl Trap {
     }
 }
 
-/// Call the wasm function pointed to by `callee`.
+/// Call the wasm function pointed to by `callee`, and the functions it
+/// tail-calls.
 ///
 /// * `vmctx` - the callee vmctx argument
 /// * `caller_vmctx` - the caller vmctx argument
@@ -535,6 +543,7 @@ pub unsafe fn wasmer_call_trampoline(
         mem::transmute::<_, extern "C" fn(VMFunctionEnvironment, *const VMFunctionBody, *mut u8)>(
             trampoline,
         )(vmctx, callee, values_vec);
+        make_pending_tail_calls(values_vec);
     })
 }
 
@@ -546,13 +555,21 @@ pub unsafe fn catch_traps<F>(trap_handler: &dyn TrapHandler, mut closure: F) ->
 where
     F: FnMut(),
 {
-    return CallThreadState::new(trap_handler).with(|cx| {
-        wasmer_register_setjmp(
-            cx.jmp_buf.as_ptr(),
-            call_closure::<F>,
-            &mut closure as *mut F as *mut u8,
-        )
-    });
+    let mut call = || {
+        CallThreadState::new(trap_handler).with(|cx| {
+            wasmer_register_setjmp(
+                cx.jmp_buf.as_ptr(),
+                call_closure::<F>,
+                &mut closure as *mut F as *mut u8,
+            )
+        })
+    };
+    // The stack is limited by the outermost call into WebAssembly.
+    return if tls::with(|state| state.is_some()) {
+        call()
+    } else {
+        stack::with_stack_limit(trap_handler.stack_config(), call)
+    };
 
     extern "C" fn call_closure<F>(payload: *mut u8)
     where
@@ -585,6 +602,39 @@ where
     Ok(global_results.assume_init())
 }
 
+/// Runs `closure`, a call made in the body of a `try`, catching the
+/// exception it raises: the exception is then pending in the current call
+/// into WebAssembly, for the handler of the `try`, and `true` is returned.
+///
+/// Other traps, and panics, carry on unwinding to the caller.
+///
+/// # Safety
+///
+/// Check [`catch_traps`]. WebAssembly code must also be on the stack, aka
+/// `catch_traps` must have been previously called and not yet returned.
+pub(crate) unsafe fn catch_exceptions<F>(closure: F) -> bool
+where
+    F: FnMut(),
+{
+    // The handler outlives the call, which returns before its own caller.
+    let trap_handler = tls::with(|state| {
+        let state = state.expect("exceptions can only be caught while WebAssembly is running");
+        mem::transmute::<&dyn TrapHandler, &'static dyn TrapHandler>(state.trap_handler)
+    });
+    match panic::catch_unwind(AssertUnwindSafe(|| catch_traps(trap_handler, closure))) {
+        Ok(Ok(())) => false,
+        Ok(Err(Trap::User(error))) => match error.downcast::<VMException>() {
+            Ok(exception) => {
+                with_exceptions(|exceptions| exceptions.throw(*exception));
+                true
+            }
+            Err(error) => raise_user_trap(error),
+        },
+        Ok(Err(trap)) => raise_lib_trap(trap),
+        Err(panic) => resume_panic(panic),
+    }
+}
+
 /// Temporary state stored on the stack which is registered in the `tls` module
 /// below for calls into wasm.
 pub struct CallThreadState<'a> {
@@ -594,6 +644,8 @@ pub struct CallThreadState<'a> {
     prev: Cell<tls::Ptr>,
     trap_handler: &'a (dyn TrapHandler + 'a),
     handling_trap: Cell<bool>,
+    exceptions: RefCell<ExceptionState>,
+    tail_calls: RefCell<TailCallState>,
 }
 
 /// A package of functionality needed by `catch_traps` to figure out what to do
@@ -610,6 +662,11 @@ pub unsafe trait TrapHandler {
     ///
     /// Returns `true` if `call` returns true, otherwise returns `false`.
     fn custom_trap_handler(&self, call: &dyn Fn(&TrapHandlerFn) -> bool) -> bool;
+
+    /// How much stack WebAssembly code called with this handler can use.
+    fn stack_config(&self) -> StackConfig {
+        StackConfig::default()
+    }
 }
 
 enum UnwindReason {
@@ -637,6 +694,8 @@ impl<'a> CallThreadState<'a> {
             prev: Cell::new(ptr::null()),
             trap_handler,
             handling_trap: Cell::new(false),
+            exceptions: RefCell::new(ExceptionState::default()),
+            tail_calls: RefCell::new(TailCallState::default()),
         }
     }
 
@@ -743,6 +802,35 @@ impl<'a> Drop for CallThreadState<'a> {
     }
 }
 
+/// Runs `closure` with the exceptions of the current call into WebAssembly.
+///
+/// # Panics
+///
+/// Panics if no WebAssembly code is on the stack, aka `catch_traps` must
+/// have been previously called and not yet returned.
+pub(crate) fn with_exceptions<R>(closure: impl FnOnce(&mut ExceptionState) -> R) -> R {
+    tls::with(|state| {
+        let state = state.expect("exceptions can only be thrown while WebAssembly is running");
+        let mut exceptions = state.exceptions.borrow_mut();
+        closure(&mut exceptions)
+    })
+}
+
+/// Runs `closure` with the pending tail call of the current call into
+/// WebAssembly.
+///
+/// # Panics
+///
+/// Panics if no WebAssembly code is on the stack, aka `catch_traps` must
+/// have been previously called and not yet returned.
+pub(crate) fn with_tail_calls<R>(closure: impl FnOnce(&mut TailCallState) -> R) -> R {
+    tls::with(|state| {
+        let state = state.expect("tail calls can only be made while WebAssembly is running");
+        let mut tail_calls = state.tail_calls.borrow_mut();
+        closure(&mut tail_calls)
+    })
+}
+
 // A private inner module for managing the TLS state that we require across
 // calls in wasm. The WebAssembly code is called from C++ and then a trap may
 // happen which requires us to read some contextual state to figure out what to
@@ -990,3 +1078,56 @@ pub fn lazy_per_thread_init() -> Result<(), Trap> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trap::VMExceptionTag;
+    use wasmer_types::Type;
+
+    struct Handler;
+
+    unsafe impl TrapHandler for Handler {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn custom_trap_handler(&self, _call: &dyn Fn(&TrapHandlerFn) -> bool) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn calls_in_try_bodies_catch_exceptions() {
+        let tag = VMExceptionTag::new(&[Type::I32]);
+        let result = unsafe {
+            catch_traps(&Handler, || {
+                let exception = VMException::new(tag.clone(), vec![42]);
+                assert!(catch_exceptions(|| raise_user_trap(Box::new(
+                    exception.clone()
+                ))));
+                assert!(with_exceptions(
+                    |exceptions| exceptions.pending_has_tag(&tag)
+                ));
+                assert!(!catch_exceptions(|| {}));
+            })
+        };
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn calls_in_try_bodies_let_other_traps_through() {
+        let result = unsafe {
+            catch_traps(&Handler, || {
+                catch_exceptions(|| raise_lib_trap(Trap::lib(TrapCode::UnreachableCodeReached)));
+                unreachable!("the trap must unwind past the try");
+            })
+        };
+        match result {
+            Err(Trap::Lib { trap_code, .. }) => {
+                assert_eq!(trap_code, TrapCode::UnreachableCodeReached)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
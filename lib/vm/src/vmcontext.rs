@@ -340,7 +340,7 @@ pub struct VMMemoryDefinition {
     pub base: *mut u8,
 
     /// The current logical size of this linear memory in bytes.
-    pub current_length: u32,
+    pub current_length: usize,
 }
 
 /// # Safety
@@ -357,7 +357,7 @@ unsafe impl Sync for VMMemoryDefinition {}
 impl MemoryUsage for VMMemoryDefinition {
     fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
         if tracker.track(self.base as *const _ as *const ()) {
-            POINTER_BYTE_SIZE * (self.current_length as usize)
+            POINTER_BYTE_SIZE * self.current_length
         } else {
             0
         }
@@ -375,14 +375,14 @@ impl VMMemoryDefinition {
     /// # Safety
     /// The memory is not copied atomically and is not synchronized: it's the
     /// caller's responsibility to synchronize.
-    pub(crate) unsafe fn memory_copy(&self, dst: u32, src: u32, len: u32) -> Result<(), Trap> {
+    pub(crate) unsafe fn memory_copy(&self, dst: u64, src: u64, len: u64) -> Result<(), Trap> {
         // https://webassembly.github.io/reference-types/core/exec/instructions.html#exec-memory-copy
         if src
             .checked_add(len)
-            .map_or(true, |n| n > self.current_length)
+            .map_or(true, |n| n > self.current_length as u64)
             || dst
                 .checked_add(len)
-                .map_or(true, |m| m > self.current_length)
+                .map_or(true, |m| m > self.current_length as u64)
         {
             return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
         }
@@ -409,10 +409,10 @@ impl VMMemoryDefinition {
     /// # Safety
     /// The memory is not filled atomically and is not synchronized: it's the
     /// caller's responsibility to synchronize.
-    pub(crate) unsafe fn memory_fill(&self, dst: u32, val: u32, len: u32) -> Result<(), Trap> {
+    pub(crate) unsafe fn memory_fill(&self, dst: u64, val: u32, len: u64) -> Result<(), Trap> {
         if dst
             .checked_add(len)
-            .map_or(true, |m| m > self.current_length)
+            .map_or(true, |m| m > self.current_length as u64)
         {
             return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
         }
@@ -452,12 +452,10 @@ mod test_vmmemory_definition {
             offset_of!(VMMemoryDefinition, current_length),
             usize::from(offsets.vmmemory_definition_current_length())
         );
-        /* TODO: Assert that the size of `current_length` matches.
         assert_eq!(
-            size_of::<VMMemoryDefinition::current_length>(),
+            size_of::<usize>(),
             usize::from(offsets.size_of_vmmemory_definition_current_length())
         );
-        */
     }
 }
 
@@ -999,9 +997,110 @@ impl VMBuiltinFunctionIndex {
     pub const fn get_externref_dec_index() -> Self {
         Self(25)
     }
+    /// Returns an index for wasm's `memory.grow` builtin function for
+    /// 64-bit memories.
+    pub const fn get_memory64_grow_index() -> Self {
+        Self(26)
+    }
+    /// Returns an index for wasm's imported `memory.grow` builtin function
+    /// for 64-bit memories.
+    pub const fn get_imported_memory64_grow_index() -> Self {
+        Self(27)
+    }
+    /// Returns an index for wasm's `memory.size` builtin function for
+    /// 64-bit memories.
+    pub const fn get_memory64_size_index() -> Self {
+        Self(28)
+    }
+    /// Returns an index for wasm's imported `memory.size` builtin function
+    /// for 64-bit memories.
+    pub const fn get_imported_memory64_size_index() -> Self {
+        Self(29)
+    }
+    /// Returns an index for wasm's `memory.copy` for locally defined 64-bit
+    /// memories.
+    pub const fn get_memory64_copy_index() -> Self {
+        Self(30)
+    }
+    /// Returns an index for wasm's `memory.copy` for imported 64-bit memories.
+    pub const fn get_imported_memory64_copy_index() -> Self {
+        Self(31)
+    }
+    /// Returns an index for wasm's `memory.fill` for locally defined 64-bit
+    /// memories.
+    pub const fn get_memory64_fill_index() -> Self {
+        Self(32)
+    }
+    /// Returns an index for wasm's `memory.fill` for imported 64-bit memories.
+    pub const fn get_imported_memory64_fill_index() -> Self {
+        Self(33)
+    }
+    /// Returns an index for wasm's `memory.init` instruction for 64-bit
+    /// memories.
+    pub const fn get_memory64_init_index() -> Self {
+        Self(34)
+    }
+    /// Returns an index for wasm's `memory.copy` between two different
+    /// 32-bit memories.
+    pub const fn get_memory32_copy_between_index() -> Self {
+        Self(35)
+    }
+    /// Returns an index for wasm's `memory.copy` between two different
+    /// memories, at least one of which is a 64-bit memory.
+    pub const fn get_memory64_copy_between_index() -> Self {
+        Self(36)
+    }
+    /// Returns an index for wasm's `throw`.
+    pub const fn get_throw_index() -> Self {
+        Self(37)
+    }
+    /// Returns an index for raising the pending exception to the caller.
+    pub const fn get_exception_raise_index() -> Self {
+        Self(38)
+    }
+    /// Returns an index for checking the tag of the pending exception.
+    pub const fn get_exception_matches_index() -> Self {
+        Self(39)
+    }
+    /// Returns an index for catching the pending exception.
+    pub const fn get_exception_catch_index() -> Self {
+        Self(40)
+    }
+    /// Returns an index for wasm's `rethrow`.
+    pub const fn get_exception_rethrow_index() -> Self {
+        Self(41)
+    }
+    /// Returns an index for releasing a caught exception.
+    pub const fn get_exception_release_index() -> Self {
+        Self(42)
+    }
+    /// Returns an index for wasm's `return_call`.
+    pub const fn get_return_call_index() -> Self {
+        Self(43)
+    }
+    /// Returns an index for wasm's `return_call_indirect`.
+    pub const fn get_return_call_indirect_index() -> Self {
+        Self(44)
+    }
+    /// Returns an index for checking for a pending tail call after a call.
+    pub const fn get_tail_call_pending_index() -> Self {
+        Self(45)
+    }
+    /// Returns an index for making the pending tail calls.
+    pub const fn get_tail_call_index() -> Self {
+        Self(46)
+    }
+    /// Returns an index for a `call` in the body of a `try`.
+    pub const fn get_try_call_index() -> Self {
+        Self(47)
+    }
+    /// Returns an index for a `call_indirect` in the body of a `try`.
+    pub const fn get_try_call_indirect_index() -> Self {
+        Self(48)
+    }
     /// Returns the total number of builtin functions.
     pub const fn builtin_functions_total_number() -> u32 {
-        26
+        49
     }
 
     /// Return the index as an u32 number.
@@ -1084,6 +1183,52 @@ impl VMBuiltinFunctionsArray {
         ptrs[VMBuiltinFunctionIndex::get_externref_dec_index().index() as usize] =
             wasmer_vm_externref_dec as usize;
 
+        ptrs[VMBuiltinFunctionIndex::get_memory64_grow_index().index() as usize] =
+            wasmer_vm_memory64_grow as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory64_grow_index().index() as usize] =
+            wasmer_vm_imported_memory64_grow as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory64_size_index().index() as usize] =
+            wasmer_vm_memory64_size as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory64_size_index().index() as usize] =
+            wasmer_vm_imported_memory64_size as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory64_copy_index().index() as usize] =
+            wasmer_vm_memory64_copy as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory64_copy_index().index() as usize] =
+            wasmer_vm_imported_memory64_copy as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory64_fill_index().index() as usize] =
+            wasmer_vm_memory64_fill as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory64_fill_index().index() as usize] =
+            wasmer_vm_imported_memory64_fill as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory64_init_index().index() as usize] =
+            wasmer_vm_memory64_init as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory32_copy_between_index().index() as usize] =
+            wasmer_vm_memory32_copy_between as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory64_copy_between_index().index() as usize] =
+            wasmer_vm_memory64_copy_between as usize;
+        ptrs[VMBuiltinFunctionIndex::get_throw_index().index() as usize] = wasmer_vm_throw as usize;
+        ptrs[VMBuiltinFunctionIndex::get_exception_raise_index().index() as usize] =
+            wasmer_vm_exception_raise as usize;
+        ptrs[VMBuiltinFunctionIndex::get_exception_matches_index().index() as usize] =
+            wasmer_vm_exception_matches as usize;
+        ptrs[VMBuiltinFunctionIndex::get_exception_catch_index().index() as usize] =
+            wasmer_vm_exception_catch as usize;
+        ptrs[VMBuiltinFunctionIndex::get_exception_rethrow_index().index() as usize] =
+            wasmer_vm_exception_rethrow as usize;
+        ptrs[VMBuiltinFunctionIndex::get_exception_release_index().index() as usize] =
+            wasmer_vm_exception_release as usize;
+        ptrs[VMBuiltinFunctionIndex::get_return_call_index().index() as usize] =
+            wasmer_vm_return_call as usize;
+        ptrs[VMBuiltinFunctionIndex::get_return_call_indirect_index().index() as usize] =
+            wasmer_vm_return_call_indirect as usize;
+        ptrs[VMBuiltinFunctionIndex::get_tail_call_pending_index().index() as usize] =
+            wasmer_vm_tail_call_pending as usize;
+        ptrs[VMBuiltinFunctionIndex::get_tail_call_index().index() as usize] =
+            wasmer_vm_tail_call as usize;
+        ptrs[VMBuiltinFunctionIndex::get_try_call_index().index() as usize] =
+            wasmer_vm_try_call as usize;
+        ptrs[VMBuiltinFunctionIndex::get_try_call_indirect_index().index() as usize] =
+            wasmer_vm_try_call_indirect as usize;
+
         debug_assert!(ptrs.iter().cloned().all(|p| p != 0));
 
         Self { ptrs }
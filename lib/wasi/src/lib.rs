@@ -22,16 +22,17 @@ mod utils;
 use crate::syscalls::*;
 
 pub use crate::state::{
-    Fd, Pipe, Stderr, Stdin, Stdout, WasiFile, WasiFs, WasiFsError, WasiState, WasiStateBuilder,
-    WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
+    channel_pipe, pack_dir_image, unpack_dir_image, ChannelPipe, Fd, HostFile, PathAccess, Pipe,
+    PollWaker, PreopenDirBuilder, Stderr, Stdin, Stdout, WasiFile, WasiFs, WasiFsError, WasiState,
+    WasiStateBuilder, WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
 };
 pub use crate::syscalls::types;
 pub use crate::utils::{get_wasi_version, get_wasi_versions, is_wasi_module, WasiVersion};
 
 use thiserror::Error;
 use wasmer::{
-    imports, ChainableNamedResolver, Function, ImportObject, LazyInit, Memory, Module,
-    NamedResolver, Store, WasmerEnv,
+    imports, ChainableNamedResolver, Function, HostEnvInitError, ImportObject, Instance, LazyInit,
+    Memory, Module, NamedResolver, Store, WasmerEnv,
 };
 
 use std::sync::{Arc, Mutex, MutexGuard};
@@ -47,7 +48,7 @@ pub enum WasiError {
 }
 
 /// The environment provided to the WASI imports.
-#[derive(Debug, Clone, WasmerEnv)]
+#[derive(Debug, Clone)]
 pub struct WasiEnv {
     /// Shared state of the WASI system. Manages all the data that the
     /// executing WASI program can see.
@@ -56,7 +57,8 @@ pub struct WasiEnv {
     /// if the lock is held and the Wasm calls into a host function that tries
     /// to lock this mutex, the program will deadlock.
     pub state: Arc<Mutex<WasiState>>,
-    #[wasmer(export)]
+    /// The name of the exported memory the WASI calls access.
+    memory_export: String,
     memory: LazyInit<Memory>,
 }
 
@@ -64,10 +66,26 @@ impl WasiEnv {
     pub fn new(state: WasiState) -> Self {
         Self {
             state: Arc::new(Mutex::new(state)),
+            memory_export: "memory".to_string(),
             memory: LazyInit::new(),
         }
     }
 
+    /// Use the memory exported as `name` instead of `"memory"`.
+    ///
+    /// Modules with several memories can pick the one the WASI calls
+    /// read from and write to. It must be set before the instance is
+    /// created.
+    pub fn set_memory_export(&mut self, name: impl Into<String>) -> &mut Self {
+        self.memory_export = name.into();
+        self
+    }
+
+    /// The name of the exported memory the WASI calls access.
+    pub fn memory_export(&self) -> &str {
+        &self.memory_export
+    }
+
     /// Get an `ImportObject` for a specific version of WASI detected in the module.
     pub fn import_object(&mut self, module: &Module) -> Result<ImportObject, WasiError> {
         let wasi_version = get_wasi_version(module, false).ok_or(WasiError::UnknownWasiVersion)?;
@@ -112,6 +130,22 @@ impl WasiEnv {
         self.state.lock().unwrap()
     }
 
+    /// Get access to the memory exported as [`WasiEnv::memory_export`].
+    ///
+    /// It is `None` until the instance has been created.
+    pub fn memory_ref(&self) -> Option<&Memory> {
+        self.memory.get_ref()
+    }
+
+    /// Gets the memory without checking if it's been initialized.
+    ///
+    /// # Safety
+    /// The instance must have been created, or the memory initialized
+    /// with `WasmerEnv::init_with_instance`.
+    pub unsafe fn memory_ref_unchecked(&self) -> &Memory {
+        self.memory.get_unchecked()
+    }
+
     /// Get a reference to the memory
     pub fn memory(&self) -> &Memory {
         self.memory_ref()
@@ -128,6 +162,14 @@ impl WasiEnv {
     }
 }
 
+impl WasmerEnv for WasiEnv {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let memory = instance.exports.get_memory(&self.memory_export)?;
+        self.memory.initialize(memory.clone());
+        Ok(())
+    }
+}
+
 /// Create an [`ImportObject`] with an existing [`WasiEnv`]. `WasiEnv`
 /// needs a [`WasiState`], that can be constructed from a
 /// [`WasiStateBuilder`](state::WasiStateBuilder).
@@ -1,9 +1,9 @@
 //! Builder system for configuring a [`WasiState`] and creating it.
 
-use crate::state::{WasiFile, WasiFs, WasiFsError, WasiState};
+use crate::state::{PathAccess, WasiFile, WasiFs, WasiFsError, WasiState};
 use crate::syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO};
 use crate::WasiEnv;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use thiserror::Error;
 
 /// Creates an empty [`WasiStateBuilder`].
@@ -37,6 +37,8 @@ pub struct WasiStateBuilder {
     args: Vec<Vec<u8>>,
     envs: Vec<(Vec<u8>, Vec<u8>)>,
     preopens: Vec<PreopenedDir>,
+    path_access: Vec<(PathBuf, PathAccess)>,
+    enforce_requested_rights: bool,
     #[allow(clippy::type_complexity)]
     setup_fs_fn: Option<Box<dyn Fn(&mut WasiFs) -> Result<(), String> + Send>>,
     stdout_override: Option<Box<dyn WasiFile>>,
@@ -50,6 +52,8 @@ impl std::fmt::Debug for WasiStateBuilder {
             .field("args", &self.args)
             .field("envs", &self.envs)
             .field("preopens", &self.preopens)
+            .field("path_access", &self.path_access)
+            .field("enforce_requested_rights", &self.enforce_requested_rights)
             .field("setup_fs_fn exists", &self.setup_fs_fn.is_some())
             .field("stdout_override exists", &self.stdout_override.is_some())
             .field("stderr_override exists", &self.stderr_override.is_some())
@@ -69,6 +73,8 @@ pub enum WasiStateCreationError {
     PreopenedDirectoryNotFound(PathBuf),
     #[error("preopened directory error: `{0}`")]
     PreopenedDirectoryError(String),
+    #[error("path access rule has wrong format: `{0}`")]
+    PathAccessFormattingError(String),
     #[error("mapped dir alias has wrong format: `{0}`")]
     MappedDirAliasFormattingError(String),
     #[error("wasi filesystem creation error: `{0}`")]
@@ -89,6 +95,53 @@ fn validate_mapped_dir_alias(alias: &str) -> Result<(), WasiStateCreationError>
     Ok(())
 }
 
+fn validate_path_access(path: &Path) -> Result<(), WasiStateCreationError> {
+    // Rules must stay inside their preopened directory, so they can be
+    // matched against the host paths the WASI filesystem builds.
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(WasiStateCreationError::PathAccessFormattingError(format!(
+            "Path \"{}\" must be relative to the preopened directory, without `..`",
+            path.to_string_lossy()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Finds the preopened directory containing `guest_path`, and returns it
+/// with the path relative to it.
+///
+/// When preopened directories are nested, the innermost one is used.
+fn find_preopen<'a>(
+    preopens: &'a mut [PreopenedDir],
+    guest_path: &Path,
+) -> Option<(&'a mut PreopenedDir, PathBuf)> {
+    let guest_path = without_root(guest_path);
+    preopens
+        .iter_mut()
+        .filter_map(|preopen| {
+            let name = match &preopen.alias {
+                Some(alias) => without_root(Path::new(alias)),
+                None => without_root(&preopen.path),
+            };
+            let relative_path = guest_path.strip_prefix(&name).ok()?.to_path_buf();
+            Some((name.components().count(), preopen, relative_path))
+        })
+        .max_by_key(|(depth, _, _)| *depth)
+        .map(|(_, preopen, relative_path)| (preopen, relative_path))
+}
+
+/// Strips the `/` and `.` components of a path, as preopened directories
+/// are all mounted at the virtual root.
+fn without_root(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::RootDir | Component::CurDir))
+        .collect()
+}
+
 // TODO add other WasiFS APIs here like swapping out stdout, for example (though we need to
 // return stdout somehow, it's unclear what that API should look like)
 impl WasiStateBuilder {
@@ -242,6 +295,32 @@ impl WasiStateBuilder {
         Ok(self)
     }
 
+    /// Preopen a directory that the WASI module can read but not modify.
+    ///
+    /// This opens the given directory at the virtual root, `/`.
+    pub fn preopen_dir_read_only<FilePath>(
+        &mut self,
+        po_dir: FilePath,
+    ) -> Result<&mut Self, WasiStateCreationError>
+    where
+        FilePath: AsRef<Path>,
+    {
+        self.preopen(|p| p.directory(&po_dir).read(true))
+    }
+
+    /// Preopen a directory that the WASI module can read but not modify,
+    /// with a different name exposed to the WASI.
+    pub fn map_dir_read_only<FilePath>(
+        &mut self,
+        alias: &str,
+        po_dir: FilePath,
+    ) -> Result<&mut Self, WasiStateCreationError>
+    where
+        FilePath: AsRef<Path>,
+    {
+        self.preopen(|p| p.directory(&po_dir).alias(alias).read(true))
+    }
+
     /// Preopen directorys with a different names exposed to the WASI.
     pub fn map_dirs<I, FilePath>(
         &mut self,
@@ -258,6 +337,34 @@ impl WasiStateBuilder {
         Ok(self)
     }
 
+    /// Restrict the access to a path, as the WASI module sees it, and to
+    /// everything below it.
+    ///
+    /// The path must be inside one of the preopened directories, for
+    /// example `/fixtures/expected` for a directory mapped as `fixtures`.
+    /// See [`PreopenDirBuilder::path_access`].
+    pub fn path_access<FilePath>(&mut self, guest_path: FilePath, access: PathAccess) -> &mut Self
+    where
+        FilePath: AsRef<Path>,
+    {
+        self.path_access
+            .push((guest_path.as_ref().to_path_buf(), access));
+
+        self
+    }
+
+    /// Give the files opened by the module only the rights it asks for,
+    /// rather than every right inherited from their directory.
+    ///
+    /// This is off by default, since many modules ask for fewer rights
+    /// than they use.  The rights of the directories and the access rules
+    /// of the paths apply either way.
+    pub fn enforce_requested_rights(&mut self, enforce: bool) -> &mut Self {
+        self.enforce_requested_rights = enforce;
+
+        self
+    }
+
     /// Overwrite the default WASI `stdout`, if you want to hold on to the
     /// original `stdout` use [`WasiFs::swap_file`] after building.
     pub fn stdout(&mut self, new_file: Box<dyn WasiFile>) -> &mut Self {
@@ -361,10 +468,24 @@ impl WasiStateBuilder {
 
         // self.preopens are checked in [`PreopenDirBuilder::build`]
 
+        for (guest_path, access) in self.path_access.iter() {
+            let (preopen, relative_path) = find_preopen(&mut self.preopens, guest_path)
+                .ok_or_else(|| {
+                    WasiStateCreationError::PathAccessFormattingError(format!(
+                        "Path \"{}\" is not inside a preopened directory",
+                        guest_path.to_string_lossy()
+                    ))
+                })?;
+            validate_path_access(&relative_path)?;
+            preopen.path_access.push((relative_path, *access));
+        }
+        self.path_access.clear();
+
         // this deprecation warning only applies to external callers
         #[allow(deprecated)]
         let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens)
             .map_err(WasiStateCreationError::WasiFsCreationError)?;
+        wasi_fs.enforce_requested_rights = self.enforce_requested_rights;
         // set up the file system, overriding base files and calling the setup function
         if let Some(stdin_override) = self.stdin_override.take() {
             wasi_fs
@@ -419,6 +540,7 @@ pub struct PreopenDirBuilder {
     read: bool,
     write: bool,
     create: bool,
+    path_access: Vec<(PathBuf, PathAccess)>,
 }
 
 /// The built version of `PreopenDirBuilder`
@@ -429,6 +551,7 @@ pub(crate) struct PreopenedDir {
     pub(crate) read: bool,
     pub(crate) write: bool,
     pub(crate) create: bool,
+    pub(crate) path_access: Vec<(PathBuf, PathAccess)>,
 }
 
 impl PreopenDirBuilder {
@@ -484,6 +607,23 @@ impl PreopenDirBuilder {
         self
     }
 
+    /// Restrict the access to a path inside the directory, and to
+    /// everything below it.
+    ///
+    /// `path` is relative to the preopened directory. The closest rule to
+    /// a file wins, so a directory can be read-only with a writable
+    /// subdirectory, or a single file can be hidden with
+    /// [`PathAccess::Deny`]. The rules can only take rights away: a path
+    /// is never writable if the preopened directory isn't.
+    pub fn path_access<FilePath>(&mut self, path: FilePath, access: PathAccess) -> &mut Self
+    where
+        FilePath: AsRef<Path>,
+    {
+        self.path_access.push((path.as_ref().to_path_buf(), access));
+
+        self
+    }
+
     pub(crate) fn build(&self) -> Result<PreopenedDir, WasiStateCreationError> {
         // ensure at least one is set
         if !(self.read || self.write || self.create) {
@@ -503,6 +643,9 @@ impl PreopenDirBuilder {
         if let Some(alias) = &self.alias {
             validate_mapped_dir_alias(alias)?;
         }
+        for (rule_path, _) in &self.path_access {
+            validate_path_access(rule_path)?;
+        }
 
         Ok(PreopenedDir {
             path,
@@ -510,6 +653,7 @@ impl PreopenDirBuilder {
             read: self.read,
             write: self.write,
             create: self.create,
+            path_access: self.path_access.clone(),
         })
     }
 }
@@ -557,6 +701,102 @@ mod test {
         );
     }
 
+    #[test]
+    fn path_access_must_stay_in_the_preopened_dir() {
+        let output = create_wasi_state("test_prog")
+            .preopen(|p| {
+                p.directory(".")
+                    .read(true)
+                    .path_access("../escape", PathAccess::ReadWrite)
+            })
+            .map(|_| ());
+        match output {
+            Err(WasiStateCreationError::PathAccessFormattingError(_)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        assert!(create_wasi_state("test_prog")
+            .preopen(|p| {
+                p.directory(".")
+                    .read(true)
+                    .path_access("./src/secret", PathAccess::Deny)
+            })
+            .is_ok());
+    }
+
+    #[test]
+    fn path_access_is_resolved_against_preopens() {
+        let mut builder = create_wasi_state("test_prog");
+        builder
+            .preopen(|p| p.directory("src").alias("code").read(true))
+            .unwrap()
+            .preopen(|p| p.directory("src/state").alias("code/state").read(true))
+            .unwrap()
+            .path_access("/code/lib.rs", PathAccess::Deny)
+            .path_access("./code/state/mod.rs", PathAccess::ReadOnly);
+        assert!(builder.build().is_ok());
+        assert_eq!(
+            builder.preopens[0].path_access,
+            vec![(PathBuf::from("lib.rs"), PathAccess::Deny)]
+        );
+        assert_eq!(
+            builder.preopens[1].path_access,
+            vec![(PathBuf::from("mod.rs"), PathAccess::ReadOnly)]
+        );
+
+        let output = create_wasi_state("test_prog")
+            .preopen(|p| p.directory("src").alias("code").read(true))
+            .unwrap()
+            .path_access("/elsewhere", PathAccess::Deny)
+            .build();
+        match output {
+            Err(WasiStateCreationError::PathAccessFormattingError(_)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn host_paths_are_resolved_inside_preopens() {
+        use crate::syscalls::types::{__WASI_ENOENT, __WASI_RIGHT_FD_READ, __WASI_RIGHT_FD_WRITE};
+
+        let mut state = create_wasi_state("test_prog")
+            .preopen(|p| p.directory("src").alias("code").read(true))
+            .unwrap()
+            .path_access("/code/lib.rs", PathAccess::Deny)
+            .build()
+            .unwrap();
+        // the virtual root comes first
+        let code = state.fs.preopen_fds[1];
+
+        let (host_path, rights) = state
+            .fs
+            .resolve_host_path(code, "state/../state/mod.rs", true)
+            .unwrap();
+        assert!(host_path.ends_with("src/state/mod.rs"));
+        assert_ne!(rights & __WASI_RIGHT_FD_READ, 0);
+        assert_eq!(rights & __WASI_RIGHT_FD_WRITE, 0);
+
+        // missing files can be created in existing directories
+        let (host_path, _) = state
+            .fs
+            .resolve_host_path(code, "state/new.rs", true)
+            .unwrap();
+        assert!(host_path.ends_with("src/state/new.rs"));
+        assert_eq!(
+            state.fs.resolve_host_path(code, "missing/new.rs", true),
+            Err(__WASI_ENOENT)
+        );
+
+        assert_eq!(
+            state.fs.resolve_host_path(code, "lib.rs", true),
+            Err(__WASI_ENOENT)
+        );
+        assert!(state
+            .fs
+            .resolve_host_path(code, "../Cargo.toml", true)
+            .is_err());
+    }
+
     #[test]
     fn nul_character_in_args() {
         let output = create_wasi_state("test_prog").arg("--h\0elp").build();
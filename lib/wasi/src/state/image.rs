@@ -0,0 +1,169 @@
+//! Directory images: a tree of files packed into a single buffer, to
+//! ship it inside another file (like an executable made by
+//! `wasmer create-exe`) and unpack it where the WASI module runs.
+//!
+//! The format is a magic header followed by entries, each of them a
+//! kind byte, the length of its path (`u32`, little-endian) and its
+//! `/`-separated relative path.  File entries are then followed by the
+//! length of their contents (`u64`, little-endian) and the contents.
+//! Parents always come before their children.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+
+const MAGIC: &[u8; 8] = b"WASMERFS";
+const DIRECTORY: u8 = 0;
+const FILE: u8 = 1;
+
+/// Packs the tree below `dir` into a directory image.
+///
+/// Only directories and regular files are packed, following symlinks.
+pub fn pack_dir_image<P: AsRef<Path>>(dir: P) -> io::Result<Vec<u8>> {
+    let mut image = MAGIC.to_vec();
+    pack_entries(dir.as_ref(), "", &mut image)?;
+    Ok(image)
+}
+
+fn pack_entries(dir: &Path, prefix: &str, image: &mut Vec<u8>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    // Sorted to make images reproducible.
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|name| invalid_data(format!("the file name {:?} is not valid UTF-8", name)))?;
+        let path = format!("{}{}", prefix, name);
+        let metadata = fs::metadata(entry.path())?;
+        if metadata.is_dir() {
+            push_header(image, DIRECTORY, &path);
+            pack_entries(&entry.path(), &format!("{}/", path), image)?;
+        } else if metadata.is_file() {
+            let contents = fs::read(entry.path())?;
+            push_header(image, FILE, &path);
+            image.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+            image.extend_from_slice(&contents);
+        }
+    }
+    Ok(())
+}
+
+fn push_header(image: &mut Vec<u8>, kind: u8, path: &str) {
+    image.push(kind);
+    image.extend_from_slice(&(path.len() as u32).to_le_bytes());
+    image.extend_from_slice(path.as_bytes());
+}
+
+/// Unpacks a directory image made by [`pack_dir_image`] into `dest`,
+/// which must exist.
+///
+/// Paths of the image escaping `dest` are rejected.
+pub fn unpack_dir_image<P: AsRef<Path>>(image: &[u8], dest: P) -> io::Result<()> {
+    let dest = dest.as_ref();
+    let mut reader = ImageReader(image);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid_data("not a directory image".to_string()));
+    }
+    while !reader.0.is_empty() {
+        let kind = reader.take(1)?[0];
+        let path_len = u32::from_le_bytes(<[u8; 4]>::try_from(reader.take(4)?).unwrap());
+        let path = std::str::from_utf8(reader.take(path_len as usize)?)
+            .map_err(|_| invalid_data("a path is not valid UTF-8".to_string()))?;
+        let relative = Path::new(path);
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(invalid_data(format!(
+                "the path `{}` is not a plain relative path",
+                path
+            )));
+        }
+        match kind {
+            DIRECTORY => fs::create_dir(dest.join(relative))?,
+            FILE => {
+                let len = u64::from_le_bytes(<[u8; 8]>::try_from(reader.take(8)?).unwrap());
+                let contents = reader.take(usize::try_from(len).map_err(|_| truncated())?)?;
+                fs::write(dest.join(relative), contents)?;
+            }
+            _ => return Err(invalid_data(format!("unknown entry kind {}", kind))),
+        }
+    }
+    Ok(())
+}
+
+struct ImageReader<'a>(&'a [u8]);
+
+impl<'a> ImageReader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(truncated());
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+}
+
+fn truncated() -> io::Error {
+    invalid_data("the directory image is truncated".to_string())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wasmer-wasi-image-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = scratch_dir("source");
+        fs::create_dir_all(source.join("etc/empty")).unwrap();
+        fs::write(source.join("etc/motd"), b"hello").unwrap();
+        fs::write(source.join("root.txt"), b"").unwrap();
+
+        let image = pack_dir_image(&source).unwrap();
+        let dest = scratch_dir("dest");
+        unpack_dir_image(&image, &dest).unwrap();
+
+        assert!(dest.join("etc/empty").is_dir());
+        assert_eq!(fs::read(dest.join("etc/motd")).unwrap(), b"hello");
+        assert_eq!(fs::read(dest.join("root.txt")).unwrap(), b"");
+        // Packing is reproducible.
+        assert_eq!(pack_dir_image(&dest).unwrap(), image);
+
+        fs::remove_dir_all(&source).unwrap();
+        fs::remove_dir_all(&dest).unwrap();
+    }
+
+    #[test]
+    fn rejects_escaping_and_truncated_images() {
+        let dest = scratch_dir("escape");
+        let mut image = MAGIC.to_vec();
+        push_header(&mut image, DIRECTORY, "../escape");
+        assert!(unpack_dir_image(&image, &dest).is_err());
+        assert!(!dest.join("../escape").exists());
+
+        let mut image = MAGIC.to_vec();
+        push_header(&mut image, FILE, "file");
+        image.extend_from_slice(&10u64.to_le_bytes());
+        image.extend_from_slice(b"short");
+        assert!(unpack_dir_image(&image, &dest).is_err());
+
+        assert!(unpack_dir_image(b"garbage", &dest).is_err());
+        fs::remove_dir_all(&dest).unwrap();
+    }
+}
@@ -16,9 +16,13 @@
 #![allow(clippy::cognitive_complexity, clippy::too_many_arguments)]
 
 mod builder;
+mod image;
+mod policy;
 mod types;
 
 pub use self::builder::*;
+pub use self::image::*;
+pub use self::policy::*;
 pub use self::types::*;
 use crate::syscalls::types::*;
 use generational_arena::Arena;
@@ -52,6 +56,42 @@ const STDOUT_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
     | __WASI_RIGHT_FD_FILESTAT_GET
     | __WASI_RIGHT_POLL_FD_READWRITE;
 const STDERR_DEFAULT_RIGHTS: __wasi_rights_t = STDOUT_DEFAULT_RIGHTS;
+// TODO: review tell' and fd_readwrite
+/// the rights every preopened directory gets
+const BASE_RIGHTS: __wasi_rights_t =
+    __WASI_RIGHT_FD_ADVISE | __WASI_RIGHT_FD_TELL | __WASI_RIGHT_FD_SEEK;
+/// the rights given by the read permission of a preopened directory
+const READ_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_READ
+    | __WASI_RIGHT_PATH_OPEN
+    | __WASI_RIGHT_FD_READDIR
+    | __WASI_RIGHT_PATH_READLINK
+    | __WASI_RIGHT_PATH_FILESTAT_GET
+    | __WASI_RIGHT_FD_FILESTAT_GET
+    | __WASI_RIGHT_PATH_LINK_SOURCE
+    | __WASI_RIGHT_PATH_RENAME_SOURCE
+    | __WASI_RIGHT_POLL_FD_READWRITE
+    | __WASI_RIGHT_SOCK_SHUTDOWN;
+/// the rights given by the write permission of a preopened directory
+const WRITE_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
+    | __WASI_RIGHT_FD_WRITE
+    | __WASI_RIGHT_FD_SYNC
+    | __WASI_RIGHT_FD_ALLOCATE
+    | __WASI_RIGHT_PATH_OPEN
+    | __WASI_RIGHT_PATH_RENAME_TARGET
+    | __WASI_RIGHT_PATH_FILESTAT_SET_SIZE
+    | __WASI_RIGHT_PATH_FILESTAT_SET_TIMES
+    | __WASI_RIGHT_FD_FILESTAT_SET_SIZE
+    | __WASI_RIGHT_FD_FILESTAT_SET_TIMES
+    | __WASI_RIGHT_PATH_REMOVE_DIRECTORY
+    | __WASI_RIGHT_PATH_UNLINK_FILE
+    | __WASI_RIGHT_POLL_FD_READWRITE
+    | __WASI_RIGHT_SOCK_SHUTDOWN;
+/// the rights given by the create permission of a preopened directory
+const CREATE_RIGHTS: __wasi_rights_t = __WASI_RIGHT_PATH_CREATE_DIRECTORY
+    | __WASI_RIGHT_PATH_CREATE_FILE
+    | __WASI_RIGHT_PATH_LINK_TARGET
+    | __WASI_RIGHT_PATH_OPEN
+    | __WASI_RIGHT_PATH_RENAME_TARGET;
 
 /// A completely aribtrary "big enough" number used as the upper limit for
 /// the number of symlinks that can be traversed when resolving a path
@@ -163,6 +203,17 @@ pub struct WasiFs {
     inode_counter: Cell<u64>,
     /// for fds still open after the file has been deleted
     pub orphan_fds: HashMap<Inode, InodeVal>,
+    /// the access rules restricting paths below the preopened directories
+    #[serde(default)]
+    path_rules: PathRules,
+    /// the read-only preopened directories, whose rights mask everything
+    /// below them, whichever fd they are reached from
+    #[serde(default)]
+    read_only_preopens: Vec<Inode>,
+    /// whether `path_open` only gives the rights asked for, see
+    /// [`WasiStateBuilder::enforce_requested_rights`]
+    #[serde(default)]
+    pub(crate) enforce_requested_rights: bool,
 }
 
 impl WasiFs {
@@ -299,6 +350,7 @@ impl WasiFs {
             read,
             write,
             create,
+            path_access,
         } in preopens
         {
             debug!(
@@ -328,47 +380,22 @@ impl WasiFs {
             };
 
             let rights = {
-                // TODO: review tell' and fd_readwrite
-                let mut rights =
-                    __WASI_RIGHT_FD_ADVISE | __WASI_RIGHT_FD_TELL | __WASI_RIGHT_FD_SEEK;
+                let mut rights = BASE_RIGHTS;
                 if *read {
-                    rights |= __WASI_RIGHT_FD_READ
-                        | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_FD_READDIR
-                        | __WASI_RIGHT_PATH_READLINK
-                        | __WASI_RIGHT_PATH_FILESTAT_GET
-                        | __WASI_RIGHT_FD_FILESTAT_GET
-                        | __WASI_RIGHT_PATH_LINK_SOURCE
-                        | __WASI_RIGHT_PATH_RENAME_SOURCE
-                        | __WASI_RIGHT_POLL_FD_READWRITE
-                        | __WASI_RIGHT_SOCK_SHUTDOWN;
+                    rights |= READ_RIGHTS;
                 }
                 if *write {
-                    rights |= __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
-                        | __WASI_RIGHT_FD_WRITE
-                        | __WASI_RIGHT_FD_SYNC
-                        | __WASI_RIGHT_FD_ALLOCATE
-                        | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_PATH_RENAME_TARGET
-                        | __WASI_RIGHT_PATH_FILESTAT_SET_SIZE
-                        | __WASI_RIGHT_PATH_FILESTAT_SET_TIMES
-                        | __WASI_RIGHT_FD_FILESTAT_SET_SIZE
-                        | __WASI_RIGHT_FD_FILESTAT_SET_TIMES
-                        | __WASI_RIGHT_PATH_REMOVE_DIRECTORY
-                        | __WASI_RIGHT_PATH_UNLINK_FILE
-                        | __WASI_RIGHT_POLL_FD_READWRITE
-                        | __WASI_RIGHT_SOCK_SHUTDOWN;
+                    rights |= WRITE_RIGHTS;
                 }
                 if *create {
-                    rights |= __WASI_RIGHT_PATH_CREATE_DIRECTORY
-                        | __WASI_RIGHT_PATH_CREATE_FILE
-                        | __WASI_RIGHT_PATH_LINK_TARGET
-                        | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_PATH_RENAME_TARGET;
+                    rights |= CREATE_RIGHTS;
                 }
 
                 rights
             };
+            for (relative_path, access) in path_access {
+                wasi_fs.path_rules.insert(path.join(relative_path), *access);
+            }
             let inode = if let Some(alias) = &alias {
                 wasi_fs.create_inode(kind, true, alias.clone())
             } else {
@@ -380,6 +407,12 @@ impl WasiFs {
                     e
                 )
             })?;
+            // a read-only directory stays read-only when it's reached from
+            // another fd, for example through the virtual root, while other
+            // preopens of the same host directory keep their own rights
+            if *read && !*write {
+                wasi_fs.read_only_preopens.push(inode);
+            }
             let fd_flags = {
                 let mut fd_flags = 0;
                 if *read {
@@ -428,6 +461,9 @@ impl WasiFs {
             next_fd: Cell::new(3),
             inode_counter: Cell::new(1024),
             orphan_fds: HashMap::new(),
+            path_rules: PathRules::default(),
+            read_only_preopens: vec![],
+            enforce_requested_rights: false,
         };
         wasi_fs.create_stdin();
         wasi_fs.create_stdout();
@@ -768,6 +804,10 @@ impl WasiFs {
                             "." => continue 'path_iter,
                             _ => (),
                         }
+                        // denied paths are hidden, as if they didn't exist
+                        if self.path_rules.access(&path.join(component)) == Some(PathAccess::Deny) {
+                            return Err(__WASI_ENOENT);
+                        }
                         // used for full resolution of symlinks
                         let mut loop_for_symlink = false;
                         if let Some(entry) =
@@ -1084,6 +1124,122 @@ impl WasiFs {
             .map(|v| (v, new_entity_name))
     }
 
+    /// Returns the rights mask that the access rules give to `host_path`.
+    pub fn path_rights(&self, host_path: &Path) -> __wasi_rights_t {
+        self.path_rules.rights(host_path)
+    }
+
+    /// Returns the rights mask that the access rules give to the file or
+    /// directory at `inode`.
+    pub(crate) fn inode_rights(&self, inode: Inode) -> __wasi_rights_t {
+        match &self.inodes[inode].kind {
+            Kind::File { path, .. } | Kind::Dir { path, .. } => {
+                self.path_rights(path) & self.preopen_rights(inode)
+            }
+            _ => ALL_RIGHTS,
+        }
+    }
+
+    /// Returns the rights mask that the preopened directory containing
+    /// `inode` gives to it, and to the files created below it.
+    pub(crate) fn preopen_rights(&self, inode: Inode) -> __wasi_rights_t {
+        if self
+            .read_only_preopens
+            .iter()
+            .any(|&preopen| self.is_below(preopen, inode))
+        {
+            PathAccess::ReadOnly.rights()
+        } else {
+            ALL_RIGHTS
+        }
+    }
+
+    /// Returns whether `inode` is `dir` or one of the inodes loaded below it.
+    fn is_below(&self, dir: Inode, inode: Inode) -> bool {
+        dir == inode
+            || match &self.inodes[dir].kind {
+                Kind::Dir { entries, .. } => {
+                    entries.values().any(|&entry| self.is_below(entry, inode))
+                }
+                _ => false,
+            }
+    }
+
+    /// Resolves `path`, relative to the directory open at `base`, to the
+    /// host path it designates, for hosts implementing other ABIs on top of
+    /// this filesystem.  Like for WASI calls, the path can't leave the
+    /// preopened directories.
+    ///
+    /// The path doesn't have to exist, but its parent directory does, so
+    /// that it can be created.  The rights returned are the ones of `base`,
+    /// masked by the access rules of the path and by its preopened directory.
+    pub fn resolve_host_path(
+        &mut self,
+        base: __wasi_fd_t,
+        path: &str,
+        follow_symlinks: bool,
+    ) -> Result<(PathBuf, __wasi_rights_t), __wasi_errno_t> {
+        let base_rights = self.get_fd(base)?.rights;
+        let (host_path, inode) = match self.get_inode_at_path(base, path, follow_symlinks) {
+            Ok(inode) => match &self.inodes[inode].kind {
+                Kind::File { path, .. } | Kind::Dir { path, .. }
+                    if !path.as_os_str().is_empty() =>
+                {
+                    (path.clone(), inode)
+                }
+                // the virtual root and special files have no host path
+                _ => return Err(__WASI_EACCES),
+            },
+            Err(_) => {
+                // missing paths are looked up as `EINVAL`
+                let (parent, name) = self
+                    .get_parent_inode_at_path(base, Path::new(path), follow_symlinks)
+                    .map_err(|errno| match errno {
+                        __WASI_EINVAL => __WASI_ENOENT,
+                        errno => errno,
+                    })?;
+                if name == ".." {
+                    return Err(__WASI_ENOENT);
+                }
+                match &self.inodes[parent].kind {
+                    Kind::Dir { path, .. } => (path.join(name), parent),
+                    Kind::Root { .. } => return Err(__WASI_EACCES),
+                    _ => return Err(__WASI_ENOTDIR),
+                }
+            }
+        };
+        if self.path_rules.access(&host_path) == Some(PathAccess::Deny) {
+            return Err(__WASI_ENOENT);
+        }
+        let rights = base_rights & self.path_rights(&host_path) & self.preopen_rights(inode);
+        Ok((host_path, rights))
+    }
+
+    /// Forgets the cached inodes of `host_path` and of everything below it,
+    /// after they were removed or renamed on the host by [`resolve_host_path`]
+    /// users, so that they are looked up again when they are next reached.
+    ///
+    /// [`resolve_host_path`]: WasiFs::resolve_host_path
+    pub fn forget_host_path(&mut self, host_path: &Path) {
+        let stale = self
+            .inodes
+            .iter()
+            .filter_map(|(inode, inode_val)| match &inode_val.kind {
+                Kind::File { path, .. } | Kind::Dir { path, .. }
+                    if !inode_val.is_preopened && path.starts_with(host_path) =>
+                {
+                    Some(inode)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (_, inode_val) in self.inodes.iter_mut() {
+            if let Kind::Dir { entries, .. } = &mut inode_val.kind {
+                entries.retain(|_, inode| !stale.contains(inode));
+            }
+        }
+    }
+
     pub fn get_fd(&self, fd: __wasi_fd_t) -> Result<&Fd, __wasi_errno_t> {
         self.fd_map.get(&fd).ok_or(__WASI_EBADF)
     }
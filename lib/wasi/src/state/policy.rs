@@ -0,0 +1,114 @@
+//! Per-path access rules, restricting what a WASI module can do with
+//! some of the files and directories below its preopened directories.
+
+use super::{ALL_RIGHTS, BASE_RIGHTS, READ_RIGHTS};
+use crate::syscalls::types::__wasi_rights_t;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The access a WASI module has to a path, and to everything below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathAccess {
+    /// The path is hidden: the module can't open, list or even see it.
+    Deny,
+    /// The module can read the path but not modify it.
+    ReadOnly,
+    /// The module can read and modify the path, within the rights of
+    /// the file descriptor it uses to reach it.
+    ReadWrite,
+}
+
+impl PathAccess {
+    /// The WASI rights granted by this access.
+    ///
+    /// They are a mask: a file descriptor never gets more rights than
+    /// the directory it was opened from, whatever the path allows.
+    pub fn rights(self) -> __wasi_rights_t {
+        match self {
+            Self::Deny => 0,
+            Self::ReadOnly => BASE_RIGHTS | READ_RIGHTS,
+            Self::ReadWrite => ALL_RIGHTS,
+        }
+    }
+}
+
+/// The access rules of a [`WasiFs`](super::WasiFs), keyed by host path.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct PathRules {
+    rules: Vec<(PathBuf, PathAccess)>,
+}
+
+impl PathRules {
+    /// Sets the access of `host_path`, replacing any previous rule for
+    /// the exact same path.
+    pub(crate) fn insert(&mut self, host_path: PathBuf, access: PathAccess) {
+        match self.rules.iter_mut().find(|(path, _)| *path == host_path) {
+            Some(rule) => rule.1 = access,
+            None => self.rules.push((host_path, access)),
+        }
+    }
+
+    /// Returns the access of `host_path`, given by the rule of its closest
+    /// ancestor (or itself), if any.
+    pub(crate) fn access(&self, host_path: &Path) -> Option<PathAccess> {
+        self.rules
+            .iter()
+            .filter(|(path, _)| host_path.starts_with(path))
+            .max_by_key(|(path, _)| path.components().count())
+            .map(|(_, access)| *access)
+    }
+
+    /// Returns the rights mask of `host_path`.
+    pub(crate) fn rights(&self, host_path: &Path) -> __wasi_rights_t {
+        self.access(host_path)
+            .map_or(ALL_RIGHTS, |access| access.rights())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::syscalls::types::{__WASI_RIGHT_FD_READ, __WASI_RIGHT_FD_WRITE};
+
+    #[test]
+    fn closest_rule_wins() {
+        let mut rules = PathRules::default();
+        rules.insert(PathBuf::from("data"), PathAccess::ReadOnly);
+        rules.insert(PathBuf::from("data/secrets"), PathAccess::Deny);
+        rules.insert(PathBuf::from("data/out"), PathAccess::ReadWrite);
+
+        assert_eq!(rules.access(Path::new("other")), None);
+        assert_eq!(
+            rules.access(Path::new("data/fixture.txt")),
+            Some(PathAccess::ReadOnly)
+        );
+        assert_eq!(
+            rules.access(Path::new("data/secrets/key")),
+            Some(PathAccess::Deny)
+        );
+        assert_eq!(
+            rules.access(Path::new("data/out/result.txt")),
+            Some(PathAccess::ReadWrite)
+        );
+        // Paths are compared component by component.
+        assert_eq!(
+            rules.access(Path::new("data/secrets2")),
+            Some(PathAccess::ReadOnly)
+        );
+    }
+
+    #[test]
+    fn rights_are_masks() {
+        let mut rules = PathRules::default();
+        assert_eq!(rules.rights(Path::new("anything")), ALL_RIGHTS);
+
+        rules.insert(PathBuf::from("data"), PathAccess::ReadWrite);
+        rules.insert(PathBuf::from("data"), PathAccess::ReadOnly);
+        let rights = rules.rights(Path::new("data/file"));
+        assert_ne!(rights & __WASI_RIGHT_FD_READ, 0);
+        assert_eq!(rights & __WASI_RIGHT_FD_WRITE, 0);
+
+        rules.insert(PathBuf::from("data"), PathAccess::Deny);
+        assert_eq!(rules.rights(Path::new("data/file")), 0);
+    }
+}
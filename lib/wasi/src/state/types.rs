@@ -10,7 +10,9 @@ use std::{
     fs,
     io::{self, Read, Seek, Write},
     path::PathBuf,
-    time::SystemTime,
+    sync::{Arc, Condvar, Mutex, MutexGuard, Weak},
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 use thiserror::Error;
 use tracing::debug;
@@ -211,6 +213,22 @@ pub trait WasiFile: fmt::Debug + Send + Write + Read + Seek + 'static + Upcastab
     fn get_raw_fd(&self) -> Option<i32> {
         None
     }
+
+    /// Used for polling files that have no host fd (see [`WasiFile::get_raw_fd`]).
+    /// Returns the events of `interest` that are ready now.  This function must not block.
+    /// Default reports every event as ready, like for a regular file
+    fn poll_ready(&self, interest: PollEventSet) -> Result<PollEventSet, WasiFsError> {
+        Ok(interest)
+    }
+
+    /// Used for polling files whose [`WasiFile::poll_ready`] can report
+    /// that they aren't ready.  Wakes `waker` up whenever the readiness
+    /// of the file may change, for as long as the waker is alive.
+    /// Returns `false` when the file can't do that, and is then checked
+    /// at regular intervals.  Default returns `false`
+    fn register_poll_waker(&self, _waker: &Arc<PollWaker>) -> bool {
+        false
+    }
 }
 
 // Implementation of `Upcastable` taken from https://users.rust-lang.org/t/why-does-downcasting-not-work-for-subtraits/33286/7 .
@@ -361,46 +379,188 @@ impl PollEventBuilder {
     }
 }
 
+/// How long [`poll`] waits between two checks of the files which can't wake
+/// it up.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Wakes up a [`poll`] waiting on files without a host fd, when their
+/// readiness may have changed.
+///
+/// See [`WasiFile::register_poll_waker`].
+#[derive(Debug, Default)]
+pub struct PollWaker {
+    /// how many times the waker was woken up
+    wakeups: Mutex<u64>,
+    woken: Condvar,
+}
+
+impl PollWaker {
+    /// Wakes up the [`poll`] waiting on this waker, if any.
+    pub fn wake(&self) {
+        *self.wakeups.lock().unwrap() += 1;
+        self.woken.notify_all();
+    }
+
+    fn wakeups(&self) -> u64 {
+        *self.wakeups.lock().unwrap()
+    }
+
+    /// Waits until the waker is woken up after `wakeups` were seen, or
+    /// until `timeout`.
+    fn wait(&self, wakeups: u64, timeout: Option<Duration>) {
+        let guard = self.wakeups.lock().unwrap();
+        match timeout {
+            Some(timeout) => {
+                let _ = self
+                    .woken
+                    .wait_timeout_while(guard, timeout, |seen| *seen == wakeups)
+                    .unwrap();
+            }
+            None => {
+                let _ = self
+                    .woken
+                    .wait_while(guard, |seen| *seen == wakeups)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Registers `waker` on the files of `selfs` without a host fd, returning
+/// whether all of them will wake it up.
+fn register_poll_waker(selfs: &[&dyn WasiFile], waker: &Arc<PollWaker>) -> bool {
+    selfs
+        .iter()
+        .filter(|file| file.get_raw_fd().is_none())
+        .fold(true, |all, file| file.register_poll_waker(waker) && all)
+}
+
+/// How long to wait until `deadline`, or forever.
+fn remaining(deadline: Option<Instant>) -> Option<Duration> {
+    deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Waits until some of the `events` are ready on `selfs`, or until `timeout`.
+///
+/// The events seen on each file are written to `seen_events`, and the number
+/// of files with events is returned.  Without a timeout, this blocks until an
+/// event is seen.
 #[cfg(unix)]
 pub(crate) fn poll(
     selfs: &[&dyn WasiFile],
     events: &[PollEventSet],
     seen_events: &mut [PollEventSet],
+    timeout: Option<Duration>,
 ) -> Result<u32, WasiFsError> {
     if !(selfs.len() == events.len() && events.len() == seen_events.len()) {
         return Err(WasiFsError::InvalidInput);
     }
-    let mut fds = selfs
-        .iter()
-        .enumerate()
-        .filter_map(|(i, s)| s.get_raw_fd().map(|rfd| (i, rfd)))
-        .map(|(i, host_fd)| libc::pollfd {
-            fd: host_fd,
-            events: poll_event_set_to_platform_poll_events(events[i]),
-            revents: 0,
-        })
-        .collect::<Vec<_>>();
-    let result = unsafe { libc::poll(fds.as_mut_ptr(), selfs.len() as _, 1) };
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let waker = Arc::new(PollWaker::default());
+    let wakeable = register_poll_waker(selfs, &waker);
+    loop {
+        let wakeups = waker.wakeups();
+        let mut seen = 0;
+        // files backed by a host fd are polled by the host, the others
+        // report their own readiness
+        let mut host_fds = vec![];
+        let mut fds = vec![];
+        for (i, file) in selfs.iter().enumerate() {
+            match file.get_raw_fd() {
+                Some(host_fd) => {
+                    host_fds.push(i);
+                    fds.push(libc::pollfd {
+                        fd: host_fd,
+                        events: poll_event_set_to_platform_poll_events(events[i]),
+                        revents: 0,
+                    });
+                }
+                None => {
+                    seen_events[i] = file.poll_ready(events[i])?;
+                    if seen_events[i] != 0 {
+                        seen += 1;
+                    }
+                }
+            }
+        }
+        if !fds.is_empty() {
+            // only wait on the host fds if nothing else is ready yet, and
+            // until the timeout if there is nothing else to check
+            let wait = if seen > 0 {
+                Some(Duration::from_secs(0))
+            } else if fds.len() == selfs.len() {
+                remaining(deadline)
+            } else {
+                Some(remaining(deadline).map_or(POLL_INTERVAL, |r| r.min(POLL_INTERVAL)))
+            };
+            let wait = wait.map_or(-1, |wait| {
+                // rounded up, so that the deadline is reached
+                let millis = (wait.as_nanos() + 999_999) / 1_000_000;
+                std::cmp::min(millis, libc::c_int::MAX as u128) as libc::c_int
+            });
+            let result = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as _, wait) };
+            if result < 0 {
+                // TODO: check errno and return value
+                return Err(WasiFsError::IOError);
+            }
+            // convert result and write back values
+            for (i, fd) in host_fds.into_iter().zip(fds) {
+                seen_events[i] = platform_poll_events_to_pollevent_set(fd.revents);
+                if seen_events[i] != 0 {
+                    seen += 1;
+                }
+            }
+        } else if seen == 0 {
+            wait_for_files(&waker, wakeable, wakeups, deadline);
+        }
 
-    if result < 0 {
-        // TODO: check errno and return value
-        return Err(WasiFsError::IOError);
-    }
-    // convert result and write back values
-    for (i, fd) in fds.into_iter().enumerate() {
-        seen_events[i] = platform_poll_events_to_pollevent_set(fd.revents);
+        if seen > 0 || deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+            return Ok(seen);
+        }
     }
-    // unwrap is safe because we check for negative values above
-    Ok(result.try_into().unwrap())
 }
 
+/// Waits until some of the `events` are ready on `selfs`, or until `timeout`.
+///
+/// Only files without a host fd can be polled on non-Unix-like targets.
 #[cfg(not(unix))]
 pub(crate) fn poll(
-    _selfs: &[&dyn WasiFile],
-    _events: &[PollEventSet],
-    _seen_events: &mut [PollEventSet],
-) -> Result<(), WasiFsError> {
-    unimplemented!("HostFile::poll in WasiFile is not implemented for non-Unix-like targets yet");
+    selfs: &[&dyn WasiFile],
+    events: &[PollEventSet],
+    seen_events: &mut [PollEventSet],
+    timeout: Option<Duration>,
+) -> Result<u32, WasiFsError> {
+    if !(selfs.len() == events.len() && events.len() == seen_events.len()) {
+        return Err(WasiFsError::InvalidInput);
+    }
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let waker = Arc::new(PollWaker::default());
+    let wakeable = register_poll_waker(selfs, &waker);
+    loop {
+        let wakeups = waker.wakeups();
+        let mut seen = 0;
+        for (i, file) in selfs.iter().enumerate() {
+            seen_events[i] = file.poll_ready(events[i])?;
+            if seen_events[i] != 0 {
+                seen += 1;
+            }
+        }
+        if seen > 0 || deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+            return Ok(seen);
+        }
+        wait_for_files(&waker, wakeable, wakeups, deadline);
+    }
+}
+
+/// Waits for files without a host fd to change, or until `deadline`: on
+/// `waker` if they all wake it up, or else for a [`POLL_INTERVAL`].
+fn wait_for_files(waker: &PollWaker, wakeable: bool, wakeups: u64, deadline: Option<Instant>) {
+    let remaining = remaining(deadline);
+    if wakeable {
+        waker.wait(wakeups, remaining);
+    } else {
+        thread::sleep(remaining.map_or(POLL_INTERVAL, |r| r.min(POLL_INTERVAL)));
+    }
 }
 
 pub trait WasiPath {}
@@ -1038,6 +1198,335 @@ impl WasiFile for Pipe {
     }
 }
 
+/// Creates a connected pair of in-memory pipes.
+///
+/// What is written to one end can be read from the other one, in both
+/// directions.  Reading from an empty pipe blocks until the other end writes
+/// something, or is dropped (then reading returns end-of-file).  This makes it
+/// possible to drive an interactive WASI module from the host, with one end
+/// given to the module as its `stdin` or `stdout` while the host keeps the
+/// other one.  The module must then run in another thread than the host end.
+///
+/// Usage:
+/// ```no_run
+/// # use wasmer_wasi::{channel_pipe, WasiState, WasiStateCreationError};
+/// # use std::io::Write;
+/// # fn main() -> Result<(), WasiStateCreationError> {
+/// let (guest_stdin, mut host_stdin) = channel_pipe();
+/// let (guest_stdout, host_stdout) = channel_pipe();
+/// let wasi_env = WasiState::new("repl")
+///     .stdin(Box::new(guest_stdin))
+///     .stdout(Box::new(guest_stdout))
+///     .finalize()?;
+/// // run the module in another thread, then talk to it
+/// host_stdin.write_all(b"1 + 1\n").unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn channel_pipe() -> (ChannelPipe, ChannelPipe) {
+    let a_to_b = Arc::new(Channel::default());
+    let b_to_a = Arc::new(Channel::default());
+    (
+        ChannelPipe::new(b_to_a.clone(), a_to_b.clone()),
+        ChannelPipe::new(a_to_b, b_to_a),
+    )
+}
+
+/// The bytes flowing in one direction of a [`ChannelPipe`].
+#[derive(Debug, Default)]
+struct Channel {
+    state: Mutex<ChannelState>,
+    /// notified when bytes are written, or when an end is closed
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    buffer: VecDeque<u8>,
+    /// nothing will be written anymore
+    writer_closed: bool,
+    /// nothing will be read anymore
+    reader_closed: bool,
+    /// the polls waiting for bytes to be written, or for an end to be closed
+    poll_wakers: Vec<Weak<PollWaker>>,
+}
+
+impl Channel {
+    /// A channel whose both ends are already closed.
+    fn closed() -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(ChannelState {
+                writer_closed: true,
+                reader_closed: true,
+                ..ChannelState::default()
+            }),
+            changed: Condvar::new(),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<ChannelState> {
+        // the lock is never held while running code that can panic
+        self.state.lock().unwrap()
+    }
+
+    /// Wakes up the readers and the polls waiting on the channel, once
+    /// `state` has changed.
+    fn notify(&self, state: &mut ChannelState) {
+        self.changed.notify_all();
+        state.poll_wakers.retain(|waker| match waker.upgrade() {
+            Some(waker) => {
+                waker.wake();
+                true
+            }
+            None => false,
+        });
+    }
+
+    fn register_poll_waker(&self, waker: &Arc<PollWaker>) {
+        let mut state = self.lock();
+        state.poll_wakers.retain(|waker| waker.strong_count() > 0);
+        state.poll_wakers.push(Arc::downgrade(waker));
+    }
+}
+
+/// One end of an in-memory pipe created by [`channel_pipe`].
+///
+/// Unlike [`Pipe`], reading waits for the other end to write.  The buffer
+/// of each direction is unbounded, so writing never blocks.
+///
+/// A `ChannelPipe` can't be carried over when freezing the WASI state: it is
+/// deserialized as a pipe whose other end is gone.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChannelPipe {
+    #[serde(skip, default = "Channel::closed")]
+    rx: Arc<Channel>,
+    #[serde(skip, default = "Channel::closed")]
+    tx: Arc<Channel>,
+    #[serde(skip)]
+    nonblocking: bool,
+    #[serde(skip)]
+    read_timeout: Option<Duration>,
+}
+
+impl ChannelPipe {
+    fn new(rx: Arc<Channel>, tx: Arc<Channel>) -> Self {
+        Self {
+            rx,
+            tx,
+            nonblocking: false,
+            read_timeout: None,
+        }
+    }
+
+    /// Makes reading from an empty pipe fail with
+    /// [`io::ErrorKind::WouldBlock`] instead of waiting.
+    pub fn set_nonblocking(&mut self, nonblocking: bool) {
+        self.nonblocking = nonblocking;
+    }
+
+    /// Sets how long reading from an empty pipe waits before failing with
+    /// [`io::ErrorKind::WouldBlock`].  `None`, the default, waits forever.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Closes the writing side of this end: once the other end has read
+    /// everything that was written, it reads end-of-file.
+    pub fn close_write(&mut self) {
+        let mut state = self.tx.lock();
+        state.writer_closed = true;
+        self.tx.notify(&mut state);
+    }
+
+    /// Returns whether the other end has been dropped.
+    pub fn is_closed(&self) -> bool {
+        self.rx.lock().writer_closed && self.tx.lock().reader_closed
+    }
+}
+
+impl Drop for ChannelPipe {
+    fn drop(&mut self) {
+        self.close_write();
+        let mut state = self.rx.lock();
+        state.reader_closed = true;
+        self.rx.notify(&mut state);
+    }
+}
+
+impl Read for ChannelPipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.rx.lock();
+        while state.buffer.is_empty() && !buf.is_empty() {
+            if state.writer_closed {
+                return Ok(0);
+            }
+            if self.nonblocking {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(io::ErrorKind::WouldBlock.into());
+                    }
+                    self.rx
+                        .changed
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.rx.changed.wait(state).unwrap(),
+            };
+        }
+        let amt = std::cmp::min(buf.len(), state.buffer.len());
+        for (i, byte) in state.buffer.drain(..amt).enumerate() {
+            buf[i] = byte;
+        }
+        Ok(amt)
+    }
+}
+
+impl Write for ChannelPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.tx.lock();
+        if state.reader_closed {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        state.buffer.extend(buf);
+        self.tx.notify(&mut state);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ChannelPipe {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek in a pipe",
+        ))
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for ChannelPipe {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        self.rx.lock().buffer.len() as u64
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Err(WasiFsError::InvalidInput)
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.rx.lock().buffer.len())
+    }
+    fn poll_ready(&self, interest: PollEventSet) -> Result<PollEventSet, WasiFsError> {
+        let mut ready = PollEventBuilder::new();
+        if interest & PollEvent::PollIn as PollEventSet != 0 {
+            let rx = self.rx.lock();
+            if !rx.buffer.is_empty() || rx.writer_closed {
+                ready = ready.add(PollEvent::PollIn);
+            }
+            if rx.buffer.is_empty() && rx.writer_closed {
+                ready = ready.add(PollEvent::PollHangUp);
+            }
+        }
+        if interest & PollEvent::PollOut as PollEventSet != 0 {
+            if self.tx.lock().reader_closed {
+                ready = ready.add(PollEvent::PollHangUp);
+            } else {
+                ready = ready.add(PollEvent::PollOut);
+            }
+        }
+        Ok(ready.build())
+    }
+    fn register_poll_waker(&self, waker: &Arc<PollWaker>) -> bool {
+        self.rx.register_poll_waker(waker);
+        self.tx.register_poll_waker(waker);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn channel_pipe_reads_what_the_other_end_writes() {
+        let (mut guest, mut host) = channel_pipe();
+        host.write_all(b"hello").unwrap();
+        guest.write_all(b"world").unwrap();
+        assert_eq!(guest.bytes_available(), Ok(5));
+
+        let mut buf = [0; 8];
+        assert_eq!(guest.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+        assert_eq!(host.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"world");
+    }
+
+    #[test]
+    fn channel_pipe_read_waits_for_the_other_end() {
+        let (mut guest, mut host) = channel_pipe();
+        let reader = thread::spawn(move || {
+            let mut line = String::new();
+            guest.read_to_string(&mut line).unwrap();
+            line
+        });
+        host.write_all(b"1 + 1\n").unwrap();
+        drop(host);
+        assert_eq!(reader.join().unwrap(), "1 + 1\n");
+    }
+
+    #[test]
+    fn channel_pipe_readiness() {
+        let (mut guest, mut host) = channel_pipe();
+        let read = PollEvent::PollIn as PollEventSet;
+        let write = PollEvent::PollOut as PollEventSet;
+        assert_eq!(guest.poll_ready(read), Ok(0));
+        assert_eq!(guest.poll_ready(write), Ok(write));
+
+        guest.set_nonblocking(true);
+        let mut buf = [0; 1];
+        assert_eq!(
+            guest.read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+
+        host.write_all(b"x").unwrap();
+        assert_eq!(guest.poll_ready(read), Ok(read));
+
+        host.close_write();
+        guest.read_exact(&mut buf).unwrap();
+        assert_eq!(
+            guest.poll_ready(read),
+            Ok(read | PollEvent::PollHangUp as PollEventSet)
+        );
+        assert_eq!(guest.read(&mut buf).unwrap(), 0);
+
+        drop(host);
+        assert!(guest.is_closed());
+        assert_eq!(
+            guest.write(b"x").unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+}
+
 /*
 TODO: Think about using this
 trait WasiFdBacking: std::fmt::Debug {
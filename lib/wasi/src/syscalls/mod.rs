@@ -18,7 +18,7 @@ use crate::{
     ptr::{Array, WasmPtr},
     state::{
         self, host_file_type_to_wasi_file_type, iterate_poll_events, poll, Fd, HostFile, Inode,
-        InodeVal, Kind, PollEvent, PollEventBuilder, WasiFile, WasiFsError, WasiState,
+        InodeVal, Kind, PollEvent, PollEventBuilder, WasiFile, WasiFsError, WasiState, ALL_RIGHTS,
         MAX_SYMLINKS,
     },
     WasiEnv, WasiError,
@@ -52,7 +52,7 @@ fn write_bytes_inner<T: Write>(
         let bytes = iov_inner.buf.deref(memory, 0, iov_inner.buf_len)?;
         write_loc
             .write_all(&bytes.iter().map(|b_cell| b_cell.get()).collect::<Vec<u8>>())
-            .map_err(|_| __WASI_EIO)?;
+            .map_err(|e| WasiFsError::from(e).into_wasi_err())?;
 
         // TODO: handle failure more accurately
         bytes_written += iov_inner.buf_len;
@@ -82,7 +82,17 @@ fn read_bytes<T: Read>(
         let bytes = iov_inner.buf.deref(memory, 0, iov_inner.buf_len)?;
         let mut raw_bytes: &mut [u8] =
             unsafe { &mut *(bytes as *const [_] as *mut [_] as *mut [u8]) };
-        bytes_read += reader.read(raw_bytes).map_err(|_| __WASI_EIO)? as u32;
+        let amt = reader
+            .read(raw_bytes)
+            .map_err(|e| WasiFsError::from(e).into_wasi_err())?;
+        bytes_read += amt as u32;
+        // a short read means there is nothing more to read for now: reading
+        // the next buffer could block, e.g. on a pipe.  Files and buffers
+        // only read short at their end, where reading on would read nothing,
+        // so like `readv` this only changes how much is read from streams
+        if amt < raw_bytes.len() {
+            break;
+        }
     }
     Ok(bytes_read)
 }
@@ -311,6 +321,11 @@ pub fn fd_advise(
     advice: __wasi_advice_t,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_advise: fd={}", fd);
+    let state = env.state();
+    let fd_entry = wasi_try!(state.fs.get_fd(fd));
+    if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_ADVISE) {
+        return __WASI_EACCES;
+    }
 
     // this is used for our own benefit, so just returning success is a valid
     // implementation for now
@@ -973,6 +988,9 @@ pub fn fd_readdir(
     let buf_arr_cell = wasi_try!(buf.deref(memory, 0, buf_len));
     let bufused_cell = wasi_try!(bufused.deref(memory));
     let working_dir = wasi_try!(state.fs.fd_map.get(&fd).ok_or(__WASI_EBADF));
+    if !has_rights(working_dir.rights, __WASI_RIGHT_FD_READDIR) {
+        return __WASI_EACCES;
+    }
     let mut cur_cookie = cookie;
     let mut buf_idx = 0;
 
@@ -987,6 +1005,8 @@ pub fn fd_readdir(
                 .map_err(|_| __WASI_EIO));
             let mut entry_vec = wasi_try!(fs_info
                 .into_iter()
+                // denied paths are hidden from the listing too
+                .filter(|entry| state.fs.path_rights(&entry.path()) != 0)
                 .map(|entry| Ok((
                     entry.file_name().to_string_lossy().to_string(),
                     host_file_type_to_wasi_file_type(entry.file_type().map_err(|_| __WASI_EIO)?),
@@ -1394,9 +1414,17 @@ pub fn path_create_directory(
                     let mut adjusted_path = path.clone();
                     // TODO: double check this doesn't risk breaking the sandbox
                     adjusted_path.push(comp);
+                    let path_rights = state.fs.path_rights(&adjusted_path)
+                        & state.fs.preopen_rights(cur_dir_inode);
+                    if path_rights == 0 {
+                        return __WASI_ENOENT;
+                    }
                     if adjusted_path.exists() && !adjusted_path.is_dir() {
                         return __WASI_ENOTDIR;
                     } else if !adjusted_path.exists() {
+                        if !has_rights(path_rights, __WASI_RIGHT_PATH_CREATE_DIRECTORY) {
+                            return __WASI_EACCES;
+                        }
                         wasi_try!(std::fs::create_dir(&adjusted_path).ok(), __WASI_EIO);
                     }
                     let kind = Kind::Dir {
@@ -1526,6 +1554,12 @@ pub fn path_filestat_set_times(
         path_string,
         flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
     ));
+    if !has_rights(
+        state.fs.inode_rights(file_inode),
+        __WASI_RIGHT_PATH_FILESTAT_SET_TIMES,
+    ) {
+        return __WASI_EACCES;
+    }
     let stat = wasi_try!(state
         .fs
         .get_stat_for_kind(&state.fs.inodes[file_inode].kind)
@@ -1622,6 +1656,15 @@ pub fn path_link(
         wasi_try!(state
             .fs
             .get_parent_inode_at_path(new_fd, &target_path_arg, false));
+    if !(has_rights(
+        state.fs.inode_rights(source_inode),
+        __WASI_RIGHT_PATH_LINK_SOURCE,
+    ) && has_rights(
+        state.fs.inode_rights(target_parent_inode),
+        __WASI_RIGHT_PATH_LINK_TARGET,
+    )) {
+        return __WASI_EACCES;
+    }
 
     if state.fs.inodes[source_inode].stat.st_nlink == __wasi_linkcount_t::max_value() {
         return __WASI_EMLINK;
@@ -1702,6 +1745,16 @@ pub fn path_open(
     if !has_rights(working_dir.rights, __WASI_RIGHT_PATH_OPEN) {
         return __WASI_EACCES;
     }
+    if o_flags & __WASI_O_CREAT != 0
+        && !has_rights(working_dir.rights, __WASI_RIGHT_PATH_CREATE_FILE)
+    {
+        return __WASI_EACCES;
+    }
+    if o_flags & __WASI_O_TRUNC != 0
+        && !has_rights(working_dir.rights, __WASI_RIGHT_PATH_FILESTAT_SET_SIZE)
+    {
+        return __WASI_EACCES;
+    }
     let path_string = unsafe { get_input_str!(memory, path, path_len) };
 
     debug!("=> fd: {}, path: {}", dirfd, &path_string);
@@ -1714,10 +1767,31 @@ pub fn path_open(
     );
 
     let mut open_flags = 0;
-    // TODO: traverse rights of dirs properly
-    // COMMENTED OUT: WASI isn't giving appropriate rights here when opening
-    //              TODO: look into this; file a bug report if this is a bug
-    let adjusted_rights = /*fs_rights_base &*/ working_dir_rights_inheriting;
+    // the new fd gets the rights inherited from the directory, within the
+    // access rules of the path; many modules ask for fewer rights than they
+    // use, so the rights asked for are only enforced on demand
+    let path_rights = match maybe_inode {
+        Ok(inode) => state.fs.inode_rights(inode),
+        Err(_) => ALL_RIGHTS,
+    };
+    let requested_rights = if state.fs.enforce_requested_rights {
+        fs_rights_base
+    } else {
+        ALL_RIGHTS
+    };
+    let adjusted_rights = requested_rights & working_dir_rights_inheriting & path_rights;
+    let adjusted_rights_inheriting =
+        fs_rights_inheriting & working_dir_rights_inheriting & path_rights;
+    // asking to write where it's not allowed fails now, rather than on the
+    // first write
+    if fs_rights_base & __WASI_RIGHT_FD_WRITE != 0 && adjusted_rights & __WASI_RIGHT_FD_WRITE == 0 {
+        return __WASI_EACCES;
+    }
+    if o_flags & __WASI_O_TRUNC != 0
+        && !has_rights(path_rights, __WASI_RIGHT_PATH_FILESTAT_SET_SIZE)
+    {
+        return __WASI_EACCES;
+    }
     let inode = if let Ok(inode) = maybe_inode {
         // Happy path, we found the file we're trying to open
         match &mut state.fs.inodes[inode].kind {
@@ -1818,6 +1892,12 @@ pub fn path_open(
                 Kind::Root { .. } => return __WASI_EACCES,
                 _ => return __WASI_EINVAL,
             };
+            if !has_rights(
+                state.fs.path_rights(&new_file_host_path) & state.fs.preopen_rights(parent_inode),
+                __WASI_RIGHT_PATH_CREATE_FILE,
+            ) {
+                return __WASI_EACCES;
+            }
             // once we got the data we need from the parent, we lookup the host file
             // todo: extra check that opening with write access is okay
             let handle = {
@@ -1870,11 +1950,10 @@ pub fn path_open(
         inode, state.fs.inodes[inode]
     );
 
-    // TODO: check and reduce these
     // TODO: ensure a mutable fd to root can never be opened
     let out_fd = wasi_try!(state.fs.create_fd(
         adjusted_rights,
-        fs_rights_inheriting,
+        adjusted_rights_inheriting,
         fs_flags,
         open_flags,
         inode
@@ -1958,9 +2037,18 @@ pub fn path_remove_directory(
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
 
     let base_dir = wasi_try!(state.fs.fd_map.get(&fd), __WASI_EBADF);
+    if !has_rights(base_dir.rights, __WASI_RIGHT_PATH_REMOVE_DIRECTORY) {
+        return __WASI_EACCES;
+    }
     let path_str = unsafe { get_input_str!(memory, path, path_len) };
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
+    if !has_rights(
+        state.fs.inode_rights(inode),
+        __WASI_RIGHT_PATH_REMOVE_DIRECTORY,
+    ) {
+        return __WASI_EACCES;
+    }
     let (parent_inode, childs_name) =
         wasi_try!(state
             .fs
@@ -1993,7 +2081,7 @@ pub fn path_remove_directory(
         ),
     }
 
-    if std::fs::remove_dir(path_str).is_err() {
+    if std::fs::remove_dir(&host_path_to_remove).is_err() {
         // reinsert to prevent FS from being in bad state
         if let Kind::Dir {
             ref mut entries, ..
@@ -2058,6 +2146,18 @@ pub fn path_rename(
         wasi_try!(state.fs.get_parent_inode_at_path(old_fd, source_path, true));
     let (target_parent_inode, target_entry_name) =
         wasi_try!(state.fs.get_parent_inode_at_path(new_fd, target_path, true));
+    {
+        let source_inode = wasi_try!(state.fs.get_inode_at_path(old_fd, source_str, true));
+        if !(has_rights(
+            state.fs.inode_rights(source_inode),
+            __WASI_RIGHT_PATH_RENAME_SOURCE | __WASI_RIGHT_PATH_UNLINK_FILE,
+        ) && has_rights(
+            state.fs.inode_rights(target_parent_inode),
+            __WASI_RIGHT_PATH_RENAME_TARGET,
+        )) {
+            return __WASI_EACCES;
+        }
+    }
 
     let host_adjusted_target_path = match &state.fs.inodes[target_parent_inode].kind {
         Kind::Dir { entries, path, .. } => {
@@ -2159,6 +2259,12 @@ pub fn path_symlink(
     let new_path_path = std::path::Path::new(new_path_str);
     let (target_parent_inode, entry_name) =
         wasi_try!(state.fs.get_parent_inode_at_path(fd, new_path_path, true));
+    if !has_rights(
+        state.fs.inode_rights(target_parent_inode),
+        __WASI_RIGHT_PATH_SYMLINK,
+    ) {
+        return __WASI_EACCES;
+    }
 
     // short circuit if anything is wrong, before we create an inode
     match &state.fs.inodes[target_parent_inode].kind {
@@ -2230,6 +2336,9 @@ pub fn path_unlink_file(
     debug!("Requested file: {}", path_str);
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
+    if !has_rights(state.fs.inode_rights(inode), __WASI_RIGHT_PATH_UNLINK_FILE) {
+        return __WASI_EACCES;
+    }
     let (parent_inode, childs_name) =
         wasi_try!(state
             .fs
@@ -2324,14 +2433,14 @@ pub fn poll_oneoff(
     let out_ptr = wasi_try!(nevents.deref(memory));
 
     let mut fds = vec![];
+    // the index of the subscription of each polled fd
+    let mut fd_subs = vec![];
     let mut clock_subs = vec![];
     let mut in_events = vec![];
-    let mut total_ns_slept = 0;
 
-    for sub in subscription_array.iter() {
+    for (sub_index, sub) in subscription_array.iter().enumerate() {
         let s: WasiSubscription = wasi_try!(sub.get().try_into());
         let mut peb = PollEventBuilder::new();
-        let mut ns_to_sleep = 0;
 
         let fd = match s.event_type {
             EventType::Read(__wasi_subscription_fs_readwrite_t { fd }) => {
@@ -2345,7 +2454,7 @@ pub fn poll_oneoff(
                     }
                 }
                 in_events.push(peb.add(PollEvent::PollIn).build());
-                Some(fd)
+                fd
             }
             EventType::Write(__wasi_subscription_fs_readwrite_t { fd }) => {
                 match fd {
@@ -2359,82 +2468,88 @@ pub fn poll_oneoff(
                     }
                 }
                 in_events.push(peb.add(PollEvent::PollOut).build());
-                Some(fd)
+                fd
             }
             EventType::Clock(clock_info) => {
                 if clock_info.clock_id == __WASI_CLOCK_REALTIME {
-                    // this is a hack
-                    // TODO: do this properly
-                    ns_to_sleep = clock_info.timeout;
-                    clock_subs.push(clock_info);
-                    None
+                    clock_subs.push((sub_index, clock_info));
+                    continue;
                 } else {
                     unimplemented!("Polling not implemented for clocks yet");
                 }
             }
         };
 
-        if let Some(fd) = fd {
-            let wasi_file_ref: &dyn WasiFile = match fd {
-                __WASI_STDERR_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stderr().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                __WASI_STDIN_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stdin().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                __WASI_STDOUT_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stdout().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                _ => {
-                    let fd_entry = wasi_try!(state.fs.get_fd(fd));
-                    let inode = fd_entry.inode;
-                    if !has_rights(fd_entry.rights, __WASI_RIGHT_POLL_FD_READWRITE) {
-                        return __WASI_EACCES;
-                    }
+        let wasi_file_ref: &dyn WasiFile = match fd {
+            __WASI_STDERR_FILENO => wasi_try!(
+                wasi_try!(state.fs.stderr().map_err(WasiFsError::into_wasi_err)).as_ref(),
+                __WASI_EBADF
+            )
+            .as_ref(),
+            __WASI_STDIN_FILENO => wasi_try!(
+                wasi_try!(state.fs.stdin().map_err(WasiFsError::into_wasi_err)).as_ref(),
+                __WASI_EBADF
+            )
+            .as_ref(),
+            __WASI_STDOUT_FILENO => wasi_try!(
+                wasi_try!(state.fs.stdout().map_err(WasiFsError::into_wasi_err)).as_ref(),
+                __WASI_EBADF
+            )
+            .as_ref(),
+            _ => {
+                let fd_entry = wasi_try!(state.fs.get_fd(fd));
+                let inode = fd_entry.inode;
+                if !has_rights(fd_entry.rights, __WASI_RIGHT_POLL_FD_READWRITE) {
+                    return __WASI_EACCES;
+                }
 
-                    match &state.fs.inodes[inode].kind {
-                        Kind::File { handle, .. } => {
-                            if let Some(h) = handle {
-                                h.as_ref()
-                            } else {
-                                return __WASI_EBADF;
-                            }
-                        }
-                        Kind::Dir { .. }
-                        | Kind::Root { .. }
-                        | Kind::Buffer { .. }
-                        | Kind::Symlink { .. } => {
-                            unimplemented!("polling read on non-files not yet supported")
+                match &state.fs.inodes[inode].kind {
+                    Kind::File { handle, .. } => {
+                        if let Some(h) = handle {
+                            h.as_ref()
+                        } else {
+                            return __WASI_EBADF;
                         }
                     }
+                    Kind::Dir { .. }
+                    | Kind::Root { .. }
+                    | Kind::Buffer { .. }
+                    | Kind::Symlink { .. } => {
+                        unimplemented!("polling read on non-files not yet supported")
+                    }
                 }
-            };
-            fds.push(wasi_file_ref);
-        } else {
-            let remaining_ns = ns_to_sleep as i64 - total_ns_slept as i64;
-            if remaining_ns > 0 {
-                debug!("Sleeping for {} nanoseconds", remaining_ns);
-                let duration = std::time::Duration::from_nanos(remaining_ns as u64);
-                std::thread::sleep(duration);
-                total_ns_slept += remaining_ns;
             }
-        }
+        };
+        fds.push(wasi_file_ref);
+        fd_subs.push(sub_index);
     }
+
+    // the clocks are relative timeouts: the soonest one bounds the wait
+    let timeout = clock_subs
+        .iter()
+        .map(|(_, clock_info)| std::time::Duration::from_nanos(clock_info.timeout))
+        .min();
+
     let mut seen_events = vec![Default::default(); in_events.len()];
-    wasi_try!(poll(
-        fds.as_slice(),
-        in_events.as_slice(),
-        seen_events.as_mut_slice()
-    )
-    .map_err(|e| e.into_wasi_err()));
+    if fds.is_empty() {
+        if let Some(timeout) = timeout {
+            debug!("Sleeping for {:?}", timeout);
+            std::thread::sleep(timeout);
+        }
+    } else {
+        wasi_try!(poll(
+            fds.as_slice(),
+            in_events.as_slice(),
+            seen_events.as_mut_slice(),
+            timeout,
+        )
+        .map_err(|e| e.into_wasi_err()));
+    }
 
     for (i, seen_event) in seen_events.into_iter().enumerate() {
+        if seen_event == 0 {
+            continue;
+        }
         let mut flags = 0;
         let mut error = __WASI_EAGAIN;
         let mut bytes_available = 0;
@@ -2456,10 +2571,11 @@ pub fn poll_oneoff(
                 }
             }
         }
+        let sub = subscription_array[fd_subs[i]].get();
         let event = __wasi_event_t {
-            userdata: subscription_array[i].get().userdata,
+            userdata: sub.userdata,
             error,
-            type_: subscription_array[i].get().type_,
+            type_: sub.type_,
             u: unsafe {
                 __wasi_event_u {
                     fd_readwrite: __wasi_event_fd_readwrite_t {
@@ -2472,23 +2588,28 @@ pub fn poll_oneoff(
         event_array[events_seen].set(event);
         events_seen += 1;
     }
-    for clock_info in clock_subs {
-        let event = __wasi_event_t {
-            // TOOD: review userdata value
-            userdata: 0,
-            error: __WASI_ESUCCESS,
-            type_: __WASI_EVENTTYPE_CLOCK,
-            u: unsafe {
-                __wasi_event_u {
-                    fd_readwrite: __wasi_event_fd_readwrite_t {
-                        nbytes: 0,
-                        flags: 0,
-                    },
-                }
-            },
-        };
-        event_array[events_seen].set(event);
-        events_seen += 1;
+    // the clocks only fire when no fd became ready before them
+    if events_seen == 0 {
+        for (sub_index, clock_info) in clock_subs {
+            if Some(std::time::Duration::from_nanos(clock_info.timeout)) != timeout {
+                continue;
+            }
+            let event = __wasi_event_t {
+                userdata: subscription_array[sub_index].get().userdata,
+                error: __WASI_ESUCCESS,
+                type_: __WASI_EVENTTYPE_CLOCK,
+                u: unsafe {
+                    __wasi_event_u {
+                        fd_readwrite: __wasi_event_fd_readwrite_t {
+                            nbytes: 0,
+                            flags: 0,
+                        },
+                    }
+                },
+            };
+            event_array[events_seen].set(event);
+            events_seen += 1;
+        }
     }
     out_ptr.set(events_seen as u32);
     __WASI_ESUCCESS
@@ -2563,3 +2684,257 @@ pub fn sock_shutdown(env: &WasiEnv, sock: __wasi_fd_t, how: __wasi_sdflags_t) ->
     debug!("wasi::sock_shutdown");
     unimplemented!("wasi::sock_shutdown")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{channel_pipe, ChannelPipe, Pipe, WasiState, WasiStateBuilder};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use wasmer::{MemoryType, Store, JIT};
+
+    /// A WASI env with `stdin`, and a memory for the arguments of the calls.
+    fn env(stdin: Box<dyn WasiFile>) -> WasiEnv {
+        let store = Store::new(&JIT::headless().engine());
+        let memory = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+        let mut env = WasiState::new("test").stdin(stdin).finalize().unwrap();
+        env.memory.initialize(memory);
+        env
+    }
+
+    /// Reads stdin into buffers of `lens` bytes, returning what was read.
+    fn read_stdin(env: &WasiEnv, lens: &[u32]) -> Result<Vec<u8>, __wasi_errno_t> {
+        let iovs = WasmPtr::<__wasi_iovec_t, Array>::new(0);
+        let nread = WasmPtr::<u32>::new(64);
+        let mut offset = 128;
+        for (iov, &len) in iovs
+            .deref(env.memory(), 0, lens.len() as u32)
+            .unwrap()
+            .iter()
+            .zip(lens)
+        {
+            iov.set(__wasi_iovec_t {
+                buf: WasmPtr::new(offset),
+                buf_len: len,
+            });
+            offset += len;
+        }
+        match fd_read(env, __WASI_STDIN_FILENO, iovs, lens.len() as u32, nread) {
+            __WASI_ESUCCESS => {}
+            errno => return Err(errno),
+        }
+        let nread = nread.deref(env.memory()).unwrap().get();
+        let bytes = WasmPtr::<u8, Array>::new(128)
+            .deref(env.memory(), 0, nread)
+            .unwrap();
+        Ok(bytes.iter().map(Cell::get).collect())
+    }
+
+    #[test]
+    fn fd_read_fills_all_buffers_from_files() {
+        let mut stdin = Pipe::new();
+        stdin.write_all(b"0123456789").unwrap();
+        let env = env(Box::new(stdin));
+        assert_eq!(read_stdin(&env, &[4, 4, 4]), Ok(b"0123456789".to_vec()));
+        assert_eq!(read_stdin(&env, &[4, 4]), Ok(vec![]));
+    }
+
+    #[test]
+    fn fd_read_stops_at_what_a_pipe_has_to_read() {
+        let (mut guest, mut host) = channel_pipe();
+        // reading more than what was written would fail after a second
+        guest.set_read_timeout(Some(Duration::from_secs(1)));
+        host.write_all(b"1 + 1\n").unwrap();
+        let env = env(Box::new(guest));
+
+        assert_eq!(read_stdin(&env, &[4, 8, 8]), Ok(b"1 + 1\n".to_vec()));
+        assert_eq!(read_stdin(&env, &[4]), Err(__WASI_EAGAIN));
+
+        host.write_all(b"2").unwrap();
+        drop(host);
+        assert_eq!(read_stdin(&env, &[4, 4]), Ok(b"2".to_vec()));
+        assert_eq!(read_stdin(&env, &[4]), Ok(vec![]));
+    }
+
+    /// Polls stdin for reading, with a timeout, returning the userdata of
+    /// the events: `1` for stdin, and `2` for the timeout.
+    fn poll_stdin(env: &WasiEnv, timeout: Duration) -> Vec<__wasi_userdata_t> {
+        let subscriptions = WasmPtr::<__wasi_subscription_t, Array>::new(0);
+        let events = WasmPtr::<__wasi_event_t, Array>::new(512);
+        let nevents = WasmPtr::<u32>::new(1024);
+        let cells = subscriptions.deref(env.memory(), 0, 2).unwrap();
+        cells[0].set(__wasi_subscription_t {
+            userdata: 1,
+            type_: __WASI_EVENTTYPE_FD_READ,
+            u: __wasi_subscription_u {
+                fd_readwrite: __wasi_subscription_fs_readwrite_t {
+                    fd: __WASI_STDIN_FILENO,
+                },
+            },
+        });
+        cells[1].set(__wasi_subscription_t {
+            userdata: 2,
+            type_: __WASI_EVENTTYPE_CLOCK,
+            u: __wasi_subscription_u {
+                clock: __wasi_subscription_clock_t {
+                    clock_id: __WASI_CLOCK_REALTIME,
+                    timeout: timeout.as_nanos() as u64,
+                    precision: 0,
+                    flags: 0,
+                },
+            },
+        });
+        assert_eq!(
+            poll_oneoff(env, subscriptions, events, 2, nevents),
+            __WASI_ESUCCESS
+        );
+        let nevents = nevents.deref(env.memory()).unwrap().get();
+        events
+            .deref(env.memory(), 0, nevents)
+            .unwrap()
+            .iter()
+            .map(|event| event.get().userdata)
+            .collect()
+    }
+
+    #[test]
+    fn poll_oneoff_times_out_on_empty_pipes() {
+        let (guest, _host) = channel_pipe();
+        let env = env(Box::new(guest));
+        assert_eq!(poll_stdin(&env, Duration::from_millis(10)), vec![2]);
+    }
+
+    #[test]
+    fn poll_oneoff_wakes_up_when_a_pipe_is_written_to() {
+        let (guest, mut host): (ChannelPipe, ChannelPipe) = channel_pipe();
+        let env = env(Box::new(guest));
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            host.write_all(b"x").unwrap();
+            host
+        });
+
+        let start = Instant::now();
+        assert_eq!(poll_stdin(&env, Duration::from_secs(60)), vec![1]);
+        assert!(start.elapsed() < Duration::from_secs(30));
+        let _host = writer.join().unwrap();
+        assert_eq!(read_stdin(&env, &[4]), Ok(b"x".to_vec()));
+    }
+
+    /// Makes a scratch directory holding an empty file `f`.
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "wasmer-wasi-rights-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("f"), b"").unwrap();
+        dir
+    }
+
+    /// A WASI env with the directories preopened by `builder`, and a memory
+    /// for the arguments of the calls.
+    fn env_with_dirs(builder: &mut WasiStateBuilder) -> WasiEnv {
+        let store = Store::new(&JIT::headless().engine());
+        let memory = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+        let mut env = builder.finalize().unwrap();
+        env.memory.initialize(memory);
+        env
+    }
+
+    /// Opens `path` relative to `dirfd` asking only for
+    /// `__WASI_RIGHT_FD_READ`, returning the rights of the new fd.
+    fn open_for_reading(env: &WasiEnv, dirfd: __wasi_fd_t, path: &str) -> __wasi_rights_t {
+        let path_ptr = WasmPtr::<u8, Array>::new(0);
+        for (cell, byte) in path_ptr
+            .deref(env.memory(), 0, path.len() as u32)
+            .unwrap()
+            .iter()
+            .zip(path.bytes())
+        {
+            cell.set(byte);
+        }
+        let fd = WasmPtr::<__wasi_fd_t>::new(256);
+        let errno = path_open(
+            env,
+            dirfd,
+            0,
+            path_ptr,
+            path.len() as u32,
+            0,
+            __WASI_RIGHT_FD_READ,
+            0,
+            0,
+            fd,
+        );
+        assert_eq!(errno, __WASI_ESUCCESS);
+        let fd = fd.deref(env.memory()).unwrap().get();
+        env.state().fs.get_fd(fd).unwrap().rights
+    }
+
+    #[test]
+    fn path_open_gives_the_rights_of_the_directory() {
+        let dir = scratch_dir("inherited");
+        let env = env_with_dirs(WasiState::new("test").map_dir("dir", &dir).unwrap());
+        let dirfd = env.state().fs.preopen_fds[1];
+        let rights = open_for_reading(&env, dirfd, "f");
+        assert_ne!(rights & __WASI_RIGHT_FD_READ, 0);
+        assert_ne!(rights & __WASI_RIGHT_FD_WRITE, 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn path_open_can_enforce_the_rights_asked_for() {
+        let dir = scratch_dir("enforced");
+        let env = env_with_dirs(
+            WasiState::new("test")
+                .map_dir("dir", &dir)
+                .unwrap()
+                .enforce_requested_rights(true),
+        );
+        let dirfd = env.state().fs.preopen_fds[1];
+        assert_eq!(open_for_reading(&env, dirfd, "f"), __WASI_RIGHT_FD_READ);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn read_only_preopens_only_restrict_themselves() {
+        let dir = scratch_dir("read-only");
+        let env = env_with_dirs(
+            WasiState::new("test")
+                .map_dir_read_only("ro", &dir)
+                .unwrap()
+                .map_dir("rw", &dir)
+                .unwrap(),
+        );
+        let (root_fd, ro_fd, rw_fd) = {
+            let state = env.state();
+            let preopen_fds = &state.fs.preopen_fds;
+            (preopen_fds[0], preopen_fds[1], preopen_fds[2])
+        };
+
+        assert_eq!(
+            open_for_reading(&env, ro_fd, "f") & __WASI_RIGHT_FD_WRITE,
+            0
+        );
+        assert_ne!(
+            open_for_reading(&env, rw_fd, "f") & __WASI_RIGHT_FD_WRITE,
+            0
+        );
+        // reaching a directory from another fd keeps its rights
+        assert_eq!(
+            open_for_reading(&env, root_fd, "ro/f") & __WASI_RIGHT_FD_WRITE,
+            0
+        );
+        assert_eq!(
+            open_for_reading(&env, rw_fd, "../ro/f") & __WASI_RIGHT_FD_WRITE,
+            0
+        );
+        assert_ne!(
+            open_for_reading(&env, root_fd, "rw/f") & __WASI_RIGHT_FD_WRITE,
+            0
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
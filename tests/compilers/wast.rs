@@ -27,9 +27,21 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     let mut features = Features::default();
     let is_bulkmemory = wast_path.contains("bulk-memory");
     let is_simd = wast_path.contains("simd");
+    let is_multi_memory = wast_path.contains("multi-memory");
+    let is_exceptions = wast_path.contains("exceptions");
+    let is_tail_call = wast_path.contains("tail-call");
     if is_bulkmemory {
         features.bulk_memory(true);
     }
+    if is_multi_memory {
+        features.multi_memory(true);
+    }
+    if is_exceptions {
+        features.exceptions(true);
+    }
+    if is_tail_call {
+        features.tail_call(true);
+    }
     if is_simd {
         features.simd(true);
     }
@@ -77,5 +89,17 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     }
     wast.fail_fast = false;
     let path = Path::new(wast_path);
-    wast.run_file(path)
+    let result = wast.run_file(path);
+    // Reports are kept per compiler and engine, to track their conformance.
+    if let Some(report_dir) = std::env::var_os("WASMER_WAST_REPORT_DIR") {
+        let report_dir = Path::new(&report_dir)
+            .join(format!("{:?}-{:?}", config.compiler, config.engine).to_lowercase());
+        std::fs::create_dir_all(&report_dir)?;
+        let report_name = format!(
+            "{}.json",
+            wast_path.replace(|c: char| c == '/' || c == '\\', "_")
+        );
+        std::fs::write(report_dir.join(report_name), wast.report().to_json())?;
+    }
+    result
 }
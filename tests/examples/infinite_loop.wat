@@ -0,0 +1,4 @@
+(module
+  (func (export "_start")
+    (loop $continue
+      (br $continue))))
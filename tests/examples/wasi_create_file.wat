@@ -0,0 +1,20 @@
+(module
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "file.txt")
+  ;; Creates `file.txt` for writing in the first pre-opened directory, and
+  ;; exits with the WASI error code of `path_open`.
+  (func (export "_start")
+    (call $proc_exit
+      (call $path_open
+        (i32.const 4)   ;; the first pre-opened directory
+        (i32.const 0)   ;; lookup flags
+        (i32.const 16)  ;; path
+        (i32.const 8)   ;; path length
+        (i32.const 1)   ;; `O_CREAT`
+        (i64.const 64)  ;; `FD_WRITE`
+        (i64.const 0)   ;; inherited rights
+        (i32.const 0)   ;; fd flags
+        (i32.const 0))))) ;; where the new fd is stored
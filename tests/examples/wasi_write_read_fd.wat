@@ -0,0 +1,36 @@
+(module
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "file.txt")
+  ;; the iovec of the write: 2 bytes at offset 32
+  (data (i32.const 24) "\20\00\00\00\02\00\00\00")
+  (data (i32.const 32) "ok")
+  ;; Creates `file.txt` in the first pre-opened directory asking only for
+  ;; `FD_READ`, writes to it, and exits with the WASI error code of
+  ;; `path_open`, or of `fd_write` if `path_open` succeeded.
+  (func (export "_start")
+    (local $errno i32)
+    (local.set $errno
+      (call $path_open
+        (i32.const 4)   ;; the first pre-opened directory
+        (i32.const 0)   ;; lookup flags
+        (i32.const 16)  ;; path
+        (i32.const 8)   ;; path length
+        (i32.const 1)   ;; `O_CREAT`
+        (i64.const 2)   ;; `FD_READ`
+        (i64.const 0)   ;; inherited rights
+        (i32.const 0)   ;; fd flags
+        (i32.const 0))) ;; where the new fd is stored
+    (if (i32.eqz (local.get $errno))
+      (then
+        (local.set $errno
+          (call $fd_write
+            (i32.load (i32.const 0)) ;; the new fd
+            (i32.const 24)           ;; iovecs
+            (i32.const 1)            ;; iovec count
+            (i32.const 40)))))       ;; where the written size is stored
+    (call $proc_exit (local.get $errno))))
@@ -0,0 +1,56 @@
+//! Basic tests for the `cache` subcommand
+
+use std::process::Command;
+use wasmer_integration_tests_cli::{C_ASSET_PATH, WASMER_PATH};
+
+fn wasi_test_wasm_path() -> String {
+    format!("{}/{}", C_ASSET_PATH, "qjs.wasm")
+}
+
+#[test]
+fn cache_stats_and_prune() -> anyhow::Result<()> {
+    let cache_dir = tempfile::tempdir()?;
+    let wasmer = |args: &[&str]| {
+        Command::new(WASMER_PATH)
+            .env("WASMER_CACHE_DIR", cache_dir.path())
+            .env_remove("WASMER_CACHE_MAX_SIZE")
+            .args(args)
+            .output()
+    };
+
+    // Running a module stores its artifact in the cache.
+    let output = wasmer(&["run", &wasi_test_wasm_path(), "--", "-e", "print(1)"])?;
+    assert!(output.status.success());
+
+    let output = wasmer(&["cache", "stats"])?;
+    assert!(output.status.success());
+    let stdout_output = std::str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout_output.contains("Total: 1 artifact"),
+        "{}",
+        stdout_output
+    );
+
+    // A limit is required to prune.
+    let output = wasmer(&["cache", "prune"])?;
+    assert!(!output.status.success());
+
+    let output = wasmer(&["cache", "prune", "--max-size", "0"])?;
+    assert!(output.status.success());
+    let stderr_output = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr_output.contains("Removed 1 artifact"),
+        "{}",
+        stderr_output
+    );
+
+    let output = wasmer(&["cache", "stats"])?;
+    let stdout_output = std::str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout_output.contains("Total: 0 artifacts"),
+        "{}",
+        stdout_output
+    );
+
+    Ok(())
+}
@@ -26,6 +26,8 @@ struct WasmerCreateExe {
     native_executable_path: PathBuf,
     /// Compiler with which to compile the Wasm.
     compiler: Compiler,
+    /// Additional arguments of the command.
+    extra_args: Vec<String>,
 }
 
 impl Default for WasmerCreateExe {
@@ -40,6 +42,7 @@ impl Default for WasmerCreateExe {
             wasm_path: PathBuf::from(create_exe_test_wasm_path()),
             native_executable_path,
             compiler: Compiler::Cranelift,
+            extra_args: vec![],
         }
     }
 }
@@ -53,6 +56,7 @@ impl WasmerCreateExe {
             .arg(&self.compiler.to_flag())
             .arg("-o")
             .arg(&self.native_executable_path)
+            .args(&self.extra_args)
             .output()?;
 
         if !output.status.success() {
@@ -160,3 +164,102 @@ fn create_exe_works_with_file() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn create_exe_bundles_config_and_modules() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let operating_dir: PathBuf = temp_dir.path().to_owned();
+
+    let wasm_path = operating_dir.join(create_exe_test_wasm_path());
+    #[cfg(not(windows))]
+    let (executable_path, greeter_path) = (
+        operating_dir.join("wasm.out"),
+        operating_dir.join("greeter"),
+    );
+    #[cfg(windows)]
+    let (executable_path, greeter_path) = (
+        operating_dir.join("wasm.exe"),
+        operating_dir.join("greeter.exe"),
+    );
+
+    let scripts_dir = operating_dir.join("scripts");
+    fs::create_dir(&scripts_dir)?;
+    fs::write(scripts_dir.join("test.js"), JS_TEST_SRC_CODE)?;
+    fs::write(
+        scripts_dir.join("greeter.js"),
+        b"print(JSON.stringify('Hi from the greeter'));\n",
+    )?;
+
+    WasmerCreateExe {
+        current_dir: operating_dir.clone(),
+        wasm_path: wasm_path.clone(),
+        native_executable_path: executable_path.clone(),
+        compiler: Compiler::Cranelift,
+        extra_args: vec![
+            format!("--module=greeter={}", wasm_path.display()),
+            format!("--embed-dir=embedded:{}", scripts_dir.display()),
+            "--arg=--script".to_string(),
+        ],
+    }
+    .run()
+    .context("Failed to create-exe wasm with Wasmer")?;
+
+    // The scripts are in the executable, they don't have to exist anymore.
+    fs::remove_dir_all(&scripts_dir)?;
+
+    let result = run_code(
+        &operating_dir,
+        &executable_path,
+        &["embedded/test.js".to_string()],
+    )
+    .context("Failed to run generated executable")?;
+    let result_lines = result.lines().collect::<Vec<&str>>();
+    assert_eq!(result_lines, vec!["\"Hello, World\""],);
+
+    // Invoked by the name of another module, the executable runs it.
+    fs::copy(&executable_path, &greeter_path)?;
+    let result = run_code(
+        &operating_dir,
+        &greeter_path,
+        &["embedded/greeter.js".to_string()],
+    )
+    .context("Failed to run generated executable")?;
+    let result_lines = result.lines().collect::<Vec<&str>>();
+    assert_eq!(result_lines, vec!["\"Hi from the greeter\""],);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn create_exe_checks_the_target_of_libwasmer() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let libwasmer_dir = temp_dir.path().join("libwasmer");
+    let host_libwasmer = get_libwasmer_path();
+    let target_lib_dir = libwasmer_dir.join("aarch64-unknown-linux-gnu").join("lib");
+    fs::create_dir_all(&target_lib_dir)?;
+    fs::copy(
+        &host_libwasmer,
+        target_lib_dir.join(host_libwasmer.file_name().unwrap()),
+    )?;
+
+    // The libwasmer of the host is given as the one of the target.
+    let output = Command::new(get_wasmer_path())
+        .current_dir(temp_dir.path())
+        .arg("create-exe")
+        .arg(create_exe_test_wasm_path())
+        .arg("--target=aarch64-unknown-linux-gnu")
+        .arg(format!("--libwasmer-dir={}", libwasmer_dir.display()))
+        .arg("-o")
+        .arg("wasm.out")
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = std::str::from_utf8(&output.stderr)?;
+    assert!(
+        stderr.contains("can't be linked in an executable for `aarch64-unknown-linux-gnu`"),
+        "{}",
+        stderr
+    );
+
+    Ok(())
+}
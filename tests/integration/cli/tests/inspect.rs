@@ -0,0 +1,44 @@
+//! Basic tests for the `inspect` subcommand
+
+use anyhow::bail;
+use std::process::Command;
+use wasmer_integration_tests_cli::{C_ASSET_PATH, WASMER_PATH};
+
+fn wasi_test_wasm_path() -> String {
+    format!("{}/{}", C_ASSET_PATH, "qjs.wasm")
+}
+
+#[test]
+fn inspect_json_works() -> anyhow::Result<()> {
+    let output = Command::new(WASMER_PATH)
+        .arg("inspect")
+        .arg("--json")
+        .arg(wasi_test_wasm_path())
+        .output()?;
+
+    if !output.status.success() {
+        bail!(
+            "inspect failed with: stdout: {}\n\nstderr: {}",
+            std::str::from_utf8(&output.stdout)
+                .expect("stdout is not utf8! need to handle arbitrary bytes"),
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+
+    let stdout_output = std::str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout_output.trim_start().starts_with('{'));
+    for key in &[
+        "\"custom_sections\"",
+        "\"functions\"",
+        "\"data_segments\"",
+        "\"memories\"",
+        "\"start_function\"",
+        "\"required_features\"",
+    ] {
+        assert!(stdout_output.contains(key), "missing {} in {}", key, stdout_output);
+    }
+    assert!(stdout_output.contains("\"wasi_snapshot_preview1\""));
+
+    Ok(())
+}
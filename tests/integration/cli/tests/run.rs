@@ -57,3 +57,184 @@ fn run_no_imports_wasm_works() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn test_infinite_loop_wat_path() -> String {
+    format!("{}/{}", ASSET_PATH, "infinite_loop.wat")
+}
+
+fn test_memory_grow_wat_path() -> String {
+    format!("{}/{}", ASSET_PATH, "memory_grow.wat")
+}
+
+#[test]
+fn run_fuel_limit_exits_with_its_code() -> anyhow::Result<()> {
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg("--fuel")
+        .arg("10000")
+        .arg(test_infinite_loop_wat_path())
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(123));
+    let stderr_output = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr_output.contains("ran out of fuel"),
+        "{}",
+        stderr_output
+    );
+
+    Ok(())
+}
+
+#[test]
+fn run_timeout_exits_with_its_code() -> anyhow::Result<()> {
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg("--timeout")
+        .arg("500ms")
+        .arg(test_infinite_loop_wat_path())
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(124));
+    let stderr_output = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(stderr_output.contains("timed out"), "{}", stderr_output);
+
+    Ok(())
+}
+
+#[test]
+fn run_max_memory_exits_with_its_code() -> anyhow::Result<()> {
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg("--max-memory")
+        .arg("1MiB")
+        .arg(test_memory_grow_wat_path())
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(122));
+    let stderr_output = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(stderr_output.contains("memory limit"), "{}", stderr_output);
+
+    // Without the limit, the module grows its memory just fine.
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg(test_memory_grow_wat_path())
+        .output()?;
+    assert!(output.status.success());
+
+    Ok(())
+}
+
+fn test_wasi_create_file_wat_path() -> String {
+    format!("{}/{}", ASSET_PATH, "wasi_create_file.wat")
+}
+
+/// The WASI error code for "permission denied".
+const WASI_EACCES: i32 = 2;
+
+#[test]
+fn run_wasi_read_only_mapdir() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mapdir = format!("out:{}", dir.path().display());
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg(test_wasi_create_file_wat_path())
+        .arg("--mapdir")
+        .arg(format!("{}:ro", mapdir))
+        .output()?;
+    assert_eq!(output.status.code(), Some(WASI_EACCES));
+    assert!(!dir.path().join("file.txt").exists());
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg(test_wasi_create_file_wat_path())
+        .arg("--mapdir")
+        .arg(mapdir)
+        .output()?;
+    assert_eq!(output.status.code(), Some(0));
+    assert!(dir.path().join("file.txt").exists());
+
+    Ok(())
+}
+
+fn test_wasi_write_read_fd_wat_path() -> String {
+    format!("{}/{}", ASSET_PATH, "wasi_write_read_fd.wat")
+}
+
+#[test]
+fn run_wasi_enforce_requested_rights() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mapdir = format!("out:{}", dir.path().display());
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg(test_wasi_write_read_fd_wat_path())
+        .arg("--mapdir")
+        .arg(&mapdir)
+        .output()?;
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg("--wasi-enforce-requested-rights")
+        .arg(test_wasi_write_read_fd_wat_path())
+        .arg("--mapdir")
+        .arg(&mapdir)
+        .output()?;
+    assert_eq!(output.status.code(), Some(WASI_EACCES));
+
+    Ok(())
+}
+
+#[test]
+fn run_wasi_policy_file() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let policy_path = dir.path().join("policy");
+    std::fs::create_dir(dir.path().join("out"))?;
+    std::fs::write(
+        &policy_path,
+        format!(
+            "# the module can write in `out`, except for `file.txt`\nmapdir out:{}\ndeny /out/file.txt\n",
+            dir.path().join("out").display()
+        ),
+    )?;
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg(test_wasi_create_file_wat_path())
+        .arg("--wasi-policy")
+        .arg(&policy_path)
+        .output()?;
+    assert_eq!(output.status.code(), Some(WASI_EACCES));
+    assert!(!dir.path().join("out").join("file.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn run_trace_imports_prints_wasi_calls() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg("--trace-imports")
+        .arg(test_wasi_create_file_wat_path())
+        .arg("--mapdir")
+        .arg(format!("out:{}:ro", dir.path().display()))
+        .output()?;
+    assert_eq!(output.status.code(), Some(WASI_EACCES));
+    let stderr_output = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr_output.contains("path_open(4, 0, \"file.txt\", 8, 1, 64, 0, 0, 0) = 2 (EACCES)\n"),
+        "unexpected stderr: {}",
+        stderr_output
+    );
+    assert!(
+        stderr_output.contains("proc_exit(2) = ?"),
+        "unexpected stderr: {}",
+        stderr_output
+    );
+
+    Ok(())
+}
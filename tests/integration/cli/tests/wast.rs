@@ -0,0 +1,69 @@
+//! Basic tests for the `wast` subcommand
+
+use std::process::Command;
+use wasmer_integration_tests_cli::WASMER_PATH;
+
+const WAST: &str = r#"
+(module $M
+  (func (export "answer") (result i32) (i32.const 42)))
+(assert_return (invoke "answer") (i32.const 42))
+(assert_return (invoke "answer") (i32.const 43))
+(thread $T (shared (module $M))
+  (register "m" $M)
+  (module
+    (import "m" "answer" (func $answer (result i32)))
+    (func (export "twice") (result i32) (i32.add (call $answer) (call $answer))))
+  (assert_return (invoke "twice") (i32.const 84)))
+(wait $T)
+(module quote "(module)")
+"#;
+
+#[test]
+fn wast_writes_reports() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let wast_path = temp_dir.path().join("answer.wast");
+    std::fs::write(&wast_path, WAST)?;
+
+    let json_path = temp_dir.path().join("report.json");
+    let output = Command::new(WASMER_PATH)
+        .arg("wast")
+        .arg(&wast_path)
+        .arg("--report")
+        .arg(&json_path)
+        .output()?;
+    // One assertion fails.
+    assert!(!output.status.success());
+    let stderr = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("7 directives passed, 1 failed, 1 skipped."),
+        "{}",
+        stderr
+    );
+
+    let json = std::fs::read_to_string(&json_path)?;
+    for expected in &[
+        "\"passed\": 7",
+        "\"failed\": 1",
+        "\"skipped\": 1",
+        "\"directive\": \"thread\"",
+        "\"directive\": \"wait\"",
+        "\"reason\": \"quoted modules are not run\"",
+    ] {
+        assert!(json.contains(expected), "missing {} in {}", expected, json);
+    }
+
+    let junit_path = temp_dir.path().join("report.xml");
+    Command::new(WASMER_PATH)
+        .arg("wast")
+        .arg(&wast_path)
+        .arg("--report")
+        .arg(&junit_path)
+        .arg("--report-format")
+        .arg("junit")
+        .output()?;
+    let junit = std::fs::read_to_string(&junit_path)?;
+    assert!(junit.contains("<testsuites tests=\"9\" failures=\"1\" skipped=\"1\">"));
+    assert!(junit.contains("<testcase name=\"assert_return at 5:1\""));
+
+    Ok(())
+}
@@ -124,6 +124,15 @@ impl Engine for DummyEngine {
         &self.engine_id
     }
 
+    fn fingerprint(&self) -> String {
+        format!(
+            "dummy-{} target={} features={:?}",
+            env!("CARGO_PKG_VERSION"),
+            self.target.fingerprint(),
+            self.features
+        )
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
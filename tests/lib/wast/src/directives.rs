@@ -0,0 +1,172 @@
+//! The directives of wast scripts, with the ones of proposals which the
+//! `wast` crate doesn't parse yet.
+
+use wast::parser::{Cursor, Parse, Parser, Peek, Result};
+use wast::{Span, WastDirective, WastInvoke};
+
+mod kw {
+    wast::custom_keyword!(assert_exception);
+    wast::custom_keyword!(module);
+    wast::custom_keyword!(shared);
+    wast::custom_keyword!(thread);
+    wast::custom_keyword!(wait);
+}
+
+/// A wast script.
+pub(crate) struct Script<'a> {
+    pub(crate) directives: Vec<Directive<'a>>,
+}
+
+impl<'a> Parse<'a> for Script<'a> {
+    fn parse(parser: Parser<'a>) -> Result<Self> {
+        let mut directives = Vec::new();
+        // Like for the `wast` crate, a script without directives is a
+        // single module.
+        if parser.peek2::<DirectiveToken>() {
+            while !parser.is_empty() {
+                directives.push(parser.parens(|p| p.parse())?);
+            }
+        } else {
+            let module = parser.parse::<wast::Wat>()?.module;
+            directives.push(Directive::Core(WastDirective::Module(module)));
+        }
+        Ok(Self { directives })
+    }
+}
+
+struct DirectiveToken;
+
+impl Peek for DirectiveToken {
+    fn peek(cursor: Cursor<'_>) -> bool {
+        match cursor.keyword() {
+            Some((kw, _)) => {
+                kw.starts_with("assert_")
+                    || matches!(kw, "module" | "register" | "invoke" | "thread" | "wait")
+            }
+            None => false,
+        }
+    }
+
+    fn display() -> &'static str {
+        "directive"
+    }
+}
+
+/// A directive of a wast script.
+pub(crate) enum Directive<'a> {
+    /// A directive parsed by the `wast` crate.
+    Core(WastDirective<'a>),
+    /// `(thread $name (shared (module $module))* directive*)`, running
+    /// its directives in a new thread, with the `shared` modules of the
+    /// script.
+    Thread {
+        span: Span,
+        name: &'a str,
+        shared: Vec<&'a str>,
+        /// Where the directives of the thread start and end.
+        body: (Span, Span),
+    },
+    /// `(wait $thread)`, waiting for a thread to finish.
+    Wait { span: Span, thread: &'a str },
+    /// `(assert_exception (invoke ...))`, of the exception handling
+    /// proposal.
+    AssertException { span: Span, call: WastInvoke<'a> },
+}
+
+impl<'a> Parse<'a> for Directive<'a> {
+    fn parse(parser: Parser<'a>) -> Result<Self> {
+        if parser.peek::<kw::thread>() {
+            let span = parser.parse::<kw::thread>()?.0;
+            let name = parser.parse::<wast::Id>()?.name();
+            let mut shared = Vec::new();
+            while parser.peek2::<kw::shared>() {
+                shared.push(parser.parens(|p| {
+                    p.parse::<kw::shared>()?;
+                    p.parens(|p| {
+                        p.parse::<kw::module>()?;
+                        Ok(p.parse::<wast::Id>()?.name())
+                    })
+                })?);
+            }
+            // The directives are parsed again by the thread running them.
+            let start = parser.cur_span();
+            while !parser.is_empty() {
+                parser.parens(|p| p.parse::<Directive>())?;
+            }
+            let end = parser.cur_span();
+            Ok(Directive::Thread {
+                span,
+                name,
+                shared,
+                body: (start, end),
+            })
+        } else if parser.peek::<kw::wait>() {
+            let span = parser.parse::<kw::wait>()?.0;
+            let thread = parser.parse::<wast::Id>()?.name();
+            Ok(Directive::Wait { span, thread })
+        } else if parser.peek::<kw::assert_exception>() {
+            let span = parser.parse::<kw::assert_exception>()?.0;
+            let call = parser.parens(|p| p.parse())?;
+            Ok(Directive::AssertException { span, call })
+        } else {
+            Ok(Directive::Core(parser.parse()?))
+        }
+    }
+}
+
+impl Directive<'_> {
+    /// Where the directive is defined.
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Directive::Core(directive) => directive.span(),
+            Directive::Thread { span, .. }
+            | Directive::Wait { span, .. }
+            | Directive::AssertException { span, .. } => *span,
+        }
+    }
+
+    /// The keyword of the directive, like `assert_return`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Directive::Core(directive) => match directive {
+                WastDirective::Module(_) | WastDirective::QuoteModule { .. } => "module",
+                WastDirective::AssertMalformed { .. } => "assert_malformed",
+                WastDirective::AssertInvalid { .. } => "assert_invalid",
+                WastDirective::Register { .. } => "register",
+                WastDirective::Invoke(_) => "invoke",
+                WastDirective::AssertTrap { .. } => "assert_trap",
+                WastDirective::AssertReturn { .. } => "assert_return",
+                WastDirective::AssertExhaustion { .. } => "assert_exhaustion",
+                WastDirective::AssertUnlinkable { .. } => "assert_unlinkable",
+            },
+            Directive::Thread { .. } => "thread",
+            Directive::Wait { .. } => "wait",
+            Directive::AssertException { .. } => "assert_exception",
+        }
+    }
+}
+
+/// The byte offset of `span` in `text`.
+pub(crate) fn span_offset(span: Span, text: &str) -> usize {
+    let (line, col) = span.linecol_in(text);
+    text.split_terminator('\n')
+        .take(line)
+        .map(|line| line.len() + 1)
+        .sum::<usize>()
+        + col
+}
+
+/// The script run by a thread whose directives are between `start` and
+/// `end` in `text`: everything else is blanked, so that the directives
+/// keep their lines and columns.
+pub(crate) fn thread_script(text: &str, start: usize, end: usize) -> String {
+    let mut script = String::with_capacity(text.len());
+    for (offset, c) in text.char_indices() {
+        if (start..end).contains(&offset) || c == '\n' {
+            script.push(c);
+        } else {
+            script.extend(std::iter::repeat(' ').take(c.len_utf8()));
+        }
+    }
+    script
+}
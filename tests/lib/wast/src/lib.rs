@@ -18,12 +18,15 @@
     )
 )]
 
+mod directives;
 mod error;
+mod report;
 mod spectest;
 mod wasi_wast;
 mod wast;
 
 pub use crate::error::{DirectiveError, DirectiveErrors};
+pub use crate::report::{DirectiveOutcome, DirectiveResult, Report, ReportFormat};
 pub use crate::spectest::spectest_importobject;
 pub use crate::wasi_wast::WasiTest;
 pub use crate::wast::Wast;
@@ -0,0 +1,216 @@
+//! Structured results of the directives of wast scripts, to track
+//! the conformance of a compiler and engine without reading logs.
+
+use serde::Serialize;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// What happened when running a directive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "lowercase")]
+pub enum DirectiveOutcome {
+    /// The directive ran as expected.
+    Pass,
+    /// The directive failed, with this message.
+    Fail(String),
+    /// The directive wasn't run, for this reason.
+    Skip(String),
+}
+
+/// The result of a directive of a wast script.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DirectiveResult {
+    /// The script where the directive is defined
+    pub filename: String,
+    /// The line where the directive is defined
+    pub line: usize,
+    /// The column where the directive is defined
+    pub col: usize,
+    /// The kind of the directive, like `assert_return`
+    pub directive: String,
+    /// What happened when running the directive
+    #[serde(flatten)]
+    pub outcome: DirectiveOutcome,
+}
+
+/// The results of all the directives run by a [`Wast`], in order.
+///
+/// [`Wast`]: crate::Wast
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The results of the directives
+    pub results: Vec<DirectiveResult>,
+}
+
+/// The formats a [`Report`] can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A JSON object with a summary and the results of all directives.
+    Json,
+    /// A JUnit XML file with a test suite per script, and a test case per
+    /// directive.
+    Junit,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "junit" => Ok(Self::Junit),
+            _ => Err(format!(
+                "unknown report format `{}`, expected `json` or `junit`",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Summary {
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    summary: Summary,
+    results: &'a [DirectiveResult],
+}
+
+impl Report {
+    /// The number of directives which passed.
+    pub fn passed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, DirectiveOutcome::Pass))
+    }
+
+    /// The number of directives which failed.
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, DirectiveOutcome::Fail(_)))
+    }
+
+    /// The number of directives which were skipped.
+    pub fn skipped(&self) -> usize {
+        self.count(|outcome| matches!(outcome, DirectiveOutcome::Skip(_)))
+    }
+
+    fn count(&self, filter: impl Fn(&DirectiveOutcome) -> bool) -> usize {
+        self.results
+            .iter()
+            .filter(|result| filter(&result.outcome))
+            .count()
+    }
+
+    /// Writes the report in `format`.
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Junit => self.to_junit(),
+        }
+    }
+
+    /// Writes the report as JSON.
+    pub fn to_json(&self) -> String {
+        let report = JsonReport {
+            summary: Summary {
+                passed: self.passed(),
+                failed: self.failed(),
+                skipped: self.skipped(),
+            },
+            results: &self.results,
+        };
+        serde_json::to_string_pretty(&report).expect("a report is always serializable")
+    }
+
+    /// Writes the report as JUnit XML.
+    pub fn to_junit(&self) -> String {
+        let mut suites: Vec<(&str, Vec<&DirectiveResult>)> = Vec::new();
+        for result in &self.results {
+            match suites
+                .iter_mut()
+                .find(|(filename, _)| *filename == result.filename)
+            {
+                Some((_, results)) => results.push(result),
+                None => suites.push((&result.filename, vec![result])),
+            }
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            xml,
+            "<testsuites tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+            self.results.len(),
+            self.failed(),
+            self.skipped()
+        )
+        .unwrap();
+        for (filename, results) in suites {
+            let failures = results
+                .iter()
+                .filter(|result| matches!(result.outcome, DirectiveOutcome::Fail(_)))
+                .count();
+            let skipped = results
+                .iter()
+                .filter(|result| matches!(result.outcome, DirectiveOutcome::Skip(_)))
+                .count();
+            writeln!(
+                xml,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+                xml_escape(filename),
+                results.len(),
+                failures,
+                skipped
+            )
+            .unwrap();
+            for result in results {
+                let name = format!("{} at {}:{}", result.directive, result.line, result.col);
+                match &result.outcome {
+                    DirectiveOutcome::Pass => writeln!(
+                        xml,
+                        "    <testcase name=\"{}\" classname=\"{}\"/>",
+                        xml_escape(&name),
+                        xml_escape(filename)
+                    ),
+                    DirectiveOutcome::Fail(message) => writeln!(
+                        xml,
+                        "    <testcase name=\"{}\" classname=\"{}\">\n      <failure message=\"{}\"/>\n    </testcase>",
+                        xml_escape(&name),
+                        xml_escape(filename),
+                        xml_escape(message)
+                    ),
+                    DirectiveOutcome::Skip(reason) => writeln!(
+                        xml,
+                        "    <testcase name=\"{}\" classname=\"{}\">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                        xml_escape(&name),
+                        xml_escape(filename),
+                        xml_escape(reason)
+                    ),
+                }
+                .unwrap();
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            // other control characters aren't allowed in XML 1.0
+            c if c.is_control() && c != '\t' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,11 +1,32 @@
+use crate::directives::{span_offset, thread_script, Directive, Script};
 use crate::error::{DirectiveError, DirectiveErrors};
+use crate::report::{DirectiveOutcome, DirectiveResult, Report};
 use crate::spectest::spectest_importobject;
 use anyhow::{anyhow, bail, Result};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::str;
+use std::thread::{self, JoinHandle};
+use thiserror::Error;
 use wasmer::*;
 
+/// A directive which wasn't run, for this reason.
+#[derive(Debug, Error)]
+#[error("skipped: {0}")]
+struct Skipped(String);
+
+/// A thread started by a `thread` directive.
+struct Thread {
+    /// The script where the `thread` directive is defined
+    filename: String,
+    /// The line where the `thread` directive is defined
+    line: usize,
+    /// The column where the `thread` directive is defined
+    col: usize,
+    /// The thread, returning the results of its directives
+    handle: JoinHandle<Report>,
+}
+
 /// The wast test script language allows modules to be defined and actions
 /// to be performed on them.
 pub struct Wast {
@@ -33,6 +54,13 @@ pub struct Wast {
     /// A flag indicating that assert_trap and assert_exhaustion should be skipped.
     /// See https://github.com/wasmerio/wasmer/issues/1550 for more info
     disable_assert_trap_exhaustion: bool,
+    /// The kinds of directives to skip, with the reason why.
+    skipped_directives: HashMap<String, String>,
+    /// The threads started by `thread` directives, until they are waited
+    /// for.
+    threads: HashMap<String, Thread>,
+    /// The results of the directives run so far.
+    report: Report,
 }
 
 impl Wast {
@@ -49,6 +77,9 @@ impl Wast {
             extern_refs: BTreeMap::new(),
             fail_fast: true,
             disable_assert_trap_exhaustion: false,
+            skipped_directives: HashMap::new(),
+            threads: HashMap::new(),
+            report: Report::default(),
         }
     }
 
@@ -71,6 +102,18 @@ impl Wast {
         self.disable_assert_trap_exhaustion = true;
     }
 
+    /// Skip the directives of the kind `directive` (eg. `assert_exhaustion`),
+    /// reporting them as skipped because of `reason`.
+    pub fn skip_directive(&mut self, directive: &str, reason: &str) {
+        self.skipped_directives
+            .insert(directive.to_string(), reason.to_string());
+    }
+
+    /// The results of the directives run so far.
+    pub fn report(&self) -> &Report {
+        &self.report
+    }
+
     /// Construct a new instance of `Wast` with the spectests imports.
     pub fn new_with_spectest(store: Store) -> Self {
         let import_object = spectest_importobject(&store);
@@ -149,9 +192,42 @@ impl Wast {
         bail!("expected '{}', got '{}'", expected, actual)
     }
 
-    fn run_directive(&mut self, directive: wast::WastDirective) -> Result<()> {
+    fn assert_exception(&self, result: Result<Vec<Val>>) -> Result<()> {
+        match result {
+            Ok(values) => bail!("expected an exception, got {:?}", values),
+            Err(e) => match e.downcast::<RuntimeError>().map(Exception::from_error) {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => bail!("expected an exception, got '{}'", e),
+                Err(e) => bail!("expected an exception, got '{}'", e),
+            },
+        }
+    }
+
+    fn run_directive(&mut self, directive: Directive, filename: &str, text: &str) -> Result<()> {
         use wast::WastDirective::*;
 
+        if let Some(reason) = self.skipped_directives.get(directive.name()) {
+            bail!(Skipped(reason.clone()));
+        }
+        let directive = match directive {
+            Directive::Core(directive) => directive,
+            Directive::Thread {
+                span,
+                name,
+                shared,
+                body: (start, end),
+            } => {
+                let script = thread_script(text, span_offset(start, text), span_offset(end, text));
+                let (line, col) = span.linecol_in(text);
+                return self.spawn_thread(name, &shared, filename, line + 1, col, script);
+            }
+            Directive::Wait { span: _, thread } => return self.wait(thread),
+            Directive::AssertException { span: _, call } => {
+                let result = self.perform_invoke(call);
+                return self.assert_exception(result);
+            }
+        };
+
         match directive {
             Module(mut module) => {
                 let binary = module.encode()?;
@@ -180,20 +256,22 @@ impl Wast {
                 exec,
                 message,
             } => {
-                if !self.disable_assert_trap_exhaustion {
-                    let result = self.perform_execute(exec);
-                    self.assert_trap(result, message)?;
+                if self.disable_assert_trap_exhaustion {
+                    bail!(Skipped("assert_trap is disabled".to_string()));
                 }
+                let result = self.perform_execute(exec);
+                self.assert_trap(result, message)?;
             }
             AssertExhaustion {
                 span: _,
                 call,
                 message,
             } => {
-                if !self.disable_assert_trap_exhaustion {
-                    let result = self.perform_invoke(call);
-                    self.assert_trap(result, message)?;
+                if self.disable_assert_trap_exhaustion {
+                    bail!(Skipped("assert_exhaustion is disabled".to_string()));
                 }
+                let result = self.perform_invoke(call);
+                self.assert_trap(result, message)?;
             }
             AssertInvalid {
                 span: _,
@@ -215,7 +293,7 @@ impl Wast {
                 }
             }
             QuoteModule { .. } => {
-                // Do nothing
+                bail!(Skipped("quoted modules are not run".to_string()));
             }
             AssertMalformed {
                 module,
@@ -226,7 +304,9 @@ impl Wast {
                     wast::QuoteModule::Module(m) => m,
                     // This is a `*.wat` parser test which we're not
                     // interested in.
-                    wast::QuoteModule::Quote(_) => return Ok(()),
+                    wast::QuoteModule::Quote(_) => {
+                        bail!(Skipped("text format tests are not run".to_string()))
+                    }
                 };
                 let bytes = module.encode()?;
                 if self.module(None, &bytes).is_ok() {
@@ -268,32 +348,51 @@ impl Wast {
         };
 
         let buf = wast::parser::ParseBuffer::new(wast).map_err(adjust_wast)?;
-        let ast = wast::parser::parse::<wast::Wast>(&buf).map_err(adjust_wast)?;
+        let ast = wast::parser::parse::<Script>(&buf).map_err(adjust_wast)?;
         let mut errors = Vec::with_capacity(ast.directives.len());
         for directive in ast.directives {
-            let sp = directive.span();
-            if let Err(e) = self.run_directive(directive) {
-                let message = format!("{}", e);
-                // If depends on an instance that doesn't exist
-                if message.contains("no previous instance found") {
-                    continue;
-                }
-                // We don't compute it, comes from instantiating an instance
-                // that we expected to fail.
-                if self.current.is_none() && self.current_is_allowed_failure {
-                    continue;
-                }
-                let (line, col) = sp.linecol_in(wast);
-                errors.push(DirectiveError {
-                    line: line + 1,
-                    col,
-                    message,
-                });
-                if self.fail_fast {
-                    break;
-                }
+            let (line, col) = directive.span().linecol_in(wast);
+            let name = directive.name();
+            let outcome = match self.run_directive(directive, filename, wast) {
+                Ok(()) => DirectiveOutcome::Pass,
+                Err(e) => match e.downcast::<Skipped>() {
+                    Ok(Skipped(reason)) => DirectiveOutcome::Skip(reason),
+                    Err(e) => {
+                        let message = format!("{}", e);
+                        // If depends on an instance that doesn't exist
+                        if message.contains("no previous instance found") {
+                            DirectiveOutcome::Skip("there is no module to run it on".to_string())
+                        }
+                        // We don't compute it, comes from instantiating an instance
+                        // that we expected to fail.
+                        else if self.current.is_none() && self.current_is_allowed_failure {
+                            DirectiveOutcome::Skip(
+                                "its module is an allowed instantiation failure".to_string(),
+                            )
+                        } else {
+                            errors.push(DirectiveError {
+                                line: line + 1,
+                                col,
+                                message: message.clone(),
+                            });
+                            DirectiveOutcome::Fail(message)
+                        }
+                    }
+                },
+            };
+            let failed = matches!(outcome, DirectiveOutcome::Fail(_));
+            self.report.results.push(DirectiveResult {
+                filename: filename.to_string(),
+                line: line + 1,
+                col,
+                directive: name.to_string(),
+                outcome,
+            });
+            if failed && self.fail_fast {
+                break;
             }
         }
+        errors.extend(self.join_threads());
         if !errors.is_empty() {
             return Err(DirectiveErrors {
                 filename: filename.to_string(),
@@ -309,6 +408,110 @@ impl Wast {
         let bytes = std::fs::read(path)?;
         self.run_buffer(path.to_str().unwrap(), &bytes)
     }
+
+    /// Runs `script`, the directives of a `thread` directive, in a new
+    /// thread with its own `Wast` sharing the `shared` instances.
+    ///
+    /// Threads always get the spectest imports.
+    fn spawn_thread(
+        &mut self,
+        name: &str,
+        shared: &[&str],
+        filename: &str,
+        line: usize,
+        col: usize,
+        script: String,
+    ) -> Result<()> {
+        if self.threads.contains_key(name) {
+            bail!("the thread `{}` is already running", name);
+        }
+        let shared = shared
+            .iter()
+            .map(|&module| Ok((module.to_string(), self.get_instance(Some(module))?)))
+            .collect::<Result<Vec<_>>>()?;
+        let store = self.store.clone();
+        let allowed_instantiation_failures = self.allowed_instantiation_failures.clone();
+        let match_trap_messages = self.match_trap_messages.clone();
+        let skipped_directives = self.skipped_directives.clone();
+        let fail_fast = self.fail_fast;
+        let disable_assert_trap_exhaustion = self.disable_assert_trap_exhaustion;
+        let thread_filename = filename.to_string();
+        let handle = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let mut wast = Self::new_with_spectest(store);
+                wast.instances.extend(shared);
+                wast.allowed_instantiation_failures = allowed_instantiation_failures;
+                wast.match_trap_messages = match_trap_messages;
+                wast.skipped_directives = skipped_directives;
+                wast.fail_fast = fail_fast;
+                wast.disable_assert_trap_exhaustion = disable_assert_trap_exhaustion;
+                // The failures are in the report.
+                let _ = wast.run_buffer(&thread_filename, script.as_bytes());
+                wast.report
+            })?;
+        self.threads.insert(
+            name.to_string(),
+            Thread {
+                filename: filename.to_string(),
+                line,
+                col,
+                handle,
+            },
+        );
+        Ok(())
+    }
+
+    /// Waits for a thread started by a `thread` directive, adding the
+    /// results of its directives to the report.
+    fn wait(&mut self, name: &str) -> Result<()> {
+        let thread = self
+            .threads
+            .remove(name)
+            .ok_or_else(|| anyhow!("failed to find the thread `{}`", name))?;
+        let report = thread
+            .handle
+            .join()
+            .map_err(|_| anyhow!("the thread `{}` panicked", name))?;
+        let failed = report.failed();
+        self.report.results.extend(report.results);
+        if failed > 0 {
+            bail!("{} directives failed in the thread `{}`", failed, name);
+        }
+        Ok(())
+    }
+
+    /// Waits for the threads which weren't waited for by a `wait`
+    /// directive, adding the results of their directives to the report.
+    ///
+    /// Returns the directives that failed in the threads, and the
+    /// `thread` directives of the threads that panicked.
+    fn join_threads(&mut self) -> Vec<DirectiveError> {
+        let mut errors = Vec::new();
+        for (name, thread) in self.threads.drain() {
+            let results = match thread.handle.join() {
+                Ok(report) => report.results,
+                Err(_) => vec![DirectiveResult {
+                    filename: thread.filename,
+                    line: thread.line,
+                    col: thread.col,
+                    directive: "thread".to_string(),
+                    outcome: DirectiveOutcome::Fail(format!("the thread `{}` panicked", name)),
+                }],
+            };
+            for result in &results {
+                if let DirectiveOutcome::Fail(message) = &result.outcome {
+                    errors.push(DirectiveError {
+                        line: result.line,
+                        col: result.col,
+                        message: format!("in the thread `{}`: {}", name, message),
+                    });
+                }
+            }
+            self.report.results.extend(results);
+        }
+        errors
+    }
 }
 
 // This is the implementation specific to the Runtime
@@ -0,0 +1,118 @@
+;; Exceptions thrown by `throw`, in the same function or in a callee, are
+;; caught by the innermost `try` with a matching `catch` clause.
+
+(module
+  (event $e0)
+  (event $e1 (param i32))
+  (event $e2 (param i64 f64))
+
+  (func $throw_e0 (throw $e0))
+  (func $throw_e1 (param i32) (throw $e1 (local.get 0)))
+  (func $throw_if (param i32)
+    (if (local.get 0) (then (throw $e1 (local.get 0)))))
+
+  (func (export "catch_local") (result i32)
+    (try (result i32)
+      (do (throw $e1 (i32.const 42)))
+      (catch $e1)))
+
+  (func (export "catch_from_callee") (param i32) (result i32)
+    (try (result i32)
+      (do (call $throw_e1 (local.get 0)) (i32.const 0))
+      (catch $e1 (i32.add (i32.const 100)))))
+
+  (func (export "no_throw") (param i32) (result i32)
+    (try (result i32)
+      (do (call $throw_if (local.get 0)) (i32.const 7))
+      (catch $e1)))
+
+  (func (export "catch_second_clause") (result i32)
+    (try (result i32)
+      (do (call $throw_e0) (i32.const 0))
+      (catch $e1)
+      (catch $e0 (i32.const 2))))
+
+  (func (export "catch_all") (result i32)
+    (try (result i32)
+      (do (throw $e2 (i64.const 1) (f64.const 2)))
+      (catch $e1)
+      (catch_all (i32.const 3))))
+
+  (func (export "multi_value_payload") (result i64 f64)
+    (try (result i64 f64)
+      (do (throw $e2 (i64.const -1) (f64.const 0.5)))
+      (catch $e2)))
+
+  (func (export "nested") (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (call $throw_e0) (i32.const 0))
+          (catch $e1)))
+      (catch $e0 (i32.const 4))))
+
+  (func (export "throw_in_catch") (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (throw $e0))
+          (catch $e0 (throw $e1 (i32.const 5)))))
+      (catch $e1)))
+
+  (func (export "rethrow") (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (throw $e1 (i32.const 6)))
+          (catch_all (rethrow 0))))
+      (catch $e1)))
+
+  (func (export "delegate") (result i32)
+    try (result i32)
+      try (result i32)
+        call $throw_e0
+        i32.const 0
+      delegate 0
+    catch $e0
+      i32.const 8
+    end)
+
+  (func (export "catch_in_loop") (param i32) (result i32)
+    (local $sum i32)
+    (loop $continue
+      (local.set $sum
+        (i32.add
+          (local.get $sum)
+          (try (result i32)
+            (do (call $throw_e1 (local.get 0)) (i32.const 0))
+            (catch $e1))))
+      (local.tee 0 (i32.sub (local.get 0) (i32.const 1)))
+      (br_if $continue))
+    (local.get $sum))
+
+  (func (export "uncaught") (param i32) (result i32)
+    (call $throw_if (local.get 0))
+    (i32.const 9))
+
+  (func (export "uncaught_by_clauses") (result i32)
+    (try (result i32)
+      (do (call $throw_e0) (i32.const 0))
+      (catch $e1))))
+
+(assert_return (invoke "catch_local") (i32.const 42))
+(assert_return (invoke "catch_from_callee" (i32.const 1)) (i32.const 101))
+(assert_return (invoke "no_throw" (i32.const 0)) (i32.const 7))
+(assert_return (invoke "no_throw" (i32.const 11)) (i32.const 11))
+(assert_return (invoke "catch_second_clause") (i32.const 2))
+(assert_return (invoke "catch_all") (i32.const 3))
+(assert_return (invoke "multi_value_payload") (i64.const -1) (f64.const 0.5))
+(assert_return (invoke "nested") (i32.const 4))
+(assert_return (invoke "throw_in_catch") (i32.const 5))
+(assert_return (invoke "rethrow") (i32.const 6))
+(assert_return (invoke "delegate") (i32.const 8))
+(assert_return (invoke "catch_in_loop" (i32.const 4)) (i32.const 10))
+(assert_return (invoke "uncaught" (i32.const 0)) (i32.const 9))
+(assert_exception (invoke "uncaught" (i32.const 1)))
+(assert_exception (invoke "uncaught_by_clauses"))
+;; Nothing is left pending once an exception escaped.
+(assert_return (invoke "catch_local") (i32.const 42))
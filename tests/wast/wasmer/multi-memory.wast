@@ -0,0 +1,76 @@
+;; Accesses to several memories of a module, imported or not, go to the
+;; memory they name.
+
+(module $mem
+  (memory (export "shared") 1)
+  (data (i32.const 0) "\01\02\03\04"))
+
+(register "mem" $mem)
+
+(module
+  (import "mem" "shared" (memory $shared 1))
+  (memory $small 1 2)
+  (memory $large 2)
+  (data (memory $small) (i32.const 8) "\aa\bb")
+  (data $passive "\10\20\30\40")
+
+  (func (export "load_shared") (param i32) (result i32)
+    (i32.load8_u (memory $shared) (local.get 0)))
+  (func (export "load_small") (param i32) (result i32)
+    (i32.load8_u (memory $small) (local.get 0)))
+  (func (export "load_large") (param i32) (result i32)
+    (i32.load8_u (memory $large) (local.get 0)))
+  (func (export "store_large") (param i32 i32)
+    (i32.store (memory $large) offset=4 (local.get 0) (local.get 1)))
+  (func (export "load32_large") (param i32) (result i32)
+    (i32.load (memory $large) offset=4 (local.get 0)))
+
+  (func (export "size_small") (result i32) (memory.size $small))
+  (func (export "size_large") (result i32) (memory.size $large))
+  (func (export "grow_small") (param i32) (result i32)
+    (memory.grow $small (local.get 0)))
+
+  (func (export "copy_shared_to_large") (param i32 i32 i32)
+    (memory.copy $large $shared (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy_large") (param i32 i32 i32)
+    (memory.copy $large $large (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "fill_small") (param i32 i32 i32)
+    (memory.fill $small (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "init_large") (param i32 i32 i32)
+    (memory.init $passive $large (local.get 0) (local.get 1) (local.get 2))))
+
+(assert_return (invoke "load_shared" (i32.const 1)) (i32.const 2))
+(assert_return (invoke "load_small" (i32.const 1)) (i32.const 0))
+(assert_return (invoke "load_small" (i32.const 9)) (i32.const 0xbb))
+(assert_return (invoke "load_large" (i32.const 9)) (i32.const 0))
+
+(invoke "store_large" (i32.const 0x1_0000) (i32.const 0x1234_5678))
+(assert_return (invoke "load32_large" (i32.const 0x1_0000)) (i32.const 0x1234_5678))
+(assert_return (invoke "load_small" (i32.const 0x4)) (i32.const 0))
+(assert_trap (invoke "store_large" (i32.const 0x2_0000) (i32.const 0)) "out of bounds memory access")
+(assert_trap (invoke "load_small" (i32.const 0x1_0000)) "out of bounds memory access")
+
+(assert_return (invoke "size_small") (i32.const 1))
+(assert_return (invoke "size_large") (i32.const 2))
+(assert_return (invoke "grow_small" (i32.const 1)) (i32.const 1))
+(assert_return (invoke "grow_small" (i32.const 1)) (i32.const -1))
+(assert_return (invoke "size_small") (i32.const 2))
+(assert_return (invoke "size_large") (i32.const 2))
+(assert_return (invoke "load_small" (i32.const 0x1_0000)) (i32.const 0))
+
+(invoke "copy_shared_to_large" (i32.const 0x100) (i32.const 0) (i32.const 4))
+(assert_return (invoke "load_large" (i32.const 0x103)) (i32.const 4))
+(assert_return (invoke "load_shared" (i32.const 0x103)) (i32.const 0))
+(assert_trap (invoke "copy_shared_to_large" (i32.const 0) (i32.const 0xffff) (i32.const 2)) "out of bounds memory access")
+(assert_trap (invoke "copy_shared_to_large" (i32.const 0x1_ffff) (i32.const 0) (i32.const 2)) "out of bounds memory access")
+
+(invoke "copy_large" (i32.const 0x200) (i32.const 0x100) (i32.const 4))
+(assert_return (invoke "load_large" (i32.const 0x200)) (i32.const 1))
+
+(invoke "fill_small" (i32.const 0x20) (i32.const 0xcc) (i32.const 2))
+(assert_return (invoke "load_small" (i32.const 0x21)) (i32.const 0xcc))
+(assert_return (invoke "load_large" (i32.const 0x21)) (i32.const 0))
+
+(invoke "init_large" (i32.const 0x300) (i32.const 1) (i32.const 3))
+(assert_return (invoke "load_large" (i32.const 0x300)) (i32.const 0x20))
+(assert_return (invoke "load_small" (i32.const 0x300)) (i32.const 0))